@@ -0,0 +1,2806 @@
+//noinspection ALL
+use super::commands::editor::Command;
+/// Re-exports the buffer ID type from the types' module.
+pub use super::types::buffer::ID;
+
+/// Module containing metadata related to buffers, such as file path, language, and timestamps.
+pub mod meta {
+    /// Metadata associated with a buffer, including file path, language, modification status, and creation time.
+    #[derive(Debug, Clone)]
+    pub struct Data {
+        /// Optional file path associated with the buffer.
+        pub file_path: Option<String>,
+        /// Optional programming language of the buffer.
+        pub language: Option<String>,
+        /// Indicates whether the buffer has been modified.
+        pub modified: bool,
+        /// Digest of the content last read from or written to disk, or of the initial content
+        /// for a buffer that was never saved. The buffer is modified whenever its content
+        /// digest differs from this.
+        pub saved_digest: Option<u64>,
+        /// Timestamp of when the buffer was created.
+        pub created_at: std::time::SystemTime,
+        /// The indentation found in the buffer's initial content, see [`detect_indentation`];
+        /// `None` if it had no indented lines, in which case the editor's settings apply.
+        pub indentation: Option<Indentation>,
+    }
+
+    impl Data {
+        /// Returns the token that starts a line comment in the buffer's language, see
+        /// [`line_comment`].
+        pub fn line_comment(&self) -> Option<&'static str> {
+            line_comment(self.language.as_deref())
+        }
+    }
+
+    /// How the lines of a buffer are indented.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Indentation {
+        /// One tab per level.
+        Tabs,
+        /// This many spaces per level.
+        Spaces(usize),
+    }
+
+    impl Indentation {
+        /// Returns the text of one level of indentation.
+        pub fn unit(self) -> String {
+            match self {
+                Self::Tabs => "\t".to_string(),
+                Self::Spaces(width) => " ".repeat(width),
+            }
+        }
+
+        /// Returns the width of one level of indentation in columns, counting a tab as
+        /// `tab_size` columns.
+        pub fn width(self, tab_size: usize) -> usize {
+            match self {
+                Self::Tabs => tab_size,
+                Self::Spaces(width) => width,
+            }
+        }
+    }
+
+    /// How many lines [`detect_indentation`] looks at, so that opening a large file stays fast.
+    const DETECTION_LINES: usize = 10_000;
+
+    /// Infers how a text is indented from its lines.
+    ///
+    /// Lines starting with a tab vote for tabs, lines starting with spaces for spaces; blank
+    /// lines and the ` * ` continuation lines of block comments don't vote. The width of a
+    /// space indent is the increase in indentation most often seen from one line to the next,
+    /// the smaller one on a tie.
+    ///
+    /// # Returns
+    ///
+    /// The indentation, or `None` if no line is indented, or the spaces never step in by
+    /// 2 to 8 columns.
+    pub fn detect_indentation(text: &str) -> Option<Indentation> {
+        let (mut tabs, mut spaces) = (0, 0);
+        let mut increases = [0usize; 9];
+        let mut previous = 0;
+        for line in text.lines().take(DETECTION_LINES) {
+            let content = line.trim_start_matches([' ', '\t']);
+            if content.is_empty() {
+                continue;
+            }
+            let indent = &line[..line.len() - content.len()];
+            if indent.starts_with('\t') {
+                tabs += 1;
+                continue;
+            }
+            if indent.len() == 1 && content.starts_with('*') {
+                continue;
+            }
+            if !indent.is_empty() {
+                spaces += 1;
+            }
+            if let Some(count) = indent
+                .len()
+                .checked_sub(previous)
+                .and_then(|increase| increases.get_mut(increase))
+            {
+                *count += 1;
+            }
+            previous = indent.len();
+        }
+
+        if tabs == 0 && spaces == 0 {
+            None
+        } else if tabs > spaces {
+            Some(Indentation::Tabs)
+        } else {
+            // The last maximum wins, so going from wide to narrow settles ties on the narrower
+            (2..increases.len())
+                .rev()
+                .filter(|&width| increases[width] > 0)
+                .max_by_key(|&width| increases[width])
+                .map(Indentation::Spaces)
+        }
+    }
+
+    /// A change to a buffer's metadata, recorded by the editor state so that what shows the
+    /// metadata can update from the changes instead of reading it again every frame.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Change {
+        /// The buffer was created.
+        Created { buffer_id: super::ID },
+        /// The buffer's content started or stopped differing from the saved content.
+        Modified {
+            buffer_id: super::ID,
+            modified: bool,
+        },
+        /// The buffer was saved to or opened from another file.
+        FilePath {
+            buffer_id: super::ID,
+            path: Option<String>,
+        },
+        /// The buffer's language was set or detected from its file.
+        Language {
+            buffer_id: super::ID,
+            language: Option<String>,
+        },
+        /// The buffer was closed.
+        Closed { buffer_id: super::ID },
+    }
+
+    impl Change {
+        /// Returns the buffer whose metadata changed.
+        pub fn buffer_id(&self) -> super::ID {
+            match self {
+                Self::Created { buffer_id }
+                | Self::Modified { buffer_id, .. }
+                | Self::FilePath { buffer_id, .. }
+                | Self::Language { buffer_id, .. }
+                | Self::Closed { buffer_id } => *buffer_id,
+            }
+        }
+    }
+
+    /// Languages by file extension, in lower case.
+    const EXTENSIONS: &[(&str, &str)] = &[
+        ("rs", "rust"),
+        ("c", "c"),
+        ("h", "c"),
+        ("cc", "cpp"),
+        ("cpp", "cpp"),
+        ("hpp", "cpp"),
+        ("go", "go"),
+        ("java", "java"),
+        ("js", "javascript"),
+        ("mjs", "javascript"),
+        ("jsx", "javascript"),
+        ("ts", "typescript"),
+        ("tsx", "typescript"),
+        ("py", "python"),
+        ("sh", "shell"),
+        ("bash", "shell"),
+        ("toml", "toml"),
+        ("yml", "yaml"),
+        ("yaml", "yaml"),
+        ("rb", "ruby"),
+        ("pl", "perl"),
+        ("r", "r"),
+        ("lua", "lua"),
+        ("sql", "sql"),
+        ("hs", "haskell"),
+        ("tex", "tex"),
+        ("erl", "erlang"),
+        ("lisp", "lisp"),
+        ("scm", "scheme"),
+        ("clj", "clojure"),
+        ("html", "html"),
+        ("htm", "html"),
+        ("css", "css"),
+        ("json", "json"),
+        ("md", "markdown"),
+        ("markdown", "markdown"),
+        ("txt", "text"),
+        ("rst", "rst"),
+        ("adoc", "asciidoc"),
+        ("org", "org"),
+    ];
+
+    /// The line comment token of each language, or `None` for languages without line
+    /// comments.
+    pub const LINE_COMMENTS: &[(&str, Option<&str>)] = &[
+        ("rust", Some("//")),
+        ("c", Some("//")),
+        ("cpp", Some("//")),
+        ("go", Some("//")),
+        ("java", Some("//")),
+        ("javascript", Some("//")),
+        ("typescript", Some("//")),
+        ("python", Some("#")),
+        ("shell", Some("#")),
+        ("bash", Some("#")),
+        ("toml", Some("#")),
+        ("yaml", Some("#")),
+        ("ruby", Some("#")),
+        ("perl", Some("#")),
+        ("r", Some("#")),
+        ("lua", Some("--")),
+        ("sql", Some("--")),
+        ("haskell", Some("--")),
+        ("tex", Some("%")),
+        ("latex", Some("%")),
+        ("erlang", Some("%")),
+        ("lisp", Some(";")),
+        ("scheme", Some(";")),
+        ("clojure", Some(";")),
+        ("html", None),
+        ("css", None),
+        ("json", None),
+        ("markdown", None),
+        ("text", None),
+    ];
+
+    /// Returns the language of a file from its extension, if it is a known one.
+    pub fn language_for_path(path: &str) -> Option<&'static str> {
+        let extension = std::path::Path::new(path)
+            .extension()?
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        EXTENSIONS
+            .iter()
+            .find(|(known, _)| *known == extension)
+            .map(|(_, language)| *language)
+    }
+
+    /// Returns the token that starts a line comment in a language. Languages missing from
+    /// [`LINE_COMMENTS`], and buffers without a language, use `//`.
+    pub fn line_comment(language: Option<&str>) -> Option<&'static str> {
+        match language
+            .and_then(|language| LINE_COMMENTS.iter().find(|(known, _)| *known == language))
+        {
+            Some((_, token)) => *token,
+            None => Some("//"),
+        }
+    }
+}
+
+/// Module containing the editor state and buffer management logic.
+// The state's deprecated public fields are still its own storage
+#[allow(deprecated)]
+pub mod editor {
+    use crate::buffer::meta;
+    use crate::hash::ContentHash;
+    use crate::view;
+    use std::collections::HashMap;
+
+    /// Commands that reverse or reapply edits, per buffer, most recent last.
+    type History = HashMap<super::ID, Vec<super::Command>>;
+
+    /// Represents the state of the editor, including buffers, metadata, cursors, and undo/redo stacks.
+    ///
+    /// Buffers, their metadata and cursors are reached through methods such as
+    /// [`State::buffer`], [`State::metadata_mut`] and [`State::cursor_mut`]. The public fields
+    /// are deprecated and will become private in the next release.
+    ///
+    /// # Sharing between threads
+    ///
+    /// The state is `Send` and `Sync`, so it can be shared as `Arc<RwLock<State>>`: the editor
+    /// takes the write lock to edit, and background work takes the read lock only to call
+    /// [`State::snapshot`], then releases it and works on the snapshot. Never wait for the
+    /// state's lock while holding a snapshot-related lock of your own, and never hold the read
+    /// lock across slow work, or typing stalls until it is done.
+    ///
+    /// The only lock inside the state guards the cache behind [`State::snapshot`]. It is
+    /// always taken after the lock on the state and released before the call returns, so it
+    /// can't take part in a deadlock.
+    #[derive(Debug, Clone)]
+    pub struct State {
+        /// Maps buffer IDs to their corresponding piece tables.
+        #[deprecated(note = "use `State::buffer` or `State::buffers`")]
+        pub buffers: HashMap<super::ID, super::super::piece::Table>,
+        /// Maps buffer IDs to their metadata.
+        #[deprecated(note = "use `State::metadata` or `State::metadata_mut`")]
+        pub buffer_metadata: HashMap<super::ID, meta::Data>,
+        /// Maps buffer IDs to their cursor states.
+        #[deprecated(note = "use `State::get_cursor_state` or `State::cursor_mut`")]
+        pub cursors: HashMap<super::ID, super::super::cursor::State>,
+        /// The currently active buffer, if any.
+        #[deprecated(note = "use `State::get_active_buffer` or `State::set_active_buffer`")]
+        pub active_buffer: Option<super::ID>,
+
+        /// Undo stack for each buffer: the commands that reverse each edit, most recent last.
+        pub(crate) undo_stack: History,
+        /// Redo stack for each buffer: the commands that reapply each undone edit.
+        pub(crate) redo_stack: History,
+
+        /// Named marks, kept in step with edits to their buffers.
+        #[deprecated(note = "use `State::marks` or `State::marks_mut`")]
+        pub marks: super::super::marks::Registry,
+        /// Where recent edits were made, across buffers.
+        #[deprecated(note = "use `State::edits` or `State::edits_mut`")]
+        pub edits: super::super::edits::History,
+
+        /// Views showing buffers in panes, each with its own cursor.
+        pub(crate) views: HashMap<view::ID, view::State>,
+        /// The view whose cursor is currently held in `cursors`, per buffer.
+        pub(crate) focused_views: HashMap<super::ID, view::ID>,
+        /// Changes to buffer metadata not yet taken by [`State::take_metadata_changes`].
+        pub(crate) metadata_changes: Vec<meta::Change>,
+        /// Content hashes of all buffers, kept up to date from edits.
+        pub(crate) hashes: HashMap<super::ID, ContentHash>,
+        /// The text of each buffer's last snapshot.
+        pub(crate) snapshots: super::super::snapshot::Cache,
+        /// Non-editable text shown in buffers, kept in step with edits.
+        pub(crate) virtual_text: super::super::virtual_text::Registry,
+        /// Folded regions of buffers, kept in step with edits. Like `cursors`, these are the
+        /// folds of each buffer's focused view; other views keep their own.
+        pub(crate) folds: super::super::folds::Registry,
+    }
+
+    /// How many edits each buffer's undo history keeps.
+    const UNDO_LIMIT: usize = 1000;
+
+    /// How many metadata changes are kept for a reader that doesn't take them.
+    const CHANGE_LIMIT: usize = 1000;
+
+    /// Combines the inverses of the edits a command made, in the order they were made, into
+    /// the one command that reverses them all.
+    fn reverse(mut inverses: Vec<super::Command>) -> Option<super::Command> {
+        inverses.reverse();
+        match inverses.len() {
+            0 => None,
+            1 => inverses.pop(),
+            _ => Some(super::Command::Batch(inverses)),
+        }
+    }
+
+    /// Returns the offset an edit command leaves the caret at: after inserted text or where
+    /// text was deleted. For a batch, the last of its edits counts.
+    fn caret_after(command: &super::Command) -> Option<usize> {
+        match command {
+            super::Command::InsertText { offset, text, .. } => Some(offset + text.len()),
+            super::Command::DeleteText { start, .. } => Some(*start),
+            super::Command::Batch(commands) => commands.iter().rev().find_map(caret_after),
+            _ => None,
+        }
+    }
+
+    /// Builds the edits that turn `old` into `new`: one per run of changed lines, narrowed down
+    /// to the part of the run that actually differs, ordered from the end of the text towards
+    /// the start.
+    fn reload_commands(buffer_id: super::ID, old: &str, new: &str) -> Vec<super::Command> {
+        use super::super::diff;
+
+        let line_starts = |text| -> Vec<usize> {
+            std::iter::once(0)
+                .chain(diff::lines(text).iter().scan(0, |offset, line| {
+                    *offset += line.len();
+                    Some(*offset)
+                }))
+                .collect()
+        };
+        let (old_starts, new_starts) = (line_starts(old), line_starts(new));
+        let mut commands = Vec::new();
+        for hunk in diff::line_hunks(old, new, diff::Options::default())
+            .iter()
+            .rev()
+        {
+            let removed = &old[old_starts[hunk.old.start]..old_starts[hunk.old.end]];
+            let added = &new[new_starts[hunk.new.start]..new_starts[hunk.new.end]];
+            let prefix = common_prefix(removed, added);
+            let suffix = common_prefix_len(
+                removed[prefix..].chars().rev(),
+                added[prefix..].chars().rev(),
+            );
+            let start = old_starts[hunk.old.start] + prefix;
+            let length = removed.len() - prefix - suffix;
+            let text = &added[prefix..added.len() - suffix];
+            // Inserting before deleting keeps what followed the old text after the new text
+            if !text.is_empty() {
+                commands.push(super::Command::InsertText {
+                    buffer_id,
+                    offset: start,
+                    text: text.to_string(),
+                });
+            }
+            if length > 0 {
+                commands.push(super::Command::DeleteText {
+                    buffer_id,
+                    start: start + text.len(),
+                    length,
+                });
+            }
+        }
+        commands
+    }
+
+    /// Builds the edits and cursor moves that carry out a line command, or nothing if the
+    /// lines can't move further or the command isn't one.
+    ///
+    /// The command works on the cursor's line, or on every line the selection touches; a
+    /// selection ending at the start of a line doesn't touch that line.
+    fn line_commands(
+        command: &super::Command,
+        table: &super::super::piece::Table,
+        cursor: &super::super::cursor::State,
+    ) -> Vec<super::Command> {
+        use super::super::types::{Position, Range};
+
+        let selection = cursor.selection().filter(|s| !s.is_empty());
+        let (first, last) = match selection.map(Range::ordered) {
+            Some(Range { start, end }) if end.line > start.line && end.column == 0 => {
+                (start.line, end.line - 1)
+            }
+            Some(Range { start, end }) => (start.line, end.line),
+            None => (cursor.position().line, cursor.position().line),
+        };
+        let last_line = table.end_position().line;
+        let line_start = |line| table.position_to_offset(Position { line, column: 0 });
+        // Where a line ends, before its line break
+        let line_end = |line| {
+            if line < last_line {
+                line_start(line + 1) - 1
+            } else {
+                table.len()
+            }
+        };
+        let text = |start: usize, end: usize| table.get_text(start, end - start);
+        // Moves the cursor and selection down by `lines`, which may be negative
+        let follow = |buffer_id, lines: isize| {
+            let shift = |position: Position| Position {
+                line: position.line.saturating_add_signed(lines),
+                column: position.column,
+            };
+            let mut commands = vec![super::Command::MoveCursor {
+                buffer_id,
+                position: shift(cursor.position()),
+            }];
+            if let Some(range) = selection {
+                commands.push(super::Command::SetSelection {
+                    buffer_id,
+                    range: Range {
+                        start: shift(range.start),
+                        end: shift(range.end),
+                    },
+                });
+            }
+            commands
+        };
+        // Moves the cursor and selection to new columns on the same lines
+        let reindent = |buffer_id, column: &dyn Fn(Position) -> usize| {
+            let moved = |position: Position| Position {
+                line: position.line,
+                column: column(position),
+            };
+            let mut commands = vec![super::Command::MoveCursor {
+                buffer_id,
+                position: moved(cursor.position()),
+            }];
+            if let Some(range) = selection {
+                commands.push(super::Command::SetSelection {
+                    buffer_id,
+                    range: Range {
+                        start: moved(range.start),
+                        end: moved(range.end),
+                    },
+                });
+            }
+            commands
+        };
+        // Swaps the block of lines from `top` with the single line after it
+        let swap = |buffer_id, top: usize, split: usize, bottom: usize| {
+            let start = line_start(top);
+            let end = line_end(bottom);
+            let upper = text(start, line_end(split));
+            let lower = text(line_start(split + 1), end);
+            vec![
+                super::Command::DeleteText {
+                    buffer_id,
+                    start,
+                    length: end - start,
+                },
+                super::Command::InsertText {
+                    buffer_id,
+                    offset: start,
+                    text: format!("{}\n{}", lower, upper),
+                },
+            ]
+        };
+
+        match *command {
+            super::Command::DeleteLines { buffer_id } => {
+                // The last line has no line break of its own, so it takes the one before it
+                let (start, end, line) = if last < last_line {
+                    (line_start(first), line_start(last + 1), first)
+                } else if first > 0 {
+                    (line_end(first - 1), table.len(), first - 1)
+                } else {
+                    (0, table.len(), 0)
+                };
+                // The line the cursor lands on, as it reads after the deletion
+                let landing = if last < last_line {
+                    text(line_start(last + 1), line_end(last + 1))
+                } else if first > 0 {
+                    text(line_start(first - 1), line_end(first - 1))
+                } else {
+                    String::new()
+                };
+                let mut column = cursor.position().column.min(landing.len());
+                while !landing.is_char_boundary(column) {
+                    column -= 1;
+                }
+                if start == end {
+                    return Vec::new();
+                }
+                vec![
+                    super::Command::DeleteText {
+                        buffer_id,
+                        start,
+                        length: end - start,
+                    },
+                    super::Command::MoveCursor {
+                        buffer_id,
+                        position: Position { line, column },
+                    },
+                ]
+            }
+            super::Command::DuplicateLines { buffer_id } => {
+                let copy = text(line_start(first), line_end(last));
+                let mut commands = vec![super::Command::InsertText {
+                    buffer_id,
+                    offset: line_end(last),
+                    text: format!("\n{}", copy),
+                }];
+                commands.extend(follow(buffer_id, (last - first + 1) as isize));
+                commands
+            }
+            super::Command::MoveLines {
+                buffer_id,
+                up: true,
+            } if first > 0 => {
+                let mut commands = swap(buffer_id, first - 1, first - 1, last);
+                commands.extend(follow(buffer_id, -1));
+                commands
+            }
+            super::Command::MoveLines {
+                buffer_id,
+                up: false,
+            } if last < last_line => {
+                let mut commands = swap(buffer_id, first, last, last + 1);
+                commands.extend(follow(buffer_id, 1));
+                commands
+            }
+            super::Command::IndentLines {
+                buffer_id,
+                ref unit,
+            } => {
+                let widths: Vec<usize> = (first..=last)
+                    .map(|line| {
+                        let blank = text(line_start(line), line_end(line)).trim().is_empty();
+                        if blank { 0 } else { unit.len() }
+                    })
+                    .collect();
+                // From the bottom up, so the offsets above stay valid
+                let mut commands: Vec<_> = widths
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|&(_, &width)| width > 0)
+                    .map(|(i, _)| super::Command::InsertText {
+                        buffer_id,
+                        offset: line_start(first + i),
+                        text: unit.clone(),
+                    })
+                    .collect();
+                if commands.is_empty() {
+                    return commands;
+                }
+                // Positions at the start of a line stay there, so whole selected lines stay
+                // selected
+                commands.extend(reindent(buffer_id, &|position: Position| match position
+                    .line
+                    .checked_sub(first)
+                    .and_then(|i| widths.get(i))
+                {
+                    Some(width) if position.column > 0 => position.column + width,
+                    _ => position.column,
+                }));
+                commands
+            }
+            super::Command::DedentLines {
+                buffer_id,
+                tab_size,
+            } => {
+                let widths: Vec<usize> = (first..=last)
+                    .map(|line| {
+                        let content = text(line_start(line), line_end(line));
+                        if content.starts_with('\t') {
+                            1
+                        } else {
+                            content
+                                .bytes()
+                                .take(tab_size)
+                                .take_while(|&b| b == b' ')
+                                .count()
+                        }
+                    })
+                    .collect();
+                let mut commands: Vec<_> = widths
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|&(_, &width)| width > 0)
+                    .map(|(i, &width)| super::Command::DeleteText {
+                        buffer_id,
+                        start: line_start(first + i),
+                        length: width,
+                    })
+                    .collect();
+                if commands.is_empty() {
+                    return commands;
+                }
+                commands.extend(reindent(buffer_id, &|position: Position| match position
+                    .line
+                    .checked_sub(first)
+                    .and_then(|i| widths.get(i))
+                {
+                    Some(width) => position.column.saturating_sub(*width),
+                    None => position.column,
+                }));
+                commands
+            }
+            super::Command::SortLines {
+                buffer_id,
+                case_insensitive,
+                reverse,
+            } if first < last => {
+                let start = line_start(first);
+                let end = line_end(last);
+                let original = text(start, end);
+                let mut lines: Vec<&str> = original.split('\n').collect();
+                let key = |line: &str| {
+                    if case_insensitive {
+                        line.to_lowercase()
+                    } else {
+                        line.to_string()
+                    }
+                };
+                if reverse {
+                    lines.sort_by_cached_key(|line| std::cmp::Reverse(key(line)));
+                } else {
+                    lines.sort_by_cached_key(|line| key(line));
+                }
+                let sorted = lines.join("\n");
+                if sorted == original {
+                    return Vec::new();
+                }
+                // The sorted lines end up selected, including the line break if it was
+                let end_position = match selection.map(Range::ordered) {
+                    Some(Range { end, .. }) if end.line > last => end,
+                    _ => Position {
+                        line: last,
+                        column: lines.last().map_or(0, |line| line.len()),
+                    },
+                };
+                vec![
+                    super::Command::DeleteText {
+                        buffer_id,
+                        start,
+                        length: end - start,
+                    },
+                    super::Command::InsertText {
+                        buffer_id,
+                        offset: start,
+                        text: sorted,
+                    },
+                    super::Command::MoveCursor {
+                        buffer_id,
+                        position: end_position,
+                    },
+                    super::Command::SetSelection {
+                        buffer_id,
+                        range: Range {
+                            start: Position {
+                                line: first,
+                                column: 0,
+                            },
+                            end: end_position,
+                        },
+                    },
+                ]
+            }
+            super::Command::JoinLines { buffer_id } if first < last_line => {
+                let last = last.max(first + 1);
+                let mut joins = Vec::new();
+                let mut joined = text(line_start(first), line_end(first));
+                let mut column = joined.len();
+                for line in first + 1..=last {
+                    let above = text(line_start(line - 1), line_end(line - 1));
+                    let below = text(line_start(line), line_end(line));
+                    let content = below.trim_start();
+                    // A blank line above was already swallowed by the previous join
+                    let start = (line_start(line - 1) + above.trim_end().len())
+                        .max(joins.last().map_or(0, |&(_, end, _)| end));
+                    let end = line_start(line) + below.len() - content.len();
+                    let kept = joined.trim_end().len();
+                    let separator = if kept == 0 || content.trim_end().is_empty() {
+                        ""
+                    } else {
+                        " "
+                    };
+                    column = kept + separator.len();
+                    joined = format!("{}{}{}", &joined[..kept], separator, content);
+                    joins.push((start, end, separator));
+                }
+                // Join from the bottom up so the offsets above stay valid
+                let mut commands = Vec::new();
+                for (start, end, separator) in joins.into_iter().rev() {
+                    commands.push(super::Command::DeleteText {
+                        buffer_id,
+                        start,
+                        length: end - start,
+                    });
+                    if !separator.is_empty() {
+                        commands.push(super::Command::InsertText {
+                            buffer_id,
+                            offset: start,
+                            text: separator.to_string(),
+                        });
+                    }
+                }
+                commands.push(super::Command::MoveCursor {
+                    buffer_id,
+                    position: Position {
+                        line: first,
+                        column,
+                    },
+                });
+                commands
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the length in bytes of the longest common start of two texts.
+    fn common_prefix(a: &str, b: &str) -> usize {
+        common_prefix_len(a.chars(), b.chars())
+    }
+
+    /// Returns the length in bytes of the characters two sequences start with in common.
+    fn common_prefix_len(a: impl Iterator<Item = char>, b: impl Iterator<Item = char>) -> usize {
+        a.zip(b)
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum()
+    }
+
+    impl State {
+        /// Creates a new editor state with no buffers.
+        pub fn new() -> Self {
+            Self {
+                buffers: HashMap::new(),
+                buffer_metadata: HashMap::new(),
+                cursors: HashMap::new(),
+                active_buffer: None,
+                undo_stack: HashMap::new(),
+                redo_stack: HashMap::new(),
+                marks: Default::default(),
+                edits: Default::default(),
+                views: HashMap::new(),
+                focused_views: HashMap::new(),
+                metadata_changes: Vec::new(),
+                hashes: HashMap::new(),
+                snapshots: Default::default(),
+                virtual_text: Default::default(),
+                folds: Default::default(),
+            }
+        }
+
+        /// Returns the text of all buffers, by buffer ID.
+        pub fn buffers(&self) -> &HashMap<super::ID, super::super::piece::Table> {
+            &self.buffers
+        }
+
+        /// Returns the text of a buffer, if it exists.
+        pub fn buffer(&self, buffer_id: super::ID) -> Option<&super::super::piece::Table> {
+            self.buffers.get(&buffer_id)
+        }
+
+        /// Returns the IDs of all buffers, in no particular order.
+        pub fn buffer_ids(&self) -> impl Iterator<Item = super::ID> + '_ {
+            self.buffers.keys().copied()
+        }
+
+        /// Returns the metadata of a buffer, if it exists.
+        pub fn metadata(&self, buffer_id: super::ID) -> Option<&meta::Data> {
+            self.buffer_metadata.get(&buffer_id)
+        }
+
+        /// Returns the metadata of a buffer for modification, if it exists.
+        ///
+        /// Changes made here are not reported by [`State::take_metadata_changes`]. Set the
+        /// file path and language through [`State::set_file_path`] and
+        /// [`State::set_language`], and the modified flag through [`State::mark_saved`].
+        pub fn metadata_mut(&mut self, buffer_id: super::ID) -> Option<&mut meta::Data> {
+            self.buffer_metadata.get_mut(&buffer_id)
+        }
+
+        /// Sets the file a buffer is saved to, and its language to the one the file's extension
+        /// names, if it names one.
+        pub fn set_file_path(&mut self, buffer_id: super::ID, path: String) {
+            if let Some(language) = meta::language_for_path(&path) {
+                self.set_language(buffer_id, Some(language.to_string()));
+            }
+            let Some(meta) = self.buffer_metadata.get_mut(&buffer_id) else {
+                return;
+            };
+            if meta.file_path.as_ref() != Some(&path) {
+                meta.file_path = Some(path.clone());
+                self.record_change(meta::Change::FilePath {
+                    buffer_id,
+                    path: Some(path),
+                });
+            }
+        }
+
+        /// Sets the language of a buffer, such as `rust`.
+        pub fn set_language(&mut self, buffer_id: super::ID, language: Option<String>) {
+            let Some(meta) = self.buffer_metadata.get_mut(&buffer_id) else {
+                return;
+            };
+            if meta.language != language {
+                meta.language.clone_from(&language);
+                self.record_change(meta::Change::Language {
+                    buffer_id,
+                    language,
+                });
+            }
+        }
+
+        /// Returns the changes to buffer metadata since the last call, oldest first.
+        ///
+        /// Only the most recent changes are kept between calls, so a reader that takes them
+        /// rarely may miss some; it should then read the metadata again.
+        pub fn take_metadata_changes(&mut self) -> Vec<meta::Change> {
+            std::mem::take(&mut self.metadata_changes)
+        }
+
+        fn record_change(&mut self, change: meta::Change) {
+            if self.metadata_changes.len() >= CHANGE_LIMIT {
+                self.metadata_changes.remove(0);
+            }
+            self.metadata_changes.push(change);
+        }
+
+        /// Returns the buffer holding a file, if the file is open.
+        pub fn buffer_for_path(&self, path: &str) -> Option<super::ID> {
+            self.buffer_metadata
+                .iter()
+                .find(|(_, meta)| meta.file_path.as_deref() == Some(path))
+                .map(|(id, _)| *id)
+        }
+
+        /// Places the pending marks of a file into the buffer it was just opened in, see
+        /// [`super::super::marks::Registry::open_file`].
+        pub fn open_file_marks(&mut self, buffer_id: super::ID, path: &str) {
+            if let Some(table) = self.buffers.get(&buffer_id) {
+                self.marks.open_file(buffer_id, path, table);
+            }
+        }
+
+        /// Returns where recent edits were made, across buffers.
+        pub fn edits(&self) -> &super::super::edits::History {
+            &self.edits
+        }
+
+        /// Returns where recent edits were made for modification, such as stepping back
+        /// through them.
+        pub fn edits_mut(&mut self) -> &mut super::super::edits::History {
+            &mut self.edits
+        }
+
+        /// Returns the named marks of all buffers.
+        pub fn marks(&self) -> &super::super::marks::Registry {
+            &self.marks
+        }
+
+        /// Returns the named marks of all buffers for modification.
+        pub fn marks_mut(&mut self) -> &mut super::super::marks::Registry {
+            &mut self.marks
+        }
+
+        /// Returns the virtual text of all buffers.
+        pub fn virtual_text(&self) -> &super::super::virtual_text::Registry {
+            &self.virtual_text
+        }
+
+        /// Returns the virtual text of all buffers for modification.
+        pub fn virtual_text_mut(&mut self) -> &mut super::super::virtual_text::Registry {
+            &mut self.virtual_text
+        }
+
+        /// Returns the folded regions of all buffers, as the focused view of each shows them.
+        pub fn folds(&self) -> &super::super::folds::Registry {
+            &self.folds
+        }
+
+        /// Returns the folded regions of all buffers, as the focused view of each shows them,
+        /// for modification.
+        pub fn folds_mut(&mut self) -> &mut super::super::folds::Registry {
+            &mut self.folds
+        }
+
+        /// Returns the lines folded away in a buffer, as ordered ranges that don't overlap.
+        pub fn hidden_lines(&self, buffer_id: super::ID) -> Vec<std::ops::Range<usize>> {
+            self.buffers
+                .get(&buffer_id)
+                .map(|table| self.folds.hidden_lines(buffer_id, table))
+                .unwrap_or_default()
+        }
+
+        /// Unfolds the folds of a buffer that hide a line, so that it shows.
+        ///
+        /// # Returns
+        ///
+        /// Whether any fold was unfolded.
+        pub fn reveal_line(&mut self, buffer_id: super::ID, line: usize) -> bool {
+            self.buffers
+                .get(&buffer_id)
+                .is_some_and(|table| self.folds.reveal(buffer_id, line, table))
+        }
+
+        /// Unfolds the innermost fold around a line of a buffer, or else folds the innermost
+        /// foldable region around it. A caret the fold hides moves to the fold's header.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        /// * `line` - The line, usually the caret's.
+        /// * `tab_size` - How many columns of indentation a tab counts for.
+        ///
+        /// # Returns
+        ///
+        /// Whether anything was folded or unfolded.
+        pub fn toggle_fold(&mut self, buffer_id: super::ID, line: usize, tab_size: usize) -> bool {
+            let Some(table) = self.buffers.get(&buffer_id) else {
+                return false;
+            };
+            if self.folds.unfold(buffer_id, line, table) {
+                return true;
+            }
+            let regions = super::super::folds::regions(&table.get_text(0, table.len()), tab_size);
+            let folded = super::super::folds::innermost(&regions, line)
+                .is_some_and(|region| self.folds.fold(buffer_id, region, table));
+            if folded {
+                self.move_caret_out_of_folds(buffer_id);
+            }
+            folded
+        }
+
+        /// Folds every foldable region of a buffer. A caret the folds hide moves to the header
+        /// of the outermost fold around it.
+        ///
+        /// # Returns
+        ///
+        /// Whether anything was folded.
+        pub fn fold_all(&mut self, buffer_id: super::ID, tab_size: usize) -> bool {
+            let Some(table) = self.buffers.get(&buffer_id) else {
+                return false;
+            };
+            let regions = super::super::folds::regions(&table.get_text(0, table.len()), tab_size);
+            let mut folded = false;
+            for region in regions {
+                folded |= self.folds.fold(buffer_id, region, table);
+            }
+            if folded {
+                self.move_caret_out_of_folds(buffer_id);
+            }
+            folded
+        }
+
+        /// Moves the caret of a buffer to the end of the header of the fold hiding it, if any.
+        fn move_caret_out_of_folds(&mut self, buffer_id: super::ID) {
+            use super::super::types::Position;
+            let (Some(table), Some(cursor)) =
+                (self.buffers.get(&buffer_id), self.cursors.get(&buffer_id))
+            else {
+                return;
+            };
+            let line = cursor.position().line;
+            let hidden = self.folds.hidden_lines(buffer_id, table);
+            let Some(range) = hidden.iter().find(|range| range.contains(&line)) else {
+                return;
+            };
+            let header = range.start - 1;
+            let start = table.position_to_offset(Position {
+                line: header,
+                column: 0,
+            });
+            let end = table.position_to_offset(Position {
+                line: header + 1,
+                column: 0,
+            });
+            let text = table.get_text(start, end - start);
+            let position = Position {
+                line: header,
+                column: text.trim_end_matches(['\r', '\n']).len(),
+            };
+            let _ = self.execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position,
+            });
+        }
+
+        /// Creates a new buffer with the given content and initializes its metadata, cursor, and undo/redo stacks.
+        ///
+        /// # Arguments
+        ///
+        /// * `content` - The initial content of the buffer.
+        ///
+        /// # Returns
+        ///
+        /// The unique ID of the newly created buffer.
+        pub fn create_buffer(&mut self, content: String) -> super::ID {
+            let buffer_id = super::ID::new();
+            let indentation = meta::detect_indentation(&content);
+            let piece_table = super::super::piece::Table::new(content);
+            let hash = ContentHash::of_table(&piece_table);
+            let saved_digest = Some(hash.digest());
+            self.buffers.insert(buffer_id, piece_table);
+            self.hashes.insert(buffer_id, hash);
+
+            self.buffer_metadata.insert(
+                buffer_id,
+                meta::Data {
+                    file_path: None,
+                    language: None,
+                    modified: false,
+                    saved_digest,
+                    created_at: std::time::SystemTime::now(),
+                    indentation,
+                },
+            );
+            self.cursors.insert(
+                buffer_id,
+                super::super::cursor::State {
+                    position: super::super::types::Position { line: 0, column: 0 },
+                    selection: None,
+                    buffer_id,
+                    preferred_column: None,
+                },
+            );
+            self.undo_stack.insert(buffer_id, Vec::new());
+            self.redo_stack.insert(buffer_id, Vec::new());
+            // if self.active_buffer.is_none() {
+            //     self.active_buffer = Some(buffer_id);
+            // }
+            self.active_buffer = Some(buffer_id);
+            self.record_change(meta::Change::Created { buffer_id });
+
+            buffer_id
+        }
+
+        /// Executes an editor command, such as inserting or deleting text, moving the cursor, or saving a buffer.
+        ///
+        /// Edits are recorded in the undo history of their buffer, a batch as a single step.
+        ///
+        /// # Arguments
+        ///
+        /// * `command` - The command to execute.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the command cannot be executed.
+        pub fn execute_command(&mut self, command: super::Command) -> anyhow::Result<()> {
+            let inverses = self.apply_command(command)?;
+            let mut seen = std::collections::HashSet::new();
+            let buffer_ids: Vec<super::ID> = inverses
+                .iter()
+                .map(|(id, _)| *id)
+                .filter(|id| seen.insert(*id))
+                .collect();
+            for buffer_id in buffer_ids {
+                let inverses = inverses
+                    .iter()
+                    .filter(|(id, _)| *id == buffer_id)
+                    .map(|(_, inverse)| inverse.clone())
+                    .collect();
+                if let Some(inverse) = reverse(inverses) {
+                    self.record_undo(buffer_id, inverse);
+                }
+            }
+            Ok(())
+        }
+
+        /// Executes a command without recording it for undo.
+        ///
+        /// # Returns
+        ///
+        /// The buffers edited and the commands that reverse each edit, in the order the edits
+        /// were made.
+        fn apply_command(
+            &mut self,
+            command: super::Command,
+        ) -> anyhow::Result<Vec<(super::ID, super::Command)>> {
+            let mut inverses = Vec::new();
+            let delta = super::super::delta::Delta::from_command(&command);
+            // Other views of an edited buffer follow the edit, so capture where they were
+            let followers = match delta {
+                Some((buffer_id, _)) => self.unfocused_view_anchors(buffer_id),
+                None => Vec::new(),
+            };
+            // A suggestion only lasts until the text changes or the cursor leaves it
+            self.dismiss_stale_suggestion(&command);
+            match command {
+                super::Command::InsertText {
+                    buffer_id,
+                    offset,
+                    text,
+                } => {
+                    if let Some(buffer) = self.buffers.get_mut(&buffer_id) {
+                        buffer.insert(offset, &text)?;
+                        inverses.push((
+                            buffer_id,
+                            super::Command::DeleteText {
+                                buffer_id,
+                                start: offset,
+                                length: text.len(),
+                            },
+                        ));
+                    }
+                }
+                super::Command::DeleteText {
+                    buffer_id,
+                    start,
+                    length,
+                } => {
+                    if let Some(buffer) = self.buffers.get_mut(&buffer_id) {
+                        let text = buffer.get_text(start, length);
+                        buffer.delete(start, length)?;
+                        inverses.push((
+                            buffer_id,
+                            super::Command::InsertText {
+                                buffer_id,
+                                offset: start,
+                                text,
+                            },
+                        ));
+                    }
+                }
+                super::Command::MoveCursor {
+                    buffer_id,
+                    position,
+                } => {
+                    if let Some(cursor) = self.cursors.get_mut(&buffer_id) {
+                        cursor.position = position;
+                        cursor.selection = None;
+                    }
+                }
+                super::Command::SetSelection { buffer_id, range } => {
+                    if let Some(cursor) = self.cursors.get_mut(&buffer_id) {
+                        cursor.selection = Some(range);
+                    }
+                }
+                super::Command::SelectAll { buffer_id } => {
+                    if let (Some(table), Some(cursor)) = (
+                        self.buffers.get(&buffer_id),
+                        self.cursors.get_mut(&buffer_id),
+                    ) {
+                        let end = table.end_position();
+                        cursor.position = end;
+                        cursor.selection = Some(super::super::types::Range {
+                            start: super::super::types::Position { line: 0, column: 0 },
+                            end,
+                        });
+                    }
+                }
+
+                super::Command::DeleteLines { buffer_id }
+                | super::Command::DuplicateLines { buffer_id }
+                | super::Command::MoveLines { buffer_id, .. }
+                | super::Command::JoinLines { buffer_id }
+                | super::Command::IndentLines { buffer_id, .. }
+                | super::Command::DedentLines { buffer_id, .. }
+                | super::Command::SortLines { buffer_id, .. } => {
+                    let commands =
+                        match (self.buffers.get(&buffer_id), self.cursors.get(&buffer_id)) {
+                            (Some(table), Some(cursor)) => line_commands(&command, table, cursor),
+                            _ => Vec::new(),
+                        };
+                    return self.execute_batch(commands);
+                }
+
+                super::Command::NewBuffer { content } => {
+                    self.create_buffer(content);
+                }
+
+                super::Command::SaveBuffer {
+                    buffer_id,
+                    file_path,
+                } => {
+                    self.set_file_path(buffer_id, file_path);
+                    self.mark_saved(buffer_id);
+                }
+
+                super::Command::CloseBuffer { buffer_id } => {
+                    self.close_buffer(buffer_id);
+                }
+
+                super::Command::Batch(commands) => {
+                    return self.execute_batch(commands);
+                }
+            }
+            if let Some((buffer_id, delta)) = delta {
+                self.marks.apply(buffer_id, &delta);
+                self.virtual_text.apply(buffer_id, &delta);
+                self.folds.apply(buffer_id, &delta);
+                if let Some(table) = self.buffers.get(&buffer_id) {
+                    self.edits.record(buffer_id, &delta, table);
+                    if let Some(hash) = self.hashes.get_mut(&buffer_id) {
+                        hash.apply(&delta, table);
+                    }
+                    for (view_id, anchor) in followers {
+                        if let Some(view) = self.views.get_mut(&view_id) {
+                            view.follow_edit(anchor, &delta, table);
+                        }
+                    }
+                }
+                self.mark_buffer_modified(buffer_id);
+            }
+            Ok(inverses)
+        }
+
+        /// Executes the commands of a batch in order. If one of them fails, the edits already made
+        /// are reversed and the cursors put back, so a compound edit is never half applied.
+        ///
+        /// Only text edits and cursors are rolled back: a batch that creates, saves or closes a
+        /// buffer before failing keeps that.
+        fn execute_batch(
+            &mut self,
+            commands: Vec<super::Command>,
+        ) -> anyhow::Result<Vec<(super::ID, super::Command)>> {
+            if commands.is_empty() {
+                return Ok(Vec::new());
+            }
+            let cursors = self.cursors.clone();
+            let views = self.views.clone();
+            let edits = self.edits.clone();
+            let mut inverses = Vec::new();
+            for (index, command) in commands.into_iter().enumerate() {
+                match self.apply_command(command) {
+                    Ok(more) => inverses.extend(more),
+                    Err(e) => {
+                        // Each inverse was made from the text its edit changed, so it applies
+                        for (_, inverse) in inverses.into_iter().rev() {
+                            let _ = self.apply_command(inverse);
+                        }
+                        self.cursors = cursors;
+                        self.views = views;
+                        self.edits = edits;
+                        return Err(e.context(format!("command {} of batch failed", index + 1)));
+                    }
+                }
+            }
+            Ok(inverses)
+        }
+
+        /// Pushes the command that reverses an edit onto a buffer's undo history, and forgets
+        /// the undone edits that could have been redone.
+        ///
+        /// A character inserted right after text inserted on the same line before it extends
+        /// that step instead, so a run of typing is undone at once. A line break ends the run.
+        fn record_undo(&mut self, buffer_id: super::ID, inverse: super::Command) {
+            let table = self.buffers.get(&buffer_id);
+            let inserted = |start: usize, length: usize| {
+                table.map_or_else(String::new, |table| table.get_text(start, length))
+            };
+            let is_typed_char = |text: &str| text.chars().count() == 1 && text != "\n";
+            let stack = self.undo_stack.entry(buffer_id).or_default();
+            match (stack.last_mut(), &inverse) {
+                (
+                    Some(super::Command::DeleteText {
+                        start: run_start,
+                        length: run_length,
+                        ..
+                    }),
+                    super::Command::DeleteText { start, length, .. },
+                ) if *run_start + *run_length == *start
+                    && !inserted(*run_start, *run_length).contains('\n')
+                    && is_typed_char(&inserted(*start, *length)) =>
+                {
+                    *run_length += length;
+                }
+                _ => {
+                    stack.push(inverse);
+                    if stack.len() > UNDO_LIMIT {
+                        stack.remove(0);
+                    }
+                }
+            }
+            if let Some(redo) = self.redo_stack.get_mut(&buffer_id) {
+                redo.clear();
+            }
+        }
+
+        /// Reverses the most recent edit of a buffer that has not been undone yet, and places
+        /// the caret where it was made.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        ///
+        /// # Returns
+        ///
+        /// Whether there was an edit to undo.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if reversing the edit fails, in which case the buffer and its
+        /// history are left as they were.
+        pub fn undo(&mut self, buffer_id: super::ID) -> anyhow::Result<bool> {
+            self.step_history(buffer_id, true)
+        }
+
+        /// Reapplies the most recently undone edit of a buffer, and places the caret where it
+        /// was made.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        ///
+        /// # Returns
+        ///
+        /// Whether there was an edit to redo.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if reapplying the edit fails, in which case the buffer and its
+        /// history are left as they were.
+        pub fn redo(&mut self, buffer_id: super::ID) -> anyhow::Result<bool> {
+            self.step_history(buffer_id, false)
+        }
+
+        /// Returns the history a step is taken from and the one its inverse goes to.
+        fn history(&mut self, undo: bool) -> (&mut History, &mut History) {
+            if undo {
+                (&mut self.undo_stack, &mut self.redo_stack)
+            } else {
+                (&mut self.redo_stack, &mut self.undo_stack)
+            }
+        }
+
+        /// Applies the top command of the undo or redo history and pushes its inverse onto the
+        /// other one.
+        fn step_history(&mut self, buffer_id: super::ID, undo: bool) -> anyhow::Result<bool> {
+            let Some(command) = self.history(undo).0.get_mut(&buffer_id).and_then(Vec::pop) else {
+                return Ok(false);
+            };
+            let caret = caret_after(&command);
+            let inverses = match self.apply_command(command.clone()) {
+                Ok(inverses) => inverses,
+                Err(e) => {
+                    self.history(undo)
+                        .0
+                        .entry(buffer_id)
+                        .or_default()
+                        .push(command);
+                    return Err(e);
+                }
+            };
+            let inverses = inverses.into_iter().map(|(_, inverse)| inverse).collect();
+            if let Some(inverse) = reverse(inverses) {
+                self.history(undo)
+                    .1
+                    .entry(buffer_id)
+                    .or_default()
+                    .push(inverse);
+            }
+            if let (Some(caret), Some(table), Some(cursor)) = (
+                caret,
+                self.buffers.get(&buffer_id),
+                self.cursors.get_mut(&buffer_id),
+            ) {
+                cursor.position = table.offset_to_position(caret.min(table.len()));
+                cursor.selection = None;
+                cursor.preferred_column = None;
+            }
+            Ok(true)
+        }
+
+        /// Dismisses the suggestion of a buffer that a command edits or moves the cursor away from.
+        fn dismiss_stale_suggestion(&mut self, command: &super::Command) {
+            let (buffer_id, stale) = match command {
+                super::Command::InsertText { buffer_id, .. }
+                | super::Command::DeleteText { buffer_id, .. } => (*buffer_id, true),
+                super::Command::MoveCursor {
+                    buffer_id,
+                    position,
+                } => {
+                    let Some(suggestion) = self.virtual_text.suggestion(*buffer_id) else {
+                        return;
+                    };
+                    let offset = self
+                        .buffers
+                        .get(buffer_id)
+                        .map(|table| table.position_to_offset(*position));
+                    (*buffer_id, offset != Some(suggestion.offset))
+                }
+                _ => return,
+            };
+            if stale {
+                self.virtual_text.dismiss_suggestion(buffer_id);
+            }
+        }
+
+        /// Closes a buffer, dropping its contents, metadata, cursor and undo/redo history.
+        ///
+        /// If the closed buffer was active, the most recently created remaining buffer becomes active.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer to close.
+        ///
+        /// # Returns
+        ///
+        /// `true` if the buffer existed and was closed.
+        pub fn close_buffer(&mut self, buffer_id: super::ID) -> bool {
+            let Some(table) = self.buffers.remove(&buffer_id) else {
+                return false;
+            };
+            let meta = self.buffer_metadata.remove(&buffer_id);
+            self.record_change(meta::Change::Closed { buffer_id });
+            let path = meta.as_ref().and_then(|meta| meta.file_path.as_deref());
+            self.marks.close_buffer(buffer_id, path, &table);
+            self.edits.close_buffer(buffer_id);
+            self.views.retain(|_, view| view.buffer_id != buffer_id);
+            self.focused_views.remove(&buffer_id);
+            self.hashes.remove(&buffer_id);
+            self.snapshots.remove(buffer_id);
+            self.virtual_text.clear_buffer(buffer_id);
+            self.folds.clear_buffer(buffer_id);
+            self.cursors.remove(&buffer_id);
+            self.undo_stack.remove(&buffer_id);
+            self.redo_stack.remove(&buffer_id);
+
+            if self.active_buffer == Some(buffer_id) {
+                self.active_buffer = self
+                    .buffer_metadata
+                    .iter()
+                    .max_by_key(|(_, meta)| meta.created_at)
+                    .map(|(id, _)| *id);
+            }
+            true
+        }
+
+        /// Opens a new view of a buffer, starting at the buffer's current cursor.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The buffer to show.
+        ///
+        /// # Returns
+        ///
+        /// The new view's ID, or `None` if the buffer does not exist.
+        pub fn create_view(&mut self, buffer_id: super::ID) -> Option<view::ID> {
+            let cursor = self.cursors.get(&buffer_id)?.clone();
+            let view_id = view::ID::new();
+            self.views.insert(
+                view_id,
+                view::State {
+                    buffer_id,
+                    cursor,
+                    overrides: view::Overrides::default(),
+                    folds: self.folds.of_buffer(buffer_id),
+                },
+            );
+            Some(view_id)
+        }
+
+        /// Closes a view. The buffer and its other views are unaffected.
+        ///
+        /// # Arguments
+        ///
+        /// * `view_id` - The view to close.
+        pub fn close_view(&mut self, view_id: view::ID) {
+            if let Some(view) = self.views.remove(&view_id)
+                && self.focused_views.get(&view.buffer_id) == Some(&view_id)
+            {
+                self.focused_views.remove(&view.buffer_id);
+            }
+        }
+
+        /// Returns a view, if it exists.
+        pub fn view(&self, view_id: view::ID) -> Option<&view::State> {
+            self.views.get(&view_id)
+        }
+
+        /// Returns a view for changing its settings, if it exists.
+        pub fn view_mut(&mut self, view_id: view::ID) -> Option<&mut view::State> {
+            self.views.get_mut(&view_id)
+        }
+
+        /// Makes a view's cursor and folds the buffer's current ones.
+        ///
+        /// Cursor commands, [`State::get_cursor_state`] and folding address cursors and folds
+        /// by buffer, so a view must be focused before it is edited or drawn. The cursor and
+        /// folds of the view that was focused before are stored back into that view.
+        ///
+        /// # Arguments
+        ///
+        /// * `view_id` - The view to focus.
+        pub fn focus_view(&mut self, view_id: view::ID) {
+            let Some(buffer_id) = self.views.get(&view_id).map(|view| view.buffer_id) else {
+                return;
+            };
+            match self.focused_views.insert(buffer_id, view_id) {
+                // The buffer's cursor already is this view's cursor, and may be newer
+                Some(previous) if previous == view_id => return,
+                Some(previous) => {
+                    let folds = self.folds.take_buffer(buffer_id);
+                    if let Some(view) = self.views.get_mut(&previous) {
+                        view.folds = folds;
+                        if let Some(cursor) = self.cursors.get(&buffer_id) {
+                            view.cursor = cursor.clone();
+                        }
+                    }
+                }
+                None => {
+                    self.folds.take_buffer(buffer_id);
+                }
+            }
+            if let Some(view) = self.views.get(&view_id) {
+                self.cursors.insert(buffer_id, view.cursor.clone());
+                self.folds.merge(view.folds.clone());
+            }
+        }
+
+        /// Returns the positions of a buffer's views other than the focused one, as offsets.
+        fn unfocused_view_anchors(&self, buffer_id: super::ID) -> Vec<(view::ID, view::Anchor)> {
+            let Some(table) = self.buffers.get(&buffer_id) else {
+                return Vec::new();
+            };
+            let focused = self.focused_views.get(&buffer_id);
+            self.views
+                .iter()
+                .filter(|(id, view)| view.buffer_id == buffer_id && Some(*id) != focused)
+                .map(|(id, view)| (*id, view.anchor(table)))
+                .collect()
+        }
+
+        /// Updates the modified flag of the specified buffer after an edit.
+        ///
+        /// The buffer counts as modified only while its content differs from the saved content,
+        /// so undoing back to the saved text clears the flag again.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the edited buffer.
+        fn mark_buffer_modified(&mut self, buffer_id: super::ID) {
+            let digest = self.content_digest(buffer_id);
+            if let Some(meta) = self.buffer_metadata.get_mut(&buffer_id) {
+                let modified = digest.is_none() || meta.saved_digest != digest;
+                if meta.modified != modified {
+                    meta.modified = modified;
+                    self.record_change(meta::Change::Modified {
+                        buffer_id,
+                        modified,
+                    });
+                }
+            }
+        }
+
+        /// Records the buffer's current content as its saved content and clears its modified flag.
+        ///
+        /// Call this after the buffer was written to, or read from, disk.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the saved buffer.
+        pub fn mark_saved(&mut self, buffer_id: super::ID) {
+            let digest = self.content_digest(buffer_id);
+            if let Some(meta) = self.buffer_metadata.get_mut(&buffer_id) {
+                meta.saved_digest = digest;
+                if meta.modified {
+                    meta.modified = false;
+                    self.record_change(meta::Change::Modified {
+                        buffer_id,
+                        modified: false,
+                    });
+                }
+            }
+        }
+
+        /// Returns a digest of the buffer's content, without reading the text.
+        ///
+        /// Buffers with equal content have equal digests, so this can be compared against
+        /// [`ContentHash::of`] the content of a file on disk.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        pub fn content_digest(&self, buffer_id: super::ID) -> Option<u64> {
+            self.hashes.get(&buffer_id).map(ContentHash::digest)
+        }
+
+        /// Takes a snapshot of a buffer, to read it on another thread while it keeps changing.
+        ///
+        /// The text is only copied when it changed since the last snapshot of the buffer;
+        /// otherwise the snapshot shares the previous one's text.
+        ///
+        /// # Returns
+        ///
+        /// The snapshot, or `None` if the buffer doesn't exist.
+        pub fn snapshot(&self, buffer_id: super::ID) -> Option<super::super::snapshot::Snapshot> {
+            let table = self.buffers.get(&buffer_id)?;
+            let digest = self.content_digest(buffer_id)?;
+            let text = self
+                .snapshots
+                .text(buffer_id, digest, || table.get_text(0, table.len()));
+            Some(super::super::snapshot::new(
+                buffer_id,
+                digest,
+                self.buffer_metadata.get(&buffer_id)?.clone(),
+                self.cursors.get(&buffer_id)?.position(),
+                text,
+            ))
+        }
+
+        /// Returns how many bytes of text the snapshot cache holds for each buffer.
+        pub fn snapshot_sizes(&self) -> Vec<(super::ID, usize)> {
+            self.snapshots.sizes()
+        }
+
+        /// Drops the text cached for a buffer's snapshots to save memory. Snapshots already
+        /// taken keep their text; the next one copies it again.
+        pub fn forget_snapshot(&self, buffer_id: super::ID) {
+            self.snapshots.remove(buffer_id);
+        }
+
+        /// Replaces the text of a buffer with a new version, such as its file's content after the
+        /// file changed on disk, by editing only what differs. The buffer is then marked saved.
+        ///
+        /// See [`State::replace_text`].
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        /// * `text` - The new text.
+        ///
+        /// # Returns
+        ///
+        /// Whether the text changed.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the buffer does not exist or the edits fail, in which case the
+        /// buffer is left as it was.
+        pub fn reload_text(&mut self, buffer_id: super::ID, text: &str) -> anyhow::Result<bool> {
+            let changed = self.replace_text(buffer_id, text)?;
+            self.mark_saved(buffer_id);
+            Ok(changed)
+        }
+
+        /// Replaces the text of a buffer with a new version, such as the output of a formatter,
+        /// by editing only what differs, in one undoable step.
+        ///
+        /// Unlike recreating the buffer, this keeps everything that follows edits — marks,
+        /// virtual text, other views, the caret and the selection — on the text they were on,
+        /// wherever that text survived.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        /// * `text` - The new text.
+        ///
+        /// # Returns
+        ///
+        /// Whether the text changed.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the buffer does not exist or the edits fail, in which case the
+        /// buffer is left as it was.
+        pub fn replace_text(&mut self, buffer_id: super::ID, text: &str) -> anyhow::Result<bool> {
+            if self.matches_text(buffer_id, text) {
+                return Ok(false);
+            }
+            let old = self
+                .get_buffer_text(buffer_id)
+                .ok_or_else(|| anyhow::anyhow!("no buffer {:?}", buffer_id))?;
+            let commands = reload_commands(buffer_id, &old, text);
+
+            // The caret and selection are positions, so they are carried along by offset
+            let table = &self.buffers[&buffer_id];
+            let cursor = self.cursors.get(&buffer_id).map(|cursor| {
+                let offset = |position| table.position_to_offset(position).min(table.len());
+                (
+                    offset(cursor.position),
+                    cursor.selection.map(|s| (offset(s.start), offset(s.end))),
+                )
+            });
+            let map = |offset: usize| {
+                commands
+                    .iter()
+                    .filter_map(super::super::delta::Delta::from_command)
+                    .fold(offset, |offset, (_, delta)| delta.map_offset(offset))
+            };
+            let cursor = cursor.map(|(caret, selection)| {
+                (
+                    map(caret),
+                    selection.map(|(start, end)| (map(start), map(end))),
+                )
+            });
+
+            self.execute_command(super::Command::Batch(commands))?;
+            if let (Some((caret, selection)), Some(table), Some(state)) = (
+                cursor,
+                self.buffers.get(&buffer_id),
+                self.cursors.get_mut(&buffer_id),
+            ) {
+                state.position = table.offset_to_position(caret);
+                state.selection = selection.map(|(start, end)| super::super::types::Range {
+                    start: table.offset_to_position(start),
+                    end: table.offset_to_position(end),
+                });
+            }
+            Ok(true)
+        }
+
+        /// Compacts the piece tables of buffers that edits have split into many pieces, so
+        /// reading their text stays fast. The text of every buffer stays the same.
+        ///
+        /// # Arguments
+        ///
+        /// * `min_pieces` - How many pieces a buffer must have to be compacted.
+        ///
+        /// # Returns
+        ///
+        /// How many buffers were compacted.
+        pub fn compact_buffers(&mut self, min_pieces: usize) -> usize {
+            let mut compacted = 0;
+            for table in self.buffers.values_mut() {
+                if table.piece_count() >= min_pieces {
+                    table.compact();
+                    compacted += 1;
+                }
+            }
+            compacted
+        }
+
+        /// Returns whether the buffer's content equals `text`.
+        ///
+        /// Compares lengths and digests instead of the full text.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        /// * `text` - The text to compare with, e.g. the file's content on disk.
+        pub fn matches_text(&self, buffer_id: super::ID, text: &str) -> bool {
+            self.hashes.get(&buffer_id).is_some_and(|hash| {
+                hash.len() == text.len() && hash.digest() == ContentHash::of(text.bytes()).digest()
+            })
+        }
+
+        /// Retrieves the full text of the specified buffer, if it exists.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        ///
+        /// # Returns
+        ///
+        /// An `Option` containing the buffer's text, or `None` if the buffer does not exist.
+        pub fn get_buffer_text(&self, buffer_id: super::ID) -> Option<String> {
+            self.buffers
+                .get(&buffer_id)
+                .map(|buffer| buffer.get_text(0, buffer.len()))
+        }
+
+        /// Returns the ID of the currently active buffer, if any.
+        pub fn get_active_buffer(&self) -> Option<super::ID> {
+            self.active_buffer
+        }
+
+        /// Makes a buffer the active one, or leaves no buffer active. IDs of buffers that
+        /// don't exist are ignored.
+        pub fn set_active_buffer(&mut self, buffer_id: Option<super::ID>) {
+            if buffer_id.is_none_or(|id| self.buffers.contains_key(&id)) {
+                self.active_buffer = buffer_id;
+            }
+        }
+
+        /// Retrieves the cursor state for the specified buffer, if it exists.
+        ///
+        /// # Arguments
+        ///
+        /// * `buffer_id` - The ID of the buffer.
+        ///
+        /// # Returns
+        ///
+        /// An `Option` containing a reference to the cursor state, or `None` if not found.
+        pub fn get_cursor_state(
+            &self,
+            buffer_id: super::ID,
+        ) -> Option<&super::super::cursor::State> {
+            self.cursors.get(&buffer_id)
+        }
+
+        /// Returns the cursor state of a buffer for modification, such as forgetting its
+        /// preferred column.
+        pub fn cursor_mut(
+            &mut self,
+            buffer_id: super::ID,
+        ) -> Option<&mut super::super::cursor::State> {
+            self.cursors.get_mut(&buffer_id)
+        }
+    }
+
+    impl Default for State {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::ID;
+    use super::editor::State;
+
+    #[test]
+    fn create_buffer_initializes_all_fields() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("hello".to_string());
+        assert!(state.buffers.contains_key(&buffer_id));
+        assert!(state.buffer_metadata.contains_key(&buffer_id));
+        assert!(state.cursors.contains_key(&buffer_id));
+        assert!(state.undo_stack.contains_key(&buffer_id));
+        assert!(state.redo_stack.contains_key(&buffer_id));
+        assert_eq!(state.active_buffer, Some(buffer_id));
+    }
+
+    #[test]
+    fn execute_command_insert_text_marks_buffer_modified() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        let _ = state.execute_command(super::Command::InsertText {
+            buffer_id,
+            offset: 1,
+            text: "x".to_string(),
+        });
+        let meta = state.buffer_metadata.get(&buffer_id).unwrap();
+        assert!(meta.modified);
+    }
+
+    #[test]
+    fn execute_command_delete_text_marks_buffer_modified() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        let _ = state.execute_command(super::Command::DeleteText {
+            buffer_id,
+            start: 0,
+            length: 1,
+        });
+        let meta = state.buffer_metadata.get(&buffer_id).unwrap();
+        assert!(meta.modified);
+    }
+
+    #[test]
+    fn execute_command_records_edit_locations() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc\ndef".to_string());
+        let _ = state.execute_command(super::Command::InsertText {
+            buffer_id,
+            offset: 5,
+            text: "x".to_string(),
+        });
+        let last = state.edits.previous().unwrap();
+        assert_eq!((last.buffer_id, last.offset), (buffer_id, 6));
+
+        state.close_buffer(buffer_id);
+        assert_eq!(state.edits.previous(), None);
+    }
+
+    #[test]
+    fn execute_command_move_cursor_updates_position_and_clears_selection() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        let pos = super::super::types::Position { line: 1, column: 2 };
+        let _ = state.execute_command(super::Command::MoveCursor {
+            buffer_id,
+            position: pos,
+        });
+        let cursor = state.cursors.get(&buffer_id).unwrap();
+        assert_eq!(cursor.position, pos);
+        assert!(cursor.selection.is_none());
+    }
+
+    #[test]
+    fn execute_command_set_selection_sets_selection() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        let range = super::super::types::Range {
+            start: super::super::types::Position { line: 0, column: 0 },
+            end: super::super::types::Position { line: 0, column: 2 },
+        };
+        let _ = state.execute_command(super::Command::SetSelection { buffer_id, range });
+        let cursor = state.cursors.get(&buffer_id).unwrap();
+        assert_eq!(cursor.selection, Some(range));
+    }
+
+    #[test]
+    fn execute_command_select_all_selects_whole_text() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("ab\ncdé".to_string());
+        let _ = state.execute_command(super::Command::SelectAll { buffer_id });
+        let cursor = state.cursors.get(&buffer_id).unwrap();
+        let end = super::super::types::Position { line: 1, column: 4 };
+        assert_eq!(cursor.position, end);
+        assert_eq!(
+            cursor.selection,
+            Some(super::super::types::Range {
+                start: super::super::types::Position { line: 0, column: 0 },
+                end,
+            })
+        );
+    }
+
+    #[test]
+    fn execute_command_new_buffer_creates_new_buffer() {
+        let mut state = State::new();
+        let before = state.buffers.len();
+        let _ = state.execute_command(super::Command::NewBuffer {
+            content: "new".to_string(),
+        });
+        assert_eq!(state.buffers.len(), before + 1);
+    }
+
+    #[test]
+    fn execute_command_save_buffer_updates_file_path_and_clears_modified() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        state.buffer_metadata.get_mut(&buffer_id).unwrap().modified = true;
+        let _ = state.execute_command(super::Command::SaveBuffer {
+            buffer_id,
+            file_path: "foo.txt".to_string(),
+        });
+        let meta = state.buffer_metadata.get(&buffer_id).unwrap();
+        assert_eq!(meta.file_path, Some("foo.txt".to_string()));
+        assert!(!meta.modified);
+    }
+
+    #[test]
+    fn reverting_to_saved_content_clears_modified() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        state
+            .execute_command(super::Command::InsertText {
+                buffer_id,
+                offset: 3,
+                text: "d".to_string(),
+            })
+            .unwrap();
+        assert!(state.buffer_metadata[&buffer_id].modified);
+        state
+            .execute_command(super::Command::DeleteText {
+                buffer_id,
+                start: 3,
+                length: 1,
+            })
+            .unwrap();
+        assert!(!state.buffer_metadata[&buffer_id].modified);
+        assert!(state.matches_text(buffer_id, "abc"));
+        assert!(!state.matches_text(buffer_id, "abd"));
+    }
+
+    #[test]
+    fn undo_and_redo_reverse_edits() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("hello world".to_string());
+        state
+            .execute_command(super::Command::Batch(vec![
+                super::Command::DeleteText {
+                    buffer_id,
+                    start: 6,
+                    length: 5,
+                },
+                super::Command::InsertText {
+                    buffer_id,
+                    offset: 6,
+                    text: "there".to_string(),
+                },
+            ]))
+            .unwrap();
+        state
+            .execute_command(super::Command::DeleteText {
+                buffer_id,
+                start: 0,
+                length: 6,
+            })
+            .unwrap();
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "there");
+
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "hello there");
+        assert_eq!(state.cursors[&buffer_id].position.column, 6);
+        // The batch is undone as one step
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "hello world");
+        assert!(!state.buffer_metadata[&buffer_id].modified);
+        assert!(!state.undo(buffer_id).unwrap());
+
+        assert!(state.redo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "hello there");
+        assert!(state.buffer_metadata[&buffer_id].modified);
+
+        // A new edit drops what could have been redone
+        state
+            .execute_command(super::Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "> ".to_string(),
+            })
+            .unwrap();
+        assert!(!state.redo(buffer_id).unwrap());
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "hello there");
+    }
+
+    #[test]
+    fn typing_is_undone_a_line_at_a_time() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer(String::new());
+        for (offset, c) in "ab\ncd".char_indices() {
+            state
+                .execute_command(super::Command::InsertText {
+                    buffer_id,
+                    offset,
+                    text: c.to_string(),
+                })
+                .unwrap();
+        }
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab\n");
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab");
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "");
+        assert!(state.redo(buffer_id).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab");
+    }
+
+    #[test]
+    fn reloading_text_keeps_marks_and_the_caret() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("one\ntwo\nthree\n".to_string());
+        state.marks.set('a', buffer_id, 10);
+        let caret = super::super::types::Position { line: 2, column: 2 };
+        state
+            .execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position: caret,
+            })
+            .unwrap();
+
+        let reloaded = state
+            .reload_text(buffer_id, "zero\none\n2\nthree\n")
+            .unwrap();
+        assert!(reloaded);
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "zero\none\n2\nthree\n"
+        );
+        assert_eq!(state.marks.get('a', buffer_id).unwrap().offset, 13);
+        let position = state.get_cursor_state(buffer_id).unwrap().position();
+        assert_eq!(
+            position,
+            super::super::types::Position { line: 3, column: 2 }
+        );
+        assert!(!state.buffer_metadata[&buffer_id].modified);
+
+        assert!(
+            !state
+                .reload_text(buffer_id, "zero\none\n2\nthree\n")
+                .unwrap()
+        );
+        assert!(state.reload_text(ID::new(), "").is_err());
+    }
+
+    #[test]
+    fn reload_edits_only_what_differs() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("let a = 1;\nx\n".to_string());
+        let semicolon = super::super::types::Position { line: 0, column: 9 };
+        state
+            .execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position: semicolon,
+            })
+            .unwrap();
+        state.reload_text(buffer_id, "let a = 22;\nx\n").unwrap();
+        // The caret stays before the semicolon rather than going to the start of the line
+        let position = state.get_cursor_state(buffer_id).unwrap().position();
+        assert_eq!(
+            position,
+            super::super::types::Position {
+                line: 0,
+                column: 10
+            }
+        );
+    }
+
+    #[test]
+    fn replacing_text_is_one_undoable_edit() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("fn f( ){\n1\n}\n".to_string());
+        assert!(
+            state
+                .replace_text(buffer_id, "fn f() {\n    1\n}\n")
+                .unwrap()
+        );
+        assert!(state.buffer_metadata[&buffer_id].modified);
+        assert!(state.undo(buffer_id).unwrap());
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "fn f( ){\n1\n}\n"
+        );
+        assert!(!state.replace_text(buffer_id, "fn f( ){\n1\n}\n").unwrap());
+    }
+
+    #[test]
+    fn editing_or_leaving_a_suggestion_dismisses_it() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("fn main".to_string());
+        let at_end = super::super::types::Position { line: 0, column: 7 };
+        state.virtual_text_mut().suggest(buffer_id, 7, "() {}");
+        state
+            .execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position: at_end,
+            })
+            .unwrap();
+        assert!(state.virtual_text().suggestion(buffer_id).is_some());
+
+        state
+            .execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position: super::super::types::Position { line: 0, column: 2 },
+            })
+            .unwrap();
+        assert!(state.virtual_text().suggestion(buffer_id).is_none());
+
+        state.virtual_text_mut().suggest(buffer_id, 7, "() {}");
+        state
+            .execute_command(super::Command::InsertText {
+                buffer_id,
+                offset: 7,
+                text: "(".to_string(),
+            })
+            .unwrap();
+        assert!(state.virtual_text().suggestion(buffer_id).is_none());
+    }
+
+    #[test]
+    fn batches_are_one_undo_step_per_buffer() {
+        let mut state = State::new();
+        let a = state.create_buffer("a".to_string());
+        let b = state.create_buffer("b".to_string());
+        let insert = |buffer_id, text: &str| super::Command::InsertText {
+            buffer_id,
+            offset: 0,
+            text: text.to_string(),
+        };
+        state
+            .execute_command(super::Command::Batch(vec![
+                insert(a, "a"),
+                insert(b, "b"),
+                insert(a, "a"),
+            ]))
+            .unwrap();
+        assert_eq!(state.get_buffer_text(a).unwrap(), "aaa");
+        assert!(state.undo(a).unwrap());
+        assert_eq!(state.get_buffer_text(a).unwrap(), "a");
+        assert!(!state.undo(a).unwrap());
+    }
+
+    #[test]
+    fn undo_puts_the_caret_where_typing_continues() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("ééé\nx".to_string());
+        state
+            .execute_command(super::Command::InsertText {
+                buffer_id,
+                offset: 6,
+                text: "!".to_string(),
+            })
+            .unwrap();
+        assert!(state.undo(buffer_id).unwrap());
+        let position = state.cursors[&buffer_id].position;
+        assert_eq!(state.buffers[&buffer_id].position_to_offset(position), 6);
+    }
+
+    #[test]
+    fn execute_command_batch_is_all_or_nothing() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("one two".to_string());
+        state
+            .execute_command(super::Command::Batch(vec![
+                super::Command::DeleteText {
+                    buffer_id,
+                    start: 0,
+                    length: 3,
+                },
+                super::Command::InsertText {
+                    buffer_id,
+                    offset: 0,
+                    text: "1".to_string(),
+                },
+            ]))
+            .unwrap();
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "1 two");
+
+        let failed = state.execute_command(super::Command::Batch(vec![
+            super::Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "x".to_string(),
+            },
+            super::Command::DeleteText {
+                buffer_id,
+                start: 4,
+                length: 10,
+            },
+        ]));
+        assert!(failed.is_err());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "1 two");
+        assert_eq!(state.edits.iter().count(), 1);
+
+        // Edits to other buffers, cursors and marks are rolled back too
+        let other = state.create_buffer("abc".to_string());
+        state.marks_mut().set('m', other, 2);
+        let caret = state.cursors[&other].position;
+        let failed = state.execute_command(super::Command::Batch(vec![
+            super::Command::InsertText {
+                buffer_id: other,
+                offset: 0,
+                text: "zz".to_string(),
+            },
+            super::Command::MoveCursor {
+                buffer_id: other,
+                position: super::super::types::Position { line: 0, column: 4 },
+            },
+            super::Command::DeleteText {
+                buffer_id,
+                start: 4,
+                length: 10,
+            },
+        ]));
+        assert!(failed.is_err());
+        assert_eq!(state.get_buffer_text(other).unwrap(), "abc");
+        assert!(!state.buffer_metadata[&other].modified);
+        assert_eq!(state.cursors[&other].position, caret);
+        assert_eq!(state.marks().get('m', other).unwrap().offset, 2);
+        assert!(!state.undo(other).unwrap());
+    }
+
+    #[test]
+    fn get_buffer_text_returns_none_for_nonexistent_buffer() {
+        let state = State::new();
+        let fake_id = ID::new();
+        assert!(state.get_buffer_text(fake_id).is_none());
+    }
+
+    #[test]
+    fn close_buffer_removes_all_state() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("bye".to_string());
+        assert!(state.close_buffer(buffer_id));
+        assert!(state.get_buffer_text(buffer_id).is_none());
+        assert!(state.get_cursor_state(buffer_id).is_none());
+        assert!(!state.buffer_metadata.contains_key(&buffer_id));
+        assert!(!state.undo_stack.contains_key(&buffer_id));
+        assert!(!state.redo_stack.contains_key(&buffer_id));
+        assert_eq!(state.get_active_buffer(), None);
+        assert!(!state.close_buffer(buffer_id));
+    }
+
+    #[test]
+    fn close_active_buffer_activates_most_recent_remaining() {
+        let mut state = State::new();
+        let first = state.create_buffer("1".to_string());
+        let second = state.create_buffer("2".to_string());
+        let third = state.create_buffer("3".to_string());
+        state.buffer_metadata.get_mut(&first).unwrap().created_at = std::time::UNIX_EPOCH;
+        let _ = state.execute_command(super::Command::CloseBuffer { buffer_id: third });
+        assert_eq!(state.get_active_buffer(), Some(second));
+    }
+
+    #[test]
+    fn close_inactive_buffer_keeps_active() {
+        let mut state = State::new();
+        let first = state.create_buffer("1".to_string());
+        let second = state.create_buffer("2".to_string());
+        assert!(state.close_buffer(first));
+        assert_eq!(state.get_active_buffer(), Some(second));
+    }
+
+    #[test]
+    fn line_commands_work_on_the_touched_lines() {
+        use super::super::types::{Position, Range};
+        let at = |line, column| Position { line, column };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("one\ntwo\nthree\nfour".to_string());
+        let cursor = state.cursors.get_mut(&buffer_id).unwrap();
+        cursor.position = at(2, 0);
+        // Ending at the start of line 2 leaves that line out
+        cursor.selection = Some(Range {
+            start: at(0, 1),
+            end: at(2, 0),
+        });
+
+        let _ = state.execute_command(super::Command::MoveLines {
+            buffer_id,
+            up: false,
+        });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "three\none\ntwo\nfour"
+        );
+        let cursor = state.get_cursor_state(buffer_id).unwrap();
+        assert_eq!(cursor.position(), at(3, 0));
+        assert_eq!(cursor.selection().unwrap().start, at(1, 1));
+
+        let _ = state.execute_command(super::Command::DuplicateLines { buffer_id });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "three\none\ntwo\none\ntwo\nfour"
+        );
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            at(5, 0)
+        );
+
+        let _ = state.execute_command(super::Command::DeleteLines { buffer_id });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "three\none\ntwo\nfour"
+        );
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            at(3, 0)
+        );
+
+        state.undo(buffer_id).unwrap();
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "three\none\ntwo\none\ntwo\nfour"
+        );
+    }
+
+    #[test]
+    fn line_commands_keep_the_caret_column_in_bytes() {
+        use super::super::types::Position;
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("ab\nééé\nx".to_string());
+        state.cursors.get_mut(&buffer_id).unwrap().position = Position { line: 1, column: 4 };
+        let offset = |state: &State| {
+            let caret = state.get_cursor_state(buffer_id).unwrap().position();
+            state.buffers[&buffer_id].position_to_offset(caret)
+        };
+
+        // The copy below keeps the caret between the same characters
+        let _ = state.execute_command(super::Command::DuplicateLines { buffer_id });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab\nééé\nééé\nx");
+        assert_eq!(offset(&state), 14);
+
+        // A shorter line below takes the caret to its end, not past it
+        let _ = state.execute_command(super::Command::DeleteLines { buffer_id });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab\nééé\nx");
+        assert_eq!(offset(&state), 11);
+
+        // From the end of a wider line the column is clamped the same way
+        state.cursors.get_mut(&buffer_id).unwrap().position = Position { line: 1, column: 6 };
+        let _ = state.execute_command(super::Command::DeleteLines { buffer_id });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ab\nx");
+        assert_eq!(offset(&state), 4);
+    }
+
+    #[test]
+    fn line_commands_stop_at_the_buffer_edges() {
+        use super::super::types::Position;
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("first\nlast".to_string());
+
+        let _ = state.execute_command(super::Command::MoveLines {
+            buffer_id,
+            up: true,
+        });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "first\nlast");
+
+        state.cursors.get_mut(&buffer_id).unwrap().position = Position { line: 1, column: 4 };
+        let _ = state.execute_command(super::Command::MoveLines {
+            buffer_id,
+            up: true,
+        });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "last\nfirst");
+        let _ = state.execute_command(super::Command::DeleteLines { buffer_id });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "first");
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            Position { line: 0, column: 4 }
+        );
+    }
+
+    #[test]
+    fn indenting_lines_keeps_the_selection_on_its_text() {
+        use super::super::types::{Position, Range};
+        let at = |line, column| Position { line, column };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("a\n\n\tb\n  c\nd".to_string());
+        let _ = state.execute_command(super::Command::MoveCursor {
+            buffer_id,
+            position: at(3, 0),
+        });
+        let _ = state.execute_command(super::Command::SetSelection {
+            buffer_id,
+            range: Range {
+                start: at(0, 1),
+                end: at(3, 0),
+            },
+        });
+
+        let _ = state.execute_command(super::Command::IndentLines {
+            buffer_id,
+            unit: "    ".to_string(),
+        });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "    a\n\n    \tb\n  c\nd"
+        );
+        let cursor = state.get_cursor_state(buffer_id).unwrap();
+        assert_eq!(cursor.position(), at(3, 0));
+        assert_eq!(
+            cursor.selection(),
+            Some(Range {
+                start: at(0, 5),
+                end: at(3, 0)
+            })
+        );
+
+        // One level comes off each line: four spaces, a tab, or the spaces there are
+        let _ = state.execute_command(super::Command::SetSelection {
+            buffer_id,
+            range: Range {
+                start: at(0, 5),
+                end: at(3, 3),
+            },
+        });
+        let _ = state.execute_command(super::Command::DedentLines {
+            buffer_id,
+            tab_size: 4,
+        });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "a\n\n\tb\nc\nd");
+        let selection = state.get_cursor_state(buffer_id).unwrap().selection();
+        assert_eq!(
+            selection,
+            Some(Range {
+                start: at(0, 1),
+                end: at(3, 1)
+            })
+        );
+
+        // Both were single steps
+        state.undo(buffer_id).unwrap();
+        state.undo(buffer_id).unwrap();
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "a\n\n\tb\n  c\nd"
+        );
+    }
+
+    #[test]
+    fn sorting_lines_replaces_and_selects_them() {
+        use super::super::types::{Position, Range};
+        let at = |line, column| Position { line, column };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("keep\nb\nC\na\nB\nkeep".to_string());
+        let select = |state: &mut State| {
+            let _ = state.execute_command(super::Command::SetSelection {
+                buffer_id,
+                range: Range {
+                    start: at(1, 0),
+                    end: at(5, 0),
+                },
+            });
+        };
+        let sort = |state: &mut State, case_insensitive, reverse| {
+            let _ = state.execute_command(super::Command::SortLines {
+                buffer_id,
+                case_insensitive,
+                reverse,
+            });
+            state.get_buffer_text(buffer_id).unwrap()
+        };
+
+        select(&mut state);
+        assert_eq!(sort(&mut state, false, false), "keep\nB\nC\na\nb\nkeep");
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().selection(),
+            Some(Range {
+                start: at(1, 0),
+                end: at(5, 0)
+            })
+        );
+        // Lines equal but for case keep their order, also in reverse
+        assert_eq!(sort(&mut state, true, false), "keep\na\nB\nb\nC\nkeep");
+        assert_eq!(sort(&mut state, true, true), "keep\nC\nB\nb\na\nkeep");
+        assert_eq!(sort(&mut state, false, true), "keep\nb\na\nC\nB\nkeep");
+
+        // Sorting sorted lines is no step, so undo reverts the last sort that changed them
+        assert_eq!(sort(&mut state, false, true), "keep\nb\na\nC\nB\nkeep");
+        state.undo(buffer_id).unwrap();
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "keep\nC\nB\nb\na\nkeep"
+        );
+
+        // A caret alone touches one line, which is already sorted
+        let _ = state.execute_command(super::Command::MoveCursor {
+            buffer_id,
+            position: at(1, 0),
+        });
+        assert_eq!(sort(&mut state, false, false), "keep\nC\nB\nb\na\nkeep");
+
+        // The selection ends after the last sorted character, whose column counts bytes
+        let buffer_id = state.create_buffer("ä\nzz".to_string());
+        let _ = state.execute_command(super::Command::SetSelection {
+            buffer_id,
+            range: Range {
+                start: at(0, 0),
+                end: at(1, 1),
+            },
+        });
+        let _ = state.execute_command(super::Command::SortLines {
+            buffer_id,
+            case_insensitive: false,
+            reverse: false,
+        });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "zz\nä");
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().selection(),
+            Some(Range {
+                start: at(0, 0),
+                end: at(1, 2)
+            })
+        );
+    }
+
+    #[test]
+    fn join_lines_collapses_whitespace() {
+        use super::super::types::{Position, Range};
+        let at = |line, column| Position { line, column };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("fn main() {  \n    call();\n\n  }\nrest".to_string());
+
+        let _ = state.execute_command(super::Command::JoinLines { buffer_id });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "fn main() { call();\n\n  }\nrest"
+        );
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            at(0, 12)
+        );
+
+        state.cursors.get_mut(&buffer_id).unwrap().selection = Some(Range {
+            start: at(0, 3),
+            end: at(2, 1),
+        });
+        let _ = state.execute_command(super::Command::JoinLines { buffer_id });
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "fn main() { call(); }\nrest"
+        );
+        let cursor = state.get_cursor_state(buffer_id).unwrap();
+        assert_eq!(cursor.position(), at(0, 20));
+        assert!(cursor.selection().is_none());
+
+        state.undo(buffer_id).unwrap();
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "fn main() { call();\n\n  }\nrest"
+        );
+
+        // The caret lands before the joined text, whatever the width of the characters above
+        let buffer_id = state.create_buffer("é\nb".to_string());
+        let _ = state.execute_command(super::Command::JoinLines { buffer_id });
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "é b");
+        let caret = state.get_cursor_state(buffer_id).unwrap().position();
+        assert_eq!(state.buffers[&buffer_id].position_to_offset(caret), 3);
+    }
+
+    #[test]
+    fn indentation_is_detected_from_the_content() {
+        use super::meta::{Indentation, detect_indentation};
+        let detect = |lines: &[&str]| detect_indentation(&lines.join("\n"));
+        assert_eq!(
+            detect(&["fn a() {", "  if x {", "    y();", "  }", "}"]),
+            Some(Indentation::Spaces(2))
+        );
+        assert_eq!(
+            detect(&[
+                "def a():",
+                "    if x:",
+                "        y()",
+                "    z(1,",
+                "      2)",
+                "b = 1"
+            ]),
+            Some(Indentation::Spaces(4))
+        );
+        assert_eq!(
+            detect(&["a {", "\tb {", "\t\tc", "\t}", "  d", "}"]),
+            Some(Indentation::Tabs)
+        );
+        // Block comment continuations and blank lines don't count
+        assert_eq!(
+            detect(&["/**", " * a", " */", "x {", "", "   y", "}"]),
+            Some(Indentation::Spaces(3))
+        );
+        assert_eq!(detect(&["plain", "text"]), None);
+        assert_eq!(detect(&["a", " b"]), None);
+
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("a:\n\tb\n".to_string());
+        let indentation = state.metadata(buffer_id).unwrap().indentation;
+        assert_eq!(indentation, Some(Indentation::Tabs));
+        assert_eq!(indentation.unwrap().unit(), "\t");
+        assert_eq!(Indentation::Spaces(2).width(8), 2);
+    }
+
+    #[test]
+    fn language_and_comment_token_follow_the_file() {
+        use super::meta::{language_for_path, line_comment};
+        assert_eq!(language_for_path("/src/main.RS"), Some("rust"));
+        assert_eq!(language_for_path("notes.md"), Some("markdown"));
+        assert_eq!(language_for_path("Makefile"), None);
+        assert_eq!(line_comment(Some("rust")), Some("//"));
+        assert_eq!(line_comment(Some("python")), Some("#"));
+        assert_eq!(line_comment(Some("lua")), Some("--"));
+        assert_eq!(line_comment(Some("html")), None);
+        assert_eq!(line_comment(None), Some("//"));
+
+        let mut state = State::new();
+        let buffer_id = state.create_buffer(String::new());
+        let _ = state.execute_command(super::Command::SaveBuffer {
+            buffer_id,
+            file_path: "/tmp/script.py".to_string(),
+        });
+        let meta = &state.buffer_metadata[&buffer_id];
+        assert_eq!(meta.language.as_deref(), Some("python"));
+        assert_eq!(meta.line_comment(), Some("#"));
+    }
+
+    #[test]
+    fn get_cursor_state_returns_none_for_nonexistent_buffer() {
+        let state = State::new();
+        let fake_id = ID::new();
+        assert!(state.get_cursor_state(fake_id).is_none());
+    }
+
+    #[test]
+    fn get_active_buffer_returns_active_buffer() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        assert_eq!(state.get_active_buffer(), Some(buffer_id));
+    }
+
+    #[test]
+    fn metadata_changes_are_recorded_once_each() {
+        use super::meta::Change;
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("abc".to_string());
+        state.set_file_path(buffer_id, "/tmp/a.py".to_string());
+        state.set_file_path(buffer_id, "/tmp/a.py".to_string());
+        for offset in [3, 4] {
+            let _ = state.execute_command(super::Command::InsertText {
+                buffer_id,
+                offset,
+                text: "d".to_string(),
+            });
+        }
+        state.mark_saved(buffer_id);
+        state.close_buffer(buffer_id);
+        assert_eq!(
+            state.take_metadata_changes(),
+            vec![
+                Change::Created { buffer_id },
+                Change::Language {
+                    buffer_id,
+                    language: Some("python".to_string())
+                },
+                Change::FilePath {
+                    buffer_id,
+                    path: Some("/tmp/a.py".to_string())
+                },
+                Change::Modified {
+                    buffer_id,
+                    modified: true
+                },
+                Change::Modified {
+                    buffer_id,
+                    modified: false
+                },
+                Change::Closed { buffer_id },
+            ]
+        );
+        assert!(state.take_metadata_changes().is_empty());
+    }
+
+    #[test]
+    fn accessors_reach_buffers_by_id_and_path() {
+        let mut state = State::new();
+        let first = state.create_buffer("abc".to_string());
+        let second = state.create_buffer("def".to_string());
+        state.set_file_path(first, "/a.rs".to_string());
+        assert_eq!(state.buffer_for_path("/a.rs"), Some(first));
+        assert_eq!(state.buffer_for_path("/b.rs"), None);
+        assert_eq!(
+            state.metadata(first).unwrap().language.as_deref(),
+            Some("rust")
+        );
+        assert_eq!(state.buffer(second).unwrap().get_text(0, 3), "def");
+        assert_eq!(state.buffer_ids().count(), 2);
+
+        state.set_active_buffer(Some(first));
+        assert_eq!(state.get_active_buffer(), Some(first));
+        state.set_active_buffer(Some(ID::new()));
+        assert_eq!(state.get_active_buffer(), Some(first));
+        state.set_active_buffer(None);
+        assert_eq!(state.get_active_buffer(), None);
+
+        state.cursor_mut(second).unwrap().preferred_column = Some(2);
+        assert_eq!(
+            state.get_cursor_state(second).unwrap().preferred_column,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn folding_moves_a_hidden_caret_to_the_header() {
+        use crate::types::Position;
+
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("fn a() {\n    b();\n    c();\n}\n".to_string());
+        let caret = |state: &State| state.get_cursor_state(buffer_id).unwrap().position();
+        state
+            .execute_command(super::Command::MoveCursor {
+                buffer_id,
+                position: Position { line: 2, column: 4 },
+            })
+            .unwrap();
+
+        assert!(state.toggle_fold(buffer_id, 2, 4));
+        assert_eq!(
+            state.hidden_lines(buffer_id),
+            [std::ops::Range { start: 1, end: 3 }]
+        );
+        assert_eq!(caret(&state), Position { line: 0, column: 8 });
+
+        // Edits above the fold move it, and toggling on the header unfolds it
+        state
+            .execute_command(super::Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "\n".to_string(),
+            })
+            .unwrap();
+        assert_eq!(
+            state.hidden_lines(buffer_id),
+            [std::ops::Range { start: 2, end: 4 }]
+        );
+        assert!(state.toggle_fold(buffer_id, 1, 4));
+        assert!(state.hidden_lines(buffer_id).is_empty());
+
+        assert!(state.fold_all(buffer_id, 4));
+        assert!(!state.fold_all(buffer_id, 4));
+        assert!(state.folds_mut().unfold_all(buffer_id));
+        assert!(!state.toggle_fold(buffer_id, 0, 4));
+    }
+}
@@ -3,7 +3,7 @@ use super::types::buffer::*;
 
 /// Module containing editor-related commands and their responses.
 pub mod editor {
-    use crate::led::types::{Position, Range};
+    use crate::types::{Position, Range};
     use serde::{Deserialize, Serialize};
 
     /// Represents an editor command, such as inserting or deleting text,
@@ -46,6 +46,73 @@ pub mod editor {
             range: Range,
         },
 
+        /// Command to select the whole text of a buffer, leaving the cursor at its end.
+        SelectAll {
+            /// The ID of the buffer to select in.
+            buffer_id: super::ID,
+        },
+
+        /// Command to delete the lines the cursor or selection is on, leaving the cursor on the
+        /// line that follows them.
+        DeleteLines {
+            /// The ID of the buffer to delete lines from.
+            buffer_id: super::ID,
+        },
+
+        /// Command to copy the lines the cursor or selection is on below them, moving the
+        /// cursor and selection onto the copy.
+        DuplicateLines {
+            /// The ID of the buffer to duplicate lines in.
+            buffer_id: super::ID,
+        },
+
+        /// Command to swap the lines the cursor or selection is on with the line above or below
+        /// them. The cursor and selection move along with the lines; at the start or end of the
+        /// buffer nothing happens.
+        MoveLines {
+            /// The ID of the buffer to move lines in.
+            buffer_id: super::ID,
+            /// Whether the lines move up rather than down.
+            up: bool,
+        },
+
+        /// Command to join the lines a selection touches, or the cursor's line and the next one,
+        /// into one line. Whitespace around each line break becomes a single space, or nothing
+        /// next to a blank line, and the cursor moves to the last join.
+        JoinLines {
+            /// The ID of the buffer to join lines in.
+            buffer_id: super::ID,
+        },
+
+        /// Command to indent the lines the cursor or selection is on by one level, keeping the
+        /// cursor and selection on the same text. Blank lines are left alone.
+        IndentLines {
+            /// The ID of the buffer to indent lines in.
+            buffer_id: super::ID,
+            /// The text of one level of indentation, such as four spaces or a tab.
+            unit: String,
+        },
+
+        /// Command to remove one level of indentation from the lines the cursor or selection is
+        /// on: a leading tab, or up to `tab_size` leading spaces.
+        DedentLines {
+            /// The ID of the buffer to dedent lines in.
+            buffer_id: super::ID,
+            /// How many spaces make one level of indentation.
+            tab_size: usize,
+        },
+
+        /// Command to sort the lines a selection touches, replacing them in one edit and
+        /// selecting them. Without a selection of several lines nothing happens.
+        SortLines {
+            /// The ID of the buffer to sort lines in.
+            buffer_id: super::ID,
+            /// Whether lines that differ only in case count as equal; they keep their order.
+            case_insensitive: bool,
+            /// Whether the lines are sorted from last to first.
+            reverse: bool,
+        },
+
         /// Command to create a new buffer with the given content.
         NewBuffer {
             /// The initial content of the new buffer.
@@ -59,6 +126,16 @@ pub mod editor {
             /// The file path to save the buffer to.
             file_path: String,
         },
+
+        /// Command to close a buffer, discarding its contents.
+        CloseBuffer {
+            /// The ID of the buffer to close.
+            buffer_id: super::ID,
+        },
+
+        /// Command to execute several commands in order as one: if any of them fails, none of
+        /// them take effect.
+        Batch(Vec<Command>),
     }
 
     /// Represents the response to an editor command, including any resulting commands,
@@ -78,7 +155,7 @@ pub mod editor {
 mod tests {
     use super::editor::*;
     use super::*;
-    use crate::led::types::{Position, Range};
+    use crate::types::{Position, Range};
     use uuid::Uuid;
 
     #[test]
@@ -164,6 +241,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn command_close_buffer_fields_are_set_correctly() {
+        let buffer_id = ID(Uuid::new_v4());
+        let cmd = Command::CloseBuffer { buffer_id };
+        if let Command::CloseBuffer { buffer_id: bid } = cmd {
+            assert_eq!(bid, buffer_id);
+        } else {
+            panic!("Expected CloseBuffer variant");
+        }
+    }
+
     #[test]
     fn response_fields_are_set_correctly() {
         let commands = vec![
@@ -192,4 +280,24 @@ mod tests {
         let cmd_back: Command = serde_json::from_str(&json).unwrap();
         assert_eq!(format!("{:?}", cmd), format!("{:?}", cmd_back));
     }
+
+    #[test]
+    fn command_batch_serde_roundtrip() {
+        let buffer_id = ID(Uuid::new_v4());
+        let cmd = Command::Batch(vec![
+            Command::DeleteText {
+                buffer_id,
+                start: 0,
+                length: 3,
+            },
+            Command::Batch(vec![Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "new".to_string(),
+            }]),
+        ]);
+        let json = serde_json::to_string(&cmd).unwrap();
+        let cmd_back: Command = serde_json::from_str(&json).unwrap();
+        assert_eq!(cmd, cmd_back);
+    }
 }
\ No newline at end of file
@@ -1,16 +1,17 @@
-use crate::led::types::{Position, Range};
+use crate::types::{Position, Range};
 
 /// Represents the state of a cursor in the editor, including its position,
 /// optional selection range, and the buffer it belongs to.
 #[derive(Debug, Clone)]
 pub struct State {
     /// The current position of the cursor.
-    pub(crate) position: Position,
+    pub position: Position,
     /// The currently selected range, if any.
-    pub(crate) selection: Option<Range>,
+    pub selection: Option<Range>,
     /// The identifier of the buffer the cursor is associated with.
-    pub(crate) buffer_id: super::buffer::ID,
-    /// The preferred column for vertical navigation (persistent across frames).
+    pub buffer_id: super::buffer::ID,
+    /// The preferred column for vertical navigation (persistent across frames), counted in
+    /// cells from the line's start so that tabs before it don't shift it.
     pub preferred_column: Option<usize>,
 }
 
@@ -50,8 +51,8 @@ impl State {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::led::buffer;
-    use crate::led::types::{Position, Range};
+    use crate::buffer;
+    use crate::types::{Position, Range};
     use uuid::Uuid;
 
     #[test]
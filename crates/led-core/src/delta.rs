@@ -0,0 +1,130 @@
+use super::commands::editor::Command;
+use super::types::buffer::ID;
+
+/// A change to a buffer's text, expressed in byte offsets.
+///
+/// Deltas let anything that remembers offsets into a buffer (marks, for example) follow edits
+/// without re-reading the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delta {
+    /// `length` bytes were inserted at `offset`.
+    Insert { offset: usize, length: usize },
+    /// `length` bytes were removed starting at `start`.
+    Delete { start: usize, length: usize },
+}
+
+impl Delta {
+    /// Returns the delta a command applies to its buffer, if it edits text.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to inspect.
+    ///
+    /// # Returns
+    ///
+    /// The edited buffer and the delta, or `None` for commands that do not change text.
+    pub fn from_command(command: &Command) -> Option<(ID, Self)> {
+        match command {
+            Command::InsertText {
+                buffer_id,
+                offset,
+                text,
+            } => Some((
+                *buffer_id,
+                Self::Insert {
+                    offset: *offset,
+                    length: text.len(),
+                },
+            )),
+            Command::DeleteText {
+                buffer_id,
+                start,
+                length,
+            } => Some((
+                *buffer_id,
+                Self::Delete {
+                    start: *start,
+                    length: *length,
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// Maps an offset from before the change to the equivalent offset after it.
+    ///
+    /// An offset at an insertion point stays in front of the inserted text, and offsets inside
+    /// deleted text collapse to the start of the deletion.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset before the change.
+    pub fn map_offset(&self, offset: usize) -> usize {
+        match *self {
+            Self::Insert { offset: at, length } => {
+                if offset > at {
+                    offset + length
+                } else {
+                    offset
+                }
+            }
+            Self::Delete { start, length } => {
+                if offset >= start + length {
+                    offset - length
+                } else {
+                    offset.min(start)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_shifts_offsets_after_insertion_point() {
+        let delta = Delta::Insert {
+            offset: 5,
+            length: 3,
+        };
+        assert_eq!(delta.map_offset(2), 2);
+        assert_eq!(delta.map_offset(5), 5);
+        assert_eq!(delta.map_offset(6), 9);
+    }
+
+    #[test]
+    fn delete_shifts_and_collapses_offsets() {
+        let delta = Delta::Delete {
+            start: 5,
+            length: 3,
+        };
+        assert_eq!(delta.map_offset(4), 4);
+        assert_eq!(delta.map_offset(6), 5);
+        assert_eq!(delta.map_offset(8), 5);
+        assert_eq!(delta.map_offset(10), 7);
+    }
+
+    #[test]
+    fn from_command_only_reports_text_edits() {
+        let id = ID::new();
+        let insert = Command::InsertText {
+            buffer_id: id,
+            offset: 1,
+            text: "héllo".to_string(),
+        };
+        assert_eq!(
+            Delta::from_command(&insert),
+            Some((
+                id,
+                Delta::Insert {
+                    offset: 1,
+                    length: 6
+                }
+            ))
+        );
+        let close = Command::CloseBuffer { buffer_id: id };
+        assert_eq!(Delta::from_command(&close), None);
+    }
+}
@@ -0,0 +1,150 @@
+use similar::{Algorithm, DiffTag};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Differences to leave out when comparing lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Compare lines without any of their whitespace, so changes to indentation or spacing
+    /// alone don't count.
+    pub ignore_whitespace: bool,
+    /// Treat `\r\n` and `\n` line breaks, and a missing break at the end, as the same.
+    pub ignore_line_endings: bool,
+    /// Compare lines regardless of case.
+    pub ignore_case: bool,
+}
+
+impl Options {
+    /// Returns whether any difference is left out, so a diff may hide changes.
+    pub fn filters(&self) -> bool {
+        self.ignore_whitespace || self.ignore_line_endings || self.ignore_case
+    }
+
+    /// Returns what a line is compared as.
+    fn normalize<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(if self.ignore_line_endings {
+            line.trim_end_matches(['\r', '\n'])
+        } else {
+            line
+        });
+        if self.ignore_whitespace {
+            // Line breaks are still compared unless line endings are ignored as well
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            let mut stripped: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+            stripped.push_str(ending);
+            line = Cow::Owned(stripped);
+        }
+        if self.ignore_case {
+            line = Cow::Owned(line.to_lowercase());
+        }
+        line
+    }
+}
+
+/// A run of changed lines: the lines of the old text that were replaced by lines of the new
+/// one. Either range may be empty, for lines that were only inserted or only deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Indexes of the old text's lines.
+    pub old: Range<usize>,
+    /// Indexes of the new text's lines.
+    pub new: Range<usize>,
+}
+
+/// Splits a text into lines, each with its line break.
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Compares two texts line by line.
+///
+/// # Arguments
+///
+/// * `old` - The text before the change.
+/// * `new` - The text after it.
+/// * `options` - The differences to leave out.
+///
+/// # Returns
+///
+/// The runs of changed lines, in order. Lines outside them are the same in both texts, as
+/// far as `options` are concerned.
+pub fn line_hunks(old: &str, new: &str, options: Options) -> Vec<Hunk> {
+    let normalize = |text| -> Vec<Cow<str>> {
+        lines(text)
+            .into_iter()
+            .map(|line| options.normalize(line))
+            .collect()
+    };
+    let (old, new) = (normalize(old), normalize(new));
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, &old, &new) {
+        let (tag, old, new) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        match hunks.last_mut() {
+            Some(last) if last.old.end == old.start && last.new.end == new.start => {
+                last.old.end = old.end;
+                last.new.end = new.end;
+            }
+            _ => hunks.push(Hunk { old, new }),
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(old: Range<usize>, new: Range<usize>) -> Hunk {
+        Hunk { old, new }
+    }
+
+    #[test]
+    fn finds_changed_runs_of_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+        let hunks = line_hunks(old, new, Options::default());
+        assert_eq!(hunks, [hunk(1..2, 1..2), hunk(4..4, 4..5)]);
+        assert!(line_hunks(old, old, Options::default()).is_empty());
+        assert_eq!(
+            line_hunks("a\nb\n", "", Options::default()),
+            [hunk(0..2, 0..0)]
+        );
+    }
+
+    #[test]
+    fn options_leave_out_differences() {
+        let old = "fn a() {\r\n    b();\r\n}";
+        let new = "fn a() {\n\tb ();\n}\n";
+        assert_eq!(line_hunks(old, new, Options::default()).len(), 1);
+
+        let endings = Options {
+            ignore_line_endings: true,
+            ..Options::default()
+        };
+        assert_eq!(line_hunks(old, new, endings), [hunk(1..2, 1..2)]);
+
+        let whitespace = Options {
+            ignore_whitespace: true,
+            ..Options::default()
+        };
+        // The line breaks still differ
+        assert_eq!(line_hunks(old, new, whitespace).len(), 1);
+        let both = Options {
+            ignore_whitespace: true,
+            ignore_line_endings: true,
+            ignore_case: false,
+        };
+        assert!(both.filters());
+        assert!(line_hunks(old, new, both).is_empty());
+
+        let case = Options {
+            ignore_case: true,
+            ..Options::default()
+        };
+        assert!(line_hunks("Hello\n", "HELLO\n", case).is_empty());
+        assert!(!Options::default().filters());
+    }
+}
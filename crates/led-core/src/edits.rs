@@ -0,0 +1,144 @@
+use super::delta::Delta;
+use super::piece::Table;
+use super::types::buffer::ID;
+use std::collections::VecDeque;
+
+/// How many edit locations are remembered.
+pub const MAX_LOCATIONS: usize = 50;
+
+/// Where an edit was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The edited buffer.
+    pub buffer_id: ID,
+    /// Byte offset just after the edit, where typing would continue.
+    pub offset: usize,
+}
+
+/// The locations of recent edits across all buffers, for going back to where the text was last
+/// changed.
+///
+/// Unlike moving between marks or search hits, only edits are recorded. Edits on the line of
+/// the most recent location update it rather than adding another, so typing a word is one
+/// location. Recorded locations follow later edits to their buffer, like marks.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Oldest first.
+    locations: VecDeque<Location>,
+    /// Index of the location last gone to, while going back through them.
+    current: Option<usize>,
+}
+
+impl History {
+    /// Records an edit and moves the recorded locations of its buffer to follow it.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The edited buffer.
+    /// * `delta` - The change made to its text.
+    /// * `table` - The buffer's text after the change.
+    pub fn record(&mut self, buffer_id: ID, delta: &Delta, table: &Table) {
+        for location in self.locations.iter_mut() {
+            if location.buffer_id == buffer_id {
+                location.offset = delta.map_offset(location.offset);
+            }
+        }
+        let offset = match *delta {
+            Delta::Insert { offset, length } => offset + length,
+            Delta::Delete { start, .. } => start,
+        };
+        let location = Location { buffer_id, offset };
+        let same_line = self.locations.back().is_some_and(|last| {
+            last.buffer_id == buffer_id
+                && table.offset_to_position(last.offset.min(table.len())).line
+                    == table.offset_to_position(offset).line
+        });
+        if same_line {
+            self.locations.pop_back();
+        } else if self.locations.len() == MAX_LOCATIONS {
+            self.locations.pop_front();
+        }
+        self.locations.push_back(location);
+        self.current = None;
+    }
+
+    /// Returns the location to go to for "Go to Last Edit": the most recent edit first, then
+    /// each one before it on every further call, wrapping around to the most recent again.
+    pub fn previous(&mut self) -> Option<Location> {
+        let last = self.locations.len().checked_sub(1)?;
+        let index = match self.current {
+            Some(0) | None => last,
+            Some(index) => index - 1,
+        };
+        self.current = Some(index);
+        self.locations.get(index).copied()
+    }
+
+    /// Forgets the locations in a closed buffer.
+    pub fn close_buffer(&mut self, buffer_id: ID) {
+        self.locations.retain(|l| l.buffer_id != buffer_id);
+        self.current = None;
+    }
+
+    /// Returns the recorded locations, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Location> {
+        self.locations.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(history: &mut History, table: &mut Table, buffer_id: ID, offset: usize, text: &str) {
+        table.insert(offset, text).unwrap();
+        let delta = Delta::Insert {
+            offset,
+            length: text.len(),
+        };
+        history.record(buffer_id, &delta, table);
+    }
+
+    #[test]
+    fn edits_on_one_line_are_one_location() {
+        let buffer_id = ID::new();
+        let mut table = Table::new("one\ntwo\nthree\n".to_string());
+        let mut history = History::default();
+        edit(&mut history, &mut table, buffer_id, 0, "a");
+        edit(&mut history, &mut table, buffer_id, 1, "b");
+        edit(&mut history, &mut table, buffer_id, 10, "c");
+        let offsets: Vec<usize> = history.iter().map(|l| l.offset).collect();
+        assert_eq!(offsets, [2, 11]);
+
+        // Locations follow edits before them
+        edit(&mut history, &mut table, buffer_id, 0, "\n");
+        let offsets: Vec<usize> = history.iter().map(|l| l.offset).collect();
+        assert_eq!(offsets, [3, 12, 1]);
+    }
+
+    #[test]
+    fn previous_cycles_back_through_buffers() {
+        let (first, second) = (ID::new(), ID::new());
+        let mut first_table = Table::new("a\nb\n".to_string());
+        let mut second_table = Table::new("x\n".to_string());
+        let mut history = History::default();
+        assert_eq!(history.previous(), None);
+        edit(&mut history, &mut first_table, first, 0, "1");
+        edit(&mut history, &mut second_table, second, 0, "2");
+        edit(&mut history, &mut first_table, first, 3, "3");
+
+        let visited: Vec<(ID, usize)> = (0..4)
+            .map(|_| history.previous().unwrap())
+            .map(|l| (l.buffer_id, l.offset))
+            .collect();
+        assert_eq!(visited, [(first, 4), (second, 1), (first, 1), (first, 4)]);
+
+        // A new edit starts again from the most recent location
+        history.previous();
+        edit(&mut history, &mut second_table, second, 3, "4");
+        assert_eq!(history.previous().unwrap().offset, 4);
+
+        history.close_buffer(second);
+        assert!(history.iter().all(|l| l.buffer_id == first));
+    }
+}
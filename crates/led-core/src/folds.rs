@@ -0,0 +1,430 @@
+use super::delta::Delta;
+use super::piece::Table;
+use super::types::{Position, buffer::ID};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Lines that can be folded away: the lines after `start` up to and including `end`. The
+/// `start` line stays visible as the fold's header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// The header line, counted from 0.
+    pub start: usize,
+    /// The last line folded away.
+    pub end: usize,
+}
+
+impl Region {
+    /// Returns the lines the region hides when folded.
+    pub fn hidden(&self) -> Range<usize> {
+        self.start + 1..self.end + 1
+    }
+
+    /// Returns whether a line is the region's header or one of the lines it folds away.
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+}
+
+/// Finds the regions of a text that can be folded, ordered by their header line.
+///
+/// A line with an unclosed `{` starts a region that reaches up to the line before the matching
+/// `}`, so the closing brace stays visible. Any other line starts one if the lines after it are
+/// indented deeper; the region reaches up to the last of them, with blank lines between them
+/// folded too. A region folds away at least one line.
+///
+/// # Arguments
+///
+/// * `text` - The buffer's text.
+/// * `tab_size` - How many columns of indentation a tab counts for.
+pub fn regions(text: &str, tab_size: usize) -> Vec<Region> {
+    let lines: Vec<&str> = text.lines().collect();
+    // The last line of the region each header starts
+    let mut ends: BTreeMap<usize, usize> = BTreeMap::new();
+
+    let mut open = Vec::new();
+    for (number, line) in lines.iter().enumerate() {
+        for byte in line.bytes() {
+            match byte {
+                b'{' => open.push(number),
+                b'}' => {
+                    if let Some(start) = open.pop()
+                        && number > start + 1
+                    {
+                        let end = ends.entry(start).or_insert(number - 1);
+                        *end = (*end).max(number - 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let indents: Vec<Option<usize>> = lines
+        .iter()
+        .map(|line| indent_width(line, tab_size))
+        .collect();
+    for (start, indent) in indents.iter().enumerate() {
+        let Some(indent) = *indent else {
+            continue;
+        };
+        if ends.contains_key(&start) {
+            continue;
+        }
+        let deeper = indents[start + 1..]
+            .iter()
+            .take_while(|other| other.is_none_or(|other| other > indent))
+            .enumerate()
+            .filter(|(_, other)| other.is_some())
+            .last();
+        if let Some((index, _)) = deeper {
+            ends.insert(start, start + 1 + index);
+        }
+    }
+
+    ends.into_iter()
+        .map(|(start, end)| Region { start, end })
+        .collect()
+}
+
+/// Returns the width of a line's indentation, or `None` for a blank line.
+fn indent_width(line: &str, tab_size: usize) -> Option<usize> {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_size - width % tab_size.max(1),
+            c if c.is_whitespace() => {}
+            _ => return Some(width),
+        }
+    }
+    None
+}
+
+/// Returns the innermost region around a line, if any.
+pub fn innermost(regions: &[Region], line: usize) -> Option<Region> {
+    regions
+        .iter()
+        .filter(|region| region.contains(line))
+        .max_by_key(|region| region.start)
+        .copied()
+}
+
+/// Returns whether a line is among the hidden lines returned by [`Registry::hidden_lines`].
+pub fn is_hidden(hidden: &[Range<usize>], line: usize) -> bool {
+    let index = hidden.partition_point(|range| range.end <= line);
+    hidden.get(index).is_some_and(|range| range.start <= line)
+}
+
+/// Returns the line that moving onto `line` lands on, skipping folded lines: the line after them
+/// when moving down, their header when moving up.
+///
+/// # Arguments
+///
+/// * `hidden` - The hidden lines, as returned by [`Registry::hidden_lines`].
+/// * `line` - The line moved onto.
+/// * `down` - Whether the move goes down rather than up.
+pub fn skip_hidden(hidden: &[Range<usize>], line: usize, down: bool) -> usize {
+    let index = hidden.partition_point(|range| range.end <= line);
+    match hidden.get(index).filter(|range| range.start <= line) {
+        Some(range) if down => range.end,
+        Some(range) => range.start - 1,
+        None => line,
+    }
+}
+
+/// A folded region, kept as byte offsets so that edits move it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fold {
+    buffer_id: ID,
+    /// Byte offset of the end of the header line.
+    start: usize,
+    /// Byte offset of the end of the last folded line.
+    end: usize,
+}
+
+/// The folded regions of all buffers.
+///
+/// Folds follow edits through [`Registry::apply`]; one whose folded lines were all deleted is
+/// dropped. Nested folds may overlap, and a line stays hidden as long as any fold hides it.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    folds: Vec<Fold>,
+}
+
+impl Registry {
+    /// Folds a region of a buffer.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the region was already folded, folds away no line or is past the text.
+    pub fn fold(&mut self, buffer_id: ID, region: Region, table: &Table) -> bool {
+        if region.end <= region.start || region.end >= table.lines() {
+            return false;
+        }
+        let fold = Fold {
+            buffer_id,
+            start: line_end(table, region.start),
+            end: line_end(table, region.end),
+        };
+        if self.folds.contains(&fold) {
+            return false;
+        }
+        self.folds.push(fold);
+        true
+    }
+
+    /// Returns the folded regions of a buffer, ordered by their header line.
+    pub fn folded(&self, buffer_id: ID, table: &Table) -> Vec<Region> {
+        let folds: Vec<&Fold> = self
+            .folds
+            .iter()
+            .filter(|fold| fold.buffer_id == buffer_id)
+            .collect();
+        let offsets: Vec<usize> = folds
+            .iter()
+            .flat_map(|fold| [fold.start, fold.end])
+            .collect();
+        let lines = lines_at(table, &offsets);
+        let mut regions: Vec<Region> = lines
+            .chunks_exact(2)
+            .map(|pair| Region {
+                start: pair[0],
+                end: pair[1],
+            })
+            .filter(|region| region.end > region.start)
+            .collect();
+        regions.sort_by_key(|region| (region.start, region.end));
+        regions.dedup();
+        regions
+    }
+
+    /// Returns the lines hidden in a buffer, as ordered ranges that don't overlap.
+    pub fn hidden_lines(&self, buffer_id: ID, table: &Table) -> Vec<Range<usize>> {
+        let mut hidden: Vec<Range<usize>> = Vec::new();
+        for range in self.folded(buffer_id, table).iter().map(Region::hidden) {
+            match hidden.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => hidden.push(range),
+            }
+        }
+        hidden
+    }
+
+    /// Unfolds the folds of a buffer that hide a line, so that it shows.
+    ///
+    /// # Returns
+    ///
+    /// Whether any fold was unfolded.
+    pub fn reveal(&mut self, buffer_id: ID, line: usize, table: &Table) -> bool {
+        self.unfold_where(buffer_id, table, |region| region.hidden().contains(&line))
+    }
+
+    /// Unfolds the innermost fold of a buffer with a line as its header or among its folded
+    /// lines.
+    ///
+    /// # Returns
+    ///
+    /// Whether a fold was unfolded.
+    pub fn unfold(&mut self, buffer_id: ID, line: usize, table: &Table) -> bool {
+        let Some(region) = innermost(&self.folded(buffer_id, table), line) else {
+            return false;
+        };
+        self.unfold_where(buffer_id, table, |folded| folded == region)
+    }
+
+    /// Unfolds everything in a buffer.
+    ///
+    /// # Returns
+    ///
+    /// Whether anything was folded.
+    pub fn unfold_all(&mut self, buffer_id: ID) -> bool {
+        let before = self.folds.len();
+        self.folds.retain(|fold| fold.buffer_id != buffer_id);
+        self.folds.len() != before
+    }
+
+    /// Removes the folds of a buffer whose current region matches.
+    fn unfold_where(
+        &mut self,
+        buffer_id: ID,
+        table: &Table,
+        matches: impl Fn(Region) -> bool,
+    ) -> bool {
+        let before = self.folds.len();
+        let mut folds = std::mem::take(&mut self.folds);
+        folds.retain(|fold| {
+            if fold.buffer_id != buffer_id {
+                return true;
+            }
+            let lines = lines_at(table, &[fold.start, fold.end]);
+            !matches(Region {
+                start: lines[0],
+                end: lines[1],
+            })
+        });
+        self.folds = folds;
+        self.folds.len() != before
+    }
+
+    /// Forgets the folds of a closed buffer.
+    pub fn clear_buffer(&mut self, buffer_id: ID) {
+        self.unfold_all(buffer_id);
+    }
+
+    /// Returns a copy of the folds of one buffer.
+    pub(crate) fn of_buffer(&self, buffer_id: ID) -> Registry {
+        Registry {
+            folds: self
+                .folds
+                .iter()
+                .filter(|fold| fold.buffer_id == buffer_id)
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Removes the folds of one buffer and returns them.
+    pub(crate) fn take_buffer(&mut self, buffer_id: ID) -> Registry {
+        let (taken, kept) = std::mem::take(&mut self.folds)
+            .into_iter()
+            .partition(|fold| fold.buffer_id == buffer_id);
+        self.folds = kept;
+        Registry { folds: taken }
+    }
+
+    /// Adds the folds of another registry.
+    pub(crate) fn merge(&mut self, other: Registry) {
+        self.folds.extend(other.folds);
+    }
+
+    /// Moves the folds of a buffer to follow an edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The edited buffer.
+    /// * `delta` - The change made to its text.
+    pub fn apply(&mut self, buffer_id: ID, delta: &Delta) {
+        if let Delta::Delete { start, length } = *delta {
+            // A fold goes with its folded lines
+            self.folds.retain(|fold| {
+                fold.buffer_id != buffer_id || start > fold.start + 1 || fold.end > start + length
+            });
+        }
+        for fold in self.folds.iter_mut().filter(|f| f.buffer_id == buffer_id) {
+            fold.start = delta.map_offset(fold.start);
+            fold.end = delta.map_offset(fold.end);
+        }
+        self.folds.retain(|fold| fold.end > fold.start);
+    }
+}
+
+/// Returns the byte offset of the end of a line, before its line break.
+fn line_end(table: &Table, line: usize) -> usize {
+    if line + 1 < table.lines() {
+        table.position_to_offset(Position {
+            line: line + 1,
+            column: 0,
+        }) - 1
+    } else {
+        table.len()
+    }
+}
+
+/// Returns the line of each offset, counting line breaks in one pass over the text.
+fn lines_at(table: &Table, offsets: &[usize]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|&index| offsets[index]);
+    let mut lines = vec![0; offsets.len()];
+    let mut bytes = table.bytes_from(0);
+    let (mut line, mut offset) = (0, 0);
+    for index in order {
+        while offset < offsets[index] {
+            match bytes.next() {
+                Some(b'\n') => line += 1,
+                Some(_) => {}
+                None => break,
+            }
+            offset += 1;
+        }
+        lines[index] = line;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(start: usize, end: usize) -> Region {
+        Region { start, end }
+    }
+
+    #[test]
+    fn regions_follow_braces_and_indentation() {
+        let text = "fn main() {\n    if x {\n        a();\n    } else {\n        b();\n\n        c();\n    }\n}\n";
+        assert_eq!(regions(text, 4), [region(0, 7), region(1, 2), region(3, 6)]);
+
+        let text = "def f():\n\tif x:\n\t\treturn 1\n\n\treturn 2\n\nnext = 1\n[\n]\n";
+        assert_eq!(regions(text, 4), [region(0, 4), region(1, 2)]);
+        assert_eq!(innermost(&regions(text, 4), 2), Some(region(1, 2)));
+        assert_eq!(innermost(&regions(text, 4), 6), None);
+    }
+
+    #[test]
+    fn folds_hide_lines_and_follow_edits() {
+        let mut table = Table::new("a {\n  b\n  c\n}\nd {\n  e\n}".to_string());
+        let mut registry = Registry::default();
+        let (id, other) = (ID::new(), ID::new());
+        assert!(registry.fold(id, region(0, 2), &table));
+        assert!(!registry.fold(id, region(0, 2), &table));
+        assert!(!registry.fold(id, region(6, 6), &table));
+        assert!(registry.fold(id, region(4, 5), &table));
+        assert!(registry.fold(other, region(0, 1), &table));
+        assert_eq!(registry.hidden_lines(id, &table), [1..3, 5..6]);
+
+        // A line inserted before the first fold moves both down
+        table.insert(0, "top\n").unwrap();
+        registry.apply(
+            id,
+            &Delta::Insert {
+                offset: 0,
+                length: 4,
+            },
+        );
+        assert_eq!(registry.folded(id, &table), [region(1, 3), region(5, 6)]);
+
+        assert!(registry.reveal(id, 6, &table));
+        assert!(!registry.reveal(id, 1, &table));
+        assert!(registry.unfold(id, 1, &table));
+        assert!(registry.folded(id, &table).is_empty());
+
+        // Deleting the folded lines drops the fold
+        assert!(registry.fold(id, region(1, 3), &table));
+        table.delete(8, 8).unwrap();
+        registry.apply(
+            id,
+            &Delta::Delete {
+                start: 8,
+                length: 8,
+            },
+        );
+        assert_eq!(table.get_text(0, table.len()), "top\na {\n}\nd {\n  e\n}");
+        assert!(registry.folded(id, &table).is_empty());
+        assert_eq!(
+            registry.hidden_lines(other, &table),
+            [Range { start: 1, end: 2 }]
+        );
+    }
+
+    #[test]
+    fn moves_skip_hidden_lines() {
+        let hidden = [2..4, 6..7];
+        assert_eq!(skip_hidden(&hidden, 2, true), 4);
+        assert_eq!(skip_hidden(&hidden, 3, false), 1);
+        assert_eq!(skip_hidden(&hidden, 5, true), 5);
+        assert_eq!(skip_hidden(&hidden, 6, false), 5);
+        assert!(is_hidden(&hidden, 3));
+        assert!(!is_hidden(&hidden, 4));
+    }
+}
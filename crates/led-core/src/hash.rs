@@ -0,0 +1,295 @@
+use super::delta::Delta;
+use super::piece::Table;
+
+/// Chunks are never cut shorter than this, except at the end of the text.
+const MIN_CHUNK: usize = 256;
+/// Chunks are always cut at this length, even without a content boundary.
+const MAX_CHUNK: usize = 16 * 1024;
+/// A content boundary is where the low bits of the rolling hash are all zero, which happens
+/// every 2 KiB on average.
+const BOUNDARY_MASK: u64 = (1 << 11) - 1;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Pseudo-random values for the rolling "gear" hash, one per byte value.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64
+    let mut table = [0; 256];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// A run of content with its hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Chunk {
+    len: usize,
+    hash: u64,
+}
+
+/// Splits a byte stream into content-defined chunks.
+///
+/// Where a chunk ends depends only on the bytes since the chunk started, so chunking resumed
+/// from any chunk boundary yields the same chunks as chunking from the start.
+#[derive(Debug, Clone, Copy)]
+struct Chunker {
+    len: usize,
+    gear: u64,
+    hash: u64,
+}
+
+impl Chunker {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            gear: 0,
+            hash: FNV_OFFSET,
+        }
+    }
+
+    /// Adds a byte, returning the finished chunk if a boundary follows it.
+    fn push(&mut self, byte: u8) -> Option<Chunk> {
+        self.len += 1;
+        self.gear = (self.gear << 1).wrapping_add(GEAR[byte as usize]);
+        self.hash = (self.hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+        let boundary = self.len >= MIN_CHUNK && self.gear & BOUNDARY_MASK == 0;
+        if boundary || self.len >= MAX_CHUNK {
+            let chunk = self.chunk();
+            *self = Self::new();
+            Some(chunk)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the final, possibly short, chunk at the end of the text.
+    fn finish(self) -> Option<Chunk> {
+        (self.len > 0).then(|| self.chunk())
+    }
+
+    fn chunk(&self) -> Chunk {
+        Chunk {
+            len: self.len,
+            hash: self.hash,
+        }
+    }
+}
+
+/// A hash of a buffer's content that can be kept up to date from edits.
+///
+/// The content is split into content-defined chunks that are hashed separately. After an edit
+/// only the chunks around it are rehashed, and the result is identical to hashing the new
+/// content from scratch, so digests of a buffer and of a file on disk can be compared directly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentHash {
+    chunks: Vec<Chunk>,
+}
+
+impl ContentHash {
+    /// Hashes content from scratch.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The content.
+    pub fn of(bytes: impl IntoIterator<Item = u8>) -> Self {
+        let mut chunker = Chunker::new();
+        let mut chunks: Vec<Chunk> = bytes.into_iter().filter_map(|b| chunker.push(b)).collect();
+        chunks.extend(chunker.finish());
+        Self { chunks }
+    }
+
+    /// Hashes the content of a piece table from scratch.
+    pub fn of_table(table: &Table) -> Self {
+        Self::of(table.bytes_from(0))
+    }
+
+    /// Returns the length of the hashed content in bytes.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|c| c.len).sum()
+    }
+
+    /// Returns `true` if the hashed content is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns a single value summarizing the content. Equal content has equal digests.
+    pub fn digest(&self) -> u64 {
+        self.chunks.iter().fold(FNV_OFFSET, |acc, chunk| {
+            let acc = (acc ^ chunk.hash).wrapping_mul(FNV_PRIME);
+            (acc ^ chunk.len as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    /// Updates the hash after an edit, rehashing only the chunks the edit touched.
+    ///
+    /// Chunking restarts at the boundary before the edit and stops as soon as a new boundary
+    /// lines up with an old boundary past the edit, since everything after it is unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - The edit.
+    /// * `table` - The content after the edit.
+    pub fn apply(&mut self, delta: &Delta, table: &Table) {
+        // Where the edit starts, where it ends in the old text, and where it ends in the new
+        let (edit_start, old_edit_end, edit_end) = match *delta {
+            Delta::Insert { offset, length } => (offset, offset, offset + length),
+            Delta::Delete { start, length } => (start, start + length, start),
+        };
+
+        // The chunk containing the edit; an edit at the very end belongs to the last chunk,
+        // whose end is the end of the text rather than a content boundary
+        let mut first = 0;
+        let mut chunk_start = 0;
+        while first + 1 < self.chunks.len() && chunk_start + self.chunks[first].len <= edit_start {
+            chunk_start += self.chunks[first].len;
+            first += 1;
+        }
+
+        // Old boundaries past the edit, in new offsets, where rechunking can stop
+        let mut resync = Vec::new();
+        let mut old_end = chunk_start;
+        for (i, chunk) in self.chunks.iter().enumerate().skip(first) {
+            old_end += chunk.len;
+            if i + 1 == self.chunks.len() {
+                break;
+            }
+            let new_end = delta.map_offset(old_end);
+            if old_end > edit_start && old_end >= old_edit_end {
+                resync.push((i, new_end));
+            }
+        }
+
+        let mut rehashed = Vec::new();
+        let mut chunker = Chunker::new();
+        let mut position = chunk_start;
+        let mut candidates = resync.into_iter().peekable();
+        for byte in table.bytes_from(chunk_start) {
+            position += 1;
+            let Some(chunk) = chunker.push(byte) else {
+                continue;
+            };
+            rehashed.push(chunk);
+            while candidates.next_if(|&(_, end)| end < position).is_some() {}
+            if let Some(&(last, end)) = candidates.peek()
+                && end == position
+                && position >= edit_end
+            {
+                self.chunks.splice(first..=last, rehashed);
+                return;
+            }
+        }
+        rehashed.extend(chunker.finish());
+        self.chunks.truncate(first);
+        self.chunks.extend(rehashed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random numbers for edit sequences.
+    fn lcg(state: &mut u64) -> usize {
+        *state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (*state >> 33) as usize
+    }
+
+    fn sample_text(len: usize) -> String {
+        let mut state = 7;
+        (0..len)
+            .map(|_| match lcg(&mut state) % 12 {
+                0 => '\n',
+                1 | 2 => ' ',
+                n => (b'a' + n as u8) as char,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn equal_content_has_equal_digest() {
+        let a = ContentHash::of("hello world".bytes());
+        let b = ContentHash::of("hello world".bytes());
+        let c = ContentHash::of("hello world!".bytes());
+        assert_eq!(a.digest(), b.digest());
+        assert_ne!(a.digest(), c.digest());
+        assert_eq!(a.len(), 11);
+        assert!(ContentHash::of("".bytes()).is_empty());
+    }
+
+    #[test]
+    fn long_content_is_split_into_bounded_chunks() {
+        let text = sample_text(100_000);
+        let hash = ContentHash::of(text.bytes());
+        assert!(hash.chunks.len() > 10);
+        assert!(hash.chunks.iter().all(|c| c.len <= MAX_CHUNK));
+        assert_eq!(hash.len(), text.len());
+    }
+
+    #[test]
+    fn incremental_updates_match_hashing_from_scratch() {
+        let mut table = Table::new(sample_text(60_000));
+        let mut hash = ContentHash::of_table(&table);
+        let mut state = 42;
+        for _ in 0..200 {
+            let offset = lcg(&mut state) % (table.len() + 1);
+            let delta = if lcg(&mut state).is_multiple_of(2) {
+                let text = sample_text(lcg(&mut state) % 600);
+                table.insert(offset, &text).unwrap();
+                Delta::Insert {
+                    offset,
+                    length: text.len(),
+                }
+            } else {
+                let length = (lcg(&mut state) % 600).min(table.len() - offset);
+                table.delete(offset, length).unwrap();
+                Delta::Delete {
+                    start: offset,
+                    length,
+                }
+            };
+            hash.apply(&delta, &table);
+            assert_eq!(hash, ContentHash::of_table(&table));
+        }
+    }
+
+    #[test]
+    fn edits_that_cancel_out_restore_the_digest() {
+        let mut table = Table::new(sample_text(20_000));
+        let mut hash = ContentHash::of_table(&table);
+        let original = hash.digest();
+
+        table.insert(5_000, "typo").unwrap();
+        hash.apply(
+            &Delta::Insert {
+                offset: 5_000,
+                length: 4,
+            },
+            &table,
+        );
+        assert_ne!(hash.digest(), original);
+
+        table.delete(5_000, 4).unwrap();
+        hash.apply(
+            &Delta::Delete {
+                start: 5_000,
+                length: 4,
+            },
+            &table,
+        );
+        assert_eq!(hash.digest(), original);
+    }
+}
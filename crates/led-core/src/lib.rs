@@ -0,0 +1,26 @@
+//! The editing engine behind LED: buffers and their piece tables, cursors, views, marks,
+//! virtual text, folds, search and the commands that change them, and saving files safely,
+//! without any GUI.
+//!
+//! The `led` crate re-exports every module, so code written against `led::buffer` and friends
+//! keeps working.
+
+pub mod buffer;
+pub mod commands;
+pub mod cursor;
+pub mod delta;
+pub mod diff;
+pub mod edits;
+pub mod folds;
+pub mod hash;
+pub mod marks;
+pub mod piece_table;
+pub mod save;
+pub mod search;
+pub mod snapshot;
+pub mod types;
+pub mod util;
+pub mod view;
+pub mod virtual_text;
+
+pub use piece_table::piece;
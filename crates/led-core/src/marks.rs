@@ -0,0 +1,294 @@
+use super::buffer::editor::State;
+use super::delta::Delta;
+use super::piece::Table;
+use super::types::{Position, buffer::ID};
+use serde::{Deserialize, Serialize};
+
+/// A named position in a buffer, kept as a byte offset so edits can move it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    /// The mark's name, a letter.
+    pub name: char,
+    /// The buffer the mark points into.
+    pub buffer_id: ID,
+    /// Byte offset of the mark within the buffer.
+    pub offset: usize,
+}
+
+/// A mark in a file on disk, as stored in the session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileMark {
+    /// The mark's name, a letter.
+    pub name: char,
+    /// Path of the file the mark points into.
+    pub path: String,
+    /// Line and column of the mark.
+    pub position: Position,
+}
+
+/// Returns whether `name` can name a mark. Marks are named by ASCII letters.
+pub fn is_valid_name(name: char) -> bool {
+    name.is_ascii_alphabetic()
+}
+
+/// Returns whether a mark name is global.
+///
+/// Lowercase marks are local to their buffer, so every buffer can have its own `a`. Uppercase
+/// marks are unique across all buffers and can be jumped to from anywhere.
+pub fn is_global(name: char) -> bool {
+    name.is_ascii_uppercase()
+}
+
+/// All marks of the editor.
+///
+/// Marks in open buffers follow edits through [`Registry::apply`]. Marks of files that are not
+/// open (restored from the session, or left behind by a closed buffer) are kept as
+/// [`FileMark`]s until the file is opened again.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    /// Marks in open buffers, sorted by name.
+    marks: Vec<Mark>,
+    /// Marks of files that are not currently open.
+    pending: Vec<FileMark>,
+}
+
+impl Registry {
+    /// Sets a mark, replacing any mark of the same name it would shadow.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The mark's name.
+    /// * `buffer_id` - The buffer to place the mark in.
+    /// * `offset` - Byte offset of the mark.
+    ///
+    /// # Returns
+    ///
+    /// `false` if `name` is not a valid mark name.
+    pub fn set(&mut self, name: char, buffer_id: ID, offset: usize) -> bool {
+        if !is_valid_name(name) {
+            return false;
+        }
+        self.marks
+            .retain(|m| !(m.name == name && (is_global(name) || m.buffer_id == buffer_id)));
+        if is_global(name) {
+            self.pending.retain(|m| m.name != name);
+        }
+        self.marks.push(Mark {
+            name,
+            buffer_id,
+            offset,
+        });
+        self.marks.sort_by_key(|m| m.name);
+        true
+    }
+
+    /// Looks up a mark as seen from a buffer: local marks of that buffer, or global marks.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The mark's name.
+    /// * `buffer_id` - The buffer the lookup is made from.
+    pub fn get(&self, name: char, buffer_id: ID) -> Option<Mark> {
+        self.marks
+            .iter()
+            .find(|m| m.name == name && (is_global(name) || m.buffer_id == buffer_id))
+            .copied()
+    }
+
+    /// Returns the marks in open buffers, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter()
+    }
+
+    /// Returns the marks of files that are not currently open.
+    pub fn pending(&self) -> &[FileMark] {
+        &self.pending
+    }
+
+    /// Moves the marks of a buffer to follow an edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The edited buffer.
+    /// * `delta` - The change made to its text.
+    pub fn apply(&mut self, buffer_id: ID, delta: &Delta) {
+        for mark in self.marks.iter_mut().filter(|m| m.buffer_id == buffer_id) {
+            mark.offset = delta.map_offset(mark.offset);
+        }
+    }
+
+    /// Places pending marks of a file into the buffer it was opened in.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer holding the file.
+    /// * `path` - The file's path.
+    /// * `table` - The buffer's text.
+    pub fn open_file(&mut self, buffer_id: ID, path: &str, table: &Table) {
+        let (restored, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|m| m.path == path);
+        self.pending = pending;
+        for mark in restored {
+            // The file may have changed since the mark was saved
+            let offset = table.position_to_offset(table.clamp_position(mark.position));
+            self.set(mark.name, buffer_id, offset);
+        }
+    }
+
+    /// Forgets the marks of a closed buffer, keeping them as pending marks if it was backed by a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The closed buffer.
+    /// * `path` - The buffer's file path, if any.
+    /// * `table` - The buffer's text at the time it was closed.
+    pub fn close_buffer(&mut self, buffer_id: ID, path: Option<&str>, table: &Table) {
+        let (closed, open) = std::mem::take(&mut self.marks)
+            .into_iter()
+            .partition::<Vec<_>, _>(|m| m.buffer_id == buffer_id);
+        self.marks = open;
+        if let Some(path) = path {
+            self.pending
+                .extend(closed.into_iter().map(|m| to_file_mark(&m, path, table)));
+        }
+    }
+
+    /// Replaces the pending marks, e.g. with those restored from a session.
+    pub fn set_pending(&mut self, marks: Vec<FileMark>) {
+        self.pending = marks;
+    }
+}
+
+fn to_file_mark(mark: &Mark, path: &str, table: &Table) -> FileMark {
+    FileMark {
+        name: mark.name,
+        path: path.to_string(),
+        position: table.offset_to_position(mark.offset.min(table.len())),
+    }
+}
+
+/// Returns every mark that belongs to a file, for saving in the session.
+///
+/// Marks in buffers without a file path cannot be restored later and are left out.
+///
+/// # Arguments
+///
+/// * `state` - The editor state.
+pub fn file_marks(state: &State) -> Vec<FileMark> {
+    let mut marks: Vec<FileMark> = state
+        .marks()
+        .iter()
+        .filter_map(|mark| {
+            let path = state.metadata(mark.buffer_id)?.file_path.as_deref()?;
+            let table = state.buffer(mark.buffer_id)?;
+            Some(to_file_mark(mark, path, table))
+        })
+        .collect();
+    marks.extend(state.marks().pending().iter().cloned());
+    marks.sort_by(|a, b| (a.name, &a.path).cmp(&(b.name, &b.path)));
+    marks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::editor::Command;
+
+    #[test]
+    fn local_marks_are_per_buffer_and_global_marks_are_unique() {
+        let mut registry = Registry::default();
+        let (a, b) = (ID::new(), ID::new());
+
+        assert!(registry.set('a', a, 1));
+        assert!(registry.set('a', b, 2));
+        assert_eq!(registry.get('a', a).unwrap().offset, 1);
+        assert_eq!(registry.get('a', b).unwrap().offset, 2);
+
+        registry.set('A', a, 3);
+        registry.set('A', b, 4);
+        assert_eq!(registry.get('A', a).unwrap().buffer_id, b);
+        assert_eq!(registry.iter().count(), 3);
+
+        assert!(!registry.set('1', a, 0));
+    }
+
+    #[test]
+    fn marks_follow_edits() {
+        let mut state = State::new();
+        let id = state.create_buffer("one two three".to_string());
+        state.marks_mut().set('t', id, 8);
+
+        state
+            .execute_command(Command::InsertText {
+                buffer_id: id,
+                offset: 0,
+                text: "zero ".to_string(),
+            })
+            .unwrap();
+        assert_eq!(state.marks().get('t', id).unwrap().offset, 13);
+
+        state
+            .execute_command(Command::DeleteText {
+                buffer_id: id,
+                start: 5,
+                length: 4,
+            })
+            .unwrap();
+        assert_eq!(state.marks().get('t', id).unwrap().offset, 9);
+    }
+
+    #[test]
+    fn closed_file_marks_are_restored_when_reopened() {
+        let mut state = State::new();
+        let id = state.create_buffer("first\nsecond".to_string());
+        state.set_file_path(id, "/a.txt".to_string());
+        state.marks_mut().set('m', id, 8);
+
+        state.close_buffer(id);
+        assert_eq!(state.marks().iter().count(), 0);
+        let saved = file_marks(&state);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].position, Position { line: 1, column: 2 });
+
+        let reopened = state.create_buffer("first\nsecond".to_string());
+        let table = state.buffers()[&reopened].clone();
+        state.marks_mut().open_file(reopened, "/a.txt", &table);
+        assert_eq!(state.marks().get('m', reopened).unwrap().offset, 8);
+        assert!(state.marks().pending().is_empty());
+    }
+
+    #[test]
+    fn file_marks_round_trip_after_wide_characters() {
+        let mut state = State::new();
+        let id = state.create_buffer("ééé\nx".to_string());
+        state.set_file_path(id, "/wide.txt".to_string());
+        state.marks_mut().set('m', id, 4);
+        state.close_buffer(id);
+        assert_eq!(
+            file_marks(&state)[0].position,
+            Position { line: 0, column: 4 }
+        );
+
+        let reopened = state.create_buffer("ééé\nx".to_string());
+        let table = state.buffers()[&reopened].clone();
+        state.marks_mut().open_file(reopened, "/wide.txt", &table);
+        assert_eq!(state.marks().get('m', reopened).unwrap().offset, 4);
+
+        // A file that got shorter keeps the mark on its last line
+        state.set_file_path(reopened, "/wide.txt".to_string());
+        state.close_buffer(reopened);
+        let shorter = state.create_buffer("é".to_string());
+        let table = state.buffers()[&shorter].clone();
+        state.marks_mut().open_file(shorter, "/wide.txt", &table);
+        assert_eq!(state.marks().get('m', shorter).unwrap().offset, 2);
+    }
+
+    #[test]
+    fn file_marks_skip_unsaved_buffers() {
+        let mut state = State::new();
+        let id = state.create_buffer("scratch".to_string());
+        state.marks_mut().set('a', id, 0);
+        assert!(file_marks(&state).is_empty());
+    }
+}
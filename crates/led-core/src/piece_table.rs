@@ -34,8 +34,8 @@ pub mod piece {
         pub line_breaks: u32,
     }
 
-    use crate::led::types::source::ID;
-    use crate::led::util::count_line_breaks;
+    use crate::types::source::ID;
+    use crate::util::count_line_breaks;
     use std::cmp::PartialEq;
     use std::collections::BTreeMap;
 
@@ -69,11 +69,10 @@ pub mod piece {
     /// Implements equality for the ID type.
     impl PartialEq for ID {
         fn eq(&self, other: &Self) -> bool {
-            match (self, other) {
-                (ID::Original, ID::Original) => true,
-                (ID::Add, ID::Add) => true,
-                _ => false,
-            }
+            matches!(
+                (self, other),
+                (ID::Original, ID::Original) | (ID::Add, ID::Add)
+            )
         }
     }
 
@@ -112,11 +111,70 @@ pub mod piece {
             self.total_length
         }
 
+        /// Returns `true` if the document contains no text.
+        pub fn is_empty(&self) -> bool {
+            self.total_length == 0
+        }
+
         /// Returns the total number of lines in the document.
         pub fn lines(&self) -> usize {
             self.total_lines
         }
 
+        /// Returns how many pieces the document is made of, which grows with every edit.
+        pub fn piece_count(&self) -> usize {
+            self.pieces.len()
+        }
+
+        /// Rebuilds the table as a single piece holding the current text, dropping text that
+        /// was inserted and deleted since. The text itself is unchanged.
+        pub fn compact(&mut self) {
+            *self = Self::new(self.get_text(0, self.total_length));
+        }
+
+        /// Returns the bytes of the document from `offset` to the end, without copying the text.
+        ///
+        /// # Arguments
+        ///
+        /// * `offset` - The byte offset to start at.
+        pub fn bytes_from(&self, offset: usize) -> impl Iterator<Item = u8> + '_ {
+            let mut piece_start = 0;
+            self.pieces.iter().flat_map(move |piece| {
+                let skip = offset.saturating_sub(piece_start).min(piece.length);
+                piece_start += piece.length;
+                let source_text = match piece.source {
+                    ID::Original => &self.original,
+                    ID::Add => &self.add_buffer,
+                };
+                source_text.as_bytes()[piece.start + skip..piece.start + piece.length]
+                    .iter()
+                    .copied()
+            })
+        }
+
+        /// Returns the bytes of the document before `offset`, nearest first, without copying
+        /// the text.
+        ///
+        /// # Arguments
+        ///
+        /// * `offset` - The byte offset to start at; its own byte is not included.
+        pub fn bytes_before(&self, offset: usize) -> impl Iterator<Item = u8> + '_ {
+            let mut piece_end = self.total_length;
+            self.pieces.iter().rev().flat_map(move |piece| {
+                let piece_start = piece_end - piece.length;
+                piece_end = piece_start;
+                let take = offset.saturating_sub(piece_start).min(piece.length);
+                let source_text = match piece.source {
+                    ID::Original => &self.original,
+                    ID::Add => &self.add_buffer,
+                };
+                source_text.as_bytes()[piece.start..piece.start + take]
+                    .iter()
+                    .rev()
+                    .copied()
+            })
+        }
+
         /// Inserts text at the specified offset.
         ///
         /// # Arguments
@@ -138,7 +196,7 @@ pub mod piece {
                 source: ID::Add,
                 start: add_start,
                 length: text.len(),
-                line_breaks: count_line_breaks(&text.to_string()),
+                line_breaks: count_line_breaks(text),
             };
             if let Some(split_result) = self.split_piece_at(piece_idx, offset) {
                 self.pieces.insert(split_result.insert_idx, new_piece);
@@ -229,83 +287,144 @@ pub mod piece {
         ///
         /// # Arguments
         ///
-        /// * `offset` - The character offset in the document.
+        /// * `offset` - The byte offset in the document.
         ///
         /// # Returns
         ///
-        /// The corresponding `Position` (line and column).
+        /// The corresponding `Position`, whose column is a byte offset into its line as
+        /// everywhere else. An offset past the end of the document gives the start.
         pub fn offset_to_position(&self, offset: usize) -> super::Position {
             if offset > self.total_length {
                 return super::Position { line: 0, column: 0 };
             }
-            let mut current_line = 0;
-            let mut current_offset = 0;
-            let mut last_line_start = 0;
-
+            let (mut line, mut line_start, mut piece_start) = (0, 0, 0);
             for piece in &self.pieces {
+                if piece_start >= offset {
+                    break;
+                }
+                let within = (offset - piece_start).min(piece.length);
+                if piece.line_breaks > 0 {
+                    let src_txt = match piece.source {
+                        ID::Original => &self.original,
+                        ID::Add => &self.add_buffer,
+                    };
+                    let bytes = &src_txt.as_bytes()[piece.start..piece.start + within];
+                    // Whole pieces before the offset count their line breaks without a scan
+                    line += if within == piece.length {
+                        piece.line_breaks as usize
+                    } else {
+                        bytes.iter().filter(|&&b| b == b'\n').count()
+                    };
+                    if let Some(i) = bytes.iter().rposition(|&b| b == b'\n') {
+                        line_start = piece_start + i + 1;
+                    }
+                }
+                piece_start += piece.length;
+            }
+            super::Position {
+                line,
+                column: offset - line_start,
+            }
+        }
+
+        /// Returns the position at the end of the document: the last line, and the byte column
+        /// after its last character.
+        pub fn end_position(&self) -> super::Position {
+            let line = self.lines() - 1;
+            let mut piece_start = self.len();
+            for piece in self.pieces.iter().rev() {
+                piece_start -= piece.length;
+                if piece.line_breaks == 0 {
+                    continue;
+                }
                 let src_txt = match piece.source {
                     ID::Original => &self.original,
                     ID::Add => &self.add_buffer,
                 };
                 let piece_txt = &src_txt[piece.start..piece.start + piece.length];
-                let mut line_start = current_offset;
-                for (i, ch) in piece_txt.char_indices() {
-                    if current_offset + i == offset {
-                        return super::Position {
-                            line: current_line,
-                            column: offset - line_start,
-                        };
-                    }
-                    if ch == '\n' {
-                        current_line += 1;
-                        line_start = current_offset + i + 1;
-                        last_line_start = line_start;
-                    }
-                }
-                current_offset += piece.length;
+                let line_start = piece_start + piece_txt.rfind('\n').map_or(0, |i| i + 1);
+                return super::Position {
+                    line,
+                    column: self.len() - line_start,
+                };
             }
-            // If offset is at the end of the document, return last line and column
             super::Position {
-                line: current_line,
-                column: offset - last_line_start,
+                line,
+                column: self.len(),
             }
         }
 
         /// Converts a line and column position to an offset.
         ///
+        /// A column past the end of its line gives the end of the line, and one inside a
+        /// character gives the start of that character. A line past the end of the document
+        /// gives the end of the document.
+        ///
         /// # Arguments
         ///
-        /// * `pos` - The position (line and column).
+        /// * `pos` - The position (line and byte column).
         ///
         /// # Returns
         ///
-        /// The corresponding character offset.
+        /// The corresponding byte offset.
         pub fn position_to_offset(&self, pos: super::Position) -> usize {
-            let mut current_line = 0;
-            let mut current_column = 0;
-            let mut offset = 0;
+            let Some(line_start) = self.line_start(pos.line) else {
+                return self.total_length;
+            };
+            let mut offset = line_start;
+            let mut bytes = self.bytes_from(line_start);
+            for column in 0..=pos.column {
+                match bytes.next() {
+                    None | Some(b'\n') => return line_start + column,
+                    // UTF-8 continuation bytes are 0b10xx_xxxx; any other byte starts a character
+                    Some(byte) if byte & 0xC0 != 0x80 => offset = line_start + column,
+                    Some(_) => {}
+                }
+            }
+            offset
+        }
 
+        /// Returns the nearest position that is in the document: a line past the end becomes
+        /// the last line, and a column past the end of its line becomes the end of the line.
+        ///
+        /// # Arguments
+        ///
+        /// * `pos` - The position, which may come from an older version of the text.
+        pub fn clamp_position(&self, pos: super::Position) -> super::Position {
+            let line = pos.line.min(self.total_lines.saturating_sub(1));
+            let offset = self.position_to_offset(super::Position { line, ..pos });
+            self.offset_to_position(offset)
+        }
+
+        /// Returns the offset a line starts at, or `None` if the document has no such line.
+        fn line_start(&self, line: usize) -> Option<usize> {
+            if line == 0 {
+                return Some(0);
+            }
+            let (mut current_line, mut piece_start) = (0, 0);
             for piece in &self.pieces {
+                let breaks = piece.line_breaks as usize;
+                if current_line + breaks < line {
+                    current_line += breaks;
+                    piece_start += piece.length;
+                    continue;
+                }
                 let src_txt = match piece.source {
                     ID::Original => &self.original,
                     ID::Add => &self.add_buffer,
                 };
-                let piece_txt = &src_txt[piece.start..piece.start + piece.length];
-                for ch in piece_txt.chars() {
-                    if current_line == pos.line && current_column == pos.column {
-                        return offset;
-                    }
-                    if ch == '\n' {
+                let bytes = &src_txt.as_bytes()[piece.start..piece.start + piece.length];
+                for (i, &byte) in bytes.iter().enumerate() {
+                    if byte == b'\n' {
                         current_line += 1;
-                        current_column = 0;
-                    } else {
-                        current_column += 1;
+                        if current_line == line {
+                            return Some(piece_start + i + 1);
+                        }
                     }
-                    offset += ch.len_utf8();
                 }
+                piece_start += piece.length;
             }
-            // If position is past the end, return total length
-            self.total_length
+            None
         }
 
         /// Finds the index of the piece containing the given offset.
@@ -390,14 +509,14 @@ pub mod piece {
                 source: piece.source,
                 start: piece.start,
                 length: offset_in_piece,
-                line_breaks: count_line_breaks(&left_text.to_string()),
+                line_breaks: count_line_breaks(left_text),
             };
 
             let right_piece = Piece {
                 source: piece.source,
                 start: piece.start + offset_in_piece,
                 length: piece.length - offset_in_piece,
-                line_breaks: count_line_breaks(&right_text.to_string()),
+                line_breaks: count_line_breaks(right_text),
             };
 
             self.pieces[piece_idx] = left_piece;
@@ -450,7 +569,7 @@ pub mod piece {
                 };
                 &source_text[piece.start + offset_in_piece_start..piece.start + offset_in_piece_end]
             };
-            let deleted_line_breaks = count_line_breaks(&deleted_text.to_string());
+            let deleted_line_breaks = count_line_breaks(deleted_text);
 
             if deleted_length == piece.length {
                 self.pieces.remove(piece_idx);
@@ -475,8 +594,7 @@ pub mod piece {
                         &match piece.source {
                             ID::Original => &self.original,
                             ID::Add => &self.add_buffer,
-                        }[piece.start + offset_in_piece_end..piece.start + piece.length]
-                            .to_string(),
+                        }[piece.start + offset_in_piece_end..piece.start + piece.length],
                     ),
                 };
                 piece.length = offset_in_piece_start;
@@ -484,8 +602,7 @@ pub mod piece {
                     &match piece.source {
                         ID::Original => &self.original,
                         ID::Add => &self.add_buffer,
-                    }[piece.start..piece.start + offset_in_piece_start]
-                        .to_string(),
+                    }[piece.start..piece.start + offset_in_piece_start],
                 );
                 self.pieces.insert(piece_idx + 1, right_piece);
             }
@@ -529,8 +646,7 @@ pub mod piece {
                     ID::Original => &self.original,
                     ID::Add => &self.add_buffer,
                 }[self.pieces[start_piece_idx].start
-                    ..self.pieces[start_piece_idx].start + offset_in_first_piece]
-                    .to_string(),
+                    ..self.pieces[start_piece_idx].start + offset_in_first_piece],
             );
 
             // Mutate last piece: keep only the right part
@@ -541,8 +657,7 @@ pub mod piece {
                     ID::Original => &self.original,
                     ID::Add => &self.add_buffer,
                 }[self.pieces[end_piece_idx].start
-                    ..self.pieces[end_piece_idx].start + self.pieces[end_piece_idx].length]
-                    .to_string(),
+                    ..self.pieces[end_piece_idx].start + self.pieces[end_piece_idx].length],
             );
 
             // Remove all pieces between first and last (exclusive)
@@ -558,14 +673,14 @@ pub mod piece {
             if self
                 .pieces
                 .get(end_piece_idx)
-                .map_or(false, |p| p.length == 0)
+                .is_some_and(|p| p.length == 0)
             {
                 to_remove.push(end_piece_idx);
             }
             if self
                 .pieces
                 .get(start_piece_idx)
-                .map_or(false, |p| p.length == 0)
+                .is_some_and(|p| p.length == 0)
                 && start_piece_idx != end_piece_idx
             {
                 to_remove.push(start_piece_idx);
@@ -670,6 +785,42 @@ mod split {
 mod tests {
     use super::piece::Table;
 
+    #[test]
+    fn bytes_from_walks_pieces_in_order() {
+        let mut table = Table::new("Hello World".to_string());
+        table.insert(5, ",").unwrap();
+        let all: Vec<u8> = table.bytes_from(0).collect();
+        assert_eq!(all, b"Hello, World");
+        let tail: Vec<u8> = table.bytes_from(4).collect();
+        assert_eq!(tail, b"o, World");
+        assert_eq!(table.bytes_from(table.len()).count(), 0);
+    }
+
+    #[test]
+    fn bytes_before_walks_pieces_backwards() {
+        let mut table = Table::new("Hello World".to_string());
+        table.insert(5, ",").unwrap();
+        let head: Vec<u8> = table.bytes_before(7).collect();
+        assert_eq!(head, b" ,olleH");
+        assert_eq!(table.bytes_before(0).count(), 0);
+        assert_eq!(table.bytes_before(table.len()).count(), table.len());
+    }
+
+    #[test]
+    fn end_position_is_after_the_last_character() {
+        let mut table = Table::new("ab\ncd".to_string());
+        assert_eq!(table.end_position(), table.offset_to_position(table.len()));
+        table.insert(5, "é\nxyz").unwrap();
+        table.insert(0, "\n").unwrap();
+        let end = table.end_position();
+        assert_eq!((end.line, end.column), (3, 3));
+        table.delete(3, table.len() - 3).unwrap();
+        let end = table.end_position();
+        assert_eq!((end.line, end.column), (1, 2));
+        let end = Table::new(String::new()).end_position();
+        assert_eq!((end.line, end.column), (0, 0));
+    }
+
     #[test]
     fn new_table_has_correct_length_and_lines() {
         let text = String::from("Hello\nWorld\n");
@@ -753,6 +904,20 @@ mod tests {
         assert_eq!(offset, 7);
     }
 
+    #[test]
+    fn offset_to_position_counts_columns_across_pieces() {
+        let mut table = Table::new("ab\ncd".to_string());
+        table.insert(4, "x").unwrap();
+        table.insert(6, "y").unwrap();
+        // "ab\ncxdy" is now split over four pieces
+        let pos = table.offset_to_position(6);
+        assert_eq!(pos.line, 1);
+        assert_eq!(pos.column, 3);
+        let pos = table.offset_to_position(7);
+        assert_eq!(pos.line, 1);
+        assert_eq!(pos.column, 4);
+    }
+
     #[test]
     fn offset_to_position_at_end() {
         let table = Table::new("abc\ndef".to_string());
@@ -761,6 +926,32 @@ mod tests {
         assert_eq!(pos.column, 3);
     }
 
+    #[test]
+    fn columns_are_bytes_clamped_to_their_line() {
+        let mut table = Table::new("é\nabc\n".to_string());
+        table.insert(3, "x").unwrap();
+        let at = |line, column| super::super::types::Position { line, column };
+        // "é\nxabc\n": the caret after "é" is at byte column 2
+        assert_eq!(table.offset_to_position(2), at(0, 2));
+        assert_eq!(table.position_to_offset(at(0, 2)), 2);
+        // Inside "é" goes to its start; past the line's end goes to the end of the line
+        assert_eq!(table.position_to_offset(at(0, 1)), 0);
+        assert_eq!(table.position_to_offset(at(0, 9)), 2);
+        assert_eq!(table.position_to_offset(at(1, 9)), 7);
+        assert_eq!(table.position_to_offset(at(2, 0)), 8);
+        assert_eq!(table.offset_to_position(5), at(1, 2));
+    }
+
+    #[test]
+    fn clamp_position_keeps_the_line_when_it_can() {
+        let table = Table::new("long line\nab\nshort".to_string());
+        let at = |line, column| super::super::types::Position { line, column };
+        assert_eq!(table.clamp_position(at(1, 1)), at(1, 1));
+        assert_eq!(table.clamp_position(at(1, 9)), at(1, 2));
+        assert_eq!(table.clamp_position(at(7, 3)), at(2, 3));
+        assert_eq!(table.clamp_position(at(7, 9)), at(2, 5));
+    }
+
     #[test]
     fn position_to_offset_past_end_returns_total_length() {
         let table = Table::new("abc\ndef".to_string());
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result as AnyResult, bail};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes a file so that a crash or full disk partway through leaves either the old content or
+/// the new, never a mix.
+///
+/// The content goes to a temporary file next to the target, which is flushed to disk and then
+/// renamed over the target. An existing file keeps its permissions, and a symbolic link keeps
+/// pointing at the same file, which is the one replaced. On Unix the file's owner is not kept;
+/// it becomes the user saving it. Hard links to the old file keep the old content.
+///
+/// # Arguments
+///
+/// * `path` - The file to write, which need not exist yet.
+/// * `content` - The bytes to write.
+///
+/// # Errors
+///
+/// Returns an error if the file is read-only, or if the temporary file can't be written or
+/// renamed. The target is untouched in that case and the temporary file is removed.
+pub fn write_atomically(path: &Path, content: impl AsRef<[u8]>) -> AnyResult<()> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let permissions = match fs::metadata(&target) {
+        Ok(metadata) if metadata.permissions().readonly() => {
+            bail!("{} is read-only", target.display())
+        }
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(_) => None,
+    };
+    let temp = temp_path(&target);
+    let written = write_temp(&temp, content.as_ref(), permissions)
+        .and_then(|()| fs::rename(&temp, &target).context("failed to replace the file"));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written?;
+    // Make the rename itself durable; not every file system can sync a directory
+    #[cfg(unix)]
+    if let Some(dir) = target.parent().and_then(|dir| fs::File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Returns a path for the temporary file in the target's directory, so the rename stays on one
+/// file system. It is hidden on Unix and unlikely to clash with anything.
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+    target.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()))
+}
+
+/// Writes the content to a new file and flushes it to disk.
+fn write_temp(temp: &Path, content: &[u8], permissions: Option<fs::Permissions>) -> AnyResult<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp)
+        .context("failed to create a temporary file")?;
+    file.write_all(content)
+        .context("failed to write the temporary file")?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)
+            .context("failed to copy the file's permissions")?;
+    }
+    file.sync_all()
+        .context("failed to flush the file to disk")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-save-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Names of the files in a directory, sorted.
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn creates_and_replaces_files_without_leaving_temporary_ones() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        write_atomically(&path, "one").unwrap();
+        write_atomically(&path, "two").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+        assert_eq!(entries(&dir), ["a.txt"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_only_files_are_left_alone() {
+        let dir = temp_dir();
+        let path = dir.join("locked.txt");
+        fs::write(&path, "old").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions.clone()).unwrap();
+
+        assert!(write_atomically(&path, "new").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(entries(&dir), ["locked.txt"]);
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_and_symbolic_links_are_kept() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let dir = temp_dir();
+        let path = dir.join("run.sh");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o751)).unwrap();
+        let link = dir.join("link.sh");
+        symlink(&path, &link).unwrap();
+
+        write_atomically(&link, "new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o751);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
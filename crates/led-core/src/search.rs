@@ -0,0 +1,644 @@
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Why a query is not a valid regular expression.
+pub use regex::Error;
+
+/// Finds the next occurrence of `needle` at or after `from`, wrapping around to the start.
+///
+/// # Arguments
+///
+/// * `text` - The text to search.
+/// * `needle` - The text to look for.
+/// * `from` - The byte offset to start searching at.
+///
+/// # Returns
+///
+/// The byte range of the match, or `None` if `needle` is empty or does not occur.
+pub fn find_next(text: &str, needle: &str, from: usize) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    let from = from.min(text.len());
+    let start = text
+        .get(from..)
+        .and_then(|tail| tail.find(needle))
+        .map(|i| from + i)
+        .or_else(|| text.find(needle))?;
+    Some(start..start + needle.len())
+}
+
+/// Finds the last occurrence of `needle` ending at or before `before`, wrapping around to the end.
+///
+/// # Arguments
+///
+/// * `text` - The text to search.
+/// * `needle` - The text to look for.
+/// * `before` - The byte offset the match must end at or before.
+///
+/// # Returns
+///
+/// The byte range of the match, or `None` if `needle` is empty or does not occur.
+pub fn find_prev(text: &str, needle: &str, before: usize) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    let before = before.min(text.len());
+    let start = text
+        .get(..before)
+        .and_then(|head| head.rfind(needle))
+        .or_else(|| text.rfind(needle))?;
+    Some(start..start + needle.len())
+}
+
+/// Returns a regular expression that matches `word` only as a whole word, not inside a
+/// longer one.
+pub fn whole_word(word: &str) -> String {
+    format!(r"\b{}\b", regex::escape(word))
+}
+
+/// Starts building a query's regular expression, with `^` and `$` matching at every line.
+fn builder(source: &str) -> RegexBuilder {
+    let mut builder = RegexBuilder::new(source);
+    builder.multi_line(true).crlf(true);
+    builder
+}
+
+/// Converts the byte ranges of matches into positions in the text, in one pass over it.
+///
+/// Columns are byte offsets within their line, as
+/// [`Table::offset_to_position`](crate::piece_table::piece::Table::offset_to_position) counts
+/// them. A match may span lines.
+///
+/// # Arguments
+///
+/// * `text` - The text that was searched.
+/// * `matches` - The byte ranges, in any order; offsets past the end of the text are taken as
+///   its end.
+pub fn positions(text: &str, matches: &[Range<usize>]) -> Vec<super::types::Range> {
+    let mut offsets: Vec<usize> = matches
+        .iter()
+        .flat_map(|found| [found.start, found.end])
+        .map(|offset| offset.min(text.len()))
+        .collect();
+    offsets.sort_unstable();
+    offsets.dedup();
+
+    let bytes = text.as_bytes();
+    let (mut at, mut line, mut line_start) = (0, 0, 0);
+    let found: Vec<super::types::Position> = offsets
+        .iter()
+        .map(|&offset| {
+            for (i, byte) in bytes[at..offset].iter().enumerate() {
+                if *byte == b'\n' {
+                    line += 1;
+                    line_start = at + i + 1;
+                }
+            }
+            at = offset;
+            super::types::Position {
+                line,
+                column: offset - line_start,
+            }
+        })
+        .collect();
+    let position = |offset: usize| found[offsets.binary_search(&offset.min(text.len())).unwrap()];
+    matches
+        .iter()
+        .map(|range| super::types::Range {
+            start: position(range.start),
+            end: position(range.end),
+        })
+        .collect()
+}
+
+/// A query to find: literal text, or a regular expression whose replacements may refer to its
+/// capture groups.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    /// Whether the query is literal text, so replacements are inserted as they are.
+    literal: bool,
+    /// Whether matches ignore case and replacements take on the case of what they replace.
+    preserve_case: bool,
+}
+
+impl Pattern {
+    /// Compiles a query.
+    ///
+    /// Regular expressions may span lines: `\n` matches a line break, and `^` and `$` match
+    /// at the start and end of every line, before a `\r\n` as well as a `\n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text or regular expression to find.
+    /// * `regex` - Whether `query` is a regular expression rather than literal text.
+    /// * `preserve_case` - Whether to match regardless of case and adapt each replacement to
+    ///   the case of its match, see [`match_case`].
+    ///
+    /// # Returns
+    ///
+    /// The pattern, or `None` if the query is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `query` is not a valid regular expression.
+    pub fn new(
+        query: &str,
+        regex: bool,
+        preserve_case: bool,
+    ) -> Result<Option<Self>, regex::Error> {
+        if query.is_empty() {
+            return Ok(None);
+        }
+        let source = if regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        Ok(Some(Self {
+            regex: builder(&source).case_insensitive(preserve_case).build()?,
+            literal: !regex,
+            preserve_case,
+        }))
+    }
+
+    /// Compiles a query as the find bar searches for it. Regular expressions may span lines,
+    /// as with [`Pattern::new`].
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text or regular expression to find.
+    /// * `regex` - Whether `query` is a regular expression rather than literal text.
+    /// * `match_case` - Whether matches must have the query's case.
+    /// * `whole_word` - Whether matches must not start or end inside a word, see
+    ///   [`whole_word`].
+    ///
+    /// # Returns
+    ///
+    /// The pattern, or `None` if the query is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `query` is not a valid regular expression.
+    pub fn query(
+        query: &str,
+        regex: bool,
+        match_case: bool,
+        whole_word: bool,
+    ) -> Result<Option<Self>, regex::Error> {
+        if query.is_empty() {
+            return Ok(None);
+        }
+        let source = match (regex, whole_word) {
+            (true, true) => format!(r"\b(?:{})\b", query),
+            (true, false) => query.to_string(),
+            (false, true) => self::whole_word(query),
+            (false, false) => regex::escape(query),
+        };
+        Ok(Some(Self {
+            regex: builder(&source).case_insensitive(!match_case).build()?,
+            literal: !regex,
+            preserve_case: false,
+        }))
+    }
+
+    /// Finds every match within a range of the text, without overlaps.
+    ///
+    /// Patterns that can match nothing, such as `^`, match at most once at each offset and not
+    /// right after another match. The whole text is searched, so `$` and `\b` see the text
+    /// around the range rather than treating its ends as the text's; a match that runs past the
+    /// end of the range is left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search.
+    /// * `within` - The byte range matches must lie in.
+    pub fn find_all(&self, text: &str, within: Range<usize>) -> Vec<Range<usize>> {
+        let end = within.end.min(text.len());
+        let mut matches: Vec<Range<usize>> = Vec::new();
+        let mut at = within.start.min(end);
+        while let Some(found) = self.regex.find_at(text, at) {
+            let found = found.range();
+            if found.end > end {
+                break;
+            }
+            if !(found.is_empty() && matches.last().is_some_and(|last| last.end == found.end)) {
+                matches.push(found.clone());
+            }
+            at = if found.is_empty() {
+                match text[found.end..].chars().next() {
+                    Some(c) => found.end + c.len_utf8(),
+                    None => break,
+                }
+            } else {
+                found.end
+            };
+        }
+        matches
+    }
+
+    /// Finds the first match within a range of the text that starts at or after `from`,
+    /// wrapping around to the start of the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search.
+    /// * `from` - The byte offset to start searching at.
+    /// * `within` - The byte range matches must lie in.
+    pub fn find_next(&self, text: &str, from: usize, within: Range<usize>) -> Option<Range<usize>> {
+        let matches = self.find_all(text, within);
+        matches
+            .iter()
+            .find(|found| found.start >= from)
+            .or(matches.first())
+            .cloned()
+    }
+
+    /// Finds the last match within a range of the text that starts before `before`, wrapping
+    /// around to the end of the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search.
+    /// * `before` - The byte offset the match must start before.
+    /// * `within` - The byte range matches must lie in.
+    pub fn find_prev(
+        &self,
+        text: &str,
+        before: usize,
+        within: Range<usize>,
+    ) -> Option<Range<usize>> {
+        let matches = self.find_all(text, within);
+        matches
+            .iter()
+            .rev()
+            .find(|found| found.start < before)
+            .or(matches.last())
+            .cloned()
+    }
+
+    /// Returns the text a match is replaced with.
+    ///
+    /// For a regular expression, `$1` and `${name}` in `replacement` stand for the text of a
+    /// capture group and `$$` for a dollar sign; literal queries insert `replacement` as it is.
+    /// When the pattern preserves case, the result then takes on the case of the match.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text that was searched.
+    /// * `found` - The byte range of the match.
+    /// * `replacement` - The replacement, possibly referring to capture groups.
+    pub fn expand(&self, text: &str, found: Range<usize>, replacement: &str) -> String {
+        let mut expanded = String::new();
+        if self.literal {
+            expanded.push_str(replacement);
+        } else if let Some(captures) = self.regex.captures_at(text, found.start)
+            // Searched in the whole text, so anchors and alternations see what `find_all` saw
+            && captures.get_match().range() == found
+        {
+            captures.expand(replacement, &mut expanded);
+        }
+        if self.preserve_case {
+            match_case(&text[found], &expanded)
+        } else {
+            expanded
+        }
+    }
+
+    /// Replaces every match within a scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search.
+    /// * `replacement` - The text each match is replaced with, as for [`Pattern::expand`].
+    /// * `scope` - The byte range matches must lie in.
+    ///
+    /// # Returns
+    ///
+    /// The edit to make, or `None` if nothing matches.
+    pub fn replace_all(
+        &self,
+        text: &str,
+        replacement: &str,
+        scope: Range<usize>,
+    ) -> Option<Replacement> {
+        let matches = self.find_all(text, scope);
+        let range = matches.first()?.start..matches.last()?.end;
+        let mut replaced = String::new();
+        let mut edits = Vec::with_capacity(matches.len());
+        let mut from = range.start;
+        for found in matches {
+            let expanded = self.expand(text, found.clone(), replacement);
+            replaced.push_str(&text[from..found.start]);
+            replaced.push_str(&expanded);
+            from = found.end;
+            edits.push((found, expanded.len()));
+        }
+        Some(Replacement {
+            range,
+            text: replaced,
+            edits,
+        })
+    }
+}
+
+/// Adapts a replacement to the case of the text it replaces.
+///
+/// An all-uppercase match (of more than one letter) makes the replacement uppercase, an
+/// all-lowercase one makes it lowercase, and a capitalized one capitalizes its first letter.
+/// Matches in mixed case, or without letters, leave the replacement as it is.
+///
+/// # Arguments
+///
+/// * `matched` - The text being replaced.
+/// * `replacement` - The text replacing it.
+pub fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched
+        .chars()
+        .filter(|c| c.is_uppercase() || c.is_lowercase())
+        .collect();
+    let Some((first, rest)) = letters.split_first() else {
+        return replacement.to_string();
+    };
+    if !rest.is_empty() && letters.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if letters.iter().all(|c| c.is_lowercase()) {
+        replacement.to_lowercase()
+    } else if first.is_uppercase() && rest.iter().all(|c| c.is_lowercase()) {
+        let mut chars = replacement.chars();
+        match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// The edit that replaces every match within a scope, made as one change to the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// The byte range of the original text that is replaced, from the first match to the last.
+    pub range: Range<usize>,
+    /// The text that takes the range's place.
+    pub text: String,
+    /// Each match in the original text, with the length of the text that replaces it.
+    edits: Vec<(Range<usize>, usize)>,
+}
+
+impl Replacement {
+    /// Returns how many matches are replaced.
+    pub fn count(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Returns where an offset of the original text ends up after the replacement.
+    ///
+    /// Offsets inside a match move to the start of the text that replaces it.
+    pub fn map(&self, offset: usize) -> usize {
+        let mut mapped = offset;
+        for (found, length) in &self.edits {
+            if offset >= found.end {
+                mapped = mapped + length - found.len();
+            } else if offset > found.start {
+                return mapped - (offset - found.start);
+            }
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_next_searches_forward_and_wraps() {
+        let text = "foo bar foo";
+        assert_eq!(find_next(text, "foo", 0), Some(0..3));
+        assert_eq!(find_next(text, "foo", 1), Some(8..11));
+        assert_eq!(find_next(text, "foo", 9), Some(0..3));
+        assert_eq!(find_next(text, "baz", 0), None);
+        assert_eq!(find_next(text, "", 0), None);
+    }
+
+    #[test]
+    fn find_prev_searches_backward_and_wraps() {
+        let text = "foo bar foo";
+        assert_eq!(find_prev(text, "foo", 11), Some(8..11));
+        assert_eq!(find_prev(text, "foo", 8), Some(0..3));
+        assert_eq!(find_prev(text, "foo", 2), Some(8..11));
+    }
+
+    fn pattern(query: &str, regex: bool) -> Pattern {
+        Pattern::new(query, regex, false).unwrap().unwrap()
+    }
+
+    #[test]
+    fn whole_words_step_both_ways_and_wrap() {
+        let text = "foo food foo.bar";
+        let pattern = pattern(&whole_word("foo"), true);
+        assert_eq!(pattern.find_all(text, 0..text.len()), [0..3, 9..12]);
+        assert_eq!(pattern.find_next(text, 3, 0..text.len()), Some(9..12));
+        assert_eq!(pattern.find_next(text, 12, 0..text.len()), Some(0..3));
+        assert_eq!(pattern.find_prev(text, 9, 0..text.len()), Some(0..3));
+        assert_eq!(pattern.find_prev(text, 0, 0..text.len()), Some(9..12));
+        assert_eq!(whole_word("a.b"), r"\ba\.b\b");
+    }
+
+    #[test]
+    fn regular_expressions_span_lines() {
+        let text = "fn a() {}\r\n  fn b() {\n}\nfnord";
+        let all = |query: &str| pattern(query, true).find_all(text, 0..text.len());
+        assert_eq!(all(r"^\s*fn \w"), [0..4, 11..17]);
+        assert_eq!(all(r"\{$"), [Range { start: 20, end: 21 }]);
+        assert_eq!(all(r"\{\n\}"), [Range { start: 20, end: 23 }]);
+
+        let query = |query, whole_word| {
+            Pattern::query(query, true, true, whole_word)
+                .unwrap()
+                .unwrap()
+                .find_all(text, 0..text.len())
+        };
+        assert_eq!(query("fn|fnord", true), [0..2, 13..15, 24..29]);
+        assert!(Pattern::query("(", true, true, false).is_err());
+    }
+
+    #[test]
+    fn searching_within_a_range_sees_the_text_around_it() {
+        let text = "foobar\nfoo";
+        // The range ends inside "foobar", which is neither a line end nor a word boundary
+        assert_eq!(
+            pattern("foo$", true).find_all(text, 0..3),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(
+            pattern(r"foo\b", true).find_all(text, 0..3),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(
+            pattern("foo$", true).find_all(text, 0..text.len()),
+            [Range { start: 7, end: 10 }]
+        );
+        // Nor is its start a line start
+        assert_eq!(
+            pattern("^bar", true).find_all(text, 3..6),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(
+            pattern("bar", true).find_all(text, 3..6),
+            [Range { start: 3, end: 6 }]
+        );
+        // A match running past the end of the range is left out
+        assert_eq!(
+            pattern("foob", true).find_all(text, 0..3),
+            Vec::<Range<usize>>::new()
+        );
+    }
+
+    #[test]
+    fn match_positions_are_found_in_one_pass() {
+        let text = "ab\ncdé\nf";
+        let at = |line, column| crate::types::Position { line, column };
+        let found = positions(text, &[7..10, 1..4, 10..99]);
+        let found: Vec<_> = found.iter().map(|range| (range.start, range.end)).collect();
+        assert_eq!(
+            found,
+            [
+                (at(1, 4), at(2, 1)),
+                (at(0, 1), at(1, 1)),
+                (at(2, 1), at(2, 1))
+            ]
+        );
+    }
+
+    #[test]
+    fn literal_queries_can_match_case_and_whole_words() {
+        let text = "Find find.x findings (find)";
+        let found = |match_case, whole_word| {
+            Pattern::query("find", false, match_case, whole_word)
+                .unwrap()
+                .unwrap()
+                .find_all(text, 0..text.len())
+        };
+        assert_eq!(found(false, false), [0..4, 5..9, 12..16, 22..26]);
+        assert_eq!(found(true, false), [5..9, 12..16, 22..26]);
+        assert_eq!(found(false, true), [0..4, 5..9, 22..26]);
+        assert_eq!(found(true, true), [5..9, 22..26]);
+        // The query is literal text, not a regular expression
+        let dot = Pattern::query("d.", false, true, false).unwrap().unwrap();
+        assert_eq!(
+            dot.find_all(text, 0..text.len()),
+            [Range { start: 8, end: 10 }]
+        );
+        assert!(Pattern::query("", false, true, true).unwrap().is_none());
+    }
+
+    #[test]
+    fn replace_all_makes_one_edit_within_the_scope() {
+        let text = "foo bar foo baz foo";
+        let foo = pattern("foo", false);
+        let replacement = foo.replace_all(text, "x", 0..text.len()).unwrap();
+        assert_eq!(replacement.count(), 3);
+        assert_eq!(replacement.range, 0..19);
+        assert_eq!(replacement.text, "x bar x baz x");
+
+        // Only matches wholly inside the scope are replaced
+        let replacement = foo.replace_all(text, "quux", 2..15).unwrap();
+        assert_eq!(replacement.count(), 1);
+        assert_eq!(replacement.range, 8..11);
+        assert_eq!(replacement.text, "quux");
+
+        assert_eq!(
+            pattern("nope", false).replace_all(text, "x", 0..text.len()),
+            None
+        );
+        assert!(Pattern::new("", false, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn replacement_maps_offsets_into_the_new_text() {
+        let text = "foo bar foo";
+        let replacement = pattern("foo", false)
+            .replace_all(text, "x", 0..text.len())
+            .unwrap();
+        assert_eq!(replacement.map(0), 0);
+        assert_eq!(replacement.map(2), 0);
+        assert_eq!(replacement.map(3), 1);
+        assert_eq!(replacement.map(5), 3);
+        assert_eq!(replacement.map(9), 6);
+        assert_eq!(replacement.map(11), 7);
+    }
+
+    #[test]
+    fn regex_replacements_expand_capture_groups() {
+        let text = "let a = 1; let bb = 22;";
+        let assignment = pattern(r"let (\w+) = (?<value>\d+)", true);
+        let found = assignment.find_next(text, 5, 0..text.len()).unwrap();
+        assert_eq!(&text[found.clone()], "let bb = 22");
+        assert_eq!(assignment.expand(text, found, "${value} -> $1"), "22 -> bb");
+
+        // The groups are those of the match found in the whole text, not in the text up to it
+        let anchored = pattern(r"(ab)$|(a)b", true);
+        assert_eq!(anchored.find_all("abc", 0..3), vec![0..2]);
+        assert_eq!(anchored.expand("abc", 0..2, "<$1|$2>"), "<|a>");
+
+        let replacement = assignment
+            .replace_all(text, "const $1: i32 = ${value}", 0..text.len())
+            .unwrap();
+        assert_eq!(replacement.text, "const a: i32 = 1; const bb: i32 = 22");
+
+        // Literal queries don't treat `$` or regex syntax specially
+        let literal = pattern("a.b", false);
+        assert_eq!(literal.find_all("axb a.b", 0..7), vec![4..7]);
+        assert_eq!(literal.expand("a.b", 0..3, "$1"), "$1");
+
+        assert!(Pattern::new("(unclosed", true, false).is_err());
+    }
+
+    #[test]
+    fn replacements_can_preserve_case() {
+        assert_eq!(match_case("FOO", "bar"), "BAR");
+        assert_eq!(match_case("Foo", "bar baz"), "Bar baz");
+        assert_eq!(match_case("foo", "BarBaz"), "barbaz");
+        assert_eq!(match_case("fooBar", "baz"), "baz");
+        assert_eq!(match_case("A", "bee"), "Bee");
+        assert_eq!(match_case("42", "Bee"), "Bee");
+
+        let text = "Color color COLOR";
+        let color = Pattern::new("color", false, true).unwrap().unwrap();
+        let replacement = color.replace_all(text, "hue", 0..text.len()).unwrap();
+        assert_eq!(replacement.text, "Hue hue HUE");
+
+        let word = Pattern::new(r"(\w+)or\b", true, true).unwrap().unwrap();
+        assert_eq!(word.expand(text, 0..5, "${1}our"), "Colour");
+    }
+
+    #[test]
+    fn empty_matches_advance_and_wrap() {
+        let text = "one\ntwo\n";
+        let line_start = pattern("(?m)^", true);
+        assert_eq!(
+            line_start.find_all(text, 0..text.len()),
+            vec![0..0, 4..4, 8..8]
+        );
+        let replacement = line_start.replace_all(text, "> ", 0..text.len()).unwrap();
+        assert_eq!(replacement.text, "> one\n> two\n> ");
+
+        // An empty match right after another match is skipped
+        assert_eq!(pattern("a*", true).find_all("baa", 0..3), vec![0..0, 1..3]);
+
+        // Searching from past the last match wraps to the start of the scope
+        let letter = pattern("o", false);
+        assert_eq!(letter.find_next(text, 6, 0..text.len()), Some(6..7));
+        assert_eq!(letter.find_next(text, 7, 0..text.len()), Some(0..1));
+        assert_eq!(letter.find_next(text, 7, 3..text.len()), Some(6..7));
+    }
+
+    #[test]
+    fn offsets_inside_multibyte_characters_fall_back_to_wrapping() {
+        let text = "é x é";
+        assert_eq!(find_next(text, "é", 1), Some(0..2));
+    }
+}
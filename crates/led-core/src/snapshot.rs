@@ -0,0 +1,106 @@
+use super::buffer::meta;
+use super::types::Position;
+use super::types::buffer::ID;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A buffer as it was at one point in time: its text, metadata and caret, immutable and cheap
+/// to share with other threads.
+///
+/// Snapshots let background work such as indexing, linting or searching read buffers while
+/// the editor keeps changing them, without holding a lock on the editor state for as long as
+/// the work takes. See [`crate::buffer::editor::State::snapshot`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub buffer_id: ID,
+    /// The content digest of the text; equal texts have equal digests.
+    pub digest: u64,
+    pub meta: meta::Data,
+    /// Where the caret was.
+    pub position: Position,
+    text: Arc<str>,
+}
+
+impl Snapshot {
+    /// Returns the buffer's text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns a handle to the text that can be kept without copying it.
+    pub fn shared_text(&self) -> Arc<str> {
+        Arc::clone(&self.text)
+    }
+}
+
+/// The text of the last snapshot of each buffer, reused while the buffer doesn't change so
+/// that taking snapshots of an idle buffer doesn't copy its text again.
+///
+/// The cache has its own lock, so snapshots can be taken through a shared reference to the
+/// state. The lock is only held to look up or store a text.
+#[derive(Debug, Default)]
+pub(crate) struct Cache {
+    texts: Mutex<HashMap<ID, (u64, Arc<str>)>>,
+}
+
+impl Cache {
+    /// Returns the text cached for a buffer at a digest, or builds and caches it.
+    pub(crate) fn text(
+        &self,
+        buffer_id: ID,
+        digest: u64,
+        build: impl FnOnce() -> String,
+    ) -> Arc<str> {
+        let mut texts = self.texts.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((cached, text)) = texts.get(&buffer_id)
+            && *cached == digest
+        {
+            return Arc::clone(text);
+        }
+        let text: Arc<str> = build().into();
+        texts.insert(buffer_id, (digest, Arc::clone(&text)));
+        text
+    }
+
+    /// Returns how many bytes of text are cached for each buffer.
+    pub(crate) fn sizes(&self) -> Vec<(ID, usize)> {
+        self.texts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(buffer_id, (_, text))| (*buffer_id, text.len()))
+            .collect()
+    }
+
+    /// Forgets the text of a buffer, which closed or whose text is evicted to save memory.
+    pub(crate) fn remove(&self, buffer_id: ID) {
+        self.texts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&buffer_id);
+    }
+}
+
+impl Clone for Cache {
+    /// Clones start empty; the texts are rebuilt on demand.
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Builds a snapshot from its parts; the editor state supplies them under its own borrow.
+pub(crate) fn new(
+    buffer_id: ID,
+    digest: u64,
+    meta: meta::Data,
+    position: Position,
+    text: Arc<str>,
+) -> Snapshot {
+    Snapshot {
+        buffer_id,
+        digest,
+        meta,
+        position,
+        text,
+    }
+}
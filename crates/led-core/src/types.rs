@@ -14,6 +14,8 @@ pub mod buffer {
         ///
         /// # Returns
         /// A new `ID` instance with a unique UUID.
+        // Not `Default`: a defaulted ID would silently be a fresh one that refers to nothing
+        #[allow(clippy::new_without_default)]
         pub fn new() -> Self {
             Self(uuid::Uuid::new_v4())
         }
@@ -38,13 +40,14 @@ pub mod source {
 /// Represents a position in the document (line and column).
 ///
 /// The `Position` struct is used to specify a location within a document,
-/// using zero-based line and column numbers.
+/// using zero-based line and column numbers. Columns are byte offsets into the line's UTF-8
+/// text, never counts of characters; a caret's column always falls between characters.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[derive(PartialEq)]
 pub struct Position {
     /// Line number (zero-based).
     pub line: usize,
-    /// Column number (zero-based).
+    /// Column number (zero-based), in bytes from the start of the line.
     pub column: usize,
 }
 
@@ -61,9 +64,28 @@ pub struct Range {
     pub end: Position,
 }
 
+impl Range {
+    /// Returns the range with its start before its end.
+    ///
+    /// Selections keep their anchor in `start` and the caret in `end`, so a selection made
+    /// backwards has `end` before `start`.
+    pub fn ordered(self) -> Self {
+        if (self.start.line, self.start.column) <= (self.end.line, self.end.column) {
+            self
+        } else {
+            Range { start: self.end, end: self.start }
+        }
+    }
+
+    /// Returns whether the range covers no text.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
 /// Module containing cursor-related types.
 pub mod cursor {
-    use crate::led::types::{Position, Range};
+    use crate::types::{Position, Range};
 
     /// Represents the state of a cursor, including its position, selection, and buffer ID.
     ///
@@ -129,6 +151,17 @@ mod tests {
         assert_ne!(range1, range3, "Ranges with different positions should not be equal");
     }
 
+    #[test]
+    fn range_ordered_and_empty() {
+        let start = Position { line: 1, column: 2 };
+        let end = Position { line: 0, column: 4 };
+        let backwards = Range { start, end };
+        assert_eq!(backwards.ordered(), Range { start: end, end: start });
+        assert_eq!(backwards.ordered().ordered(), backwards.ordered());
+        assert!(!backwards.is_empty());
+        assert!(Range { start, end: start }.is_empty());
+    }
+
     #[test]
     fn cursor_state_with_and_without_selection() {
         let pos = Position { line: 2, column: 5 };
@@ -0,0 +1,167 @@
+/// Counts the number of line breaks (`'\n'` characters) in the given text.
+///
+/// # Arguments
+///
+/// * `text` - The text to search.
+///
+/// # Returns
+///
+/// The number of line breaks as a `u32`.
+pub(crate) fn count_line_breaks(text: &str) -> u32 {
+    text.chars().filter(|&c| c == '\n').count() as u32
+}
+
+/// Counts the number of whitespace-separated words in the given text.
+///
+/// # Arguments
+///
+/// * `text` - The text to count words in.
+///
+/// # Returns
+///
+/// The number of words.
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// What a character counts as when moving by word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    /// Whitespace, including line breaks, which is skipped.
+    Space,
+    /// Alphanumeric characters and `_`, which form words.
+    Word,
+    /// Any other character; runs of them are stopped at like words.
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Finds where the next word ends, skipping any whitespace in front of it.
+///
+/// A word is a run of alphanumeric characters and `_`, or a run of punctuation, so
+/// `foo.bar()` is stepped through as `foo`, `.`, `bar` and `()`.
+///
+/// # Arguments
+///
+/// * `text` - The text to move through.
+/// * `offset` - The byte offset to start at.
+///
+/// # Returns
+///
+/// The byte offset just after the word, or the end of the text if no word follows.
+pub fn next_word_end(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    let mut chars = text[offset..]
+        .char_indices()
+        .skip_while(|(_, c)| c.is_whitespace())
+        .peekable();
+    let Some(&(_, first)) = chars.peek() else {
+        return text.len();
+    };
+    let class = char_class(first);
+    chars
+        .find(|(_, c)| char_class(*c) != class)
+        .map_or(text.len(), |(i, _)| offset + i)
+}
+
+/// Finds where the previous word starts, skipping any whitespace behind it. Words are as for
+/// [`next_word_end`].
+///
+/// # Arguments
+///
+/// * `text` - The text to move through.
+/// * `offset` - The byte offset to start at.
+///
+/// # Returns
+///
+/// The byte offset of the word's first character, or 0 if no word precedes it.
+pub fn prev_word_start(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    let mut chars = text[..offset]
+        .char_indices()
+        .rev()
+        .skip_while(|(_, c)| c.is_whitespace())
+        .peekable();
+    let Some(&(_, last)) = chars.peek() else {
+        return 0;
+    };
+    let class = char_class(last);
+    chars
+        .find(|(_, c)| char_class(*c) != class)
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_no_line_breaks_in_empty_string() {
+        let text = String::from("");
+        assert_eq!(count_line_breaks(&text), 0);
+    }
+
+    #[test]
+    fn counts_no_line_breaks_in_single_line() {
+        let text = String::from("Hello, world!");
+        assert_eq!(count_line_breaks(&text), 0);
+    }
+
+    #[test]
+    fn counts_single_line_break() {
+        let text = String::from("Hello\nworld!");
+        assert_eq!(count_line_breaks(&text), 1);
+    }
+
+    #[test]
+    fn counts_multiple_line_breaks() {
+        let text = String::from("a\nb\nc\nd");
+        assert_eq!(count_line_breaks(&text), 3);
+    }
+
+    #[test]
+    fn counts_line_breaks_at_start_and_end() {
+        let text = String::from("\nHello\nworld!\n");
+        assert_eq!(count_line_breaks(&text), 3);
+    }
+
+    #[test]
+    fn counts_only_newline_characters() {
+        let text = String::from("line1\r\nline2\r\nline3");
+        // Only '\n' is counted, not '\r'
+        assert_eq!(count_line_breaks(&text), 2);
+    }
+
+    #[test]
+    fn counts_words_separated_by_any_whitespace() {
+        assert_eq!(count_words(""), 0);
+        assert_eq!(count_words("  one\ttwo\n three  "), 3);
+    }
+
+    #[test]
+    fn moves_between_word_starts_and_ends() {
+        let text = "let größe = foo.bar();\n  next";
+        assert_eq!(next_word_end(text, 0), 3);
+        assert_eq!(next_word_end(text, 3), 11);
+        assert_eq!(next_word_end(text, 11), 13);
+        assert_eq!(next_word_end(text, 17), 18);
+        assert_eq!(next_word_end(text, 21), 24);
+        assert_eq!(next_word_end(text, 24), 31);
+        assert_eq!(next_word_end(text, 31), 31);
+        assert_eq!(prev_word_start(text, 31), 27);
+        assert_eq!(prev_word_start(text, 27), 21);
+        assert_eq!(prev_word_start(text, 11), 4);
+        assert_eq!(prev_word_start(text, 6), 4);
+        assert_eq!(prev_word_start(text, 3), 0);
+        assert_eq!(prev_word_start(text, 0), 0);
+    }
+}
@@ -0,0 +1,297 @@
+use super::cursor;
+use super::delta::Delta;
+use super::folds;
+use super::piece::Table;
+use super::types::{Range, buffer};
+
+/// Unique identifier for a view, backed by a UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ID(pub uuid::Uuid);
+
+impl ID {
+    /// Creates a new unique view ID.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4())
+    }
+}
+
+/// How a view lays out its buffer where it differs from the editor-wide settings, so one pane
+/// can show a zoomed-out overview of a buffer next to another pane editing it at normal size.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Overrides {
+    /// Font size in points, instead of the editor's.
+    pub font_size: Option<f32>,
+    /// Whether lines are soft-wrapped, instead of the editor's setting.
+    pub soft_wrap: Option<bool>,
+}
+
+/// A buffer as shown in one pane: everything about it that is not the buffer's text.
+///
+/// Several views can show the same buffer. They share its piece table, so an edit made in one
+/// view is visible in all of them, but each view keeps its own cursor and selection, its own
+/// folds (and, in the UI, its own scroll position).
+#[derive(Debug, Clone)]
+pub struct State {
+    /// The buffer the view shows.
+    pub(crate) buffer_id: buffer::ID,
+    /// The view's cursor and selection.
+    pub(crate) cursor: cursor::State,
+    /// The view's own font size and wrapping.
+    pub(crate) overrides: Overrides,
+    /// The view's folded regions of its buffer.
+    pub(crate) folds: folds::Registry,
+}
+
+impl State {
+    /// Returns the buffer the view shows.
+    pub fn buffer_id(&self) -> buffer::ID {
+        self.buffer_id
+    }
+
+    /// Returns the view's cursor.
+    pub fn cursor(&self) -> &cursor::State {
+        &self.cursor
+    }
+
+    /// Returns the view's folds. While the view is focused, they are the buffer's current folds,
+    /// see [`State::folds`](crate::buffer::editor::State::folds).
+    pub fn folds(&self) -> &folds::Registry {
+        &self.folds
+    }
+
+    /// Returns the settings the view overrides.
+    pub fn overrides(&self) -> Overrides {
+        self.overrides
+    }
+
+    /// Sets the settings the view overrides; `Overrides::default()` goes back to the editor's.
+    pub fn set_overrides(&mut self, overrides: Overrides) {
+        self.overrides = overrides;
+    }
+
+    /// Captures the view's cursor and selection as byte offsets, ahead of an edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The buffer's text before the edit.
+    pub(crate) fn anchor(&self, table: &Table) -> Anchor {
+        Anchor {
+            position: table.position_to_offset(self.cursor.position),
+            selection: self.cursor.selection.map(|selection| {
+                (
+                    table.position_to_offset(selection.start),
+                    table.position_to_offset(selection.end),
+                )
+            }),
+        }
+    }
+
+    /// Moves the view's cursor, selection and folds to follow an edit made in another view.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor` - The view's offsets from before the edit, see [`State::anchor`].
+    /// * `delta` - The edit.
+    /// * `table` - The buffer's text after the edit.
+    pub(crate) fn follow_edit(&mut self, anchor: Anchor, delta: &Delta, table: &Table) {
+        let remap = |offset| table.offset_to_position(delta.map_offset(offset).min(table.len()));
+        self.cursor.position = remap(anchor.position);
+        self.cursor.selection = anchor.selection.map(|(start, end)| Range {
+            start: remap(start),
+            end: remap(end),
+        });
+        self.cursor.preferred_column = None;
+        self.folds.apply(self.buffer_id, delta);
+    }
+}
+
+/// A view's cursor and selection as byte offsets, captured before an edit.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Anchor {
+    position: usize,
+    selection: Option<(usize, usize)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Overrides;
+    use crate::buffer::editor::State as EditorState;
+    use crate::commands::editor::Command;
+    use crate::types::Position;
+
+    #[test]
+    fn views_share_text_but_keep_their_own_cursor() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("one\ntwo\n".to_string());
+        let left = state.create_view(buffer_id).unwrap();
+        let right = state.create_view(buffer_id).unwrap();
+
+        state.focus_view(right);
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: Position { line: 1, column: 2 },
+            })
+            .unwrap();
+
+        state.focus_view(left);
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            Position { line: 0, column: 0 }
+        );
+        state.focus_view(right);
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            Position { line: 1, column: 2 }
+        );
+    }
+
+    #[test]
+    fn views_override_settings_independently() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("text".to_string());
+        let overview = state.create_view(buffer_id).unwrap();
+        let editing = state.create_view(buffer_id).unwrap();
+
+        let zoomed_out = Overrides {
+            font_size: Some(8.0),
+            soft_wrap: Some(false),
+        };
+        state.view_mut(overview).unwrap().set_overrides(zoomed_out);
+        assert_eq!(state.view(overview).unwrap().overrides(), zoomed_out);
+        assert_eq!(
+            state.view(editing).unwrap().overrides(),
+            Overrides::default()
+        );
+    }
+
+    #[test]
+    fn edits_in_one_view_move_the_cursor_of_another() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("one\ntwo\n".to_string());
+        let left = state.create_view(buffer_id).unwrap();
+        let right = state.create_view(buffer_id).unwrap();
+
+        state.focus_view(right);
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: Position { line: 1, column: 1 },
+            })
+            .unwrap();
+
+        state.focus_view(left);
+        state
+            .execute_command(Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "zero\n".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            state.view(right).unwrap().cursor().position(),
+            Position { line: 2, column: 1 }
+        );
+        assert_eq!(
+            state.get_buffer_text(buffer_id).unwrap(),
+            "zero\none\ntwo\n"
+        );
+    }
+
+    #[test]
+    fn other_views_follow_edits_after_wide_characters() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("ééé x".to_string());
+        let left = state.create_view(buffer_id).unwrap();
+        let right = state.create_view(buffer_id).unwrap();
+
+        // The right view's caret sits before "x", at byte column 7
+        state.focus_view(right);
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: Position { line: 0, column: 7 },
+            })
+            .unwrap();
+
+        state.focus_view(left);
+        state
+            .execute_command(Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "ä".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            state.view(right).unwrap().cursor().position(),
+            Position { line: 0, column: 9 }
+        );
+        state.focus_view(right);
+        state
+            .execute_command(Command::InsertText {
+                buffer_id,
+                offset: 9,
+                text: "!".to_string(),
+            })
+            .unwrap();
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "äééé !x");
+    }
+
+    #[test]
+    fn each_view_folds_on_its_own() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("fn a() {\n    x\n}\n".to_string());
+        let left = state.create_view(buffer_id).unwrap();
+        let right = state.create_view(buffer_id).unwrap();
+
+        state.focus_view(left);
+        assert!(state.toggle_fold(buffer_id, 0, 4));
+        assert_eq!(state.hidden_lines(buffer_id), vec![1..2]);
+        state.focus_view(right);
+        assert!(state.hidden_lines(buffer_id).is_empty());
+
+        // The folded view's fold follows an edit made in the other
+        state
+            .execute_command(Command::InsertText {
+                buffer_id,
+                offset: 0,
+                text: "// a\n".to_string(),
+            })
+            .unwrap();
+        assert!(state.hidden_lines(buffer_id).is_empty());
+        state.focus_view(left);
+        assert_eq!(state.hidden_lines(buffer_id), vec![2..3]);
+    }
+
+    #[test]
+    fn refocusing_a_view_keeps_its_newer_cursor() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer("one\ntwo\n".to_string());
+        let view = state.create_view(buffer_id).unwrap();
+        state.focus_view(view);
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: Position { line: 1, column: 0 },
+            })
+            .unwrap();
+        state.focus_view(view);
+        assert_eq!(
+            state.get_cursor_state(buffer_id).unwrap().position(),
+            Position { line: 1, column: 0 }
+        );
+    }
+
+    #[test]
+    fn closing_a_buffer_closes_its_views() {
+        let mut state = EditorState::new();
+        let buffer_id = state.create_buffer(String::new());
+        let view = state.create_view(buffer_id).unwrap();
+        state.close_buffer(buffer_id);
+        assert!(state.view(view).is_none());
+        assert!(state.create_view(buffer_id).is_none());
+    }
+}
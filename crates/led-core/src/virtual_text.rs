@@ -0,0 +1,485 @@
+use super::delta::Delta;
+use super::types::{Position, buffer};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Unique identifier for a piece of virtual text, backed by a UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ID(pub uuid::Uuid);
+
+impl ID {
+    /// Creates a new unique virtual text ID.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4())
+    }
+}
+
+/// Where virtual text is shown relative to its anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Within the line, pushing the text after the anchor to the right.
+    Inline,
+    /// As a block of its own rows above the anchor's line.
+    Above,
+    /// As a block of its own rows below the anchor's line.
+    Below,
+}
+
+/// How virtual text is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Style {
+    /// Unobtrusive annotations such as inlay hints.
+    #[default]
+    Hint,
+    /// A preview of text that is not inserted yet.
+    Ghost,
+    Info,
+    Warning,
+    Error,
+}
+
+/// Non-editable text shown in a buffer's widget without being part of the buffer.
+///
+/// Virtual text is anchored at a byte offset that follows edits like a mark does. The widget
+/// makes room for it, keeps the caret and pointer positions mapped to the real text around it,
+/// and reports clicks on it through [`Registry::take_clicks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub id: ID,
+    /// The buffer the text is shown in.
+    pub buffer_id: buffer::ID,
+    /// Byte offset of the anchor within the buffer.
+    pub offset: usize,
+    pub kind: Kind,
+    /// The text. Inline text uses only its first line; blocks get one row per line.
+    pub text: String,
+    pub style: Style,
+    /// Action run when the text is clicked, e.g. a keymap action or a Lua command name.
+    pub action: Option<String>,
+}
+
+impl Item {
+    /// Creates virtual text with the default style and no click action.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer to show the text in.
+    /// * `offset` - Byte offset of the anchor.
+    /// * `kind` - Where the text is shown relative to the anchor.
+    /// * `text` - The text to show.
+    pub fn new(buffer_id: buffer::ID, offset: usize, kind: Kind, text: impl Into<String>) -> Self {
+        Self {
+            id: ID::new(),
+            buffer_id,
+            offset,
+            kind,
+            text: text.into(),
+            style: Style::default(),
+            action: None,
+        }
+    }
+
+    /// Returns the number of columns inline text takes up.
+    pub fn width(&self) -> usize {
+        self.text
+            .lines()
+            .next()
+            .map_or(0, |line| line.chars().count())
+    }
+
+    /// Returns the number of rows a block takes up.
+    pub fn height(&self) -> usize {
+        self.text.lines().count().max(1)
+    }
+}
+
+/// A suggested continuation of a buffer's text, such as a snippet preview or a completion.
+///
+/// The suggestion is shown as ghost text at its anchor until it is accepted or dismissed. Its
+/// first line is shown inline and any further lines as a block below the anchor's line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Byte offset the text would be inserted at.
+    pub offset: usize,
+    /// The suggested text.
+    pub text: String,
+    /// The virtual text showing the suggestion.
+    items: Vec<ID>,
+}
+
+/// All virtual text of the editor.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    items: Vec<Item>,
+    /// Items clicked since the last [`Registry::take_clicks`].
+    clicks: Vec<ID>,
+    /// The suggestion shown in each buffer; a buffer has at most one.
+    suggestions: HashMap<buffer::ID, Suggestion>,
+}
+
+impl Registry {
+    /// Adds virtual text, returning its ID.
+    pub fn add(&mut self, item: Item) -> ID {
+        let id = item.id;
+        self.items.push(item);
+        id
+    }
+
+    /// Removes virtual text.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the text existed and was removed.
+    pub fn remove(&mut self, id: ID) -> bool {
+        let before = self.items.len();
+        self.items.retain(|item| item.id != id);
+        self.items.len() != before
+    }
+
+    /// Returns virtual text by ID.
+    pub fn get(&self, id: ID) -> Option<&Item> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    /// Returns the virtual text of a buffer, in the order it was added.
+    pub fn for_buffer(&self, buffer_id: buffer::ID) -> impl Iterator<Item = &Item> {
+        self.items
+            .iter()
+            .filter(move |item| item.buffer_id == buffer_id)
+    }
+
+    /// Removes all virtual text of a buffer.
+    pub fn clear_buffer(&mut self, buffer_id: buffer::ID) {
+        self.items.retain(|item| item.buffer_id != buffer_id);
+        self.suggestions.remove(&buffer_id);
+    }
+
+    /// Moves the virtual text of a buffer to follow an edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The edited buffer.
+    /// * `delta` - The change made to its text.
+    pub fn apply(&mut self, buffer_id: buffer::ID, delta: &Delta) {
+        for item in self.items.iter_mut().filter(|i| i.buffer_id == buffer_id) {
+            item.offset = delta.map_offset(item.offset);
+        }
+        if let Some(suggestion) = self.suggestions.get_mut(&buffer_id) {
+            suggestion.offset = delta.map_offset(suggestion.offset);
+        }
+    }
+
+    /// Records a click on virtual text, for the owner of the text to pick up.
+    pub fn click(&mut self, id: ID) {
+        self.clicks.push(id);
+    }
+
+    /// Returns and forgets the clicks recorded since the last call.
+    pub fn take_clicks(&mut self) -> Vec<ID> {
+        std::mem::take(&mut self.clicks)
+    }
+
+    /// Shows a suggestion in a buffer, replacing any suggestion already shown there.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer to show the suggestion in.
+    /// * `offset` - Byte offset the text would be inserted at, usually the caret.
+    /// * `text` - The suggested text. Empty text just dismisses the current suggestion.
+    pub fn suggest(&mut self, buffer_id: buffer::ID, offset: usize, text: impl Into<String>) {
+        self.dismiss_suggestion(buffer_id);
+        let text = text.into();
+        if text.is_empty() {
+            return;
+        }
+        let (first, rest) = match text.split_once('\n') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (text.as_str(), None),
+        };
+        let mut items = Vec::new();
+        if !first.is_empty() {
+            let mut inline = Item::new(buffer_id, offset, Kind::Inline, first);
+            inline.style = Style::Ghost;
+            items.push(self.add(inline));
+        }
+        if let Some(rest) = rest {
+            let mut block = Item::new(buffer_id, offset, Kind::Below, rest);
+            block.style = Style::Ghost;
+            items.push(self.add(block));
+        }
+        self.suggestions.insert(
+            buffer_id,
+            Suggestion {
+                offset,
+                text,
+                items,
+            },
+        );
+    }
+
+    /// Returns the suggestion shown in a buffer, if any.
+    pub fn suggestion(&self, buffer_id: buffer::ID) -> Option<&Suggestion> {
+        self.suggestions.get(&buffer_id)
+    }
+
+    /// Stops showing a buffer's suggestion and returns it, e.g. for inserting it.
+    pub fn take_suggestion(&mut self, buffer_id: buffer::ID) -> Option<Suggestion> {
+        let suggestion = self.suggestions.remove(&buffer_id)?;
+        self.items
+            .retain(|item| !suggestion.items.contains(&item.id));
+        Some(suggestion)
+    }
+
+    /// Stops showing a buffer's suggestion.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the buffer had a suggestion.
+    pub fn dismiss_suggestion(&mut self, buffer_id: buffer::ID) -> bool {
+        self.take_suggestion(buffer_id).is_some()
+    }
+}
+
+/// Inline virtual text placed on a visual row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineSpan {
+    /// Index of the text row in the widget's rows.
+    pub row: usize,
+    /// Column within the line (not the row) the text is inserted at.
+    pub column: usize,
+    /// Number of columns the text takes up.
+    pub width: usize,
+    /// Index of the item in the arranged items.
+    pub item: usize,
+}
+
+/// A virtual text block placed between text rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSpan {
+    /// First visual row of the block.
+    pub top: usize,
+    /// Number of visual rows the block takes up.
+    pub height: usize,
+    /// Index of the item in the arranged items.
+    pub item: usize,
+}
+
+/// Where virtual text ends up among the visual rows of a widget.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Arrangement {
+    /// Visual row each text row is drawn on, counting the rows taken by blocks.
+    pub row_tops: Vec<usize>,
+    /// Total number of visual rows, including blocks after the last text row.
+    pub visual_rows: usize,
+    pub inlines: Vec<InlineSpan>,
+    pub blocks: Vec<BlockSpan>,
+}
+
+impl Arrangement {
+    /// Returns the number of columns taken up by inline text on a row before `column`.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Index of the text row.
+    /// * `column` - Column within the line.
+    /// * `inclusive` - Whether text inserted at `column` itself counts. The caret at an
+    ///   insertion point sits before the inserted text, but the character there is drawn after it.
+    pub fn inline_shift(&self, row: usize, column: usize, inclusive: bool) -> usize {
+        self.inlines
+            .iter()
+            .filter(|span| {
+                span.row == row && (span.column < column || inclusive && span.column == column)
+            })
+            .map(|span| span.width)
+            .sum()
+    }
+}
+
+/// Places virtual text among a widget's rows.
+///
+/// Blocks above a line go before its first row and blocks below it after its last row. Inline
+/// text goes on the row containing its column, or on the last row of its line if it is anchored
+/// at the end. Blocks anchored past the last row are placed after it.
+///
+/// # Arguments
+///
+/// * `rows` - The widget's text rows, as a line index and a byte range within that line.
+/// * `items` - The virtual text to place, with the position of each anchor.
+pub fn arrange(rows: &[(usize, Range<usize>)], items: &[(Position, &Item)]) -> Arrangement {
+    let mut arrangement = Arrangement::default();
+    let mut visual = 0;
+    let push_blocks = |arrangement: &mut Arrangement, visual: &mut usize, kind, line| {
+        for (index, (position, item)) in items.iter().enumerate() {
+            if item.kind == kind && position.line == line {
+                arrangement.blocks.push(BlockSpan {
+                    top: *visual,
+                    height: item.height(),
+                    item: index,
+                });
+                *visual += item.height();
+            }
+        }
+    };
+
+    for (row, (line, range)) in rows.iter().enumerate() {
+        let first = range.start == 0;
+        let last = rows.get(row + 1).is_none_or(|(next, _)| next != line);
+        if first {
+            push_blocks(&mut arrangement, &mut visual, Kind::Above, *line);
+        }
+        arrangement.row_tops.push(visual);
+        visual += 1;
+        for (index, (position, item)) in items.iter().enumerate() {
+            let on_row = range.contains(&position.column) || last && position.column >= range.end;
+            if item.kind == Kind::Inline && position.line == *line && on_row {
+                arrangement.inlines.push(InlineSpan {
+                    row,
+                    column: position.column.min(range.end),
+                    width: item.width(),
+                    item: index,
+                });
+            }
+        }
+        if last {
+            push_blocks(&mut arrangement, &mut visual, Kind::Below, *line);
+        }
+    }
+
+    // Blocks of lines without rows, such as a trailing empty line
+    let last_line = rows.last().map(|(line, _)| *line);
+    for (index, (position, item)) in items.iter().enumerate() {
+        if item.kind != Kind::Inline && last_line.is_none_or(|last| position.line > last) {
+            arrangement.blocks.push(BlockSpan {
+                top: visual,
+                height: item.height(),
+                item: index,
+            });
+            visual += item.height();
+        }
+    }
+    arrangement.visual_rows = visual;
+    arrangement
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn registry_follows_edits_and_reports_clicks() {
+        let buffer_id = buffer::ID::new();
+        let mut registry = Registry::default();
+        let id = registry.add(Item::new(buffer_id, 10, Kind::Inline, ": i32"));
+        registry.apply(
+            buffer_id,
+            &Delta::Insert {
+                offset: 2,
+                length: 3,
+            },
+        );
+        assert_eq!(registry.get(id).unwrap().offset, 13);
+
+        registry.click(id);
+        assert_eq!(registry.take_clicks(), vec![id]);
+        assert!(registry.take_clicks().is_empty());
+
+        registry.clear_buffer(buffer_id);
+        assert!(registry.get(id).is_none());
+        assert!(!registry.remove(id));
+    }
+
+    #[test]
+    fn suggestions_are_shown_as_ghost_text() {
+        let buffer_id = buffer::ID::new();
+        let mut registry = Registry::default();
+        registry.suggest(buffer_id, 4, "(x) {\n    x\n}");
+        let items: Vec<&Item> = registry.for_buffer(buffer_id).collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            (items[0].kind, items[0].text.as_str()),
+            (Kind::Inline, "(x) {")
+        );
+        assert_eq!(
+            (items[1].kind, items[1].text.as_str()),
+            (Kind::Below, "    x\n}")
+        );
+        assert!(items.iter().all(|item| item.style == Style::Ghost));
+
+        // A new suggestion replaces the old one
+        registry.suggest(buffer_id, 4, "()");
+        assert_eq!(registry.for_buffer(buffer_id).count(), 1);
+        registry.apply(
+            buffer_id,
+            &Delta::Insert {
+                offset: 0,
+                length: 2,
+            },
+        );
+        let suggestion = registry.take_suggestion(buffer_id).unwrap();
+        assert_eq!((suggestion.offset, suggestion.text.as_str()), (6, "()"));
+        assert_eq!(registry.for_buffer(buffer_id).count(), 0);
+        assert!(!registry.dismiss_suggestion(buffer_id));
+    }
+
+    #[test]
+    fn blocks_push_rows_down() {
+        let buffer_id = buffer::ID::new();
+        let above = Item::new(buffer_id, 0, Kind::Above, "one\ntwo");
+        let below = Item::new(buffer_id, 0, Kind::Below, "three");
+        let rows = vec![(0, 0..4), (1, 0..4)];
+        let arrangement = arrange(&rows, &[(at(1, 0), &above), (at(0, 2), &below)]);
+        assert_eq!(arrangement.row_tops, vec![0, 4]);
+        assert_eq!(arrangement.visual_rows, 5);
+        assert_eq!(
+            arrangement.blocks,
+            vec![
+                BlockSpan {
+                    top: 1,
+                    height: 1,
+                    item: 1
+                },
+                BlockSpan {
+                    top: 2,
+                    height: 2,
+                    item: 0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_text_shifts_later_columns() {
+        let buffer_id = buffer::ID::new();
+        let hint = Item::new(buffer_id, 0, Kind::Inline, ": u8");
+        // A line wrapped into two rows; the hint sits at its end
+        let rows = vec![(0, 0..5), (0, 5..8)];
+        let arrangement = arrange(&rows, &[(at(0, 8), &hint)]);
+        assert_eq!(
+            arrangement.inlines,
+            vec![InlineSpan {
+                row: 1,
+                column: 8,
+                width: 4,
+                item: 0
+            }]
+        );
+        assert_eq!(arrangement.inline_shift(1, 8, false), 0);
+        assert_eq!(arrangement.inline_shift(1, 8, true), 4);
+        assert_eq!(arrangement.inline_shift(0, 8, true), 0);
+    }
+
+    #[test]
+    fn blocks_past_the_last_row_go_at_the_end() {
+        let buffer_id = buffer::ID::new();
+        let block = Item::new(buffer_id, 0, Kind::Above, "result");
+        let arrangement = arrange(&[(0, 0..3)], &[(at(1, 0), &block)]);
+        assert_eq!(arrangement.row_tops, vec![0]);
+        assert_eq!(arrangement.blocks[0].top, 1);
+        assert_eq!(arrangement.visual_rows, 2);
+    }
+}
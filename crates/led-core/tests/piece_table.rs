@@ -1,5 +1,5 @@
-use led::piece_table::piece::Table;
-use led::types::Position;
+use led_core::piece_table::piece::Table;
+use led_core::types::Position;
 
 fn make_table_with_text(text: &str) -> Table {
     let mut table = Table::new("".to_string());
@@ -88,3 +88,46 @@ fn test_insert_delete_multiline() {
     assert_eq!(table.get_text(0, table.len()), "foo\nbar\nbaz");
     assert_eq!(table.lines(), 3);
 }
+
+#[test]
+fn test_compact_keeps_the_text() {
+    let mut table = make_table_with_text("Hello\nworld");
+    let _ = table.insert(5, ",");
+    let _ = table.delete(0, 1);
+    assert!(table.piece_count() > 1);
+    table.compact();
+    assert_eq!(table.piece_count(), 1);
+    assert_eq!(table.get_text(0, table.len()), "ello,\nworld");
+    assert_eq!(table.lines(), 2);
+}
+
+#[test]
+fn test_positions_agree_with_the_text_when_lines_span_pieces() {
+    let mut table = Table::new("fn main() {\n}\n".to_string());
+    // Typing inside a line, one piece per keystroke, as the editor does
+    let mut offset = 12;
+    for text in ["    let", " x", " = ", "\"é\"", ";\n"] {
+        table.insert(offset, text).unwrap();
+        offset += text.len();
+    }
+    let _ = table.delete(16, 1);
+    let text = table.get_text(0, table.len());
+    assert_eq!(text, "fn main() {\n    et x = \"é\";\n}\n");
+    assert!(table.piece_count() > 3);
+
+    for offset in (0..=text.len()).filter(|&offset| text.is_char_boundary(offset)) {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let expected = Position {
+            line: before.matches('\n').count(),
+            column: offset - line_start,
+        };
+        assert_eq!(
+            table.offset_to_position(offset),
+            expected,
+            "offset {}",
+            offset
+        );
+        assert_eq!(table.position_to_offset(expected), offset);
+    }
+}
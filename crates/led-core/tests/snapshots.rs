@@ -0,0 +1,106 @@
+use led_core::buffer::editor::State;
+use led_core::commands::editor::Command;
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// How many characters the writer types in each stress test.
+const KEYSTROKES: usize = 1_000;
+const READERS: usize = 4;
+
+#[test]
+fn state_can_be_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<State>();
+    assert_send_sync::<led_core::snapshot::Snapshot>();
+}
+
+#[test]
+fn snapshots_share_text_until_it_changes() {
+    let mut state = State::new();
+    let id = state.create_buffer("abc".to_string());
+    let first = state.snapshot(id).unwrap();
+    let second = state.snapshot(id).unwrap();
+    assert!(Arc::ptr_eq(&first.shared_text(), &second.shared_text()));
+
+    state
+        .execute_command(Command::InsertText {
+            buffer_id: id,
+            offset: 3,
+            text: "d".to_string(),
+        })
+        .unwrap();
+    let third = state.snapshot(id).unwrap();
+    assert_eq!(first.text(), "abc");
+    assert_eq!(third.text(), "abcd");
+    assert_ne!(first.digest, third.digest);
+    assert!(state.snapshot(led_core::buffer::ID::new()).is_none());
+}
+
+/// Readers take snapshots through the read lock while a writer types one character at a time.
+/// Every snapshot must show a whole number of keystrokes, and no reader may see the text
+/// shrink.
+#[test]
+fn readers_see_consistent_snapshots_during_heavy_typing() {
+    let mut state = State::new();
+    let id = state.create_buffer(String::new());
+    let shared = Arc::new(RwLock::new(state));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let mut seen = 0;
+                let mut snapshots = 0;
+                while seen < KEYSTROKES * 2 {
+                    let snapshot = shared.read().unwrap().snapshot(id).unwrap();
+                    // The lock is released; the snapshot stays valid while typing goes on
+                    let text = snapshot.text();
+                    assert!(text.len() >= seen, "text shrank");
+                    assert_eq!(text.len() % 2, 0, "torn keystroke");
+                    assert!(text.as_bytes().chunks(2).all(|pair| pair == b"ab"));
+                    seen = text.len();
+                    snapshots += 1;
+                }
+                snapshots
+            })
+        })
+        .collect();
+
+    for _ in 0..KEYSTROKES {
+        let mut state = shared.write().unwrap();
+        let offset = state.buffer(id).unwrap().len();
+        // One keystroke is two edits in a batch, so a reader must never see only the first
+        state
+            .execute_command(Command::Batch(vec![
+                Command::InsertText {
+                    buffer_id: id,
+                    offset,
+                    text: "a".to_string(),
+                },
+                Command::InsertText {
+                    buffer_id: id,
+                    offset: offset + 1,
+                    text: "b".to_string(),
+                },
+            ]))
+            .unwrap();
+    }
+
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+    let state = shared.read().unwrap();
+    assert_eq!(state.snapshot(id).unwrap().text().len(), KEYSTROKES * 2);
+}
+
+/// Snapshots taken before the buffer is closed stay readable on other threads.
+#[test]
+fn snapshots_outlive_their_buffer() {
+    let mut state = State::new();
+    let id = state.create_buffer("kept".to_string());
+    let snapshot = state.snapshot(id).unwrap();
+    state.close_buffer(id);
+    let reader = thread::spawn(move || snapshot.text().to_string());
+    assert_eq!(reader.join().unwrap(), "kept");
+    assert!(state.snapshot(id).is_none());
+}
@@ -0,0 +1,470 @@
+use led_core::buffer::{self, editor::State};
+use led_core::commands::editor::Command;
+use led_core::types::{Position, Range};
+use led_core::{save, search};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// One file open in the terminal editor, edited through the same engine as the GUI.
+pub struct Editor {
+    /// The editing engine holding the buffer and its cursor.
+    state: State,
+    /// The buffer being edited.
+    buffer_id: buffer::ID,
+    /// Where the buffer is saved to.
+    path: PathBuf,
+    /// The search query being typed, while the find prompt is open.
+    prompt: Option<String>,
+    /// The last query searched for.
+    query: String,
+    /// A message shown in the status line until the next key.
+    pub status: String,
+    /// The first line in view.
+    pub scroll: usize,
+    /// Set when Ctrl+Q was refused because of unsaved changes, so pressing it again quits.
+    quit_armed: bool,
+    /// Whether the editor should exit.
+    pub quit: bool,
+}
+
+impl Editor {
+    /// Opens a file, or starts an empty buffer that is saved to `path` if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but can't be read as UTF-8 text.
+    pub fn open(path: PathBuf) -> anyhow::Result<Self> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer(content);
+        state.set_file_path(buffer_id, path.display().to_string());
+        Ok(Self {
+            state,
+            buffer_id,
+            path,
+            prompt: None,
+            query: String::new(),
+            status: String::new(),
+            scroll: 0,
+            quit_armed: false,
+            quit: false,
+        })
+    }
+
+    /// Returns the text of the buffer.
+    pub fn text(&self) -> String {
+        self.state
+            .get_buffer_text(self.buffer_id)
+            .unwrap_or_default()
+    }
+
+    /// Returns the caret position.
+    pub fn position(&self) -> Position {
+        self.state
+            .get_cursor_state(self.buffer_id)
+            .map_or(Position { line: 0, column: 0 }, |cursor| cursor.position())
+    }
+
+    /// Returns the selected range, start first, if anything is selected.
+    pub fn selection(&self) -> Option<Range> {
+        self.state
+            .get_cursor_state(self.buffer_id)
+            .and_then(|cursor| cursor.selection())
+            .map(|selection| selection.ordered())
+            .filter(|selection| !selection.is_empty())
+    }
+
+    /// Returns the file name shown in the status line.
+    pub fn name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+
+    /// Returns whether the buffer has unsaved changes.
+    pub fn modified(&self) -> bool {
+        self.state
+            .metadata(self.buffer_id)
+            .is_some_and(|meta| meta.modified)
+    }
+
+    /// Returns the search query being typed, while the find prompt is open.
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    /// Scrolls the least needed to keep the caret's line among the `height` lines in view.
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        let line = self.position().line;
+        if line < self.scroll {
+            self.scroll = line;
+        } else if height > 0 && line >= self.scroll + height {
+            self.scroll = line + 1 - height;
+        }
+    }
+
+    /// Applies a key press: editing and moving the caret, or typing into the find prompt.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        self.status.clear();
+        if key.code != KeyCode::Char('q') {
+            self.quit_armed = false;
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') if ctrl => self.request_quit(),
+            KeyCode::Char('s') if ctrl => self.save(),
+            KeyCode::Char('f') if ctrl => self.prompt = Some(self.query.clone()),
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::F(3) => self.find_next(),
+            KeyCode::Char(c) if !ctrl => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Enter => self.insert("\n"),
+            KeyCode::Tab => self.insert("\t"),
+            KeyCode::Backspace => self.delete_backward(),
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::Left => self.move_horizontally(false),
+            KeyCode::Right => self.move_horizontally(true),
+            KeyCode::Up => self.move_vertically(-1),
+            KeyCode::Down => self.move_vertically(1),
+            KeyCode::PageUp => self.move_vertically(-20),
+            KeyCode::PageDown => self.move_vertically(20),
+            KeyCode::Home if ctrl => self.move_to(Position { line: 0, column: 0 }),
+            KeyCode::End if ctrl => self.move_to_offset(self.text().len()),
+            KeyCode::Home => self.move_to(Position {
+                line: self.position().line,
+                column: 0,
+            }),
+            KeyCode::End => {
+                let line = self.position().line;
+                let column = self.line(line).len();
+                self.move_to(Position { line, column });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                self.query = self.prompt.take().unwrap_or_default();
+                self.find_next();
+            }
+            KeyCode::Backspace => {
+                prompt.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => prompt.push(c),
+            _ => {}
+        }
+    }
+
+    /// Quits, unless the buffer has unsaved changes and this is the first Ctrl+Q in a row.
+    fn request_quit(&mut self) {
+        if self.modified() && !self.quit_armed {
+            self.quit_armed = true;
+            self.status = "Unsaved changes; press Ctrl+Q again to quit without saving".to_string();
+            return;
+        }
+        self.quit = true;
+    }
+
+    /// Writes the buffer to its file.
+    fn save(&mut self) {
+        let text = self.text();
+        if let Err(err) = save::write_atomically(&self.path, &text) {
+            self.status = format!("Could not save {}: {err:#}", self.path.display());
+            return;
+        }
+        self.execute(Command::SaveBuffer {
+            buffer_id: self.buffer_id,
+            file_path: self.path.display().to_string(),
+        });
+        self.status = format!("Saved {} bytes to {}", text.len(), self.path.display());
+    }
+
+    /// Selects the next occurrence of the last query after the caret, wrapping around.
+    fn find_next(&mut self) {
+        if self.query.is_empty() {
+            return;
+        }
+        let text = self.text();
+        let from = self.offset(self.position());
+        let Some(found) = search::find_next(&text, &self.query, from) else {
+            self.status = format!("Not found: {}", self.query);
+            return;
+        };
+        if found.start < from {
+            self.status = "Search wrapped to the top".to_string();
+        }
+        let start = self.position_at(found.start);
+        let end = self.position_at(found.end);
+        self.execute(Command::MoveCursor {
+            buffer_id: self.buffer_id,
+            position: end,
+        });
+        self.execute(Command::SetSelection {
+            buffer_id: self.buffer_id,
+            range: Range { start, end },
+        });
+    }
+
+    /// Replaces the selection, if any, with `text` and moves the caret after it.
+    fn insert(&mut self, text: &str) {
+        let offset = self
+            .delete_selection()
+            .unwrap_or(self.offset(self.position()));
+        self.execute(Command::InsertText {
+            buffer_id: self.buffer_id,
+            offset,
+            text: text.to_string(),
+        });
+        self.move_to_offset(offset + text.len());
+    }
+
+    /// Deletes the selection, or the character before the caret.
+    fn delete_backward(&mut self) {
+        if self.delete_selection().is_some() {
+            return;
+        }
+        let offset = self.offset(self.position());
+        let text = self.text();
+        if let Some(c) = text[..offset].chars().next_back() {
+            self.delete(offset - c.len_utf8(), c.len_utf8());
+        }
+    }
+
+    /// Deletes the selection, or the character after the caret.
+    fn delete_forward(&mut self) {
+        if self.delete_selection().is_some() {
+            return;
+        }
+        let offset = self.offset(self.position());
+        let text = self.text();
+        if let Some(c) = text[offset..].chars().next() {
+            self.delete(offset, c.len_utf8());
+        }
+    }
+
+    /// Deletes the selected text.
+    ///
+    /// # Returns
+    ///
+    /// The offset the selection started at, or `None` if nothing was selected.
+    fn delete_selection(&mut self) -> Option<usize> {
+        let selection = self.selection()?;
+        let start = self.offset(selection.start);
+        let end = self.offset(selection.end);
+        self.delete(start, end - start);
+        Some(start)
+    }
+
+    fn delete(&mut self, start: usize, length: usize) {
+        self.execute(Command::DeleteText {
+            buffer_id: self.buffer_id,
+            start,
+            length,
+        });
+        self.move_to_offset(start);
+    }
+
+    /// Moves the caret a character right or left, across line breaks.
+    fn move_horizontally(&mut self, forward: bool) {
+        let text = self.text();
+        let offset = self.offset(self.position());
+        let offset = if forward {
+            text[offset..]
+                .chars()
+                .next()
+                .map_or(offset, |c| offset + c.len_utf8())
+        } else {
+            text[..offset]
+                .chars()
+                .next_back()
+                .map_or(offset, |c| offset - c.len_utf8())
+        };
+        self.move_to_offset(offset);
+    }
+
+    /// Moves the caret `lines` lines down, or up if negative, keeping its column where the
+    /// line is long enough.
+    fn move_vertically(&mut self, lines: isize) {
+        let position = self.position();
+        let last = self.text().split('\n').count() - 1;
+        let line = position.line.saturating_add_signed(lines).min(last);
+        let text = self.line(line);
+        let mut column = position.column.min(text.len());
+        while !text.is_char_boundary(column) {
+            column -= 1;
+        }
+        self.move_to(Position { line, column });
+    }
+
+    fn move_to_offset(&mut self, offset: usize) {
+        let position = self.position_at(offset);
+        self.move_to(position);
+    }
+
+    /// Moves the caret, dropping the selection.
+    fn move_to(&mut self, position: Position) {
+        self.execute(Command::MoveCursor {
+            buffer_id: self.buffer_id,
+            position,
+        });
+    }
+
+    fn undo(&mut self) {
+        if let Err(err) = self.state.undo(self.buffer_id) {
+            self.status = format!("Could not undo: {err}");
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Err(err) = self.state.redo(self.buffer_id) {
+            self.status = format!("Could not redo: {err}");
+        }
+    }
+
+    fn execute(&mut self, command: Command) {
+        if let Err(err) = self.state.execute_command(command) {
+            self.status = err.to_string();
+        }
+    }
+
+    /// Returns the text of a line, without its line break.
+    fn line(&self, line: usize) -> String {
+        self.text()
+            .split('\n')
+            .nth(line)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        self.state
+            .buffers()
+            .get(&self.buffer_id)
+            .map_or(0, |buffer| buffer.position_to_offset(position))
+    }
+
+    fn position_at(&self, offset: usize) -> Position {
+        self.state
+            .buffers()
+            .get(&self.buffer_id)
+            .map_or(Position { line: 0, column: 0 }, |buffer| {
+                buffer.offset_to_position(offset)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(name: &str, text: &str) -> (Editor, PathBuf) {
+        let path = std::env::temp_dir().join(format!("led-tui-{}-{name}.txt", std::process::id()));
+        fs::write(&path, text).unwrap();
+        (Editor::open(path.clone()).unwrap(), path)
+    }
+
+    fn press(editor: &mut Editor, code: KeyCode) {
+        editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn ctrl(editor: &mut Editor, c: char) {
+        editor.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn typing_edits_at_the_caret() {
+        let (mut editor, path) = editor("typing", "ab\ncd");
+        press(&mut editor, KeyCode::Right);
+        press(&mut editor, KeyCode::Char('x'));
+        press(&mut editor, KeyCode::Enter);
+        assert_eq!(editor.text(), "ax\nb\ncd");
+        assert_eq!(editor.position(), Position { line: 1, column: 0 });
+        press(&mut editor, KeyCode::Backspace);
+        press(&mut editor, KeyCode::Delete);
+        assert_eq!(editor.text(), "ax\ncd");
+        assert!(editor.modified());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn moving_down_keeps_the_column_within_the_line() {
+        let (mut editor, path) = editor("moving", "hello\nhi\nworld");
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Down);
+        assert_eq!(editor.position(), Position { line: 1, column: 2 });
+        editor.handle_key(KeyEvent::new(KeyCode::End, KeyModifiers::CONTROL));
+        assert_eq!(editor.position(), Position { line: 2, column: 5 });
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn end_goes_to_the_end_of_a_line_of_wide_characters() {
+        let (mut editor, path) = editor("end", "ééé\nx");
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Char('!'));
+        assert_eq!(editor.text(), "ééé!\nx");
+        press(&mut editor, KeyCode::Left);
+        press(&mut editor, KeyCode::Left);
+        press(&mut editor, KeyCode::Char('?'));
+        assert_eq!(editor.text(), "éé?é!\nx");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn find_selects_the_next_match_and_wraps() {
+        let (mut editor, path) = editor("find", "one two one");
+        ctrl(&mut editor, 'f');
+        for c in "one".chars() {
+            press(&mut editor, KeyCode::Char(c));
+        }
+        press(&mut editor, KeyCode::Enter);
+        let first = Range {
+            start: Position { line: 0, column: 0 },
+            end: Position { line: 0, column: 3 },
+        };
+        assert_eq!(editor.selection(), Some(first));
+        press(&mut editor, KeyCode::F(3));
+        assert_eq!(
+            editor.position(),
+            Position {
+                line: 0,
+                column: 11
+            }
+        );
+        press(&mut editor, KeyCode::F(3));
+        assert_eq!(editor.selection(), Some(first));
+        assert_eq!(editor.text(), "one two one");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn saving_writes_the_file_and_quit_asks_only_when_modified() {
+        let (mut editor, path) = editor("save", "text");
+        press(&mut editor, KeyCode::Char('!'));
+        ctrl(&mut editor, 'q');
+        assert!(!editor.quit);
+        ctrl(&mut editor, 's');
+        assert_eq!(fs::read_to_string(&path).unwrap(), "!text");
+        assert!(!editor.modified());
+        ctrl(&mut editor, 'q');
+        assert!(editor.quit);
+        fs::remove_file(path).unwrap();
+    }
+}
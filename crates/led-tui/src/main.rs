@@ -0,0 +1,36 @@
+mod editor;
+mod view;
+
+use editor::Editor;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::path::PathBuf;
+
+/// Edits one file in the terminal, for use over SSH or wherever there is no display.
+///
+/// Ctrl+S saves, Ctrl+F finds (F3 finds again), Ctrl+Z and Ctrl+Y undo and redo, and Ctrl+Q
+/// quits.
+fn main() -> anyhow::Result<()> {
+    let Some(path) = std::env::args_os().nth(1) else {
+        eprintln!("Usage: led-tui <file>");
+        std::process::exit(2);
+    };
+    let mut editor = Editor::open(PathBuf::from(path))?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut editor);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, editor: &mut Editor) -> anyhow::Result<()> {
+    while !editor.quit {
+        terminal.draw(|frame| view::draw(frame, editor))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            editor.handle_key(key);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::editor::Editor;
+use led_core::types::Range;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+
+/// How many cells a tab takes up at most.
+const TAB_WIDTH: usize = 4;
+
+/// Draws the lines in view with the selection highlighted, and a status line below them.
+pub fn draw(frame: &mut Frame, editor: &mut Editor) {
+    let [text_area, status_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    editor.scroll_to_cursor(text_area.height as usize);
+
+    let text = editor.text();
+    let position = editor.position();
+    let selection = editor.selection();
+    let before_caret = &text.split('\n').nth(position.line).unwrap_or_default()[..position.column];
+    let caret_x = cells(before_caret);
+    // Long lines scroll sideways so the caret stays in view
+    let width = (text_area.width as usize).max(1);
+    let left = (caret_x + 1).saturating_sub(width);
+
+    let lines: Vec<Line> = text
+        .split('\n')
+        .enumerate()
+        .skip(editor.scroll)
+        .take(text_area.height as usize)
+        .map(|(index, line)| row(index, line, selection, left))
+        .collect();
+    frame.render_widget(Paragraph::new(lines), text_area);
+
+    let status = match editor.prompt() {
+        Some(query) => format!("Find: {query}"),
+        None => format!(
+            " {}{}  {}:{}  {}",
+            editor.name(),
+            if editor.modified() { " *" } else { "" },
+            position.line + 1,
+            // Columns are bytes; people count characters
+            before_caret.chars().count() + 1,
+            editor.status,
+        ),
+    };
+    frame.render_widget(Paragraph::new(status.as_str()).reversed(), status_area);
+
+    let caret = match editor.prompt() {
+        Some(query) => Position::new(
+            status_area.x + "Find: ".len() as u16 + query.chars().count() as u16,
+            status_area.y,
+        ),
+        None => Position::new(
+            text_area.x + (caret_x - left) as u16,
+            text_area.y + (position.line - editor.scroll) as u16,
+        ),
+    };
+    frame.set_cursor_position(caret);
+}
+
+/// Lays out one line of text from cell `left` on, with tabs expanded and the selected part
+/// reversed.
+fn row(index: usize, line: &str, selection: Option<Range>, left: usize) -> Line<'static> {
+    let selected = selection.and_then(|Range { start, end }| {
+        (start.line..=end.line).contains(&index).then(|| {
+            let from = if index == start.line { start.column } else { 0 };
+            let to = if index == end.line {
+                end.column
+            } else {
+                line.len()
+            };
+            from..to
+        })
+    });
+    let mut spans = Vec::new();
+    let mut x = 0;
+    for (column, c) in line.char_indices() {
+        let (shown, next) = match c {
+            '\t' => {
+                let next = (x / TAB_WIDTH + 1) * TAB_WIDTH;
+                // A tab cut off by the left edge only shows its visible cells
+                (" ".repeat(next - x.max(left)), next)
+            }
+            c => (c.to_string(), x + 1),
+        };
+        if next > left {
+            let style = if selected
+                .as_ref()
+                .is_some_and(|range| range.contains(&column))
+            {
+                Style::new().reversed()
+            } else {
+                Style::new()
+            };
+            spans.push(Span::styled(shown, style));
+        }
+        x = next;
+    }
+    Line::from(spans)
+}
+
+/// Returns how many cells a piece of a line takes up on screen.
+fn cells(text: &str) -> usize {
+    text.chars().fold(0, |x, c| match c {
+        '\t' => (x / TAB_WIDTH + 1) * TAB_WIDTH,
+        _ => x + 1,
+    })
+}
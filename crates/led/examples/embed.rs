@@ -0,0 +1,50 @@
+//! Embeds LED's editor in another egui application: a notes window with a code pane on the
+//! right and a few controls on the left.
+//!
+//! Run it with `cargo run -p led --example embed`.
+
+use eframe::egui;
+use led::editor_view::EditorView;
+
+struct Notes {
+    editor: EditorView,
+    saved: String,
+}
+
+impl eframe::App for Notes {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("controls").show(ctx, |ui| {
+            ui.checkbox(&mut self.editor.show_line_numbers, "Line numbers");
+            ui.add(egui::Slider::new(&mut self.editor.font_size, 8.0..=32.0).text("Font size"));
+            if ui
+                .add_enabled(self.editor.is_modified(), egui::Button::new("Save"))
+                .clicked()
+            {
+                // A real application would write the text to a file here
+                self.saved = self.editor.text();
+                self.editor.mark_saved();
+            }
+            ui.label(format!("{} bytes saved", self.saved.len()));
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let response = self.editor.show(ui);
+            if response.text_changed {
+                ctx.request_repaint();
+            }
+        });
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let text = "fn main() {\n    println!(\"Hello from an embedded editor\");\n}\n";
+    eframe::run_native(
+        "Embedded LED",
+        eframe::NativeOptions::default(),
+        Box::new(|_cc| {
+            Ok(Box::new(Notes {
+                editor: EditorView::new(text),
+                saved: text.to_string(),
+            }))
+        }),
+    )
+}
@@ -0,0 +1,406 @@
+# German messages of the user interface. Messages missing here are shown in English.
+
+language-name = Deutsch
+untitled = Unbenannt
+window-title = { $name } — LED
+window-title-modified = { $name } ● — LED
+button-ok = OK
+button-cancel = Abbrechen
+button-dismiss = Schließen
+button-hide = Ausblenden
+
+## Welcome screen
+
+welcome-tagline = The Editor 4U
+welcome-new-file = Neue Datei
+welcome-open-file = Datei öffnen...
+
+## Status bar
+
+status-frame-time = Frame: { $ms } ms
+status-latency = Taste→Bild p95: { $ms } ms
+status-over-budget = über dem Budget
+status-cursor = Z. { $line }, Sp. { $column }
+status-set-mark = Marke setzen: Buchstaben eingeben (Esc bricht ab)
+status-jump-to-mark = Zu Marke springen: Buchstaben eingeben (Esc bricht ab)
+status-change-surround = Umgebendes Paar ändern: neue Klammer oder neues Anführungszeichen eingeben (Esc bricht ab)
+status-indexing = Projekt wird indiziert
+status-conflict = Konflikt { $current } von { $total }
+status-conflicts = { $total ->
+    [one] Ein Konflikt
+   *[other] { $total } Konflikte
+}
+status-bom = BOM
+status-bom-hint = Beginnt mit einer UTF-8-Bytereihenfolgemarke. Klicken, um sie zu entfernen.
+status-no-final-newline = Kein abschließender Zeilenumbruch
+status-no-final-newline-hint = Klicken, um einen Zeilenumbruch anzuhängen.
+status-plain-text = Nur Text
+
+## Menus
+
+menu-file = Datei
+menu-new = Neu
+menu-open = Öffnen
+menu-save = Speichern
+menu-close = Schließen
+menu-reload = Von Festplatte neu laden
+menu-check-changes = Geänderte Dateien formatieren und prüfen
+menu-import-settings = Einstellungen importieren…
+menu-check-for-updates = Nach Updates suchen
+menu-preferences = Einstellungen
+menu-exit = Beenden
+menu-edit = Bearbeiten
+menu-undo = Rückgängig
+menu-redo = Wiederholen
+menu-select-all = Alles auswählen
+menu-go-to-last-edit = Zur letzten Änderung
+menu-go-to-line = Gehe zu Zeile …
+menu-jump-to-bracket = Zur passenden Klammer springen
+menu-delete-line = Zeile löschen
+menu-duplicate-line = Zeile duplizieren
+menu-move-line-up = Zeile nach oben verschieben
+menu-move-line-down = Zeile nach unten verschieben
+menu-join-lines = Zeilen verbinden
+menu-toggle-comment = Zeilenkommentar umschalten
+menu-sort-lines = Zeilen sortieren
+menu-sort-lines-case-insensitive = Zeilen ohne Groß-/Kleinschreibung sortieren
+menu-sort-lines-reverse = Zeilen absteigend sortieren
+menu-transform = Umwandeln
+menu-upper-case = Großbuchstaben
+menu-lower-case = Kleinbuchstaben
+menu-title-case = Wortanfänge groß
+menu-change-surround = Umgebendes Paar ändern
+menu-delete-surround = Umgebendes Paar entfernen
+menu-evaluate-selection = Auswahl auswerten
+menu-find = Suchen
+menu-find-replace = Suchen und Ersetzen
+menu-find-word-next = Nächstes Vorkommen des Worts suchen
+menu-find-word-previous = Vorheriges Vorkommen des Worts suchen
+menu-find-in-files = In Dateien suchen
+menu-go-to-symbol = Gehe zu Symbol
+menu-rebuild-index = Projektindex neu erstellen
+menu-toggle-bom = Bytereihenfolgemarke umschalten
+menu-add-final-newline = Abschließenden Zeilenumbruch anfügen
+menu-set-mark = Marke setzen
+menu-jump-to-mark = Zu Marke springen
+menu-merge-conflicts = Merge-Konflikte
+menu-accept-ours = Unsere übernehmen
+menu-accept-theirs = Ihre übernehmen
+menu-accept-both = Beide übernehmen
+menu-next-conflict = Nächster Konflikt
+menu-view = Ansicht
+menu-command-palette = Befehlspalette
+menu-line-numbers = Zeilennummern anzeigen
+menu-notes = Notizen
+menu-marks = Marken
+menu-split-view = Geteilte Ansicht
+menu-switch-buffer = Puffer wechseln…
+switcher-hint = Puffername oder Pfad
+menu-pane = Bereich
+menu-pane-zoom-in = Vergrößern
+menu-pane-zoom-out = Verkleinern
+menu-pane-toggle-wrap = Zeilenumbruch
+menu-pane-reset = Zoom und Umbruch zurücksetzen
+menu-pane-split-right = Rechts teilen
+menu-pane-split-down = Unten teilen
+menu-pane-close = Bereich schließen
+menu-folding = Faltung
+menu-toggle-fold = Am Cursor falten oder entfalten
+menu-fold-all = Alles falten
+menu-unfold-all = Alles entfalten
+menu-zen-mode = Zen-Modus
+menu-typewriter-scrolling = Schreibmaschinen-Scrollen
+menu-soft-wrap = Weicher Zeilenumbruch
+menu-wrap-column = Umbruchspalte:
+menu-font-size = Schriftgröße:
+menu-tab-size = Tabulatorbreite:
+menu-tab-arrows = Tabulatoren als Pfeile anzeigen
+
+## Preferences
+
+prefs-language = Sprache:
+prefs-language-system = Systemsprache
+prefs-last-buffer-closed = Wenn der letzte Puffer geschlossen wird:
+prefs-confirm-quit = Beenden mit ungespeicherten Änderungen bestätigen
+prefs-selection-toolbar = Werkzeugleiste für Mausauswahl anzeigen
+prefs-selection-counts = Zähler beim Auswählen anzeigen
+prefs-bom-on-save = Bytereihenfolgemarke beim Speichern:
+prefs-evaluation-result = Ergebnis von „Auswahl auswerten“:
+prefs-date-format = Datumsformat:
+prefs-time-format = Uhrzeitformat:
+prefs-date-format-hint = Bestandteile in eckigen Klammern, etwa [day].[month].[year] oder [hour]:[minute]
+prefs-final-newline = Beim Speichern mit Zeilenumbruch abschließen
+prefs-trim-trailing-whitespace = Beim Speichern Leerzeichen am Zeilenende entfernen
+prefs-native-file-dialogs = Dateidialoge des Systems verwenden
+prefs-insert-tabs = In Dateien ohne Einrückung mit Tabulatoren einrücken
+prefs-project-index = Projekt für die Dateisuche indizieren
+prefs-show-ignored = Ignorierte Dateien abgeblendet anzeigen
+prefs-show-ignored-hint = Dateien, die .gitignore oder .ledignore ausschließen
+prefs-index-size-cap = Maximale Indexgröße (MB):
+prefs-search-result-limit = Angezeigte Suchergebnisse:
+prefs-search-max-file = Bei der Suche Dateien überspringen ab (KB):
+prefs-track-latency = Tastenlatenz messen
+prefs-track-latency-hint = Wird in der Statusleiste angezeigt und auf stderr protokolliert
+prefs-delays = Verzögerungen nach dem Tippen (ms):
+prefs-delay-highlights = Hervorhebungen
+prefs-delay-suggestions = Vorschläge
+prefs-delay-autosave = Notizen speichern
+prefs-long-line-wrap = Zeilen umbrechen, die länger sind als (Bytes):
+prefs-long-line-wrap-hint = Auch ohne weichen Umbruch; 0 bricht sie nie um
+prefs-frame-budget = Hintergrundarbeit pro Frame (ms):
+prefs-cache-ceiling = Obergrenze für Zwischenspeicher (MB):
+prefs-check-for-updates = Beim Start nach Updates suchen
+prefs-update-channel = Update-Kanal:
+prefs-release-feed = Release-Feed:
+prefs-processes = Von Skripten gestartete Programme:
+prefs-process-timeout = Zeitlimit (ms):
+prefs-process-output = Behaltene Ausgabe (KB):
+last-buffer-closed-welcome-screen = Startbildschirm anzeigen
+last-buffer-closed-scratch-buffer = Leeren Puffer öffnen
+last-buffer-closed-quit = Beenden
+bom-on-save-preserve = Unverändert lassen
+bom-on-save-add = Immer hinzufügen
+bom-on-save-remove = Immer entfernen
+evaluation-result-popup = In einem Popup anzeigen
+evaluation-result-insert = Hinter der Auswahl einfügen
+update-channel-stable = Stabil
+update-channel-nightly = Nightly
+
+## Command palette
+
+palette-title = Befehlspalette
+palette-hint = Befehl eingeben
+file-picker-open = Datei öffnen
+file-picker-save = Speichern unter
+file-picker-hint = Pfad eingeben; Tab vervollständigt
+file-picker-show-hidden = Versteckte Dateien anzeigen
+file-picker-recent = Zuletzt:
+action-file-new = Neue Datei
+action-file-open = Datei öffnen
+action-file-save = Datei speichern
+action-file-close = Puffer schließen
+action-file-reload = Datei von Festplatte neu laden
+action-file-check-changes = Geänderte Dateien formatieren und prüfen
+action-app-quit = Beenden
+action-view-toggle-notes = Notizen ein/aus
+action-view-toggle-zen = Zen-Modus ein/aus
+action-view-toggle-marks = Markenliste ein/aus
+action-view-toggle-split = Geteilte Ansicht ein/aus
+action-view-switch-buffer = Puffer wechseln
+action-pane-zoom-in = Bereich vergrößern
+action-pane-zoom-out = Bereich verkleinern
+action-pane-toggle-wrap = Zeilenumbruch im Bereich ein/aus
+action-pane-reset = Zoom und Umbruch des Bereichs zurücksetzen
+action-pane-split-right = Bereich rechts teilen
+action-pane-split-down = Bereich unten teilen
+action-pane-close = Bereich schließen
+action-pane-focus-left = Zum Bereich links wechseln
+action-pane-focus-right = Zum Bereich rechts wechseln
+action-pane-focus-up = Zum Bereich oben wechseln
+action-pane-focus-down = Zum Bereich unten wechseln
+action-view-toggle-fold = Am Cursor falten oder entfalten
+action-view-fold-all = Alles falten
+action-view-unfold-all = Alles entfalten
+action-mark-set = Marke setzen
+action-mark-jump = Zu Marke springen
+action-edit-undo = Rückgängig
+action-edit-redo = Wiederholen
+action-edit-select-all = Alles auswählen
+action-edit-goto-last-edit = Zur letzten Änderung
+action-edit-goto-line = Gehe zu Zeile/Spalte
+action-edit-jump-to-bracket = Zur passenden Klammer springen
+action-edit-delete-line = Zeile löschen
+action-edit-duplicate-line = Zeile duplizieren
+action-edit-move-line-up = Zeile nach oben verschieben
+action-edit-move-line-down = Zeile nach unten verschieben
+action-edit-join-lines = Zeilen verbinden
+action-edit-toggle-comment = Zeilenkommentar umschalten
+action-edit-sort-lines = Zeilen sortieren
+action-edit-sort-lines-case-insensitive = Zeilen ohne Groß-/Kleinschreibung sortieren
+action-edit-sort-lines-reverse = Zeilen absteigend sortieren
+action-edit-upper-case = In Großbuchstaben umwandeln
+action-edit-lower-case = In Kleinbuchstaben umwandeln
+action-edit-title-case = Wortanfänge groß schreiben
+action-edit-change-surround = Umgebendes Paar ändern
+action-edit-delete-surround = Umgebendes Paar entfernen
+action-edit-increment-number = Zahl erhöhen
+action-edit-decrement-number = Zahl verringern
+action-edit-increment-sequence = Zahlen fortlaufend erhöhen
+action-edit-evaluate-selection = Auswahl auswerten
+action-edit-insert-date = Datum einfügen
+action-edit-insert-time = Uhrzeit einfügen
+action-search-find = Suchen
+action-search-replace = Suchen und Ersetzen
+action-search-word-next = Nächstes Vorkommen des Worts suchen
+action-search-word-previous = Vorheriges Vorkommen des Worts suchen
+action-search-find-in-files = In Dateien suchen
+action-search-goto-symbol = Gehe zu Symbol im Arbeitsbereich
+action-index-rebuild = Projektindex neu erstellen
+action-app-import-settings = Einstellungen aus VS Code oder Sublime Text importieren
+action-app-check-for-updates = Nach Updates suchen
+action-app-record-input = Eingabeaufzeichnung starten oder beenden
+
+## Project search
+
+search-files-title = In Dateien suchen
+search-files-hint = Gesuchter Text
+search-symbol-title = Gehe zu Symbol im Arbeitsbereich
+search-symbol-hint = Symbolname
+search-indexing = Projekt wird indiziert…
+search-index-off = Der Projektindex ist ausgeschaltet.
+search-build-index = Index erstellen
+search-index-capped = Maximale Indexgröße erreicht: nur { $count } Dateien werden durchsucht.
+search-include-hint = Einzuschließende Dateien, z. B. src/**/*.rs
+search-exclude-hint = Auszuschließende Dateien, z. B. target/**
+search-truncated = Suche gekürzt: die ersten { $count } Ergebnisse werden angezeigt.
+find-hint = Suchen
+find-regex = Regulärer Ausdruck
+find-match-case = Groß-/Kleinschreibung
+find-whole-word = Ganzes Wort
+find-previous = Vorheriger Treffer (Umschalt+Eingabe)
+find-next = Nächster Treffer (Eingabe)
+find-position = { $index } von { $count }
+find-no-matches = Keine Treffer
+find-toggle-replace = Ersetzen
+find-replace-in-selection = Alle in Auswahl
+replace-title = Suchen und Ersetzen
+replace-find-hint = Suchen
+replace-with-hint = Ersetzen durch
+replace-in-selection = Nur in der Auswahl
+replace-matches = { $count ->
+    [one] 1 Treffer
+   *[other] { $count } Treffer
+}
+replace-regex = Regulärer Ausdruck
+replace-regex-hint = Im Ersatztext auf Gruppen mit $1 oder ${"{"}name{"}"} verweisen
+replace-preserve-case = Groß-/Kleinschreibung erhalten
+replace-preserve-case-hint = Unabhängig von Groß-/Kleinschreibung suchen und jeder Ersetzung die Schreibweise des ersetzten Texts geben: GROSS, klein oder Großgeschrieben
+replace-one = Ersetzen
+replace-skip = Überspringen
+replace-all = Alle ersetzen
+replace-done = { $count ->
+    [0] Nichts zu ersetzen
+    [one] 1 Treffer ersetzt
+   *[other] { $count } Treffer ersetzt
+}
+replace-apply = { $count ->
+    [one] 1 Änderung übernehmen
+   *[other] { $count } Änderungen übernehmen
+}
+replace-preview-truncated = Vorschau gekürzt: nur die ersten { $count } Änderungen werden angezeigt und übernommen.
+replace-files-done = { $skipped ->
+    [0] { $count ->
+        [one] 1 Datei geändert
+       *[other] { $count } Dateien geändert
+    }
+   *[other] { $count } Dateien geändert; { $skipped } wurden seit der Vorschau geändert und übersprungen
+}
+drop-hint = Dateien zum Öffnen hier ablegen
+drop-skipped = { $count ->
+    [one] 1 abgelegtes Element ist keine lesbare Datei und wurde übersprungen
+   *[other] { $count } abgelegte Elemente sind keine lesbaren Dateien und wurden übersprungen
+}
+search-wrapped = Suche am anderen Ende fortgesetzt
+search-no-word = Kein Wort unter dem Cursor
+comment-unsupported = Diese Sprache hat keine Zeilenkommentare
+case-needs-selection = Zuerst den umzuwandelnden Text auswählen
+theme-not-found = Kein Farbschema namens „{ $name }“; das Standardschema wird verwendet
+recording-started = Eingabe wird aufgezeichnet; der Befehl beendet die Aufzeichnung
+recording-saved = { $count } Eingaben in { $path } gespeichert
+goto-line-title = Gehe zu Zeile
+goto-line-hint = Zeile 1–{ $count } oder Zeile:Spalte
+goto-line-invalid = Eine Zeilennummer oder Zeile:Spalte eingeben
+goto-line-out-of-range = Die Datei hat { $count } Zeilen
+no-matching-bracket = Keine passende Klammer neben dem Cursor
+nothing-to-fold = Am Cursor gibt es nichts zu falten
+reload-unsaved = Der Puffer hat ungespeicherte Änderungen; vor dem Neuladen speichern oder schließen
+evaluate-no-selection = Wähle einen Ausdruck zum Auswerten aus
+evaluate-no-result = Die Auswahl lieferte nichts
+evaluate-result = = { $value }
+evaluate-failed = Auswertung fehlgeschlagen: { $error }
+palette-snippet = Textbaustein einfügen: { $name }
+snippet-failed = Textbaustein fehlgeschlagen: { $error }
+
+## Marks and notes
+
+marks-title = Marken
+tab-close = Schließen (Mittelklick)
+marks-empty = Keine Marken. Setze eine mit { $shortcut }.
+marks-set-mark-menu = Bearbeiten > Marke setzen
+notes-title = Notizen
+notes-unavailable = Notizen sind nicht verfügbar
+
+## Selection toolbar
+
+toolbar-copy = Kopieren
+toolbar-cut = Ausschneiden
+toolbar-comment = Kommentieren
+toolbar-search = Suchen
+toolbar-counts = { $chars ->
+    [one] 1 Zeichen
+   *[other] { $chars } Zeichen
+}, { $words ->
+    [one] 1 Wort
+   *[other] { $words } Wörter
+}
+selection-counts = { $lines ->
+    [one] 1 Zeile
+   *[other] { $lines } Zeilen
+}, { $words ->
+    [one] 1 Wort
+   *[other] { $words } Wörter
+}, { $chars ->
+    [one] 1 Zeichen
+   *[other] { $chars } Zeichen
+}
+
+## Dialogs
+
+quit-title = LED beenden?
+quit-unsaved = { $count ->
+    [one] Dieser Puffer hat ungespeicherte Änderungen:
+   *[other] Diese Puffer haben ungespeicherte Änderungen:
+}
+quit-without-saving = Ohne Speichern beenden
+close-title = Änderungen speichern?
+close-unsaved = { $name } hat ungespeicherte Änderungen.
+close-save = Speichern
+close-discard = Verwerfen
+import-title = Einstellungen importieren
+import-from = Aus:
+import-folder = Ordner:
+import-run = Importieren
+import-no-project = Kein Projekt geöffnet
+import-done = { $settings } Einstellungen und { $shortcuts } Tastenkürzel in den .led-Ordner des Projekts importiert.
+import-skipped = Nicht importiert ({ $count }):
+permission-title = Darf das Skript Programme ausführen?
+permission-body = Dieses Skript möchte Programme auf deinem Computer ausführen:
+permission-limits = Programme laufen im Projekt und werden nach { $seconds } s beendet.
+permission-remembered = Deine Antwort wird für dieses Projekt gespeichert.
+permission-allow = Erlauben
+permission-deny = Ablehnen
+settings-problems-title = Probleme in den Einstellungen
+problems-title = Probleme
+check-no-project = Kein Projekt geöffnet
+check-no-tools = Keine Formatierer oder Linter festgelegt. Trage `formatters` oder `linters` in settings.json ein.
+check-running = Geänderte Dateien werden formatiert und geprüft…
+check-done = { $problems ->
+    [0] { $formatted } von { $files } geprüften Dateien formatiert; keine Probleme gefunden
+    [one] { $formatted } von { $files } geprüften Dateien formatiert; 1 Problem gefunden
+   *[other] { $formatted } von { $files } geprüften Dateien formatiert; { $problems } Probleme gefunden
+}
+check-failed = Formatieren und Prüfen fehlgeschlagen: { $error }
+
+## Updates
+
+updates-title = Updates
+update-available-title = Update verfügbar
+update-ready-title = Update bereit
+update-no-feed = Kein Release-Feed festgelegt. Trage `update_feed_url` in settings.json ein.
+update-up-to-date = LED { $version } ist die neueste Version.
+update-available = LED { $version } ist verfügbar (installiert ist { $current }).
+update-staged = LED { $version } wurde heruntergeladen und wird beim Beenden installiert.
+update-download = Herunterladen und beim Beenden installieren
+update-manual = Lade es von der Release-Seite des Projekts herunter.
+update-later = Später
+update-downloading = Wird heruntergeladen…
@@ -0,0 +1,407 @@
+# English messages of the user interface, in a subset of the Fluent syntax
+# (see https://projectfluent.org). Every other language falls back to these.
+
+language-name = English
+untitled = Untitled
+window-title = { $name } — LED
+window-title-modified = { $name } ● — LED
+button-ok = OK
+button-cancel = Cancel
+button-dismiss = Dismiss
+button-hide = Hide
+
+## Welcome screen
+
+welcome-tagline = The Editor 4U
+welcome-new-file = New File
+welcome-open-file = Open File...
+
+## Status bar
+
+status-frame-time = Frame: { $ms }ms
+status-latency = Key→paint p95: { $ms }ms
+status-over-budget = over budget
+status-cursor = Ln { $line }, Col { $column }
+status-set-mark = Set mark: type a letter (Esc to cancel)
+status-jump-to-mark = Jump to mark: type a letter (Esc to cancel)
+status-change-surround = Change surrounding pair: type the new bracket or quote (Esc to cancel)
+status-indexing = Indexing project
+status-conflict = Conflict { $current } of { $total }
+status-conflicts = { $total ->
+    [one] One conflict
+   *[other] { $total } conflicts
+}
+status-bom = BOM
+status-bom-hint = Starts with a UTF-8 byte order mark. Click to remove it.
+status-no-final-newline = No final newline
+status-no-final-newline-hint = Click to append a line break.
+status-plain-text = Plain Text
+
+## Menus
+
+menu-file = File
+menu-new = New
+menu-open = Open
+menu-save = Save
+menu-close = Close
+menu-reload = Reload from Disk
+menu-check-changes = Format and Lint Changed Files
+menu-import-settings = Import Settings…
+menu-check-for-updates = Check for Updates
+menu-preferences = Preferences
+menu-exit = Exit
+menu-edit = Edit
+menu-undo = Undo
+menu-redo = Redo
+menu-select-all = Select All
+menu-go-to-last-edit = Go to Last Edit
+menu-go-to-line = Go to Line…
+menu-jump-to-bracket = Jump to Matching Bracket
+menu-delete-line = Delete Line
+menu-duplicate-line = Duplicate Line
+menu-move-line-up = Move Line Up
+menu-move-line-down = Move Line Down
+menu-join-lines = Join Lines
+menu-toggle-comment = Toggle Line Comment
+menu-sort-lines = Sort Lines
+menu-sort-lines-case-insensitive = Sort Lines, Ignoring Case
+menu-sort-lines-reverse = Sort Lines in Reverse
+menu-transform = Transform
+menu-upper-case = Upper Case
+menu-lower-case = Lower Case
+menu-title-case = Title Case
+menu-change-surround = Change Surrounding Pair
+menu-delete-surround = Delete Surrounding Pair
+menu-evaluate-selection = Evaluate Selection
+menu-find = Find
+menu-find-replace = Find and Replace
+menu-find-word-next = Find Next Occurrence of Word
+menu-find-word-previous = Find Previous Occurrence of Word
+menu-find-in-files = Find in Files
+menu-go-to-symbol = Go to Symbol
+menu-rebuild-index = Rebuild Project Index
+menu-toggle-bom = Toggle Byte Order Mark
+menu-add-final-newline = Add Final Newline
+menu-set-mark = Set Mark
+menu-jump-to-mark = Jump to Mark
+menu-merge-conflicts = Merge Conflicts
+menu-accept-ours = Accept Ours
+menu-accept-theirs = Accept Theirs
+menu-accept-both = Accept Both
+menu-next-conflict = Next Conflict
+menu-view = View
+menu-command-palette = Command Palette
+menu-line-numbers = Show Line Numbers
+menu-notes = Notes
+menu-marks = Marks
+menu-split-view = Split View
+menu-switch-buffer = Switch Buffer…
+switcher-hint = Buffer name or path
+menu-pane = Pane
+menu-pane-zoom-in = Zoom In
+menu-pane-zoom-out = Zoom Out
+menu-pane-toggle-wrap = Soft Wrap
+menu-pane-reset = Reset Zoom and Wrap
+menu-pane-split-right = Split Right
+menu-pane-split-down = Split Down
+menu-pane-close = Close Pane
+menu-folding = Folding
+menu-toggle-fold = Fold or Unfold at Caret
+menu-fold-all = Fold All
+menu-unfold-all = Unfold All
+menu-zen-mode = Zen Mode
+menu-typewriter-scrolling = Typewriter Scrolling
+menu-soft-wrap = Soft Wrap
+menu-wrap-column = Wrap Column:
+menu-font-size = Font Size:
+menu-tab-size = Tab Size:
+menu-tab-arrows = Show Tabs as Arrows
+
+## Preferences
+
+prefs-language = Language:
+prefs-language-system = System default
+prefs-last-buffer-closed = When the last buffer is closed:
+prefs-confirm-quit = Confirm quit with unsaved changes
+prefs-selection-toolbar = Show toolbar for pointer selections
+prefs-selection-counts = Show counts while selecting
+prefs-bom-on-save = Byte order mark on save:
+prefs-evaluation-result = Result of Evaluate Selection:
+prefs-date-format = Date format:
+prefs-time-format = Time format:
+prefs-date-format-hint = Components in square brackets, such as [year]-[month]-[day] or [hour repr:12]:[minute] [period]
+prefs-final-newline = Ensure final newline on save
+prefs-trim-trailing-whitespace = Trim trailing whitespace on save
+prefs-native-file-dialogs = Use the system's file dialogs
+prefs-insert-tabs = Indent with tabs in files without indentation
+prefs-project-index = Index project for find in files
+prefs-show-ignored = Show ignored files dimmed
+prefs-show-ignored-hint = Files excluded by .gitignore or .ledignore
+prefs-index-size-cap = Index size cap (MB):
+prefs-search-result-limit = Search results listed:
+prefs-search-max-file = Skip files in searches larger than (KB):
+prefs-track-latency = Measure keystroke latency
+prefs-track-latency-hint = Shown in the status bar and logged to stderr
+prefs-delays = Delays after typing (ms):
+prefs-delay-highlights = Highlights
+prefs-delay-suggestions = Suggestions
+prefs-delay-autosave = Notes autosave
+prefs-long-line-wrap = Wrap lines longer than (bytes):
+prefs-long-line-wrap-hint = Even when soft wrap is off; 0 never wraps them
+prefs-frame-budget = Background work per frame (ms):
+prefs-cache-ceiling = Cache memory ceiling (MB):
+prefs-check-for-updates = Check for updates at startup
+prefs-update-channel = Update channel:
+prefs-release-feed = Release feed:
+prefs-processes = Programs run by scripts:
+prefs-process-timeout = Time limit (ms):
+prefs-process-output = Output kept (KB):
+last-buffer-closed-welcome-screen = Show welcome screen
+last-buffer-closed-scratch-buffer = Open scratch buffer
+last-buffer-closed-quit = Quit
+bom-on-save-preserve = Keep as is
+bom-on-save-add = Always add
+bom-on-save-remove = Always remove
+evaluation-result-popup = Show in a popup
+evaluation-result-insert = Insert after the selection
+update-channel-stable = Stable
+update-channel-nightly = Nightly
+
+## Command palette
+
+palette-title = Command Palette
+palette-hint = Type a command
+file-picker-open = Open File
+file-picker-save = Save As
+file-picker-hint = Type a path; Tab completes it
+file-picker-show-hidden = Show hidden files
+file-picker-recent = Recent:
+action-file-new = New File
+action-file-open = Open File
+action-file-save = Save File
+action-file-close = Close Buffer
+action-file-reload = Reload File from Disk
+action-file-check-changes = Format and Lint Changed Files
+action-app-quit = Quit
+action-view-toggle-notes = Toggle Notes
+action-view-toggle-zen = Toggle Zen Mode
+action-view-toggle-marks = Toggle Marks Panel
+action-view-toggle-split = Toggle Split View
+action-view-switch-buffer = Switch Buffer
+action-pane-zoom-in = Zoom In Pane
+action-pane-zoom-out = Zoom Out Pane
+action-pane-toggle-wrap = Toggle Soft Wrap in Pane
+action-pane-reset = Reset Pane Zoom and Wrap
+action-pane-split-right = Split Pane Right
+action-pane-split-down = Split Pane Down
+action-pane-close = Close Pane
+action-pane-focus-left = Focus Pane to the Left
+action-pane-focus-right = Focus Pane to the Right
+action-pane-focus-up = Focus Pane Above
+action-pane-focus-down = Focus Pane Below
+action-view-toggle-fold = Fold or Unfold at Caret
+action-view-fold-all = Fold All
+action-view-unfold-all = Unfold All
+action-mark-set = Set Mark
+action-mark-jump = Jump to Mark
+action-edit-undo = Undo
+action-edit-redo = Redo
+action-edit-select-all = Select All
+action-edit-goto-last-edit = Go to Last Edit
+action-edit-goto-line = Go to Line/Column
+action-edit-jump-to-bracket = Jump to Matching Bracket
+action-edit-delete-line = Delete Line
+action-edit-duplicate-line = Duplicate Line
+action-edit-move-line-up = Move Line Up
+action-edit-move-line-down = Move Line Down
+action-edit-join-lines = Join Lines
+action-edit-toggle-comment = Toggle Line Comment
+action-edit-sort-lines = Sort Lines
+action-edit-sort-lines-case-insensitive = Sort Lines, Ignoring Case
+action-edit-sort-lines-reverse = Sort Lines in Reverse
+action-edit-upper-case = Transform to Upper Case
+action-edit-lower-case = Transform to Lower Case
+action-edit-title-case = Transform to Title Case
+action-edit-change-surround = Change Surrounding Pair
+action-edit-delete-surround = Delete Surrounding Pair
+action-edit-increment-number = Increment Number
+action-edit-decrement-number = Decrement Number
+action-edit-increment-sequence = Increment Numbers as Sequence
+action-edit-evaluate-selection = Evaluate Selection
+action-edit-insert-date = Insert Date
+action-edit-insert-time = Insert Time
+action-search-find = Find
+action-search-replace = Find and Replace
+action-search-word-next = Find Next Occurrence of Word
+action-search-word-previous = Find Previous Occurrence of Word
+action-search-find-in-files = Find in Files
+action-search-goto-symbol = Go to Symbol in Workspace
+action-index-rebuild = Rebuild Project Index
+action-app-import-settings = Import Settings from VS Code or Sublime Text
+action-app-check-for-updates = Check for Updates
+action-app-record-input = Start or Stop Recording Input
+
+## Project search
+
+search-files-title = Find in Files
+search-files-hint = Text to find
+search-symbol-title = Go to Symbol in Workspace
+search-symbol-hint = Symbol name
+search-indexing = Indexing project…
+search-index-off = The project index is off.
+search-build-index = Build Index
+search-index-capped = Index size cap reached: only { $count } files are searched.
+search-include-hint = Files to include, e.g. src/**/*.rs
+search-exclude-hint = Files to exclude, e.g. target/**
+search-truncated = Search was truncated: showing the first { $count } results.
+find-hint = Find
+find-regex = Regular expression
+find-match-case = Match case
+find-whole-word = Whole word
+find-previous = Previous match (Shift+Enter)
+find-next = Next match (Enter)
+find-position = { $index } of { $count }
+find-no-matches = No matches
+find-toggle-replace = Replace
+find-replace-in-selection = All in Selection
+replace-title = Find and Replace
+replace-find-hint = Find
+replace-with-hint = Replace with
+replace-in-selection = In selection only
+replace-matches = { $count ->
+    [one] 1 match
+   *[other] { $count } matches
+}
+replace-regex = Regular expression
+replace-regex-hint = Refer to capture groups in the replacement as $1 or ${"{"}name{"}"}
+replace-preserve-case = Preserve case
+replace-preserve-case-hint = Match regardless of case, and give each replacement the case of the text it replaces: UPPER, lower or Capitalized
+replace-one = Replace
+replace-skip = Skip
+replace-all = Replace All
+replace-done = { $count ->
+    [0] Nothing to replace
+    [one] Replaced 1 match
+   *[other] Replaced { $count } matches
+}
+replace-apply = { $count ->
+    [one] Apply 1 change
+   *[other] Apply { $count } changes
+}
+replace-preview-truncated = Preview was truncated: only the first { $count } changes are listed and made.
+replace-files-done = { $skipped ->
+    [0] { $count ->
+        [one] Changed 1 file
+       *[other] Changed { $count } files
+    }
+   *[other] Changed { $count } files; { $skipped } changed since the preview and were left alone
+}
+drop-hint = Drop files to open them
+drop-skipped = { $count ->
+    [one] 1 dropped item is not a readable file and was skipped
+   *[other] { $count } dropped items are not readable files and were skipped
+}
+search-wrapped = Search wrapped
+search-no-word = No word under the cursor
+comment-unsupported = This language has no line comments
+case-needs-selection = Select the text to convert first
+theme-not-found = No theme named “{ $name }”; using the default theme
+recording-started = Recording input; run the command again to stop
+recording-saved = Saved { $count } input events to { $path }
+goto-line-title = Go to Line
+goto-line-hint = Line 1–{ $count }, or line:column
+goto-line-invalid = Type a line number, or line:column
+goto-line-out-of-range = The file has { $count } lines
+no-matching-bracket = No matching bracket next to the caret
+nothing-to-fold = Nothing to fold at the caret
+reload-unsaved = The buffer has unsaved changes; save or close it before reloading
+evaluate-no-selection = Select an expression to evaluate
+evaluate-no-result = The selection returned nothing
+evaluate-result = = { $value }
+evaluate-failed = Evaluation failed: { $error }
+palette-snippet = Insert Snippet: { $name }
+snippet-failed = Snippet failed: { $error }
+
+## Marks and notes
+
+marks-title = Marks
+tab-close = Close (middle-click)
+marks-empty = No marks. Set one with { $shortcut }.
+marks-set-mark-menu = Edit > Set Mark
+notes-title = Notes
+notes-unavailable = Notes are unavailable
+
+## Selection toolbar
+
+toolbar-copy = Copy
+toolbar-cut = Cut
+toolbar-comment = Comment
+toolbar-search = Search
+toolbar-counts = { $chars ->
+    [one] 1 char
+   *[other] { $chars } chars
+}, { $words ->
+    [one] 1 word
+   *[other] { $words } words
+}
+selection-counts = { $lines ->
+    [one] 1 line
+   *[other] { $lines } lines
+}, { $words ->
+    [one] 1 word
+   *[other] { $words } words
+}, { $chars ->
+    [one] 1 char
+   *[other] { $chars } chars
+}
+
+## Dialogs
+
+quit-title = Quit LED?
+quit-unsaved = { $count ->
+    [one] The following buffer has unsaved changes:
+   *[other] The following buffers have unsaved changes:
+}
+quit-without-saving = Quit Without Saving
+close-title = Save Changes?
+close-unsaved = { $name } has unsaved changes.
+close-save = Save
+close-discard = Discard
+import-title = Import Settings
+import-from = From:
+import-folder = Folder:
+import-run = Import
+import-no-project = No project is open
+import-done = Imported { $settings } settings and { $shortcuts } shortcuts into the project's .led folder.
+import-skipped = Not imported ({ $count }):
+permission-title = Allow Script to Run Programs?
+permission-body = This script wants to run programs on your computer:
+permission-limits = Programs run inside the project and are stopped after { $seconds } s.
+permission-remembered = Your answer is remembered for this project.
+permission-allow = Allow
+permission-deny = Deny
+settings-problems-title = Settings Problems
+problems-title = Problems
+check-no-project = No project is open
+check-no-tools = No formatters or linters are set. Add `formatters` or `linters` to settings.json.
+check-running = Formatting and linting changed files…
+check-done = { $problems ->
+    [0] Formatted { $formatted } of { $files } checked files; no problems found
+    [one] Formatted { $formatted } of { $files } checked files; found 1 problem
+   *[other] Formatted { $formatted } of { $files } checked files; found { $problems } problems
+}
+check-failed = Format and lint failed: { $error }
+
+## Updates
+
+updates-title = Updates
+update-available-title = Update Available
+update-ready-title = Update Ready
+update-no-feed = No release feed is set. Add `update_feed_url` to settings.json.
+update-up-to-date = LED { $version } is the newest version.
+update-available = LED { $version } is available (you have { $current }).
+update-staged = LED { $version } has been downloaded and will be installed when you quit.
+update-download = Download and Install on Quit
+update-manual = Download it from the project's releases page.
+update-later = Later
+update-downloading = Downloading…
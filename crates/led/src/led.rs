@@ -1,10 +1,52 @@
-pub mod buffer;
-pub mod commands;
-pub mod cursor;
-pub mod piece_table;
+pub use led_core::{
+    buffer, commands, cursor, delta, diff, edits, folds, hash, marks, piece_table, save, search,
+    snapshot, types, util, view, virtual_text,
+};
 
-pub use piece_table::piece;
+pub mod batch;
+pub mod bidi;
+pub mod brackets;
+pub mod cache;
+pub mod case;
+pub mod check;
+pub mod comment;
+pub mod conflict;
+pub mod debounce;
+pub mod dirs;
+pub mod editor_view;
+pub mod file_format;
+pub mod file_picker;
+pub mod goto;
+pub mod i18n;
+pub mod idle;
+pub mod ignore_rules;
+pub mod import;
+pub mod indent;
+pub mod index;
+pub mod input;
+pub mod keymap;
+pub mod latency;
+pub mod launch;
+pub mod layout;
 pub mod lua;
+pub mod notes;
+pub mod number;
+pub mod palette;
+pub mod project;
+pub mod prose;
+pub mod render;
+pub mod replace;
+pub mod sandbox;
+pub mod scheduler;
+pub mod session;
+pub mod settings;
+pub mod shell;
+pub mod snippet;
+pub mod surround;
+pub mod switcher;
+pub mod tab_bar;
+pub mod tabs;
+pub mod testing;
 pub mod txt;
-pub mod types;
-pub mod util;
+pub mod update;
+pub mod word;
@@ -0,0 +1,238 @@
+use anyhow::{Context, Result as AnyResult, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::buffer::editor::State;
+use super::commands::editor::Command;
+use super::lua::{ProcessPolicy, Runtime};
+use super::settings::{self, Settings};
+use super::{dirs, file_format, project, sandbox, save};
+
+/// Command-line flag that runs a Lua script over files instead of opening the editor, as in
+/// `led --batch script.lua file…`.
+pub const BATCH_FLAG: &str = "--batch";
+
+/// Exit status when a file could not be read, edited by the script or saved.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status when the command line or the script can't be used at all.
+pub const EXIT_USAGE: i32 = 2;
+
+/// A batch run asked for on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// The Lua script run on each file.
+    pub script: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// Reads a batch run from the command line: [`BATCH_FLAG`], then the script, then the files.
+///
+/// # Arguments
+///
+/// * `args` - The command-line arguments, without the program name.
+///
+/// # Returns
+///
+/// `None` if [`BATCH_FLAG`] wasn't passed, so the editor opens as usual.
+///
+/// # Errors
+///
+/// Returns an error if the script or the files are missing.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Option<AnyResult<Invocation>> {
+    let mut args = args.into_iter().filter(|arg| arg != dirs::PORTABLE_FLAG);
+    args.by_ref().find(|arg| arg == BATCH_FLAG)?;
+    let Some(script) = args.next() else {
+        return Some(Err(anyhow!("{} needs a Lua script", BATCH_FLAG)));
+    };
+    let files: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    if files.is_empty() {
+        return Some(Err(anyhow!(
+            "{} needs files to run {} on",
+            BATCH_FLAG,
+            script
+        )));
+    }
+    Some(Ok(Invocation {
+        script: script.into(),
+        files,
+    }))
+}
+
+/// Runs a script on each file of a batch run without opening a window, and saves the files
+/// the script changed.
+///
+/// Each file is loaded into a buffer of its own and the script runs once for it, reading and
+/// editing it through `kup.buffer`. Files are saved as the editor saves them, following the
+/// settings of the project in the working directory. A file the script fails on is left
+/// untouched and the run goes on with the next. Problems are reported on standard error.
+///
+/// # Returns
+///
+/// The process exit status: 0 if every file was processed, [`EXIT_FAILURE`] if any failed and
+/// [`EXIT_USAGE`] if the script couldn't be run at all.
+pub fn run(invocation: &Invocation) -> i32 {
+    let script = match fs::read_to_string(&invocation.script) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", invocation.script.display(), e);
+            return EXIT_USAGE;
+        }
+    };
+    let root = std::env::current_dir().ok();
+    let settings = load_settings(root.as_deref());
+    let mut runtime = match Runtime::new().and_then(|mut runtime| {
+        runtime.load_default_config()?;
+        Ok(runtime)
+    }) {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start the Lua runtime: {:#}", e);
+            return EXIT_USAGE;
+        }
+    };
+    if let Some(root) = root {
+        // Scripts can only run the programs the user already allowed in the editor
+        let permissions = sandbox::Permissions::load(&sandbox::permissions_path(&root))
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load process permissions: {:#}", e);
+                sandbox::Permissions::default()
+            });
+        runtime.set_process_policy(ProcessPolicy {
+            project_root: root,
+            limits: sandbox::Limits {
+                timeout: std::time::Duration::from_millis(settings.process_timeout_ms),
+                max_output: settings.process_output_kb as usize * 1024,
+            },
+            permissions,
+        });
+    }
+
+    let name = invocation.script.to_string_lossy();
+    let mut status = 0;
+    for file in &invocation.files {
+        if let Err(e) = process(&mut runtime, &name, &script, file, &settings) {
+            eprintln!("{}: {:#}", file.display(), e);
+            status = EXIT_FAILURE;
+        }
+    }
+    status
+}
+
+/// Loads the global and project settings files, as the editor does at startup.
+fn load_settings(root: Option<&Path>) -> Settings {
+    let mut files: Vec<PathBuf> = settings::global_settings_path().into_iter().collect();
+    files.extend(root.map(settings::project_settings_path));
+    let detected = root.map(project::detected_settings).unwrap_or_default();
+    let (settings, problems) = Settings::load(detected, &files);
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    settings
+}
+
+/// Runs the script on one file and saves it if the script changed it.
+///
+/// # Returns
+///
+/// Whether the file was saved.
+fn process(
+    runtime: &mut Runtime,
+    name: &str,
+    script: &str,
+    file: &Path,
+    settings: &Settings,
+) -> AnyResult<bool> {
+    let original = fs::read_to_string(file).context("failed to read the file")?;
+    let mut state = State::new();
+    let buffer_id = state.create_buffer(original.clone());
+    state.set_file_path(buffer_id, file.to_string_lossy().to_string());
+    runtime.run_batch_script(name, script, &mut state, buffer_id)?;
+
+    let text = state.get_buffer_text(buffer_id).unwrap_or_default();
+    if text == original {
+        return Ok(false);
+    }
+    let commands = file_format::prepare_for_save(buffer_id, &text, settings);
+    state.execute_command(Command::Batch(commands))?;
+    let content = state.get_buffer_text(buffer_id).unwrap_or_default();
+    save::write_atomically(file, content).context("failed to save the file")?;
+    Ok(true)
+}
+
+/// Returns the byte range of a line's text in a buffer's text, without its line break.
+///
+/// # Arguments
+///
+/// * `line` - The line, counted from 1 as scripts count them.
+pub fn line_range(text: &str, line: usize) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for (number, content) in text.split_inclusive('\n').enumerate() {
+        if number + 1 == line {
+            let content = content.trim_end_matches('\n').trim_end_matches('\r');
+            return Some((start, start + content.len()));
+        }
+        start += content.len();
+    }
+    // The empty line after a final line break, or of an empty text
+    (line == text.split_inclusive('\n').count() + 1 && (text.is_empty() || text.ends_with('\n')))
+        .then_some((text.len(), text.len()))
+}
+
+/// Returns the byte offset of a column in a buffer's text, or `None` past the end of its line.
+///
+/// # Arguments
+///
+/// * `line` - The line, counted from 1.
+/// * `column` - The character in the line, counted from 1; one past the last character is the
+///   end of the line.
+pub fn column_offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let (start, end) = line_range(text, line)?;
+    let column = column.checked_sub(1)?;
+    text[start..end]
+        .char_indices()
+        .map(|(index, _)| start + index)
+        .chain(std::iter::once(end))
+        .nth(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn batch_runs_are_read_from_the_command_line() {
+        assert!(parse(args("--portable")).is_none());
+        assert_eq!(
+            parse(args("--portable --batch fix.lua a.txt b.txt"))
+                .unwrap()
+                .unwrap(),
+            Invocation {
+                script: PathBuf::from("fix.lua"),
+                files: vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+            }
+        );
+        assert!(parse(args("--batch")).unwrap().is_err());
+        assert!(parse(args("--batch fix.lua")).unwrap().is_err());
+    }
+
+    #[test]
+    fn lines_and_columns_are_found_in_the_text() {
+        let text = "one\r\nzwö\n";
+        assert_eq!(line_range(text, 1), Some((0, 3)));
+        assert_eq!(line_range(text, 2), Some((5, 9)));
+        assert_eq!(line_range(text, 3), Some((10, 10)));
+        assert_eq!(line_range(text, 4), None);
+        assert_eq!(line_range("", 1), Some((0, 0)));
+        assert_eq!(line_range("no break", 2), None);
+
+        assert_eq!(column_offset(text, 2, 1), Some(5));
+        assert_eq!(column_offset(text, 2, 3), Some(7));
+        assert_eq!(column_offset(text, 2, 4), Some(9));
+        assert_eq!(column_offset(text, 2, 5), None);
+        assert_eq!(column_offset(text, 2, 0), None);
+    }
+}
@@ -0,0 +1,530 @@
+use std::ops::Range;
+
+/// The direction a paragraph of text runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// The bidirectional character types of the Unicode Bidirectional Algorithm (UAX #9) that
+/// matter within a single line without explicit embeddings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// Left-to-right letters.
+    L,
+    /// Right-to-left letters, such as Hebrew.
+    R,
+    /// Arabic letters.
+    Al,
+    /// European digits.
+    En,
+    /// Number separators: plus and minus.
+    Es,
+    /// Number terminators, such as currency and percent signs.
+    Et,
+    /// Arabic-Indic digits.
+    An,
+    /// Common separators, such as commas and colons.
+    Cs,
+    /// Combining marks, which take the type of the character they attach to.
+    Nsm,
+    /// Whitespace and tabs.
+    Ws,
+    /// Other neutrals: punctuation and symbols.
+    On,
+}
+
+/// Returns the bidirectional type of a character, approximated by script ranges.
+fn class(c: char) -> Class {
+    match c {
+        '0'..='9' | '\u{06f0}'..='\u{06f9}' => Class::En,
+        '+' | '-' => Class::Es,
+        '#' | '$' | '%' | '°' | '\u{a2}'..='\u{a5}' | '\u{20a0}'..='\u{20cf}' => Class::Et,
+        ',' | '.' | '/' | ':' | '\u{a0}' => Class::Cs,
+        ' ' | '\t' | '\u{2000}'..='\u{200a}' | '\u{3000}' => Class::Ws,
+        '\u{0300}'..='\u{036f}'
+        | '\u{0591}'..='\u{05bd}'
+        | '\u{05bf}'
+        | '\u{05c1}'..='\u{05c2}'
+        | '\u{05c4}'..='\u{05c5}'
+        | '\u{05c7}'
+        | '\u{0610}'..='\u{061a}'
+        | '\u{064b}'..='\u{065f}'
+        | '\u{0670}'
+        | '\u{06d6}'..='\u{06dc}'
+        | '\u{06df}'..='\u{06e4}'
+        | '\u{06e7}'..='\u{06e8}'
+        | '\u{06ea}'..='\u{06ed}' => Class::Nsm,
+        '\u{0660}'..='\u{0669}' | '\u{066b}'..='\u{066c}' => Class::An,
+        // Right-to-left mark
+        '\u{200f}'
+        | '\u{0590}'..='\u{05ff}'
+        | '\u{07c0}'..='\u{085f}'
+        | '\u{fb1d}'..='\u{fb4f}'
+        | '\u{10800}'..='\u{10fff}'
+        | '\u{1e800}'..='\u{1efff}' => Class::R,
+        '\u{0600}'..='\u{07bf}'
+        | '\u{0860}'..='\u{08ff}'
+        | '\u{fb50}'..='\u{fdff}'
+        | '\u{fe70}'..='\u{feff}' => Class::Al,
+        // Left-to-right mark
+        '\u{200e}' => Class::L,
+        c if c.is_alphanumeric() => Class::L,
+        _ => Class::On,
+    }
+}
+
+/// Returns whether text contains right-to-left letters, and so needs reordering for display.
+///
+/// Lines without any are shown in logical order, which is also their visual order.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(class(c), Class::R | Class::Al))
+}
+
+/// Returns the direction of a paragraph: that of its first strong letter, or left-to-right if
+/// it has none.
+pub fn paragraph_direction(text: &str) -> Direction {
+    text.chars()
+        .find_map(|c| match class(c) {
+            Class::L => Some(Direction::LeftToRight),
+            Class::R | Class::Al => Some(Direction::RightToLeft),
+            _ => None,
+        })
+        .unwrap_or(Direction::LeftToRight)
+}
+
+/// Resolves the embedding level of each character of a paragraph (rules W1–W7, N1–N2 and
+/// I1–I2 of UAX #9).
+fn resolve_levels(classes: &[Class], base: u8) -> Vec<u8> {
+    let n = classes.len();
+    let outer = if base % 2 == 1 { Class::R } else { Class::L };
+    let mut types = classes.to_vec();
+
+    // W1: combining marks take the type of the preceding character
+    for i in 0..n {
+        if types[i] == Class::Nsm {
+            types[i] = if i == 0 { outer } else { types[i - 1] };
+        }
+    }
+    // W2: European digits after Arabic letters are Arabic digits
+    let mut last_strong = outer;
+    for t in types.iter_mut() {
+        match *t {
+            Class::L | Class::R | Class::Al => last_strong = *t,
+            Class::En if last_strong == Class::Al => *t = Class::An,
+            _ => {}
+        }
+    }
+    // W3: Arabic letters are right-to-left letters from here on
+    for t in types.iter_mut().filter(|t| **t == Class::Al) {
+        *t = Class::R;
+    }
+    // W4: a single separator between two numbers of the same kind joins them
+    for i in 1..n.saturating_sub(1) {
+        let (before, after) = (types[i - 1], types[i + 1]);
+        if before == after
+            && ((before == Class::En && matches!(types[i], Class::Es | Class::Cs))
+                || (before == Class::An && types[i] == Class::Cs))
+        {
+            types[i] = before;
+        }
+    }
+    // W5: terminators next to European digits belong to the number
+    let mut i = 0;
+    while i < n {
+        if types[i] != Class::Et {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && types[i] == Class::Et {
+            i += 1;
+        }
+        if (start > 0 && types[start - 1] == Class::En) || (i < n && types[i] == Class::En) {
+            types[start..i].fill(Class::En);
+        }
+    }
+    // W6: remaining separators and terminators are neutral
+    for t in types.iter_mut() {
+        if matches!(*t, Class::Es | Class::Et | Class::Cs) {
+            *t = Class::On;
+        }
+    }
+    // W7: European digits in left-to-right text are left-to-right
+    let mut last_strong = outer;
+    for t in types.iter_mut() {
+        match *t {
+            Class::L | Class::R => last_strong = *t,
+            Class::En if last_strong == Class::L => *t = Class::L,
+            _ => {}
+        }
+    }
+    // N1, N2: neutrals between text of the same direction take it, others the paragraph's
+    let strong = |t: Class| match t {
+        Class::L => Some(Class::L),
+        Class::R | Class::En | Class::An => Some(Class::R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < n {
+        if strong(types[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && strong(types[i]).is_none() {
+            i += 1;
+        }
+        let before = start
+            .checked_sub(1)
+            .and_then(|j| strong(types[j]))
+            .unwrap_or(outer);
+        let after = types.get(i).and_then(|t| strong(*t)).unwrap_or(outer);
+        types[start..i].fill(if before == after { before } else { outer });
+    }
+    // I1, I2: implicit levels
+    types
+        .iter()
+        .map(|t| match (base % 2 == 1, t) {
+            (false, Class::R) => base + 1,
+            (false, Class::En | Class::An) => base + 2,
+            (true, Class::L | Class::En | Class::An) => base + 1,
+            _ => base,
+        })
+        .collect()
+}
+
+/// Returns the character shown for a character in right-to-left text, where brackets face the
+/// other way (rule L4).
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
+
+/// The visual order of a line of text that mixes directions.
+///
+/// Columns are byte offsets within the line, as in [`super::types::Position`]; visual
+/// positions count characters from the left. Every character takes one cell, except tabs,
+/// which extend to the next tab stop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    direction: Direction,
+    /// The characters, in logical order.
+    chars: Vec<char>,
+    /// The column of each character, and the length of the line.
+    columns: Vec<usize>,
+    /// The resolved embedding level of each character; odd levels run right to left.
+    levels: Vec<u8>,
+    /// The logical index of the character shown at each visual position.
+    visual: Vec<usize>,
+    /// The visual position of each character.
+    positions: Vec<usize>,
+    /// The cell at the left edge of each visual position, and the width of the line.
+    edges: Vec<usize>,
+}
+
+impl Line {
+    /// Orders a line of a paragraph for display.
+    ///
+    /// # Arguments
+    ///
+    /// * `paragraph` - The whole paragraph, which decides the direction and the levels.
+    /// * `range` - The byte range of the line within the paragraph, such as one soft-wrapped
+    ///   row of it.
+    /// * `tab_size` - The distance between tab stops, in cells.
+    pub fn new(paragraph: &str, range: Range<usize>, tab_size: usize) -> Self {
+        let direction = paragraph_direction(paragraph);
+        let base = match direction {
+            Direction::LeftToRight => 0,
+            Direction::RightToLeft => 1,
+        };
+        let classes: Vec<Class> = paragraph.chars().map(class).collect();
+        let all_levels = resolve_levels(&classes, base);
+
+        let mut chars = Vec::new();
+        let mut columns = Vec::new();
+        let mut levels = Vec::new();
+        let mut line_classes = Vec::new();
+        for (i, (offset, c)) in paragraph.char_indices().enumerate() {
+            if range.contains(&offset) {
+                chars.push(c);
+                columns.push(offset - range.start);
+                levels.push(all_levels[i]);
+                line_classes.push(classes[i]);
+            }
+        }
+        columns.push(range.len());
+
+        // L1: tabs, whitespace before them and at the end of the line go back to the
+        // paragraph level
+        let mut trailing = true;
+        for i in (0..chars.len()).rev() {
+            if chars[i] == '\t' {
+                levels[i] = base;
+                trailing = true;
+            } else if trailing && line_classes[i] == Class::Ws {
+                levels[i] = base;
+            } else {
+                trailing = false;
+            }
+        }
+
+        // L2: from the highest level down to the lowest odd one, reverse every run at that
+        // level or above
+        let mut visual: Vec<usize> = (0..chars.len()).collect();
+        let highest = levels.iter().copied().max().unwrap_or(0);
+        let lowest_odd = levels.iter().copied().filter(|l| l % 2 == 1).min();
+        if let Some(lowest_odd) = lowest_odd {
+            for level in (lowest_odd..=highest).rev() {
+                let mut i = 0;
+                while i < visual.len() {
+                    if levels[visual[i]] < level {
+                        i += 1;
+                        continue;
+                    }
+                    let start = i;
+                    while i < visual.len() && levels[visual[i]] >= level {
+                        i += 1;
+                    }
+                    visual[start..i].reverse();
+                }
+            }
+        }
+        let mut positions = vec![0; chars.len()];
+        for (position, &index) in visual.iter().enumerate() {
+            positions[index] = position;
+        }
+        let mut edges = vec![0];
+        for &index in &visual {
+            let left = edges[edges.len() - 1];
+            edges.push(
+                left + if chars[index] == '\t' {
+                    super::tabs::tab_width(left, tab_size)
+                } else {
+                    1
+                },
+            );
+        }
+
+        Self {
+            direction,
+            chars,
+            columns,
+            levels,
+            visual,
+            positions,
+            edges,
+        }
+    }
+
+    /// Returns the direction of the paragraph the line belongs to.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the characters to draw, from left to right, with the cell each is drawn at and
+    /// the column it comes from.
+    ///
+    /// Brackets in right-to-left text are mirrored.
+    pub fn glyphs(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.visual.iter().zip(&self.edges).map(|(&i, &cell)| {
+            let c = self.chars[i];
+            let c = if self.levels[i] % 2 == 1 {
+                mirror(c)
+            } else {
+                c
+            };
+            (cell, self.columns[i], c)
+        })
+    }
+
+    /// Returns the width of the line, in cells.
+    pub fn width(&self) -> usize {
+        self.edges[self.edges.len() - 1]
+    }
+
+    /// Returns the logical index of the character at or after a column.
+    fn index(&self, column: usize) -> usize {
+        self.columns.partition_point(|c| *c < column)
+    }
+
+    fn is_rtl(&self, index: usize) -> bool {
+        self.levels[index] % 2 == 1
+    }
+
+    /// Returns the visual position of the caret for a logical index.
+    fn caret_of(&self, index: usize) -> usize {
+        if let Some(&position) = self.positions.get(index) {
+            // The leading edge of the character after the caret
+            position + usize::from(self.is_rtl(index))
+        } else if let Some(last) = index.checked_sub(1).filter(|i| *i < self.chars.len()) {
+            // At the end, the trailing edge of the last character
+            self.positions[last] + usize::from(!self.is_rtl(last))
+        } else {
+            0
+        }
+    }
+
+    /// Returns where the caret is drawn for a column, in cells from the left edge of the line.
+    pub fn caret_x(&self, column: usize) -> usize {
+        self.edges[self.caret_of(self.index(column))]
+    }
+
+    /// Returns the column a click lands on.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The horizontal position of the click, in cells from the left edge of the line.
+    pub fn column_at(&self, x: f32) -> usize {
+        let position = self
+            .edges
+            .partition_point(|edge| *edge as f32 <= x)
+            .checked_sub(1)
+            .filter(|position| *position < self.visual.len());
+        let index = if let Some(position) = position {
+            // The caret goes before or after the clicked character, whichever side is nearer
+            let index = self.visual[position];
+            let (left, right) = (self.edges[position], self.edges[position + 1]);
+            let left_half = x - (left as f32) < (right - left) as f32 / 2.0;
+            if left_half != self.is_rtl(index) {
+                index
+            } else {
+                index + 1
+            }
+        } else {
+            (0..=self.chars.len())
+                .min_by(|a, b| {
+                    let distance = |i: &usize| (self.edges[self.caret_of(*i)] as f32 - x).abs();
+                    distance(a).total_cmp(&distance(b))
+                })
+                .unwrap_or(0)
+        };
+        self.columns[index]
+    }
+
+    /// Returns the column one step left or right of a column on screen, or `None` at the
+    /// line's edge.
+    pub fn move_visually(&self, column: usize, right: bool) -> Option<usize> {
+        let from = self.caret_of(self.index(column));
+        let targets: Box<dyn Iterator<Item = usize>> = if right {
+            Box::new(from + 1..=self.chars.len())
+        } else {
+            Box::new((0..from).rev())
+        };
+        for target in targets {
+            if let Some(index) = (0..=self.chars.len())
+                .find(|&i| self.caret_of(i) == target && self.columns[i] != column)
+            {
+                return Some(self.columns[index]);
+            }
+        }
+        None
+    }
+
+    /// Returns the cells covered by the characters between two columns, as ranges from left to
+    /// right.
+    pub fn selection(&self, from: usize, to: usize) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (position, &index) in self.visual.iter().enumerate() {
+            if !(from..to).contains(&self.columns[index]) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(range) if range.end == position => range.end += 1,
+                _ => ranges.push(position..position + 1),
+            }
+        }
+        ranges
+            .into_iter()
+            .map(|range| self.edges[range.start]..self.edges[range.end])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visual(text: &str) -> String {
+        Line::new(text, 0..text.len(), 4)
+            .glyphs()
+            .map(|(_, _, c)| c)
+            .collect()
+    }
+
+    #[test]
+    fn lines_are_reordered_for_display() {
+        assert!(!has_rtl("plain text, 123"));
+        assert_eq!(visual("plain text, 123"), "plain text, 123");
+        assert_eq!(visual("abc אבג def"), "abc גבא def");
+        assert_eq!(paragraph_direction("אבג 123 abc!"), Direction::RightToLeft);
+        assert_eq!(visual("אבג 123 abc!"), "!abc 123 גבא");
+        // Brackets are mirrored in right-to-left text
+        assert_eq!(visual("א(ב)"), "(ב)א");
+        // Trailing whitespace stays at the end in the paragraph's direction
+        assert_eq!(visual("abc אבג  "), "abc גבא  ");
+
+        // A soft-wrapped row keeps the levels of its paragraph
+        let text = "abc אבג דהו";
+        let start = text.find('ד').unwrap();
+        let row = Line::new(text, start..text.len(), 4);
+        assert_eq!(row.glyphs().map(|(_, _, c)| c).collect::<String>(), "והד");
+        assert_eq!(row.glyphs().next(), Some((0, "דה".len(), 'ו')));
+    }
+
+    #[test]
+    fn carets_move_and_hit_test_visually() {
+        // a b ␠ א ב, shown as a b ␠ ב א
+        let text = "ab אב";
+        let line = Line::new(text, 0..text.len(), 4);
+        let carets: Vec<usize> = [0, 1, 2, 3, 5, 7]
+            .iter()
+            .map(|&c| line.caret_x(c))
+            .collect();
+        assert_eq!(carets, [0, 1, 2, 5, 4, 3]);
+
+        let mut column = 0;
+        let mut visited = Vec::new();
+        while let Some(next) = line.move_visually(column, true) {
+            visited.push(next);
+            column = next;
+        }
+        assert_eq!(visited, [1, 2, 7, 5, 3]);
+        assert_eq!(line.move_visually(0, false), None);
+        assert_eq!(line.move_visually(7, false), Some(2));
+
+        // The left half of ב is its logical end
+        assert_eq!(line.column_at(3.2), 7);
+        assert_eq!(line.column_at(3.8), 5);
+        assert_eq!(line.column_at(-1.0), 0);
+        assert_eq!(line.column_at(9.0), 3);
+
+        // Selecting א alone covers the rightmost cell
+        assert_eq!(line.selection(3, 5), vec![4..5]);
+        assert_eq!(line.selection(1, 5), vec![1..3, 4..5]);
+
+        // Tabs extend to the next tab stop
+        let text = "ab\tאב";
+        let line = Line::new(text, 0..text.len(), 4);
+        assert_eq!(line.width(), 6);
+        assert_eq!((line.caret_x(3), line.caret_x(7)), (6, 4));
+        assert_eq!(line.selection(2, 3), vec![2..4]);
+        assert_eq!(line.column_at(2.9), 2);
+        assert_eq!(line.column_at(3.1), 3);
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Result as AnyResult;
+
+use super::buffer::editor::State;
+use super::commands::editor::Command;
+use super::piece_table::piece::Table;
+use super::types::buffer::ID;
+
+/// The bracket pairs that are matched, as opening and closing characters.
+pub const PAIRS: &[(u8, u8)] = &[(b'(', b')'), (b'[', b']'), (b'{', b'}')];
+
+/// How far from a bracket its match is looked for, in bytes, so that an unmatched bracket in a
+/// large file isn't scanned to the end on every frame.
+pub const MAX_DISTANCE: usize = 1 << 20;
+
+/// A bracket next to the caret and the bracket matching it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Byte offset of the bracket next to the caret.
+    pub bracket: usize,
+    /// Byte offset of the bracket matching it.
+    pub matching: usize,
+    /// Whether the bracket is the character after the caret rather than the one before it.
+    pub after_caret: bool,
+}
+
+/// Finds the bracket next to an offset and its match.
+///
+/// The bracket after the offset is tried before the one before it. Brackets of the same kind
+/// between the two nest, other kinds are skipped over. The text is scanned in the piece table
+/// without copying it.
+///
+/// # Arguments
+///
+/// * `table` - The buffer's text.
+/// * `offset` - The byte offset, usually the caret's.
+pub fn find(table: &Table, offset: usize) -> Option<Match> {
+    let offset = offset.min(table.len());
+    let after = table
+        .bytes_from(offset)
+        .next()
+        .map(|byte| (offset, byte, true));
+    let before = table
+        .bytes_before(offset)
+        .next()
+        .map(|byte| (offset - 1, byte, false));
+    [after, before]
+        .into_iter()
+        .flatten()
+        .find_map(|(bracket, byte, after_caret)| {
+            Some(Match {
+                bracket,
+                matching: matching(table, bracket, byte)?,
+                after_caret,
+            })
+        })
+}
+
+/// Returns the offset of the bracket matching `byte` at `offset`, if `byte` is a bracket and
+/// its match is within [`MAX_DISTANCE`].
+fn matching(table: &Table, offset: usize, byte: u8) -> Option<usize> {
+    let &(open, close) = PAIRS
+        .iter()
+        .find(|&&(open, close)| byte == open || byte == close)?;
+    // Brackets are ASCII, so they can't be mistaken for part of another character
+    let mut depth = 0;
+    if byte == open {
+        table
+            .bytes_from(offset + 1)
+            .take(MAX_DISTANCE)
+            .position(|b| closes(&mut depth, b, open, close))
+            .map(|i| offset + 1 + i)
+    } else {
+        table
+            .bytes_before(offset)
+            .take(MAX_DISTANCE)
+            .position(|b| closes(&mut depth, b, close, open))
+            .map(|i| offset - 1 - i)
+    }
+}
+
+/// Follows the nesting of a scan that started at a `start` bracket; returns whether `byte` is
+/// the `end` bracket matching it.
+fn closes(depth: &mut usize, byte: u8, start: u8, end: u8) -> bool {
+    if byte == start {
+        *depth += 1;
+    } else if byte == end {
+        if *depth == 0 {
+            return true;
+        }
+        *depth -= 1;
+    }
+    false
+}
+
+/// Moves the caret of a buffer to the bracket matching the one next to it.
+///
+/// The caret keeps its side of the bracket: before a bracket it goes before the match, after
+/// one after the match, so that jumping again goes back.
+///
+/// # Errors
+///
+/// Returns an error if the caret could not be moved.
+///
+/// # Returns
+///
+/// Whether the caret was next to a matched bracket.
+pub fn jump(state: &mut State, buffer_id: ID) -> AnyResult<bool> {
+    let (Some(table), Some(cursor)) = (state.buffer(buffer_id), state.get_cursor_state(buffer_id))
+    else {
+        return Ok(false);
+    };
+    let Some(found) = find(table, table.position_to_offset(cursor.position())) else {
+        return Ok(false);
+    };
+    let target = if found.after_caret {
+        found.matching
+    } else {
+        found.matching + 1
+    };
+    let position = table.offset_to_position(target);
+    state.execute_command(Command::MoveCursor {
+        buffer_id,
+        position,
+    })?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::Position;
+    use super::*;
+
+    #[test]
+    fn finds_the_match_of_the_bracket_next_to_the_caret() {
+        let mut table = Table::new("f(a, [b(c)], ".to_string());
+        table.insert(table.len(), "{d})").unwrap();
+        let text = "f(a, [b(c)], {d})";
+        let found = |offset| find(&table, offset).map(|m| (m.bracket, m.matching, m.after_caret));
+
+        // Nested brackets of the same kind are skipped over, other kinds ignored
+        assert_eq!(found(1), Some((1, 16, true)));
+        assert_eq!(found(17), Some((16, 1, false)));
+        assert_eq!(found(text.find('[').unwrap() + 1), Some((5, 10, false)));
+        // The bracket after the caret comes first
+        assert_eq!(found(10), Some((10, 5, true)));
+        assert_eq!(found(3), None);
+
+        let unmatched = Table::new("((a)".to_string());
+        assert_eq!(find(&unmatched, 0), None);
+        assert_eq!(find(&unmatched, 1).map(|m| m.matching), Some(3));
+    }
+
+    #[test]
+    fn jumping_goes_back_and_forth() {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("if (ä) {\n}".to_string());
+        let caret = |state: &State| state.get_cursor_state(buffer_id).unwrap().position();
+        let at = |line, column| Position { line, column };
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: at(0, 3),
+            })
+            .unwrap();
+        assert!(jump(&mut state, buffer_id).unwrap());
+        // Columns are bytes, and "ä" takes two
+        assert_eq!(caret(&state), at(0, 6));
+        assert!(jump(&mut state, buffer_id).unwrap());
+        assert_eq!(caret(&state), at(0, 3));
+
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: at(1, 1),
+            })
+            .unwrap();
+        assert!(jump(&mut state, buffer_id).unwrap());
+        assert_eq!(caret(&state), at(0, 9));
+
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: at(0, 1),
+            })
+            .unwrap();
+        assert!(!jump(&mut state, buffer_id).unwrap());
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use super::buffer::ID;
+
+/// A kind of cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The text shared by a buffer's snapshots.
+    Snapshot,
+    /// The merge conflicts found in a buffer.
+    ConflictHighlights,
+    /// The project's search index, kept on disk as well.
+    ProjectIndex,
+}
+
+/// What a cache is kept for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Owner {
+    Buffer(ID),
+    /// The open project, whose caches are used by project-wide searches.
+    Project,
+}
+
+impl From<ID> for Owner {
+    fn from(buffer_id: ID) -> Self {
+        Self::Buffer(buffer_id)
+    }
+}
+
+/// Accounts for the memory the caches hold and picks which to evict.
+///
+/// The owner reports the size of each cache, which buffers are shown each frame and when the
+/// project's caches are used. Above a ceiling, the caches used least recently go first; an
+/// evicted cache is rebuilt when it is needed again.
+#[derive(Debug, Clone, Default)]
+pub struct Manager {
+    sizes: HashMap<(Kind, Owner), usize>,
+    /// When each buffer was last shown, or the project's caches last used.
+    shown: HashMap<Owner, Instant>,
+}
+
+impl Manager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that a buffer is on screen, or the project's caches in use, which keeps their
+    /// caches longest.
+    pub fn shown(&mut self, owner: impl Into<Owner>, now: Instant) {
+        self.shown.insert(owner.into(), now);
+    }
+
+    /// Records the size of a cache in bytes; an empty cache is no longer tracked.
+    pub fn set_size(&mut self, kind: Kind, owner: impl Into<Owner>, bytes: usize) {
+        let key = (kind, owner.into());
+        if bytes == 0 {
+            self.sizes.remove(&key);
+        } else {
+            self.sizes.insert(key, bytes);
+        }
+    }
+
+    /// Returns the bytes all tracked caches hold.
+    pub fn total(&self) -> usize {
+        self.sizes.values().sum()
+    }
+
+    /// Stops tracking the caches of a closed buffer.
+    pub fn forget_buffer(&mut self, buffer_id: ID) {
+        let owner = Owner::Buffer(buffer_id);
+        self.sizes.retain(|(_, of), _| *of != owner);
+        self.shown.remove(&owner);
+    }
+
+    /// Picks caches to evict until the rest fit under a ceiling, and stops tracking them.
+    ///
+    /// # Arguments
+    ///
+    /// * `ceiling` - The bytes the caches may hold; 0 evicts them all.
+    ///
+    /// # Returns
+    ///
+    /// The caches to drop, those never used or used longest ago first, and the larger of an
+    /// owner's caches before the smaller.
+    pub fn evict(&mut self, ceiling: usize) -> Vec<(Kind, Owner)> {
+        let mut total = self.total();
+        if total <= ceiling {
+            return Vec::new();
+        }
+        let mut entries: Vec<((Kind, Owner), usize)> = self
+            .sizes
+            .iter()
+            .map(|(key, bytes)| (*key, *bytes))
+            .collect();
+        entries.sort_by_key(|&((_, owner), bytes)| {
+            (self.shown.get(&owner).copied(), std::cmp::Reverse(bytes))
+        });
+        let mut evicted = Vec::new();
+        for (key, bytes) in entries {
+            if total <= ceiling {
+                break;
+            }
+            total -= bytes;
+            self.sizes.remove(&key);
+            evicted.push(key);
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn caches_of_buffers_shown_longest_ago_go_first() {
+        let start = Instant::now();
+        let (old, recent, hidden) = (ID::new(), ID::new(), ID::new());
+        let mut manager = Manager::new();
+        manager.shown(old, start);
+        manager.shown(recent, start + Duration::from_secs(1));
+        manager.set_size(Kind::Snapshot, old, 300);
+        manager.set_size(Kind::ConflictHighlights, old, 50);
+        manager.set_size(Kind::Snapshot, recent, 400);
+        manager.set_size(Kind::Snapshot, hidden, 100);
+        assert_eq!(manager.total(), 850);
+
+        assert!(manager.evict(1_000).is_empty());
+        assert_eq!(
+            manager.evict(450),
+            vec![
+                (Kind::Snapshot, Owner::Buffer(hidden)),
+                (Kind::Snapshot, Owner::Buffer(old))
+            ]
+        );
+        assert_eq!(manager.total(), 450);
+
+        manager.forget_buffer(recent);
+        assert_eq!(manager.total(), 50);
+        assert_eq!(
+            manager.evict(0),
+            vec![(Kind::ConflictHighlights, Owner::Buffer(old))]
+        );
+        manager.set_size(Kind::Snapshot, old, 0);
+        assert_eq!(manager.total(), 0);
+    }
+
+    #[test]
+    fn the_project_index_ages_with_the_last_search() {
+        let start = Instant::now();
+        let buffer_id = ID::new();
+        let mut manager = Manager::new();
+        manager.shown(Owner::Project, start);
+        manager.shown(buffer_id, start + Duration::from_secs(1));
+        manager.set_size(Kind::ProjectIndex, Owner::Project, 500);
+        manager.set_size(Kind::Snapshot, buffer_id, 100);
+        assert_eq!(
+            manager.evict(100),
+            vec![(Kind::ProjectIndex, Owner::Project)]
+        );
+
+        // Searching again keeps the rebuilt index over buffers not shown since
+        manager.shown(Owner::Project, start + Duration::from_secs(2));
+        manager.set_size(Kind::ProjectIndex, Owner::Project, 500);
+        assert_eq!(
+            manager.evict(500),
+            vec![(Kind::Snapshot, Owner::Buffer(buffer_id))]
+        );
+        manager.forget_buffer(buffer_id);
+        assert_eq!(manager.total(), 500);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result as AnyResult;
+
+use super::buffer::editor::State;
+use super::commands::editor::Command;
+use super::types::Range;
+use super::types::buffer::ID;
+
+/// A letter case text can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    /// The first letter of each word upper case, the others lower case.
+    Title,
+}
+
+/// Converts text to a letter case.
+///
+/// Words are runs of letters and digits; an apostrophe inside a word doesn't end it, so `don't`
+/// becomes `Don't` in title case.
+/// Conversions may change the length of the text, as `ß` becomes `SS` in upper case.
+pub fn convert(text: &str, case: Case) -> String {
+    match case {
+        Case::Upper => text.to_uppercase(),
+        Case::Lower => text.to_lowercase(),
+        Case::Title => {
+            let mut converted = String::with_capacity(text.len());
+            let mut in_word = false;
+            for c in text.chars() {
+                if in_word {
+                    converted.extend(c.to_lowercase());
+                } else {
+                    converted.extend(c.to_uppercase());
+                }
+                in_word = c.is_alphanumeric() || (in_word && c == '\'');
+            }
+            converted
+        }
+    }
+}
+
+/// Converts the text selected in a buffer to a letter case, as one edit. The converted text
+/// stays selected, with the caret on the same end of the selection.
+///
+/// # Errors
+///
+/// Returns an error if the edit could not be applied.
+///
+/// # Returns
+///
+/// Whether the text changed; it doesn't without a selection or when the text already is in the
+/// case.
+pub fn convert_selection(state: &mut State, buffer_id: ID, case: Case) -> AnyResult<bool> {
+    let (Some(table), Some(cursor)) = (
+        state.buffer(buffer_id),
+        state.get_cursor_state(buffer_id).cloned(),
+    ) else {
+        return Ok(false);
+    };
+    let Some(selection) = cursor.selection().filter(|range| !range.is_empty()) else {
+        return Ok(false);
+    };
+    let ordered = selection.ordered();
+    let start = table.position_to_offset(ordered.start);
+    let length = table.position_to_offset(ordered.end) - start;
+    let text = table.get_text(start, length);
+    let converted = convert(&text, case);
+    if converted == text {
+        return Ok(false);
+    }
+    let end = start + converted.len();
+    state.execute_command(Command::Batch(vec![
+        Command::DeleteText {
+            buffer_id,
+            start,
+            length,
+        },
+        Command::InsertText {
+            buffer_id,
+            offset: start,
+            text: converted,
+        },
+    ]))?;
+
+    let table = &state.buffers()[&buffer_id];
+    let (start, end) = (
+        table.offset_to_position(start),
+        table.offset_to_position(end),
+    );
+    // A selection made upwards keeps its direction
+    let range = if selection.start == ordered.start {
+        Range { start, end }
+    } else {
+        Range {
+            start: end,
+            end: start,
+        }
+    };
+    let caret = if cursor.position() == ordered.start {
+        start
+    } else {
+        end
+    };
+    state.execute_command(Command::Batch(vec![
+        Command::MoveCursor {
+            buffer_id,
+            position: caret,
+        },
+        Command::SetSelection { buffer_id, range },
+    ]))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::types::Position;
+
+    #[test]
+    fn converts_letter_case() {
+        assert_eq!(convert("Hello, wörld", Case::Upper), "HELLO, WÖRLD");
+        assert_eq!(convert("Straße", Case::Upper), "STRASSE");
+        assert_eq!(convert("Hello, WÖRLD", Case::Lower), "hello, wörld");
+        assert_eq!(
+            convert("the QUICK brown-fox don't 2nd", Case::Title),
+            "The Quick Brown-Fox Don't 2nd"
+        );
+    }
+
+    #[test]
+    fn converting_keeps_the_selection_on_its_text() {
+        let at = |line, column| Position { line, column };
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("a straße\nb".to_string());
+        assert!(!convert_selection(&mut state, buffer_id, Case::Upper).unwrap());
+
+        // Selected upwards, with the caret at the start
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id,
+                position: at(0, 2),
+            })
+            .unwrap();
+        state
+            .execute_command(Command::SetSelection {
+                buffer_id,
+                range: Range {
+                    start: at(0, 9),
+                    end: at(0, 2),
+                },
+            })
+            .unwrap();
+        assert!(convert_selection(&mut state, buffer_id, Case::Upper).unwrap());
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "a STRASSE\nb");
+        let cursor = state.get_cursor_state(buffer_id).unwrap();
+        assert_eq!(cursor.position(), at(0, 2));
+        assert_eq!(
+            cursor.selection(),
+            Some(Range {
+                start: at(0, 9),
+                end: at(0, 2)
+            })
+        );
+        assert!(!convert_selection(&mut state, buffer_id, Case::Upper).unwrap());
+
+        // One undo step
+        state.undo(buffer_id).unwrap();
+        assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "a straße\nb");
+    }
+}
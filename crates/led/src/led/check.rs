@@ -0,0 +1,289 @@
+use anyhow::{Result as AnyResult, anyhow};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::sandbox::{self, Limits};
+use super::shell::Environment;
+use super::types::Position;
+
+/// A problem a linter reported in a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub path: PathBuf,
+    /// Where the problem is; the column is 0 when the linter didn't name one.
+    pub position: Position,
+    pub message: String,
+}
+
+/// The formatters and linters to run, by file extension, and how to run them.
+#[derive(Debug, Clone)]
+pub struct Tools {
+    pub formatters: BTreeMap<String, String>,
+    pub linters: BTreeMap<String, String>,
+    pub env: Environment,
+    pub limits: Limits,
+}
+
+/// What checking one file found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub path: PathBuf,
+    /// The text that was checked.
+    pub text: String,
+    /// The formatter's output, if it changed the text.
+    pub formatted: Option<String>,
+    pub findings: Vec<Finding>,
+    /// Why a formatter or linter could not be run or failed.
+    pub errors: Vec<String>,
+}
+
+/// Formats and lints the files with unsaved or uncommitted changes.
+///
+/// Each file is checked with the formatter and linter set for its extension, and files without
+/// either are skipped. The linter sees the formatted text, so it doesn't report what formatting
+/// fixes.
+///
+/// # Arguments
+///
+/// * `root` - The project root, where the tools run.
+/// * `unsaved` - The text of each buffer with unsaved changes, by path. Other files are read
+///   from disk.
+/// * `tools` - The formatters and linters.
+///
+/// # Returns
+///
+/// A report for every file that was checked, in path order.
+pub fn check(root: &Path, unsaved: HashMap<PathBuf, String>, tools: &Tools) -> Vec<Report> {
+    let mut paths: Vec<PathBuf> = unsaved.keys().cloned().collect();
+    // Outside a Git repository only the unsaved buffers count as changed
+    if let Ok(changed) = changed_files(root, &tools.env, tools.limits) {
+        paths.extend(changed);
+    }
+    paths.sort();
+    paths.dedup();
+
+    let mut reports = Vec::new();
+    for path in paths {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        let formatter = tools.formatters.get(extension);
+        let linter = tools.linters.get(extension);
+        if formatter.is_none() && linter.is_none() {
+            continue;
+        }
+        let text = match unsaved.get(&path) {
+            Some(text) => text.clone(),
+            None => match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(_) => continue,
+            },
+        };
+        let mut report = Report {
+            path,
+            text,
+            formatted: None,
+            findings: Vec::new(),
+            errors: Vec::new(),
+        };
+        if let Some(formatter) = formatter {
+            match format(root, formatter, &report.path, &report.text, tools) {
+                Ok(formatted) => {
+                    report.formatted = Some(formatted).filter(|f| *f != report.text);
+                }
+                Err(e) => report.errors.push(format!("{:#}", e)),
+            }
+        }
+        if let Some(linter) = linter {
+            let text = report.formatted.as_ref().unwrap_or(&report.text);
+            match lint(root, linter, &report.path, text, tools) {
+                Ok(findings) => report.findings = findings,
+                Err(e) => report.errors.push(format!("{:#}", e)),
+            }
+        }
+        reports.push(report);
+    }
+    reports
+}
+
+/// Lists the files Git reports as changed or untracked under `root`, skipping deleted files.
+///
+/// # Errors
+///
+/// Returns an error if Git can't be run or `root` isn't in a Git repository.
+pub fn changed_files(root: &Path, env: &Environment, limits: Limits) -> AnyResult<Vec<PathBuf>> {
+    let git = |args: &[&str]| -> AnyResult<String> {
+        let mut command = env.command("git");
+        command.args(args).current_dir(root);
+        let output = sandbox::run(command, limits)?;
+        if output.status != Some(0) {
+            return Err(anyhow!("git {}: {}", args.join(" "), output.stderr.trim()));
+        }
+        Ok(output.stdout)
+    };
+    let changed = git(&["diff", "--name-only", "--relative", "-z", "HEAD"])?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "-z"])?;
+    Ok(changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|name| !name.is_empty())
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+/// Runs a formatter on a file's text.
+///
+/// # Errors
+///
+/// Returns an error if the formatter can't be run, fails, or its output was cut short.
+fn format(
+    root: &Path,
+    template: &str,
+    path: &Path,
+    text: &str,
+    tools: &Tools,
+) -> AnyResult<String> {
+    let command = command_for(template, path, root, &tools.env)?;
+    let output = sandbox::pipe(command, text.to_string(), tools.limits)?;
+    if output.timed_out || output.truncated {
+        return Err(anyhow!("{}: output was cut short", template));
+    }
+    if output.status != Some(0) {
+        return Err(anyhow!("{}: {}", template, output.stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Runs a linter on a file's text and collects what it reports on either output.
+///
+/// # Errors
+///
+/// Returns an error if the linter can't be run or times out.
+fn lint(
+    root: &Path,
+    template: &str,
+    path: &Path,
+    text: &str,
+    tools: &Tools,
+) -> AnyResult<Vec<Finding>> {
+    let command = command_for(template, path, root, &tools.env)?;
+    let output = sandbox::pipe(command, text.to_string(), tools.limits)?;
+    if output.timed_out {
+        return Err(anyhow!("{}: timed out", template));
+    }
+    let mut findings = parse_findings(&output.stdout, path);
+    findings.extend(parse_findings(&output.stderr, path));
+    Ok(findings)
+}
+
+/// Builds the process for a configured command line, which is split at whitespace, with
+/// `{file}` replaced by the file's path.
+///
+/// # Errors
+///
+/// Returns an error if the command line is empty.
+fn command_for(
+    template: &str,
+    path: &Path,
+    root: &Path,
+    env: &Environment,
+) -> AnyResult<std::process::Command> {
+    let file = path.to_string_lossy();
+    let mut words = template
+        .split_whitespace()
+        .map(|word| word.replace("{file}", &file));
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow!("empty command for {}", path.display()))?;
+    let mut command = env.command(&program);
+    command.args(words).current_dir(root);
+    Ok(command)
+}
+
+/// Reads the problems a linter reported as `file:line:column: message` or `file:line: message`
+/// lines, with 1-based numbers. Other lines are skipped.
+///
+/// The linter read the text from standard input, so it may name the file as `-` or `<stdin>`;
+/// every finding is attributed to `path`.
+pub fn parse_findings(output: &str, path: &Path) -> Vec<Finding> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // The file name may itself contain colons, as in `C:\`, so look for the first
+            // colon followed by a line number and another colon
+            let (line_number, rest) = line.match_indices(':').find_map(|(i, _)| {
+                let (number, rest) = line[i + 1..].split_once(':')?;
+                Some((number.parse::<usize>().ok()?, rest))
+            })?;
+            let (column, message) = rest
+                .split_once(':')
+                .and_then(|(column, message)| Some((column.parse::<usize>().ok()?, message)))
+                .unwrap_or((1, rest));
+            Some(Finding {
+                path: path.to_path_buf(),
+                position: Position {
+                    line: line_number.saturating_sub(1),
+                    column: column.saturating_sub(1),
+                },
+                message: message.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn findings_are_read_with_or_without_a_column() {
+        let output = "src/main.rs:3:5: unused variable `x`\n\
+                      note: something else\n\
+                      -:10: line too long\n\
+                      C:\\src\\lib.rs:1:1: missing docs\n";
+        let path = Path::new("/project/src/main.rs");
+        let findings = parse_findings(output, path);
+        let at = |line, column| Position { line, column };
+        assert_eq!(
+            findings
+                .iter()
+                .map(|f| (f.position, f.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (at(2, 4), "unused variable `x`"),
+                (at(9, 0), "line too long"),
+                (at(0, 0), "missing docs"),
+            ]
+        );
+        assert!(findings.iter().all(|f| f.path == path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_formatted_then_linted() {
+        let root = std::env::temp_dir().join(format!("led-check-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("notes.txt");
+        let tools = Tools {
+            formatters: BTreeMap::from([("txt".to_string(), "tr a-z A-Z".to_string())]),
+            linters: BTreeMap::from([("txt".to_string(), "grep -Hn TODO".to_string())]),
+            env: Environment::inherited(),
+            limits: Limits {
+                timeout: std::time::Duration::from_secs(10),
+                max_output: 1024,
+            },
+        };
+        let unsaved = HashMap::from([(path.clone(), "one\ntodo: two\n".to_string())]);
+
+        let reports = check(&root, unsaved, &tools);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].formatted.as_deref(), Some("ONE\nTODO: TWO\n"));
+        assert_eq!(reports[0].findings.len(), 1);
+        assert_eq!(reports[0].findings[0].position.line, 1);
+        assert_eq!(reports[0].findings[0].message, "TODO: TWO");
+        assert!(reports[0].errors.is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
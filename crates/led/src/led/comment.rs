@@ -0,0 +1,234 @@
+use anyhow::Result as AnyResult;
+
+use super::buffer::editor::State;
+use super::commands::editor::Command;
+use super::delta::Delta;
+use super::types::buffer::ID;
+use super::types::{Position, Range};
+
+/// Toggles line comments on the lines under a buffer's cursor or selection, as one edit, with
+/// the token of the buffer's language. The caret and selection stay on the same text.
+///
+/// A selection ending at the start of a line does not include that line.
+///
+/// # Errors
+///
+/// Returns an error if the edit could not be applied.
+///
+/// # Returns
+///
+/// Whether the text changed; it doesn't when the language has no line comments or the lines
+/// are blank.
+pub fn toggle(state: &mut State, buffer_id: ID) -> AnyResult<bool> {
+    let (Some(text), Some(cursor), Some(meta)) = (
+        state.get_buffer_text(buffer_id),
+        state.get_cursor_state(buffer_id).cloned(),
+        state.metadata(buffer_id),
+    ) else {
+        return Ok(false);
+    };
+    let Some(prefix) = meta.line_comment() else {
+        return Ok(false);
+    };
+    let selection = cursor.selection().filter(|range| !range.is_empty());
+    let (first_line, last_line) = match selection.map(Range::ordered) {
+        Some(Range { start, end }) if end.column == 0 && end.line > start.line => {
+            (start.line, end.line - 1)
+        }
+        Some(Range { start, end }) => (start.line, end.line),
+        None => (cursor.position().line, cursor.position().line),
+    };
+    let commands = toggle_lines(buffer_id, &text, first_line, last_line, prefix);
+    if commands.is_empty() {
+        return Ok(false);
+    }
+
+    let follow = |position: Position| {
+        let table = &state.buffers()[&buffer_id];
+        commands
+            .iter()
+            .filter_map(Delta::from_command)
+            .fold(table.position_to_offset(position), |offset, (_, delta)| {
+                delta.map_offset(offset)
+            })
+    };
+    let caret = follow(cursor.position());
+    let selected = selection.map(|range| (follow(range.start), follow(range.end)));
+    state.execute_command(Command::Batch(commands))?;
+
+    let table = &state.buffers()[&buffer_id];
+    let mut moves = vec![Command::MoveCursor {
+        buffer_id,
+        position: table.offset_to_position(caret),
+    }];
+    if let Some((start, end)) = selected {
+        moves.push(Command::SetSelection {
+            buffer_id,
+            range: Range {
+                start: table.offset_to_position(start),
+                end: table.offset_to_position(end),
+            },
+        });
+    }
+    state.execute_command(Command::Batch(moves))?;
+    Ok(true)
+}
+
+/// Builds the commands that toggle line comments on a range of lines.
+///
+/// If every non-blank line in the range is already commented, the comment token (and one
+/// following space, if present) is removed from each of them. Otherwise the token and a space
+/// are inserted at the smallest indentation of the non-blank lines, so the commented block
+/// stays aligned. Blank lines are left untouched.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer the commands apply to.
+/// * `text` - The full text of the buffer.
+/// * `first_line` - The first line to toggle (zero-based).
+/// * `last_line` - The last line to toggle, inclusive.
+/// * `prefix` - The line comment token, see [`super::buffer::meta::line_comment`].
+///
+/// # Returns
+///
+/// The edit commands, ordered from the end of the buffer towards the start so that each
+/// command's offsets remain valid after the previous ones have been applied.
+pub fn toggle_lines(
+    buffer_id: ID,
+    text: &str,
+    first_line: usize,
+    last_line: usize,
+    prefix: &str,
+) -> Vec<Command> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if index > last_line {
+            break;
+        }
+        let content = line.trim_end_matches(['\n', '\r']);
+        if index >= first_line && !content.trim().is_empty() {
+            let indent = content.len() - content.trim_start().len();
+            lines.push((offset, &content[indent..], indent));
+        }
+        offset += line.len();
+    }
+
+    let commented = !lines.is_empty()
+        && lines
+            .iter()
+            .all(|(_, content, _)| content.starts_with(prefix));
+
+    let mut commands = Vec::new();
+    if commented {
+        for &(start, content, indent) in lines.iter().rev() {
+            let rest = &content[prefix.len()..];
+            let length = prefix.len() + usize::from(rest.starts_with(' '));
+            commands.push(Command::DeleteText {
+                buffer_id,
+                start: start + indent,
+                length,
+            });
+        }
+    } else {
+        let column = lines
+            .iter()
+            .map(|&(_, _, indent)| indent)
+            .min()
+            .unwrap_or(0);
+        for &(start, _, _) in lines.iter().rev() {
+            commands.push(Command::InsertText {
+                buffer_id,
+                offset: start + column,
+                text: format!("{prefix} "),
+            });
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::buffer::editor::State;
+
+    fn toggle(text: &str, first_line: usize, last_line: usize) -> String {
+        let mut state = State::new();
+        let id = state.create_buffer(text.to_string());
+        for command in toggle_lines(id, text, first_line, last_line, "//") {
+            state.execute_command(command).unwrap();
+        }
+        state.get_buffer_text(id).unwrap()
+    }
+
+    #[test]
+    fn toggling_keeps_the_selection_on_its_text() {
+        let mut state = State::new();
+        let id = state.create_buffer("a = 1\nb = 2\nc = 3\n".to_string());
+        state.set_language(id, Some("python".to_string()));
+        let at = |line, column| Position { line, column };
+        state
+            .execute_command(Command::MoveCursor {
+                buffer_id: id,
+                position: at(2, 0),
+            })
+            .unwrap();
+        state
+            .execute_command(Command::SetSelection {
+                buffer_id: id,
+                range: Range {
+                    start: at(0, 2),
+                    end: at(2, 0),
+                },
+            })
+            .unwrap();
+
+        assert!(super::toggle(&mut state, id).unwrap());
+        assert_eq!(
+            state.get_buffer_text(id).unwrap(),
+            "# a = 1\n# b = 2\nc = 3\n"
+        );
+        let cursor = state.get_cursor_state(id).unwrap();
+        assert_eq!(cursor.position(), at(2, 0));
+        assert_eq!(
+            cursor.selection(),
+            Some(Range {
+                start: at(0, 4),
+                end: at(2, 0)
+            })
+        );
+
+        assert!(super::toggle(&mut state, id).unwrap());
+        assert_eq!(state.get_buffer_text(id).unwrap(), "a = 1\nb = 2\nc = 3\n");
+
+        state.set_language(id, Some("json".to_string()));
+        assert!(!super::toggle(&mut state, id).unwrap());
+    }
+
+    #[test]
+    fn comments_lines_at_common_indentation() {
+        let text = "fn a() {\n    b();\n\n  c();\n}\n";
+        assert_eq!(
+            toggle(text, 1, 3),
+            "fn a() {\n  //   b();\n\n  // c();\n}\n"
+        );
+    }
+
+    #[test]
+    fn uncomments_when_all_lines_are_commented() {
+        let text = "// a\n  //b\n// c\n";
+        assert_eq!(toggle(text, 0, 1), "a\n  b\n// c\n");
+    }
+
+    #[test]
+    fn mixed_lines_are_commented() {
+        assert_eq!(toggle("// a\nb", 0, 1), "// // a\n// b");
+    }
+
+    #[test]
+    fn blank_range_produces_no_commands() {
+        let mut state = State::new();
+        let id = state.create_buffer("\n\n".to_string());
+        assert!(toggle_lines(id, "\n\n", 0, 1, "//").is_empty());
+    }
+}
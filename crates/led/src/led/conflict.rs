@@ -0,0 +1,441 @@
+use super::commands::editor::Command;
+use super::scheduler::{Step, Task};
+use super::types::buffer::ID;
+use std::ops::Range;
+
+/// Marker opening a conflict and the "ours" side.
+const OURS_MARKER: &str = "<<<<<<<";
+/// Marker opening the common-ancestor section written by `merge.conflictStyle=diff3`.
+const BASE_MARKER: &str = "|||||||";
+/// Marker separating "ours" (or the base section) from "theirs".
+const SEPARATOR_MARKER: &str = "=======";
+/// Marker closing a conflict after the "theirs" side.
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// Identifies which part of a conflict a line belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// One of the `<<<<<<<`, `|||||||`, `=======` or `>>>>>>>` marker lines.
+    Marker,
+    /// A line of the current branch's version.
+    Ours,
+    /// A line of the common ancestor's version (diff3 style conflicts only).
+    Base,
+    /// A line of the incoming branch's version.
+    Theirs,
+}
+
+/// How a conflict should be rewritten when it is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep only the current branch's version.
+    Ours,
+    /// Keep only the incoming branch's version.
+    Theirs,
+    /// Keep both versions, ours first.
+    Both,
+}
+
+/// A single merge conflict found in a buffer.
+///
+/// Byte ranges are offsets into the buffer text, line ranges are zero-based line numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// The whole conflict, from the start of the `<<<<<<<` line to the end of the `>>>>>>>` line.
+    pub range: Range<usize>,
+    /// The text of the "ours" side, without marker lines.
+    pub ours: Range<usize>,
+    /// The text of the common ancestor, if the conflict uses the diff3 style.
+    pub base: Option<Range<usize>>,
+    /// The text of the "theirs" side, without marker lines.
+    pub theirs: Range<usize>,
+    /// The lines spanned by the conflict, marker lines included.
+    pub lines: Range<usize>,
+    /// The line holding the `|||||||` marker, if any.
+    pub base_line: Option<usize>,
+    /// The line holding the `=======` marker.
+    pub separator_line: usize,
+}
+
+impl Region {
+    /// Returns which part of the conflict the given line belongs to.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The zero-based line number.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the line lies outside the conflict.
+    pub fn side_of_line(&self, line: usize) -> Option<Side> {
+        if !self.lines.contains(&line) {
+            return None;
+        }
+        let ours_end = self.base_line.unwrap_or(self.separator_line);
+        if line == self.lines.start
+            || line == self.lines.end - 1
+            || line == self.separator_line
+            || Some(line) == self.base_line
+        {
+            Some(Side::Marker)
+        } else if line < ours_end {
+            Some(Side::Ours)
+        } else if line < self.separator_line {
+            Some(Side::Base)
+        } else {
+            Some(Side::Theirs)
+        }
+    }
+
+    /// Builds the text that replaces the conflict for the given resolution.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The buffer text the conflict was detected in.
+    /// * `resolution` - Which side(s) to keep.
+    pub fn resolved_text(&self, text: &str, resolution: Resolution) -> String {
+        let ours = &text[self.ours.clone()];
+        let theirs = &text[self.theirs.clone()];
+        match resolution {
+            Resolution::Ours => ours.to_string(),
+            Resolution::Theirs => theirs.to_string(),
+            Resolution::Both => {
+                let mut both = String::with_capacity(ours.len() + theirs.len() + 1);
+                both.push_str(ours);
+                if !ours.is_empty() && !ours.ends_with('\n') {
+                    both.push('\n');
+                }
+                both.push_str(theirs);
+                both
+            }
+        }
+    }
+
+    /// Produces the edit commands that rewrite the conflict with the chosen side(s).
+    ///
+    /// The conflict is replaced through ordinary `DeleteText`/`InsertText` commands,
+    /// so the rewrite goes through `execute_command` like any other edit.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_id` - The buffer the conflict belongs to.
+    /// * `text` - The buffer text the conflict was detected in.
+    /// * `resolution` - Which side(s) to keep.
+    pub fn resolve(&self, buffer_id: ID, text: &str, resolution: Resolution) -> Vec<Command> {
+        let replacement = self.resolved_text(text, resolution);
+        let mut commands = vec![Command::DeleteText {
+            buffer_id,
+            start: self.range.start,
+            length: self.range.len(),
+        }];
+        if !replacement.is_empty() {
+            commands.push(Command::InsertText {
+                buffer_id,
+                offset: self.range.start,
+                text: replacement,
+            });
+        }
+        commands
+    }
+}
+
+/// Returns `true` if the line is the given conflict marker.
+///
+/// Markers must start the line and be followed by the end of the line or a space
+/// (git appends the branch name after `<<<<<<<`, `|||||||` and `>>>>>>>`).
+fn is_marker(line: &str, marker: &str) -> bool {
+    let line = line.trim_end_matches(['\n', '\r']);
+    match line.strip_prefix(marker) {
+        Some(rest) => rest.is_empty() || rest.starts_with(' '),
+        None => false,
+    }
+}
+
+/// Finds all complete merge conflicts in the given text.
+///
+/// Unterminated or malformed conflicts are skipped.
+///
+/// # Arguments
+///
+/// * `text` - The text to scan.
+///
+/// # Returns
+///
+/// The conflicts in document order.
+pub fn detect(text: &str) -> Vec<Region> {
+    let mut scanner = Scanner::new(usize::MAX);
+    scanner.scan(text);
+    scanner.regions
+}
+
+/// Finds the merge conflicts of a text a few lines at a time, as a [`Task`] over the text.
+///
+/// The text must not change between steps; start a new scanner when it does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scanner {
+    /// How many lines each step scans.
+    lines_per_step: usize,
+    /// Where the next step starts.
+    offset: usize,
+    line: usize,
+    // (offset, line) of the markers seen for the conflict currently being parsed
+    open: Option<(usize, usize)>,
+    base: Option<(usize, usize)>,
+    separator: Option<(usize, usize)>,
+    regions: Vec<Region>,
+}
+
+impl Scanner {
+    /// Creates a scanner starting at the beginning of the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines_per_step` - How many lines each step scans.
+    pub fn new(lines_per_step: usize) -> Self {
+        Self {
+            lines_per_step: lines_per_step.max(1),
+            offset: 0,
+            line: 0,
+            open: None,
+            base: None,
+            separator: None,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Returns the conflicts found so far, in document order.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Returns the conflicts found, once the scan is done.
+    pub fn into_regions(self) -> Vec<Region> {
+        self.regions
+    }
+
+    /// Scans the next lines of the text.
+    fn scan(&mut self, text: &str) -> Step {
+        // Most text has no conflicts at all, which is much quicker to rule out up front
+        if self.offset == 0 && !text.contains(OURS_MARKER) {
+            self.offset = text.len();
+            return Step::Done;
+        }
+        let lines = text[self.offset..].split_inclusive('\n');
+        for line in lines.take(self.lines_per_step) {
+            let (offset, line_idx) = (self.offset, self.line);
+            let next_offset = offset + line.len();
+            if is_marker(line, OURS_MARKER) {
+                self.open = Some((offset, line_idx));
+                self.base = None;
+                self.separator = None;
+            } else if self.open.is_some()
+                && self.separator.is_none()
+                && is_marker(line, BASE_MARKER)
+            {
+                self.base = Some((offset, line_idx));
+            } else if self.open.is_some()
+                && self.separator.is_none()
+                && is_marker(line, SEPARATOR_MARKER)
+            {
+                self.separator = Some((offset, line_idx));
+            } else if is_marker(line, THEIRS_MARKER)
+                && let (Some((start, start_line)), Some((sep, sep_line))) =
+                    (self.open, self.separator)
+            {
+                let base = self.base;
+                let ours_start = start + text[start..].find('\n').map_or(0, |i| i + 1);
+                let ours_end = base.map_or(sep, |(b, _)| b);
+                let sep_end = sep + text[sep..].find('\n').map_or(0, |i| i + 1);
+                self.regions.push(Region {
+                    range: start..next_offset,
+                    ours: ours_start..ours_end,
+                    base: base.map(|(b, _)| b + text[b..].find('\n').map_or(0, |i| i + 1)..sep),
+                    theirs: sep_end..offset,
+                    lines: start_line..line_idx + 1,
+                    base_line: base.map(|(_, l)| l),
+                    separator_line: sep_line,
+                });
+                self.open = None;
+                self.base = None;
+                self.separator = None;
+            }
+            self.offset = next_offset;
+            self.line += 1;
+        }
+        if self.offset >= text.len() {
+            Step::Done
+        } else {
+            Step::Continue
+        }
+    }
+}
+
+impl Task<str> for Scanner {
+    fn step(&mut self, text: &str) -> Step {
+        self.scan(text)
+    }
+}
+
+/// Returns the conflict containing the offset, or else the first one after it.
+///
+/// # Arguments
+///
+/// * `regions` - The conflicts returned by [`detect`].
+/// * `offset` - A byte offset, typically the cursor position.
+pub fn at_or_after(regions: &[Region], offset: usize) -> Option<&Region> {
+    regions.iter().find(|r| offset < r.range.end)
+}
+
+/// Returns the first conflict starting after the offset, wrapping around to the first conflict.
+///
+/// # Arguments
+///
+/// * `regions` - The conflicts returned by [`detect`].
+/// * `offset` - A byte offset, typically the cursor position.
+pub fn next_after(regions: &[Region], offset: usize) -> Option<&Region> {
+    regions
+        .iter()
+        .find(|r| r.range.start > offset)
+        .or_else(|| regions.first())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str =
+        "fn a() {}\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\nfn b() {}\n";
+
+    #[test]
+    fn detect_finds_nothing_in_plain_text() {
+        assert!(detect("fn main() {}\n").is_empty());
+    }
+
+    #[test]
+    fn detect_finds_simple_conflict() {
+        let regions = detect(SIMPLE);
+        assert_eq!(regions.len(), 1);
+        let region = &regions[0];
+        assert_eq!(&SIMPLE[region.ours.clone()], "ours\n");
+        assert_eq!(&SIMPLE[region.theirs.clone()], "theirs\n");
+        assert_eq!(
+            &SIMPLE[region.range.clone()],
+            "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\n"
+        );
+        assert_eq!(region.lines, 1..6);
+        assert_eq!(region.separator_line, 3);
+        assert!(region.base.is_none());
+    }
+
+    #[test]
+    fn detect_handles_diff3_base_section() {
+        let text = "<<<<<<< HEAD\nours\n||||||| base\norig\n=======\ntheirs\n>>>>>>> x\n";
+        let regions = detect(text);
+        assert_eq!(regions.len(), 1);
+        let region = &regions[0];
+        assert_eq!(&text[region.ours.clone()], "ours\n");
+        assert_eq!(&text[region.base.clone().unwrap()], "orig\n");
+        assert_eq!(&text[region.theirs.clone()], "theirs\n");
+        assert_eq!(region.side_of_line(3), Some(Side::Base));
+    }
+
+    #[test]
+    fn detect_skips_unterminated_conflict() {
+        assert!(detect("<<<<<<< HEAD\nours\n=======\ntheirs\n").is_empty());
+    }
+
+    #[test]
+    fn detect_handles_conflict_at_end_without_newline() {
+        let text = "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> x";
+        let regions = detect(text);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].range, 0..text.len());
+    }
+
+    #[test]
+    fn scanning_in_steps_matches_detect() {
+        let text = format!("{}{}{}", SIMPLE, "x\n".repeat(5), SIMPLE);
+        let mut scanner = Scanner::new(2);
+        let mut steps = 0;
+        while scanner.scan(&text) == Step::Continue {
+            steps += 1;
+        }
+        assert!(steps > 5);
+        assert_eq!(scanner.regions(), detect(&text).as_slice());
+        assert_eq!(scanner.into_regions().len(), 2);
+    }
+
+    #[test]
+    fn side_of_line_classifies_each_line() {
+        let region = &detect(SIMPLE)[0];
+        assert_eq!(region.side_of_line(0), None);
+        assert_eq!(region.side_of_line(1), Some(Side::Marker));
+        assert_eq!(region.side_of_line(2), Some(Side::Ours));
+        assert_eq!(region.side_of_line(3), Some(Side::Marker));
+        assert_eq!(region.side_of_line(4), Some(Side::Theirs));
+        assert_eq!(region.side_of_line(5), Some(Side::Marker));
+        assert_eq!(region.side_of_line(6), None);
+    }
+
+    #[test]
+    fn resolved_text_keeps_requested_sides() {
+        let region = &detect(SIMPLE)[0];
+        assert_eq!(region.resolved_text(SIMPLE, Resolution::Ours), "ours\n");
+        assert_eq!(region.resolved_text(SIMPLE, Resolution::Theirs), "theirs\n");
+        assert_eq!(
+            region.resolved_text(SIMPLE, Resolution::Both),
+            "ours\ntheirs\n"
+        );
+    }
+
+    #[test]
+    fn resolve_emits_delete_then_insert() {
+        let buffer_id = ID::new();
+        let region = &detect(SIMPLE)[0];
+        let commands = region.resolve(buffer_id, SIMPLE, Resolution::Theirs);
+        assert_eq!(
+            commands,
+            vec![
+                Command::DeleteText {
+                    buffer_id,
+                    start: region.range.start,
+                    length: region.range.len(),
+                },
+                Command::InsertText {
+                    buffer_id,
+                    offset: region.range.start,
+                    text: "theirs\n".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_to_empty_side_only_deletes() {
+        let text = "<<<<<<< HEAD\n=======\ntheirs\n>>>>>>> x\n";
+        let region = &detect(text)[0];
+        let commands = region.resolve(ID::new(), text, Resolution::Ours);
+        assert_eq!(commands.len(), 1);
+    }
+
+    #[test]
+    fn navigation_helpers_find_expected_regions() {
+        let text = format!("{SIMPLE}{SIMPLE}");
+        let regions = detect(&text);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(at_or_after(&regions, 0), Some(&regions[0]));
+        assert_eq!(
+            at_or_after(&regions, regions[0].range.start + 3),
+            Some(&regions[0])
+        );
+        assert_eq!(
+            next_after(&regions, regions[0].range.start),
+            Some(&regions[1])
+        );
+        assert_eq!(
+            next_after(&regions, regions[1].range.start),
+            Some(&regions[0])
+        );
+        assert_eq!(at_or_after(&regions, text.len()), None);
+    }
+}
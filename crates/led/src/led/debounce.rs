@@ -0,0 +1,142 @@
+use std::time::{Duration, Instant};
+
+/// When a debounced reaction runs relative to the changes it reacts to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Edge {
+    /// Run right away after a quiet period, then at most once per interval while changes keep
+    /// coming. Suits reactions the user is looking at, such as highlighting.
+    #[default]
+    Leading,
+    /// Run one interval after the first change not reacted to yet. Suits reactions that
+    /// shouldn't happen on every burst of typing, such as saving.
+    Trailing,
+}
+
+/// Limits an expensive reaction to changes, such as re-highlighting after typing, to at most
+/// one run per interval.
+///
+/// The owner reports changes with [`Debouncer::changed`] and asks [`Debouncer::ready`] each
+/// frame whether to run the reaction now. All changes in between are coalesced into one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Debouncer {
+    interval: Duration,
+    edge: Edge,
+    /// When the first change not reacted to yet happened.
+    pending_since: Option<Instant>,
+    /// When the reaction last ran.
+    last_run: Option<Instant>,
+}
+
+impl Debouncer {
+    /// Creates a debouncer with nothing pending.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The shortest time between two runs of the reaction.
+    /// * `edge` - Whether a change after a quiet period is reacted to at once or after the
+    ///   interval.
+    pub fn new(interval: Duration, edge: Edge) -> Self {
+        Self {
+            interval,
+            edge,
+            pending_since: None,
+            last_run: None,
+        }
+    }
+
+    /// Returns the shortest time between two runs.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Changes the shortest time between two runs, e.g. after the user changed a setting.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Reports a change to react to.
+    pub fn changed(&mut self, now: Instant) {
+        self.pending_since.get_or_insert(now);
+    }
+
+    /// Returns `true` if a change is waiting for the reaction.
+    pub fn is_pending(&self) -> bool {
+        self.pending_since.is_some()
+    }
+
+    /// Returns when the pending change is due to be reacted to, if there is one.
+    ///
+    /// Callers that only run while there is input can use this to schedule a repaint.
+    pub fn deadline(&self) -> Option<Instant> {
+        let since = self.pending_since?;
+        Some(match self.edge {
+            Edge::Leading => match self.last_run {
+                Some(last) => since.max(last + self.interval),
+                None => since,
+            },
+            Edge::Trailing => since + self.interval,
+        })
+    }
+
+    /// Returns whether to run the reaction now, and if so records that it ran.
+    pub fn ready(&mut self, now: Instant) -> bool {
+        if self.deadline().is_none_or(|deadline| now < deadline) {
+            return false;
+        }
+        self.pending_since = None;
+        self.last_run = Some(now);
+        true
+    }
+
+    /// Forgets the pending change, e.g. because the reaction ran for another reason.
+    pub fn cancel(&mut self) {
+        self.pending_since = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn ms(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn leading_edge_runs_at_once_then_once_per_interval() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(INTERVAL, Edge::Leading);
+        assert!(!debouncer.ready(start));
+
+        debouncer.changed(start);
+        assert!(debouncer.ready(start));
+
+        // A burst of typing within the interval runs once, when the interval is up
+        let mut runs = 0;
+        for t in (10..=190).step_by(10) {
+            debouncer.changed(ms(start, t));
+            runs += debouncer.ready(ms(start, t)) as usize;
+        }
+        assert_eq!(runs, 1);
+        assert_eq!(debouncer.deadline(), Some(ms(start, 200)));
+        assert!(debouncer.ready(ms(start, 200)));
+        assert!(!debouncer.is_pending());
+    }
+
+    #[test]
+    fn trailing_edge_waits_an_interval_after_the_first_change() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(INTERVAL, Edge::Trailing);
+        debouncer.changed(start);
+        debouncer.changed(ms(start, 50));
+        assert!(!debouncer.ready(ms(start, 99)));
+        assert!(debouncer.ready(ms(start, 100)));
+        assert!(!debouncer.ready(ms(start, 300)));
+
+        debouncer.changed(ms(start, 300));
+        debouncer.cancel();
+        assert_eq!(debouncer.deadline(), None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Command-line flag that turns on portable mode.
+pub const PORTABLE_FLAG: &str = "--portable";
+/// File next to the executable whose presence turns on portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+/// Directory next to the executable that holds the user's configuration in portable mode.
+pub const PORTABLE_DATA_DIR: &str = "led-data";
+
+/// The portable data directory, once [`init`] decided whether portable mode is on.
+static PORTABLE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Returns the portable data directory if portable mode is requested, by flag or by a marker
+/// file next to the executable.
+///
+/// # Arguments
+///
+/// * `exe_dir` - The directory containing the executable.
+/// * `flag` - Whether [`PORTABLE_FLAG`] was passed.
+pub fn portable_dir(exe_dir: &Path, flag: bool) -> Option<PathBuf> {
+    (flag || exe_dir.join(PORTABLE_MARKER).is_file()).then(|| exe_dir.join(PORTABLE_DATA_DIR))
+}
+
+/// Decides once, at startup, whether LED runs in portable mode, and creates the data directory
+/// if it does.
+///
+/// Later calls return the first decision.
+///
+/// # Arguments
+///
+/// * `args` - The command-line arguments, without the program name.
+///
+/// # Returns
+///
+/// The portable data directory, or `None` outside of portable mode.
+pub fn init(args: impl IntoIterator<Item = String>) -> Option<&'static Path> {
+    PORTABLE
+        .get_or_init(|| {
+            let flag = args.into_iter().any(|arg| arg == PORTABLE_FLAG);
+            let exe = std::env::current_exe().ok()?;
+            let dir = portable_dir(exe.parent()?, flag)?;
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                eprintln!("Failed to create {}: {}", dir.display(), e);
+            }
+            Some(dir)
+        })
+        .as_deref()
+}
+
+/// Returns the portable data directory, or `None` outside of portable mode or before [`init`].
+pub fn portable() -> Option<&'static Path> {
+    PORTABLE.get().and_then(Option::as_deref)
+}
+
+/// Returns the directory holding the user's configuration, such as global settings and Lua
+/// plugins.
+///
+/// In portable mode this is the data directory next to the executable. Otherwise it is
+/// `%APPDATA%\led` on Windows and `$XDG_CONFIG_HOME/led` or `~/.config/led` elsewhere, or
+/// `None` if neither variable is set.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = portable() {
+        return Some(dir.to_path_buf());
+    }
+    let var = |name| {
+        std::env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let dir = if cfg!(windows) {
+        var("APPDATA")?
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))?
+    };
+    Some(dir.join("led"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portable_mode_follows_flag_or_marker() {
+        let dir = std::env::temp_dir().join(format!("led-dirs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(portable_dir(&dir, false), None);
+        assert_eq!(portable_dir(&dir, true), Some(dir.join(PORTABLE_DATA_DIR)));
+
+        std::fs::write(dir.join(PORTABLE_MARKER), "").unwrap();
+        assert_eq!(portable_dir(&dir, false), Some(dir.join(PORTABLE_DATA_DIR)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
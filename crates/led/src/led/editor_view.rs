@@ -0,0 +1,273 @@
+use egui::Ui;
+use saran::theme::Theme;
+
+use super::brackets;
+use super::buffer::{ID, editor::State};
+use super::case::{self, Case};
+use super::commands::editor::{Command, Response};
+use super::comment;
+use super::keymap::{Keymap, Platform, action};
+use super::txt::edtr::Widget;
+
+/// The keymap actions an embedded editor runs itself; shortcuts for the others are left to
+/// the application.
+const ACTIONS: &[&str] = &[
+    action::UNDO,
+    action::REDO,
+    action::SELECT_ALL,
+    action::DELETE_LINE,
+    action::DUPLICATE_LINE,
+    action::MOVE_LINE_UP,
+    action::MOVE_LINE_DOWN,
+    action::JOIN_LINES,
+    action::TOGGLE_COMMENT,
+    action::SORT_LINES,
+    action::SORT_LINES_CASE_INSENSITIVE,
+    action::SORT_LINES_REVERSE,
+    action::UPPER_CASE,
+    action::LOWER_CASE,
+    action::TITLE_CASE,
+    action::JUMP_TO_BRACKET,
+    action::TOGGLE_FOLD,
+    action::FOLD_ALL,
+    action::UNFOLD_ALL,
+];
+
+/// A code editor pane for other egui applications: a buffer, the widget that draws and edits
+/// it, and the keymap for the editing shortcuts, bundled so that one call per frame shows it.
+///
+/// The application owns the view and calls [`EditorView::show`] wherever the pane goes. See
+/// `examples/embed.rs` for a complete program.
+pub struct EditorView {
+    state: State,
+    buffer_id: ID,
+    keymap: Keymap,
+    theme: Theme,
+    pub show_line_numbers: bool,
+    /// The font size in points; pinching changes it.
+    pub font_size: f32,
+    pub tab_size: usize,
+    /// Column at which lines are soft-wrapped, if any.
+    pub wrap_column: Option<usize>,
+    /// Whether the pane receives keyboard input.
+    pub focused: bool,
+}
+
+impl EditorView {
+    /// Creates an editor holding `text`, with LED's default keymap and theme.
+    pub fn new(text: impl Into<String>) -> Self {
+        let mut state = State::new();
+        let buffer_id = state.create_buffer(text.into());
+        Self {
+            state,
+            buffer_id,
+            keymap: Keymap::defaults(Platform::current()),
+            theme: saran::style::System::new().get_active_theme().clone(),
+            show_line_numbers: true,
+            font_size: 14.0,
+            tab_size: 4,
+            wrap_column: None,
+            focused: true,
+        }
+    }
+
+    /// Replaces the keymap, e.g. with one loaded from a project by [`Keymap::load`].
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Replaces the colors the editor draws with.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the text being edited.
+    pub fn text(&self) -> String {
+        self.state
+            .get_buffer_text(self.buffer_id)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the text as a single edit that can be undone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the edit could not be applied.
+    pub fn set_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.state.replace_text(self.buffer_id, text)?;
+        Ok(())
+    }
+
+    /// Returns whether the text changed since the editor was created or last marked saved.
+    pub fn is_modified(&self) -> bool {
+        self.state
+            .metadata(self.buffer_id)
+            .is_some_and(|meta| meta.modified)
+    }
+
+    /// Marks the current text as saved, e.g. after the application wrote it to a file.
+    pub fn mark_saved(&mut self) {
+        self.state.mark_saved(self.buffer_id);
+    }
+
+    /// Returns the ID of the buffer being edited, for use with [`EditorView::state_mut`].
+    pub fn buffer_id(&self) -> ID {
+        self.buffer_id
+    }
+
+    /// Returns the editor state, for commands the view doesn't offer itself.
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Shows the editor in the space left in `ui` and handles this frame's input, including
+    /// the editing shortcuts of the keymap while the pane is focused.
+    ///
+    /// # Returns
+    ///
+    /// What the input did to the text and the cursor.
+    pub fn show(&mut self, ui: &mut Ui) -> Response {
+        let mut response = Response {
+            commands: Vec::new(),
+            cursor_moved: false,
+            text_changed: false,
+        };
+        if self.focused {
+            for name in self
+                .keymap
+                .take_actions(ui.ctx(), |name| ACTIONS.contains(&name))
+            {
+                response.text_changed |= self.run_action(&name);
+                response.cursor_moved = true;
+            }
+        }
+
+        let mut widget = Widget::new(self.buffer_id, &mut self.state, self.theme.clone());
+        widget.show_line_numbers = self.show_line_numbers;
+        widget.font_size = self.font_size;
+        widget.tab_size = self.tab_size;
+        widget.wrap_column = self.wrap_column;
+        widget.focused = self.focused;
+        if let Some(shown) = widget.show(ui) {
+            response.commands = shown.commands;
+            response.cursor_moved |= shown.cursor_moved;
+            response.text_changed |= shown.text_changed;
+        }
+        self.font_size = widget.font_size;
+        response
+    }
+
+    /// Runs one of the editing actions on the buffer.
+    ///
+    /// # Returns
+    ///
+    /// Whether the text may have changed.
+    fn run_action(&mut self, name: &str) -> bool {
+        let buffer_id = self.buffer_id;
+        let command = match name {
+            action::UNDO | action::REDO => {
+                let stepped = if name == action::UNDO {
+                    self.state.undo(buffer_id)
+                } else {
+                    self.state.redo(buffer_id)
+                };
+                return stepped.unwrap_or_else(|e| {
+                    eprintln!("Failed to {}: {:#}", name, e);
+                    false
+                });
+            }
+            action::SELECT_ALL => Command::SelectAll { buffer_id },
+            action::DELETE_LINE => Command::DeleteLines { buffer_id },
+            action::DUPLICATE_LINE => Command::DuplicateLines { buffer_id },
+            action::MOVE_LINE_UP => Command::MoveLines {
+                buffer_id,
+                up: true,
+            },
+            action::MOVE_LINE_DOWN => Command::MoveLines {
+                buffer_id,
+                up: false,
+            },
+            action::JOIN_LINES => Command::JoinLines { buffer_id },
+            action::SORT_LINES
+            | action::SORT_LINES_CASE_INSENSITIVE
+            | action::SORT_LINES_REVERSE => Command::SortLines {
+                buffer_id,
+                case_insensitive: name == action::SORT_LINES_CASE_INSENSITIVE,
+                reverse: name == action::SORT_LINES_REVERSE,
+            },
+            action::TOGGLE_COMMENT => {
+                return comment::toggle(&mut self.state, buffer_id).unwrap_or_else(|e| {
+                    eprintln!("Failed to {}: {:#}", name, e);
+                    false
+                });
+            }
+            action::UPPER_CASE | action::LOWER_CASE | action::TITLE_CASE => {
+                let case = match name {
+                    action::UPPER_CASE => Case::Upper,
+                    action::LOWER_CASE => Case::Lower,
+                    _ => Case::Title,
+                };
+                return case::convert_selection(&mut self.state, buffer_id, case).unwrap_or_else(
+                    |e| {
+                        eprintln!("Failed to {}: {:#}", name, e);
+                        false
+                    },
+                );
+            }
+            action::TOGGLE_FOLD => {
+                let line = self
+                    .state
+                    .get_cursor_state(buffer_id)
+                    .map_or(0, |cursor| cursor.position().line);
+                self.state.toggle_fold(buffer_id, line, self.tab_size);
+                return false;
+            }
+            action::FOLD_ALL => {
+                self.state.fold_all(buffer_id, self.tab_size);
+                return false;
+            }
+            action::UNFOLD_ALL => {
+                self.state.folds_mut().unfold_all(buffer_id);
+                return false;
+            }
+            action::JUMP_TO_BRACKET => {
+                if let Err(e) = brackets::jump(&mut self.state, buffer_id) {
+                    eprintln!("Failed to {}: {:#}", name, e);
+                }
+                return false;
+            }
+            _ => return false,
+        };
+        let edits = !matches!(command, Command::SelectAll { .. });
+        match self.state.execute_command(command) {
+            Ok(()) => edits,
+            Err(e) => {
+                eprintln!("Failed to run {}: {:#}", name, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing_actions_run_on_the_buffer() {
+        let mut editor = EditorView::new("one\ntwo\n");
+        assert!(!editor.is_modified());
+
+        assert!(editor.run_action(action::DUPLICATE_LINE));
+        assert_eq!(editor.text(), "one\none\ntwo\n");
+        assert!(editor.is_modified());
+        assert!(editor.run_action(action::UNDO));
+        assert_eq!(editor.text(), "one\ntwo\n");
+        assert!(!editor.run_action(action::SAVE_FILE));
+
+        editor.set_text("three\n").unwrap();
+        editor.mark_saved();
+        assert_eq!(editor.text(), "three\n");
+        assert!(!editor.is_modified());
+    }
+}
@@ -0,0 +1,195 @@
+use super::commands::editor::Command;
+use super::settings::{BomOnSave, Settings};
+use super::types::buffer::ID;
+
+/// The UTF-8 byte order mark as it appears in decoded text.
+pub const BOM: char = '\u{feff}';
+
+/// Returns whether the text starts with a UTF-8 byte order mark.
+pub fn has_bom(text: &str) -> bool {
+    text.starts_with(BOM)
+}
+
+/// Returns whether the text is missing a line break at its end.
+///
+/// Empty text has no last line, so it is never reported as missing one.
+pub fn lacks_final_newline(text: &str) -> bool {
+    !text.is_empty() && !text.ends_with('\n')
+}
+
+/// Builds the edit that adds the byte order mark if it is missing, or removes it if present.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer to edit.
+/// * `text` - The buffer's current text.
+pub fn toggle_bom(buffer_id: ID, text: &str) -> Command {
+    if has_bom(text) {
+        Command::DeleteText {
+            buffer_id,
+            start: 0,
+            length: BOM.len_utf8(),
+        }
+    } else {
+        Command::InsertText {
+            buffer_id,
+            offset: 0,
+            text: BOM.to_string(),
+        }
+    }
+}
+
+/// Builds the edit that appends a line break, if the text is missing one.
+///
+/// The line break matches the style of the text: `\r\n` if the first line ends with it,
+/// otherwise `\n`.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer to edit.
+/// * `text` - The buffer's current text.
+pub fn append_final_newline(buffer_id: ID, text: &str) -> Option<Command> {
+    if !lacks_final_newline(text) {
+        return None;
+    }
+    let crlf = text.find('\n').is_some_and(|i| text[..i].ends_with('\r'));
+    Some(Command::InsertText {
+        buffer_id,
+        offset: text.len(),
+        text: if crlf { "\r\n" } else { "\n" }.to_string(),
+    })
+}
+
+/// Builds the edits that remove the spaces and tabs at the end of every line.
+///
+/// The edits are ordered from the end of the text to its start, so each one leaves the offsets
+/// of the next unchanged. Line breaks, `\r\n` included, are kept.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer to edit.
+/// * `text` - The buffer's current text.
+pub fn trim_trailing_whitespace(buffer_id: ID, text: &str) -> Vec<Command> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let trimmed = content.trim_end_matches([' ', '\t']);
+        if trimmed.len() < content.len() {
+            commands.push(Command::DeleteText {
+                buffer_id,
+                start: start + trimmed.len(),
+                length: content.len() - trimmed.len(),
+            });
+        }
+        start += line.len();
+    }
+    commands.reverse();
+    commands
+}
+
+/// Builds the edits that bring a buffer in line with the save settings before it is written.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer about to be saved.
+/// * `text` - The buffer's current text.
+/// * `settings` - The user's settings.
+///
+/// # Returns
+///
+/// The edits, in the order they should be applied.
+pub fn prepare_for_save(buffer_id: ID, text: &str, settings: &Settings) -> Vec<Command> {
+    let mut commands = Vec::new();
+    // Append first, so the BOM edit at offset zero does not shift the append offset
+    if settings.ensure_final_newline
+        && let Some(command) = append_final_newline(buffer_id, text)
+    {
+        commands.push(command);
+    }
+    if settings.trim_trailing_whitespace {
+        commands.extend(trim_trailing_whitespace(buffer_id, text));
+    }
+    let wants_bom = match settings.bom_on_save {
+        BomOnSave::Preserve => has_bom(text),
+        BomOnSave::Add => true,
+        BomOnSave::Remove => false,
+    };
+    if wants_bom != has_bom(text) {
+        commands.push(toggle_bom(buffer_id, text));
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::buffer::editor::State;
+
+    fn apply(text: &str, commands: impl Fn(ID, &str) -> Vec<Command>) -> String {
+        let mut state = State::new();
+        let id = state.create_buffer(text.to_string());
+        for command in commands(id, text) {
+            state.execute_command(command).unwrap();
+        }
+        state.get_buffer_text(id).unwrap()
+    }
+
+    #[test]
+    fn detects_bom_and_missing_newline() {
+        assert!(has_bom("\u{feff}text"));
+        assert!(!has_bom("text"));
+        assert!(lacks_final_newline("text"));
+        assert!(!lacks_final_newline("text\n"));
+        assert!(!lacks_final_newline(""));
+    }
+
+    #[test]
+    fn toggle_bom_adds_and_removes() {
+        let added = apply("text", |id, text| vec![toggle_bom(id, text)]);
+        assert_eq!(added, "\u{feff}text");
+        let removed = apply(&added, |id, text| vec![toggle_bom(id, text)]);
+        assert_eq!(removed, "text");
+    }
+
+    #[test]
+    fn append_final_newline_matches_line_endings() {
+        let unix = apply("a\nb", |id, text| {
+            append_final_newline(id, text).into_iter().collect()
+        });
+        assert_eq!(unix, "a\nb\n");
+        let windows = apply("a\r\nb", |id, text| {
+            append_final_newline(id, text).into_iter().collect()
+        });
+        assert_eq!(windows, "a\r\nb\r\n");
+        let id = ID::new();
+        assert!(append_final_newline(id, "done\n").is_none());
+    }
+
+    #[test]
+    fn trim_trailing_whitespace_keeps_line_breaks() {
+        let trimmed = apply("a  \n\tb\t\r\n \nc \t", trim_trailing_whitespace);
+        assert_eq!(trimmed, "a\n\tb\r\n\nc");
+        assert!(trim_trailing_whitespace(ID::new(), "clean\n\n").is_empty());
+    }
+
+    #[test]
+    fn prepare_for_save_applies_settings() {
+        let settings = Settings {
+            bom_on_save: BomOnSave::Add,
+            ensure_final_newline: true,
+            trim_trailing_whitespace: true,
+            ..Settings::default()
+        };
+        let saved = apply("text  \nmore ", |id, text| {
+            prepare_for_save(id, text, &settings)
+        });
+        assert_eq!(saved, "\u{feff}text\nmore\n");
+
+        let defaults = Settings::default();
+        let untouched = apply("\u{feff}text", |id, text| {
+            prepare_for_save(id, text, &defaults)
+        });
+        assert_eq!(untouched, "\u{feff}text");
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use super::palette;
+
+/// How many recently used directories the picker offers.
+pub const MAX_RECENT: usize = 8;
+
+/// A file or directory in the directory the picker lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Returns whether a character separates the components of a typed path. `/` does on every
+/// platform.
+fn is_separator(c: char) -> bool {
+    c == '/' || std::path::is_separator(c)
+}
+
+/// Splits a typed path into the directory to list and the name typed so far in it.
+///
+/// # Arguments
+///
+/// * `input` - What the user typed; relative paths start at `base`.
+/// * `base` - The directory relative paths start at, such as the project root.
+pub fn split<'a>(input: &'a str, base: &Path) -> (PathBuf, &'a str) {
+    let (dir, name) = match input.rfind(is_separator) {
+        // The root itself keeps its separator
+        Some(0) => (&input[..1], &input[1..]),
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input),
+    };
+    (base.join(dir), name)
+}
+
+/// Lists a directory, subdirectories first and each group by name. Hidden entries, whose names
+/// start with a dot, are only listed when asked for.
+///
+/// # Errors
+///
+/// Returns an error if the directory can't be read.
+pub fn list(dir: &Path, show_hidden: bool) -> std::io::Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| Entry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            // Follows symbolic links, so a link to a directory can be entered
+            is_dir: entry.path().is_dir(),
+        })
+        .filter(|entry| show_hidden || !entry.name.starts_with('.'))
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// Returns the entries matching a typed name, best match first, as the command palette ranks
+/// its commands. Without a name, all entries match in their listed order.
+pub fn filter<'a>(entries: &'a [Entry], name: &str) -> Vec<&'a Entry> {
+    let mut matches: Vec<(i32, usize, &Entry)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| Some((palette::score(name, &entry.name)?, index, entry)))
+        .collect();
+    matches.sort_by(|(a_score, a_index, _), (b_score, b_index, _)| {
+        b_score.cmp(a_score).then(a_index.cmp(b_index))
+    });
+    matches.into_iter().map(|(_, _, entry)| entry).collect()
+}
+
+/// Replaces the name typed last in a path with an entry's, ending with a separator if the
+/// entry is a directory so that its content is listed next.
+pub fn complete(input: &str, entry: &Entry) -> String {
+    let dir = input.rfind(is_separator).map_or("", |i| &input[..=i]);
+    let separator = if entry.is_dir { "/" } else { "" };
+    format!("{}{}{}", dir, entry.name, separator)
+}
+
+/// Moves a directory to the front of the recently used ones, keeping at most [`MAX_RECENT`].
+pub fn remember(recent: &mut Vec<PathBuf>, dir: PathBuf) {
+    recent.retain(|known| *known != dir);
+    recent.insert(0, dir);
+    recent.truncate(MAX_RECENT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool) -> Entry {
+        Entry {
+            name: name.to_string(),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn typed_paths_split_into_directory_and_name() {
+        let base = Path::new("/project");
+        assert_eq!(split("src/ma", base), (PathBuf::from("/project/src"), "ma"));
+        assert_eq!(split("src/", base), (PathBuf::from("/project/src"), ""));
+        assert_eq!(split("Cargo", base), (PathBuf::from("/project"), "Cargo"));
+        assert_eq!(split("/etc/ho", base), (PathBuf::from("/etc"), "ho"));
+        assert_eq!(split("/us", base), (PathBuf::from("/"), "us"));
+    }
+
+    #[test]
+    fn directories_are_listed_first_and_hidden_entries_on_request() {
+        let dir = std::env::temp_dir().join(format!("led-picker-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join(".gitignore"), "").unwrap();
+
+        let names = |show_hidden| {
+            list(&dir, show_hidden)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(false), ["src", "Cargo.toml"]);
+        assert_eq!(names(true), [".git", "src", ".gitignore", "Cargo.toml"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn names_are_matched_and_completed() {
+        let entries = [
+            entry("src", true),
+            entry("README.md", false),
+            entry("rustfmt.toml", false),
+        ];
+        let names = |name| {
+            filter(&entries, name)
+                .into_iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(""), ["src", "README.md", "rustfmt.toml"]);
+        assert_eq!(names("rm"), ["README.md", "rustfmt.toml"]);
+        assert_eq!(names("rf"), ["rustfmt.toml"]);
+
+        assert_eq!(complete("../s", &entries[0]), "../src/");
+        assert_eq!(complete("rea", &entries[1]), "README.md");
+    }
+
+    #[test]
+    fn recent_directories_are_most_recent_first() {
+        let mut recent = Vec::new();
+        for i in 0..MAX_RECENT + 2 {
+            remember(&mut recent, PathBuf::from(format!("/dir{i}")));
+        }
+        remember(&mut recent, PathBuf::from("/dir5"));
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0], PathBuf::from("/dir5"));
+        assert_eq!(recent[1], PathBuf::from(format!("/dir{}", MAX_RECENT + 1)));
+    }
+}
@@ -0,0 +1,74 @@
+use super::types::Position;
+
+/// Why a typed location can't be gone to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invalid {
+    /// The text isn't a line number optionally followed by `:` and a column number.
+    Syntax,
+    /// The line isn't in the buffer, which has this many lines.
+    Line { lines: usize },
+}
+
+/// Reads a location typed as `line` or `line:column` into a position in a text.
+///
+/// Lines and columns count from 1, columns in characters as people count them; the returned
+/// position's column is in bytes like every [`Position`]. A column past the end of its line
+/// goes to the end of the line; without a column, the location is the line's start.
+///
+/// # Errors
+///
+/// Returns [`Invalid::Syntax`] if the input isn't a location and [`Invalid::Line`] if the
+/// text has no such line.
+pub fn parse(input: &str, text: &str) -> Result<Position, Invalid> {
+    let (line, column) = match input.trim().split_once(':') {
+        Some((line, column)) => (line.trim(), Some(column.trim())),
+        None => (input.trim(), None),
+    };
+    let number = |digits: &str| {
+        digits
+            .parse::<usize>()
+            .ok()
+            .filter(|number| *number > 0 && digits.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let line = number(line).ok_or(Invalid::Syntax)?;
+    let column = match column {
+        Some(column) => number(column).ok_or(Invalid::Syntax)?,
+        None => 1,
+    };
+
+    let lines = text.split('\n').count();
+    let content = text
+        .split('\n')
+        .nth(line - 1)
+        .ok_or(Invalid::Line { lines })?;
+    let content = content.trim_end_matches('\r');
+    let column = content
+        .char_indices()
+        .nth(column - 1)
+        .map_or(content.len(), |(index, _)| index);
+    Ok(Position {
+        line: line - 1,
+        column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_are_read_and_checked_against_the_text() {
+        let text = "first\r\nzwölf\n";
+        let at = |line, column| Position { line, column };
+        assert_eq!(parse("2", text), Ok(at(1, 0)));
+        // The fourth character of "zwölf" starts at byte 4
+        assert_eq!(parse(" 2:4 ", text), Ok(at(1, 4)));
+        assert_eq!(parse("1:99", text), Ok(at(0, 5)));
+        assert_eq!(parse("3", text), Ok(at(2, 0)));
+        assert_eq!(parse("4", text), Err(Invalid::Line { lines: 3 }));
+
+        for input in ["", "0", "a", "2:", ":3", "2:0", "+2", "-1", "1:2:3"] {
+            assert_eq!(parse(input, text), Err(Invalid::Syntax), "{:?}", input);
+        }
+    }
+}
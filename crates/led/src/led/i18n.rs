@@ -0,0 +1,497 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::fmt;
+use std::sync::{Arc, LazyLock, RwLock};
+use unic_langid::LanguageIdentifier;
+
+/// The translations that ship with LED: locale, and the bundle's source.
+const BUILTIN: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en.ftl")),
+    ("de", include_str!("../../locales/de.ftl")),
+];
+/// The locale used for messages missing from the selected language.
+const FALLBACK: &str = "en";
+/// The message naming a bundle's language in that language, as listed in the preferences.
+const LANGUAGE_NAME: &str = "language-name";
+
+/// A value substituted into a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Arg {
+    Text(String),
+    /// A count, which also selects plural forms.
+    Number(i64),
+}
+
+impl From<&str> for Arg {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<usize> for Arg {
+    fn from(n: usize) -> Self {
+        Self::Number(n as i64)
+    }
+}
+
+impl From<u64> for Arg {
+    fn from(n: u64) -> Self {
+        Self::Number(n as i64)
+    }
+}
+
+impl From<i64> for Arg {
+    fn from(n: i64) -> Self {
+        Self::Number(n)
+    }
+}
+
+/// The messages of one language, in the [Fluent](https://projectfluent.org) syntax:
+///
+/// ```text
+/// # Comments start with a hash
+/// save = Save
+/// greeting = Hello, { $name }!
+/// unsaved = { $count ->
+///     [one] One buffer has unsaved changes.
+///    *[other] { $count } buffers have unsaved changes.
+/// }
+/// ```
+///
+/// Bundles are cheap to clone; clones share the parsed messages.
+#[derive(Clone)]
+pub struct Bundle {
+    locale: String,
+    bundle: Arc<FluentBundle<FluentResource>>,
+}
+
+impl fmt::Debug for Bundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bundle")
+            .field("locale", &self.locale)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Bundle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.bundle, &other.bundle)
+    }
+}
+
+impl Bundle {
+    /// Parses a bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The language the bundle is in, such as `de` or `pt-BR`.
+    /// * `source` - The messages.
+    ///
+    /// # Returns
+    ///
+    /// The bundle and a description of each message that couldn't be parsed and was left out.
+    pub fn parse(locale: &str, source: &str) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(
+            |(resource, parse_errors)| {
+                for error in parse_errors {
+                    let line = source[..error.pos.start.min(source.len())]
+                        .matches('\n')
+                        .count();
+                    errors.push(format!("line {}: {}", line + 1, error));
+                }
+                resource
+            },
+        );
+        // Plural rules are looked up by language; an unknown one gets the default rules
+        let language: LanguageIdentifier = locale.parse().unwrap_or_default();
+        let mut bundle = FluentBundle::new_concurrent(vec![language]);
+        // Bidi isolation marks around arguments show up as boxes in the interface
+        bundle.set_use_isolating(false);
+        if let Err(duplicates) = bundle.add_resource(resource) {
+            errors.extend(duplicates.iter().map(ToString::to_string));
+        }
+        let bundle = Self {
+            locale: locale.to_string(),
+            bundle: Arc::new(bundle),
+        };
+        (bundle, errors)
+    }
+
+    /// Returns the language the bundle is in.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Formats a message, or returns `None` if the bundle doesn't have it. An argument the
+    /// message uses but isn't given shows as `{$name}`.
+    pub fn format(&self, id: &str, args: &[(&str, Arg)]) -> Option<String> {
+        let pattern = self.bundle.get_message(id)?.value()?;
+        let mut fluent_args = FluentArgs::new();
+        for (name, arg) in args {
+            match arg {
+                Arg::Text(text) => fluent_args.set(*name, text.as_str()),
+                Arg::Number(n) => fluent_args.set(*name, *n),
+            }
+        }
+        let mut errors = Vec::new();
+        let text = self
+            .bundle
+            .format_pattern(pattern, Some(&fluent_args), &mut errors);
+        Some(text.into_owned())
+    }
+}
+
+/// Returns the language part of a locale, such as `pt` for `pt-BR` or `pt_BR.UTF-8`.
+fn primary_language(locale: &str) -> &str {
+    locale.split(['-', '_', '.', '@']).next().unwrap_or(locale)
+}
+
+/// Looks messages up in a chain of bundles: the selected language first, then English.
+#[derive(Debug, Clone, Default)]
+pub struct Localizer {
+    bundles: Vec<Bundle>,
+}
+
+impl Localizer {
+    /// Creates a localizer for a language.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The language to show, such as `de` or `pt-BR`.
+    /// * `available` - The bundles to choose from. Of several bundles for the same language,
+    ///   earlier ones win.
+    pub fn new(language: &str, available: &[Bundle]) -> Self {
+        let primary = primary_language(language);
+        let mut bundles: Vec<Bundle> = Vec::new();
+        // Exact matches, then the language in general, then the fallback
+        let stages: [&dyn Fn(&Bundle) -> bool; 3] = [
+            &|b| b.locale.eq_ignore_ascii_case(language),
+            &|b| primary_language(&b.locale).eq_ignore_ascii_case(primary),
+            &|b| b.locale == FALLBACK,
+        ];
+        for matches in stages {
+            for bundle in available.iter().filter(|b| matches(b)) {
+                if !bundles.contains(bundle) {
+                    bundles.push(bundle.clone());
+                }
+            }
+        }
+        Self { bundles }
+    }
+
+    /// Formats a message from the first bundle that has it.
+    pub fn format(&self, id: &str, args: &[(&str, Arg)]) -> Option<String> {
+        self.bundles
+            .iter()
+            .find_map(|bundle| bundle.format(id, args))
+    }
+}
+
+/// The language selection and the bundles it draws from.
+#[derive(Debug, Default)]
+struct State {
+    /// The language setting the localizer was built for; empty means the system language.
+    setting: String,
+    /// Bundles added by plugins, which take precedence over the built-in ones.
+    plugin_bundles: Vec<Bundle>,
+    localizer: Localizer,
+}
+
+impl State {
+    fn rebuild(&mut self) {
+        let mut available = self.plugin_bundles.clone();
+        available.extend(builtin_bundles());
+        self.localizer = Localizer::new(&resolve(&self.setting), &available);
+    }
+}
+
+static STATE: LazyLock<RwLock<State>> = LazyLock::new(|| {
+    let mut state = State::default();
+    state.rebuild();
+    RwLock::new(state)
+});
+
+fn builtin_bundles() -> impl Iterator<Item = Bundle> {
+    BUILTIN
+        .iter()
+        .map(|(locale, source)| Bundle::parse(locale, source).0)
+}
+
+/// Returns the language to show for a setting: the setting itself, or the system language if
+/// it is empty.
+fn resolve(setting: &str) -> String {
+    if !setting.is_empty() {
+        return setting.to_string();
+    }
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .map(|value| primary_language(&value).to_string())
+        .unwrap_or_else(|| FALLBACK.to_string())
+}
+
+/// Selects the language of the user interface.
+///
+/// # Arguments
+///
+/// * `setting` - A language such as `de`, or an empty string for the system language.
+pub fn set_language(setting: &str) {
+    if STATE.read().is_ok_and(|state| state.setting == setting) {
+        return;
+    }
+    if let Ok(mut state) = STATE.write() {
+        state.setting = setting.to_string();
+        state.rebuild();
+    }
+}
+
+/// Adds a plugin's translations, which take precedence over LED's own for the same messages.
+///
+/// # Returns
+///
+/// A description of each message that couldn't be parsed and was left out.
+pub fn add_bundle(locale: &str, source: &str) -> Vec<String> {
+    let (bundle, errors) = Bundle::parse(locale, source);
+    if let Ok(mut state) = STATE.write() {
+        state.plugin_bundles.push(bundle);
+        state.rebuild();
+    }
+    errors
+}
+
+/// Returns the languages the interface can be shown in, as locale and name, sorted by name.
+pub fn languages() -> Vec<(String, String)> {
+    let mut bundles: Vec<Bundle> = builtin_bundles().collect();
+    if let Ok(state) = STATE.read() {
+        bundles.extend(state.plugin_bundles.iter().cloned());
+    }
+    let mut languages: Vec<(String, String)> = Vec::new();
+    for bundle in &bundles {
+        if languages.iter().any(|(locale, _)| *locale == bundle.locale) {
+            continue;
+        }
+        // Plugin bundles for a built-in language usually don't name it
+        if let Some(name) = bundle.format(LANGUAGE_NAME, &[]) {
+            languages.push((bundle.locale.clone(), name));
+        }
+    }
+    languages.sort_by(|a, b| a.1.cmp(&b.1));
+    languages
+}
+
+/// Returns a message in the selected language, or its id if no bundle has it.
+pub fn tr(id: &str) -> String {
+    tr_with(id, &[])
+}
+
+/// Returns a message in the selected language with arguments substituted, or its id if no
+/// bundle has it.
+pub fn tr_with(id: &str, args: &[(&str, Arg)]) -> String {
+    tr_or(id, args, id)
+}
+
+/// Returns a message in the selected language, or `fallback` if no bundle has it.
+pub fn tr_or(id: &str, args: &[(&str, Arg)], fallback: &str) -> String {
+    STATE
+        .read()
+        .ok()
+        .and_then(|state| state.localizer.format(id, args))
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Returns the title of a built-in action or Lua command in the command palette: the message
+/// `action-<id>`, with the dots and underscores of the id written as dashes, or `title`.
+pub fn action_title(id: &str, title: &str) -> String {
+    tr_or(
+        &format!("action-{}", id.replace(['.', '_'], "-")),
+        &[],
+        title,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+# A comment
+title = Notes
+greeting = Hello, { $name }!
+braces = Use { "{" } and { "}" }
+unsaved = { $count ->
+    [0] Nothing is unsaved.
+    [one] One buffer has unsaved changes.
+   *[other] { $count } buffers have unsaved changes.
+}
+multiline =
+    First line
+    second line
+"#;
+
+    #[test]
+    fn messages_format_arguments_and_plurals() {
+        let (bundle, errors) = Bundle::parse("en", SOURCE);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(bundle.format("title", &[]).as_deref(), Some("Notes"));
+        assert_eq!(
+            bundle
+                .format("greeting", &[("name", "Ada".into())])
+                .as_deref(),
+            Some("Hello, Ada!")
+        );
+        assert_eq!(
+            bundle.format("greeting", &[]).as_deref(),
+            Some("Hello, {$name}!")
+        );
+        assert_eq!(bundle.format("braces", &[]).as_deref(), Some("Use { and }"));
+        let unsaved = |n: usize| bundle.format("unsaved", &[("count", n.into())]).unwrap();
+        assert_eq!(unsaved(0), "Nothing is unsaved.");
+        assert_eq!(unsaved(1), "One buffer has unsaved changes.");
+        assert_eq!(unsaved(3), "3 buffers have unsaved changes.");
+        assert_eq!(
+            bundle.format("multiline", &[]).as_deref(),
+            Some("First line\nsecond line")
+        );
+        assert_eq!(bundle.format("missing", &[]), None);
+    }
+
+    #[test]
+    fn invalid_messages_are_reported_and_skipped() {
+        let (bundle, errors) = Bundle::parse(
+            "en",
+            "ok = Fine\nno equals sign\nbad = { $n ->\n    [one] x\n}\n9lives = x\nopen = { $n",
+        );
+        assert_eq!(bundle.format("ok", &[]).as_deref(), Some("Fine"));
+        for id in ["bad", "9lives", "open"] {
+            assert_eq!(bundle.format(id, &[]), None);
+        }
+        // Errors are reported where they are found; "9lives" is skipped with the broken
+        // message before it
+        assert_eq!(
+            errors,
+            [
+                "line 2: Expected a token starting with \"=\"",
+                "line 5: The select expression must have a default variant",
+                "line 7: Expected a token starting with \"}\"",
+            ]
+        );
+
+        let (_, errors) = Bundle::parse("en", "twice = a\ntwice = b");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn plurals_follow_the_language() {
+        let source =
+            "files = { $n ->\n    [one] one\n    [few] few\n    [many] many\n   *[other] other\n}";
+        let forms = |locale| -> Vec<String> {
+            let (bundle, _) = Bundle::parse(locale, source);
+            [0usize, 1, 2, 5, 11, 21, 22, 25]
+                .iter()
+                .map(|&n| bundle.format("files", &[("n", n.into())]).unwrap())
+                .collect()
+        };
+        assert_eq!(
+            forms("en-US"),
+            [
+                "other", "one", "other", "other", "other", "other", "other", "other"
+            ]
+        );
+        assert_eq!(
+            forms("fr"),
+            [
+                "one", "one", "other", "other", "other", "other", "other", "other"
+            ]
+        );
+        assert_eq!(
+            forms("ru"),
+            ["many", "one", "few", "many", "many", "one", "few", "many"]
+        );
+        assert_eq!(forms("ja"), ["other"; 8]);
+    }
+
+    #[test]
+    fn localizer_falls_back_to_english() {
+        let (en, _) = Bundle::parse("en", "save = Save\nquit = Quit");
+        let (de, _) = Bundle::parse("de", "save = Speichern");
+        let (plugin, _) = Bundle::parse("de-AT", "save = Sichern");
+        let available = [plugin, de, en];
+        let localizer = Localizer::new("de-AT", &available);
+        assert_eq!(localizer.format("save", &[]).as_deref(), Some("Sichern"));
+        assert_eq!(localizer.format("quit", &[]).as_deref(), Some("Quit"));
+        let localizer = Localizer::new("de", &available);
+        assert_eq!(localizer.format("save", &[]).as_deref(), Some("Speichern"));
+        let localizer = Localizer::new("fi", &available);
+        assert_eq!(localizer.format("save", &[]).as_deref(), Some("Save"));
+    }
+
+    /// Returns the message ids a bundle defines, which start the lines that aren't indented.
+    fn ids(source: &str) -> Vec<String> {
+        let (_, errors) = Bundle::parse("en", source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once('='))
+            .map(|(id, _)| id.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn builtin_translations_are_complete() {
+        let english = ids(BUILTIN[0].1);
+        for (locale, source) in BUILTIN {
+            let mut missing: Vec<String> = english
+                .iter()
+                .filter(|id| !ids(source).contains(id))
+                .cloned()
+                .collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} lacks {:?}", locale, missing);
+            for id in ids(source) {
+                assert!(
+                    english.contains(&id),
+                    "{} has unknown message {}",
+                    locale,
+                    id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_message_used_in_the_interface_exists() {
+        let english = ids(BUILTIN[0].1);
+        let mut prefixes: Vec<String> = english
+            .iter()
+            .map(|id| format!("\"{}-", id.split('-').next().unwrap()))
+            .collect();
+        prefixes.dedup();
+        for source in [include_str!("txt.rs"), include_str!("settings.rs")] {
+            for prefix in &prefixes {
+                for (start, _) in source.match_indices(prefix.as_str()) {
+                    let rest = &source[start + 1..];
+                    let id = &rest[..rest.find('"').unwrap()];
+                    if id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                        assert!(english.contains(&id.to_string()), "no message {}", id);
+                    }
+                }
+            }
+        }
+
+        let (bundle, _) = Bundle::parse("en", BUILTIN[0].1);
+        let localizer = Localizer::new("en", &[bundle]);
+        for (id, title) in super::super::keymap::action::TITLES {
+            let message = format!("action-{}", id.replace(['.', '_'], "-"));
+            assert_eq!(localizer.format(&message, &[]).as_deref(), Some(*title));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+/// Identifies the idle period work was started in, to tell whether it has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ticket(u64);
+
+/// A registered piece of idle work.
+#[derive(Debug, Clone)]
+struct Job {
+    name: String,
+    /// How long the user must have been idle before the job runs.
+    delay: Duration,
+    /// Whether the job ran since the last input.
+    ran: bool,
+}
+
+/// Work that waits until the user stops giving input for a while, such as checking a whole
+/// document or compacting buffers.
+///
+/// Subsystems register jobs by name with the idle time they need. The owner reports input with
+/// [`Idle::input`] and asks [`Idle::due`] each frame which jobs to run; each job runs once per
+/// idle period. Input cancels work in progress: work started with a [`Ticket`] checks
+/// [`Idle::is_cancelled`] between increments and stops once the user is back.
+#[derive(Debug, Clone)]
+pub struct Idle {
+    jobs: Vec<Job>,
+    /// When the last input happened, or when the registry was created.
+    last_input: Instant,
+    /// Counts the inputs, so tickets from earlier idle periods can be told apart.
+    generation: u64,
+}
+
+impl Idle {
+    /// Creates a registry with no jobs, counting the user as idle from `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            jobs: Vec::new(),
+            last_input: now,
+            generation: 0,
+        }
+    }
+
+    /// Registers a job, or changes the delay of the job with that name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Names the job; registering the same name again replaces it.
+    /// * `delay` - How long the user must have been idle before the job runs.
+    pub fn register(&mut self, name: &str, delay: Duration) {
+        match self.jobs.iter_mut().find(|job| job.name == name) {
+            Some(job) => job.delay = delay,
+            None => self.jobs.push(Job {
+                name: name.to_string(),
+                delay,
+                ran: false,
+            }),
+        }
+    }
+
+    /// Removes a job.
+    pub fn unregister(&mut self, name: &str) {
+        self.jobs.retain(|job| job.name != name);
+    }
+
+    /// Reports input from the user, which restarts the wait for every job and cancels the work
+    /// of the idle period that ended.
+    pub fn input(&mut self, now: Instant) {
+        self.last_input = now;
+        self.generation += 1;
+        for job in &mut self.jobs {
+            job.ran = false;
+        }
+    }
+
+    /// Returns the jobs to run now, in the order they were registered, and records that they
+    /// ran in this idle period.
+    pub fn due(&mut self, now: Instant) -> Vec<String> {
+        let idle = now.saturating_duration_since(self.last_input);
+        self.jobs
+            .iter_mut()
+            .filter(|job| !job.ran && idle >= job.delay)
+            .map(|job| {
+                job.ran = true;
+                job.name.clone()
+            })
+            .collect()
+    }
+
+    /// Returns when the next job is due, if one is waiting, so the owner can schedule a frame
+    /// for it.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.jobs
+            .iter()
+            .filter(|job| !job.ran)
+            .map(|job| self.last_input + job.delay)
+            .min()
+    }
+
+    /// Returns a ticket for work started now.
+    pub fn ticket(&self) -> Ticket {
+        Ticket(self.generation)
+    }
+
+    /// Returns whether the user gave input since the ticket was taken, so the work should stop.
+    pub fn is_cancelled(&self, ticket: Ticket) -> bool {
+        ticket.0 != self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn jobs_run_once_per_idle_period() {
+        let start = Instant::now();
+        let mut idle = Idle::new(start);
+        idle.register("spell", Duration::from_millis(500));
+        idle.register("compact", Duration::from_millis(2_000));
+        assert_eq!(idle.deadline(), Some(ms(start, 500)));
+        assert!(idle.due(ms(start, 499)).is_empty());
+        assert_eq!(idle.due(ms(start, 500)), ["spell"]);
+        assert!(idle.due(ms(start, 600)).is_empty());
+        assert_eq!(idle.deadline(), Some(ms(start, 2_000)));
+
+        // Input restarts the wait before the slower job got to run
+        idle.input(ms(start, 1_000));
+        assert!(idle.due(ms(start, 1_400)).is_empty());
+        assert_eq!(idle.due(ms(start, 3_000)), ["spell", "compact"]);
+        assert_eq!(idle.deadline(), None);
+
+        idle.register("spell", Duration::from_millis(100));
+        idle.unregister("compact");
+        idle.input(ms(start, 4_000));
+        assert_eq!(idle.due(ms(start, 9_000)), ["spell"]);
+    }
+
+    #[test]
+    fn input_cancels_work_in_progress() {
+        let start = Instant::now();
+        let mut idle = Idle::new(start);
+        let ticket = idle.ticket();
+        assert!(!idle.is_cancelled(ticket));
+        idle.input(ms(start, 10));
+        assert!(idle.is_cancelled(ticket));
+        assert!(!idle.is_cancelled(idle.ticket()));
+    }
+}
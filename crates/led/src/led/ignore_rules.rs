@@ -0,0 +1,272 @@
+use anyhow::Result as AnyResult;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Git's per-directory ignore file.
+pub const GITIGNORE_FILE: &str = ".gitignore";
+/// LED's per-directory ignore file, for exclusions that only matter to the editor.
+///
+/// It uses the `.gitignore` syntax and is read after `.gitignore`, so it can also re-include
+/// files Git ignores with `!pattern`.
+pub const LEDIGNORE_FILE: &str = ".ledignore";
+/// Directories that are ignored whatever the rules say.
+const ALWAYS_IGNORED: &[&str] = &[".git"];
+
+/// A file found by [`Rules::files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    pub path: PathBuf,
+    /// Whether the ignore rules exclude the file.
+    pub ignored: bool,
+}
+
+/// The ignore rules of a project, following Git's semantics.
+///
+/// Rules come from `.gitignore` and `.ledignore` files in the project root and its
+/// subdirectories, plus `.git/info/exclude`. Rules in deeper directories take precedence over
+/// rules further up, and within a directory `.ledignore` takes precedence over `.gitignore`.
+/// Everything inside an ignored directory is ignored too.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    root: PathBuf,
+    /// Rules of the root directory, including `.git/info/exclude`.
+    root_rules: Option<Gitignore>,
+}
+
+impl Rules {
+    /// Loads the rules of a project's root directory. Rules in subdirectories are read when
+    /// paths below them are checked.
+    ///
+    /// Malformed patterns are reported and skipped rather than failing the whole file.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    pub fn load(project_root: &Path) -> Self {
+        let exclude = project_root.join(".git").join("info").join("exclude");
+        Self {
+            root: project_root.to_path_buf(),
+            root_rules: dir_rules(project_root, Some(&exclude)),
+        }
+    }
+
+    /// Returns whether the rules exclude a path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - An absolute path inside the project. Paths outside it are never ignored.
+    /// * `is_dir` - Whether the path is a directory, for patterns ending in `/`.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut stack = Vec::new();
+        stack.extend(self.root_rules.clone());
+        let mut dir = self.root.clone();
+        let mut parts = relative.components().peekable();
+        while let Some(part) = parts.next() {
+            let current = dir.join(part);
+            let last = parts.peek().is_none();
+            if decide(&stack, &current, is_dir || !last) {
+                return true;
+            }
+            if !last {
+                stack.extend(dir_rules(&current, None));
+            }
+            dir = current;
+        }
+        false
+    }
+
+    /// Lists the files of the project, sorted by path.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_ignored` - Whether to also list ignored files, flagged as such. Ignored
+    ///   directories are only entered when this is set.
+    /// * `descend` - Decides whether a directory is listed at all, e.g. to skip build output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the project root cannot be read. Unreadable subdirectories are
+    /// skipped.
+    pub fn files(
+        &self,
+        include_ignored: bool,
+        descend: impl Fn(&Path) -> bool,
+    ) -> AnyResult<Vec<File>> {
+        let mut found = Vec::new();
+        let stack: Vec<Gitignore> = self.root_rules.iter().cloned().collect();
+        // Only an unreadable project root is an error
+        fs::read_dir(&self.root)?;
+        self.collect(
+            &self.root,
+            &stack,
+            false,
+            include_ignored,
+            &descend,
+            &mut found,
+        );
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(found)
+    }
+
+    fn collect(
+        &self,
+        dir: &Path,
+        stack: &[Gitignore],
+        dir_ignored: bool,
+        include_ignored: bool,
+        descend: &impl Fn(&Path) -> bool,
+        found: &mut Vec<File>,
+    ) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            let is_dir = file_type.is_dir();
+            let ignored = dir_ignored || decide(stack, &path, is_dir);
+            if ignored && !include_ignored {
+                continue;
+            }
+            if is_dir {
+                let name = entry.file_name();
+                if ALWAYS_IGNORED.contains(&name.to_string_lossy().as_ref()) || !descend(&path) {
+                    continue;
+                }
+                let mut nested = stack.to_vec();
+                nested.extend(dir_rules(&path, None));
+                self.collect(&path, &nested, ignored, include_ignored, descend, found);
+            } else if file_type.is_file() {
+                found.push(File { path, ignored });
+            }
+        }
+    }
+}
+
+/// Reads the ignore files of one directory, if it has any.
+///
+/// # Arguments
+///
+/// * `dir` - The directory.
+/// * `extra` - Another file of rules with lower precedence, such as `.git/info/exclude`.
+fn dir_rules(dir: &Path, extra: Option<&Path>) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    let files = [GITIGNORE_FILE, LEDIGNORE_FILE].map(|name| dir.join(name));
+    for path in extra.into_iter().chain(files.iter().map(PathBuf::as_path)) {
+        if !path.is_file() {
+            continue;
+        }
+        found = true;
+        if let Some(e) = builder.add(path) {
+            eprintln!("Invalid ignore rule in {}: {}", path.display(), e);
+        }
+    }
+    if !found {
+        return None;
+    }
+    match builder.build() {
+        Ok(rules) => Some(rules),
+        Err(e) => {
+            eprintln!("Failed to read ignore rules in {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Returns whether the innermost rule matching a path ignores it.
+fn decide(stack: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    for rules in stack.iter().rev() {
+        let matched = rules.matched(path, is_dir);
+        if matched.is_ignore() {
+            return true;
+        }
+        if matched.is_whitelist() {
+            return false;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-ignore-{}", uuid::Uuid::new_v4()));
+        for sub in ["src/gen", "build", ".git/info"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "src/gen/out.rs",
+            "src/gen/keep.rs",
+            "build/app",
+            "notes.log",
+            "debug.log",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "build/\n*.log\n").unwrap();
+        fs::write(dir.join(".ledignore"), "!notes.log\n").unwrap();
+        fs::write(dir.join("src/.gitignore"), "gen/*\n!gen/keep.rs\n").unwrap();
+        fs::write(dir.join(".git/info/exclude"), "src/main.rs\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn combines_rules_from_all_sources() {
+        let root = temp_project();
+        let rules = Rules::load(&root);
+        assert!(rules.is_ignored(&root.join("build"), true));
+        assert!(rules.is_ignored(&root.join("build/app"), false));
+        assert!(rules.is_ignored(&root.join("debug.log"), false));
+        assert!(!rules.is_ignored(&root.join("notes.log"), false));
+        assert!(rules.is_ignored(&root.join("src/gen/out.rs"), false));
+        assert!(!rules.is_ignored(&root.join("src/gen/keep.rs"), false));
+        assert!(rules.is_ignored(&root.join("src/main.rs"), false));
+        assert!(!rules.is_ignored(Path::new("/elsewhere/debug.log"), false));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn lists_files_with_or_without_ignored_ones() {
+        let root = temp_project();
+        let rules = Rules::load(&root);
+        let relative = |files: Vec<File>| -> Vec<(String, bool)> {
+            files
+                .into_iter()
+                .map(|f| {
+                    let path = f.path.strip_prefix(&root).unwrap();
+                    (path.to_string_lossy().replace('\\', "/"), f.ignored)
+                })
+                .collect()
+        };
+
+        let visible = relative(rules.files(false, |_| true).unwrap());
+        let names: Vec<&str> = visible.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                ".gitignore",
+                ".ledignore",
+                "notes.log",
+                "src/.gitignore",
+                "src/gen/keep.rs"
+            ]
+        );
+
+        let all = relative(rules.files(true, |_| true).unwrap());
+        assert!(all.contains(&("build/app".to_string(), true)));
+        assert!(all.contains(&("src/main.rs".to_string(), true)));
+        assert!(all.contains(&("notes.log".to_string(), false)));
+        assert!(!all.iter().any(|(p, _)| p.starts_with(".git/")));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,645 @@
+use anyhow::{Context as _, Result as AnyResult, anyhow};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use super::keymap::{self, Chord, Keymap, Platform, action};
+use super::settings::{self, Diagnostic};
+
+/// Another editor whose configuration can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Editor {
+    VsCode,
+    Sublime,
+}
+
+/// VS Code commands and the actions they translate to.
+const VSCODE_COMMANDS: &[(&str, &str)] = &[
+    ("workbench.action.files.newUntitledFile", action::NEW_FILE),
+    ("workbench.action.files.openFile", action::OPEN_FILE),
+    ("workbench.action.files.openFileFolder", action::OPEN_FILE),
+    ("workbench.action.files.save", action::SAVE_FILE),
+    ("workbench.action.closeActiveEditor", action::CLOSE_BUFFER),
+    ("workbench.action.quit", action::QUIT),
+    ("workbench.action.showCommands", action::COMMAND_PALETTE),
+    ("workbench.action.toggleZenMode", action::TOGGLE_ZEN),
+    ("workbench.action.splitEditor", action::TOGGLE_SPLIT),
+    ("editor.action.startFindReplaceAction", action::REPLACE),
+    ("workbench.action.findInFiles", action::FIND_IN_FILES),
+    ("workbench.view.search", action::FIND_IN_FILES),
+    ("workbench.action.showAllSymbols", action::GOTO_SYMBOL),
+];
+
+/// An argument a Sublime Text command must be given, by name and value.
+type SublimeArg = Option<(&'static str, &'static str)>;
+
+/// Sublime Text commands, with the argument they must be given if any, and the actions they
+/// translate to.
+const SUBLIME_COMMANDS: &[(&str, SublimeArg, &str)] = &[
+    ("new_file", None, action::NEW_FILE),
+    ("prompt_open_file", None, action::OPEN_FILE),
+    ("save", None, action::SAVE_FILE),
+    ("close", None, action::CLOSE_BUFFER),
+    ("close_file", None, action::CLOSE_BUFFER),
+    ("exit", None, action::QUIT),
+    (
+        "show_overlay",
+        Some(("overlay", "command_palette")),
+        action::COMMAND_PALETTE,
+    ),
+    ("toggle_distraction_free", None, action::TOGGLE_ZEN),
+    ("show_panel", Some(("panel", "replace")), action::REPLACE),
+    (
+        "show_panel",
+        Some(("panel", "find_in_files")),
+        action::FIND_IN_FILES,
+    ),
+    ("goto_symbol_in_project", None, action::GOTO_SYMBOL),
+];
+
+impl Editor {
+    /// Returns the editor's name, for display.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::VsCode => "VS Code",
+            Self::Sublime => "Sublime Text",
+        }
+    }
+
+    /// Returns the directory the editor keeps the user's configuration in, if the user's home
+    /// directory is known.
+    pub fn default_dir(self, platform: Platform) -> Option<PathBuf> {
+        let var = |name| {
+            std::env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let config = match platform {
+            Platform::Windows => var("APPDATA")?,
+            Platform::MacOS => var("HOME")?.join("Library/Application Support"),
+            Platform::Linux => {
+                var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))?
+            }
+        };
+        Some(match (self, platform) {
+            (Self::VsCode, _) => config.join("Code").join("User"),
+            (Self::Sublime, Platform::Linux) => config.join("sublime-text/Packages/User"),
+            (Self::Sublime, _) => config.join("Sublime Text/Packages/User"),
+        })
+    }
+
+    /// Returns the name of the editor's user settings file.
+    fn settings_file(self) -> &'static str {
+        match self {
+            Self::VsCode => "settings.json",
+            Self::Sublime => "Preferences.sublime-settings",
+        }
+    }
+
+    /// Returns the name of the editor's user keymap file for a platform.
+    fn keymap_file(self, platform: Platform) -> &'static str {
+        match (self, platform) {
+            (Self::VsCode, _) => "keybindings.json",
+            (Self::Sublime, Platform::Linux) => "Default (Linux).sublime-keymap",
+            (Self::Sublime, Platform::Windows) => "Default (Windows).sublime-keymap",
+            (Self::Sublime, Platform::MacOS) => "Default (OSX).sublime-keymap",
+        }
+    }
+}
+
+/// Settings and shortcuts translated from another editor's configuration.
+#[derive(Debug, Clone, Default)]
+pub struct Import {
+    /// Settings in LED's format, by key.
+    pub settings: Map<String, Value>,
+    /// Shortcuts in LED's keymap format, for the platform they were imported on. `null`
+    /// removes a default binding.
+    pub bindings: Map<String, Value>,
+    /// Everything that could not be translated, and why.
+    pub skipped: Vec<Diagnostic>,
+}
+
+impl Import {
+    /// Reads another editor's user settings and keymap and translates them.
+    ///
+    /// Missing files are skipped, so importing only a keymap works.
+    ///
+    /// # Arguments
+    ///
+    /// * `editor` - The editor the configuration is from.
+    /// * `dir` - The editor's user configuration directory.
+    /// * `platform` - The platform whose keymap is imported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither file exists, or if one cannot be read or parsed.
+    pub fn read(editor: Editor, dir: &Path, platform: Platform) -> AnyResult<Self> {
+        let mut import = Self::default();
+        let mut found = false;
+
+        let path = dir.join(editor.settings_file());
+        if let Some(content) = read_if_exists(&path)? {
+            let value = parse_json(&content)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            import.translate_settings(editor, &path, value)?;
+            found = true;
+        }
+        let path = dir.join(editor.keymap_file(platform));
+        if let Some(content) = read_if_exists(&path)? {
+            let value = parse_json(&content)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            import.translate_keymap(editor, &path, value, platform)?;
+            found = true;
+        }
+
+        if !found {
+            return Err(anyhow!(
+                "no {} configuration found in {}",
+                editor.name(),
+                dir.display()
+            ));
+        }
+        Ok(import)
+    }
+
+    /// Translates the settings of a settings file.
+    fn translate_settings(&mut self, editor: Editor, file: &Path, value: Value) -> AnyResult<()> {
+        let Value::Object(object) = value else {
+            return Err(anyhow!("{} does not contain an object", file.display()));
+        };
+        for (key, value) in object {
+            let translated = match editor {
+                Editor::VsCode => translate_vscode_setting(&key, &value),
+                Editor::Sublime => translate_sublime_setting(&key, &value),
+            };
+            match translated {
+                Ok((led_key, led_value)) => {
+                    // Guard against a translation producing something the schema rejects
+                    let field = settings::SCHEMA.iter().find(|f| f.key == led_key);
+                    match field {
+                        Some(field) if field.kind.accepts(&led_value) => {
+                            self.settings.insert(led_key.to_string(), led_value);
+                        }
+                        _ => self.skip(file, &key, format!("unsupported value {}", value)),
+                    }
+                }
+                Err(reason) => self.skip(file, &key, reason),
+            }
+        }
+        Ok(())
+    }
+
+    /// Translates the shortcuts of a keymap file.
+    fn translate_keymap(
+        &mut self,
+        editor: Editor,
+        file: &Path,
+        value: Value,
+        platform: Platform,
+    ) -> AnyResult<()> {
+        let Value::Array(entries) = value else {
+            return Err(anyhow!("{} does not contain a list", file.display()));
+        };
+        let defaults = Keymap::defaults(platform);
+        for entry in entries {
+            let Value::Object(entry) = entry else {
+                continue;
+            };
+            let translated = match editor {
+                Editor::VsCode => translate_vscode_binding(&entry),
+                Editor::Sublime => translate_sublime_binding(&entry),
+            };
+            let (keys, binding) = match translated {
+                Ok(translated) => translated,
+                Err((keys, reason)) => {
+                    self.skip(file, &keys, reason);
+                    continue;
+                }
+            };
+            let chord = match Chord::parse(&keys, platform) {
+                Ok(chord) => chord,
+                Err(e) => {
+                    self.skip(file, &keys, e.to_string());
+                    continue;
+                }
+            };
+            let spec = chord.label(platform).to_ascii_lowercase();
+            match binding {
+                Binding::Bind(action) => {
+                    self.bindings.insert(spec, Value::from(action));
+                }
+                // Removing a binding only carries over if LED has the same one by default
+                Binding::Unbind(action) if defaults.action(chord) == Some(action) => {
+                    self.bindings.insert(spec, Value::Null);
+                }
+                Binding::Unbind(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, file: &Path, key: &str, message: String) {
+        self.skipped.push(Diagnostic {
+            file: file.to_path_buf(),
+            key: Some(key.to_string()),
+            message,
+        });
+    }
+
+    /// Merges the imported settings and shortcuts into a project's settings and keymap files,
+    /// replacing existing values for the same keys and keeping everything else.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    /// * `platform` - The platform the shortcuts were imported for.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an existing file is not a JSON object, or a file cannot be written.
+    pub fn write(&self, project_root: &Path, platform: Platform) -> AnyResult<()> {
+        if !self.settings.is_empty() {
+            let path = settings::project_settings_path(project_root);
+            merge_into_file(&path, |object| object.extend(self.settings.clone()))?;
+        }
+        if !self.bindings.is_empty() {
+            let section = match platform {
+                Platform::Linux => "linux",
+                Platform::Windows => "windows",
+                Platform::MacOS => "macos",
+            };
+            let path = keymap::keymap_path(project_root);
+            merge_into_file(&path, |object| {
+                let table = object
+                    .entry(section)
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if !table.is_object() {
+                    *table = Value::Object(Map::new());
+                }
+                if let Value::Object(table) = table {
+                    table.extend(self.bindings.clone());
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// What an imported shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Binding {
+    Bind(&'static str),
+    /// Removes the default binding of a chord to an action.
+    Unbind(&'static str),
+}
+
+fn translate_vscode_setting(key: &str, value: &Value) -> Result<(&'static str, Value), String> {
+    let unsupported = || format!("unsupported value {}", value);
+    match key {
+        "files.insertFinalNewline" => Ok(("ensure_final_newline", value.clone())),
+        "files.trimTrailingWhitespace" => Ok(("trim_trailing_whitespace", value.clone())),
+        "window.confirmBeforeClose" => match value.as_str() {
+            Some("never") => Ok(("confirm_quit", Value::Bool(false))),
+            Some("always" | "keyboardOnly") => Ok(("confirm_quit", Value::Bool(true))),
+            _ => Err(unsupported()),
+        },
+        "files.encoding" => match value.as_str() {
+            Some("utf8bom") => Ok(("bom_on_save", Value::from("Add"))),
+            Some("utf8") => Ok(("bom_on_save", Value::from("Preserve"))),
+            _ => Err(unsupported()),
+        },
+        "search.useIgnoreFiles" => match value.as_bool() {
+            Some(use_ignore) => Ok(("show_ignored_files", Value::Bool(!use_ignore))),
+            None => Err(unsupported()),
+        },
+        "editor.quickSuggestionsDelay" => Ok(("suggestion_delay_ms", value.clone())),
+        "editor.insertSpaces" => match value.as_bool() {
+            Some(spaces) => Ok(("insert_tabs", Value::Bool(!spaces))),
+            None => Err(unsupported()),
+        },
+        _ => Err("no LED equivalent".to_string()),
+    }
+}
+
+fn translate_sublime_setting(key: &str, value: &Value) -> Result<(&'static str, Value), String> {
+    match key {
+        "ensure_newline_at_eof_on_save" => Ok(("ensure_final_newline", value.clone())),
+        // Newer versions also accept "all", "none" and "not_on_caret"
+        "trim_trailing_white_space_on_save" => match value.as_str() {
+            Some("all") => Ok(("trim_trailing_whitespace", Value::Bool(true))),
+            Some("none") => Ok(("trim_trailing_whitespace", Value::Bool(false))),
+            Some(_) => Err(format!("unsupported value {}", value)),
+            None => Ok(("trim_trailing_whitespace", value.clone())),
+        },
+        "index_files" => Ok(("project_index", value.clone())),
+        "translate_tabs_to_spaces" => match value.as_bool() {
+            Some(spaces) => Ok(("insert_tabs", Value::Bool(!spaces))),
+            None => Err(format!("unsupported value {}", value)),
+        },
+        _ => Err("no LED equivalent".to_string()),
+    }
+}
+
+/// Translates an entry of VS Code's `keybindings.json`, returning its key and binding, or its
+/// key and why it was skipped.
+fn translate_vscode_binding(
+    entry: &Map<String, Value>,
+) -> Result<(String, Binding), (String, String)> {
+    let keys = entry
+        .get("key")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let command = entry
+        .get("command")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if keys.contains(' ') {
+        return Err((keys, "key sequences are not supported".to_string()));
+    }
+    if let Some(when) = entry.get("when").and_then(Value::as_str) {
+        let reason = format!("only applies when `{}`, which LED can't express", when);
+        return Err((keys, reason));
+    }
+    let (removed, command) = match command.strip_prefix('-') {
+        Some(command) => (true, command),
+        None => (false, command),
+    };
+    let Some(&(_, action)) = VSCODE_COMMANDS.iter().find(|(name, _)| *name == command) else {
+        return Err((keys, format!("no LED equivalent for `{}`", command)));
+    };
+    let binding = if removed {
+        Binding::Unbind(action)
+    } else {
+        Binding::Bind(action)
+    };
+    Ok((keys, binding))
+}
+
+/// Translates an entry of a Sublime Text keymap, returning its key and binding, or its key and
+/// why it was skipped.
+fn translate_sublime_binding(
+    entry: &Map<String, Value>,
+) -> Result<(String, Binding), (String, String)> {
+    let keys: Vec<&str> = entry
+        .get("keys")
+        .and_then(Value::as_array)
+        .map(|keys| keys.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let joined = keys.join(", ");
+    let [key] = keys.as_slice() else {
+        return Err((joined, "key sequences are not supported".to_string()));
+    };
+    let key = key.to_string();
+    if entry.contains_key("context") {
+        return Err((
+            key,
+            "only applies in a context, which LED can't express".to_string(),
+        ));
+    }
+    let command = entry
+        .get("command")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let args = entry.get("args");
+    let action = SUBLIME_COMMANDS
+        .iter()
+        .find(|(name, arg, _)| {
+            *name == command
+                && arg.is_none_or(|(arg, expected)| {
+                    args.and_then(|args| args.get(arg)).and_then(Value::as_str) == Some(expected)
+                })
+        })
+        .map(|(_, _, action)| *action);
+    match action {
+        Some(action) => Ok((key, Binding::Bind(action))),
+        None => Err((key, format!("no LED equivalent for `{}`", command))),
+    }
+}
+
+fn read_if_exists(path: &Path) -> AnyResult<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Parses JSON that may contain comments and trailing commas, as VS Code and Sublime Text
+/// configuration files do.
+fn parse_json(content: &str) -> serde_json::Result<Value> {
+    serde_json::from_str(&strip_trailing_commas(&strip_comments(content)))
+}
+
+/// Replaces `//` and `/* */` comments outside of strings with spaces, keeping line breaks so
+/// that parse errors point at the right line.
+fn strip_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    out.push(if c == '\n' { '\n' } else { ' ' });
+                    prev = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Removes commas directly before a closing bracket or brace, outside of strings.
+fn strip_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut out = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        out.push(c);
+        i += 1;
+        if in_string {
+            match c {
+                '\\' if i < chars.len() => {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && chars[i..]
+                .iter()
+                .find(|c| !c.is_whitespace())
+                .is_some_and(|c| matches!(c, '}' | ']'))
+        {
+            out.pop();
+        }
+    }
+    out
+}
+
+/// Reads a JSON object from a file, lets `update` change it, and writes it back, creating the
+/// file and its directory if needed.
+fn merge_into_file(path: &Path, update: impl FnOnce(&mut Map<String, Value>)) -> AnyResult<()> {
+    let mut object = match read_if_exists(path)? {
+        Some(content) => match serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))?
+        {
+            Value::Object(object) => object,
+            _ => return Err(anyhow!("{} does not contain an object", path.display())),
+        },
+        None => Map::new(),
+    };
+    update(&mut object);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&Value::Object(object))?;
+    std::fs::write(path, json + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn comments_and_trailing_commas_are_accepted() {
+        let content = r#"{
+            // A comment with "quotes"
+            "a": "http://example.com", /* block
+            comment */ "b": [1, 2,],
+        }"#;
+        let value = parse_json(content).unwrap();
+        assert_eq!(value["a"], "http://example.com");
+        assert_eq!(value["b"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn vscode_configuration_is_translated() {
+        let dir = temp_dir();
+        std::fs::write(
+            dir.join("settings.json"),
+            r#"{
+                "files.insertFinalNewline": true,
+                "window.confirmBeforeClose": "never",
+                "editor.fontFamily": "Fira Code",
+                "editor.quickSuggestionsDelay": 99999,
+                "editor.insertSpaces": false,
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("keybindings.json"),
+            r#"[
+                { "key": "ctrl+shift+a", "command": "workbench.action.showCommands" },
+                { "key": "ctrl+k ctrl+s", "command": "workbench.action.files.save" },
+                { "key": "ctrl+e", "command": "editor.action.find", "when": "editorFocus" },
+                { "key": "ctrl+w", "command": "-workbench.action.closeActiveEditor" },
+                { "key": "ctrl+n", "command": "-workbench.action.files.save" },
+            ]"#,
+        )
+        .unwrap();
+
+        let import = Import::read(Editor::VsCode, &dir, Platform::Linux).unwrap();
+        assert_eq!(import.settings.len(), 3);
+        assert_eq!(import.settings["ensure_final_newline"], true);
+        assert_eq!(import.settings["insert_tabs"], true);
+        assert_eq!(import.settings["confirm_quit"], false);
+        assert_eq!(import.bindings.len(), 2);
+        assert_eq!(import.bindings["ctrl+shift+a"], action::COMMAND_PALETTE);
+        // LED binds Ctrl+W to the same action, but not Ctrl+N
+        assert_eq!(import.bindings["ctrl+w"], Value::Null);
+
+        let skipped: Vec<&str> = import
+            .skipped
+            .iter()
+            .filter_map(|d| d.key.as_deref())
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                "editor.fontFamily",
+                "editor.quickSuggestionsDelay",
+                "ctrl+k ctrl+s",
+                "ctrl+e"
+            ]
+        );
+
+        // The import merges into the project's files and loads like any other configuration
+        let project = dir.join("project");
+        std::fs::create_dir_all(project.join(super::super::notes::STATE_DIR)).unwrap();
+        std::fs::write(
+            settings::project_settings_path(&project),
+            r#"{"track_latency": true, "confirm_quit": true}"#,
+        )
+        .unwrap();
+        import.write(&project, Platform::Linux).unwrap();
+        let (loaded, diagnostics) =
+            settings::Settings::load(Map::new(), &[settings::project_settings_path(&project)]);
+        assert!(diagnostics.is_empty());
+        assert!(loaded.track_latency && loaded.ensure_final_newline && !loaded.confirm_quit);
+        assert!(loaded.insert_tabs);
+        let keymap = Keymap::load(&project, Platform::Linux).unwrap();
+        assert_eq!(
+            keymap.label_for(action::COMMAND_PALETTE).as_deref(),
+            Some("Ctrl+Shift+A")
+        );
+        assert_eq!(keymap.label_for(action::CLOSE_BUFFER), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sublime_keymap_matches_commands_by_arguments() {
+        let dir = temp_dir();
+        std::fs::write(
+            dir.join("Default (OSX).sublime-keymap"),
+            r#"[
+                { "keys": ["super+shift+o"], "command": "show_overlay", "args": {"overlay": "command_palette"} },
+                { "keys": ["super+r"], "command": "show_overlay", "args": {"overlay": "goto", "text": "@"} },
+                { "keys": ["super+k", "super+b"], "command": "toggle_side_bar" },
+                { "keys": ["super+shift+j"], "command": "toggle_distraction_free", "context": [] },
+            ]"#,
+        )
+        .unwrap();
+
+        // Only a keymap exists, which is enough
+        let import = Import::read(Editor::Sublime, &dir, Platform::MacOS).unwrap();
+        assert!(import.settings.is_empty());
+        assert_eq!(import.bindings.len(), 1);
+        assert_eq!(import.bindings["shift+cmd+o"], action::COMMAND_PALETTE);
+        assert_eq!(import.skipped.len(), 3);
+
+        assert!(Import::read(Editor::Sublime, &dir, Platform::Linux).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use super::prose::PROSE_LANGUAGES;
+
+/// The characters that indent the line after one ending with them, in languages without an
+/// entry of their own.
+pub const DEFAULT_OPENERS: &str = "{([";
+
+/// Built-in openers of languages that differ from [`DEFAULT_OPENERS`].
+const OPENERS: &[(&str, &str)] = &[("python", "{([:"), ("yaml", "{([:")];
+
+/// Returns the characters that indent the next line in a language.
+///
+/// The `indent_after` setting comes first, then the built-in table. Prose has no openers, so
+/// a sentence ending in a colon doesn't indent the next one.
+///
+/// # Arguments
+///
+/// * `language` - The buffer's language, if known.
+/// * `settings` - The openers set by language in the settings.
+pub fn openers<'a>(language: Option<&str>, settings: &'a BTreeMap<String, String>) -> &'a str {
+    if let Some(openers) = language.and_then(|language| settings.get(language)) {
+        return openers;
+    }
+    if let Some((_, openers)) = OPENERS.iter().find(|(known, _)| Some(*known) == language) {
+        return openers;
+    }
+    if language.is_some_and(|language| PROSE_LANGUAGES.contains(&language)) {
+        return "";
+    }
+    DEFAULT_OPENERS
+}
+
+/// Builds the text Enter inserts: a line break followed by the indentation of the line it
+/// breaks, one level deeper if the text before the caret ends with an opener.
+///
+/// # Arguments
+///
+/// * `line` - The line the caret is on, without its line break.
+/// * `column` - The caret's byte column in the line.
+/// * `openers` - The characters that indent the next line, see [`openers`].
+/// * `unit` - One level of indentation, used unless the line is indented with tabs.
+pub fn line_break(line: &str, column: usize, openers: &str, unit: &str) -> String {
+    let before = &line[..column.min(line.len())];
+    let indent = &before[..before.len() - before.trim_start().len()];
+    let mut text = format!("\n{indent}");
+    if before
+        .trim_end()
+        .chars()
+        .last()
+        .is_some_and(|c| openers.contains(c))
+    {
+        if indent.starts_with('\t') {
+            text.push('\t');
+        } else {
+            text.push_str(unit);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openers_follow_settings_then_language() {
+        let settings = BTreeMap::from([("rust".to_string(), "{".to_string())]);
+        assert_eq!(openers(Some("rust"), &settings), "{");
+        assert_eq!(openers(Some("python"), &settings), "{([:");
+        assert_eq!(openers(Some("markdown"), &settings), "");
+        assert_eq!(openers(None, &settings), DEFAULT_OPENERS);
+    }
+
+    #[test]
+    fn line_break_keeps_and_deepens_indentation() {
+        assert_eq!(line_break("    let a = 1;", 14, "{([", "    "), "\n    ");
+        assert_eq!(line_break("    fn a() {", 12, "{([", "    "), "\n        ");
+        assert_eq!(line_break("\tif x {  ", 9, "{([", "    "), "\n\t\t");
+        assert_eq!(line_break("def a():", 8, "{([:", "  "), "\n  ");
+        assert_eq!(line_break("def a():", 8, "{([", "  "), "\n");
+        assert_eq!(line_break("fn a() {", 8, "{([", "\t"), "\n\t");
+        // Breaking inside the indentation or before the opener keeps the indentation only
+        assert_eq!(line_break("    f(x)", 2, "{([", "    "), "\n  ");
+        assert_eq!(line_break("    f(x)", 5, "{([", "    "), "\n    ");
+        assert_eq!(line_break("    f(x)", 6, "{([", "    "), "\n        ");
+    }
+}
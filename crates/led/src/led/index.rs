@@ -0,0 +1,984 @@
+use super::ignore_rules::{File, Rules};
+use super::notes::{NOTES_FILE, STATE_DIR};
+use super::palette;
+use super::types::Position;
+use anyhow::Result as AnyResult;
+use ignore::overrides::{Override, OverrideBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Directory inside [`STATE_DIR`] that holds the project index.
+pub const INDEX_DIR: &str = "index";
+/// File name of the stored index inside [`INDEX_DIR`].
+pub const INDEX_FILE: &str = "files.json";
+/// Files larger than this are left out of the index.
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Directories that are never indexed, in addition to hidden ones and those excluded by the
+/// project's ignore rules.
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+/// Bumped whenever the stored format changes, so older indexes are rebuilt.
+const FORMAT_VERSION: u32 = 1;
+
+/// Words that introduce a definition, followed by the defined name.
+const SYMBOL_KEYWORDS: &[&str] = &[
+    "fn",
+    "struct",
+    "enum",
+    "trait",
+    "mod",
+    "type",
+    "const",
+    "static",
+    "union",
+    "macro_rules!",
+    "class",
+    "def",
+    "function",
+    "interface",
+];
+/// Words that may precede a definition keyword.
+const SYMBOL_MODIFIERS: &[&str] = &[
+    "pub", "async", "unsafe", "extern", "export", "default", "local", "abstract",
+];
+
+/// Returns the path of the stored index for a project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn index_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(STATE_DIR)
+        .join(INDEX_DIR)
+        .join(INDEX_FILE)
+}
+
+/// A definition found in a file, such as a function or a type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    /// The defined name.
+    pub name: String,
+    /// The keyword that introduced it, e.g. `fn` or `class`.
+    pub kind: String,
+    /// Zero-based line of the definition.
+    pub line: usize,
+}
+
+/// A location returned by an index query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Where in the file the match starts.
+    pub position: Position,
+    /// Text to show for the hit: the matching line, or the symbol's kind and name.
+    pub preview: String,
+    /// Whether the project's ignore rules exclude the file.
+    pub ignored: bool,
+}
+
+/// The hits of a text search.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Found {
+    pub hits: Vec<Hit>,
+    /// Whether the search stopped at its limit with more hits left.
+    pub truncated: bool,
+}
+
+/// Which indexed files a text search reads.
+///
+/// Globs use the `.gitignore` syntax relative to the project root, so `*.rs` matches at any
+/// depth and `src/**/*.rs` only below `src`. A file must match one of the include globs, if
+/// there are any, and none of the exclude globs.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    globs: Override,
+    /// Files larger than this many bytes are skipped.
+    max_file_bytes: u64,
+}
+
+impl Filter {
+    /// Creates a filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project the globs are relative to.
+    /// * `include` - Comma-separated globs of the files to search; empty searches all files.
+    ///   Globs starting with `!` exclude files instead.
+    /// * `exclude` - Comma-separated globs of the files to skip. A leading `!` is optional.
+    /// * `max_file_bytes` - Files larger than this many bytes are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first glob that isn't valid.
+    pub fn new(
+        project_root: &Path,
+        include: &str,
+        exclude: &str,
+        max_file_bytes: u64,
+    ) -> AnyResult<Self> {
+        let mut builder = OverrideBuilder::new(project_root);
+        // Later globs take precedence, so exclusions come last
+        let globs = |list: &str| {
+            list.split(',')
+                .map(str::trim)
+                .filter(|glob| !glob.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let (excluded, included): (Vec<String>, Vec<String>) = globs(include)
+            .into_iter()
+            .partition(|glob| glob.starts_with('!'));
+        for glob in included {
+            builder.add(&glob)?;
+        }
+        for glob in excluded.into_iter().chain(globs(exclude)) {
+            builder.add(&format!("!{}", glob.trim_start_matches('!')))?;
+        }
+        Ok(Self {
+            globs: builder.build()?,
+            max_file_bytes,
+        })
+    }
+
+    /// Returns a filter that admits every indexed file.
+    pub fn all() -> Self {
+        Self {
+            globs: Override::empty(),
+            max_file_bytes: u64::MAX,
+        }
+    }
+
+    /// Returns whether a file is searched.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The absolute path of the file.
+    /// * `len` - The size of the file in bytes.
+    fn admits(&self, path: &Path, len: u64) -> bool {
+        len <= self.max_file_bytes && !self.globs.matched(path, false).is_ignore()
+    }
+}
+
+/// Returns whether content is binary rather than text, and so is neither indexed nor searched.
+pub fn is_binary(content: &[u8]) -> bool {
+    content.contains(&0)
+}
+
+/// What the index knows about one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// Path relative to the project root, with `/` separators.
+    path: String,
+    /// Size in bytes when the file was indexed.
+    len: u64,
+    /// Modification time in seconds since the Unix epoch when the file was indexed.
+    modified: u64,
+    /// Trigrams of the case-folded content, sorted.
+    trigrams: Vec<u32>,
+    symbols: Vec<Symbol>,
+    /// Whether the project's ignore rules exclude the file.
+    #[serde(default)]
+    ignored: bool,
+}
+
+/// The index as written to disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct Stored {
+    version: u32,
+    files: Vec<FileEntry>,
+}
+
+/// A trigram index of the text files in a project, for instant find-in-files and
+/// go-to-symbol queries.
+///
+/// Each file is reduced to the set of three-byte sequences in its case-folded content. A text
+/// query only reads the files that contain every trigram of the query, so most files are ruled
+/// out without being opened. Symbols are extracted when a file is indexed and kept in memory.
+///
+/// The index is stored under `.led/index` and refreshed incrementally: only files whose size
+/// or modification time changed are read again.
+#[derive(Debug, Clone)]
+pub struct Index {
+    root: PathBuf,
+    /// Indexed files; closed slots are `None` and reused.
+    files: Vec<Option<FileEntry>>,
+    /// Slot of each indexed file, by relative path.
+    slots: HashMap<String, usize>,
+    /// Slots of the files containing each trigram.
+    postings: HashMap<u32, BTreeSet<usize>>,
+    /// Total size of the indexed files in bytes.
+    indexed_bytes: u64,
+    /// Whether files were left out because the size cap was reached.
+    truncated: bool,
+    /// The project's ignore rules as of the last refresh.
+    rules: Rules,
+    /// Whether ignored files are indexed too, flagged as ignored.
+    include_ignored: bool,
+    /// Names of directories skipped besides the built-in ones.
+    excluded_dirs: Vec<String>,
+}
+
+impl Index {
+    /// Creates an empty index for a project.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            root: project_root.to_path_buf(),
+            files: Vec::new(),
+            slots: HashMap::new(),
+            postings: HashMap::new(),
+            indexed_bytes: 0,
+            truncated: false,
+            rules: Rules::load(project_root),
+            include_ignored: false,
+            excluded_dirs: Vec::new(),
+        }
+    }
+
+    /// Sets the names of directories to skip besides hidden ones, `target` and
+    /// `node_modules`, from the next [`Index::refresh`] on. Files already indexed in them are
+    /// dropped then.
+    pub fn set_excluded_dirs(&mut self, names: Vec<String>) {
+        self.excluded_dirs = names;
+    }
+
+    /// Loads the project's stored index, or an empty one if none was stored yet or it was
+    /// stored in an older format.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index file exists but cannot be read or parsed.
+    pub fn load(project_root: &Path) -> AnyResult<Self> {
+        let mut index = Self::new(project_root);
+        let content = match fs::read_to_string(index_path(project_root)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e.into()),
+        };
+        let stored: Stored = serde_json::from_str(&content)?;
+        if stored.version == FORMAT_VERSION {
+            for entry in stored.files {
+                index.insert(entry);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Writes the index to the project's `.led/index` directory, creating it as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save(&self) -> AnyResult<()> {
+        let path = index_path(&self.root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let stored = Stored {
+            version: FORMAT_VERSION,
+            files: self.files.iter().flatten().cloned().collect(),
+        };
+        fs::write(path, serde_json::to_string(&stored)?)?;
+        Ok(())
+    }
+
+    /// Brings the index up to date with the files on disk.
+    ///
+    /// New and changed files are read and indexed, unchanged files are kept as they are and
+    /// deleted files are dropped. Once the indexed files add up to `size_cap` bytes, the
+    /// remaining files are left out and [`Index::is_truncated`] reports it.
+    ///
+    /// The ignore rules are read again, so changes to `.gitignore` and `.ledignore` files take
+    /// effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `size_cap` - The maximum total size of the indexed files in bytes.
+    /// * `include_ignored` - Whether to index files excluded by the ignore rules, so they can
+    ///   be shown dimmed instead of hidden.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the project root cannot be read. Unreadable files and
+    /// subdirectories are skipped.
+    pub fn refresh(&mut self, size_cap: u64, include_ignored: bool) -> AnyResult<()> {
+        self.rules = Rules::load(&self.root);
+        self.include_ignored = include_ignored;
+        let mut found = self.rules.files(include_ignored, |dir| {
+            dir.file_name().is_none_or(|name| {
+                let name = name.to_string_lossy();
+                !is_skipped_dir(&name) && !self.excluded_dirs.iter().any(|n| *n == name)
+            })
+        })?;
+        let notes = self.root.join(STATE_DIR).join(NOTES_FILE);
+        if notes.is_file() {
+            found.push(File {
+                path: notes,
+                ignored: false,
+            });
+        }
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut seen = BTreeSet::new();
+        let mut total = 0;
+        self.truncated = false;
+        for File { path, ignored } in found {
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let len = metadata.len();
+            if len > MAX_FILE_BYTES {
+                continue;
+            }
+            if total + len > size_cap {
+                self.truncated = true;
+                break;
+            }
+            let relative = self.relative(&path);
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_secs());
+            let unchanged = self
+                .entry(&relative)
+                .is_some_and(|entry| entry.len == len && entry.modified == modified);
+            if !unchanged {
+                let Some(content) = read_text(&path) else {
+                    self.remove(&relative);
+                    continue;
+                };
+                self.insert(FileEntry {
+                    trigrams: trigrams(&content),
+                    symbols: symbols(&content),
+                    path: relative.clone(),
+                    len,
+                    modified,
+                    ignored,
+                });
+            } else if let Some(entry) = self
+                .slots
+                .get(&relative)
+                .and_then(|slot| self.files[*slot].as_mut())
+            {
+                // The rules may have changed even though the file didn't
+                entry.ignored = ignored;
+            }
+            total += len;
+            seen.insert(relative);
+        }
+
+        let stale: Vec<String> = self
+            .slots
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in stale {
+            self.remove(&path);
+        }
+        Ok(())
+    }
+
+    /// Reindexes a single file after it was saved, without walking the project.
+    ///
+    /// Files outside the project, in skipped directories, over [`MAX_FILE_BYTES`] or excluded
+    /// by the ignore rules (unless ignored files are indexed) are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The saved file.
+    /// * `content` - The content that was written.
+    pub fn update_file(&mut self, path: &Path, content: &str) {
+        let Ok(relative_path) = path.strip_prefix(&self.root) else {
+            return;
+        };
+        let skipped = relative_path.parent().is_some_and(|dir| {
+            dir.components()
+                .any(|part| is_skipped_dir(&part.as_os_str().to_string_lossy()))
+        });
+        let notes = path == self.root.join(STATE_DIR).join(NOTES_FILE);
+        if skipped && !notes {
+            return;
+        }
+        let ignored = !notes && self.rules.is_ignored(path, false);
+        let relative = self.relative(path);
+        if ignored && !self.include_ignored {
+            self.remove(&relative);
+            return;
+        }
+        let len = content.len() as u64;
+        if len > MAX_FILE_BYTES || is_binary(content.as_bytes()) {
+            self.remove(&relative);
+            return;
+        }
+        // The modification time is left at zero, so the next refresh reads the file again
+        self.insert(FileEntry {
+            trigrams: trigrams(content),
+            symbols: symbols(content),
+            path: relative,
+            len,
+            modified: 0,
+            ignored,
+        });
+    }
+
+    /// Returns the number of indexed files.
+    pub fn file_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the total size of the indexed files in bytes.
+    pub fn indexed_bytes(&self) -> u64 {
+        self.indexed_bytes
+    }
+
+    /// Returns roughly how many bytes the index holds in memory.
+    pub fn memory_size(&self) -> usize {
+        use std::mem::size_of;
+        let files: usize = self
+            .files
+            .iter()
+            .flatten()
+            .map(|entry| {
+                entry.path.capacity()
+                    + entry.trigrams.capacity() * size_of::<u32>()
+                    + entry
+                        .symbols
+                        .iter()
+                        .map(|symbol| {
+                            size_of::<Symbol>() + symbol.name.capacity() + symbol.kind.capacity()
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+        let slots: usize = self
+            .slots
+            .keys()
+            .map(|path| size_of::<(String, usize)>() + path.capacity())
+            .sum();
+        let postings: usize = self
+            .postings
+            .values()
+            .map(|files| size_of::<(u32, BTreeSet<usize>)>() + files.len() * size_of::<usize>())
+            .sum();
+        size_of::<Self>()
+            + self.files.capacity() * size_of::<Option<FileEntry>>()
+            + files
+            + slots
+            + postings
+    }
+
+    /// Returns whether files were left out because the size cap was reached.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Finds lines containing `query`, ignoring ASCII case.
+    ///
+    /// Only files containing all of the query's trigrams are read. Queries shorter than three
+    /// bytes have no trigrams, so they read every indexed file. Files the filter leaves out are
+    /// not read, and neither are files that turned binary since they were indexed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to look for.
+    /// * `filter` - Which files to search.
+    /// * `limit` - The maximum number of hits to return.
+    ///
+    /// # Returns
+    ///
+    /// The matching lines, ordered by path and line.
+    pub fn find_text(&self, query: &str, filter: &Filter, limit: usize) -> Found {
+        let mut found = Found::default();
+        if query.is_empty() {
+            return found;
+        }
+        let needle = query.to_ascii_lowercase();
+        for entry in self.candidates(&needle) {
+            let path = self.root.join(&entry.path);
+            if !filter.admits(&path, entry.len) {
+                continue;
+            }
+            let Some(content) = read_text(&path) else {
+                continue;
+            };
+            for (line, text) in content.lines().enumerate() {
+                let Some(column) = text.to_ascii_lowercase().find(&needle) else {
+                    continue;
+                };
+                if found.hits.len() == limit {
+                    found.truncated = true;
+                    return found;
+                }
+                found.hits.push(Hit {
+                    path: path.clone(),
+                    position: Position { line, column },
+                    preview: text.trim().to_string(),
+                    ignored: entry.ignored,
+                });
+            }
+        }
+        found
+    }
+
+    /// Returns the indexed files a search may find `literal` in, ordered by path.
+    ///
+    /// Only files containing all of the trigrams of `literal` are returned; without it, every
+    /// file the filter admits is. Files the project's ignore rules exclude are left out, so
+    /// a project-wide replace never edits generated or vendored files.
+    ///
+    /// # Arguments
+    ///
+    /// * `literal` - Text every match contains, if known.
+    /// * `filter` - Which files to search.
+    pub fn files(&self, literal: Option<&str>, filter: &Filter) -> Vec<PathBuf> {
+        self.candidates(&literal.unwrap_or_default().to_ascii_lowercase())
+            .into_iter()
+            .filter(|entry| !entry.ignored)
+            .map(|entry| (self.root.join(&entry.path), entry.len))
+            .filter(|(path, len)| filter.admits(path, *len))
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    /// Finds symbols whose names fuzzy-match `query`, best match first.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - What the user typed; see [`palette::score`] for how it matches.
+    /// * `limit` - The maximum number of hits to return.
+    pub fn find_symbols(&self, query: &str, limit: usize) -> Vec<Hit> {
+        let mut scored: Vec<(i32, &FileEntry, &Symbol)> = self
+            .files
+            .iter()
+            .flatten()
+            .flat_map(|entry| entry.symbols.iter().map(move |symbol| (entry, symbol)))
+            .filter_map(|(entry, symbol)| {
+                Some((palette::score(query, &symbol.name)?, entry, symbol))
+            })
+            .collect();
+        scored.sort_by(|(a_score, a_entry, a), (b_score, b_entry, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.name.len().cmp(&b.name.len()))
+                .then_with(|| a_entry.path.cmp(&b_entry.path))
+                .then_with(|| a.line.cmp(&b.line))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, entry, symbol)| Hit {
+                path: self.root.join(&entry.path),
+                position: Position {
+                    line: symbol.line,
+                    column: 0,
+                },
+                preview: format!("{} {}", symbol.kind, symbol.name),
+                ignored: entry.ignored,
+            })
+            .collect()
+    }
+
+    /// Returns the indexed files that may contain `needle`, ordered by path.
+    fn candidates(&self, needle: &str) -> Vec<&FileEntry> {
+        let wanted = trigrams(needle);
+        let slots: Vec<usize> = match wanted.split_first() {
+            None => (0..self.files.len()).collect(),
+            Some((first, rest)) => {
+                let Some(mut slots) = self.postings.get(first).cloned() else {
+                    return Vec::new();
+                };
+                for trigram in rest {
+                    match self.postings.get(trigram) {
+                        Some(files) => slots.retain(|slot| files.contains(slot)),
+                        None => return Vec::new(),
+                    }
+                }
+                slots.into_iter().collect()
+            }
+        };
+        let mut entries: Vec<&FileEntry> = slots
+            .into_iter()
+            .filter_map(|slot| self.files[slot].as_ref())
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+
+    /// Returns the path relative to the project root, with `/` separators.
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn entry(&self, relative: &str) -> Option<&FileEntry> {
+        self.files[*self.slots.get(relative)?].as_ref()
+    }
+
+    /// Adds a file to the index, replacing any previous entry for the same path.
+    fn insert(&mut self, entry: FileEntry) {
+        self.remove(&entry.path);
+        let slot = match self.files.iter().position(Option::is_none) {
+            Some(slot) => slot,
+            None => {
+                self.files.push(None);
+                self.files.len() - 1
+            }
+        };
+        for trigram in &entry.trigrams {
+            self.postings.entry(*trigram).or_default().insert(slot);
+        }
+        self.indexed_bytes += entry.len;
+        self.slots.insert(entry.path.clone(), slot);
+        self.files[slot] = Some(entry);
+    }
+
+    /// Drops a file from the index, if it is indexed.
+    fn remove(&mut self, relative: &str) {
+        let Some(slot) = self.slots.remove(relative) else {
+            return;
+        };
+        let Some(entry) = self.files[slot].take() else {
+            return;
+        };
+        for trigram in &entry.trigrams {
+            if let Some(files) = self.postings.get_mut(trigram) {
+                files.remove(&slot);
+                if files.is_empty() {
+                    self.postings.remove(trigram);
+                }
+            }
+        }
+        self.indexed_bytes -= entry.len;
+    }
+}
+
+/// Orders hits so that hits in the same file are next to each other.
+///
+/// Files are ordered by their first hit, and hits within a file keep their relative order, so
+/// ranked hits stay ranked: the file with the best hit comes first, starting with that hit.
+///
+/// # Arguments
+///
+/// * `hits` - The hits, e.g. as returned by [`Index::find_symbols`].
+pub fn group_by_file(hits: Vec<Hit>) -> Vec<Hit> {
+    let mut groups: Vec<(PathBuf, Vec<Hit>)> = Vec::new();
+    for hit in hits {
+        match groups.iter_mut().find(|(path, _)| *path == hit.path) {
+            Some((_, group)) => group.push(hit),
+            None => groups.push((hit.path.clone(), vec![hit])),
+        }
+    }
+    groups.into_iter().flat_map(|(_, group)| group).collect()
+}
+
+/// Reads a text file, or returns `None` if it can't be read, is binary or isn't UTF-8.
+fn read_text(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    if is_binary(&content) {
+        return None;
+    }
+    String::from_utf8(content).ok()
+}
+
+/// Returns whether a directory is never indexed.
+fn is_skipped_dir(name: &str) -> bool {
+    name.starts_with('.') || SKIPPED_DIRS.contains(&name)
+}
+
+/// Returns the distinct trigrams of the ASCII case-folded text, sorted.
+fn trigrams(text: &str) -> Vec<u32> {
+    let bytes = text.as_bytes();
+    let set: BTreeSet<u32> = bytes
+        .windows(3)
+        .map(|w| {
+            let [a, b, c] = [w[0], w[1], w[2]].map(|byte| byte.to_ascii_lowercase() as u32);
+            (a << 16) | (b << 8) | c
+        })
+        .collect();
+    set.into_iter().collect()
+}
+
+/// Extracts definitions from source text, by looking for a definition keyword at the start of
+/// a line, optionally after modifiers like `pub` or `export`.
+fn symbols(text: &str) -> Vec<Symbol> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let mut words = text
+                .split_whitespace()
+                .skip_while(|word| SYMBOL_MODIFIERS.contains(word) || word.starts_with("pub("));
+            let kind = words.next().filter(|word| SYMBOL_KEYWORDS.contains(word))?;
+            let name: String = words
+                .next()?
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            (!name.is_empty()).then(|| Symbol {
+                name,
+                kind: kind.to_string(),
+                line,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-index-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(
+            dir.join("src/main.rs"),
+            "fn main() {\n    let parser = Parser::new();\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/parser.rs"),
+            "pub struct Parser;\n\nimpl Parser {\n    pub fn new() -> Self {\n        Parser\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("target/build.rs"), "fn generated() {}\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_text_ignoring_case() {
+        let root = temp_project();
+        let mut index = Index::new(&root);
+        let empty = index.memory_size();
+        index.refresh(u64::MAX, false).unwrap();
+        assert_eq!(index.file_count(), 2);
+        assert!(index.memory_size() > empty);
+
+        let hits = index.find_text("PARSER::new", &Filter::all(), 10).hits;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, root.join("src/main.rs"));
+        assert_eq!(
+            hits[0].position,
+            Position {
+                line: 1,
+                column: 17
+            }
+        );
+        assert!(
+            index
+                .find_text("generated", &Filter::all(), 10)
+                .hits
+                .is_empty()
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn skips_excluded_dirs() {
+        let root = temp_project();
+        fs::create_dir_all(root.join("src/generated")).unwrap();
+        fs::write(root.join("src/generated/parser.rs"), "struct Parser;\n").unwrap();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+        assert_eq!(index.file_count(), 3);
+
+        index.set_excluded_dirs(vec!["generated".to_string()]);
+        index.refresh(u64::MAX, false).unwrap();
+        assert_eq!(index.file_count(), 2);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn filters_text_search_by_glob_and_size() {
+        let root = temp_project();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/parser.md"), "The Parser reads input.\n").unwrap();
+        fs::write(root.join("src/data.bin"), "Parser\0\0").unwrap();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+        let files = |filter: &Filter| {
+            let mut files: Vec<String> = index
+                .find_text("parser", filter, 100)
+                .hits
+                .iter()
+                .map(|hit| index.relative(&hit.path))
+                .collect();
+            files.dedup();
+            files
+        };
+
+        // Binary files are never indexed or searched
+        assert_eq!(
+            files(&Filter::all()),
+            ["docs/parser.md", "src/main.rs", "src/parser.rs"]
+        );
+        let rust = Filter::new(&root, "src/**/*.rs", "", u64::MAX).unwrap();
+        assert_eq!(files(&rust), ["src/main.rs", "src/parser.rs"]);
+        let not_main = Filter::new(&root, "*.rs, *.md", "!main.rs", u64::MAX).unwrap();
+        assert_eq!(files(&not_main), ["docs/parser.md", "src/parser.rs"]);
+        let small = Filter::new(&root, "", "docs/**", 60).unwrap();
+        assert_eq!(files(&small), ["src/main.rs"]);
+        assert!(Filter::new(&root, "src/[", "", u64::MAX).is_err());
+        let relative = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths.iter().map(|path| index.relative(path)).collect()
+        };
+        assert_eq!(
+            relative(index.files(Some("parser"), &rust)),
+            ["src/main.rs", "src/parser.rs"]
+        );
+        assert_eq!(relative(index.files(None, &small)), ["src/main.rs"]);
+
+        let found = index.find_text("parser", &Filter::all(), 2);
+        assert_eq!(found.hits.len(), 2);
+        assert!(found.truncated);
+        let found = index.find_text("parser", &rust, 10);
+        assert!(!found.truncated);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn finds_symbols_by_fuzzy_name() {
+        let root = temp_project();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+        let hits = index.find_symbols("pars", 10);
+        assert_eq!(hits[0].preview, "struct Parser");
+        assert_eq!(hits[0].position.line, 0);
+        let names: Vec<String> = index
+            .find_symbols("", 10)
+            .into_iter()
+            .map(|hit| hit.preview)
+            .collect();
+        assert_eq!(names, vec!["fn new", "fn main", "struct Parser"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn updates_saved_files_and_drops_deleted_ones() {
+        let root = temp_project();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+
+        let main = root.join("src/main.rs");
+        index.update_file(&main, "fn start() {}\n");
+        assert!(
+            index
+                .find_text("Parser::new", &Filter::all(), 10)
+                .hits
+                .is_empty()
+        );
+        assert_eq!(index.find_symbols("start", 10).len(), 1);
+
+        fs::remove_file(root.join("src/parser.rs")).unwrap();
+        index.refresh(u64::MAX, false).unwrap();
+        assert_eq!(index.file_count(), 1);
+        assert!(index.find_symbols("Parser", 10).is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn groups_hits_by_file_in_rank_order() {
+        let hit = |path: &str, line| Hit {
+            path: PathBuf::from(path),
+            position: Position { line, column: 0 },
+            preview: String::new(),
+            ignored: false,
+        };
+        let grouped = group_by_file(vec![
+            hit("b.rs", 4),
+            hit("a.rs", 1),
+            hit("b.rs", 2),
+            hit("a.rs", 0),
+        ]);
+        let order: Vec<(String, usize)> = grouped
+            .iter()
+            .map(|h| (h.path.display().to_string(), h.position.line))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("b.rs".to_string(), 4),
+                ("b.rs".to_string(), 2),
+                ("a.rs".to_string(), 1),
+                ("a.rs".to_string(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn ignored_files_are_left_out_or_flagged() {
+        let root = temp_project();
+        fs::write(root.join(".gitignore"), "parser.rs\n").unwrap();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+        assert!(index.find_symbols("Parser", 10).is_empty());
+
+        index.refresh(u64::MAX, true).unwrap();
+        let hits = index.find_symbols("Parser", 10);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].ignored);
+        assert!(!index.find_symbols("main", 10)[0].ignored);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn stops_at_the_size_cap() {
+        let root = temp_project();
+        let mut index = Index::new(&root);
+        index.refresh(60, false).unwrap();
+        assert_eq!(index.file_count(), 1);
+        assert!(index.is_truncated());
+        assert!(index.indexed_bytes() <= 60);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn index_round_trips_through_disk() {
+        let root = temp_project();
+        let mut index = Index::new(&root);
+        index.refresh(u64::MAX, false).unwrap();
+        index.save().unwrap();
+
+        let loaded = Index::load(&root).unwrap();
+        assert_eq!(loaded.file_count(), 2);
+        assert_eq!(
+            loaded
+                .find_text("struct parser", &Filter::all(), 10)
+                .hits
+                .len(),
+            1
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn extracts_definitions() {
+        let found = symbols(
+            "pub(crate) async fn load() {}\nclass Widget:\n    def draw(self):\nlet x = 1;\n",
+        );
+        let names: Vec<(&str, &str)> = found
+            .iter()
+            .map(|s| (s.kind.as_str(), s.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("fn", "load"), ("class", "Widget"), ("def", "draw")]
+        );
+    }
+}
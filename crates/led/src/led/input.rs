@@ -0,0 +1,121 @@
+use super::types::Position;
+
+/// Typed text longer than this, in bytes, counts as a burst even when it arrives as ordinary
+/// text events, as terminals and some input methods deliver pastes that way.
+pub const BURST_BYTES: usize = 16;
+
+/// The text typed or pasted into an editor in a row, gathered into one insertion.
+///
+/// Inserting it at once makes a large paste a single edit, so it is highlighted once and will
+/// be one step of the undo history, rather than one per chunk of text the platform delivered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Typed {
+    text: String,
+    /// How many text events were gathered.
+    chunks: usize,
+    /// Whether any of them was an explicit paste.
+    pasted: bool,
+}
+
+impl Typed {
+    /// Adds text from a text input event.
+    pub fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.chunks += 1;
+    }
+
+    /// Adds pasted text, with line endings normalized to `\n` as in buffers.
+    pub fn push_paste(&mut self, text: &str) {
+        self.text.push_str(&text.replace("\r\n", "\n"));
+        self.chunks += 1;
+        self.pasted = true;
+    }
+
+    /// Returns whether no text has been gathered.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Returns the gathered text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns whether the text is a burst — a paste, several chunks at once, a line break or
+    /// more than [`BURST_BYTES`] — rather than a keystroke. Features that react to each
+    /// keystroke, such as inserting matching brackets, leave bursts alone.
+    pub fn is_burst(&self) -> bool {
+        self.pasted || self.chunks > 1 || self.text.len() > BURST_BYTES || self.text.contains('\n')
+    }
+
+    /// Returns the gathered text, leaving this empty.
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+}
+
+/// Returns where the caret ends up after inserting text at a position.
+///
+/// # Arguments
+///
+/// * `start` - Where the text is inserted.
+/// * `text` - The inserted text.
+pub fn end_position(start: Position, text: &str) -> Position {
+    match text.rfind('\n') {
+        Some(last_break) => Position {
+            line: start.line + text.matches('\n').count(),
+            column: text.len() - last_break - 1,
+        },
+        None => Position {
+            line: start.line,
+            column: start.column + text.len(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_are_gathered_into_one_burst() {
+        let mut typed = Typed::default();
+        assert!(typed.is_empty());
+        typed.push("a");
+        assert!(!typed.is_burst());
+        typed.push("b");
+        assert!(typed.is_burst());
+        assert_eq!(typed.take().text(), "ab");
+        assert!(typed.is_empty());
+
+        typed.push_paste("x\r\ny");
+        assert_eq!(typed.text(), "x\ny");
+        assert!(typed.is_burst());
+
+        let mut typed = Typed::default();
+        typed.push("\n");
+        assert!(typed.is_burst());
+    }
+
+    #[test]
+    fn end_position_counts_bytes_and_lines() {
+        let start = Position { line: 2, column: 3 };
+        assert_eq!(end_position(start, "é"), Position { line: 2, column: 5 });
+        assert_eq!(
+            end_position(start, "one\ntwo\nthré"),
+            Position { line: 4, column: 5 }
+        );
+        assert_eq!(end_position(start, "x\n"), Position { line: 3, column: 0 });
+    }
+
+    #[test]
+    fn end_position_agrees_with_the_piece_table() {
+        let mut table = crate::led::piece_table::piece::Table::new("\nabc\n".to_string());
+        let mut caret = Position { line: 0, column: 0 };
+        for text in ["é", "x"] {
+            table.insert(table.position_to_offset(caret), text).unwrap();
+            caret = end_position(caret, text);
+        }
+        assert_eq!(table.get_text(0, table.len()), "éx\nabc\n");
+    }
+}
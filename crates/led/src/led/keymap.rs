@@ -0,0 +1,930 @@
+use anyhow::{Context as _, Result as AnyResult, anyhow};
+use saran::key::{Code, Modifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// File name of the keymap inside the project's `.led` directory.
+pub const KEYMAP_FILE: &str = "keymap.json";
+
+/// Returns the path of the keymap file for a project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn keymap_path(project_root: &Path) -> PathBuf {
+    project_root.join(super::notes::STATE_DIR).join(KEYMAP_FILE)
+}
+
+/// Names of the editor actions that can be bound to keys.
+pub mod action {
+    pub const NEW_FILE: &str = "file.new";
+    pub const OPEN_FILE: &str = "file.open";
+    pub const SAVE_FILE: &str = "file.save";
+    pub const RELOAD_FILE: &str = "file.reload";
+    pub const CHECK_CHANGES: &str = "file.check_changes";
+    pub const CLOSE_BUFFER: &str = "file.close";
+    pub const QUIT: &str = "app.quit";
+    pub const TOGGLE_NOTES: &str = "view.toggle_notes";
+    pub const TOGGLE_ZEN: &str = "view.toggle_zen";
+    pub const TOGGLE_MARKS: &str = "view.toggle_marks";
+    pub const SET_MARK: &str = "mark.set";
+    pub const JUMP_TO_MARK: &str = "mark.jump";
+    pub const UNDO: &str = "edit.undo";
+    pub const REDO: &str = "edit.redo";
+    pub const SELECT_ALL: &str = "edit.select_all";
+    pub const GOTO_LAST_EDIT: &str = "edit.goto_last_edit";
+    pub const GOTO_LINE: &str = "edit.goto_line";
+    pub const JUMP_TO_BRACKET: &str = "edit.jump_to_bracket";
+    pub const DELETE_LINE: &str = "edit.delete_line";
+    pub const DUPLICATE_LINE: &str = "edit.duplicate_line";
+    pub const MOVE_LINE_UP: &str = "edit.move_line_up";
+    pub const MOVE_LINE_DOWN: &str = "edit.move_line_down";
+    pub const JOIN_LINES: &str = "edit.join_lines";
+    pub const TOGGLE_COMMENT: &str = "edit.toggle_comment";
+    pub const SORT_LINES: &str = "edit.sort_lines";
+    pub const SORT_LINES_CASE_INSENSITIVE: &str = "edit.sort_lines_case_insensitive";
+    pub const SORT_LINES_REVERSE: &str = "edit.sort_lines_reverse";
+    pub const UPPER_CASE: &str = "edit.upper_case";
+    pub const LOWER_CASE: &str = "edit.lower_case";
+    pub const TITLE_CASE: &str = "edit.title_case";
+    pub const CHANGE_SURROUND: &str = "edit.change_surround";
+    pub const DELETE_SURROUND: &str = "edit.delete_surround";
+    pub const INCREMENT_NUMBER: &str = "edit.increment_number";
+    pub const DECREMENT_NUMBER: &str = "edit.decrement_number";
+    pub const INCREMENT_SEQUENCE: &str = "edit.increment_sequence";
+    pub const EVALUATE_SELECTION: &str = "edit.evaluate_selection";
+    pub const INSERT_DATE: &str = "edit.insert_date";
+    pub const INSERT_TIME: &str = "edit.insert_time";
+    pub const COMMAND_PALETTE: &str = "app.command_palette";
+    pub const TOGGLE_SPLIT: &str = "view.toggle_split";
+    pub const SWITCH_BUFFER: &str = "view.switch_buffer";
+    pub const PANE_ZOOM_IN: &str = "pane.zoom_in";
+    pub const PANE_ZOOM_OUT: &str = "pane.zoom_out";
+    pub const PANE_TOGGLE_WRAP: &str = "pane.toggle_wrap";
+    pub const PANE_RESET: &str = "pane.reset";
+    pub const PANE_SPLIT_RIGHT: &str = "pane.split_right";
+    pub const PANE_SPLIT_DOWN: &str = "pane.split_down";
+    pub const PANE_CLOSE: &str = "pane.close";
+    pub const PANE_FOCUS_LEFT: &str = "pane.focus_left";
+    pub const PANE_FOCUS_RIGHT: &str = "pane.focus_right";
+    pub const PANE_FOCUS_UP: &str = "pane.focus_up";
+    pub const PANE_FOCUS_DOWN: &str = "pane.focus_down";
+    pub const TOGGLE_FOLD: &str = "view.toggle_fold";
+    pub const FOLD_ALL: &str = "view.fold_all";
+    pub const UNFOLD_ALL: &str = "view.unfold_all";
+    pub const FIND: &str = "search.find";
+    pub const REPLACE: &str = "search.replace";
+    pub const FIND_WORD_NEXT: &str = "search.word_next";
+    pub const FIND_WORD_PREVIOUS: &str = "search.word_previous";
+    pub const FIND_IN_FILES: &str = "search.find_in_files";
+    pub const GOTO_SYMBOL: &str = "search.goto_symbol";
+    pub const REBUILD_INDEX: &str = "index.rebuild";
+    pub const IMPORT_SETTINGS: &str = "app.import_settings";
+    pub const CHECK_FOR_UPDATES: &str = "app.check_for_updates";
+    pub const RECORD_INPUT: &str = "app.record_input";
+
+    /// Actions a held key fires only once: those that open, close or toggle something, where
+    /// a repeat would act on what the first press opened or undo it.
+    pub const NOT_REPEATED: &[&str] = &[
+        NEW_FILE,
+        OPEN_FILE,
+        SAVE_FILE,
+        RELOAD_FILE,
+        CHECK_CHANGES,
+        CLOSE_BUFFER,
+        QUIT,
+        TOGGLE_NOTES,
+        TOGGLE_ZEN,
+        TOGGLE_MARKS,
+        SET_MARK,
+        JUMP_TO_MARK,
+        GOTO_LINE,
+        JUMP_TO_BRACKET,
+        TOGGLE_COMMENT,
+        EVALUATE_SELECTION,
+        COMMAND_PALETTE,
+        TOGGLE_SPLIT,
+        PANE_TOGGLE_WRAP,
+        PANE_SPLIT_RIGHT,
+        PANE_SPLIT_DOWN,
+        PANE_CLOSE,
+        TOGGLE_FOLD,
+        FIND,
+        REPLACE,
+        FIND_IN_FILES,
+        GOTO_SYMBOL,
+        REBUILD_INDEX,
+        IMPORT_SETTINGS,
+        CHECK_FOR_UPDATES,
+        RECORD_INPUT,
+    ];
+
+    /// Prefix of the actions that insert a snippet registered from Lua, followed by the
+    /// snippet's name, e.g. `snippet:header`.
+    pub const SNIPPET_PREFIX: &str = "snippet:";
+
+    /// Titles of the actions listed in the command palette.
+    pub const TITLES: &[(&str, &str)] = &[
+        (NEW_FILE, "New File"),
+        (OPEN_FILE, "Open File"),
+        (SAVE_FILE, "Save File"),
+        (RELOAD_FILE, "Reload File from Disk"),
+        (CHECK_CHANGES, "Format and Lint Changed Files"),
+        (CLOSE_BUFFER, "Close Buffer"),
+        (QUIT, "Quit"),
+        (TOGGLE_NOTES, "Toggle Notes"),
+        (TOGGLE_ZEN, "Toggle Zen Mode"),
+        (TOGGLE_MARKS, "Toggle Marks Panel"),
+        (TOGGLE_SPLIT, "Toggle Split View"),
+        (SWITCH_BUFFER, "Switch Buffer"),
+        (PANE_ZOOM_IN, "Zoom In Pane"),
+        (PANE_ZOOM_OUT, "Zoom Out Pane"),
+        (PANE_TOGGLE_WRAP, "Toggle Soft Wrap in Pane"),
+        (PANE_RESET, "Reset Pane Zoom and Wrap"),
+        (PANE_SPLIT_RIGHT, "Split Pane Right"),
+        (PANE_SPLIT_DOWN, "Split Pane Down"),
+        (PANE_CLOSE, "Close Pane"),
+        (PANE_FOCUS_LEFT, "Focus Pane to the Left"),
+        (PANE_FOCUS_RIGHT, "Focus Pane to the Right"),
+        (PANE_FOCUS_UP, "Focus Pane Above"),
+        (PANE_FOCUS_DOWN, "Focus Pane Below"),
+        (TOGGLE_FOLD, "Fold or Unfold at Caret"),
+        (FOLD_ALL, "Fold All"),
+        (UNFOLD_ALL, "Unfold All"),
+        (SET_MARK, "Set Mark"),
+        (JUMP_TO_MARK, "Jump to Mark"),
+        (UNDO, "Undo"),
+        (REDO, "Redo"),
+        (SELECT_ALL, "Select All"),
+        (GOTO_LAST_EDIT, "Go to Last Edit"),
+        (GOTO_LINE, "Go to Line/Column"),
+        (JUMP_TO_BRACKET, "Jump to Matching Bracket"),
+        (DELETE_LINE, "Delete Line"),
+        (DUPLICATE_LINE, "Duplicate Line"),
+        (MOVE_LINE_UP, "Move Line Up"),
+        (MOVE_LINE_DOWN, "Move Line Down"),
+        (JOIN_LINES, "Join Lines"),
+        (TOGGLE_COMMENT, "Toggle Line Comment"),
+        (SORT_LINES, "Sort Lines"),
+        (SORT_LINES_CASE_INSENSITIVE, "Sort Lines, Ignoring Case"),
+        (SORT_LINES_REVERSE, "Sort Lines in Reverse"),
+        (UPPER_CASE, "Transform to Upper Case"),
+        (LOWER_CASE, "Transform to Lower Case"),
+        (TITLE_CASE, "Transform to Title Case"),
+        (CHANGE_SURROUND, "Change Surrounding Pair"),
+        (DELETE_SURROUND, "Delete Surrounding Pair"),
+        (INCREMENT_NUMBER, "Increment Number"),
+        (DECREMENT_NUMBER, "Decrement Number"),
+        (INCREMENT_SEQUENCE, "Increment Numbers as Sequence"),
+        (EVALUATE_SELECTION, "Evaluate Selection"),
+        (INSERT_DATE, "Insert Date"),
+        (INSERT_TIME, "Insert Time"),
+        (FIND, "Find"),
+        (REPLACE, "Find and Replace"),
+        (FIND_WORD_NEXT, "Find Next Occurrence of Word"),
+        (FIND_WORD_PREVIOUS, "Find Previous Occurrence of Word"),
+        (FIND_IN_FILES, "Find in Files"),
+        (GOTO_SYMBOL, "Go to Symbol in Workspace"),
+        (REBUILD_INDEX, "Rebuild Project Index"),
+        (
+            IMPORT_SETTINGS,
+            "Import Settings from VS Code or Sublime Text",
+        ),
+        (CHECK_FOR_UPDATES, "Check for Updates"),
+        (RECORD_INPUT, "Start or Stop Recording Input"),
+    ];
+}
+
+/// Default bindings shared by all platforms. `mod` is the platform's primary modifier.
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("mod+n", action::NEW_FILE),
+    ("mod+o", action::OPEN_FILE),
+    ("mod+s", action::SAVE_FILE),
+    ("mod+w", action::CLOSE_BUFFER),
+    ("mod+q", action::QUIT),
+    ("mod+shift+n", action::TOGGLE_NOTES),
+    ("mod+alt+z", action::TOGGLE_ZEN),
+    ("mod+shift+m", action::TOGGLE_MARKS),
+    ("mod+m", action::SET_MARK),
+    ("mod+'", action::JUMP_TO_MARK),
+    ("mod+z", action::UNDO),
+    ("mod+shift+z", action::REDO),
+    ("mod+a", action::SELECT_ALL),
+    ("mod+shift+backspace", action::GOTO_LAST_EDIT),
+    ("mod+g", action::GOTO_LINE),
+    ("mod+]", action::JUMP_TO_BRACKET),
+    ("mod+shift+k", action::DELETE_LINE),
+    ("mod+shift+d", action::DUPLICATE_LINE),
+    ("alt+up", action::MOVE_LINE_UP),
+    ("alt+down", action::MOVE_LINE_DOWN),
+    ("mod+j", action::JOIN_LINES),
+    ("mod+/", action::TOGGLE_COMMENT),
+    ("f9", action::SORT_LINES),
+    ("mod+alt+s", action::CHANGE_SURROUND),
+    ("mod+alt+d", action::DELETE_SURROUND),
+    ("mod+up", action::INCREMENT_NUMBER),
+    ("mod+down", action::DECREMENT_NUMBER),
+    ("mod+shift+e", action::EVALUATE_SELECTION),
+    ("mod+shift+p", action::COMMAND_PALETTE),
+    ("mod+\\", action::TOGGLE_SPLIT),
+    ("ctrl+tab", action::SWITCH_BUFFER),
+    ("mod+[", action::TOGGLE_FOLD),
+    ("mod+alt+[", action::FOLD_ALL),
+    ("mod+alt+]", action::UNFOLD_ALL),
+    ("mod+alt+w", action::PANE_TOGGLE_WRAP),
+    ("mod+alt+\\", action::PANE_SPLIT_RIGHT),
+    ("mod+alt+-", action::PANE_SPLIT_DOWN),
+    ("mod+alt+x", action::PANE_CLOSE),
+    ("mod+alt+h", action::PANE_FOCUS_LEFT),
+    ("mod+alt+l", action::PANE_FOCUS_RIGHT),
+    ("mod+alt+k", action::PANE_FOCUS_UP),
+    ("mod+alt+j", action::PANE_FOCUS_DOWN),
+    ("mod+f", action::FIND),
+    ("mod+h", action::REPLACE),
+    ("mod+f3", action::FIND_WORD_NEXT),
+    ("mod+shift+f3", action::FIND_WORD_PREVIOUS),
+    ("mod+shift+f", action::FIND_IN_FILES),
+    ("mod+t", action::GOTO_SYMBOL),
+];
+
+/// The operating system family, which decides the primary modifier and key labels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Linux,
+    Windows,
+    MacOS,
+}
+
+impl Platform {
+    /// Returns the platform LED was compiled for.
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::MacOS
+        } else if cfg!(target_os = "windows") {
+            Self::Windows
+        } else {
+            Self::Linux
+        }
+    }
+
+    /// Returns the modifier used for most shortcuts: Command on macOS, Control elsewhere.
+    pub fn primary_modifier(self) -> Modifiers {
+        Modifiers::primary(self == Self::MacOS)
+    }
+}
+
+/// A key together with the modifiers that must be held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key: Code,
+}
+
+impl Chord {
+    /// Creates a new chord.
+    pub fn new(modifiers: Modifiers, key: Code) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Parses a chord such as `ctrl+shift+p` or `mod+s`.
+    ///
+    /// Modifier names are case-insensitive. `mod` (or `primary`) means the platform's primary
+    /// modifier; `cmd`, `super`, `meta` and `win` all name the Super key.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The textual chord.
+    /// * `platform` - The platform used to resolve `mod`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a modifier or key name is not recognized.
+    pub fn parse(spec: &str, platform: Platform) -> AnyResult<Self> {
+        let parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+        // A trailing empty part means the key itself is `+`, as in `ctrl++`
+        let (key_name, modifier_names) = match parts.as_slice() {
+            [mods @ .., "", ""] if !mods.is_empty() => ("+", mods),
+            [mods @ .., key] => (*key, mods),
+            [] => unreachable!("split always yields at least one part"),
+        };
+
+        let mut modifiers = Modifiers::empty();
+        for name in modifier_names {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "mod" | "primary" => platform.primary_modifier(),
+                "ctrl" | "control" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "opt" | "option" => Modifiers::ALT,
+                "cmd" | "command" | "super" | "meta" | "win" => Modifiers::SUPER,
+                _ => return Err(anyhow!("unknown modifier `{}` in `{}`", name, spec)),
+            };
+        }
+        let key = parse_key(key_name)
+            .ok_or_else(|| anyhow!("unknown key `{}` in `{}`", key_name, spec))?;
+        Ok(Self { modifiers, key }.normalized())
+    }
+
+    /// Returns the chord in the canonical form used for keymap lookups.
+    ///
+    /// Punctuation keys are reported by their produced symbol, which already reflects Shift
+    /// (`ctrl+shift+=` arrives as `ctrl+shift++` on a US layout, but `ctrl++` elsewhere), so
+    /// Shift is dropped for them.
+    pub fn normalized(self) -> Self {
+        match self.key {
+            Code::Other(_) => Self {
+                modifiers: self.modifiers - Modifiers::SHIFT,
+                key: self.key,
+            },
+            _ => self,
+        }
+    }
+
+    /// Turns a raw key press into the chord to look up, or `None` if it should be treated as typing.
+    ///
+    /// A press that also produced text only counts as a shortcut while Ctrl or Super is held, so
+    /// Option+letter on macOS keeps typing characters. Ctrl+Alt together with text is AltGr (as
+    /// reported on Windows), which international layouts use for characters like `@` or `{`, so
+    /// it never counts as a shortcut.
+    ///
+    /// # Arguments
+    ///
+    /// * `modifiers` - The modifiers held during the press.
+    /// * `key` - The logical key that was pressed.
+    /// * `produced_text` - Whether the press also produced a text input event.
+    pub fn from_key_press(modifiers: Modifiers, key: Code, produced_text: bool) -> Option<Self> {
+        if produced_text {
+            let altgr = modifiers.contains(Modifiers::CTRL | Modifiers::ALT);
+            let command = modifiers.intersects(Modifiers::CTRL | Modifiers::SUPER);
+            if altgr || !command {
+                return None;
+            }
+        }
+        Some(Self::new(modifiers, key).normalized())
+    }
+
+    /// Returns the chord as it is conventionally written on the given platform, e.g. `Cmd+S`.
+    pub fn label(&self, platform: Platform) -> String {
+        let mac = platform == Platform::MacOS;
+        let mut parts = Vec::new();
+        if self.modifiers.ctrl() {
+            parts.push("Ctrl");
+        }
+        if self.modifiers.alt() {
+            parts.push(if mac { "Opt" } else { "Alt" });
+        }
+        if self.modifiers.shift() {
+            parts.push("Shift");
+        }
+        if self.modifiers.super_key() {
+            parts.push(match platform {
+                Platform::MacOS => "Cmd",
+                Platform::Windows => "Win",
+                Platform::Linux => "Super",
+            });
+        }
+        let key = key_name(self.key);
+        parts.push(&key);
+        parts.join("+")
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label(Platform::current()))
+    }
+}
+
+/// Parses a key name such as `s`, `f5`, `pageup` or `/`.
+fn parse_key(name: &str) -> Option<Code> {
+    let lower = name.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "enter" | "return" => Code::Enter,
+        "escape" | "esc" => Code::Escape,
+        "tab" => Code::Tab,
+        "backspace" => Code::Backspace,
+        "up" | "arrowup" => Code::ArrowUp,
+        "down" | "arrowdown" => Code::ArrowDown,
+        "left" | "arrowleft" => Code::ArrowLeft,
+        "right" | "arrowright" => Code::ArrowRight,
+        "home" => Code::Home,
+        "end" => Code::End,
+        "pageup" => Code::PageUp,
+        "pagedown" => Code::PageDown,
+        "delete" | "del" => Code::Delete,
+        "insert" | "ins" => Code::Insert,
+        "space" => Code::Space,
+        _ => {
+            if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                return function_key(n);
+            }
+            let mut chars = lower.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            return char_key(c);
+        }
+    };
+    Some(code)
+}
+
+const LETTERS: [Code; 26] = [
+    Code::A,
+    Code::B,
+    Code::C,
+    Code::D,
+    Code::E,
+    Code::F,
+    Code::G,
+    Code::H,
+    Code::I,
+    Code::J,
+    Code::K,
+    Code::L,
+    Code::M,
+    Code::N,
+    Code::O,
+    Code::P,
+    Code::Q,
+    Code::R,
+    Code::S,
+    Code::T,
+    Code::U,
+    Code::V,
+    Code::W,
+    Code::X,
+    Code::Y,
+    Code::Z,
+];
+
+const DIGITS: [Code; 10] = [
+    Code::Zero,
+    Code::One,
+    Code::Two,
+    Code::Three,
+    Code::Four,
+    Code::Five,
+    Code::Six,
+    Code::Seven,
+    Code::Eight,
+    Code::Nine,
+];
+
+const FUNCTION_KEYS: [Code; 12] = [
+    Code::F1,
+    Code::F2,
+    Code::F3,
+    Code::F4,
+    Code::F5,
+    Code::F6,
+    Code::F7,
+    Code::F8,
+    Code::F9,
+    Code::F10,
+    Code::F11,
+    Code::F12,
+];
+
+fn function_key(n: u8) -> Option<Code> {
+    FUNCTION_KEYS.get(usize::from(n).checked_sub(1)?).copied()
+}
+
+/// Maps a single character to its key, using `Code::Other` for punctuation.
+fn char_key(c: char) -> Option<Code> {
+    match c {
+        'a'..='z' => Some(LETTERS[(c as u8 - b'a') as usize]),
+        '0'..='9' => Some(DIGITS[(c as u8 - b'0') as usize]),
+        c if c.is_ascii_punctuation() => Some(Code::Other(c as u32)),
+        _ => None,
+    }
+}
+
+/// Returns the display name of a key.
+fn key_name(key: Code) -> String {
+    if let Some(i) = LETTERS.iter().position(|k| *k == key) {
+        return char::from(b'A' + i as u8).to_string();
+    }
+    if let Some(i) = DIGITS.iter().position(|k| *k == key) {
+        return i.to_string();
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|k| *k == key) {
+        return format!("F{}", i + 1);
+    }
+    match key {
+        Code::Enter => "Enter".to_string(),
+        Code::Escape => "Esc".to_string(),
+        Code::Tab => "Tab".to_string(),
+        Code::Backspace => "Backspace".to_string(),
+        Code::ArrowUp => "Up".to_string(),
+        Code::ArrowDown => "Down".to_string(),
+        Code::ArrowLeft => "Left".to_string(),
+        Code::ArrowRight => "Right".to_string(),
+        Code::Home => "Home".to_string(),
+        Code::End => "End".to_string(),
+        Code::PageUp => "PageUp".to_string(),
+        Code::PageDown => "PageDown".to_string(),
+        Code::Delete => "Delete".to_string(),
+        Code::Insert => "Insert".to_string(),
+        Code::Space => "Space".to_string(),
+        Code::Other(code) => char::from_u32(code)
+            .filter(|c| c.is_ascii_punctuation())
+            .map_or_else(|| format!("Key{}", code), |c| c.to_string()),
+        other => format!("{:?}", other),
+    }
+}
+
+/// The contents of a keymap file.
+///
+/// Each table maps a chord to an action name; `null` removes an existing binding. Entries in
+/// the platform tables are applied after `bindings`, so they override it on that platform.
+///
+/// ```json
+/// {
+///     "bindings": { "mod+shift+n": "view.toggle_notes" },
+///     "macos": { "cmd+ctrl+z": "view.toggle_zen", "cmd+alt+z": null }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct File {
+    pub bindings: HashMap<String, Option<String>>,
+    pub linux: HashMap<String, Option<String>>,
+    pub windows: HashMap<String, Option<String>>,
+    pub macos: HashMap<String, Option<String>>,
+}
+
+impl File {
+    /// Returns the override table for a platform.
+    fn platform_bindings(&self, platform: Platform) -> &HashMap<String, Option<String>> {
+        match platform {
+            Platform::Linux => &self.linux,
+            Platform::Windows => &self.windows,
+            Platform::MacOS => &self.macos,
+        }
+    }
+}
+
+/// Maps key chords to action names for one platform.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    platform: Platform,
+    bindings: HashMap<Chord, String>,
+}
+
+impl Keymap {
+    /// Returns the built-in keymap for a platform.
+    pub fn defaults(platform: Platform) -> Self {
+        let mut keymap = Self {
+            platform,
+            bindings: HashMap::new(),
+        };
+        for (spec, action) in DEFAULT_BINDINGS {
+            let chord = Chord::parse(spec, platform).expect("default bindings are valid");
+            keymap.bind(chord, *action);
+        }
+        keymap
+    }
+
+    /// Loads the default keymap and applies the project's keymap file, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    /// * `platform` - The platform to build the keymap for.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keymap file cannot be read or contains invalid chords.
+    pub fn load(project_root: &Path, platform: Platform) -> AnyResult<Self> {
+        let mut keymap = Self::defaults(platform);
+        let path = keymap_path(project_root);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let file: File = serde_json::from_str(&content)
+                    .with_context(|| format!("failed to parse {}", path.display()))?;
+                keymap
+                    .apply(&file)
+                    .with_context(|| format!("invalid keymap in {}", path.display()))?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(keymap)
+    }
+
+    /// Applies the shared and platform-specific tables of a keymap file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a chord cannot be parsed. Bindings before the invalid one are kept.
+    pub fn apply(&mut self, file: &File) -> AnyResult<()> {
+        for table in [&file.bindings, file.platform_bindings(self.platform)] {
+            // Sort so that the outcome does not depend on hash map order
+            let mut entries: Vec<_> = table.iter().collect();
+            entries.sort_by_key(|(spec, _)| spec.as_str());
+            for (spec, action) in entries {
+                let chord = Chord::parse(spec, self.platform)?;
+                match action {
+                    Some(action) => self.bind(chord, action.clone()),
+                    None => self.unbind(chord),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the platform this keymap was built for.
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Binds a chord to an action, replacing any previous binding of that chord.
+    pub fn bind(&mut self, chord: Chord, action: impl Into<String>) {
+        self.bindings.insert(chord.normalized(), action.into());
+    }
+
+    /// Removes the binding of a chord, if any.
+    pub fn unbind(&mut self, chord: Chord) {
+        self.bindings.remove(&chord.normalized());
+    }
+
+    /// Returns the action bound to a chord.
+    pub fn action(&self, chord: Chord) -> Option<&str> {
+        self.bindings.get(&chord.normalized()).map(String::as_str)
+    }
+
+    /// Removes key presses bound in the keymap from this frame's input and returns their
+    /// actions.
+    ///
+    /// Consuming the events keeps bound shortcuts from also reaching the editor widget. A press
+    /// that produced text is followed by its `Text` event; that event is dropped along with a
+    /// matched press, and its presence lets AltGr and Option combinations keep typing
+    /// characters instead of firing shortcuts. Auto-repeat fires actions again, except those in
+    /// [`action::NOT_REPEATED`].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context whose input is read.
+    /// * `handles` - Whether the caller handles an action; presses bound to other actions are
+    ///   left in the input.
+    pub fn take_actions(&self, ctx: &egui::Context, handles: impl Fn(&str) -> bool) -> Vec<String> {
+        ctx.input_mut(|i| {
+            let mut actions = Vec::new();
+            let mut events = std::mem::take(&mut i.events).into_iter().peekable();
+            while let Some(event) = events.next() {
+                let egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat,
+                    modifiers,
+                    ..
+                } = event
+                else {
+                    i.events.push(event);
+                    continue;
+                };
+                let produced_text = matches!(events.peek(), Some(egui::Event::Text(_)));
+                let action = Chord::from_key_press(modifiers.into(), key.into(), produced_text)
+                    .and_then(|chord| self.action(chord))
+                    .filter(|action| handles(action));
+                match action {
+                    Some(action) => {
+                        if !repeat || !action::NOT_REPEATED.contains(&action) {
+                            actions.push(action.to_string());
+                        }
+                        if produced_text {
+                            events.next();
+                        }
+                    }
+                    None => i.events.push(event),
+                }
+            }
+            actions
+        })
+    }
+
+    /// Returns the chord bound to an action. If several are bound, the one with the shortest label wins.
+    pub fn chord_for(&self, action: &str) -> Option<Chord> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| bound.as_str() == action)
+            .map(|(chord, _)| (chord.label(self.platform), *chord))
+            .min_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .map(|(_, chord)| chord)
+    }
+
+    /// Returns the label of the chord bound to an action, for display in menus.
+    pub fn label_for(&self, action: &str) -> Option<String> {
+        self.chord_for(action)
+            .map(|chord| chord.label(self.platform))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_resolves_primary_modifier_per_platform() {
+        let linux = Chord::parse("mod+s", Platform::Linux).unwrap();
+        let mac = Chord::parse("mod+s", Platform::MacOS).unwrap();
+        assert_eq!(linux, Chord::new(Modifiers::CTRL, Code::S));
+        assert_eq!(mac, Chord::new(Modifiers::SUPER, Code::S));
+    }
+
+    #[test]
+    fn parse_accepts_aliases_and_case() {
+        let chord = Chord::parse("Cmd+Option+Shift+F5", Platform::Linux).unwrap();
+        assert_eq!(
+            chord,
+            Chord::new(
+                Modifiers::SUPER | Modifiers::ALT | Modifiers::SHIFT,
+                Code::F5
+            )
+        );
+        assert_eq!(
+            Chord::parse("ctrl+/", Platform::Linux).unwrap().key,
+            Code::Other('/' as u32)
+        );
+        assert_eq!(
+            Chord::parse("ctrl++", Platform::Linux).unwrap(),
+            Chord::new(Modifiers::CTRL, Code::Other('+' as u32))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_names() {
+        assert!(Chord::parse("hyper+s", Platform::Linux).is_err());
+        assert!(Chord::parse("ctrl+f13", Platform::Linux).is_err());
+        assert!(Chord::parse("ctrl+", Platform::Linux).is_err());
+    }
+
+    #[test]
+    fn shift_is_ignored_for_punctuation() {
+        let shifted = Chord::parse("ctrl+shift+=", Platform::Linux).unwrap();
+        assert_eq!(shifted, Chord::parse("ctrl+=", Platform::Linux).unwrap());
+        assert_eq!(
+            Chord::parse("ctrl+shift+a", Platform::Linux)
+                .unwrap()
+                .modifiers,
+            Modifiers::CTRL | Modifiers::SHIFT
+        );
+
+        let mut keymap = Keymap::defaults(Platform::Linux);
+        keymap.bind(
+            Chord::new(Modifiers::CTRL, Code::Other('+' as u32)),
+            "zoom.in",
+        );
+        let pressed = Chord::new(Modifiers::CTRL | Modifiers::SHIFT, Code::Other('+' as u32));
+        assert_eq!(keymap.action(pressed), Some("zoom.in"));
+    }
+
+    #[test]
+    fn altgr_text_is_not_a_shortcut() {
+        let altgr = Modifiers::CTRL | Modifiers::ALT;
+        assert_eq!(Chord::from_key_press(altgr, Code::Q, true), None);
+        assert_eq!(
+            Chord::from_key_press(altgr, Code::Q, false),
+            Some(Chord::new(altgr, Code::Q))
+        );
+    }
+
+    #[test]
+    fn text_without_command_modifier_is_typing() {
+        assert_eq!(
+            Chord::from_key_press(Modifiers::empty(), Code::A, true),
+            None
+        );
+        assert_eq!(Chord::from_key_press(Modifiers::SHIFT, Code::A, true), None);
+        // Option+letter types characters on macOS
+        assert_eq!(Chord::from_key_press(Modifiers::ALT, Code::Z, true), None);
+        assert_eq!(
+            Chord::from_key_press(Modifiers::SUPER, Code::S, true),
+            Some(Chord::new(Modifiers::SUPER, Code::S))
+        );
+        assert_eq!(
+            Chord::from_key_press(Modifiers::empty(), Code::F5, false),
+            Some(Chord::new(Modifiers::empty(), Code::F5))
+        );
+    }
+
+    #[test]
+    fn labels_follow_platform_conventions() {
+        let chord = Chord::new(
+            Modifiers::SUPER | Modifiers::SHIFT | Modifiers::ALT,
+            Code::N,
+        );
+        assert_eq!(chord.label(Platform::MacOS), "Opt+Shift+Cmd+N");
+        assert_eq!(chord.label(Platform::Windows), "Alt+Shift+Win+N");
+        assert_eq!(
+            Chord::new(Modifiers::CTRL, Code::Seven).label(Platform::Linux),
+            "Ctrl+7"
+        );
+    }
+
+    #[test]
+    fn held_keys_repeat_edits_but_not_toggles() {
+        let keymap = Keymap::defaults(Platform::Linux);
+        let press = |key, repeat| egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat,
+            modifiers: egui::Modifiers::CTRL,
+        };
+        let ctx = egui::Context::default();
+        ctx.begin_pass(egui::RawInput {
+            events: vec![
+                press(egui::Key::Z, false),
+                press(egui::Key::Z, true),
+                press(egui::Key::Z, true),
+                press(egui::Key::W, false),
+                press(egui::Key::W, true),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            keymap.take_actions(&ctx, |_| true),
+            [
+                action::UNDO,
+                action::UNDO,
+                action::UNDO,
+                action::CLOSE_BUFFER
+            ]
+        );
+        let _ = ctx.end_pass();
+    }
+
+    #[test]
+    fn default_chords_are_unique() {
+        for platform in [Platform::Linux, Platform::Windows, Platform::MacOS] {
+            let mut seen = HashMap::new();
+            for (spec, action) in DEFAULT_BINDINGS {
+                let chord = Chord::parse(spec, platform).unwrap().normalized();
+                if let Some(other) = seen.insert(chord, *action) {
+                    panic!(
+                        "`{}` binds both {} and {} on {:?}",
+                        spec, other, action, platform
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn defaults_use_cmd_on_macos() {
+        let keymap = Keymap::defaults(Platform::MacOS);
+        assert_eq!(
+            keymap.action(Chord::new(Modifiers::SUPER, Code::S)),
+            Some(action::SAVE_FILE)
+        );
+        assert_eq!(keymap.action(Chord::new(Modifiers::CTRL, Code::S)), None);
+        assert_eq!(
+            keymap.label_for(action::TOGGLE_NOTES).as_deref(),
+            Some("Shift+Cmd+N")
+        );
+    }
+
+    #[test]
+    fn platform_overrides_apply_after_shared_bindings() {
+        let file: File = serde_json::from_str(
+            r#"{
+                "bindings": { "ctrl+k": "view.toggle_zen" },
+                "macos": { "ctrl+k": null, "cmd+s": "custom.save" },
+                "linux": { "ctrl+k": "view.toggle_notes" }
+            }"#,
+        )
+        .unwrap();
+
+        let mut mac = Keymap::defaults(Platform::MacOS);
+        mac.apply(&file).unwrap();
+        assert_eq!(mac.action(Chord::new(Modifiers::CTRL, Code::K)), None);
+        assert_eq!(
+            mac.action(Chord::new(Modifiers::SUPER, Code::S)),
+            Some("custom.save")
+        );
+
+        let mut linux = Keymap::defaults(Platform::Linux);
+        linux.apply(&file).unwrap();
+        assert_eq!(
+            linux.action(Chord::new(Modifiers::CTRL, Code::K)),
+            Some(action::TOGGLE_NOTES)
+        );
+    }
+
+    #[test]
+    fn file_rejects_unknown_sections() {
+        assert!(serde_json::from_str::<File>(r#"{"bsd": {}}"#).is_err());
+    }
+
+    #[test]
+    fn load_without_file_returns_defaults() {
+        let root = std::env::temp_dir().join(format!("led-keymap-{}", uuid::Uuid::new_v4()));
+        let keymap = Keymap::load(&root, Platform::Linux).unwrap();
+        assert_eq!(
+            keymap.action(Chord::new(Modifiers::CTRL, Code::O)),
+            Some(action::OPEN_FILE)
+        );
+    }
+}
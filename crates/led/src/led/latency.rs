@@ -0,0 +1,215 @@
+use super::buffer::editor::State;
+use super::commands::editor::Command;
+use super::types::buffer;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The longest a keystroke may take to show up on screen: one frame at 60 Hz.
+pub const KEYSTROKE_BUDGET: Duration = Duration::from_micros(16_667);
+/// How many of the most recent latencies are kept for the percentiles.
+const MAX_SAMPLES: usize = 1000;
+/// A summary is logged every time this many keystrokes have been measured.
+const LOG_EVERY: usize = 200;
+
+/// Percentiles of the measured keystroke-to-paint latencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of latencies the percentiles are taken over.
+    pub count: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Summary {
+    /// Returns whether the 99th percentile is within a budget.
+    pub fn within(&self, budget: Duration) -> bool {
+        self.p99 <= budget
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "p50 {:.1}ms, p95 {:.1}ms, p99 {:.1}ms, max {:.1}ms over {} keystrokes",
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.p99),
+            ms(self.max),
+            self.count
+        )
+    }
+}
+
+/// Measures the time from receiving a key event to painting the frame that reflects it.
+///
+/// Keystrokes are recorded as they are received and stay pending until the next paint, which
+/// completes all of them at once.
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    /// When each keystroke waiting for a paint was received.
+    pending: Vec<Instant>,
+    /// The most recent latencies, oldest first.
+    samples: VecDeque<Duration>,
+    /// Keystrokes measured since the last logged summary.
+    unlogged: usize,
+}
+
+impl Tracker {
+    /// Records a keystroke received at `at`.
+    pub fn record_input(&mut self, at: Instant) {
+        self.pending.push(at);
+    }
+
+    /// Records a paint finished at `at`, completing the pending keystrokes.
+    ///
+    /// # Returns
+    ///
+    /// A summary to log, once every few hundred keystrokes.
+    pub fn record_paint(&mut self, at: Instant) -> Option<Summary> {
+        for received in self.pending.drain(..) {
+            if self.samples.len() == MAX_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples
+                .push_back(at.saturating_duration_since(received));
+            self.unlogged += 1;
+        }
+        if self.unlogged < LOG_EVERY {
+            return None;
+        }
+        self.unlogged = 0;
+        self.summary()
+    }
+
+    /// Returns the percentiles of the recent latencies, or `None` before the first keystroke.
+    pub fn summary(&self) -> Option<Summary> {
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let max = *sorted.last()?;
+        // Nearest-rank percentile
+        let rank = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Some(Summary {
+            count: sorted.len(),
+            p50: rank(50),
+            p95: rank(95),
+            p99: rank(99),
+            max,
+        })
+    }
+}
+
+/// Replays typed text on a buffer through the core editing path and measures each keystroke.
+///
+/// Every character goes through the same commands the editor widget issues for a keystroke,
+/// followed by the buffer reads a frame does before painting. Rendering itself is not
+/// included, so this measures the part of the budget the core is responsible for.
+///
+/// # Arguments
+///
+/// * `state` - The editor state holding the buffer.
+/// * `buffer_id` - The buffer to type into, at its cursor.
+/// * `script` - The keystrokes; `\n` presses Enter and `\u{8}` presses Backspace.
+pub fn replay(state: &mut State, buffer_id: buffer::ID, script: &str) -> Tracker {
+    let mut tracker = Tracker::default();
+    for key in script.chars() {
+        tracker.record_input(Instant::now());
+        let Some(position) = state
+            .get_cursor_state(buffer_id)
+            .map(|cursor| cursor.position())
+        else {
+            break;
+        };
+        let Some(offset) = state
+            .buffers()
+            .get(&buffer_id)
+            .map(|table| table.position_to_offset(position))
+        else {
+            break;
+        };
+        let mut new_pos = position;
+        let edit = match key {
+            '\u{8}' if offset > 0 => {
+                new_pos = state.buffers()[&buffer_id].offset_to_position(offset - 1);
+                Some(Command::DeleteText {
+                    buffer_id,
+                    start: offset - 1,
+                    length: 1,
+                })
+            }
+            // Backspace at the start of the buffer does nothing
+            '\u{8}' => None,
+            '\n' => {
+                new_pos.line += 1;
+                new_pos.column = 0;
+                Some(Command::InsertText {
+                    buffer_id,
+                    offset,
+                    text: "\n".to_string(),
+                })
+            }
+            _ => {
+                new_pos.column += key.len_utf8();
+                Some(Command::InsertText {
+                    buffer_id,
+                    offset,
+                    text: key.to_string(),
+                })
+            }
+        };
+        if let Some(edit) = edit {
+            let _ = state.execute_command(edit);
+            let _ = state.execute_command(Command::MoveCursor {
+                buffer_id,
+                position: new_pos,
+            });
+        }
+        // What a frame reads before painting the buffer
+        if let Some(text) = state.get_buffer_text(buffer_id) {
+            std::hint::black_box(text.lines().count());
+        }
+        tracker.record_paint(Instant::now());
+    }
+    tracker
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_keystrokes_complete_at_the_next_paint() {
+        let start = Instant::now();
+        let mut tracker = Tracker::default();
+        assert!(tracker.summary().is_none());
+        tracker.record_input(start);
+        tracker.record_input(start + Duration::from_millis(4));
+        tracker.record_paint(start + Duration::from_millis(10));
+        // A paint without keystrokes measures nothing
+        tracker.record_paint(start + Duration::from_millis(50));
+
+        let summary = tracker.summary().unwrap();
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.p50, Duration::from_millis(6));
+        assert_eq!(summary.max, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let start = Instant::now();
+        let mut tracker = Tracker::default();
+        let mut logged = None;
+        for ms in 1..=LOG_EVERY as u64 {
+            tracker.record_input(start);
+            logged = tracker.record_paint(start + Duration::from_millis(ms));
+        }
+        let summary = logged.expect("a summary is logged every LOG_EVERY keystrokes");
+        assert_eq!(summary.p50, Duration::from_millis(100));
+        assert_eq!(summary.p95, Duration::from_millis(190));
+        assert_eq!(summary.p99, Duration::from_millis(198));
+        assert!(!summary.within(Duration::from_millis(100)));
+    }
+}
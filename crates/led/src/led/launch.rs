@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+/// Command-line argument after which every argument names a file, even one starting with `-`.
+pub const END_OF_FLAGS: &str = "--";
+
+/// A file named on the command line, such as `src/main.rs` or `src/lib.rs:42:7`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArg {
+    pub path: PathBuf,
+    /// Where to put the caret, as `line` or `line:column` counting from 1, for
+    /// [`goto::parse`](super::goto::parse) once the file is loaded.
+    pub location: Option<String>,
+}
+
+/// Reads the files to open from the command line.
+///
+/// Arguments starting with `-` are flags, such as `--portable`, and are skipped until
+/// [`END_OF_FLAGS`]. A file may be followed by `:line` or `:line:column`.
+///
+/// # Arguments
+///
+/// * `args` - The command-line arguments, without the program name.
+pub fn files(args: impl IntoIterator<Item = String>) -> Vec<FileArg> {
+    let mut flags = true;
+    let mut files = Vec::new();
+    for arg in args {
+        if flags && arg == END_OF_FLAGS {
+            flags = false;
+            continue;
+        }
+        if flags && arg.starts_with('-') {
+            continue;
+        }
+        let (path, location) = split_location(&arg);
+        files.push(FileArg {
+            path: PathBuf::from(path),
+            location: location.map(str::to_string),
+        });
+    }
+    files
+}
+
+/// Splits up to two trailing `:number` parts off an argument, leaving other colons, such as
+/// that of a Windows drive, in the path.
+fn split_location(arg: &str) -> (&str, Option<&str>) {
+    let numeric = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let mut path = arg;
+    for _ in 0..2 {
+        match path.rsplit_once(':') {
+            Some((rest, part)) if !rest.is_empty() && numeric(part) => path = rest,
+            _ => break,
+        }
+    }
+    let location = arg.get(path.len() + 1..).filter(|_| path.len() < arg.len());
+    (path, location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn locations_are_split_off_trailing_numbers_only() {
+        assert_eq!(split_location("src/main.rs"), ("src/main.rs", None));
+        assert_eq!(split_location("src/lib.rs:42"), ("src/lib.rs", Some("42")));
+        assert_eq!(
+            split_location("src/lib.rs:42:7"),
+            ("src/lib.rs", Some("42:7"))
+        );
+        assert_eq!(
+            split_location(r"C:\src\a.rs:3"),
+            (r"C:\src\a.rs", Some("3"))
+        );
+        assert_eq!(split_location("notes:todo"), ("notes:todo", None));
+        assert_eq!(split_location("a:1:2:3"), ("a:1", Some("2:3")));
+        assert_eq!(split_location(":5"), (":5", None));
+    }
+
+    #[test]
+    fn flags_are_skipped_until_the_end_of_flags() {
+        let files = files(args(&["--portable", "a.rs:2", "--", "--clean", "-"]));
+        let paths: Vec<&str> = files.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["a.rs", "--clean", "-"]);
+        assert_eq!(files[0].location.as_deref(), Some("2"));
+    }
+}
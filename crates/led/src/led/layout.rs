@@ -0,0 +1,259 @@
+use egui::{Rect, pos2};
+
+/// How the panes of a split are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Side by side, from left to right.
+    Row,
+    /// Stacked, from top to bottom.
+    Column,
+}
+
+/// A direction to move the keyboard focus between panes in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// How the editor area is divided into panes: a single pane, or panes side by side or stacked,
+/// each of which may be divided further. Panes share the space of their split equally.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout<T> {
+    Pane(T),
+    Split { axis: Axis, parts: Vec<Layout<T>> },
+}
+
+impl<T: Copy + PartialEq> Layout<T> {
+    /// Returns the panes in reading order: left to right and top to bottom.
+    pub fn panes(&self) -> Vec<T> {
+        match self {
+            Self::Pane(pane) => vec![*pane],
+            Self::Split { parts, .. } => parts.iter().flat_map(Self::panes).collect(),
+        }
+    }
+
+    /// Splits a pane, placing a new pane after it: to its right for [`Axis::Row`], below it for
+    /// [`Axis::Column`]. Splitting a pane along the axis of the split it is in adds the new pane
+    /// to that split rather than nesting another one.
+    ///
+    /// # Returns
+    ///
+    /// Whether the pane was found.
+    pub fn split(&mut self, pane: T, axis: Axis, new: T) -> bool {
+        match self {
+            Self::Pane(id) if *id == pane => {
+                *self = Self::Split {
+                    axis,
+                    parts: vec![Self::Pane(pane), Self::Pane(new)],
+                };
+                true
+            }
+            Self::Pane(_) => false,
+            Self::Split {
+                axis: split_axis,
+                parts,
+            } => {
+                if *split_axis == axis
+                    && let Some(i) = parts.iter().position(|part| *part == Self::Pane(pane))
+                {
+                    parts.insert(i + 1, Self::Pane(new));
+                    return true;
+                }
+                parts.iter_mut().any(|part| part.split(pane, axis, new))
+            }
+        }
+    }
+
+    /// Removes a pane, giving its space to the other panes of its split. A split left with a
+    /// single part is replaced by that part. The last pane can't be removed.
+    ///
+    /// # Returns
+    ///
+    /// Whether the pane was removed.
+    pub fn remove(&mut self, pane: T) -> bool {
+        let Self::Split { parts, .. } = self else {
+            return false;
+        };
+        let removed = match parts.iter().position(|part| *part == Self::Pane(pane)) {
+            Some(i) => {
+                parts.remove(i);
+                true
+            }
+            None => parts.iter_mut().any(|part| part.remove(pane)),
+        };
+        if parts.len() == 1 {
+            *self = parts.remove(0);
+        }
+        removed
+    }
+
+    /// Puts another pane in the place of one.
+    pub fn replace(&mut self, old: T, new: T) {
+        match self {
+            Self::Pane(id) if *id == old => *id = new,
+            Self::Pane(_) => {}
+            Self::Split { parts, .. } => {
+                for part in parts {
+                    part.replace(old, new);
+                }
+            }
+        }
+    }
+
+    /// Divides an area among the panes.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - The area of the whole layout.
+    /// * `gap` - The space left between neighboring panes.
+    ///
+    /// # Returns
+    ///
+    /// Each pane with its area, in reading order.
+    pub fn arrange(&self, rect: Rect, gap: f32) -> Vec<(T, Rect)> {
+        let Self::Split { axis, parts } = self else {
+            return self.panes().into_iter().map(|pane| (pane, rect)).collect();
+        };
+        let count = parts.len() as f32;
+        let (length, start) = match axis {
+            Axis::Row => (rect.width(), rect.min.x),
+            Axis::Column => (rect.height(), rect.min.y),
+        };
+        let size = ((length - gap * (count - 1.0)) / count).max(0.0);
+        let mut arranged = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            let from = start + i as f32 * (size + gap);
+            let area = match axis {
+                Axis::Row => {
+                    Rect::from_min_max(pos2(from, rect.min.y), pos2(from + size, rect.max.y))
+                }
+                Axis::Column => {
+                    Rect::from_min_max(pos2(rect.min.x, from), pos2(rect.max.x, from + size))
+                }
+            };
+            arranged.extend(part.arrange(area, gap));
+        }
+        arranged
+    }
+}
+
+/// Finds the pane next to another in a direction, for moving the keyboard focus.
+///
+/// Of the panes lying wholly in that direction, the nearest one wins; among equally near
+/// ones, the one most in line with the pane the focus moves from.
+///
+/// # Arguments
+///
+/// * `panes` - The panes with their areas, as returned by [`Layout::arrange`].
+/// * `from` - The pane the focus moves from.
+/// * `direction` - Where to look.
+pub fn neighbor<T: Copy + PartialEq>(
+    panes: &[(T, Rect)],
+    from: T,
+    direction: Direction,
+) -> Option<T> {
+    let (_, origin) = panes.iter().find(|(pane, _)| *pane == from)?;
+    let center = origin.center();
+    panes
+        .iter()
+        .filter(|(pane, _)| *pane != from)
+        .filter_map(|(pane, rect)| {
+            let (distance, offset) = match direction {
+                Direction::Left => (origin.min.x - rect.max.x, rect.center().y - center.y),
+                Direction::Right => (rect.min.x - origin.max.x, rect.center().y - center.y),
+                Direction::Up => (origin.min.y - rect.max.y, rect.center().x - center.x),
+                Direction::Down => (rect.min.y - origin.max.y, rect.center().x - center.x),
+            };
+            (distance >= 0.0).then_some((distance, offset.abs(), *pane))
+        })
+        .min_by(|(a, a_offset, _), (b, b_offset, _)| {
+            a.total_cmp(b).then(a_offset.total_cmp(b_offset))
+        })
+        .map(|(_, _, pane)| pane)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rect {
+        Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 60.0))
+    }
+
+    #[test]
+    fn splits_nest_only_across_axes() {
+        let mut layout = Layout::Pane(1);
+        assert!(layout.split(1, Axis::Row, 2));
+        assert!(layout.split(1, Axis::Row, 3));
+        assert!(layout.split(2, Axis::Column, 4));
+        assert!(!layout.split(9, Axis::Row, 5));
+        assert_eq!(layout.panes(), [1, 3, 2, 4]);
+        assert_eq!(
+            layout,
+            Layout::Split {
+                axis: Axis::Row,
+                parts: vec![
+                    Layout::Pane(1),
+                    Layout::Pane(3),
+                    Layout::Split {
+                        axis: Axis::Column,
+                        parts: vec![Layout::Pane(2), Layout::Pane(4)],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn removing_panes_collapses_splits() {
+        let mut layout = Layout::Pane(1);
+        layout.split(1, Axis::Row, 2);
+        layout.split(2, Axis::Column, 3);
+        assert!(layout.remove(3));
+        assert_eq!(
+            layout,
+            Layout::Split {
+                axis: Axis::Row,
+                parts: vec![Layout::Pane(1), Layout::Pane(2)],
+            }
+        );
+        assert!(layout.remove(1));
+        assert_eq!(layout, Layout::Pane(2));
+        assert!(!layout.remove(2));
+        layout.replace(2, 7);
+        assert_eq!(layout, Layout::Pane(7));
+    }
+
+    #[test]
+    fn panes_share_their_split_equally() {
+        let mut layout = Layout::Pane(1);
+        layout.split(1, Axis::Row, 2);
+        layout.split(2, Axis::Column, 3);
+        let arranged = layout.arrange(area(), 0.0);
+        assert_eq!(
+            arranged,
+            [
+                (1, Rect::from_min_max(pos2(0.0, 0.0), pos2(50.0, 60.0))),
+                (2, Rect::from_min_max(pos2(50.0, 0.0), pos2(100.0, 30.0))),
+                (3, Rect::from_min_max(pos2(50.0, 30.0), pos2(100.0, 60.0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn focus_moves_to_the_nearest_pane_in_line() {
+        let mut layout = Layout::Pane(1);
+        layout.split(1, Axis::Row, 2);
+        layout.split(2, Axis::Column, 3);
+        let arranged = layout.arrange(area(), 4.0);
+        assert_eq!(neighbor(&arranged, 1, Direction::Right), Some(2));
+        assert_eq!(neighbor(&arranged, 3, Direction::Left), Some(1));
+        assert_eq!(neighbor(&arranged, 2, Direction::Down), Some(3));
+        assert_eq!(neighbor(&arranged, 3, Direction::Up), Some(2));
+        assert_eq!(neighbor(&arranged, 1, Direction::Left), None);
+        assert_eq!(neighbor(&arranged, 1, Direction::Up), None);
+    }
+}
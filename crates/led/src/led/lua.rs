@@ -1,19 +1,175 @@
-use anyhow::Result as AnyResult;
+#[cfg(feature = "lua")]
+use anyhow::Context;
+use anyhow::{Result as AnyResult, anyhow};
+#[cfg(feature = "lua")]
+use std::cell::RefCell;
+#[cfg(feature = "lua")]
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "lua")]
+use std::rc::Rc;
 
+use super::buffer::editor::State;
 use super::commands::editor::Command;
-use mlua::Lua;
+use super::sandbox::{Limits, Permissions};
+use super::types::buffer::ID;
+#[cfg(feature = "lua")]
+use super::{batch, sandbox, shell};
+#[cfg(feature = "lua")]
+use mlua::{Function, Lua, LuaSerdeExt, Table, Variadic};
 
+/// File name of the user's Lua configuration, both in the configuration directory and inside
+/// the project's `.led` directory.
+pub const INIT_FILE: &str = "init.lua";
+
+/// How many Lua instructions an evaluated selection may run before it is stopped.
+#[cfg(feature = "lua")]
+const EVALUATION_INSTRUCTIONS: u32 = 1_000_000;
+
+/// The globals an evaluated selection can use: functions that neither reach outside the
+/// interpreter nor see the configuration. `pcall` and `error` are left out, since catching the
+/// error that stops a long evaluation would let it run forever.
+#[cfg(feature = "lua")]
+const EVALUATION_GLOBALS: &[&str] = &[
+    "math", "string", "table", "utf8", "tonumber", "tostring", "type", "pairs", "ipairs", "select",
+    "next", "assert",
+];
+
+/// A command registered from Lua with `kup.register_command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredCommand {
+    /// Unique name the command is invoked by, e.g. `git.blame`.
+    pub name: String,
+    /// Optional one-line description shown in the command palette.
+    pub description: Option<String>,
+    /// Prompts for the command's arguments, asked in order before it runs.
+    pub prompts: Vec<String>,
+}
+
+/// A snippet registered from Lua with `kup.register_snippet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredSnippet {
+    /// Unique name the snippet is listed by.
+    pub name: String,
+    /// Optional one-line description shown in the command palette.
+    pub description: Option<String>,
+    /// The text inserted, with `${name}` placeholders.
+    pub body: String,
+}
+
+/// Where the caret is when suggestion providers are asked for a suggestion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SuggestionContext {
+    /// Path of the buffer's file, if it has one.
+    pub path: Option<String>,
+    /// Line of the caret, counted from 0.
+    pub line: usize,
+    /// Byte column of the caret, counted from 0.
+    pub column: usize,
+    /// Text of the caret's line before the caret.
+    pub before: String,
+    /// Text of the caret's line after the caret.
+    pub after: String,
+}
+
+/// Where a panel registered from Lua is docked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelSide {
+    Left,
+    Right,
+    Bottom,
+}
+
+/// A widget in a panel registered from Lua.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanelWidget {
+    Label {
+        text: String,
+    },
+    Button {
+        text: String,
+    },
+    /// Items to pick one from.
+    List {
+        items: Vec<String>,
+        /// The highlighted item, counted from 0.
+        selected: Option<usize>,
+    },
+    /// A single-line text input. The editor keeps what is typed, by `id`.
+    Input {
+        id: String,
+        /// What the input holds when first shown.
+        text: String,
+        /// Shown while the input is empty.
+        hint: Option<String>,
+        /// Whether submitting empties the input.
+        clear_on_submit: bool,
+    },
+}
+
+/// A panel registered from Lua with `kup.register_panel`, as its render function last
+/// described it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panel {
+    /// Unique name the panel is registered under.
+    pub name: String,
+    pub title: String,
+    pub side: PanelSide,
+    pub visible: bool,
+    /// The widgets from top to bottom; empty while the panel is hidden.
+    pub widgets: Vec<PanelWidget>,
+    /// Why the render function failed, shown in place of the widgets.
+    pub error: Option<String>,
+}
+
+/// What the user did to a widget of a panel registered from Lua.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanelEvent {
+    /// A button was clicked.
+    Clicked,
+    /// An item of a list was clicked, counted from 0.
+    Selected(usize),
+    /// The text of an input was edited.
+    Changed(String),
+    /// Enter was pressed in an input.
+    Submitted(String),
+}
+
+/// What scripts may do when they run programs with `kup.run`.
+#[derive(Debug, Clone)]
+pub struct ProcessPolicy {
+    /// The project programs run in, which their working directory must stay inside.
+    pub project_root: PathBuf,
+    pub limits: Limits,
+    /// Which scripts the user allowed to run programs.
+    pub permissions: Permissions,
+}
+
+/// State shared between the runtime and its `kup.run` function.
+#[cfg(feature = "lua")]
+#[derive(Debug, Default)]
+struct Processes {
+    /// `None` until a project is open, so scripts can't run programs.
+    policy: Option<ProcessPolicy>,
+    /// Scripts that tried to run a program before the user decided whether they may.
+    permission_requests: Vec<String>,
+}
+
+#[cfg(feature = "lua")]
 pub struct Runtime {
     lua: Lua,
     pending_cmds: Vec<Command>,
+    processes: Rc<RefCell<Processes>>,
 }
 
+#[cfg(feature = "lua")]
 impl Runtime {
     pub fn new() -> AnyResult<Self> {
         let lua = Lua::new();
         Ok(Self {
             lua,
             pending_cmds: Vec::new(),
+            processes: Rc::default(),
         })
     }
 
@@ -29,17 +185,211 @@ function kup.bind_key(key, action)
     kup.keybindings[key] = action
 end
 
--- Example keybindings
-kup.bind_key("ctrl+s", function()
-    -- Save file
-    return { type = "SaveBuffer", buffer_id = kup.current_buffer }
+-- Example keybindings. A binding or command can return an editor command as a table whose
+-- `type` names it and whose other fields are its arguments; `buffer_id` defaults to
+-- kup.current_buffer, the buffer being edited.
+kup.bind_key("ctrl+a", function()
+    -- Select everything
+    return { type = "SelectAll" }
 end)
 
-kup.bind_key("ctrl+o", function()
-    -- Open file
-    return { type = "OpenFile" }
+kup.bind_key("ctrl+n", function()
+    -- New buffer
+    return { type = "NewBuffer", content = "" }
 end)
 
+-- Commands shown in the command palette
+kup.commands = {}
+
+-- Registers a named command, e.g.
+--   kup.register_command{
+--       name = "greet",
+--       description = "Print a greeting",
+--       args = { "Name" },
+--       run = function(name) print("Hello, " .. name) end,
+--   }
+function kup.register_command(spec)
+    assert(type(spec) == "table", "kup.register_command expects a table")
+    assert(type(spec.name) == "string" and spec.name ~= "", "command needs a name")
+    assert(type(spec.run) == "function", "command `" .. spec.name .. "` needs a run function")
+    kup.commands[spec.name] = {
+        description = spec.description,
+        args = spec.args or {},
+        run = spec.run,
+    }
+end
+
+-- Snippets listed in the command palette, e.g.
+--   kup.register_snippet{
+--       name = "header",
+--       description = "File header",
+--       body = "// ${file_name}, ${date}\n${cursor}",
+--   }
+-- `${name}` placeholders are filled in when the snippet is inserted: `date`,
+-- `time`, `file_name`, `file_path`, `selection`, `uuid` and those registered
+-- with kup.register_placeholder. `${cursor}` marks where the caret goes and
+-- `$$` is a literal `$`.
+kup.snippets = {}
+
+function kup.register_snippet(spec)
+    assert(type(spec) == "table", "kup.register_snippet expects a table")
+    assert(type(spec.name) == "string" and spec.name ~= "", "snippet needs a name")
+    assert(type(spec.body) == "string", "snippet `" .. spec.name .. "` needs a body")
+    kup.snippets[spec.name] = { description = spec.description, body = spec.body }
+end
+
+-- Snippet placeholders computed by a function when a snippet is inserted, e.g.
+--   kup.register_placeholder("branch", function(ctx)
+--       local result = kup.run{ cmd = "git", args = { "branch", "--show-current" } }
+--       return (result.stdout:gsub("%s+$", ""))
+--   end)
+-- `ctx` has the buffer's `path` (nil if unsaved) and the `selection`. A
+-- registered placeholder takes precedence over a built-in one of the same name.
+kup.placeholders = {}
+
+function kup.register_placeholder(name, compute)
+    assert(type(name) == "string" and name ~= "", "placeholders need a name")
+    assert(type(compute) == "function", "kup.register_placeholder expects a function")
+    kup.placeholders[name] = compute
+end
+
+-- Suggestion providers, asked for ghost text at the caret after each edit
+kup.suggestion_providers = {}
+
+-- Registers a function suggesting text to insert at the caret, e.g.
+--   kup.register_suggestion_provider(function(ctx)
+--       if ctx.before:match("fn main$") then return "() {\n}" end
+--   end)
+-- `ctx` has the buffer's `path` (nil if unsaved), the caret's 1-based
+-- `line` and `column`, and the text `before` and `after` it on its line.
+-- Providers are asked in order; the first one returning text wins.
+function kup.register_suggestion_provider(provider)
+    assert(type(provider) == "function", "kup.register_suggestion_provider expects a function")
+    table.insert(kup.suggestion_providers, provider)
+end
+
+-- Suggestions for the active buffer's caret made with kup.suggest, shown on the next frame
+kup.pending_suggestions = {}
+
+-- Shows ghost text at the caret, accepted with Tab and dismissed by typing anything else
+function kup.suggest(text)
+    assert(type(text) == "string", "kup.suggest expects a string")
+    table.insert(kup.pending_suggestions, text)
+end
+
+-- Dismisses the ghost text shown at the caret
+function kup.dismiss_suggestion()
+    table.insert(kup.pending_suggestions, "")
+end
+
+-- Editor actions run with kup.run_action, on the next frame in order, e.g.
+--   kup.run_action("edit.upper_case")
+-- The names are those of the keymap, such as `edit.sort_lines` or
+-- `edit.title_case`.
+kup.pending_actions = {}
+
+function kup.run_action(name)
+    assert(type(name) == "string" and name ~= "", "kup.run_action expects an action name")
+    table.insert(kup.pending_actions, name)
+end
+
+-- Idle handlers, run once the user has stopped typing for a while, e.g.
+--   kup.on_idle("count-todos", 1000, function() print("idle") end)
+-- Each handler runs once per pause in input, after `delay_ms` milliseconds
+-- without any. Registering a name again replaces its handler.
+kup.idle_handlers = {}
+
+function kup.on_idle(name, delay_ms, handler)
+    assert(type(name) == "string" and name ~= "", "idle handlers need a name")
+    assert(type(delay_ms) == "number" and delay_ms >= 0, "kup.on_idle expects a delay in milliseconds")
+    assert(type(handler) == "function", "kup.on_idle expects a function")
+    kup.idle_handlers[name] = { delay_ms = delay_ms, run = handler }
+end
+
+-- Translations of the interface and of command titles, in Fluent syntax, e.g.
+--   kup.register_translations("de", [[
+--   greet-title = Begrüßen
+--   action-greet = Begrüßen
+--   ]])
+-- A command's palette title is the message `action-<name>`, with dots and
+-- underscores in the name written as dashes.
+kup.pending_translations = {}
+
+function kup.register_translations(locale, source)
+    assert(type(locale) == "string" and locale ~= "", "translations need a locale")
+    assert(type(source) == "string", "kup.register_translations expects a string")
+    table.insert(kup.pending_translations, { locale = locale, source = source })
+end
+
+-- Panels docked beside or below the editor, e.g.
+--   local todos = {}
+--   kup.register_panel{
+--       name = "todos",
+--       title = "TODOs",
+--       side = "right", -- or "left" or "bottom"
+--       render = function()
+--           return {
+--               { type = "input", id = "new", hint = "New TODO", clear_on_submit = true,
+--                 on_submit = function(text) table.insert(todos, text) end },
+--               { type = "list", items = todos,
+--                 on_select = function(index, item) table.remove(todos, index) end },
+--               { type = "button", text = "Clear", on_click = function() todos = {} end },
+--               { type = "label", text = #todos .. " open" },
+--           }
+--       end,
+--   }
+-- `render` is called whenever the panel is drawn and returns its widgets
+-- from top to bottom. A list can mark its `selected` item (1-based). Inputs
+-- keep what is typed under their `id`, starting with their `text`, and call
+-- `on_change(text)` on each edit and `on_submit(text)` on Enter.
+-- kup.show_panel(name, false) hides a panel and kup.show_panel(name) shows
+-- it again; the View menu does the same.
+kup.panels = {}
+
+function kup.register_panel(spec)
+    assert(type(spec) == "table", "kup.register_panel expects a table")
+    assert(type(spec.name) == "string" and spec.name ~= "", "panel needs a name")
+    assert(type(spec.render) == "function", "panel `" .. spec.name .. "` needs a render function")
+    local side = spec.side or "right"
+    assert(side == "left" or side == "right" or side == "bottom",
+        "panel `" .. spec.name .. "` must be on the left, right or bottom")
+    kup.panels[spec.name] = {
+        title = spec.title or spec.name,
+        side = side,
+        render = spec.render,
+        visible = true,
+        rendered = {},
+    }
+end
+
+function kup.show_panel(name, shown)
+    local panel = kup.panels[name]
+    assert(panel, "no panel named `" .. tostring(name) .. "`")
+    panel.visible = shown ~= false
+end
+
+-- Runs a program in the project and returns its result, e.g.
+--   local result = kup.run{ cmd = "git", args = { "status", "--short" }, cwd = "src" }
+--   print(result.status, result.stdout, result.stderr)
+-- `cwd` is relative to the project root and may not leave it. Programs are
+-- killed after a time limit (`result.timed_out`) and only part of a large
+-- output is kept (`result.truncated`). The first time a script runs a
+-- program, the user is asked whether to allow it; until then kup.run fails.
+-- kup.run is provided by the editor.
+
+-- Scripts run with `led --batch script.lua file…` run once for each file and
+-- read and edit it through kup.buffer, e.g.
+--   local buffer = kup.buffer
+--   for line = 1, buffer.line_count() do
+--       buffer.set_line(line, (buffer.line(line):gsub("%s+$", "")))
+--   end
+-- `buffer.path` is the file's path. `buffer.text()` and `buffer.set_text(text)`
+-- read and replace the whole text, `buffer.line(n)` and `buffer.set_line(n, text)`
+-- a line without its line break, and `buffer.insert(line, column, text)`
+-- inserts before a character. Lines and columns count from 1, columns in
+-- characters. Files the script changed are saved; a script that fails leaves
+-- its file untouched. kup.buffer is provided by the editor in batch runs.
+
 -- Theme configuration
 kup.theme = {
     background = "#282c34",
@@ -61,9 +411,656 @@ print("KUP Editor configuration loaded")
 "##;
 
         self.lua.load(config_script).exec()?;
+        self.register_run()?;
+        Ok(())
+    }
+
+    /// Defines `kup.run`, which runs a program within the process policy.
+    fn register_run(&self) -> AnyResult<()> {
+        let processes = Rc::clone(&self.processes);
+        let run = self.lua.create_function(move |lua, spec: Table| {
+            // The script defining the calling function, as named by `load_file`
+            let script = lua
+                .inspect_stack(1)
+                .and_then(|frame| {
+                    frame
+                        .source()
+                        .source
+                        .map(|s| s.trim_start_matches(['@', '=']).to_string())
+                })
+                .unwrap_or_else(|| "an unknown script".to_string());
+            let policy = {
+                let mut processes = processes.borrow_mut();
+                let Some(policy) = processes.policy.clone() else {
+                    return Err(mlua::Error::runtime(
+                        "programs can only be run in a project",
+                    ));
+                };
+                match policy.permissions.decision(&script) {
+                    Some(true) => policy,
+                    Some(false) => {
+                        return Err(mlua::Error::runtime(format!(
+                            "{} is not allowed to run programs",
+                            script
+                        )));
+                    }
+                    None => {
+                        if !processes.permission_requests.contains(&script) {
+                            processes.permission_requests.push(script.clone());
+                        }
+                        return Err(mlua::Error::runtime(format!(
+                            "{} needs permission to run programs; try again once it is granted",
+                            script
+                        )));
+                    }
+                }
+            };
+
+            let program: String = spec.get("cmd")?;
+            let args: Option<Vec<String>> = spec.get("args")?;
+            let cwd: Option<String> = spec.get("cwd")?;
+            let output = sandbox::confine(&policy.project_root, cwd.as_deref())
+                .and_then(|dir| {
+                    let env = shell::login_environment().for_project(&policy.project_root)?;
+                    let mut command = env.command(&program);
+                    command.args(args.unwrap_or_default()).current_dir(dir);
+                    sandbox::run(command, policy.limits)
+                })
+                .map_err(|e| mlua::Error::runtime(format!("{:#}", e)))?;
+
+            let result = lua.create_table()?;
+            result.set("status", output.status)?;
+            result.set("stdout", output.stdout)?;
+            result.set("stderr", output.stderr)?;
+            result.set("truncated", output.truncated)?;
+            result.set("timed_out", output.timed_out)?;
+            Ok(result)
+        })?;
+        let kup: Table = self.lua.globals().get("kup")?;
+        kup.set("run", run)?;
         Ok(())
     }
 
+    /// Lets scripts run programs in a project, within limits.
+    pub fn set_process_policy(&mut self, policy: ProcessPolicy) {
+        self.processes.borrow_mut().policy = Some(policy);
+    }
+
+    /// Changes the limits on programs run by scripts, e.g. after the user changed a setting.
+    pub fn set_process_limits(&mut self, limits: Limits) {
+        if let Some(policy) = &mut self.processes.borrow_mut().policy {
+            policy.limits = limits;
+        }
+    }
+
+    /// Returns and forgets the scripts that asked to run programs since the last call, so the
+    /// user can be asked whether to allow them.
+    pub fn take_permission_requests(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.processes.borrow_mut().permission_requests)
+    }
+
+    /// Records whether a script may run programs and saves the decision in the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no project is open or the permissions cannot be saved.
+    pub fn decide_permission(&mut self, script: &str, allow: bool) -> AnyResult<()> {
+        let mut processes = self.processes.borrow_mut();
+        let policy = processes
+            .policy
+            .as_mut()
+            .ok_or_else(|| anyhow!("no project is open"))?;
+        policy.permissions.decide(script, allow);
+        policy
+            .permissions
+            .save(&sandbox::permissions_path(&policy.project_root))
+    }
+
+    /// Runs a Lua script from a file, such as a user's `init.lua`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the script fails.
+    pub fn load_file(&mut self, path: &Path) -> AnyResult<()> {
+        let script = fs::read_to_string(path)?;
+        self.lua
+            .load(&script)
+            .set_name(path.to_string_lossy())
+            .exec()?;
+        Ok(())
+    }
+
+    /// Returns the commands registered with `kup.register_command`, sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a registration does not have the expected shape.
+    pub fn registered_commands(&self) -> AnyResult<Vec<RegisteredCommand>> {
+        let Some(commands) = self.commands_table()? else {
+            return Ok(Vec::new());
+        };
+        let mut registered = Vec::new();
+        for pair in commands.pairs::<String, Table>() {
+            let (name, spec) = pair?;
+            registered.push(RegisteredCommand {
+                name,
+                description: spec.get("description")?,
+                prompts: spec
+                    .get::<_, Option<Vec<String>>>("args")?
+                    .unwrap_or_default(),
+            });
+        }
+        registered.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(registered)
+    }
+
+    /// Runs a registered command with the answers to its argument prompts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no such command is registered, the command fails or it returns
+    /// something other than an editor command.
+    pub fn run_command(&mut self, name: &str, args: &[String]) -> AnyResult<()> {
+        let command = {
+            let spec: Option<Table> = match self.commands_table()? {
+                Some(commands) => commands.get(name)?,
+                None => None,
+            };
+            let spec = spec.ok_or_else(|| anyhow!("unknown command `{}`", name))?;
+            let run: Function = spec.get("run")?;
+            let result: mlua::Value =
+                run.call(args.iter().cloned().collect::<Variadic<String>>())?;
+            editor_command(&self.lua, result)
+                .with_context(|| format!("command `{}` returned an invalid editor command", name))?
+        };
+        self.pending_cmds.extend(command);
+        Ok(())
+    }
+
+    /// Returns the snippets registered with `kup.register_snippet`, sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a registration does not have the expected shape.
+    pub fn registered_snippets(&self) -> AnyResult<Vec<RegisteredSnippet>> {
+        let Some(snippets) = self.kup_table("snippets")? else {
+            return Ok(Vec::new());
+        };
+        let mut registered = Vec::new();
+        for pair in snippets.pairs::<String, Table>() {
+            let (name, spec) = pair?;
+            registered.push(RegisteredSnippet {
+                name,
+                description: spec.get("description")?,
+                body: spec.get("body")?,
+            });
+        }
+        registered.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(registered)
+    }
+
+    /// Computes a snippet placeholder registered with `kup.register_placeholder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The placeholder's name.
+    /// * `path` - Path of the buffer's file, if it has one.
+    /// * `selection` - The selected text, empty if nothing is selected.
+    ///
+    /// # Returns
+    ///
+    /// The placeholder's value, or `None` if no placeholder of that name is registered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function fails or returns something other than a string or
+    /// number.
+    pub fn placeholder(
+        &self,
+        name: &str,
+        path: Option<&str>,
+        selection: &str,
+    ) -> AnyResult<Option<String>> {
+        let compute: Option<Function> = match self.kup_table("placeholders")? {
+            Some(placeholders) => placeholders.get(name)?,
+            None => None,
+        };
+        let Some(compute) = compute else {
+            return Ok(None);
+        };
+        let ctx = self.lua.create_table()?;
+        ctx.set("path", path)?;
+        ctx.set("selection", selection)?;
+        Ok(Some(compute.call(ctx)?))
+    }
+
+    /// Returns whether any suggestion providers are registered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kup.suggestion_providers` is not a table.
+    pub fn has_suggestion_providers(&self) -> AnyResult<bool> {
+        Ok(self
+            .kup_table("suggestion_providers")?
+            .is_some_and(|providers| providers.raw_len() > 0))
+    }
+
+    /// Asks the registered suggestion providers for text to insert at the caret.
+    ///
+    /// # Returns
+    ///
+    /// The first non-empty suggestion, or `None` if no provider had one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a provider fails or returns something other than a string or `nil`.
+    pub fn request_suggestion(&self, context: &SuggestionContext) -> AnyResult<Option<String>> {
+        let Some(providers) = self.kup_table("suggestion_providers")? else {
+            return Ok(None);
+        };
+        let ctx = self.lua.create_table()?;
+        ctx.set("path", context.path.clone())?;
+        ctx.set("line", context.line + 1)?;
+        ctx.set("column", context.column + 1)?;
+        ctx.set("before", context.before.clone())?;
+        ctx.set("after", context.after.clone())?;
+        for provider in providers.sequence_values::<Function>() {
+            let suggestion: Option<String> = provider?.call(ctx.clone())?;
+            if let Some(text) = suggestion.filter(|text| !text.is_empty()) {
+                return Ok(Some(text));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the handlers registered with `kup.on_idle`, by name, with how long the user must
+    /// be idle before each runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kup.idle_handlers` does not have the expected shape.
+    pub fn idle_handlers(&self) -> AnyResult<Vec<(String, std::time::Duration)>> {
+        let Some(handlers) = self.kup_table("idle_handlers")? else {
+            return Ok(Vec::new());
+        };
+        let mut found = Vec::new();
+        for pair in handlers.pairs::<String, Table>() {
+            let (name, handler) = pair?;
+            let delay_ms: f64 = handler.get("delay_ms")?;
+            found.push((
+                name,
+                std::time::Duration::from_secs_f64(delay_ms.max(0.0) / 1000.0),
+            ));
+        }
+        Ok(found)
+    }
+
+    /// Runs the idle handler registered under a name.
+    ///
+    /// # Returns
+    ///
+    /// `false` if no handler is registered under the name any more.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handler fails.
+    pub fn run_idle_handler(&mut self, name: &str) -> AnyResult<bool> {
+        let handler: Option<Table> = match self.kup_table("idle_handlers")? {
+            Some(handlers) => handlers.get(name)?,
+            None => None,
+        };
+        let Some(handler) = handler else {
+            return Ok(false);
+        };
+        let run: Function = handler.get("run")?;
+        run.call::<_, ()>(())?;
+        Ok(true)
+    }
+
+    /// Evaluates text, such as a selection, as Lua: as an expression if it is one, otherwise as
+    /// a block of statements whose `return` values are the result.
+    ///
+    /// The text runs in an environment of its own that holds only the math, string, table and
+    /// utf8 libraries and a few basic functions, so it can neither see the configuration nor
+    /// reach files or programs, and it is stopped after [`EVALUATION_INSTRUCTIONS`]
+    /// instructions.
+    ///
+    /// # Returns
+    ///
+    /// The values it produced, converted with `tostring` and separated by commas, or `None`
+    /// if it produced none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not valid Lua, fails, or runs too long.
+    pub fn evaluate(&self, source: &str) -> AnyResult<Option<String>> {
+        let globals = self.lua.globals();
+        let env = self.lua.create_table()?;
+        for name in EVALUATION_GLOBALS {
+            let value: mlua::Value = globals.get(*name)?;
+            // Libraries are copied, so the evaluated text can't change the configuration's
+            let value = match value {
+                mlua::Value::Table(library) => {
+                    let copy = self.lua.create_table()?;
+                    for pair in library.pairs::<mlua::Value, mlua::Value>() {
+                        let (key, value) = pair?;
+                        copy.set(key, value)?;
+                    }
+                    mlua::Value::Table(copy)
+                }
+                other => other,
+            };
+            env.set(*name, value)?;
+        }
+        let chunk = |code: &str| {
+            self.lua
+                .load(code.to_string())
+                .set_name("selection")
+                .set_environment(env.clone())
+                .into_function()
+        };
+        let function = chunk(&format!("return {}", source)).or_else(|_| chunk(source))?;
+
+        self.lua.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(EVALUATION_INSTRUCTIONS),
+            |_, _| Err(mlua::Error::runtime("evaluation took too long")),
+        );
+        let values = function.call::<_, mlua::MultiValue>(());
+        self.lua.remove_hook();
+
+        let tostring: Function = globals.get("tostring")?;
+        let shown = values?
+            .into_iter()
+            .map(|value| tostring.call::<_, String>(value))
+            .collect::<mlua::Result<Vec<_>>>()?;
+        Ok((!shown.is_empty()).then(|| shown.join(", ")))
+    }
+
+    /// Runs a script of a batch run on one buffer, which the script reads and edits through
+    /// `kup.buffer` while it runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The script's name in error messages, such as its path.
+    /// * `source` - The script.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the script is not valid Lua or fails, or one of its edits fails.
+    pub fn run_batch_script(
+        &mut self,
+        name: &str,
+        source: &str,
+        state: &mut State,
+        buffer_id: ID,
+    ) -> AnyResult<()> {
+        let path = state
+            .metadata(buffer_id)
+            .and_then(|meta| meta.file_path.clone());
+        let state = RefCell::new(state);
+        let text = || {
+            state
+                .borrow()
+                .get_buffer_text(buffer_id)
+                .ok_or_else(|| mlua::Error::runtime("the buffer is gone"))
+        };
+        // Replaces a byte range of the text, as one edit
+        let replace = |start: usize, end: usize, text: String| {
+            state
+                .borrow_mut()
+                .execute_command(Command::Batch(vec![
+                    Command::DeleteText {
+                        buffer_id,
+                        start,
+                        length: end - start,
+                    },
+                    Command::InsertText {
+                        buffer_id,
+                        offset: start,
+                        text,
+                    },
+                ]))
+                .map_err(|e| mlua::Error::runtime(format!("{:#}", e)))
+        };
+        let no_line =
+            |line: usize| mlua::Error::runtime(format!("the buffer has no line {}", line));
+
+        let kup: Table = self.lua.globals().get("kup")?;
+        self.lua.scope(|scope| {
+            let buffer = self.lua.create_table()?;
+            buffer.set("path", path)?;
+            buffer.set("text", scope.create_function(|_, ()| text())?)?;
+            buffer.set(
+                "set_text",
+                scope.create_function(|_, new: String| replace(0, text()?.len(), new))?,
+            )?;
+            buffer.set(
+                "line_count",
+                scope.create_function(|_, ()| {
+                    Ok(state
+                        .borrow()
+                        .buffer(buffer_id)
+                        .map_or(0, |table| table.lines()))
+                })?,
+            )?;
+            buffer.set(
+                "line",
+                scope.create_function(|_, line: usize| {
+                    let text = text()?;
+                    let (start, end) =
+                        batch::line_range(&text, line).ok_or_else(|| no_line(line))?;
+                    Ok(text[start..end].to_string())
+                })?,
+            )?;
+            buffer.set(
+                "set_line",
+                scope.create_function(|_, (line, new): (usize, String)| {
+                    let (start, end) =
+                        batch::line_range(&text()?, line).ok_or_else(|| no_line(line))?;
+                    replace(start, end, new)
+                })?,
+            )?;
+            buffer.set(
+                "insert",
+                scope.create_function(|_, (line, column, new): (usize, usize, String)| {
+                    let offset = batch::column_offset(&text()?, line, column).ok_or_else(|| {
+                        mlua::Error::runtime(format!(
+                            "the buffer has no column {} in line {}",
+                            column, line
+                        ))
+                    })?;
+                    replace(offset, offset, new)
+                })?,
+            )?;
+            kup.set("buffer", buffer)?;
+            let result = self.lua.load(source).set_name(name).exec();
+            kup.set("buffer", mlua::Value::Nil)?;
+            result
+        })?;
+        Ok(())
+    }
+
+    /// Returns and forgets the suggestions made with `kup.suggest` since the last call, in
+    /// order. An empty suggestion stands for `kup.dismiss_suggestion`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kup.pending_suggestions` does not have the expected shape.
+    pub fn take_suggestions(&mut self) -> AnyResult<Vec<String>> {
+        let Some(pending) = self.kup_table("pending_suggestions")? else {
+            return Ok(Vec::new());
+        };
+        let suggestions = pending
+            .clone()
+            .sequence_values::<String>()
+            .collect::<mlua::Result<Vec<_>>>()?;
+        pending.clear()?;
+        Ok(suggestions)
+    }
+
+    /// Returns and forgets the editor actions requested with `kup.run_action` since the last
+    /// call, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kup.pending_actions` does not have the expected shape.
+    pub fn take_actions(&mut self) -> AnyResult<Vec<String>> {
+        let Some(pending) = self.kup_table("pending_actions")? else {
+            return Ok(Vec::new());
+        };
+        let actions = pending
+            .clone()
+            .sequence_values::<String>()
+            .collect::<mlua::Result<Vec<_>>>()?;
+        pending.clear()?;
+        Ok(actions)
+    }
+
+    /// Returns and forgets the translations registered with `kup.register_translations` since
+    /// the last call, as locale and Fluent source, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kup.pending_translations` does not have the expected shape.
+    pub fn take_translations(&mut self) -> AnyResult<Vec<(String, String)>> {
+        let Some(pending) = self.kup_table("pending_translations")? else {
+            return Ok(Vec::new());
+        };
+        let translations = pending
+            .clone()
+            .sequence_values::<Table>()
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.get("locale")?, entry.get("source")?))
+            })
+            .collect::<mlua::Result<Vec<_>>>()?;
+        pending.clear()?;
+        Ok(translations)
+    }
+
+    /// Returns the panels registered with `kup.register_panel`, sorted by name, rendering the
+    /// visible ones.
+    ///
+    /// A failing render function doesn't fail the others; its panel carries the error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a registration does not have the expected shape.
+    pub fn panels(&self) -> AnyResult<Vec<Panel>> {
+        let Some(panels) = self.kup_table("panels")? else {
+            return Ok(Vec::new());
+        };
+        let mut registered = Vec::new();
+        for pair in panels.pairs::<String, Table>() {
+            let (name, spec) = pair?;
+            let side = match spec.get::<_, String>("side")?.as_str() {
+                "left" => PanelSide::Left,
+                "bottom" => PanelSide::Bottom,
+                _ => PanelSide::Right,
+            };
+            let visible: bool = spec.get("visible")?;
+            let (widgets, error) = if visible {
+                match render_panel(&spec) {
+                    Ok(widgets) => (widgets, None),
+                    Err(e) => (Vec::new(), Some(e.to_string())),
+                }
+            } else {
+                (Vec::new(), None)
+            };
+            registered.push(Panel {
+                name,
+                title: spec.get("title")?,
+                side,
+                visible,
+                widgets,
+                error,
+            });
+        }
+        registered.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(registered)
+    }
+
+    /// Shows or hides a panel registered with `kup.register_panel`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no such panel is registered.
+    pub fn set_panel_visible(&mut self, name: &str, visible: bool) -> AnyResult<()> {
+        self.panel(name)?.set("visible", visible)?;
+        Ok(())
+    }
+
+    /// Passes what the user did to a widget of a panel on to the widget's callback, if it has
+    /// one: `on_click()`, `on_select(index, item)`, `on_change(text)` or `on_submit(text)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The panel's name.
+    /// * `widget` - The widget's position in what the panel last rendered, counted from 0.
+    /// * `event` - What the user did.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no such panel is registered or the callback fails.
+    pub fn panel_event(&mut self, name: &str, widget: usize, event: PanelEvent) -> AnyResult<()> {
+        let rendered: Table = self.panel(name)?.get("rendered")?;
+        let Some(widget) = rendered.get::<_, Option<Table>>(widget + 1)? else {
+            return Ok(());
+        };
+        let callback = |name: &str| widget.get::<_, Option<Function>>(name);
+        match event {
+            PanelEvent::Clicked => {
+                if let Some(on_click) = callback("on_click")? {
+                    on_click.call::<_, ()>(())?;
+                }
+            }
+            PanelEvent::Selected(index) => {
+                if let Some(on_select) = callback("on_select")? {
+                    let item: Option<String> = widget
+                        .get::<_, Option<Table>>("items")?
+                        .map(|items| items.get(index + 1))
+                        .transpose()?;
+                    on_select.call::<_, ()>((index + 1, item))?;
+                }
+            }
+            PanelEvent::Changed(text) => {
+                if let Some(on_change) = callback("on_change")? {
+                    on_change.call::<_, ()>(text)?;
+                }
+            }
+            PanelEvent::Submitted(text) => {
+                if let Some(on_submit) = callback("on_submit")? {
+                    on_submit.call::<_, ()>(text)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the registration of a panel.
+    fn panel(&self, name: &str) -> AnyResult<Table<'_>> {
+        let panel: Option<Table> = match self.kup_table("panels")? {
+            Some(panels) => panels.get(name)?,
+            None => None,
+        };
+        panel.ok_or_else(|| anyhow!("no panel named `{}`", name))
+    }
+
+    /// Returns a field of the `kup` table that should hold a table, if the configuration
+    /// defined it.
+    fn kup_table(&self, name: &str) -> AnyResult<Option<Table<'_>>> {
+        let kup: Option<Table> = self.lua.globals().get("kup")?;
+        match kup {
+            Some(kup) => Ok(kup.get(name)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the `kup.commands` table, if the configuration defined it.
+    fn commands_table(&self) -> AnyResult<Option<Table<'_>>> {
+        self.kup_table("commands")
+    }
+
     pub fn proccess_frame_commands(&mut self) -> AnyResult<Vec<super::commands::editor::Command>> {
         let cmds = self.pending_cmds.clone();
         self.pending_cmds.clear();
@@ -84,12 +1081,525 @@ return nil
             key, key
         );
 
-        let result: Option<mlua::Value> = self.lua.load(&script).eval()?;
+        let result: mlua::Value = self.lua.load(&script).eval()?;
+        let command = editor_command(&self.lua, result)
+            .with_context(|| format!("`{}` returned an invalid editor command", key))?;
+        self.pending_cmds.extend(command);
+        Ok(())
+    }
 
-        if let Some(value) = result {
-            // TODO: Convert Lua result to editor::Command
-            println!("Lua keybinding result: {:?}", value);
+    /// Sets `kup.current_buffer`, the buffer commands returned from Lua edit by default.
+    pub fn set_current_buffer(&mut self, buffer_id: Option<ID>) -> AnyResult<()> {
+        if let Some(kup) = self.lua.globals().get::<_, Option<Table>>("kup")? {
+            let id = buffer_id.map(|id| id.0.to_string());
+            kup.set("current_buffer", id)?;
         }
         Ok(())
     }
 }
+
+/// Reads the editor command a command or keybinding returned, such as
+/// `{ type = "SelectAll" }`, so it can be applied with the frame's other edits. `nil` is no
+/// command.
+#[cfg(feature = "lua")]
+fn editor_command(lua: &Lua, result: mlua::Value) -> AnyResult<Option<Command>> {
+    let fields = match result {
+        mlua::Value::Nil => return Ok(None),
+        mlua::Value::Table(fields) => fields,
+        other => return Err(anyhow!("expected a table, got {}", other.type_name())),
+    };
+    let kind: String = fields
+        .get::<_, Option<String>>("type")?
+        .ok_or_else(|| anyhow!("the table has no `type`"))?;
+    let arguments = lua.create_table()?;
+    for pair in fields.pairs::<mlua::Value, mlua::Value>() {
+        let (key, value) = pair?;
+        if key.as_str() != Some("type") {
+            arguments.set(key, value)?;
+        }
+    }
+    if !arguments.contains_key("buffer_id")? {
+        let kup: Table = lua.globals().get("kup")?;
+        arguments.set("buffer_id", kup.get::<_, mlua::Value>("current_buffer")?)?;
+    }
+    let command = lua.create_table()?;
+    command.set(kind, arguments)?;
+    Ok(Some(lua.from_value(mlua::Value::Table(command))?))
+}
+
+/// Calls a panel's render function and reads the widgets it returns, keeping them in the
+/// registration so that events reach the callbacks of the widgets the user saw.
+#[cfg(feature = "lua")]
+fn render_panel(spec: &Table) -> mlua::Result<Vec<PanelWidget>> {
+    let render: Function = spec.get("render")?;
+    let rendered: Table = render.call(())?;
+    spec.set("rendered", rendered.clone())?;
+    rendered
+        .sequence_values::<Table>()
+        .map(|widget| read_panel_widget(&widget?))
+        .collect()
+}
+
+/// Reads one widget of what a panel's render function returned.
+#[cfg(feature = "lua")]
+fn read_panel_widget(widget: &Table) -> mlua::Result<PanelWidget> {
+    let kind: String = widget.get("type")?;
+    Ok(match kind.as_str() {
+        "label" => PanelWidget::Label {
+            text: widget.get("text")?,
+        },
+        "button" => PanelWidget::Button {
+            text: widget.get("text")?,
+        },
+        "list" => PanelWidget::List {
+            items: widget
+                .get::<_, Option<Vec<String>>>("items")?
+                .unwrap_or_default(),
+            selected: widget
+                .get::<_, Option<usize>>("selected")?
+                .and_then(|selected| selected.checked_sub(1)),
+        },
+        "input" => PanelWidget::Input {
+            id: widget.get("id")?,
+            text: widget.get::<_, Option<String>>("text")?.unwrap_or_default(),
+            hint: widget.get("hint")?,
+            clear_on_submit: widget
+                .get::<_, Option<bool>>("clear_on_submit")?
+                .unwrap_or(false),
+        },
+        other => {
+            return Err(mlua::Error::runtime(format!(
+                "unknown widget type `{}`",
+                other
+            )));
+        }
+    })
+}
+
+/// Why scripts can't run in a build without the `lua` feature.
+#[cfg(not(feature = "lua"))]
+const DISABLED: &str = "LED was built without Lua support";
+
+/// Stands in for the Lua runtime in builds without the `lua` feature: it keeps the runtime's
+/// interface but runs nothing, so no scripts are loaded and nothing is ever registered.
+#[cfg(not(feature = "lua"))]
+#[derive(Debug, Default)]
+pub struct Runtime;
+
+#[cfg(not(feature = "lua"))]
+impl Runtime {
+    pub fn new() -> AnyResult<Self> {
+        Ok(Self)
+    }
+
+    pub fn load_default_config(&mut self) -> AnyResult<()> {
+        Ok(())
+    }
+
+    pub fn set_process_policy(&mut self, _policy: ProcessPolicy) {}
+
+    pub fn set_process_limits(&mut self, _limits: Limits) {}
+
+    pub fn take_permission_requests(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn decide_permission(&mut self, _script: &str, _allow: bool) -> AnyResult<()> {
+        Err(anyhow!(DISABLED))
+    }
+
+    /// Fails, so a user's `init.lua` isn't silently ignored.
+    pub fn load_file(&mut self, path: &Path) -> AnyResult<()> {
+        Err(anyhow!("{}, so {} was not run", DISABLED, path.display()))
+    }
+
+    pub fn registered_commands(&self) -> AnyResult<Vec<RegisteredCommand>> {
+        Ok(Vec::new())
+    }
+
+    pub fn run_command(&mut self, name: &str, _args: &[String]) -> AnyResult<()> {
+        Err(anyhow!("no command named `{}` is registered", name))
+    }
+
+    pub fn registered_snippets(&self) -> AnyResult<Vec<RegisteredSnippet>> {
+        Ok(Vec::new())
+    }
+
+    pub fn placeholder(
+        &self,
+        _name: &str,
+        _path: Option<&str>,
+        _selection: &str,
+    ) -> AnyResult<Option<String>> {
+        Ok(None)
+    }
+
+    pub fn has_suggestion_providers(&self) -> AnyResult<bool> {
+        Ok(false)
+    }
+
+    pub fn request_suggestion(&self, _context: &SuggestionContext) -> AnyResult<Option<String>> {
+        Ok(None)
+    }
+
+    pub fn idle_handlers(&self) -> AnyResult<Vec<(String, std::time::Duration)>> {
+        Ok(Vec::new())
+    }
+
+    pub fn run_idle_handler(&mut self, _name: &str) -> AnyResult<bool> {
+        Ok(false)
+    }
+
+    pub fn evaluate(&self, _source: &str) -> AnyResult<Option<String>> {
+        Err(anyhow!(DISABLED))
+    }
+
+    /// Fails, so a batch run doesn't report its files as processed.
+    pub fn run_batch_script(
+        &mut self,
+        name: &str,
+        _source: &str,
+        _state: &mut State,
+        _buffer_id: ID,
+    ) -> AnyResult<()> {
+        Err(anyhow!("{}, so {} was not run", DISABLED, name))
+    }
+
+    pub fn take_suggestions(&mut self) -> AnyResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    pub fn take_actions(&mut self) -> AnyResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    pub fn take_translations(&mut self) -> AnyResult<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    pub fn panels(&self) -> AnyResult<Vec<Panel>> {
+        Ok(Vec::new())
+    }
+
+    pub fn set_panel_visible(&mut self, name: &str, _visible: bool) -> AnyResult<()> {
+        Err(anyhow!("no panel named `{}`", name))
+    }
+
+    pub fn panel_event(&mut self, name: &str, _widget: usize, _event: PanelEvent) -> AnyResult<()> {
+        Err(anyhow!("no panel named `{}`", name))
+    }
+
+    pub fn proccess_frame_commands(&mut self) -> AnyResult<Vec<Command>> {
+        Ok(Vec::new())
+    }
+
+    pub fn execute_keybinding(&mut self, _key: &str) -> AnyResult<()> {
+        Ok(())
+    }
+
+    pub fn set_current_buffer(&mut self, _buffer_id: Option<ID>) -> AnyResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+    use super::*;
+
+    /// Returns a runtime with the default configuration and a script run as `plugin.lua`.
+    fn runtime(script: &str) -> Runtime {
+        let mut runtime = Runtime::new().unwrap();
+        runtime.load_default_config().unwrap();
+        runtime
+            .lua
+            .load(script)
+            .set_name("plugin.lua")
+            .exec()
+            .unwrap();
+        runtime
+    }
+
+    #[test]
+    fn commands_queue_the_edits_they_return() {
+        let mut runtime = runtime(
+            r#"
+kup.register_command{
+    name = "greet",
+    description = "Greet someone",
+    args = { "Name" },
+    run = function(name) return { type = "InsertText", offset = 0, text = "Hi " .. name } end,
+}
+kup.register_command{ name = "noop", run = function() end }
+kup.register_command{ name = "bad", run = function() return { type = "Launch" } end }
+"#,
+        );
+        assert_eq!(
+            runtime.registered_commands().unwrap(),
+            [
+                RegisteredCommand {
+                    name: "bad".to_string(),
+                    description: None,
+                    prompts: Vec::new(),
+                },
+                RegisteredCommand {
+                    name: "greet".to_string(),
+                    description: Some("Greet someone".to_string()),
+                    prompts: vec!["Name".to_string()],
+                },
+                RegisteredCommand {
+                    name: "noop".to_string(),
+                    description: None,
+                    prompts: Vec::new(),
+                },
+            ]
+        );
+
+        let buffer_id = ID::new();
+        runtime.set_current_buffer(Some(buffer_id)).unwrap();
+        runtime.run_command("greet", &["Ada".to_string()]).unwrap();
+        runtime.run_command("noop", &[]).unwrap();
+        assert!(runtime.run_command("bad", &[]).is_err());
+        assert!(runtime.run_command("missing", &[]).is_err());
+        runtime.execute_keybinding("ctrl+a").unwrap();
+        assert_eq!(
+            runtime.proccess_frame_commands().unwrap(),
+            [
+                Command::InsertText {
+                    buffer_id,
+                    offset: 0,
+                    text: "Hi Ada".to_string(),
+                },
+                Command::SelectAll { buffer_id },
+            ]
+        );
+        assert!(runtime.proccess_frame_commands().unwrap().is_empty());
+    }
+
+    #[test]
+    fn suggestion_providers_see_the_caret() {
+        let mut runtime = runtime(
+            r#"
+kup.register_suggestion_provider(function(ctx)
+    if ctx.before:match("fn main$") then return "() {}" end
+end)
+kup.register_suggestion_provider(function(ctx) return ctx.line .. ":" .. ctx.column end)
+kup.suggest("later")
+"#,
+        );
+        assert!(runtime.has_suggestion_providers().unwrap());
+        let mut context = SuggestionContext {
+            before: "fn main".to_string(),
+            column: 7,
+            ..Default::default()
+        };
+        assert_eq!(
+            runtime.request_suggestion(&context).unwrap().as_deref(),
+            Some("() {}")
+        );
+        context.before = "let".to_string();
+        assert_eq!(
+            runtime.request_suggestion(&context).unwrap().as_deref(),
+            Some("1:8")
+        );
+        assert_eq!(runtime.take_suggestions().unwrap(), ["later"]);
+        assert!(runtime.take_suggestions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn programs_run_only_once_the_script_is_allowed() {
+        let root = std::env::temp_dir().join(format!("led-lua-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let mut runtime = runtime(
+            r#"
+function list() return kup.run{ cmd = "ls", args = { "-a" } }.status end
+"#,
+        );
+        // Without a project
+        assert!(runtime.lua.load("list()").exec().is_err());
+
+        runtime.set_process_policy(ProcessPolicy {
+            project_root: root.clone(),
+            limits: Limits {
+                timeout: std::time::Duration::from_secs(10),
+                max_output: 1024,
+            },
+            permissions: Permissions::default(),
+        });
+        assert!(runtime.lua.load("list()").exec().is_err());
+        assert_eq!(runtime.take_permission_requests(), ["plugin.lua"]);
+
+        runtime.decide_permission("plugin.lua", true).unwrap();
+        let status: i64 = runtime.lua.load("return list()").eval().unwrap();
+        assert_eq!(status, 0);
+        assert!(sandbox::permissions_path(&root).exists());
+
+        runtime.decide_permission("plugin.lua", false).unwrap();
+        assert!(runtime.lua.load("list()").exec().is_err());
+        assert!(runtime.take_permission_requests().is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn idle_handlers_run_by_name() {
+        let mut runtime = runtime(
+            r#"
+runs = 0
+kup.on_idle("count", 250, function() runs = runs + 1 end)
+"#,
+        );
+        assert_eq!(
+            runtime.idle_handlers().unwrap(),
+            [("count".to_string(), std::time::Duration::from_millis(250))]
+        );
+        assert!(runtime.run_idle_handler("count").unwrap());
+        assert!(!runtime.run_idle_handler("missing").unwrap());
+        let runs: i64 = runtime.lua.globals().get("runs").unwrap();
+        assert_eq!(runs, 1);
+    }
+
+    #[test]
+    fn evaluation_returns_values_and_stops_runaway_code() {
+        let runtime = Runtime::new().unwrap();
+        assert_eq!(runtime.evaluate("1 + 2").unwrap().as_deref(), Some("3"));
+        assert_eq!(
+            runtime
+                .evaluate("local x = 2 return x, x * 2")
+                .unwrap()
+                .as_deref(),
+            Some("2, 4")
+        );
+        assert_eq!(runtime.evaluate("local x = 1").unwrap(), None);
+        assert!(runtime.evaluate("while true do end").is_err());
+        // The error that stops it can't be caught to keep going
+        assert!(
+            runtime
+                .evaluate("while true do pcall(function() while true do end end) end")
+                .is_err()
+        );
+        assert_eq!(runtime.evaluate("io").unwrap().as_deref(), Some("nil"));
+    }
+
+    #[test]
+    fn snippets_are_listed_and_placeholders_computed() {
+        let runtime = runtime(
+            r#"
+kup.register_snippet{ name = "header", description = "File header", body = "// ${shout}" }
+kup.register_placeholder("shout", function(ctx) return ctx.selection:upper() end)
+"#,
+        );
+        assert_eq!(
+            runtime.registered_snippets().unwrap(),
+            [RegisteredSnippet {
+                name: "header".to_string(),
+                description: Some("File header".to_string()),
+                body: "// ${shout}".to_string(),
+            }]
+        );
+        assert_eq!(
+            runtime
+                .placeholder("shout", Some("a.rs"), "hi")
+                .unwrap()
+                .as_deref(),
+            Some("HI")
+        );
+        assert_eq!(runtime.placeholder("date", None, "hi").unwrap(), None);
+    }
+
+    #[test]
+    fn panels_render_and_receive_events() {
+        let mut runtime = runtime(
+            r#"
+local clicks = 0
+kup.register_panel{
+    name = "clicks",
+    side = "bottom",
+    render = function()
+        return {
+            { type = "button", text = "Click", on_click = function() clicks = clicks + 1 end },
+            { type = "label", text = clicks .. " clicks" },
+        }
+    end,
+}
+kup.register_panel{ name = "broken", render = function() error("no widgets") end }
+"#,
+        );
+        let panels = runtime.panels().unwrap();
+        assert_eq!(panels.len(), 2);
+        assert!(panels[0].error.as_deref().unwrap().contains("no widgets"));
+        assert_eq!(panels[1].title, "clicks");
+        assert_eq!(panels[1].side, PanelSide::Bottom);
+
+        runtime
+            .panel_event("clicks", 0, PanelEvent::Clicked)
+            .unwrap();
+        let panels = runtime.panels().unwrap();
+        assert_eq!(
+            panels[1].widgets,
+            [
+                PanelWidget::Button {
+                    text: "Click".to_string()
+                },
+                PanelWidget::Label {
+                    text: "1 clicks".to_string()
+                },
+            ]
+        );
+
+        runtime.set_panel_visible("clicks", false).unwrap();
+        assert!(runtime.panels().unwrap()[1].widgets.is_empty());
+        assert!(runtime.set_panel_visible("missing", true).is_err());
+    }
+
+    #[test]
+    fn actions_and_translations_are_handed_to_the_editor() {
+        let mut runtime = runtime(
+            r#"
+kup.run_action("edit.upper_case")
+kup.register_translations("de", "greet = Hallo")
+"#,
+        );
+        assert_eq!(runtime.take_actions().unwrap(), ["edit.upper_case"]);
+        assert_eq!(
+            runtime.take_translations().unwrap(),
+            [("de".to_string(), "greet = Hallo".to_string())]
+        );
+        assert!(runtime.take_actions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn batch_scripts_edit_their_buffer() {
+        let mut runtime = runtime("");
+        let mut state = State::new();
+        let buffer_id = state.create_buffer("one\ntwo\n".to_string());
+        state.set_file_path(buffer_id, "list.txt".to_string());
+        runtime
+            .run_batch_script(
+                "upper.lua",
+                r#"
+local buffer = kup.buffer
+assert(buffer.path == "list.txt" and buffer.line_count() == 3)
+buffer.set_line(2, buffer.line(2):upper())
+buffer.insert(1, 1, "> ")
+"#,
+                &mut state,
+                buffer_id,
+            )
+            .unwrap();
+        assert_eq!(
+            state.get_buffer_text(buffer_id).as_deref(),
+            Some("> one\nTWO\n")
+        );
+
+        let error = runtime
+            .run_batch_script(
+                "bad.lua",
+                "kup.buffer.set_line(9, 'x')",
+                &mut state,
+                buffer_id,
+            )
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("the buffer has no line 9"));
+        let gone: mlua::Value = runtime.lua.load("return kup.buffer").eval().unwrap();
+        assert!(gone.is_nil());
+    }
+}
@@ -0,0 +1,225 @@
+use super::buffer::editor::State;
+use super::debounce::{Debouncer, Edge};
+use super::types::buffer::ID;
+use anyhow::Result as AnyResult;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Directory, relative to the project root, that holds LED's per-project state.
+pub const STATE_DIR: &str = ".led";
+/// File name of the scratchpad notes inside [`STATE_DIR`].
+pub const NOTES_FILE: &str = "notes.md";
+/// How long the notes stay modified before they are written back to disk, unless configured
+/// otherwise.
+pub const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Returns the path of the scratchpad notes file for a project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn notes_path(project_root: &Path) -> PathBuf {
+    project_root.join(STATE_DIR).join(NOTES_FILE)
+}
+
+/// A project-scoped Markdown notes buffer stored under `.led/notes.md`.
+///
+/// The notes live in a regular buffer of the editor state, so they can be edited with the
+/// same widget and commands as any other buffer, but opening them does not change the
+/// active buffer.
+#[derive(Debug, Clone)]
+pub struct Scratchpad {
+    /// Location of the notes file on disk.
+    path: PathBuf,
+    /// The buffer holding the notes.
+    buffer_id: ID,
+    /// Limits saving to once per autosave delay while the notes are being edited.
+    autosave: Debouncer,
+}
+
+impl Scratchpad {
+    /// Loads the project's notes into a new buffer, creating an empty one if the file does not exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The editor state to create the notes buffer in.
+    /// * `project_root` - The root directory of the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the notes file exists but cannot be read.
+    pub fn open(state: &mut State, project_root: &Path) -> AnyResult<Self> {
+        let path = notes_path(project_root);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let previous_active = state.get_active_buffer();
+        let buffer_id = state.create_buffer(content);
+        state.set_active_buffer(previous_active);
+
+        state.set_file_path(buffer_id, path.to_string_lossy().to_string());
+        state.set_language(buffer_id, Some("markdown".to_string()));
+
+        Ok(Self {
+            path,
+            buffer_id,
+            autosave: Debouncer::new(AUTOSAVE_DELAY, Edge::Trailing),
+        })
+    }
+
+    /// Returns the ID of the buffer holding the notes.
+    pub fn buffer_id(&self) -> ID {
+        self.buffer_id
+    }
+
+    /// Changes how long the notes stay modified before they are saved.
+    pub fn set_autosave_delay(&mut self, delay: Duration) {
+        self.autosave.set_interval(delay);
+    }
+
+    /// Returns the location of the notes file on disk.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the notes to disk if they were modified, creating the `.led` directory as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save(&mut self, state: &mut State) -> AnyResult<()> {
+        let modified = state
+            .metadata(self.buffer_id)
+            .is_some_and(|meta| meta.modified);
+        if !modified {
+            self.autosave.cancel();
+            return Ok(());
+        }
+
+        let content = state.get_buffer_text(self.buffer_id).unwrap_or_default();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, content)?;
+
+        state.mark_saved(self.buffer_id);
+        self.autosave.cancel();
+        Ok(())
+    }
+
+    /// Saves the notes once they have stayed modified for the autosave delay, which is
+    /// [`AUTOSAVE_DELAY`] unless changed with [`Scratchpad::set_autosave_delay`].
+    ///
+    /// Intended to be called once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The editor state holding the notes buffer.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the notes were written to disk.
+    pub fn autosave(&mut self, state: &mut State, now: Instant) -> AnyResult<bool> {
+        let modified = state
+            .metadata(self.buffer_id)
+            .is_some_and(|meta| meta.modified);
+        if !modified {
+            self.autosave.cancel();
+            return Ok(false);
+        }
+
+        self.autosave.changed(now);
+        if !self.autosave.ready(now) {
+            return Ok(false);
+        }
+        self.save(state)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::commands::editor::Command;
+
+    fn temp_project() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-notes-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn notes_path_is_inside_state_dir() {
+        let path = notes_path(Path::new("/project"));
+        assert_eq!(path, Path::new("/project/.led/notes.md"));
+    }
+
+    #[test]
+    fn open_creates_empty_buffer_without_changing_active_buffer() {
+        let root = temp_project();
+        let mut state = State::new();
+        let main = state.create_buffer("main".to_string());
+
+        let notes = Scratchpad::open(&mut state, &root).unwrap();
+
+        assert_eq!(state.get_active_buffer(), Some(main));
+        assert_eq!(state.get_buffer_text(notes.buffer_id()).unwrap(), "");
+        let meta = state.metadata(notes.buffer_id()).unwrap();
+        assert_eq!(meta.language.as_deref(), Some("markdown"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn open_loads_existing_notes() {
+        let root = temp_project();
+        fs::create_dir_all(root.join(STATE_DIR)).unwrap();
+        fs::write(notes_path(&root), "- [ ] todo\n").unwrap();
+
+        let mut state = State::new();
+        let notes = Scratchpad::open(&mut state, &root).unwrap();
+
+        assert_eq!(
+            state.get_buffer_text(notes.buffer_id()).unwrap(),
+            "- [ ] todo\n"
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn autosave_waits_for_delay_then_writes() {
+        let root = temp_project();
+        let mut state = State::new();
+        let mut notes = Scratchpad::open(&mut state, &root).unwrap();
+        state
+            .execute_command(Command::InsertText {
+                buffer_id: notes.buffer_id(),
+                offset: 0,
+                text: "hello".to_string(),
+            })
+            .unwrap();
+
+        let start = Instant::now();
+        assert!(!notes.autosave(&mut state, start).unwrap());
+        assert!(!notes_path(&root).exists());
+
+        assert!(notes.autosave(&mut state, start + AUTOSAVE_DELAY).unwrap());
+        assert_eq!(fs::read_to_string(notes_path(&root)).unwrap(), "hello");
+        assert!(!state.metadata(notes.buffer_id()).unwrap().modified);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn save_skips_unmodified_notes() {
+        let root = temp_project();
+        let mut state = State::new();
+        let mut notes = Scratchpad::open(&mut state, &root).unwrap();
+        notes.save(&mut state).unwrap();
+        assert!(!notes_path(&root).exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
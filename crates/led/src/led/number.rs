@@ -0,0 +1,225 @@
+use super::commands::editor::Command;
+use super::types::buffer::ID;
+use std::ops::Range;
+
+/// Returns the byte ranges of the numbers in a line: decimal integers and fractions, with a
+/// leading minus sign unless it follows a word, and hexadecimal numbers starting with `0x`.
+pub fn numbers(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let word = |i: usize| bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_';
+    let digits = |from: usize, hex: bool| {
+        from + bytes[from..]
+            .iter()
+            .take_while(|b| {
+                if hex {
+                    b.is_ascii_hexdigit()
+                } else {
+                    b.is_ascii_digit()
+                }
+            })
+            .count()
+    };
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let hex = bytes[i] == b'0'
+            && matches!(bytes.get(i + 1), Some(b'x' | b'X'))
+            && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+        let end = if hex {
+            digits(i + 2, true)
+        } else {
+            let end = digits(i, false);
+            match (bytes.get(end), bytes.get(end + 1)) {
+                (Some(b'.'), Some(next)) if next.is_ascii_digit() => digits(end + 1, false),
+                _ => end,
+            }
+        };
+        let negative = !hex
+            && i > 0
+            && bytes[i - 1] == b'-'
+            && (i == 1 || !(word(i - 2) || matches!(bytes[i - 2], b')' | b']')));
+        found.push(if negative { i - 1 } else { i }..end);
+        i = end;
+    }
+    found
+}
+
+/// Returns the number under or after a column of a line, as the byte range within the line.
+pub fn number_at(line: &str, column: usize) -> Option<Range<usize>> {
+    numbers(line)
+        .into_iter()
+        .find(|number| column <= number.end)
+}
+
+/// Adds to a number, keeping its format: the digits after a decimal point, the zero padding
+/// and the letter case of hexadecimal numbers, and leading zeros of integers.
+///
+/// # Returns
+///
+/// The new number, or `None` if `number` is not one or the result overflows.
+pub fn add(number: &str, amount: i64) -> Option<String> {
+    if let Some(digits) = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        let value = u64::from_str_radix(digits, 16)
+            .ok()?
+            .wrapping_add_signed(amount);
+        let width = digits.len();
+        let prefix = &number[..2];
+        return Some(if digits.bytes().any(|b| b.is_ascii_uppercase()) {
+            format!("{prefix}{value:0width$X}")
+        } else {
+            format!("{prefix}{value:0width$x}")
+        });
+    }
+
+    let (negative, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, number),
+    };
+    let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if whole.is_empty() || !(whole.bytes().chain(fraction.bytes())).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // Fractions are added to as integers scaled by their precision, so no rounding creeps in
+    let places = fraction.len();
+    let scale = 10i128.checked_pow(places as u32)?;
+    let magnitude: i128 = format!("{whole}{fraction}").parse().ok()?;
+    let value = if negative { -magnitude } else { magnitude };
+    let value = value.checked_add(i128::from(amount).checked_mul(scale)?)?;
+
+    let sign = if value < 0 { "-" } else { "" };
+    let whole_width = if whole.len() > 1 && whole.starts_with('0') {
+        whole.len()
+    } else {
+        1
+    };
+    let magnitude = value.unsigned_abs();
+    let whole = magnitude / scale as u128;
+    Some(if fraction.is_empty() {
+        format!("{sign}{whole:0whole_width$}")
+    } else {
+        let fraction = magnitude % scale as u128;
+        format!("{sign}{whole:0whole_width$}.{fraction:0places$}")
+    })
+}
+
+/// Builds the commands that add to the first number on each of a range of lines.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer the commands apply to.
+/// * `text` - The full text of the buffer.
+/// * `first_line` - The first line (zero-based).
+/// * `last_line` - The last line, inclusive.
+/// * `amount` - What to add to each number.
+/// * `sequence` - Whether each line gets one `amount` more than the line before, so a column
+///   of equal numbers counts up.
+///
+/// # Returns
+///
+/// The edit commands, ordered from the end of the buffer towards the start so that each
+/// command's offsets remain valid after the previous ones have been applied.
+pub fn add_to_lines(
+    buffer_id: ID,
+    text: &str,
+    first_line: usize,
+    last_line: usize,
+    amount: i64,
+    sequence: bool,
+) -> Vec<Command> {
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if index > last_line {
+            break;
+        }
+        if index >= first_line
+            && let Some(number) = numbers(line).into_iter().next()
+        {
+            edits.push(offset + number.start..offset + number.end);
+        }
+        offset += line.len();
+    }
+
+    let mut commands = Vec::new();
+    for (index, range) in edits.iter().enumerate().rev() {
+        let amount = if sequence {
+            amount.saturating_mul(index as i64 + 1)
+        } else {
+            amount
+        };
+        if let Some(new) = add(&text[range.clone()], amount) {
+            commands.extend(replace(buffer_id, range.clone(), new));
+        }
+    }
+    commands
+}
+
+/// Builds the commands that replace a range of text.
+pub fn replace(buffer_id: ID, range: Range<usize>, text: String) -> [Command; 2] {
+    [
+        Command::DeleteText {
+            buffer_id,
+            start: range.start,
+            length: range.len(),
+        },
+        Command::InsertText {
+            buffer_id,
+            offset: range.start,
+            text,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::buffer::editor::State;
+
+    #[test]
+    fn finds_numbers_in_a_line() {
+        let line = "x1 = -2.50 + 0xFF - 3 ,-4 v1.2";
+        let found: Vec<&str> = numbers(line).into_iter().map(|r| &line[r]).collect();
+        assert_eq!(found, ["1", "-2.50", "0xFF", "3", "-4", "1.2"]);
+        // The number under the caret, or the next one
+        assert_eq!(number_at(line, 7).map(|r| &line[r]), Some("-2.50"));
+        assert_eq!(number_at(line, 10).map(|r| &line[r]), Some("-2.50"));
+        assert_eq!(number_at(line, 11).map(|r| &line[r]), Some("0xFF"));
+        assert_eq!(number_at("no numbers", 0), None);
+    }
+
+    #[test]
+    fn adding_keeps_the_format() {
+        assert_eq!(add("41", 1).as_deref(), Some("42"));
+        assert_eq!(add("0", -1).as_deref(), Some("-1"));
+        assert_eq!(add("-1", 3).as_deref(), Some("2"));
+        assert_eq!(add("007", 1).as_deref(), Some("008"));
+        assert_eq!(add("1.95", 1).as_deref(), Some("2.95"));
+        assert_eq!(add("0.5", -1).as_deref(), Some("-0.5"));
+        assert_eq!(add("0x0f", 1).as_deref(), Some("0x10"));
+        assert_eq!(add("0xFF", 1).as_deref(), Some("0x100"));
+        assert_eq!(add("0x00A", -1).as_deref(), Some("0x009"));
+        assert_eq!(add("abc", 1), None);
+        assert_eq!(add(&i128::MAX.to_string(), 1), None);
+    }
+
+    #[test]
+    fn lines_count_up_in_sequence() {
+        let text = "item 1\nitem 1\nnone\nitem 1\n";
+        let apply = |sequence| {
+            let mut state = State::new();
+            let id = state.create_buffer(text.to_string());
+            let commands = add_to_lines(id, text, 0, 3, 1, sequence);
+            state.execute_command(Command::Batch(commands)).unwrap();
+            state.get_buffer_text(id).unwrap()
+        };
+        assert_eq!(apply(false), "item 2\nitem 2\nnone\nitem 2\n");
+        assert_eq!(apply(true), "item 2\nitem 3\nnone\nitem 4\n");
+    }
+}
@@ -0,0 +1,180 @@
+/// A command listed in the command palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The action or command name the entry runs, e.g. `file.save`.
+    pub id: String,
+    /// Human-readable title, e.g. `Save File`.
+    pub title: String,
+    /// Optional one-line description.
+    pub description: Option<String>,
+}
+
+/// Scores how well `query` matches `text` as a case-insensitive subsequence.
+///
+/// Consecutive matches and matches at the start of a word score higher, so `sf` ranks
+/// `Save File` above `Misfit`.
+///
+/// # Arguments
+///
+/// * `query` - What the user typed.
+/// * `text` - The candidate text.
+///
+/// # Returns
+///
+/// The score, or `None` if not every character of `query` appears in order in `text`.
+pub fn score(query: &str, text: &str) -> Option<i32> {
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut total = 0;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+    for c in text.chars() {
+        let Some(&wanted) = query_chars.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(wanted));
+        if matched {
+            query_chars.next();
+            total += 1;
+            if prev_matched {
+                total += 3;
+            }
+            if prev.is_none_or(|p| matches!(p, ' ' | '.' | '_' | '-' | ':' | '/')) {
+                total += 2;
+            }
+        }
+        prev_matched = matched;
+        prev = Some(c);
+    }
+    query_chars.peek().is_none().then_some(total)
+}
+
+/// Returns the entries matching `query`, best match first.
+///
+/// Entries match on their title or their ID. Equal scores keep titles in alphabetical order.
+///
+/// # Arguments
+///
+/// * `entries` - All palette entries.
+/// * `query` - What the user typed.
+pub fn filter<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let mut matches: Vec<(i32, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let best = score(query, &entry.title).max(score(query, &entry.id))?;
+            Some((best, entry))
+        })
+        .collect();
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| a.title.cmp(&b.title))
+    });
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Completes `query` the way a shell would, for the Tab key.
+///
+/// If the titles of all matches starting with `query` share a longer common prefix, the query
+/// is extended to it. Otherwise it is replaced with the title of the best match.
+///
+/// # Arguments
+///
+/// * `matches` - The entries matching `query`, as returned by [`filter`].
+/// * `query` - What the user typed.
+///
+/// # Returns
+///
+/// The completed query, or `None` if there is nothing to complete.
+pub fn complete(matches: &[&Entry], query: &str) -> Option<String> {
+    let lower = query.to_lowercase();
+    let prefixed: Vec<&str> = matches
+        .iter()
+        .map(|entry| entry.title.as_str())
+        .filter(|title| title.to_lowercase().starts_with(&lower))
+        .collect();
+    if let Some((first, rest)) = prefixed.split_first() {
+        let common = rest.iter().fold(first.len(), |len, title| {
+            len.min(common_prefix_len(first, title))
+        });
+        if common > query.len() {
+            return Some(first[..common].to_string());
+        }
+    }
+    let best = matches.first()?;
+    (best.title != query).then(|| best.title.clone())
+}
+
+/// Returns the byte length of the case-insensitive common prefix of `a` and `b`, measured in `a`.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .take_while(|((_, x), y)| x.to_lowercase().eq(y.to_lowercase()))
+        .last()
+        .map_or(0, |((i, x), _)| i + x.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, title: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn score_requires_ordered_subsequence() {
+        assert!(score("sf", "Save File").is_some());
+        assert!(score("SAVE", "save file").is_some());
+        assert!(score("fs", "Save File").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn score_prefers_word_starts_and_runs() {
+        let word_starts = score("sf", "Save File").unwrap();
+        let scattered = score("sf", "Misfit").unwrap();
+        assert!(word_starts > scattered);
+        let run = score("save", "Save File").unwrap();
+        let spread = score("save", "Show a Very Easy").unwrap();
+        assert!(run > spread);
+    }
+
+    #[test]
+    fn filter_matches_titles_and_ids_best_first() {
+        let entries = vec![
+            entry("file.save", "Save File"),
+            entry("file.new", "New File"),
+            entry("greet", "Say Hello"),
+        ];
+        let titles: Vec<&str> = filter(&entries, "save")
+            .iter()
+            .map(|e| e.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Save File"]);
+        assert_eq!(filter(&entries, "greet").len(), 1);
+        assert_eq!(filter(&entries, "").len(), 3);
+    }
+
+    #[test]
+    fn complete_extends_common_prefix() {
+        let entries = vec![entry("a", "Toggle Notes"), entry("b", "Toggle Zen Mode")];
+        let matches = filter(&entries, "tog");
+        assert_eq!(complete(&matches, "tog").as_deref(), Some("Toggle "));
+        let matches = filter(&entries, "Toggle ");
+        assert_eq!(
+            complete(&matches, "Toggle ").as_deref(),
+            Some("Toggle Notes")
+        );
+    }
+
+    #[test]
+    fn complete_falls_back_to_best_match() {
+        let entries = vec![entry("file.save", "Save File")];
+        let matches = filter(&entries, "sf");
+        assert_eq!(complete(&matches, "sf").as_deref(), Some("Save File"));
+        assert_eq!(complete(&matches, "Save File"), None);
+        assert_eq!(complete(&[], "x"), None);
+    }
+}
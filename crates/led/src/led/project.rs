@@ -0,0 +1,156 @@
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+/// The Rust edition rustfmt formats with when `Cargo.toml` doesn't name one.
+const DEFAULT_RUST_EDITION: &str = "2021";
+
+/// A kind of project, recognized by a marker file in the project root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ecosystem {
+    Rust,
+    Node,
+    Python,
+}
+
+impl Ecosystem {
+    pub const ALL: [Self; 3] = [Self::Rust, Self::Node, Self::Python];
+
+    /// Returns the file whose presence in the project root marks the ecosystem.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::Rust => "Cargo.toml",
+            Self::Node => "package.json",
+            Self::Python => "pyproject.toml",
+        }
+    }
+
+    /// Returns the settings the ecosystem's projects get unless a settings file says otherwise:
+    /// its usual formatters and linters, and the build and dependency directories to keep out
+    /// of the project index.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The project root, for details such as the Rust edition.
+    pub fn settings(self, root: &Path) -> Map<String, Value> {
+        let value = match self {
+            Self::Rust => {
+                let edition = rust_edition(root);
+                json!({
+                    "formatters": { "rs": format!("rustfmt --edition {}", edition) },
+                    "excluded_dirs": ["target"],
+                })
+            }
+            Self::Node => {
+                let prettier = "npx --no-install prettier --stdin-filepath {file}";
+                let formatters: Map<String, Value> = ["js", "jsx", "ts", "tsx", "json", "css"]
+                    .into_iter()
+                    .map(|extension| (extension.to_string(), json!(prettier)))
+                    .collect();
+                json!({
+                    "formatters": formatters,
+                    "excluded_dirs": ["node_modules", "dist", "coverage"],
+                })
+            }
+            Self::Python => json!({
+                "formatters": { "py": "black --quiet -" },
+                "linters": {
+                    "py": "ruff check --quiet --output-format concise --stdin-filename {file} -"
+                },
+                "excluded_dirs": ["__pycache__", "venv", "build", "dist"],
+            }),
+        };
+        match value {
+            Value::Object(settings) => settings,
+            _ => unreachable!("the settings are written as objects"),
+        }
+    }
+}
+
+/// Returns the ecosystems whose marker files are in the project root; a project may belong to
+/// several, such as a Rust crate with Python bindings.
+pub fn detect(root: &Path) -> Vec<Ecosystem> {
+    Ecosystem::ALL
+        .into_iter()
+        .filter(|ecosystem| root.join(ecosystem.marker()).is_file())
+        .collect()
+}
+
+/// Returns the settings for the ecosystems detected in the project root, to be overridden by
+/// the settings files.
+///
+/// The formatters and linters of every detected ecosystem are combined, as are the excluded
+/// directories.
+pub fn detected_settings(root: &Path) -> Map<String, Value> {
+    let mut merged = Map::new();
+    for ecosystem in detect(root) {
+        for (key, value) in ecosystem.settings(root) {
+            match (merged.get_mut(&key), value) {
+                (Some(Value::Object(table)), Value::Object(more)) => table.extend(more),
+                (Some(Value::Array(list)), Value::Array(more)) => list.extend(more),
+                (_, value) => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Reads the edition from the project's `Cargo.toml`, looking for an `edition = "…"` line
+/// rather than parsing the whole manifest.
+fn rust_edition(root: &Path) -> String {
+    let manifest = std::fs::read_to_string(root.join("Cargo.toml")).unwrap_or_default();
+    manifest
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("edition"))
+        .filter_map(|rest| rest.trim_start().strip_prefix('='))
+        .map(|value| value.trim().trim_matches('"'))
+        .find(|edition| !edition.is_empty() && edition.bytes().all(|b| b.is_ascii_digit()))
+        .unwrap_or(DEFAULT_RUST_EDITION)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ecosystems_are_detected_and_combined() {
+        let root = std::env::temp_dir().join(format!("led-project-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        assert!(detect(&root).is_empty());
+        assert!(detected_settings(&root).is_empty());
+
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nedition = \"2024\"\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("pyproject.toml"), "").unwrap();
+        assert_eq!(detect(&root), vec![Ecosystem::Rust, Ecosystem::Python]);
+
+        let settings = detected_settings(&root);
+        assert_eq!(settings["formatters"]["rs"], "rustfmt --edition 2024");
+        assert_eq!(settings["formatters"]["py"], "black --quiet -");
+        assert!(settings["linters"]["py"].is_string());
+        let excluded = settings["excluded_dirs"].as_array().unwrap();
+        assert!(excluded.contains(&json!("target")));
+        assert!(excluded.contains(&json!("__pycache__")));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rust_edition_falls_back_without_one() {
+        let root = std::env::temp_dir().join(format!("led-project-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+        assert_eq!(rust_edition(&root), DEFAULT_RUST_EDITION);
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nedition.workspace = true\n",
+        )
+        .unwrap();
+        assert_eq!(rust_edition(&root), DEFAULT_RUST_EDITION);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use std::ops::Range;
+
+/// Languages treated as prose, where zen mode wraps and centers text by default.
+pub const PROSE_LANGUAGES: &[&str] = &["markdown", "text", "rst", "asciidoc", "org"];
+
+/// Returns whether a buffer language denotes a prose document.
+///
+/// # Arguments
+///
+/// * `language` - The buffer's language, if known.
+pub fn is_prose(language: Option<&str>) -> bool {
+    language.is_some_and(|lang| PROSE_LANGUAGES.contains(&lang))
+}
+
+/// Splits a single line into visual rows of at most `width` characters.
+///
+/// Rows break after whitespace where possible, and trailing whitespace is allowed to hang past
+/// the wrap column. Words longer than `width` are broken mid-word. A `width` of zero disables
+/// wrapping.
+///
+/// # Arguments
+///
+/// * `line` - The line to wrap, without its line break.
+/// * `width` - The wrap column in characters.
+///
+/// # Returns
+///
+/// The byte ranges of each row within `line`. There is always at least one row.
+pub fn wrap(line: &str, width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    if width == 0 {
+        rows.push(0..line.len());
+        return rows;
+    }
+
+    let mut start = 0;
+    let mut count = 0;
+    let mut last_break = None;
+    for (i, c) in line.char_indices() {
+        if count >= width && !c.is_whitespace() {
+            let end = match last_break {
+                Some(b) if b > start => b,
+                _ => i,
+            };
+            rows.push(start..end);
+            start = end;
+            count = line[start..i].chars().count();
+            last_break = None;
+        }
+        count += 1;
+        if c.is_whitespace() {
+            last_break = Some(i + c.len_utf8());
+        }
+    }
+    rows.push(start..line.len());
+    rows
+}
+
+/// Returns the byte offsets where sentences begin.
+///
+/// A sentence begins at the first non-whitespace character of the text, after a run of
+/// `.`, `!` or `?` (optionally followed by closing quotes or brackets) that is followed by
+/// whitespace, and at the start of every paragraph.
+pub fn sentence_starts(text: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut at_boundary = true;
+    let mut after_terminator = false;
+    let mut prev = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if after_terminator {
+                at_boundary = true;
+            }
+            // A blank line ends the current sentence even without punctuation.
+            if c == '\n' && prev == Some('\n') {
+                at_boundary = true;
+            }
+        } else {
+            if at_boundary {
+                starts.push(i);
+                at_boundary = false;
+                after_terminator = false;
+            }
+            after_terminator = matches!(c, '.' | '!' | '?')
+                || (after_terminator && matches!(c, '"' | '\'' | ')' | ']'));
+        }
+        if c != '\r' {
+            prev = Some(c);
+        }
+    }
+    starts
+}
+
+/// Returns the byte offsets of the first character of every paragraph.
+///
+/// Paragraphs are runs of non-blank lines separated by one or more blank lines.
+pub fn paragraph_starts(text: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    let mut in_paragraph = false;
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if !blank && !in_paragraph {
+            starts.push(offset);
+        }
+        in_paragraph = !blank;
+        offset += line.len();
+    }
+    starts
+}
+
+/// Returns the first boundary after `offset`, or the end of the text.
+fn next_boundary(starts: &[usize], offset: usize, len: usize) -> usize {
+    starts.iter().copied().find(|&s| s > offset).unwrap_or(len)
+}
+
+/// Returns the last boundary before `offset`, or the start of the text.
+fn prev_boundary(starts: &[usize], offset: usize) -> usize {
+    starts
+        .iter()
+        .rev()
+        .copied()
+        .find(|&s| s < offset)
+        .unwrap_or(0)
+}
+
+/// Returns the offset of the start of the next sentence after `offset`.
+pub fn next_sentence(text: &str, offset: usize) -> usize {
+    next_boundary(&sentence_starts(text), offset, text.len())
+}
+
+/// Returns the offset of the start of the sentence before `offset`.
+pub fn prev_sentence(text: &str, offset: usize) -> usize {
+    prev_boundary(&sentence_starts(text), offset)
+}
+
+/// Returns the offset of the start of the next paragraph after `offset`.
+pub fn next_paragraph(text: &str, offset: usize) -> usize {
+    next_boundary(&paragraph_starts(text), offset, text.len())
+}
+
+/// Returns the offset of the start of the paragraph before `offset`.
+pub fn prev_paragraph(text: &str, offset: usize) -> usize {
+    prev_boundary(&paragraph_starts(text), offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_prose_matches_known_languages() {
+        assert!(is_prose(Some("markdown")));
+        assert!(!is_prose(Some("rust")));
+        assert!(!is_prose(None));
+    }
+
+    #[test]
+    fn wrap_breaks_after_whitespace() {
+        let line = "hello world foo";
+        let rows = wrap(line, 11);
+        assert_eq!(rows, vec![0..12, 12..15]);
+        assert_eq!(&line[rows[1].clone()], "foo");
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        assert_eq!(wrap("abcdefgh", 3), vec![0..3, 3..6, 6..8]);
+    }
+
+    #[test]
+    fn wrap_short_and_empty_lines_are_single_rows() {
+        assert_eq!(wrap("short", 80), vec![Range { start: 0, end: 5 }]);
+        assert_eq!(wrap("", 80), vec![Range { start: 0, end: 0 }]);
+        assert_eq!(wrap("no wrap", 0), vec![Range { start: 0, end: 7 }]);
+    }
+
+    #[test]
+    fn sentence_starts_follow_terminators_and_paragraphs() {
+        let text = "One. Two? \"Three!\" Four\n\nFive";
+        let starts = sentence_starts(text);
+        let words: Vec<&str> = starts.iter().map(|&s| &text[s..s + 3]).collect();
+        assert_eq!(words, vec!["One", "Two", "\"Th", "Fou", "Fiv"]);
+    }
+
+    #[test]
+    fn sentence_starts_ignore_inline_periods() {
+        let text = "Version 1.2 is out.";
+        assert_eq!(sentence_starts(text), vec![0]);
+    }
+
+    #[test]
+    fn sentence_navigation() {
+        let text = "One. Two. Three.";
+        assert_eq!(next_sentence(text, 0), 5);
+        assert_eq!(next_sentence(text, 5), 10);
+        assert_eq!(next_sentence(text, 10), text.len());
+        assert_eq!(prev_sentence(text, 12), 10);
+        assert_eq!(prev_sentence(text, 10), 5);
+        assert_eq!(prev_sentence(text, 0), 0);
+    }
+
+    #[test]
+    fn paragraph_navigation() {
+        let text = "first\nstill first\n\n\nsecond\n\nthird\n";
+        assert_eq!(paragraph_starts(text), vec![0, 20, 28]);
+        assert_eq!(next_paragraph(text, 3), 20);
+        assert_eq!(next_paragraph(text, 28), text.len());
+        assert_eq!(prev_paragraph(text, 25), 20);
+        assert_eq!(prev_paragraph(text, 20), 0);
+    }
+}
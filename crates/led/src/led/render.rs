@@ -0,0 +1,85 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect};
+
+/// What the editor view paints on.
+///
+/// The view lays out rows, cells, virtual text and selections itself and only asks the surface
+/// to draw the result, so another frontend, such as a terminal UI or an offscreen renderer for
+/// exporting, shows exactly the same layout by implementing this trait. Positions are in the
+/// view's points; a surface with a coarser grid rounds them to its cells.
+pub trait RenderSurface {
+    /// Draws a run of text on one row, placed by the corner or edge of its bounds named by
+    /// `anchor`, e.g. [`Align2::LEFT_TOP`] to start it at `pos`.
+    fn draw_text_run(
+        &mut self,
+        pos: Pos2,
+        anchor: Align2,
+        text: &str,
+        font: &FontId,
+        color: Color32,
+    );
+
+    /// Fills a rectangle, rounding its corners by `radius` points; a square rounded by half
+    /// its size is a circle.
+    fn fill_rect(&mut self, rect: Rect, radius: f32, color: Color32);
+
+    /// Restricts drawing to the part of `rect` within the current clip, until the matching
+    /// [`RenderSurface::pop_clip`].
+    fn push_clip(&mut self, rect: Rect);
+
+    /// Restores the clip from before the last [`RenderSurface::push_clip`].
+    fn pop_clip(&mut self);
+
+    /// Returns the area drawing is restricted to.
+    fn clip_rect(&self) -> Rect;
+}
+
+/// Paints with egui, the surface of the desktop and mobile app.
+pub struct EguiSurface {
+    painter: Painter,
+    /// The clips to restore, innermost last.
+    clips: Vec<Rect>,
+}
+
+impl EguiSurface {
+    /// Creates a surface that paints with `painter`, within its clip.
+    pub fn new(painter: Painter) -> Self {
+        Self {
+            painter,
+            clips: Vec::new(),
+        }
+    }
+}
+
+impl RenderSurface for EguiSurface {
+    fn draw_text_run(
+        &mut self,
+        pos: Pos2,
+        anchor: Align2,
+        text: &str,
+        font: &FontId,
+        color: Color32,
+    ) {
+        self.painter.text(pos, anchor, text, font.clone(), color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, radius: f32, color: Color32) {
+        let radius = egui::CornerRadius::same(radius.round().clamp(0.0, 255.0) as u8);
+        self.painter.rect_filled(rect, radius, color);
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        let clip = self.painter.clip_rect();
+        self.clips.push(clip);
+        self.painter.set_clip_rect(clip.intersect(rect));
+    }
+
+    fn pop_clip(&mut self) {
+        if let Some(clip) = self.clips.pop() {
+            self.painter.set_clip_rect(clip);
+        }
+    }
+
+    fn clip_rect(&self) -> Rect {
+        self.painter.clip_rect()
+    }
+}
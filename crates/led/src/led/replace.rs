@@ -0,0 +1,223 @@
+use super::search::Pattern;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A change a project-wide replace makes: the lines holding one or more matches, as they are
+/// and as they will be. Matches that share a line make one change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Zero-based index of the first changed line.
+    pub line: usize,
+    /// The changed lines as they are, without the final line break.
+    pub old: String,
+    /// The changed lines with the matches replaced.
+    pub new: String,
+    /// Whether the change is made when the replace is applied.
+    pub accepted: bool,
+    /// Each match, with the text that replaces it.
+    edits: Vec<(Range<usize>, String)>,
+}
+
+/// The changes a project-wide replace makes to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChanges {
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// The text the changes were found in, so a file that changed since is left alone.
+    pub text: String,
+    pub changes: Vec<Change>,
+}
+
+impl FileChanges {
+    /// Returns how many of the changes are accepted.
+    pub fn accepted(&self) -> usize {
+        self.changes.iter().filter(|change| change.accepted).count()
+    }
+
+    /// Returns the file's text with the accepted changes made.
+    pub fn apply(&self) -> String {
+        let mut replaced = String::with_capacity(self.text.len());
+        let mut from = 0;
+        for (found, expanded) in self
+            .changes
+            .iter()
+            .filter(|change| change.accepted)
+            .flat_map(|change| &change.edits)
+        {
+            replaced.push_str(&self.text[from..found.start]);
+            replaced.push_str(expanded);
+            from = found.end;
+        }
+        replaced.push_str(&self.text[from..]);
+        replaced
+    }
+}
+
+/// Finds the changes replacing every match in a text makes, all of them accepted.
+///
+/// # Arguments
+///
+/// * `pattern` - What to replace.
+/// * `text` - The text to search.
+/// * `replacement` - The text each match is replaced with, as for [`Pattern::expand`].
+pub fn changes(pattern: &Pattern, text: &str, replacement: &str) -> Vec<Change> {
+    // Byte ranges of the changed lines, alongside the changes
+    let mut lines: Vec<Range<usize>> = Vec::new();
+    let mut changes: Vec<Change> = Vec::new();
+    let (mut counted, mut line) = (0, 0);
+    for found in pattern.find_all(text, 0..text.len()) {
+        let start = text[..found.start].rfind('\n').map_or(0, |i| i + 1);
+        let end = text[found.end..]
+            .find('\n')
+            .map_or(text.len(), |i| found.end + i);
+        let expanded = pattern.expand(text, found.clone(), replacement);
+        match (lines.last_mut(), changes.last_mut()) {
+            (Some(last), Some(change)) if start <= last.end => {
+                last.end = last.end.max(end);
+                change.edits.push((found, expanded));
+            }
+            _ => {
+                line += text[counted..start].matches('\n').count();
+                counted = start;
+                lines.push(start..end);
+                changes.push(Change {
+                    line,
+                    old: String::new(),
+                    new: String::new(),
+                    accepted: true,
+                    edits: vec![(found, expanded)],
+                });
+            }
+        }
+    }
+    for (range, change) in lines.into_iter().zip(&mut changes) {
+        let mut from = range.start;
+        for (found, expanded) in &change.edits {
+            change.new.push_str(&text[from..found.start]);
+            change.new.push_str(expanded);
+            from = found.end;
+        }
+        change.new.push_str(&text[from..range.end]);
+        change.old = text[range].to_string();
+    }
+    changes
+}
+
+/// Finds the changes a replace makes across files.
+///
+/// # Arguments
+///
+/// * `paths` - The files to search, in the order to list them.
+/// * `read` - Returns the text of a file, or `None` to skip it.
+/// * `pattern` - What to replace.
+/// * `replacement` - The text each match is replaced with, as for [`Pattern::expand`].
+/// * `limit` - The maximum number of changes to find.
+///
+/// # Returns
+///
+/// The files with changes, and whether the search stopped at its limit with more matches left.
+/// The file the limit was reached in keeps only the changes up to it.
+pub fn find(
+    paths: &[PathBuf],
+    mut read: impl FnMut(&Path) -> Option<String>,
+    pattern: &Pattern,
+    replacement: &str,
+    limit: usize,
+) -> (Vec<FileChanges>, bool) {
+    let mut files = Vec::new();
+    let mut left = limit;
+    for path in paths {
+        let Some(text) = read(path) else {
+            continue;
+        };
+        let mut changes = changes(pattern, &text, replacement);
+        if changes.is_empty() {
+            continue;
+        }
+        if left == 0 {
+            return (files, true);
+        }
+        let truncated = changes.len() > left;
+        changes.truncate(left);
+        left -= changes.len();
+        files.push(FileChanges {
+            path: path.clone(),
+            text,
+            changes,
+        });
+        if truncated {
+            return (files, true);
+        }
+    }
+    (files, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(query: &str, regex: bool) -> Pattern {
+        Pattern::query(query, regex, true, false).unwrap().unwrap()
+    }
+
+    #[test]
+    fn matches_sharing_a_line_make_one_change() {
+        let text = "a foo foo\nb\nfoo c\n";
+        let changes = changes(&pattern("foo", false), text, "bar");
+        let shown: Vec<(usize, &str, &str)> = changes
+            .iter()
+            .map(|c| (c.line, c.old.as_str(), c.new.as_str()))
+            .collect();
+        assert_eq!(
+            shown,
+            [(0, "a foo foo", "a bar bar"), (2, "foo c", "bar c")]
+        );
+    }
+
+    #[test]
+    fn multiline_matches_cover_every_line_they_touch() {
+        let text = "one\ntwo\nthree\n";
+        let changes = changes(&pattern(r"o\nt", true), text, "-");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].line, 1);
+        assert_eq!(changes[0].old, "two\nthree");
+        assert_eq!(changes[0].new, "tw-hree");
+    }
+
+    #[test]
+    fn only_accepted_changes_are_applied() {
+        let text = "foo\nfoo\nfoo";
+        let mut file = FileChanges {
+            path: PathBuf::from("/p/a.txt"),
+            text: text.to_string(),
+            changes: changes(&pattern("(f)oo", true), text, "${1}ee"),
+        };
+        file.changes[1].accepted = false;
+        assert_eq!(file.accepted(), 2);
+        assert_eq!(file.apply(), "fee\nfoo\nfee");
+    }
+
+    #[test]
+    fn finds_changes_across_files_up_to_a_limit() {
+        let paths = [
+            PathBuf::from("/p/a"),
+            PathBuf::from("/p/b"),
+            PathBuf::from("/p/c"),
+        ];
+        let read = |path: &Path| match path.to_str()? {
+            "/p/a" => Some("x\nx\n".to_string()),
+            "/p/b" => Some("nothing\n".to_string()),
+            _ => Some("x x\nx\n".to_string()),
+        };
+        let pattern = pattern("x", false);
+        let (files, truncated) = find(&paths, read, &pattern, "y", 10);
+        assert!(!truncated);
+        let counts: Vec<usize> = files.iter().map(|f| f.changes.len()).collect();
+        assert_eq!(counts, [2, 2]);
+
+        let (files, truncated) = find(&paths, read, &pattern, "y", 3);
+        assert!(truncated);
+        assert_eq!(files[1].path, PathBuf::from("/p/c"));
+        assert_eq!(files[1].changes.len(), 1);
+    }
+}
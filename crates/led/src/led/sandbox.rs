@@ -0,0 +1,309 @@
+use anyhow::{Context as _, Result as AnyResult, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// File name of the process permissions inside the project's `.led` directory.
+pub const PERMISSIONS_FILE: &str = "permissions.json";
+
+/// Returns the path of the process permissions file for a project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn permissions_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(super::notes::STATE_DIR)
+        .join(PERMISSIONS_FILE)
+}
+
+/// Which scripts the user allowed or refused to run programs, by script path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Permissions {
+    allowed: BTreeSet<String>,
+    denied: BTreeSet<String>,
+}
+
+impl Permissions {
+    /// Loads the permissions from a file, or returns none if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> AnyResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the permissions to a file, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> AnyResult<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Returns whether a script may run programs, or `None` if the user hasn't decided yet.
+    pub fn decision(&self, script: &str) -> Option<bool> {
+        if self.denied.contains(script) {
+            Some(false)
+        } else if self.allowed.contains(script) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Records the user's decision for a script.
+    pub fn decide(&mut self, script: &str, allow: bool) {
+        let (add, remove) = if allow {
+            (&mut self.allowed, &mut self.denied)
+        } else {
+            (&mut self.denied, &mut self.allowed)
+        };
+        remove.remove(script);
+        add.insert(script.to_string());
+    }
+}
+
+/// Limits on a process spawned on behalf of a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// How long the process may run before it is killed.
+    pub timeout: Duration,
+    /// How many bytes of each of standard output and standard error are kept. The rest is
+    /// read and discarded, so the process never blocks on a full pipe.
+    pub max_output: usize,
+}
+
+/// What a process did, as far as its limits allowed it to be captured.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Output {
+    /// The exit code, or `None` if the process was killed or ended by a signal.
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether some output was discarded because of [`Limits::max_output`].
+    pub truncated: bool,
+    /// Whether the process was killed because of [`Limits::timeout`].
+    pub timed_out: bool,
+}
+
+/// Resolves the working directory a script asked for, refusing anything outside the project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+/// * `dir` - The requested directory, relative to the project root, or `None` for the root.
+///
+/// # Errors
+///
+/// Returns an error if the directory does not exist or lies outside the project, including
+/// through `..` or symbolic links.
+pub fn confine(project_root: &Path, dir: Option<&str>) -> AnyResult<PathBuf> {
+    let root = project_root
+        .canonicalize()
+        .with_context(|| format!("project root {} not found", project_root.display()))?;
+    let requested = root.join(dir.unwrap_or("."));
+    let resolved = requested
+        .canonicalize()
+        .with_context(|| format!("directory {} not found", requested.display()))?;
+    if !resolved.starts_with(&root) {
+        return Err(anyhow!(
+            "directory {} is outside the project",
+            resolved.display()
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Reads a stream to its end on a background thread, keeping at most `max` bytes.
+fn capture(
+    mut stream: impl Read + Send + 'static,
+    max: usize,
+) -> std::thread::JoinHandle<(Vec<u8>, bool)> {
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut truncated = false;
+        let mut buf = [0; 8192];
+        while let Ok(n) = stream.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            let room = max.saturating_sub(kept.len());
+            kept.extend_from_slice(&buf[..n.min(room)]);
+            truncated |= n > room;
+        }
+        (kept, truncated)
+    })
+}
+
+/// Runs a command to completion within limits, capturing its output.
+///
+/// The command's environment and working directory are left as the caller set them; see
+/// [`super::shell::Environment::command`] and [`confine`].
+///
+/// # Errors
+///
+/// Returns an error if the process cannot be started.
+pub fn run(command: Command, limits: Limits) -> AnyResult<Output> {
+    execute(command, None, limits)
+}
+
+/// Runs a command to completion within limits like [`run`], giving it `input` on its standard
+/// input, as for a formatter that reads the text to format there.
+///
+/// # Errors
+///
+/// Returns an error if the process cannot be started.
+pub fn pipe(command: Command, input: String, limits: Limits) -> AnyResult<Output> {
+    execute(command, Some(input), limits)
+}
+
+fn execute(mut command: Command, input: Option<String>, limits: Limits) -> AnyResult<Output> {
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start {:?}", command.get_program()))?;
+    // Writing on another thread lets the time limit apply to a process that stops reading
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stdout = capture(
+        child.stdout.take().expect("stdout is piped"),
+        limits.max_output,
+    );
+    let stderr = capture(
+        child.stderr.take().expect("stderr is piped"),
+        limits.max_output,
+    );
+
+    let started = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= limits.timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait()?;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+
+    // Programs the process started in the background may keep the pipes open, so the readers
+    // are only waited for until the time limit
+    let join = |handle: std::thread::JoinHandle<(Vec<u8>, bool)>| {
+        while !handle.is_finished() && started.elapsed() < limits.timeout {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        if !handle.is_finished() {
+            return (Vec::new(), true);
+        }
+        handle.join().unwrap_or_default()
+    };
+    let (stdout, stdout_truncated) = join(stdout);
+    let (stderr, stderr_truncated) = join(stderr);
+    Ok(Output {
+        status: if timed_out { None } else { status.code() },
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: stdout_truncated || stderr_truncated,
+        timed_out,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        timeout: Duration::from_secs(10),
+        max_output: 1024,
+    };
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[test]
+    fn output_is_captured_up_to_the_limit() {
+        let output = run(sh("echo out; echo err >&2; exit 3"), LIMITS).unwrap();
+        assert_eq!(output.status, Some(3));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert!(!output.truncated && !output.timed_out);
+
+        let output = run(sh("head -c 100000 /dev/zero"), LIMITS).unwrap();
+        assert_eq!(output.stdout.len(), LIMITS.max_output);
+        assert!(output.truncated);
+        assert_eq!(output.status, Some(0));
+    }
+
+    #[test]
+    fn input_is_piped_to_the_process() {
+        let output = pipe(sh("tr a-z A-Z"), "text\n".to_string(), LIMITS).unwrap();
+        assert_eq!(output.status, Some(0));
+        assert_eq!(output.stdout, "TEXT\n");
+    }
+
+    #[test]
+    fn slow_processes_are_killed() {
+        let limits = Limits {
+            timeout: Duration::from_millis(100),
+            ..LIMITS
+        };
+        let started = Instant::now();
+        let output = run(sh("exec sleep 10"), limits).unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.status, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn working_directory_stays_inside_the_project() {
+        let dir = std::env::temp_dir().join(format!("led-sandbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let root = dir.canonicalize().unwrap();
+        assert_eq!(confine(&dir, None).unwrap(), root);
+        assert_eq!(confine(&dir, Some("src")).unwrap(), root.join("src"));
+        assert!(confine(&dir, Some("..")).is_err());
+        assert!(confine(&dir, Some("/")).is_err());
+        assert!(confine(&dir, Some("missing")).is_err());
+
+        let mut permissions = Permissions::default();
+        assert_eq!(permissions.decision("init.lua"), None);
+        permissions.decide("init.lua", true);
+        permissions.decide("init.lua", false);
+        assert_eq!(permissions.decision("init.lua"), Some(false));
+        let path = permissions_path(&dir);
+        permissions.save(&path).unwrap();
+        assert_eq!(Permissions::load(&path).unwrap(), permissions);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
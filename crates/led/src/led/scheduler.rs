@@ -0,0 +1,171 @@
+use std::time::{Duration, Instant};
+
+/// Whether an incremental task has more work left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The task made progress and wants to run again.
+    Continue,
+    /// The task is finished.
+    Done,
+}
+
+/// Work that can be done in small increments, such as scanning a large buffer a few thousand
+/// lines at a time.
+///
+/// Each step should take well under a millisecond, so that a frame's budget is not overrun
+/// by much when it runs out in the middle of a step.
+pub trait Task<C: ?Sized> {
+    /// Does the next increment of work.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - What the task works on, such as the text being scanned.
+    fn step(&mut self, ctx: &C) -> Step;
+}
+
+/// Shares a per-frame time budget among incremental tasks, yielding the rest of the frame to
+/// rendering.
+///
+/// Call [`Scheduler::begin_frame`] at the start of each frame, then let subsystems run their
+/// tasks with [`Scheduler::run`]. A task that runs out of budget picks up where it left off
+/// on the next frame; [`Scheduler::is_behind`] tells whether one is waiting, so the caller
+/// can request another frame.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    budget: Duration,
+    /// When the current frame's budget runs out.
+    deadline: Option<Instant>,
+    /// Whether a task yielded unfinished during the current frame.
+    behind: bool,
+}
+
+impl Scheduler {
+    /// Creates a scheduler.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - How much of each frame incremental work may take.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            deadline: None,
+            behind: false,
+        }
+    }
+
+    /// Returns how much of each frame incremental work may take.
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Changes how much of each frame incremental work may take, from the next frame on.
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Starts a new frame's budget.
+    pub fn begin_frame(&mut self, now: Instant) {
+        self.deadline = Some(now + self.budget);
+        self.behind = false;
+    }
+
+    /// Returns whether a task ran out of budget during the current frame.
+    pub fn is_behind(&self) -> bool {
+        self.behind
+    }
+
+    /// Runs a task until it is done or the frame's budget is used up.
+    ///
+    /// Every call runs at least one step, so tasks make progress even in frames where earlier
+    /// tasks used up the budget.
+    ///
+    /// # Returns
+    ///
+    /// [`Step::Done`] if the task finished, or [`Step::Continue`] if it should be run again on
+    /// a later frame.
+    pub fn run<C: ?Sized>(&mut self, task: &mut impl Task<C>, ctx: &C) -> Step {
+        self.run_with_clock(task, ctx, Instant::now)
+    }
+
+    /// Like [`Scheduler::run`], reading the time from `now` instead of the system clock.
+    pub fn run_with_clock<C: ?Sized>(
+        &mut self,
+        task: &mut impl Task<C>,
+        ctx: &C,
+        mut now: impl FnMut() -> Instant,
+    ) -> Step {
+        loop {
+            if task.step(ctx) == Step::Done {
+                return Step::Done;
+            }
+            if self.deadline.is_none_or(|deadline| now() >= deadline) {
+                self.behind = true;
+                return Step::Continue;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Counts down, taking a millisecond of simulated time per step.
+    struct Countdown {
+        left: usize,
+        done: Vec<usize>,
+    }
+
+    impl Countdown {
+        fn new(left: usize) -> Self {
+            Self {
+                left,
+                done: Vec::new(),
+            }
+        }
+    }
+
+    impl Task<Cell<Instant>> for Countdown {
+        fn step(&mut self, clock: &Cell<Instant>) -> Step {
+            clock.set(clock.get() + Duration::from_millis(1));
+            self.left -= 1;
+            self.done.push(self.left);
+            if self.left == 0 {
+                Step::Done
+            } else {
+                Step::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn tasks_yield_when_the_budget_is_used_up() {
+        let clock = Cell::new(Instant::now());
+        let mut scheduler = Scheduler::new(Duration::from_millis(4));
+        let mut task = Countdown::new(10);
+
+        scheduler.begin_frame(clock.get());
+        let step = scheduler.run_with_clock(&mut task, &clock, || clock.get());
+        assert_eq!(step, Step::Continue);
+        assert_eq!(task.done.len(), 4);
+        assert!(scheduler.is_behind());
+
+        // The budget is used up, but the next task still gets a step
+        let mut other = Countdown::new(5);
+        scheduler.run_with_clock(&mut other, &clock, || clock.get());
+        assert_eq!(other.done.len(), 1);
+
+        // Later frames pick up where the task left off
+        scheduler.begin_frame(clock.get());
+        assert!(!scheduler.is_behind());
+        scheduler.run_with_clock(&mut task, &clock, || clock.get());
+        scheduler.begin_frame(clock.get());
+        assert_eq!(
+            scheduler.run_with_clock(&mut task, &clock, || clock.get()),
+            Step::Done
+        );
+        assert!(!scheduler.is_behind());
+        assert_eq!(task.done, (0..10).rev().collect::<Vec<_>>());
+    }
+}
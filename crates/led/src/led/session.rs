@@ -0,0 +1,266 @@
+use super::launch;
+use super::marks::FileMark;
+use super::notes::STATE_DIR;
+use super::types::Position;
+use anyhow::Result as AnyResult;
+use egui::{Vec2, ViewportBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the session inside [`STATE_DIR`].
+pub const SESSION_FILE: &str = "session.json";
+/// How many recent search queries are kept.
+pub const MAX_SEARCH_HISTORY: usize = 50;
+/// Command-line flag that starts the editor without reopening the files of the last session.
+pub const CLEAN_FLAG: &str = "--clean";
+
+/// Returns the path of the session file for a project.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn session_path(project_root: &Path) -> PathBuf {
+    project_root.join(STATE_DIR).join(SESSION_FILE)
+}
+
+/// Editor state that is carried over between runs in the same project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Marks set in files, restored when the files are opened again.
+    pub marks: Vec<FileMark>,
+    /// Where the main window was and how large, restored at startup.
+    pub window: Option<WindowGeometry>,
+    /// Directories files were recently opened from or saved to, offered by the file picker.
+    pub recent_dirs: Vec<PathBuf>,
+    /// Recent search queries, most recent first, offered by Up/Down in the find bar.
+    pub search_history: Vec<String>,
+    /// The files that were open, in the order of their tabs, reopened at startup.
+    pub open_files: Vec<OpenFile>,
+    /// Path of the file that was active, if it was one of the open files.
+    pub active_file: Option<String>,
+}
+
+/// A file that was open, with where the user was in it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenFile {
+    pub path: String,
+    /// Line and column of the caret.
+    pub cursor: Position,
+    /// How far the text was scrolled from its top left corner, in points.
+    pub scroll: [f32; 2],
+}
+
+/// Returns whether the command-line arguments ask to start without reopening the files of the
+/// last session.
+///
+/// # Arguments
+///
+/// * `args` - The command-line arguments, without the program name.
+pub fn starts_clean(args: impl IntoIterator<Item = String>) -> bool {
+    args.into_iter()
+        .take_while(|arg| arg != launch::END_OF_FLAGS)
+        .any(|arg| arg == CLEAN_FLAG)
+}
+
+/// Moves a search query to the front of the recent ones, keeping at most
+/// [`MAX_SEARCH_HISTORY`]. Empty queries are not kept.
+pub fn remember_search(history: &mut Vec<String>, query: &str) {
+    if query.is_empty() {
+        return;
+    }
+    history.retain(|known| known != query);
+    history.insert(0, query.to_string());
+    history.truncate(MAX_SEARCH_HISTORY);
+}
+
+/// The placement of the main window, in points.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// The outer top-left corner on the desktop, unless the platform keeps windows from
+    /// knowing it, as Wayland does.
+    pub position: Option<[f32; 2]>,
+    /// The size of the content area.
+    pub size: [f32; 2],
+    /// Whether the window was maximized. The position and size are those it had before.
+    pub maximized: bool,
+    /// The size of the monitor the window was on, which tells whether the monitors changed.
+    pub monitor: Option<[f32; 2]>,
+}
+
+impl WindowGeometry {
+    /// Places a window being built at this geometry.
+    pub fn viewport(&self, builder: ViewportBuilder) -> ViewportBuilder {
+        let builder = builder
+            .with_inner_size(self.size)
+            .with_maximized(self.maximized);
+        match self.position {
+            Some(position) => builder.with_position(position),
+            None => builder,
+        }
+    }
+
+    /// Returns the geometry moved and shrunk to fit on a monitor of the given size, or `None`
+    /// if the window was last on a monitor of that size and is left where the platform put it.
+    ///
+    /// Like the platform's own placement, this takes the monitor's top-left corner to be the
+    /// desktop origin, so a window that doesn't fit is centered on it.
+    pub fn fit_to(&self, monitor: Vec2) -> Option<Self> {
+        if self.monitor == Some(monitor.into()) || monitor.x < 1.0 || monitor.y < 1.0 {
+            return None;
+        }
+        let size = Vec2::from(self.size).min(monitor);
+        let position = self.position.map(|position| {
+            let position = Vec2::from(position);
+            let fits = position.x >= 0.0
+                && position.y >= 0.0
+                && position.x + size.x <= monitor.x
+                && position.y + size.y <= monitor.y;
+            if fits {
+                position.into()
+            } else {
+                ((monitor - size) / 2.0).into()
+            }
+        });
+        Some(Self {
+            position,
+            size: size.into(),
+            maximized: self.maximized,
+            monitor: Some(monitor.into()),
+        })
+    }
+}
+
+impl Session {
+    /// Loads the project's session, or an empty one if none was saved yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session file exists but cannot be read or parsed.
+    pub fn load(project_root: &Path) -> AnyResult<Self> {
+        match fs::read_to_string(session_path(project_root)) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the session to the project's `.led` directory, creating it as needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_root` - The root directory of the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save(&self, project_root: &Path) -> AnyResult<()> {
+        let path = session_path(project_root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("led-session-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_session_is_empty() {
+        let root = temp_project();
+        assert_eq!(Session::load(&root).unwrap(), Session::default());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn session_round_trips() {
+        let root = temp_project();
+        let session = Session {
+            marks: vec![FileMark {
+                name: 'A',
+                path: "/src/main.rs".to_string(),
+                position: Position { line: 3, column: 1 },
+            }],
+            window: Some(WindowGeometry {
+                position: Some([40.0, 30.0]),
+                size: [1000.0, 700.0],
+                maximized: true,
+                monitor: Some([1920.0, 1080.0]),
+            }),
+            recent_dirs: vec![PathBuf::from("/src")],
+            search_history: vec!["parse".to_string()],
+            open_files: vec![OpenFile {
+                path: "/src/lib.rs".to_string(),
+                cursor: Position {
+                    line: 12,
+                    column: 4,
+                },
+                scroll: [0.0, 180.5],
+            }],
+            active_file: Some("/src/lib.rs".to_string()),
+        };
+        session.save(&root).unwrap();
+        assert_eq!(Session::load(&root).unwrap(), session);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn recent_searches_come_first_without_duplicates() {
+        let mut history = Vec::new();
+        for query in ["a", "b", "", "a"] {
+            remember_search(&mut history, query);
+        }
+        assert_eq!(history, ["a", "b"]);
+        for i in 0..MAX_SEARCH_HISTORY {
+            remember_search(&mut history, &i.to_string());
+        }
+        assert_eq!(history.len(), MAX_SEARCH_HISTORY);
+        assert_eq!(history[0], (MAX_SEARCH_HISTORY - 1).to_string());
+    }
+
+    #[test]
+    fn clean_flag_is_found_among_other_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(starts_clean(args(&["--portable", CLEAN_FLAG])));
+        assert!(!starts_clean(args(&["--portable"])));
+        assert!(!starts_clean(args(&["--", CLEAN_FLAG])));
+    }
+
+    #[test]
+    fn window_is_fitted_to_a_changed_monitor() {
+        let window = WindowGeometry {
+            position: Some([2000.0, 100.0]),
+            size: [1600.0, 900.0],
+            maximized: false,
+            monitor: Some([2560.0, 1440.0]),
+        };
+        assert_eq!(window.fit_to(Vec2::new(2560.0, 1440.0)), None);
+
+        let fitted = window.fit_to(Vec2::new(1280.0, 800.0)).unwrap();
+        assert_eq!(fitted.size, [1280.0, 800.0]);
+        assert_eq!(fitted.position, Some([0.0, 0.0]));
+
+        let small = WindowGeometry {
+            position: Some([100.0, 50.0]),
+            size: [800.0, 600.0],
+            ..window
+        };
+        let fitted = small.fit_to(Vec2::new(1920.0, 1080.0)).unwrap();
+        assert_eq!(fitted.position, Some([100.0, 50.0]));
+        assert_eq!(fitted.size, [800.0, 600.0]);
+    }
+}
@@ -0,0 +1,755 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::{dirs, i18n};
+
+/// File name of the settings file, both in the user's configuration directory and in a
+/// project's `.led` directory.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Returns the path of the project settings file.
+///
+/// # Arguments
+///
+/// * `project_root` - The root directory of the project.
+pub fn project_settings_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(super::notes::STATE_DIR)
+        .join(SETTINGS_FILE)
+}
+
+/// Returns the path of the user's global settings file, in the configuration directory (see
+/// [`dirs::config_dir`]).
+pub fn global_settings_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(SETTINGS_FILE))
+}
+
+/// What the editor does once the last open buffer has been closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LastBufferClosed {
+    /// Show the welcome screen with shortcuts to create or open a file.
+    #[default]
+    WelcomeScreen,
+    /// Open a fresh, empty scratch buffer.
+    ScratchBuffer,
+    /// Quit the application (still subject to the quit confirmation).
+    Quit,
+}
+
+impl LastBufferClosed {
+    /// All variants, in the order they are presented to the user.
+    pub const ALL: [Self; 3] = [Self::WelcomeScreen, Self::ScratchBuffer, Self::Quit];
+
+    /// Returns a short human-readable label for the behavior.
+    pub fn label(self) -> String {
+        i18n::tr(match self {
+            Self::WelcomeScreen => "last-buffer-closed-welcome-screen",
+            Self::ScratchBuffer => "last-buffer-closed-scratch-buffer",
+            Self::Quit => "last-buffer-closed-quit",
+        })
+    }
+}
+
+/// What happens to a file's UTF-8 byte order mark when it is saved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BomOnSave {
+    /// Keep the byte order mark if the buffer has one, and don't add one otherwise.
+    #[default]
+    Preserve,
+    /// Always write a byte order mark.
+    Add,
+    /// Never write a byte order mark.
+    Remove,
+}
+
+impl BomOnSave {
+    /// All variants, in the order they are presented to the user.
+    pub const ALL: [Self; 3] = [Self::Preserve, Self::Add, Self::Remove];
+
+    /// Returns a short human-readable label for the behavior.
+    pub fn label(self) -> String {
+        i18n::tr(match self {
+            Self::Preserve => "bom-on-save-preserve",
+            Self::Add => "bom-on-save-add",
+            Self::Remove => "bom-on-save-remove",
+        })
+    }
+}
+
+/// Where the result of evaluating a selection goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvaluationResult {
+    /// Show it in a popup, leaving the buffer alone.
+    #[default]
+    Popup,
+    /// Insert it after the selection, as ` = result`.
+    Insert,
+}
+
+impl EvaluationResult {
+    /// All variants, in the order they are presented to the user.
+    pub const ALL: [Self; 2] = [Self::Popup, Self::Insert];
+
+    /// Returns a short human-readable label for the behavior.
+    pub fn label(self) -> String {
+        i18n::tr(match self {
+            Self::Popup => "evaluation-result-popup",
+            Self::Insert => "evaluation-result-insert",
+        })
+    }
+}
+
+/// Which releases the update checker offers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpdateChannel {
+    /// Only stable releases.
+    #[default]
+    Stable,
+    /// Nightly builds as well as stable releases, whichever is newer.
+    Nightly,
+}
+
+impl UpdateChannel {
+    /// All variants, in the order they are presented to the user.
+    pub const ALL: [Self; 2] = [Self::Stable, Self::Nightly];
+
+    /// Returns a short human-readable label for the channel.
+    pub fn label(self) -> String {
+        i18n::tr(match self {
+            Self::Stable => "update-channel-stable",
+            Self::Nightly => "update-channel-nightly",
+        })
+    }
+}
+
+/// User-configurable editor behavior.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Behavior when the last buffer is closed.
+    pub on_last_buffer_closed: LastBufferClosed,
+    /// Whether quitting asks for confirmation while there is unsaved work.
+    pub confirm_quit: bool,
+    /// Whether a floating toolbar with selection actions appears after selecting with the pointer.
+    pub selection_toolbar: bool,
+    /// Whether line, word and character counts follow the pointer while dragging a selection.
+    pub selection_counts: bool,
+    /// Byte order mark handling when saving.
+    pub bom_on_save: BomOnSave,
+    /// Whether saving appends a line break to files that don't end with one.
+    pub ensure_final_newline: bool,
+    /// Whether saving removes the spaces and tabs at the end of lines.
+    pub trim_trailing_whitespace: bool,
+    /// Whether opening and saving ask for paths in the system's dialogs rather than the
+    /// editor's file picker.
+    pub native_file_dialogs: bool,
+    /// Where the result of Evaluate Selection goes.
+    pub evaluation_result: EvaluationResult,
+    /// Format of inserted dates and of the `${date}` snippet placeholder.
+    pub date_format: String,
+    /// Format of inserted times and of the `${time}` snippet placeholder.
+    pub time_format: String,
+    /// Whether project files are indexed in the background for find-in-files and go-to-symbol.
+    pub project_index: bool,
+    /// The maximum total size of the files in the project index, in megabytes.
+    pub index_size_cap_mb: u64,
+    /// The maximum number of hits listed by find-in-files and go-to-symbol.
+    pub search_result_limit: u64,
+    /// Files larger than this are skipped by find-in-files, in kilobytes. Files over 1024 KB
+    /// are never indexed.
+    pub search_max_file_kb: u64,
+    /// Whether files excluded by `.gitignore` or `.ledignore` are shown dimmed instead of hidden.
+    pub show_ignored_files: bool,
+    /// Whether keystroke-to-paint latency is measured, logged and shown in the status bar.
+    pub track_latency: bool,
+    /// The shortest time between two recomputations of highlights while typing, in milliseconds.
+    pub highlight_delay_ms: u64,
+    /// How long after an edit suggestion providers are asked for a suggestion, in milliseconds.
+    pub suggestion_delay_ms: u64,
+    /// How long the notes stay modified before they are saved, in milliseconds.
+    pub autosave_delay_ms: u64,
+    /// Lines longer than this many bytes are soft-wrapped even when soft wrap is off; 0 never
+    /// wraps them.
+    pub long_line_wrap: u64,
+    /// How much of each frame incremental background work may take, in milliseconds.
+    pub frame_budget_ms: u64,
+    /// The memory caches, such as snapshot texts, conflict highlights and the project index,
+    /// may hold before those used least recently are dropped, in megabytes.
+    pub cache_ceiling_mb: u64,
+    /// How long a program run by a script may take before it is killed, in milliseconds.
+    pub process_timeout_ms: u64,
+    /// How much of a program's output is kept for the script that ran it, in kilobytes.
+    pub process_output_kb: u64,
+    /// Whether LED looks for a newer version at startup.
+    pub check_for_updates: bool,
+    /// Which releases the update checker offers.
+    pub update_channel: UpdateChannel,
+    /// Address of the release feed the update checker reads, which must be `https`; empty
+    /// disables checking.
+    pub update_feed_url: String,
+    /// Language of the user interface, such as `de`; empty follows the system language.
+    pub language: String,
+    /// Name of the color theme.
+    pub theme: String,
+    /// Formatter commands by file extension, such as `"rs": "rustfmt --edition 2024"`. A
+    /// formatter reads the text on standard input and writes it formatted to standard output;
+    /// `{file}` in the command stands for the file's path.
+    pub formatters: BTreeMap<String, String>,
+    /// Linter commands by file extension. A linter reads the text on standard input and reports
+    /// problems as `file:line:column: message` lines; `{file}` stands for the file's path.
+    pub linters: BTreeMap<String, String>,
+    /// Names of directories the project index skips, besides hidden ones, `target` and
+    /// `node_modules`.
+    pub excluded_dirs: Vec<String>,
+    /// Characters that indent the next line when Enter follows them, by language, such as
+    /// `"python": "{([:"`. Languages without an entry use the built-in ones.
+    pub indent_after: BTreeMap<String, String>,
+    /// Whether Tab and auto-indent insert tabs rather than spaces, in files whose indentation
+    /// can't be told from their content.
+    pub insert_tabs: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            on_last_buffer_closed: LastBufferClosed::default(),
+            confirm_quit: true,
+            selection_toolbar: true,
+            selection_counts: true,
+            bom_on_save: BomOnSave::default(),
+            ensure_final_newline: false,
+            trim_trailing_whitespace: false,
+            native_file_dialogs: false,
+            evaluation_result: EvaluationResult::default(),
+            date_format: "[year]-[month]-[day]".to_string(),
+            time_format: "[hour]:[minute]".to_string(),
+            project_index: false,
+            index_size_cap_mb: 64,
+            search_result_limit: 50,
+            search_max_file_kb: 1024,
+            show_ignored_files: false,
+            track_latency: false,
+            highlight_delay_ms: 100,
+            suggestion_delay_ms: 150,
+            autosave_delay_ms: 1000,
+            long_line_wrap: 0,
+            frame_budget_ms: 4,
+            cache_ceiling_mb: 128,
+            process_timeout_ms: 30_000,
+            process_output_kb: 1024,
+            check_for_updates: true,
+            update_channel: UpdateChannel::default(),
+            update_feed_url: String::new(),
+            language: String::new(),
+            theme: saran::style::DEFAULT_THEME.to_string(),
+            formatters: BTreeMap::new(),
+            linters: BTreeMap::new(),
+            excluded_dirs: Vec::new(),
+            indent_after: BTreeMap::new(),
+            insert_tabs: false,
+        }
+    }
+}
+
+/// The type and allowed values of a setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    /// A whole number within an inclusive range.
+    Integer {
+        min: u64,
+        max: u64,
+    },
+    /// One of a fixed set of names.
+    Choice(&'static [&'static str]),
+    /// Any string.
+    Text,
+    /// An object whose values are all strings.
+    Table,
+    /// An array of strings.
+    List,
+}
+
+impl Kind {
+    /// Describes the values the kind allows, for diagnostics.
+    pub fn expected(self) -> String {
+        match self {
+            Self::Bool => "true or false".to_string(),
+            Self::Integer { min, max } => format!("a whole number from {} to {}", min, max),
+            Self::Choice(names) => {
+                let quoted: Vec<String> = names.iter().map(|n| format!("\"{}\"", n)).collect();
+                format!("one of {}", quoted.join(", "))
+            }
+            Self::Text => "a string".to_string(),
+            Self::Table => "an object of strings".to_string(),
+            Self::List => "an array of strings".to_string(),
+        }
+    }
+
+    /// Returns whether a value is allowed.
+    pub fn accepts(self, value: &Value) -> bool {
+        match self {
+            Self::Bool => value.is_boolean(),
+            Self::Integer { min, max } => value.as_u64().is_some_and(|n| (min..=max).contains(&n)),
+            Self::Choice(names) => value.as_str().is_some_and(|s| names.contains(&s)),
+            Self::Text => value.is_string(),
+            Self::Table => value
+                .as_object()
+                .is_some_and(|map| map.values().all(Value::is_string)),
+            Self::List => value
+                .as_array()
+                .is_some_and(|list| list.iter().all(Value::is_string)),
+        }
+    }
+}
+
+/// A setting in the schema of the settings files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// The key the setting is stored under.
+    pub key: &'static str,
+    pub kind: Kind,
+    /// Former keys of the setting, still read but reported as deprecated.
+    pub aliases: &'static [&'static str],
+}
+
+const fn field(key: &'static str, kind: Kind) -> Field {
+    Field {
+        key,
+        kind,
+        aliases: &[],
+    }
+}
+
+/// The schema of [`Settings`]: every key a settings file may contain.
+pub const SCHEMA: &[Field] = &[
+    field(
+        "on_last_buffer_closed",
+        Kind::Choice(&["WelcomeScreen", "ScratchBuffer", "Quit"]),
+    ),
+    field("confirm_quit", Kind::Bool),
+    field("selection_toolbar", Kind::Bool),
+    field("selection_counts", Kind::Bool),
+    field("bom_on_save", Kind::Choice(&["Preserve", "Add", "Remove"])),
+    field("ensure_final_newline", Kind::Bool),
+    field("trim_trailing_whitespace", Kind::Bool),
+    field("native_file_dialogs", Kind::Bool),
+    field("evaluation_result", Kind::Choice(&["Popup", "Insert"])),
+    field("date_format", Kind::Text),
+    field("time_format", Kind::Text),
+    field("project_index", Kind::Bool),
+    field("index_size_cap_mb", Kind::Integer { min: 1, max: 4096 }),
+    field(
+        "search_result_limit",
+        Kind::Integer {
+            min: 1,
+            max: 10_000,
+        },
+    ),
+    field("search_max_file_kb", Kind::Integer { min: 1, max: 1024 }),
+    field("show_ignored_files", Kind::Bool),
+    field("track_latency", Kind::Bool),
+    field(
+        "highlight_delay_ms",
+        Kind::Integer {
+            min: 0,
+            max: 10_000,
+        },
+    ),
+    field(
+        "suggestion_delay_ms",
+        Kind::Integer {
+            min: 0,
+            max: 10_000,
+        },
+    ),
+    field(
+        "autosave_delay_ms",
+        Kind::Integer {
+            min: 0,
+            max: 10_000,
+        },
+    ),
+    field(
+        "long_line_wrap",
+        Kind::Integer {
+            min: 0,
+            max: 1_000_000_000,
+        },
+    ),
+    field("frame_budget_ms", Kind::Integer { min: 1, max: 16 }),
+    field("cache_ceiling_mb", Kind::Integer { min: 1, max: 4096 }),
+    field(
+        "process_timeout_ms",
+        Kind::Integer {
+            min: 100,
+            max: 600_000,
+        },
+    ),
+    field(
+        "process_output_kb",
+        Kind::Integer {
+            min: 1,
+            max: 65_536,
+        },
+    ),
+    field("check_for_updates", Kind::Bool),
+    field("update_channel", Kind::Choice(&["Stable", "Nightly"])),
+    field("update_feed_url", Kind::Text),
+    field("language", Kind::Text),
+    field("theme", Kind::Text),
+    field("formatters", Kind::Table),
+    field("linters", Kind::Table),
+    field("excluded_dirs", Kind::List),
+    field("indent_after", Kind::Table),
+    field("insert_tabs", Kind::Bool),
+];
+
+/// A problem found in a settings file, worded for the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The settings file.
+    pub file: PathBuf,
+    /// The offending key, or `None` for problems with the file as a whole.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: `{}`: {}", self.file.display(), key, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Names the JSON type of a value, for diagnostics.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_u64() => "a whole number",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Checks the settings of one file against a schema.
+///
+/// # Arguments
+///
+/// * `file` - The settings file, for diagnostics.
+/// * `content` - The file's content.
+/// * `schema` - The settings the file may contain.
+///
+/// # Returns
+///
+/// The valid settings, under their current keys, and diagnostics for everything else.
+/// Invalid values are left out, so they fall back to the previous layer.
+fn validate(file: &Path, content: &str, schema: &[Field]) -> (Map<String, Value>, Vec<Diagnostic>) {
+    let mut valid = Map::new();
+    let mut diagnostics = Vec::new();
+    let diagnostic = |key: Option<&str>, message: String| Diagnostic {
+        file: file.to_path_buf(),
+        key: key.map(str::to_string),
+        message,
+    };
+
+    let object = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(object)) => object,
+        Ok(other) => {
+            let message = format!(
+                "expected an object of settings, found {}",
+                type_name(&other)
+            );
+            diagnostics.push(diagnostic(None, message));
+            return (valid, diagnostics);
+        }
+        Err(e) => {
+            let message = format!(
+                "invalid JSON at line {}, column {}: {}",
+                e.line(),
+                e.column(),
+                e
+            );
+            diagnostics.push(diagnostic(None, message));
+            return (valid, diagnostics);
+        }
+    };
+
+    for (key, value) in object {
+        let Some(field) = schema
+            .iter()
+            .find(|f| f.key == key || f.aliases.contains(&key.as_str()))
+        else {
+            diagnostics.push(diagnostic(
+                Some(&key),
+                "unknown setting, ignored".to_string(),
+            ));
+            continue;
+        };
+        if field.key != key {
+            let message = format!("deprecated, use `{}` instead", field.key);
+            diagnostics.push(diagnostic(Some(&key), message));
+            // The current key wins when a file has both
+            if valid.contains_key(field.key) {
+                continue;
+            }
+        }
+        if !field.kind.accepts(&value) {
+            let found = match &value {
+                Value::Number(_) | Value::String(_) => format!("{} ({})", type_name(&value), value),
+                _ => type_name(&value).to_string(),
+            };
+            let message = format!(
+                "expected {}, found {}; using the default",
+                field.kind.expected(),
+                found
+            );
+            diagnostics.push(diagnostic(Some(&key), message));
+            continue;
+        }
+        valid.insert(field.key.to_string(), value);
+    }
+    (valid, diagnostics)
+}
+
+impl Settings {
+    /// Loads the settings from the global and project settings files.
+    ///
+    /// Project settings override global ones, which override `base`, which overrides the
+    /// defaults. Tables such as `formatters` are merged entry by entry, so a file only needs to
+    /// name the entries it changes. Missing files are skipped. Problems in a file never stop the
+    /// others from loading: every invalid value is reported and falls back to the previous
+    /// layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - Valid settings that replace the defaults, such as those for the detected
+    ///   project type.
+    /// * `files` - The settings files, from lowest to highest precedence.
+    ///
+    /// # Returns
+    ///
+    /// The settings and the problems found in the files.
+    pub fn load(base: Map<String, Value>, files: &[PathBuf]) -> (Self, Vec<Diagnostic>) {
+        let mut merged = base;
+        let mut diagnostics = Vec::new();
+        for path in files {
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    diagnostics.push(Diagnostic {
+                        file: path.clone(),
+                        key: None,
+                        message: format!("cannot be read: {}", e),
+                    });
+                    continue;
+                }
+            };
+            let (valid, found) = validate(path, &content, SCHEMA);
+            for (key, value) in valid {
+                match (merged.get_mut(&key), value) {
+                    (Some(Value::Object(table)), Value::Object(entries)) => table.extend(entries),
+                    (_, value) => {
+                        merged.insert(key, value);
+                    }
+                }
+            }
+            diagnostics.extend(found);
+        }
+        // Every value was checked against the schema, so this only fails if the schema and
+        // the struct disagree
+        let settings = serde_json::from_value(Value::Object(merged)).unwrap_or_else(|e| {
+            eprintln!("Settings schema is out of date: {}", e);
+            Self::default()
+        });
+        (settings, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings() {
+        let settings = Settings::default();
+        assert_eq!(
+            settings.on_last_buffer_closed,
+            LastBufferClosed::WelcomeScreen
+        );
+        assert!(settings.confirm_quit);
+        assert!(settings.selection_toolbar);
+        assert!(settings.selection_counts);
+        assert_eq!(settings.bom_on_save, BomOnSave::Preserve);
+        assert!(!settings.ensure_final_newline);
+        assert!(!settings.trim_trailing_whitespace);
+        assert!(!settings.native_file_dialogs);
+        assert!(!settings.insert_tabs);
+        assert!(!settings.project_index);
+        assert_eq!(settings.index_size_cap_mb, 64);
+        assert_eq!(settings.search_result_limit, 50);
+        assert_eq!(settings.search_max_file_kb, 1024);
+        assert!(!settings.show_ignored_files);
+        assert!(!settings.track_latency);
+        assert_eq!(settings.highlight_delay_ms, 100);
+        assert_eq!(settings.suggestion_delay_ms, 150);
+        assert_eq!(settings.autosave_delay_ms, 1000);
+        assert_eq!(settings.long_line_wrap, 0);
+        assert_eq!(settings.frame_budget_ms, 4);
+        assert_eq!(settings.cache_ceiling_mb, 128);
+        assert_eq!(settings.process_timeout_ms, 30_000);
+        assert_eq!(settings.process_output_kb, 1024);
+        assert!(settings.check_for_updates);
+        assert_eq!(settings.update_channel, UpdateChannel::Stable);
+        assert_eq!(settings.update_feed_url, "");
+        assert_eq!(settings.language, "");
+        assert_eq!(settings.theme, "dark");
+        assert!(settings.formatters.is_empty());
+        assert!(settings.linters.is_empty());
+        assert!(settings.excluded_dirs.is_empty());
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"on_last_buffer_closed":"Quit"}"#).unwrap();
+        assert_eq!(settings.on_last_buffer_closed, LastBufferClosed::Quit);
+        assert!(settings.confirm_quit);
+    }
+
+    #[test]
+    fn schema_covers_every_setting() {
+        let Value::Object(defaults) = serde_json::to_value(Settings::default()).unwrap() else {
+            panic!("settings serialize to an object");
+        };
+        let mut keys: Vec<&str> = defaults.keys().map(String::as_str).collect();
+        let mut schema: Vec<&str> = SCHEMA.iter().map(|f| f.key).collect();
+        keys.sort_unstable();
+        schema.sort_unstable();
+        assert_eq!(keys, schema);
+        for field in SCHEMA {
+            assert!(field.kind.accepts(&defaults[field.key]), "{}", field.key);
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_reported_and_skipped() {
+        let file = Path::new("settings.json");
+        let content = r#"{
+            "confirm_quit": "yes",
+            "index_size_cap_mb": 0,
+            "bom_on_save": "Strip",
+            "frame_budget_ms": 8,
+            "colour": "blue"
+        }"#;
+        let (valid, diagnostics) = validate(file, content, SCHEMA);
+        assert_eq!(valid.len(), 1);
+        assert_eq!(valid["frame_budget_ms"], 8);
+
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "settings.json: `bom_on_save`: expected one of \"Preserve\", \"Add\", \"Remove\", \
+                 found a string (\"Strip\"); using the default",
+                "settings.json: `colour`: unknown setting, ignored",
+                "settings.json: `confirm_quit`: expected true or false, found a string (\"yes\"); \
+                 using the default",
+                "settings.json: `index_size_cap_mb`: expected a whole number from 1 to 4096, found \
+                 a whole number (0); using the default",
+            ]
+        );
+    }
+
+    #[test]
+    fn deprecated_keys_still_apply() {
+        const RENAMED: &[Field] = &[Field {
+            key: "confirm_quit",
+            kind: Kind::Bool,
+            aliases: &["ask_before_quit"],
+        }];
+        let file = Path::new("settings.json");
+        let (valid, diagnostics) = validate(file, r#"{"ask_before_quit": false}"#, RENAMED);
+        assert_eq!(valid["confirm_quit"], false);
+        assert_eq!(
+            diagnostics[0].message,
+            "deprecated, use `confirm_quit` instead"
+        );
+
+        let (_, diagnostics) = validate(file, "{ not json", RENAMED);
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("invalid JSON at line 1, column 3")
+        );
+    }
+
+    #[test]
+    fn project_settings_override_global_ones() {
+        let dir = std::env::temp_dir().join(format!("led-settings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let global = dir.join("global.json");
+        let project = dir.join("project.json");
+        std::fs::write(
+            &global,
+            r#"{"confirm_quit": false, "index_size_cap_mb": 10}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &project,
+            r#"{"index_size_cap_mb": 20, "frame_budget_ms": 99}"#,
+        )
+        .unwrap();
+
+        let missing = dir.join("missing.json");
+        let (settings, diagnostics) = Settings::load(Map::new(), &[missing, global, project]);
+        assert!(!settings.confirm_quit);
+        assert_eq!(settings.index_size_cap_mb, 20);
+        assert_eq!(settings.frame_budget_ms, 4);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].key.as_deref(), Some("frame_budget_ms"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tables_are_merged_over_the_base() {
+        let dir = std::env::temp_dir().join(format!("led-settings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let project = dir.join("project.json");
+        std::fs::write(
+            &project,
+            r#"{"formatters": {"py": "ruff format -"}, "excluded_dirs": ["out"]}"#,
+        )
+        .unwrap();
+        let base = serde_json::json!({
+            "formatters": {"rs": "rustfmt", "py": "black -"},
+            "excluded_dirs": ["target"],
+        });
+        let Value::Object(base) = base else {
+            unreachable!()
+        };
+
+        let (settings, diagnostics) = Settings::load(base, &[project]);
+        assert!(diagnostics.is_empty());
+        assert_eq!(settings.formatters["rs"], "rustfmt");
+        assert_eq!(settings.formatters["py"], "ruff format -");
+        assert_eq!(settings.excluded_dirs, vec!["out".to_string()]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn labels_are_distinct() {
+        let labels: Vec<String> = LastBufferClosed::ALL.iter().map(|b| b.label()).collect();
+        assert_eq!(labels.len(), 3);
+        assert!(labels.windows(2).all(|w| w[0] != w[1]));
+    }
+}
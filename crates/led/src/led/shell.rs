@@ -0,0 +1,332 @@
+use anyhow::{Context as _, Result as AnyResult, anyhow};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// File name of a project's environment file, in the project root.
+pub const ENV_FILE: &str = ".env";
+/// How long the login shell may take to report its environment before it is given up on.
+const LOGIN_SHELL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The login shell's environment, captured once.
+static LOGIN_ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+/// Environment variables to give a spawned process.
+///
+/// An editor started from a desktop launcher inherits a minimal environment, without the
+/// `PATH` entries and variables set up in the user's shell profile. Tasks, terminals and
+/// formatters should see what they would see in a terminal, so every process is spawned with
+/// [`Environment::command`] from the environment of [`login_environment`], extended with the
+/// project's `.env` file by [`Environment::for_project`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    vars: BTreeMap<String, String>,
+}
+
+impl Environment {
+    /// Returns the environment LED itself was started with.
+    pub fn inherited() -> Self {
+        Self {
+            vars: std::env::vars().collect(),
+        }
+    }
+
+    /// Returns the value of a variable.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    /// Sets a variable, replacing any previous value.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.vars.insert(name.into(), value.into());
+    }
+
+    /// Returns the variables, sorted by name.
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns this environment extended with a project's `.env` file, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or has an invalid line.
+    pub fn for_project(&self, project_root: &Path) -> AnyResult<Self> {
+        let mut env = self.clone();
+        let path = project_root.join(ENV_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => env
+                .load_env_file(&content)
+                .with_context(|| format!("invalid environment file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(env)
+    }
+
+    /// Applies the assignments of a `.env` file.
+    ///
+    /// Each line is `NAME=value`, optionally preceded by `export`. Blank lines and lines
+    /// starting with `#` are ignored. Values may be quoted: single quotes keep the text as is,
+    /// double quotes understand `\n`, `\t` and backslash-escaped characters such as `\"` and
+    /// `\$`. Outside of single quotes, `$NAME` and `${NAME}` expand to variables set so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the line of the first invalid assignment. Assignments before it
+    /// are kept.
+    pub fn load_env_file(&mut self, content: &str) -> AnyResult<()> {
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected NAME=value", number + 1))?;
+            let name = name.trim();
+            let valid_name = !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name {
+                return Err(anyhow!(
+                    "line {}: invalid variable name `{}`",
+                    number + 1,
+                    name
+                ));
+            }
+            let value = self
+                .parse_value(value.trim())
+                .map_err(|e| anyhow!("line {}: {}", number + 1, e))?;
+            self.set(name, value);
+        }
+        Ok(())
+    }
+
+    /// Unquotes and expands the value of an assignment.
+    fn parse_value(&self, value: &str) -> Result<String, String> {
+        if let Some(rest) = value.strip_prefix('\'') {
+            let end = rest.find('\'').ok_or("unterminated single quote")?;
+            return Ok(rest[..end].to_string());
+        }
+        let Some(rest) = value.strip_prefix('"') else {
+            // Unquoted values end at a comment
+            let value = value.split(" #").next().unwrap_or_default().trim_end();
+            return Ok(self.expand(value));
+        };
+        // Escaped characters are taken literally, everything between them is expanded
+        let mut parsed = String::new();
+        let mut raw = String::new();
+        let mut chars = rest.chars();
+        loop {
+            match chars.next().ok_or("unterminated double quote")? {
+                '"' => break,
+                '\\' => {
+                    parsed.push_str(&self.expand(&std::mem::take(&mut raw)));
+                    match chars.next().ok_or("unterminated double quote")? {
+                        'n' => parsed.push('\n'),
+                        't' => parsed.push('\t'),
+                        c => parsed.push(c),
+                    }
+                }
+                c => raw.push(c),
+            }
+        }
+        parsed.push_str(&self.expand(&raw));
+        Ok(parsed)
+    }
+
+    /// Expands `$NAME` and `${NAME}` to the variables set so far, or to nothing if unset.
+    fn expand(&self, value: &str) -> String {
+        let mut expanded = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(i) = rest.find('$') {
+            expanded.push_str(&rest[..i]);
+            let after = &rest[i + 1..];
+            let (name, next) = match after.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) => (&braced[..end], &braced[end + 1..]),
+                    None => ("", after),
+                },
+                None => {
+                    let end = after
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            if name.is_empty() {
+                expanded.push('$');
+            } else {
+                expanded.push_str(self.get(name).unwrap_or_default());
+            }
+            rest = next;
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// Finds an executable the way a shell would, in the directories of this environment's
+    /// `PATH`.
+    pub fn which(&self, program: &str) -> Option<PathBuf> {
+        if program.contains(std::path::MAIN_SEPARATOR) || program.contains('/') {
+            let path = PathBuf::from(program);
+            return path.is_file().then_some(path);
+        }
+        let extensions: Vec<String> = if cfg!(windows) {
+            let pathext = self.get("PATHEXT").unwrap_or(".COM;.EXE;.BAT;.CMD");
+            std::iter::once(String::new())
+                .chain(pathext.split(';').map(str::to_string))
+                .collect()
+        } else {
+            vec![String::new()]
+        };
+        std::env::split_paths(self.get("PATH")?)
+            .flat_map(|dir| {
+                extensions
+                    .iter()
+                    .map(move |ext| dir.join(format!("{}{}", program, ext)))
+            })
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Creates a command that runs `program` with exactly this environment.
+    ///
+    /// The program is looked up in this environment's `PATH`, not LED's own.
+    pub fn command(&self, program: &str) -> Command {
+        let resolved = self
+            .which(program)
+            .unwrap_or_else(|| PathBuf::from(program));
+        let mut command = Command::new(resolved);
+        command.env_clear().envs(&self.vars);
+        command
+    }
+}
+
+/// Parses the output of `env -0`: `NAME=value` entries separated by NUL bytes.
+fn parse_env_output(output: &[u8]) -> BTreeMap<String, String> {
+    output
+        .split(|&b| b == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (name, value) = entry.split_once('=')?;
+            (!name.is_empty()).then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Asks the user's login shell for the environment its profile sets up.
+///
+/// # Errors
+///
+/// Returns an error if there is no `$SHELL`, or it fails or takes too long.
+fn capture_login_shell() -> AnyResult<Environment> {
+    let shell = std::env::var("SHELL").context("SHELL is not set")?;
+    let mut child = Command::new(&shell)
+        .args(["-l", "-c", "env -0"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to start {}", shell))?;
+
+    // A profile waiting for input must not hang the editor
+    let started = Instant::now();
+    while child.try_wait()?.is_none() {
+        if started.elapsed() > LOGIN_SHELL_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!(
+                "{} took longer than {:?}",
+                shell,
+                LOGIN_SHELL_TIMEOUT
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{} exited with {}", shell, output.status));
+    }
+    let vars = parse_env_output(&output.stdout);
+    if !vars.contains_key("PATH") {
+        return Err(anyhow!("{} reported no PATH", shell));
+    }
+    Ok(Environment { vars })
+}
+
+/// Returns the environment of the user's login shell, captured on the first call.
+///
+/// Falls back to the environment LED was started with if the shell cannot be asked, and
+/// always on Windows, where programs inherit the user's environment already. Call this early,
+/// on a background thread, so that the first spawned process doesn't wait for the shell.
+pub fn login_environment() -> &'static Environment {
+    LOGIN_ENVIRONMENT.get_or_init(|| {
+        if cfg!(windows) {
+            return Environment::inherited();
+        }
+        capture_login_shell().unwrap_or_else(|e| {
+            eprintln!("Failed to read the login shell environment: {:#}", e);
+            Environment::inherited()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_files_support_quotes_comments_and_expansion() {
+        let mut env = Environment::default();
+        env.set("HOME", "/home/me");
+        env.load_env_file(
+            r#"
+            # Build settings
+            export CARGO_HOME=$HOME/.cargo
+            TARGET=${CARGO_HOME}/target # where builds go
+            GREETING="Hello\n\"world\" from $HOME"
+            LITERAL='$HOME stays'
+            PRICE="\$5"
+            EMPTY=
+            "#,
+        )
+        .unwrap();
+        assert_eq!(env.get("CARGO_HOME"), Some("/home/me/.cargo"));
+        assert_eq!(env.get("TARGET"), Some("/home/me/.cargo/target"));
+        assert_eq!(env.get("GREETING"), Some("Hello\n\"world\" from /home/me"));
+        assert_eq!(env.get("LITERAL"), Some("$HOME stays"));
+        assert_eq!(env.get("PRICE"), Some("$5"));
+        assert_eq!(env.get("EMPTY"), Some(""));
+
+        let error = env.load_env_file("OK=1\n1BAD=2").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: invalid variable name `1BAD`");
+        assert!(env.load_env_file("QUOTE=\"open").is_err());
+    }
+
+    #[test]
+    fn env_output_is_split_on_nul() {
+        let vars = parse_env_output(b"PATH=/bin:/usr/bin\0MULTI=a\nb=c\0\0");
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars["PATH"], "/bin:/usr/bin");
+        assert_eq!(vars["MULTI"], "a\nb=c");
+    }
+
+    #[test]
+    fn project_env_file_extends_the_environment() {
+        let dir = std::env::temp_dir().join(format!("led-shell-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut base = Environment::default();
+        base.set("A", "1");
+        assert_eq!(base.for_project(&dir).unwrap(), base);
+
+        std::fs::write(dir.join(ENV_FILE), "B=$A$A\n").unwrap();
+        let env = base.for_project(&dir).unwrap();
+        assert_eq!(env.get("B"), Some("11"));
+        assert_eq!(base.get("B"), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Result as AnyResult, anyhow};
+use std::sync::OnceLock;
+use time::{OffsetDateTime, UtcOffset, format_description};
+
+/// The placeholder marking where the caret goes once a snippet is inserted.
+pub const CURSOR: &str = "cursor";
+
+/// A piece of a snippet's body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part<'a> {
+    /// Text inserted as written.
+    Text(&'a str),
+    /// A `${name}` placeholder, filled in when the snippet is inserted.
+    Placeholder(&'a str),
+}
+
+/// Splits a snippet's body into text and `${name}` placeholders. `$$` stands for a literal
+/// `$`, as does a `$` not followed by `{`.
+///
+/// # Errors
+///
+/// Returns an error if a placeholder is not closed or has no name.
+pub fn parse(body: &str) -> AnyResult<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while let Some(dollar) = rest.find('$') {
+        if dollar > 0 {
+            parts.push(Part::Text(&rest[..dollar]));
+        }
+        let after = &rest[dollar + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            parts.push(Part::Text("$"));
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("placeholder `${{{}` is not closed", after))?;
+            let name = after[..end].trim();
+            if name.is_empty() {
+                return Err(anyhow!("placeholder without a name"));
+            }
+            parts.push(Part::Placeholder(name));
+            rest = &after[end + 1..];
+        } else {
+            parts.push(Part::Text("$"));
+            rest = after;
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    Ok(parts)
+}
+
+/// A snippet with its placeholders filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    /// The text to insert.
+    pub text: String,
+    /// Where the caret goes, as a byte offset into `text`, if the snippet says.
+    pub cursor: Option<usize>,
+}
+
+/// Fills in the placeholders of a snippet.
+///
+/// # Arguments
+///
+/// * `body` - The snippet's body.
+/// * `resolve` - Computes the value of a placeholder by name. It is called for every
+///   placeholder but [`CURSOR`], in order, so placeholders are evaluated at expansion time.
+///
+/// # Errors
+///
+/// Returns an error if the body does not parse or `resolve` fails.
+pub fn expand(
+    body: &str,
+    mut resolve: impl FnMut(&str) -> AnyResult<String>,
+) -> AnyResult<Expansion> {
+    let mut expansion = Expansion {
+        text: String::new(),
+        cursor: None,
+    };
+    for part in parse(body)? {
+        match part {
+            Part::Text(text) => expansion.text.push_str(text),
+            Part::Placeholder(CURSOR) => {
+                expansion.cursor.get_or_insert(expansion.text.len());
+            }
+            Part::Placeholder(name) => {
+                let value = resolve(name)
+                    .map_err(|e| e.context(format!("placeholder `{}` failed", name)))?;
+                expansion.text.push_str(&value);
+            }
+        }
+    }
+    Ok(expansion)
+}
+
+/// What the built-in placeholders are filled in from.
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    /// Path of the buffer's file, if it has one.
+    pub file_path: Option<&'a str>,
+    /// The selected text, empty if nothing is selected.
+    pub selection: &'a str,
+    /// The time the snippet is inserted at.
+    pub now: OffsetDateTime,
+    /// Format of `${date}`, see [`format`].
+    pub date_format: &'a str,
+    /// Format of `${time}`, see [`format`].
+    pub time_format: &'a str,
+}
+
+/// Returns the value of a built-in placeholder: `date`, `time`, `file_name`, `file_path`,
+/// `selection` or `uuid`. The file placeholders are empty for a buffer without a file.
+///
+/// # Returns
+///
+/// The value, or `None` if there is no built-in placeholder of that name.
+///
+/// # Errors
+///
+/// Returns an error if the date or time format is invalid.
+pub fn builtin(name: &str, context: &Context) -> AnyResult<Option<String>> {
+    let file_path = context.file_path.unwrap_or_default();
+    Ok(Some(match name {
+        "date" => format(context.now, context.date_format)?,
+        "time" => format(context.now, context.time_format)?,
+        "file_path" => file_path.to_string(),
+        "file_name" => std::path::Path::new(file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        "selection" => context.selection.to_string(),
+        "uuid" => uuid::Uuid::new_v4().to_string(),
+        _ => return Ok(None),
+    }))
+}
+
+/// Formats a point in time, such as `[year]-[month]-[day] [hour]:[minute]`.
+///
+/// Components are written in square brackets, with optional modifiers, as in
+/// `[month repr:short]` or `[hour repr:12] [period]`; see the `time` crate's format
+/// description syntax.
+///
+/// # Errors
+///
+/// Returns an error if the format is invalid.
+pub fn format(at: OffsetDateTime, format: &str) -> AnyResult<String> {
+    let description = format_description::parse(format)
+        .map_err(|e| anyhow!("invalid date format `{}`: {}", format, e))?;
+    Ok(at.format(&description)?)
+}
+
+/// Returns the local time.
+pub fn now() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(local_offset())
+}
+
+/// Returns the local time zone's offset from UTC, or UTC if it cannot be determined.
+///
+/// Some platforms only reveal the offset while the process has a single thread, so it is
+/// looked up once and remembered; call this early at startup.
+pub fn local_offset() -> UtcOffset {
+    static OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+    *OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_placeholders() {
+        assert_eq!(
+            parse("a ${ name } costs $$5, $x").unwrap(),
+            [
+                Part::Text("a "),
+                Part::Placeholder("name"),
+                Part::Text(" costs "),
+                Part::Text("$"),
+                Part::Text("5, "),
+                Part::Text("$"),
+                Part::Text("x"),
+            ]
+        );
+        assert!(parse("${open").is_err());
+        assert!(parse("${}").is_err());
+    }
+
+    #[test]
+    fn expands_placeholders_and_the_cursor() {
+        let mut asked = Vec::new();
+        let expansion = expand("fn ${name}() {\n    ${cursor}\n}${name}", |name| {
+            asked.push(name.to_string());
+            Ok(name.to_uppercase())
+        })
+        .unwrap();
+        assert_eq!(expansion.text, "fn NAME() {\n    \n}NAME");
+        assert_eq!(expansion.cursor, Some(16));
+        assert_eq!(asked, ["name", "name"]);
+
+        let failed = expand("${branch}", |_| Err(anyhow!("not a repository")));
+        assert!(format!("{:#}", failed.unwrap_err()).contains("`branch` failed"));
+    }
+
+    #[test]
+    fn builtins_fill_in_dates_and_files() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000)
+            .unwrap()
+            .to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        let context = Context {
+            file_path: Some("src/main.rs"),
+            selection: "sel",
+            now,
+            date_format: "[year]-[month]-[day]",
+            time_format: "[hour]:[minute]",
+        };
+        let value = |name| builtin(name, &context).unwrap();
+        assert_eq!(value("date").as_deref(), Some("2023-11-15"));
+        assert_eq!(value("time").as_deref(), Some("00:13"));
+        assert_eq!(value("file_name").as_deref(), Some("main.rs"));
+        assert_eq!(value("selection").as_deref(), Some("sel"));
+        assert_eq!(value("uuid").map(|uuid| uuid.len()), Some(36));
+        assert_eq!(value("branch"), None);
+
+        let unsaved = Context {
+            file_path: None,
+            date_format: "[nonsense]",
+            ..context
+        };
+        assert_eq!(builtin("file_name", &unsaved).unwrap().as_deref(), Some(""));
+        assert!(builtin("date", &unsaved).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use super::commands::editor::Command;
+use super::types::Position;
+use super::types::buffer::ID;
+
+/// The pairs a selection can be surrounded with, as opening and closing characters.
+pub const PAIRS: &[(char, char)] = &[
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
+/// Returns the pair a character opens or closes, if any.
+pub fn pair_for(c: char) -> Option<(char, char)> {
+    PAIRS
+        .iter()
+        .copied()
+        .find(|&(open, close)| c == open || c == close)
+}
+
+/// A pair of characters around a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Enclosing {
+    /// Byte offset of the opening character.
+    pub open: usize,
+    /// Byte offset of the closing character.
+    pub close: usize,
+    /// The opening and closing characters.
+    pub pair: (char, char),
+}
+
+/// Finds the innermost pair around an offset.
+///
+/// Brackets may span lines and nest; quotes are paired up from the start of the offset's line,
+/// skipping quotes escaped with a backslash. An offset directly before the closing character
+/// is inside the pair, one directly before the opening character is not.
+///
+/// # Arguments
+///
+/// * `text` - The full text of the buffer.
+/// * `offset` - The byte offset, usually the caret's.
+pub fn enclosing(text: &str, offset: usize) -> Option<Enclosing> {
+    PAIRS
+        .iter()
+        .filter_map(|&pair| {
+            let (open, close) = if pair.0 == pair.1 {
+                quotes_around(text, offset, pair.0)?
+            } else {
+                brackets_around(text, offset, pair)?
+            };
+            Some(Enclosing { open, close, pair })
+        })
+        .max_by_key(|enclosing| enclosing.open)
+}
+
+fn brackets_around(
+    text: &str,
+    offset: usize,
+    (open, close): (char, char),
+) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    let start = text[..offset].char_indices().rev().find_map(|(i, c)| {
+        if c == close {
+            depth += 1;
+        } else if c == open {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+        None
+    })?;
+    let mut depth = 0;
+    let end = text[offset..].char_indices().find_map(|(i, c)| {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(offset + i);
+            }
+            depth -= 1;
+        }
+        None
+    })?;
+    Some((start, end))
+}
+
+fn quotes_around(text: &str, offset: usize, quote: char) -> Option<(usize, usize)> {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    let line = &text[line_start..line_end];
+    let quotes: Vec<usize> = line
+        .char_indices()
+        .filter(|&(i, c)| c == quote && !line[..i].ends_with('\\'))
+        .map(|(i, _)| line_start + i)
+        .collect();
+    quotes
+        .chunks_exact(2)
+        .find(|pair| pair[0] < offset && offset <= pair[1])
+        .map(|pair| (pair[0], pair[1]))
+}
+
+/// Builds the commands that surround a range of text with a pair.
+///
+/// # Arguments
+///
+/// * `buffer_id` - The buffer the commands apply to.
+/// * `range` - The byte range to surround.
+/// * `pair` - The opening and closing characters.
+///
+/// # Returns
+///
+/// The edit commands, ordered from the end of the buffer towards the start so that each
+/// command's offsets remain valid after the previous ones have been applied.
+pub fn wrap(
+    buffer_id: ID,
+    range: std::ops::Range<usize>,
+    (open, close): (char, char),
+) -> Vec<Command> {
+    vec![
+        Command::InsertText {
+            buffer_id,
+            offset: range.end,
+            text: close.to_string(),
+        },
+        Command::InsertText {
+            buffer_id,
+            offset: range.start,
+            text: open.to_string(),
+        },
+    ]
+}
+
+/// Returns where a position ends up after [`wrap`] surrounded the text from `start`, if it was
+/// within or at the end of the surrounded text.
+pub fn wrapped_position(position: Position, start: Position, open: char) -> Position {
+    if position.line == start.line {
+        Position {
+            line: position.line,
+            column: position.column + open.len_utf8(),
+        }
+    } else {
+        position
+    }
+}
+
+/// Builds the commands that replace an enclosing pair with another.
+///
+/// # Returns
+///
+/// The edit commands, ordered from the end of the buffer towards the start.
+pub fn change(buffer_id: ID, enclosing: &Enclosing, (open, close): (char, char)) -> Vec<Command> {
+    let mut commands = Vec::new();
+    for (offset, old, new) in [
+        (enclosing.close, enclosing.pair.1, close),
+        (enclosing.open, enclosing.pair.0, open),
+    ] {
+        commands.push(Command::DeleteText {
+            buffer_id,
+            start: offset,
+            length: old.len_utf8(),
+        });
+        commands.push(Command::InsertText {
+            buffer_id,
+            offset,
+            text: new.to_string(),
+        });
+    }
+    commands
+}
+
+/// Builds the commands that remove an enclosing pair, keeping the text inside it.
+///
+/// # Returns
+///
+/// The edit commands, ordered from the end of the buffer towards the start.
+pub fn delete(buffer_id: ID, enclosing: &Enclosing) -> Vec<Command> {
+    vec![
+        Command::DeleteText {
+            buffer_id,
+            start: enclosing.close,
+            length: enclosing.pair.1.len_utf8(),
+        },
+        Command::DeleteText {
+            buffer_id,
+            start: enclosing.open,
+            length: enclosing.pair.0.len_utf8(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::buffer::editor::State;
+
+    fn apply(text: &str, commands: impl FnOnce(ID) -> Vec<Command>) -> String {
+        let mut state = State::new();
+        let id = state.create_buffer(text.to_string());
+        state.execute_command(Command::Batch(commands(id))).unwrap();
+        state.get_buffer_text(id).unwrap()
+    }
+
+    #[test]
+    fn wraps_a_range() {
+        assert_eq!(apply("a bc d", |id| wrap(id, 2..4, ('(', ')'))), "a (bc) d");
+        assert_eq!(pair_for(']'), Some(('[', ']')));
+        assert_eq!(pair_for('x'), None);
+
+        let start = Position { line: 1, column: 2 };
+        let end = Position { line: 1, column: 4 };
+        assert_eq!(
+            wrapped_position(end, start, '{'),
+            Position { line: 1, column: 5 }
+        );
+        let end = Position { line: 2, column: 4 };
+        assert_eq!(wrapped_position(end, start, '{'), end);
+    }
+
+    #[test]
+    fn finds_the_innermost_pair() {
+        let text = "f(a, [b, \"c\"], (d))";
+        let at = |s: &str| text.find(s).unwrap();
+        let inner = enclosing(text, at("c")).unwrap();
+        assert_eq!((inner.open, inner.close, inner.pair.0), (9, 11, '"'));
+        let list = enclosing(text, at("b")).unwrap();
+        assert_eq!((list.open, list.close), (5, 12));
+        // Nested brackets of the same kind are skipped over
+        let call = enclosing(text, at(", (")).unwrap();
+        assert_eq!((call.open, call.close), (1, 18));
+        assert_eq!(enclosing(text, 0), None);
+
+        // Quotes pair up within a line
+        assert_eq!(enclosing("'a' b 'c'", 5), None);
+        assert_eq!(enclosing("\"a\\\"b\"", 2).map(|e| e.close), Some(5));
+        assert_eq!(enclosing("(\na\n)", 2).map(|e| e.close), Some(4));
+    }
+
+    #[test]
+    fn changes_and_deletes_pairs() {
+        let text = "x = [1, 2];";
+        let found = enclosing(text, 6).unwrap();
+        assert_eq!(
+            apply(text, |id| change(id, &found, ('(', ')'))),
+            "x = (1, 2);"
+        );
+        assert_eq!(apply(text, |id| delete(id, &found)), "x = 1, 2;");
+    }
+}
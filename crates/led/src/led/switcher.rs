@@ -0,0 +1,95 @@
+use super::palette;
+
+/// An open buffer listed by the buffer switcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item<T> {
+    pub id: T,
+    /// The buffer's display name, e.g. its file name.
+    pub name: String,
+    /// The buffer's file, if it has one.
+    pub path: Option<String>,
+}
+
+/// Moves a buffer to the front of the most recently used ones.
+pub fn touch<T: PartialEq>(recent: &mut Vec<T>, id: T) {
+    recent.retain(|known| *known != id);
+    recent.insert(0, id);
+}
+
+/// Orders open buffers by when they were last active, most recent first. Buffers that were
+/// never active follow in their given order.
+///
+/// # Arguments
+///
+/// * `open` - The open buffers.
+/// * `recent` - Buffers by recent use, as kept by [`touch`]; closed ones are skipped.
+pub fn by_recent_use<T: Copy + PartialEq>(open: &[T], recent: &[T]) -> Vec<T> {
+    let mut ordered: Vec<T> = recent
+        .iter()
+        .filter(|id| open.contains(id))
+        .copied()
+        .collect();
+    ordered.extend(open.iter().filter(|id| !recent.contains(id)));
+    ordered
+}
+
+/// Returns the items whose name or path fuzzy-matches `query`, best match first. Equal
+/// matches keep their order, so an empty query lists every item as given.
+///
+/// # Arguments
+///
+/// * `items` - The open buffers, most recently used first.
+/// * `query` - What the user typed; see [`palette::score`] for how it matches.
+pub fn filter<'a, T>(items: &'a [Item<T>], query: &str) -> Vec<&'a Item<T>> {
+    let mut matches: Vec<(i32, &Item<T>)> = items
+        .iter()
+        .filter_map(|item| {
+            let path = item
+                .path
+                .as_deref()
+                .and_then(|path| palette::score(query, path));
+            Some((palette::score(query, &item.name).max(path)?, item))
+        })
+        .collect();
+    matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+    matches.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u32, name: &str, path: Option<&str>) -> Item<u32> {
+        Item {
+            id,
+            name: name.to_string(),
+            path: path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn buffers_are_ordered_by_recent_use() {
+        let mut recent = Vec::new();
+        for id in [1, 2, 3, 1] {
+            touch(&mut recent, id);
+        }
+        assert_eq!(recent, [1, 3, 2]);
+        assert_eq!(by_recent_use(&[2, 4, 1], &recent), [1, 2, 4]);
+    }
+
+    #[test]
+    fn filters_by_name_or_path_keeping_recent_order_for_ties() {
+        let items = [
+            item(1, "main.rs", Some("/p/src/main.rs")),
+            item(2, "Untitled", None),
+            item(3, "lib.rs", Some("/p/src/lib.rs")),
+        ];
+        let ids = |query: &str| -> Vec<u32> {
+            filter(&items, query).iter().map(|item| item.id).collect()
+        };
+        assert_eq!(ids(""), [1, 2, 3]);
+        assert_eq!(ids("lib"), [3]);
+        assert_eq!(ids("src"), [1, 3]);
+        assert!(ids("zzz").is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+/// Brings the order of the tab bar up to date with the open buffers.
+///
+/// Closed buffers lose their tab, and buffers opened since get one at the end, oldest first.
+/// The tabs of buffers that stay open keep their order.
+///
+/// # Arguments
+///
+/// * `order` - The buffers in the order of their tabs.
+/// * `open` - The open buffers, each with when it was opened.
+pub fn sync<T: Copy + PartialEq, K: Ord + Copy>(order: &mut Vec<T>, open: &[(T, K)]) {
+    order.retain(|tab| open.iter().any(|(id, _)| id == tab));
+    let mut opened: Vec<(T, K)> = open
+        .iter()
+        .filter(|(id, _)| !order.contains(id))
+        .copied()
+        .collect();
+    opened.sort_by_key(|&(_, created)| created);
+    order.extend(opened.into_iter().map(|(id, _)| id));
+}
+
+/// Moves a tab to the place of another, shifting the tabs in between toward where it was.
+///
+/// # Arguments
+///
+/// * `order` - The buffers in the order of their tabs.
+/// * `tab` - The buffer whose tab moves.
+/// * `onto` - The buffer whose tab it is dropped onto.
+pub fn move_tab<T: PartialEq>(order: &mut Vec<T>, tab: T, onto: T) {
+    let (Some(from), Some(to)) = (
+        order.iter().position(|id| *id == tab),
+        order.iter().position(|id| *id == onto),
+    ) else {
+        return;
+    };
+    let moved = order.remove(from);
+    order.insert(to, moved);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_buffers_drop_out_and_opened_ones_are_appended() {
+        let mut order = vec![3, 1, 2];
+        sync(&mut order, &[(1, 10), (2, 20), (5, 50), (4, 40)]);
+        assert_eq!(order, [1, 2, 4, 5]);
+        sync(&mut order, &[(4, 40), (2, 20), (1, 10), (5, 50)]);
+        assert_eq!(order, [1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn tabs_move_onto_the_tab_they_are_dropped_on() {
+        let mut order = vec!['a', 'b', 'c', 'd'];
+        move_tab(&mut order, 'a', 'c');
+        assert_eq!(order, ['b', 'c', 'a', 'd']);
+        move_tab(&mut order, 'd', 'b');
+        assert_eq!(order, ['d', 'b', 'c', 'a']);
+        move_tab(&mut order, 'x', 'b');
+        assert_eq!(order, ['d', 'b', 'c', 'a']);
+    }
+}
@@ -0,0 +1,124 @@
+/// Returns how many cells a tab takes when it starts at a cell: up to the next multiple of the
+/// tab size.
+pub fn tab_width(cell: usize, tab_size: usize) -> usize {
+    let tab_size = tab_size.max(1);
+    tab_size - cell % tab_size
+}
+
+/// Where each character of a row of text is drawn, counting tabs to the next tab stop.
+///
+/// Columns are byte offsets within the row, as in [`super::types::Position`]; cells are
+/// character widths from the row's left edge. Every character other than a tab takes one cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cells {
+    /// The column of each character, and the length of the row.
+    columns: Vec<usize>,
+    /// The cell each character starts at, and the width of the row.
+    cells: Vec<usize>,
+}
+
+impl Cells {
+    /// Lays out a row of text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The row's text.
+    /// * `tab_size` - The distance between tab stops, in cells.
+    pub fn new(text: &str, tab_size: usize) -> Self {
+        let mut columns = Vec::with_capacity(text.len() + 1);
+        let mut cells = Vec::with_capacity(text.len() + 1);
+        let mut cell = 0;
+        for (column, c) in text.char_indices() {
+            columns.push(column);
+            cells.push(cell);
+            cell += if c == '\t' {
+                tab_width(cell, tab_size)
+            } else {
+                1
+            };
+        }
+        columns.push(text.len());
+        cells.push(cell);
+        Self { columns, cells }
+    }
+
+    /// Returns the cell the character at a column starts at, or the row's width at its end.
+    pub fn cell(&self, column: usize) -> usize {
+        let index = self.columns.partition_point(|c| *c < column);
+        self.cells[index.min(self.cells.len() - 1)]
+    }
+
+    /// Returns the width of the row, in cells.
+    pub fn width(&self) -> usize {
+        self.cells.last().copied().unwrap_or(0)
+    }
+
+    /// Returns the columns a caret can be at: the start of each character, and the end.
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Returns the columns of the characters that are at least partly within a range of cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The cells, counted from the row's left edge.
+    pub fn columns_within(&self, cells: std::ops::Range<usize>) -> std::ops::Range<usize> {
+        let characters = self.columns.len() - 1;
+        // The first character ending after the range starts, which may be a tab reaching into it
+        let first = self.cells[1..].partition_point(|end| *end <= cells.start);
+        let last = self.cells[..characters].partition_point(|start| *start < cells.end);
+        self.columns[first]..self.columns[last.max(first)]
+    }
+
+    /// Returns the column of the character boundary nearest to a horizontal position.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The position, in cells from the row's left edge.
+    pub fn column_at(&self, x: f32) -> usize {
+        let index = (0..self.cells.len())
+            .min_by(|a, b| {
+                let distance = |i: &usize| (self.cells[*i] as f32 - x).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(0);
+        self.columns[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_extend_to_the_next_tab_stop() {
+        assert_eq!(tab_width(0, 4), 4);
+        assert_eq!(tab_width(3, 4), 1);
+        assert_eq!(tab_width(4, 4), 4);
+        assert_eq!(tab_width(5, 0), 1);
+
+        let cells = Cells::new("\tab\tc", 4);
+        let starts: Vec<usize> = [0, 1, 2, 3, 4, 5].iter().map(|&c| cells.cell(c)).collect();
+        assert_eq!(starts, [0, 4, 5, 6, 8, 9]);
+        assert_eq!(cells.width(), 9);
+
+        // Clicks land on the nearer side of a tab
+        assert_eq!(cells.column_at(1.5), 0);
+        assert_eq!(cells.column_at(2.5), 1);
+        assert_eq!(cells.column_at(6.9), 3);
+        assert_eq!(cells.column_at(7.1), 4);
+        assert_eq!(cells.column_at(20.0), 5);
+
+        // A tab that reaches into the range is included
+        let cells = Cells::new("\tab\tc", 4);
+        assert_eq!(cells.columns_within(2..5), 0..2);
+        assert_eq!(cells.columns_within(7..20), 3..5);
+        assert_eq!(cells.columns_within(20..30), 5..5);
+
+        // Multi-byte characters take one cell
+        let cells = Cells::new("é\tx", 4);
+        assert_eq!(cells.columns(), [0, 2, 3, 4]);
+        assert_eq!(cells.cell(3), 4);
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{Context as _, Result as AnyResult};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::buffer::{ID, editor::State};
+use super::editor_view::EditorView;
+
+/// Environment variable that makes [`assert_golden`] write the actual output to the golden
+/// file instead of comparing with it, to create or update golden files.
+pub const UPDATE_GOLDEN_VAR: &str = "LED_UPDATE_GOLDEN";
+
+/// A clock that only moves when told to, so that logic depending on time, such as cursor
+/// blinking, debouncing and animations, can be tested without sleeping.
+///
+/// Clones share their time, so one can be handed to the code under test while the test
+/// advances another.
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    start: Instant,
+    now: Rc<Cell<Instant>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        let start = Instant::now();
+        Self {
+            start,
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    /// Returns the clock's current time.
+    pub fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    /// Returns how far the clock moved since it was created, which a [`Harness`] passes to
+    /// egui as the input time.
+    pub fn elapsed(&self) -> Duration {
+        self.now.get() - self.start
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An input event and when it arrived, counted from the start of its recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub at_ms: u64,
+    pub event: egui::Event,
+}
+
+/// The input events of a session, saved as JSON to be replayed in tests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// The events in the order they arrived.
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /// Reads a recording from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a recording.
+    pub fn load(path: &Path) -> AnyResult<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// Writes the recording to a file, creating its directory as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save(&self, path: &Path) -> AnyResult<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Gathers the input events of a running session into a [`Recording`].
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    started: Instant,
+    recording: Recording,
+}
+
+impl Recorder {
+    /// Starts a recording at `now`.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            recording: Recording::default(),
+        }
+    }
+
+    /// Records the events of a frame that started at `now`.
+    ///
+    /// Only what the user did is kept: keys, text, the clipboard, the pointer and scrolling.
+    /// Events the platform reports about the window, such as focus changes and screenshots,
+    /// are left out.
+    pub fn record(&mut self, now: Instant, events: &[egui::Event]) {
+        let at_ms = now.saturating_duration_since(self.started).as_millis() as u64;
+        self.recording
+            .events
+            .extend(
+                events
+                    .iter()
+                    .filter(|event| is_user_input(event))
+                    .map(|event| RecordedEvent {
+                        at_ms,
+                        event: event.clone(),
+                    }),
+            );
+    }
+
+    /// Returns how many events were recorded.
+    pub fn len(&self) -> usize {
+        self.recording.events.len()
+    }
+
+    /// Returns whether no events were recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.recording.events.is_empty()
+    }
+
+    /// Ends the recording.
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Returns whether an event is something the user did, as opposed to news about the window.
+fn is_user_input(event: &egui::Event) -> bool {
+    matches!(
+        event,
+        egui::Event::Copy
+            | egui::Event::Cut
+            | egui::Event::Paste(_)
+            | egui::Event::Text(_)
+            | egui::Event::Key { .. }
+            | egui::Event::PointerMoved(_)
+            | egui::Event::PointerButton { .. }
+            | egui::Event::PointerGone
+            | egui::Event::Zoom(_)
+            | egui::Event::Ime(_)
+            | egui::Event::MouseWheel { .. }
+    )
+}
+
+/// Shows an [`EditorView`] without a window, one frame at a time, with its time taken from
+/// a [`FakeClock`], so that input can be replayed on it deterministically.
+pub struct Harness {
+    pub ctx: egui::Context,
+    pub clock: FakeClock,
+    pub view: EditorView,
+    /// The size of the window the view fills, in points.
+    pub size: egui::Vec2,
+}
+
+impl Harness {
+    /// Creates an editor holding `text` in an 800 by 600 point window.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            ctx: egui::Context::default(),
+            clock: FakeClock::new(),
+            view: EditorView::new(text),
+            size: egui::vec2(800.0, 600.0),
+        }
+    }
+
+    /// Runs one frame at the clock's current time, handing the view the events.
+    ///
+    /// The modifiers held during the frame are those of its last key event.
+    pub fn frame(&mut self, events: Vec<egui::Event>) {
+        let modifiers = events
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                egui::Event::Key { modifiers, .. } => Some(*modifiers),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, self.size)),
+            time: Some(self.clock.elapsed().as_secs_f64()),
+            modifiers,
+            events,
+            focused: true,
+            ..Default::default()
+        };
+        let view = &mut self.view;
+        let _ = self.ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                view.show(ui);
+            });
+        });
+    }
+
+    /// Types text as one text event.
+    pub fn type_text(&mut self, text: &str) {
+        self.frame(vec![egui::Event::Text(text.to_string())]);
+    }
+
+    /// Presses and releases a key in one frame.
+    pub fn press(&mut self, key: egui::Key, modifiers: egui::Modifiers) {
+        let event = |pressed| egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers,
+        };
+        self.frame(vec![event(true), event(false)]);
+    }
+
+    /// Replays a recording: the events that arrived together go into one frame, with the
+    /// clock moved to when they arrived. A last frame without events lets the view settle.
+    pub fn replay(&mut self, recording: &Recording) {
+        let start = self.clock.elapsed();
+        let mut events = recording.events.iter().peekable();
+        while let Some(first) = events.next() {
+            let mut frame = vec![first.event.clone()];
+            while let Some(next) = events.next_if(|next| next.at_ms == first.at_ms) {
+                frame.push(next.event.clone());
+            }
+            let at = start + Duration::from_millis(first.at_ms);
+            self.clock.advance(at.saturating_sub(self.clock.elapsed()));
+            self.frame(frame);
+        }
+        self.frame(Vec::new());
+    }
+
+    /// Describes the text, caret and selection of the view, for [`assert_golden`].
+    pub fn end_state(&mut self) -> String {
+        let buffer_id = self.view.buffer_id();
+        describe(self.view.state_mut(), buffer_id)
+    }
+}
+
+/// Describes a buffer's end state as compared in golden files: the caret, the selection and
+/// the text, with lines and columns counted from 1.
+///
+/// ```text
+/// caret 2:5
+/// selection 2:1-2:5
+/// ---
+/// the text
+/// ```
+pub fn describe(state: &State, buffer_id: ID) -> String {
+    let at =
+        |position: super::types::Position| format!("{}:{}", position.line + 1, position.column + 1);
+    let cursor = state.get_cursor_state(buffer_id);
+    let caret = cursor.map_or_else(|| "none".to_string(), |cursor| at(cursor.position()));
+    let selection = cursor
+        .and_then(|cursor| cursor.selection())
+        .filter(|range| !range.is_empty())
+        .map_or_else(
+            || "none".to_string(),
+            |range| format!("{}-{}", at(range.start), at(range.end)),
+        );
+    format!(
+        "caret {}\nselection {}\n---\n{}",
+        caret,
+        selection,
+        state.get_buffer_text(buffer_id).unwrap_or_default()
+    )
+}
+
+/// Asserts that output equals the content of a golden file.
+///
+/// With [`UPDATE_GOLDEN_VAR`] set, the output is written to the file instead, creating it if
+/// needed; review the change to the file before committing it.
+///
+/// # Panics
+///
+/// Panics if the output differs from the file, or the file can't be read or written.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some_and(|value| !value.is_empty()) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("failed to create the golden file's directory");
+        }
+        fs::write(path, actual).expect("failed to write the golden file");
+        return;
+    }
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {}: {}; run with {}=1 to create it",
+            path.display(),
+            e,
+            UPDATE_GOLDEN_VAR
+        )
+    });
+    assert_eq!(
+        actual,
+        expected,
+        "output differs from {}; run with {}=1 to update it",
+        path.display(),
+        UPDATE_GOLDEN_VAR
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fake_clock_moves_only_when_advanced() {
+        let clock = FakeClock::new();
+        let shared = clock.clone();
+        let start = clock.now();
+        assert_eq!(shared.now(), start);
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(shared.now(), start + Duration::from_millis(250));
+        assert_eq!(shared.elapsed(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn recordings_keep_user_input_and_round_trip() {
+        let start = Instant::now();
+        let mut recorder = Recorder::new(start);
+        recorder.record(
+            start + Duration::from_millis(40),
+            &[
+                egui::Event::Text("a".to_string()),
+                egui::Event::WindowFocused(true),
+                egui::Event::Key {
+                    key: egui::Key::Enter,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: egui::Modifiers::SHIFT,
+                },
+            ],
+        );
+        assert_eq!(recorder.len(), 2);
+        let recording = recorder.finish();
+        assert!(recording.events.iter().all(|event| event.at_ms == 40));
+
+        let path = std::env::temp_dir()
+            .join(format!("led-recording-{}", uuid::Uuid::new_v4()))
+            .join("input.json");
+        recording.save(&path).unwrap();
+        assert_eq!(Recording::load(&path).unwrap(), recording);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,28 +1,527 @@
 pub mod edtr {
-    use super::super::lua::Runtime;
+    use super::super::lua::{self, Runtime};
     use super::super::{
         super::led,
+        bidi, brackets,
         buffer::editor::State,
+        cache, case, check,
         commands::editor::{self, Response},
-        cursor,
+        comment, conflict, cursor,
+        debounce::{Debouncer, Edge},
+        delta, dirs, file_format, file_picker, folds, goto, i18n,
+        idle::Idle,
+        import, indent, index, input,
+        keymap::{self, Keymap, Platform},
+        latency, launch, layout, marks, notes, number, palette, project, prose,
+        render::{self, RenderSurface},
+        replace, sandbox, save,
+        scheduler::{Scheduler, Step, Task},
+        search,
+        session::{self, Session, WindowGeometry},
+        settings::{self, BomOnSave, EvaluationResult, LastBufferClosed, Settings, UpdateChannel},
+        shell, snippet, surround, switcher, tab_bar, tabs, testing,
         types::{Position, Range},
+        update, view, virtual_text, word,
     };
-    use egui::{Rect, Ui};
-    use rfd::FileDialog;
+    use egui::Ui;
     use saran::{context::Context as GuiContext, theme::Theme};
+    use std::collections::HashMap;
     use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc;
+
+    /// A command waiting for the user to type a character: a mark's name, or the pair to
+    /// change the surrounding pair to.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Prompt {
+        SetMark,
+        JumpToMark,
+        ChangeSurround,
+    }
+
+    /// The editor area split into panes, each showing a view of a buffer with its own cursor
+    /// and scroll position.
+    #[derive(Debug, Clone)]
+    struct Panes {
+        layout: layout::Layout<view::ID>,
+        /// The pane that receives keyboard input; it shows the active buffer.
+        focused: view::ID,
+        /// Where each pane was drawn last frame, for moving the focus between them.
+        arranged: Vec<(view::ID, egui::Rect)>,
+    }
+
+    /// State of the open command palette.
+    #[derive(Debug, Clone, Default)]
+    struct CommandPalette {
+        /// The filter text, or the answer to the current argument prompt.
+        query: String,
+        /// Index of the highlighted entry among the matches.
+        selected: usize,
+        /// The command whose arguments are being asked for, if any.
+        pending: Option<PendingCommand>,
+    }
+
+    /// State of the open buffer switcher.
+    #[derive(Debug, Clone, Default)]
+    struct BufferSwitcher {
+        query: String,
+        /// Index of the highlighted buffer among the matches.
+        selected: usize,
+        /// Whether it was opened with Control held, so releasing Control picks the highlighted
+        /// buffer.
+        held: bool,
+    }
+
+    /// A Lua command collecting answers to its argument prompts.
+    #[derive(Debug, Clone)]
+    struct PendingCommand {
+        name: String,
+        prompts: Vec<String>,
+        args: Vec<String>,
+    }
+
+    // Number of matches listed in the command palette
+    const PALETTE_MAX_ENTRIES: usize = 12;
+
+    /// Directory inside the project's `.led` directory that input recordings are saved to.
+    const RECORDINGS_DIR: &str = "recordings";
+
+    // Number of entries listed in the file picker
+    const FILE_PICKER_MAX_ENTRIES: usize = 200;
+
+    /// What a path picked in the file picker or a native dialog is for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum PickTarget {
+        Open,
+        /// Saving a buffer that has no file yet.
+        SaveAs(led::buffer::ID),
+    }
+
+    /// An open native file dialog. It runs beside the editor, which polls it every frame.
+    type NativeDialog =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Option<rfd::FileHandle>>>>;
+
+    /// State of the open file picker.
+    #[derive(Debug, Clone)]
+    struct FilePicker {
+        target: PickTarget,
+        /// The typed path, relative to the project root unless absolute.
+        input: String,
+        /// Index of the highlighted entry among the matches.
+        selected: usize,
+        show_hidden: bool,
+        /// The directory listed last, whether with hidden entries, and its entries. It is only
+        /// listed again when the typed directory changes.
+        listing: Option<(PathBuf, bool, Vec<file_picker::Entry>)>,
+    }
+
+    /// What a project search looks for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum SearchKind {
+        Text,
+        Symbol,
+    }
+
+    /// State of the open find-in-files or go-to-symbol window.
+    #[derive(Debug, Clone)]
+    struct ProjectSearch {
+        kind: SearchKind,
+        query: String,
+        /// Index of the highlighted hit.
+        selected: usize,
+        /// Comma-separated globs of the files a text search looks in.
+        include: String,
+        /// Comma-separated globs of the files a text search skips.
+        exclude: String,
+        /// The query and filters the hits were found for, so the index is only queried when
+        /// they change.
+        searched: Option<(String, String, String)>,
+        hits: Vec<index::Hit>,
+        /// Whether more hits were found than are listed.
+        truncated: bool,
+        /// Why the filters can't be used, if they can't.
+        filter_error: Option<String>,
+        /// Whether matches are replaced, with a preview of the changes listed instead of the hits.
+        replacing: bool,
+        replacement: String,
+        /// Whether the query is a regular expression when replacing.
+        regex: bool,
+        /// Whether matches must have the query's case when replacing.
+        match_case: bool,
+        /// The query, filters, replacement and options the preview was made for.
+        previewed: Option<(String, String, String, String, bool, bool)>,
+        /// The changes a replace makes, by file.
+        changes: Vec<replace::FileChanges>,
+        /// Whether more changes were found than are listed.
+        changes_truncated: bool,
+        /// Why the query can't be used for replacing, if it can't.
+        query_error: Option<String>,
+    }
+
+    impl ProjectSearch {
+        /// Returns the query and filters, to tell whether they changed since the last search.
+        fn key(&self) -> (String, String, String) {
+            (
+                self.query.clone(),
+                self.include.clone(),
+                self.exclude.clone(),
+            )
+        }
+
+        /// Returns everything the preview of a replace depends on, to tell whether it changed
+        /// since the preview was made.
+        fn preview_key(&self) -> (String, String, String, String, bool, bool) {
+            (
+                self.query.clone(),
+                self.include.clone(),
+                self.exclude.clone(),
+                self.replacement.clone(),
+                self.regex,
+                self.match_case,
+            )
+        }
+    }
+
+    /// State of the open find bar.
+    #[derive(Debug, Clone, Default)]
+    struct FindBar {
+        query: String,
+        /// Whether the query is a regular expression.
+        regex: bool,
+        /// Whether matches must have the query's case.
+        match_case: bool,
+        /// Whether matches must be whole words.
+        whole_word: bool,
+        /// Whether the replace field is shown.
+        replacing: bool,
+        replacement: String,
+        /// The byte range selected over several lines when the bar opened, which Replace All
+        /// can be limited to; moved along as matches are replaced.
+        selection: Option<std::ops::Range<usize>>,
+        /// Where the search for the current match starts: the caret when the bar opened, then
+        /// the match stepped to.
+        from: usize,
+        /// The match last selected in the buffer, so it is only selected once.
+        shown: Option<std::ops::Range<usize>>,
+        /// Whether the query field still has to take keyboard focus.
+        focus_query: bool,
+        /// Which recent query is shown while Up/Down browse the search history.
+        history: Option<usize>,
+        /// The query typed before browsing the history, shown again past the most recent one.
+        draft: String,
+    }
+
+    impl FindBar {
+        fn pattern(&self) -> Result<Option<search::Pattern>, search::Error> {
+            search::Pattern::query(&self.query, self.regex, self.match_case, self.whole_word)
+        }
+
+        /// Shows the next older or newer recent query in place of the query.
+        ///
+        /// Browsing starts from the typed query and skips a recent query that is the same.
+        /// Stepping newer than the most recent query shows the typed one again.
+        fn browse_history(&mut self, history: &[String], older: bool) {
+            let at = match (self.history, older) {
+                (None, true) => {
+                    let at = history.iter().position(|query| *query != self.query);
+                    if at.is_some() {
+                        self.draft = self.query.clone();
+                    }
+                    at
+                }
+                (Some(at), true) => Some((at + 1).min(history.len().saturating_sub(1))),
+                (Some(0), false) => {
+                    self.query = std::mem::take(&mut self.draft);
+                    None
+                }
+                (Some(at), false) => Some(at - 1),
+                (None, false) => return,
+            };
+            if let Some(query) = at.and_then(|at| history.get(at)) {
+                self.query = query.clone();
+            }
+            self.history = at;
+        }
+    }
+
+    /// State of the open find-and-replace window.
+    #[derive(Debug, Clone, Default)]
+    struct FindReplace {
+        query: String,
+        replacement: String,
+        /// Whether the query is a regular expression.
+        regex: bool,
+        /// Whether matching ignores case and replacements follow the case of each match.
+        preserve_case: bool,
+        /// The byte range that was selected when the window opened, if any.
+        selection: Option<std::ops::Range<usize>>,
+        /// The byte range matches are limited to, taken from the selection when "In selection
+        /// only" is turned on and moved along as matches in it are replaced.
+        scope: Option<std::ops::Range<usize>>,
+        /// Where the search for the highlighted match starts.
+        from: usize,
+        /// The match last selected in the buffer, so it is only selected once.
+        shown: Option<std::ops::Range<usize>>,
+        /// Whether the query field still has to take keyboard focus.
+        focus_query: bool,
+    }
+
+    impl FindReplace {
+        /// Returns the byte range matches are looked for in, within a text of length `len`.
+        fn scope(&self, len: usize) -> std::ops::Range<usize> {
+            match &self.scope {
+                Some(scope) => scope.start.min(len)..scope.end.min(len),
+                None => 0..len,
+            }
+        }
+    }
+
+    /// What the find-and-replace window does with the highlighted match.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ReplaceStep {
+        /// Replace it and move on to the next match.
+        One,
+        /// Move on to the next match without replacing it.
+        Skip,
+        /// Replace every match.
+        All,
+    }
+
+    // Most search matches highlighted in a buffer at once
+    const MAX_OCCURRENCES: usize = 10_000;
+
+    // Characters of a match and its replacement shown in the find-and-replace preview
+    const REPLACE_PREVIEW_CHARS: usize = 40;
+
+    /// Returns where the search continues after a match: its end, or past the next character
+    /// for an empty match so it isn't found again.
+    fn after(text: &str, found: std::ops::Range<usize>) -> usize {
+        if found.is_empty() {
+            found.end + text[found.end..].chars().next().map_or(1, char::len_utf8)
+        } else {
+            found.end
+        }
+    }
+
+    /// Shortens text for the find-and-replace preview, showing line breaks as `⏎`.
+    fn preview(text: &str) -> String {
+        let mut shown: String = text
+            .chars()
+            .take(REPLACE_PREVIEW_CHARS)
+            .map(|c| if c == '\n' { '⏎' } else { c })
+            .collect();
+        if text.chars().nth(REPLACE_PREVIEW_CHARS).is_some() {
+            shown.push('…');
+        }
+        shown
+    }
+
+    /// A short message shown as a toast and in the status bar, such as the outcome of a replace.
+    #[derive(Debug, Clone)]
+    struct Flash {
+        message: String,
+        until: std::time::Instant,
+    }
+
+    // How long a flash message stays up
+    const FLASH_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+
+    // The idle job compacting buffers, and how long the user must be idle before it runs
+    const COMPACT_JOB: &str = "compact-buffers";
+    const COMPACT_IDLE_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+    // How many pieces a buffer's piece table must have before it is compacted
+    const COMPACT_MIN_PIECES: usize = 256;
+    // The idle job dropping the caches over the ceiling, and how long the user must be idle
+    const TRIM_CACHES_JOB: &str = "trim-caches";
+    const TRIM_CACHES_IDLE_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+    // Idle jobs of Lua handlers are named after the handler with this prefix
+    const LUA_IDLE_PREFIX: &str = "lua:";
+
+    /// Progress of the update checker.
+    enum UpdateStatus {
+        /// Nothing to show.
+        Idle,
+        /// Waiting for the release feed. Manual checks also report that there is no update.
+        Checking {
+            job: mpsc::Receiver<anyhow::Result<Option<update::Release>>>,
+            manual: bool,
+        },
+        Available(update::Release),
+        Downloading {
+            release: update::Release,
+            job: mpsc::Receiver<anyhow::Result<()>>,
+        },
+        /// The update is downloaded and will be installed when LED exits.
+        Staged(update::Release),
+        /// A message for the user, such as a failed check.
+        Message(String),
+    }
+
+    // Number of changelog lines shown when an update is available
+    const CHANGELOG_PREVIEW_LINES: usize = 12;
+
+    /// State of the open window importing another editor's configuration.
+    #[derive(Debug, Clone)]
+    struct ImportWizard {
+        editor: import::Editor,
+        /// The editor's configuration directory, as typed.
+        dir: String,
+        /// What the last import did, or why it failed.
+        outcome: Option<Result<import::Import, String>>,
+    }
+
+    impl ImportWizard {
+        fn new(editor: import::Editor) -> Self {
+            let dir = editor
+                .default_dir(Platform::current())
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            Self {
+                editor,
+                dir,
+                outcome: None,
+            }
+        }
+    }
+
+    /// The name of the application, shown as the window title while no buffer is open.
+    pub const APP_NAME: &str = "LED Editor";
+
+    /// Returns the last component of a path, for compact display.
+    fn file_name(path: &str) -> String {
+        std::path::Path::new(path).file_name().map_or_else(
+            || path.to_string(),
+            |name| name.to_string_lossy().to_string(),
+        )
+    }
 
     pub struct App {
         edtr_state: State,
         gui_ctx: GuiContext,
         lua_runtime: Runtime,
+        settings: Settings,
+        /// Problems found in the settings files, shown until dismissed.
+        settings_problems: Vec<settings::Diagnostic>,
+        /// Scripts waiting for the user to decide whether they may run programs.
+        permission_requests: Vec<String>,
+        keymap: Keymap,
+        /// Directory the editor was started in, which scopes notes and the session.
+        project_root: Option<PathBuf>,
+        /// Where the main window is, saved with the session.
+        window: Option<WindowGeometry>,
+        /// The geometry restored from the session, until it was checked against the monitor
+        /// the window opened on.
+        window_to_fit: Option<WindowGeometry>,
+        /// The buffer the window title and the status bar's language were last shown for.
+        titled_buffer: Option<led::buffer::ID>,
+        /// The active buffer's language, as shown in the status bar.
+        status_language: String,
+
+        /// Whether the quit confirmation dialog is open.
+        confirming_quit: bool,
+        /// Set once quitting has been confirmed, so the next close request is let through.
+        quit_confirmed: bool,
+        /// A document with unsaved changes that is to be closed once the user chose to save or
+        /// discard the changes.
+        confirming_close: Option<led::buffer::ID>,
+        /// A document being saved from the close dialog, closed once it is saved.
+        closing_after_save: Option<led::buffer::ID>,
+
+        notes: Option<notes::Scratchpad>,
+        show_notes: bool,
+        notes_focused: bool,
+
+        show_marks: bool,
+        /// The panels registered from Lua, as rendered this frame.
+        lua_panels: Vec<lua::Panel>,
+        /// What was typed into the inputs of Lua panels, by panel name and input ID.
+        panel_inputs: HashMap<(String, String), String>,
+        /// Whether an input of a Lua panel has the keyboard focus, which the editors then don't.
+        panel_input_focused: bool,
+        prompt: Option<Prompt>,
+        palette: Option<CommandPalette>,
+        file_picker: Option<FilePicker>,
+        /// The location typed into the open Go to Line window.
+        goto_line: Option<String>,
+        /// The open native file dialog and what its answer is for.
+        native_dialog: Option<(PickTarget, NativeDialog)>,
+        /// Directories files were recently opened from or saved to, most recent first.
+        recent_dirs: Vec<PathBuf>,
+        /// Recent search queries, most recent first.
+        search_history: Vec<String>,
+        /// Open documents in the order of their tabs.
+        tab_order: Vec<led::buffer::ID>,
+        /// Documents by when they were last active, most recent first.
+        buffer_mru: Vec<led::buffer::ID>,
+        /// Where each document was last scrolled to in the focused pane, saved with the session.
+        scroll_offsets: HashMap<led::buffer::ID, egui::Vec2>,
+        /// Scroll positions restored from the session, applied when their documents are first
+        /// shown.
+        restored_scroll: HashMap<led::buffer::ID, egui::Vec2>,
+        buffer_switcher: Option<BufferSwitcher>,
+        project_search: Option<ProjectSearch>,
+        find_bar: Option<FindBar>,
+        find_replace: Option<FindReplace>,
+        /// The last search for the word under the caret and the buffer it was made in. Its
+        /// matches are highlighted while one of them is selected, and the find window picks it
+        /// up when opened on one.
+        word_search: Option<(led::types::buffer::ID, FindReplace)>,
+        /// Whether the main editor scrolls its caret into view on the next frame.
+        reveal_cursor: bool,
+        /// Whether the main editor scrolls its caret to the middle of the view on the next frame.
+        center_cursor: bool,
+        flash: Option<Flash>,
+        /// The merge conflicts last found in the active buffer, with the buffer and the content
+        /// digest of the text they were found in, so they are only detected again after a change.
+        conflicts: Option<(led::buffer::ID, u64, Vec<conflict::Region>)>,
+        import_wizard: Option<ImportWizard>,
+        update_status: UpdateStatus,
+
+        index: Option<index::Index>,
+        /// Receives the result of the index build running in the background.
+        index_job: Option<mpsc::Receiver<anyhow::Result<index::Index>>>,
+        /// Whether the index was dropped to save memory, to be loaded again by the next search.
+        index_evicted: bool,
+        /// Receives the reports of the format and lint run going on in the background.
+        check_job: Option<mpsc::Receiver<Vec<check::Report>>>,
+        /// What the last format and lint run found, listed in the problems panel.
+        problems: Vec<check::Finding>,
+
+        /// The buffer and content digest suggestion providers last saw, so they are only asked
+        /// again after an edit.
+        suggested_for: Option<(led::types::buffer::ID, u64)>,
+        /// Delays asking suggestion providers until typing pauses.
+        suggestion_debounce: Debouncer,
+        /// Runs incremental work, such as highlighting large buffers, within a frame budget.
+        scheduler: Scheduler,
+        /// Work waiting for a pause in input, such as compacting buffers and Lua idle handlers.
+        idle: Idle,
+        /// Tracks the memory of buffer caches and which to drop first.
+        caches: cache::Manager,
+        /// Whether the window had the keyboard focus last frame; losing it shrinks the caches.
+        window_focused: bool,
+
+        /// The panes of a split editor area. Without a split there is one pane, which has no
+        /// view of its own.
+        panes: Option<Panes>,
+
+        zen_mode: bool,
+        typewriter_scrolling: bool,
+        soft_wrap: bool,
+        wrap_column: usize,
 
         show_line_numbers: bool,
         font_size: f32,
         tab_size: usize,
+        tab_arrows: bool,
 
         frame_time: f32,
         last_frame_time: std::time::Instant,
+        /// Keystroke-to-paint latency, measured while `settings.track_latency` is on.
+        latency: latency::Tracker,
+        /// Records the input to the editor while the user has recording turned on, for tests
+        /// to replay.
+        input_recorder: Option<testing::Recorder>,
     }
 
     impl App {
@@ -31,12 +530,83 @@ pub mod edtr {
                 edtr_state: State::new(),
                 gui_ctx: GuiContext::new(cc.egui_ctx.clone()),
                 lua_runtime: Runtime::new().expect("Failed to create Lua runtime"),
+                settings: Settings::default(),
+                settings_problems: Vec::new(),
+                permission_requests: Vec::new(),
+                keymap: Keymap::defaults(Platform::current()),
+                project_root: None,
+                window: None,
+                window_to_fit: None,
+                titled_buffer: None,
+                status_language: i18n::tr("status-plain-text"),
+                confirming_quit: false,
+                quit_confirmed: false,
+                confirming_close: None,
+                closing_after_save: None,
+                notes: None,
+                show_notes: false,
+                notes_focused: false,
+                show_marks: false,
+                lua_panels: Vec::new(),
+                panel_inputs: HashMap::new(),
+                panel_input_focused: false,
+                prompt: None,
+                palette: None,
+                file_picker: None,
+                goto_line: None,
+                native_dialog: None,
+                recent_dirs: Vec::new(),
+                search_history: Vec::new(),
+                tab_order: Vec::new(),
+                buffer_mru: Vec::new(),
+                scroll_offsets: HashMap::new(),
+                restored_scroll: HashMap::new(),
+                buffer_switcher: None,
+                project_search: None,
+                find_bar: None,
+                find_replace: None,
+                word_search: None,
+                reveal_cursor: false,
+                center_cursor: false,
+                flash: None,
+                conflicts: None,
+                import_wizard: None,
+                update_status: UpdateStatus::Idle,
+                index: None,
+                index_job: None,
+                index_evicted: false,
+                check_job: None,
+                problems: Vec::new(),
+                suggested_for: None,
+                suggestion_debounce: Debouncer::new(
+                    std::time::Duration::from_millis(Settings::default().suggestion_delay_ms),
+                    Edge::Trailing,
+                ),
+                idle: {
+                    let mut idle = Idle::new(std::time::Instant::now());
+                    idle.register(COMPACT_JOB, COMPACT_IDLE_DELAY);
+                    idle.register(TRIM_CACHES_JOB, TRIM_CACHES_IDLE_DELAY);
+                    idle
+                },
+                caches: cache::Manager::new(),
+                window_focused: true,
+                scheduler: Scheduler::new(std::time::Duration::from_millis(
+                    Settings::default().frame_budget_ms,
+                )),
+                panes: None,
+                zen_mode: false,
+                typewriter_scrolling: true,
+                soft_wrap: true,
+                wrap_column: 80,
                 show_line_numbers: true,
                 font_size: 14.0,
                 tab_size: 4,
+                tab_arrows: false,
 
                 frame_time: 0.0,
                 last_frame_time: std::time::Instant::now(),
+                latency: latency::Tracker::default(),
+                input_recorder: None,
             };
 
             let content = r#"// Welcome to LED!!!!
@@ -47,9 +617,98 @@ fn main() {
 "#
             .to_string();
 
-            app.edtr_state.create_buffer(content);
+            app.configure_equi_style(&cc.egui_ctx);
+
+            // Asking the login shell can take a while, so it starts before anything needs it
+            std::thread::spawn(|| {
+                shell::login_environment();
+            });
+
+            let mut restored = None;
+            match std::env::current_dir() {
+                Ok(root) => {
+                    match notes::Scratchpad::open(&mut app.edtr_state, &root) {
+                        Ok(scratchpad) => app.notes = Some(scratchpad),
+                        Err(e) => eprintln!("Failed to load notes: {}", e),
+                    }
+                    match Keymap::load(&root, Platform::current()) {
+                        Ok(keymap) => app.keymap = keymap,
+                        Err(e) => eprintln!("Failed to load keymap: {:#}", e),
+                    }
+                    match Session::load(&root) {
+                        Ok(session) => {
+                            app.edtr_state.marks_mut().set_pending(session.marks);
+                            app.window = session.window;
+                            app.window_to_fit = session.window;
+                            app.recent_dirs = session.recent_dirs;
+                            app.search_history = session.search_history;
+                            restored = Some((session.open_files, session.active_file));
+                        }
+                        Err(e) => eprintln!("Failed to load session: {:#}", e),
+                    }
+                    app.project_root = Some(root);
+                }
+                Err(e) => eprintln!("Failed to determine project root: {}", e),
+            }
+
+            app.load_settings();
+            if app.settings.check_for_updates {
+                app.start_update_check(false);
+            }
+
+            if let Err(e) = app.lua_runtime.load_default_config() {
+                eprintln!("Failed to load Lua configuration: {:#}", e);
+            }
+            if let Some(root) = &app.project_root {
+                let permissions = sandbox::Permissions::load(&sandbox::permissions_path(root))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Failed to load process permissions: {:#}", e);
+                        sandbox::Permissions::default()
+                    });
+                app.lua_runtime.set_process_policy(lua::ProcessPolicy {
+                    project_root: root.clone(),
+                    limits: app.process_limits(),
+                    permissions,
+                });
+            }
+            // The user's plugins load first, so project scripts can build on them
+            let user_init = dirs::config_dir().map(|dir| dir.join(lua::INIT_FILE));
+            let project_init = app
+                .project_root
+                .as_ref()
+                .map(|root| root.join(notes::STATE_DIR).join(lua::INIT_FILE));
+            for init in user_init.into_iter().chain(project_init) {
+                if init.exists()
+                    && let Err(e) = app.lua_runtime.load_file(&init)
+                {
+                    eprintln!("Failed to load {}: {:#}", init.display(), e);
+                }
+            }
+            match app.lua_runtime.take_translations() {
+                Ok(bundles) => {
+                    for (locale, source) in bundles {
+                        for error in i18n::add_bundle(&locale, &source) {
+                            eprintln!("Invalid {} translation: {}", locale, error);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to read Lua translations: {:#}", e),
+            }
+
+            // Pick up where the last session in this directory left off, unless asked not to
+            if let Some((files, active)) = restored
+                && !session::starts_clean(std::env::args().skip(1))
+            {
+                app.restore_files(files, active);
+            }
+            app.open_named_files(launch::files(std::env::args().skip(1)));
+            if app.document_buffers().is_empty() {
+                app.edtr_state.create_buffer(content);
+            }
 
-            // TODO: load and configure initial Lua state
+            if app.settings.project_index {
+                app.start_indexing(false);
+            }
 
             app
         }
@@ -69,6 +728,41 @@ fn main() {
 
             ctx.set_style(style);
         }
+
+        /// Returns the limits on programs run by scripts, from the settings.
+        fn process_limits(&self) -> sandbox::Limits {
+            sandbox::Limits {
+                timeout: std::time::Duration::from_millis(self.settings.process_timeout_ms),
+                max_output: self.settings.process_output_kb as usize * 1024,
+            }
+        }
+
+        /// Loads the global and project settings files, replacing the current settings.
+        fn load_settings(&mut self) {
+            let mut files: Vec<PathBuf> = settings::global_settings_path().into_iter().collect();
+            if let Some(root) = &self.project_root {
+                files.push(settings::project_settings_path(root));
+            }
+            let detected = self
+                .project_root
+                .as_deref()
+                .map(project::detected_settings)
+                .unwrap_or_default();
+            let (settings, problems) = Settings::load(detected, &files);
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            self.settings = settings;
+            self.settings_problems = problems;
+            if let Err(e) = self
+                .gui_ctx
+                .style_system
+                .set_active_theme(&self.settings.theme)
+            {
+                eprintln!("Failed to apply the theme: {}", e);
+                self.flash(i18n::tr_with("theme-not-found", &[("name", e.name.into())]));
+            }
+        }
     }
 
     impl eframe::App for App {
@@ -76,16 +770,110 @@ fn main() {
             let now = std::time::Instant::now();
             self.frame_time = now.duration_since(self.last_frame_time).as_secs_f32();
             self.last_frame_time = now;
+            self.scheduler.set_budget(std::time::Duration::from_millis(
+                self.settings.frame_budget_ms,
+            ));
+            self.scheduler.begin_frame(now);
+            i18n::set_language(&self.settings.language);
+            self.track_window(ctx);
+            self.apply_metadata_changes(ctx);
+            self.track_caches(ctx, now);
+            self.open_dropped_files(ctx);
 
-            if let Ok(commands) = self.lua_runtime.proccess_frame_commands() {
-                for command in commands {
-                    let _ = self.edtr_state.execute_command(command);
+            // egui doesn't timestamp events, so keystrokes count as received when the frame
+            // handling them starts
+            if self.settings.track_latency {
+                let keystrokes = ctx.input(|i| {
+                    i.events
+                        .iter()
+                        .filter(|event| {
+                            matches!(
+                                event,
+                                egui::Event::Text(_) | egui::Event::Key { pressed: true, .. }
+                            )
+                        })
+                        .count()
+                });
+                for _ in 0..keystrokes {
+                    self.latency.record_input(now);
                 }
             }
+            // Only input meant for the editor is recorded, not the palette used to stop
+            if let Some(recorder) = &mut self.input_recorder
+                && self.palette.is_none()
+                && self.file_picker.is_none()
+                && self.goto_line.is_none()
+                && self.find_bar.is_none()
+                && self.buffer_switcher.is_none()
+            {
+                ctx.input(|i| recorder.record(now, &i.events));
+            }
 
-            // Ensure scroll area fills the central panel
-            egui::CentralPanel::default().show(ctx, |ui| {
-                self.render_editor_ui(ui);
+            let input = ctx.input(|i| {
+                i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::Text(_)
+                            | egui::Event::Paste(_)
+                            | egui::Event::Key { pressed: true, .. }
+                            | egui::Event::PointerButton { pressed: true, .. }
+                            | egui::Event::MouseWheel { .. }
+                    )
+                })
+            });
+            if input {
+                self.idle.input(now);
+            }
+
+            self.lua_runtime.set_process_limits(self.process_limits());
+            for script in self.lua_runtime.take_permission_requests() {
+                if !self.permission_requests.contains(&script) {
+                    self.permission_requests.push(script);
+                }
+            }
+
+            // The edits scripts queued this frame apply as one
+            if let Ok(commands) = self.lua_runtime.proccess_frame_commands()
+                && let Err(e) = self
+                    .edtr_state
+                    .execute_command(editor::Command::Batch(commands))
+            {
+                eprintln!("Failed to apply Lua edits: {:#}", e);
+            }
+
+            for action in self.keymap.take_actions(ctx, |_| true) {
+                self.run_action(ctx, &action);
+            }
+            match self.lua_runtime.take_actions() {
+                Ok(actions) => {
+                    for action in actions {
+                        self.run_action(ctx, &action);
+                    }
+                }
+                Err(e) => eprintln!("Failed to read Lua actions: {:#}", e),
+            }
+            self.handle_prompt(ctx);
+            self.poll_indexing();
+            self.poll_check();
+            self.poll_update();
+            self.run_virtual_text_actions(ctx);
+            self.update_suggestion(ctx, now);
+            self.run_idle_jobs(ctx, now);
+
+            self.handle_close_request(ctx);
+
+            if let Some(scratchpad) = &mut self.notes {
+                scratchpad.set_autosave_delay(std::time::Duration::from_millis(
+                    self.settings.autosave_delay_ms,
+                ));
+                if let Err(e) = scratchpad.autosave(&mut self.edtr_state, now) {
+                    eprintln!("Failed to save notes: {}", e);
+                }
+            }
+
+            self.lua_panels = self.lua_runtime.panels().unwrap_or_else(|e| {
+                eprintln!("Failed to read Lua panels: {:#}", e);
+                Vec::new()
             });
 
             // Menu bar
@@ -93,158 +881,5479 @@ fn main() {
                 self.render_menu_bar(ui);
             });
 
+            // Tabs of the open documents
+            if !self.zen_mode && !self.document_buffers().is_empty() {
+                egui::TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+                    self.render_tab_bar(ui);
+                });
+            }
+
+            // Status bar
+            if !self.zen_mode {
+                egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+                    ui.horizontal(|ui| self.render_status_bar(ui));
+                });
+            }
+
+            // Notes panel
+            if self.show_notes && !self.zen_mode {
+                egui::SidePanel::right("notes_panel")
+                    .resizable(true)
+                    .default_width(320.0)
+                    .show(ctx, |ui| {
+                        self.render_notes_panel(ui);
+                    });
+            }
+
+            // Marks panel
+            if self.show_marks && !self.zen_mode {
+                egui::SidePanel::left("marks_panel")
+                    .resizable(true)
+                    .default_width(220.0)
+                    .show(ctx, |ui| {
+                        self.render_marks_panel(ui);
+                    });
+            }
+
+            // Problems found by the last format and lint run
+            if !self.problems.is_empty() && !self.zen_mode {
+                egui::TopBottomPanel::bottom("problems_panel")
+                    .resizable(true)
+                    .default_height(140.0)
+                    .show(ctx, |ui| {
+                        self.render_problems_panel(ui);
+                    });
+            }
+
+            // Panels registered from Lua
+            self.panel_input_focused = false;
+            if !self.zen_mode {
+                let panels = std::mem::take(&mut self.lua_panels);
+                for panel in panels.iter().filter(|panel| panel.visible) {
+                    let id = egui::Id::new(("lua_panel", &panel.name));
+                    match panel.side {
+                        lua::PanelSide::Left => egui::SidePanel::left(id)
+                            .resizable(true)
+                            .default_width(220.0)
+                            .show(ctx, |ui| self.render_lua_panel(ui, panel)),
+                        lua::PanelSide::Right => egui::SidePanel::right(id)
+                            .resizable(true)
+                            .default_width(220.0)
+                            .show(ctx, |ui| self.render_lua_panel(ui, panel)),
+                        lua::PanelSide::Bottom => egui::TopBottomPanel::bottom(id)
+                            .resizable(true)
+                            .default_height(140.0)
+                            .show(ctx, |ui| self.render_lua_panel(ui, panel)),
+                    };
+                }
+                self.lua_panels = panels;
+            }
+
+            // Ensure scroll area fills the central panel
+            egui::CentralPanel::default().show(ctx, |ui| {
+                if ui.ui_contains_pointer() && ui.input(|i| i.pointer.primary_pressed()) {
+                    self.notes_focused = false;
+                }
+                self.validate_panes();
+                self.render_editor_ui(ui);
+            });
+
+            // Remember the order documents were active in, for the buffer switcher
+            if let Some(active) = self.edtr_state.get_active_buffer()
+                && Some(active) != self.notes_buffer_id()
+                && self.buffer_mru.first() != Some(&active)
+            {
+                switcher::touch(&mut self.buffer_mru, active);
+            }
+
+            // Leave the focused pane's cursor as the buffer's cursor for menus and shortcuts
+            if let Some(focused) = self.focused_pane() {
+                self.edtr_state.focus_view(focused);
+            }
+
+            self.render_command_palette(ctx);
+            self.render_buffer_switcher(ctx);
+            self.render_file_picker(ctx);
+            self.render_goto_line(ctx);
+            self.poll_native_dialog(ctx);
+            self.render_project_search(ctx);
+            self.render_find_bar(ctx);
+            self.render_find_replace(ctx);
+            self.render_flash(ctx);
+            self.render_drop_hint(ctx);
+            self.render_import_wizard(ctx);
+            self.render_update_status(ctx);
+
+            if self.confirming_quit {
+                self.render_quit_dialog(ctx);
+            }
+            if self.confirming_close.is_some() {
+                self.render_close_dialog(ctx);
+            }
+            self.close_saved_buffer(ctx);
+            if !self.settings_problems.is_empty() {
+                self.render_settings_problems(ctx);
+            }
+            if !self.permission_requests.is_empty() {
+                self.render_permission_request(ctx);
+            }
+
+            // The frame's shapes are complete; only tessellation and presenting remain
+            if self.settings.track_latency
+                && let Some(summary) = self.latency.record_paint(std::time::Instant::now())
+            {
+                eprintln!("Keystroke latency: {}", summary);
+            }
+
             ctx.request_repaint_after(std::time::Duration::from_millis(500));
         }
+
+        fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+            self.flush_notes();
+            self.save_session();
+            self.save_index();
+            if matches!(self.update_status, UpdateStatus::Staged(_)) {
+                match update::install_staged() {
+                    Ok(Some(version)) => println!("Installed LED {}", version),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Failed to install update: {:#}", e),
+                }
+            }
+        }
     }
 
     impl App {
         fn render_editor_ui(&mut self, ui: &mut egui::Ui) {
-            if let Some(buffer_id) = self.edtr_state.get_active_buffer() {
-                let avail_rect = ui.available_rect_before_wrap();
+            let active = self
+                .edtr_state
+                .get_active_buffer()
+                .filter(|id| Some(*id) != self.notes_buffer_id());
+            let Some(buffer_id) = active else {
+                self.render_welcome_screen(ui);
+                return;
+            };
+            // Zen mode shows only the focused pane
+            let Some(layout) = self
+                .panes
+                .as_ref()
+                .filter(|_| !self.zen_mode)
+                .map(|panes| panes.layout.clone())
+            else {
+                let pane = self.focused_pane();
+                self.render_pane(ui, buffer_id, pane, true);
+                return;
+            };
+
+            let arranged = layout.arrange(ui.max_rect(), PANE_GAP);
+            if ui.input(|i| i.pointer.primary_pressed())
+                && let Some(&(pane, _)) = arranged
+                    .iter()
+                    .find(|(_, rect)| ui.rect_contains_pointer(*rect))
+            {
+                self.focus_pane(pane);
+            }
+            let focused = self.focused_pane();
+            for &(pane, rect) in &arranged {
+                let Some(buffer_id) = self.edtr_state.view(pane).map(|v| v.buffer_id()) else {
+                    continue;
+                };
+                let mut pane_ui = ui.new_child(
+                    egui::UiBuilder::new()
+                        .max_rect(rect)
+                        .id_salt(("editor_pane", pane)),
+                );
+                pane_ui.set_clip_rect(rect.intersect(ui.clip_rect()));
+                self.render_pane(&mut pane_ui, buffer_id, Some(pane), focused == Some(pane));
+            }
+            if let Some(&(_, rect)) = arranged.iter().find(|(pane, _)| Some(*pane) == focused) {
+                ui.painter().rect_stroke(
+                    rect,
+                    0.0,
+                    egui::Stroke::new(1.0, ui.visuals().selection.bg_fill),
+                    egui::StrokeKind::Outside,
+                );
+            }
+            if let Some(panes) = &mut self.panes {
+                panes.arranged = arranged;
+            }
+        }
+
+        /// Shows a buffer in a pane: through the pane's view when the editor is split, otherwise
+        /// through the buffer's own cursor. Only the focused pane takes keyboard input and
+        /// follows requests to reveal the cursor.
+        fn render_pane(
+            &mut self,
+            ui: &mut egui::Ui,
+            buffer_id: led::buffer::ID,
+            pane: Option<view::ID>,
+            focused: bool,
+        ) {
+            if let Some(view_id) = pane {
+                self.edtr_state.focus_view(view_id);
+            }
+            let overrides = self.pane_overrides(pane);
+            let occurrences = self.occurrences(buffer_id);
+
+            let theme = self.theme();
+            let mut text_editor = Widget::new(buffer_id, &mut self.edtr_state, theme);
+            text_editor.view = pane;
+            text_editor.show_line_numbers = self.show_line_numbers;
+            text_editor.tab_size = self.tab_size;
+            text_editor.tab_arrows = self.tab_arrows;
+            if focused {
+                text_editor.reveal_cursor = std::mem::take(&mut self.reveal_cursor);
+                text_editor.center_cursor = std::mem::take(&mut self.center_cursor);
+            }
+            text_editor.focused = focused
+                && (!self.notes_focused || self.zen_mode)
+                && !self.panel_input_focused
+                && self.palette.is_none()
+                && self.file_picker.is_none()
+                && self.goto_line.is_none()
+                && self.project_search.is_none()
+                && self.find_bar.is_none()
+                && self.find_replace.is_none()
+                && self.buffer_switcher.is_none();
+            text_editor.selection_toolbar = self.settings.selection_toolbar;
+            text_editor.selection_counts = self.settings.selection_counts;
+            text_editor.highlight_delay =
+                std::time::Duration::from_millis(self.settings.highlight_delay_ms);
+            text_editor.long_line_wrap = self.settings.long_line_wrap as usize;
+            text_editor.indent_after = self.settings.indent_after.clone();
+            text_editor.use_tabs = self.settings.insert_tabs;
+            text_editor.occurrences = occurrences;
+            text_editor.scheduler = Some(&mut self.scheduler);
+            if self.zen_mode {
+                text_editor.show_line_numbers = false;
+                text_editor.center_text = true;
+                text_editor.typewriter = self.typewriter_scrolling;
+                text_editor.wrap_column = self.soft_wrap.then_some(self.wrap_column);
+            }
+            if let Some(soft_wrap) = overrides.soft_wrap {
+                text_editor.wrap_column = soft_wrap.then_some(self.wrap_column);
+            }
+
+            // Commands are executed immediately in Widget::show, so the response is not replayed here.
+            text_editor.font_size = overrides.font_size.unwrap_or(self.font_size);
+            text_editor.scroll_offset = self.restored_scroll.remove(&buffer_id);
+            text_editor.show(ui);
+            // Pinch-to-zoom adjusts the font size inside the widget
+            let font_size = text_editor.font_size;
+            let scroll_offset = text_editor.scroll_offset;
+            self.set_pane_font_size(pane, font_size);
+            if focused && let Some(offset) = scroll_offset {
+                self.scroll_offsets.insert(buffer_id, offset);
+            }
+        }
+
+        /// Returns the view of the pane that receives keyboard input, if the editor is split.
+        /// Without a split there is one pane, which has no view of its own.
+        fn focused_pane(&self) -> Option<view::ID> {
+            self.panes.as_ref().map(|panes| panes.focused)
+        }
+
+        /// Returns the settings a pane overrides; a pane without a view overrides none.
+        fn pane_overrides(&self, pane: Option<view::ID>) -> view::Overrides {
+            pane.and_then(|view_id| self.edtr_state.view(view_id))
+                .map(view::State::overrides)
+                .unwrap_or_default()
+        }
+
+        /// Changes the font size of a pane: its own one if it overrides the editor's, otherwise
+        /// the editor's.
+        fn set_pane_font_size(&mut self, pane: Option<view::ID>, font_size: f32) {
+            let mut overrides = self.pane_overrides(pane);
+            match pane.and_then(|view_id| self.edtr_state.view_mut(view_id)) {
+                Some(view) if overrides.font_size.is_some() => {
+                    overrides.font_size = Some(font_size);
+                    view.set_overrides(overrides);
+                }
+                _ => self.font_size = font_size,
+            }
+        }
+
+        /// Zooms the focused pane in or out. In a split only that pane changes; otherwise the
+        /// editor's font size does.
+        fn zoom_pane(&mut self, step: f32) {
+            let pane = self.focused_pane();
+            let mut overrides = self.pane_overrides(pane);
+            let font_size = (overrides.font_size.unwrap_or(self.font_size) + step)
+                .clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+            match pane.and_then(|view_id| self.edtr_state.view_mut(view_id)) {
+                Some(view) => {
+                    overrides.font_size = Some(font_size);
+                    view.set_overrides(overrides);
+                }
+                None => self.font_size = font_size,
+            }
+        }
+
+        /// Turns soft wrapping on or off in the focused pane. Without a split this toggles the
+        /// editor's soft wrap setting.
+        fn toggle_pane_wrap(&mut self) {
+            let pane = self.focused_pane();
+            let mut overrides = self.pane_overrides(pane);
+            let wrapped = overrides
+                .soft_wrap
+                .unwrap_or(self.soft_wrap && self.zen_mode);
+            match pane.and_then(|view_id| self.edtr_state.view_mut(view_id)) {
+                Some(view) => {
+                    overrides.soft_wrap = Some(!wrapped);
+                    view.set_overrides(overrides);
+                }
+                None => self.soft_wrap = !self.soft_wrap,
+            }
+        }
+
+        /// Makes the focused pane follow the editor's font size and wrapping again.
+        fn reset_pane(&mut self) {
+            if let Some(view) = self
+                .focused_pane()
+                .and_then(|view_id| self.edtr_state.view_mut(view_id))
+            {
+                view.set_overrides(view::Overrides::default());
+            }
+        }
+
+        /// Moves the keyboard focus to a pane, making its buffer the active one.
+        fn focus_pane(&mut self, pane: view::ID) {
+            let Some(buffer_id) = self.edtr_state.view(pane).map(|v| v.buffer_id()) else {
+                return;
+            };
+            if let Some(panes) = &mut self.panes {
+                panes.focused = pane;
+            }
+            self.edtr_state.focus_view(pane);
+            self.edtr_state.set_active_buffer(Some(buffer_id));
+            self.notes_focused = false;
+        }
+
+        /// Moves the keyboard focus to the nearest pane in a direction, if there is one.
+        fn focus_pane_toward(&mut self, direction: layout::Direction) {
+            if let Some(pane) = self
+                .panes
+                .as_ref()
+                .and_then(|panes| layout::neighbor(&panes.arranged, panes.focused, direction))
+            {
+                self.focus_pane(pane);
+            }
+        }
+
+        /// Splits the focused pane, showing its buffer in a new pane to its right or below it,
+        /// which takes the focus. An unsplit editor's single pane gets a view of its own first.
+        fn split_pane(&mut self, axis: layout::Axis) {
+            let Some(buffer_id) = self
+                .edtr_state
+                .get_active_buffer()
+                .filter(|id| Some(*id) != self.notes_buffer_id())
+            else {
+                return;
+            };
+            let mut panes = match self.panes.take() {
+                Some(panes) => panes,
+                None => {
+                    let Some(pane) = self.edtr_state.create_view(buffer_id) else {
+                        return;
+                    };
+                    Panes {
+                        layout: layout::Layout::Pane(pane),
+                        focused: pane,
+                        arranged: Vec::new(),
+                    }
+                }
+            };
+            // The new view starts from the focused pane's cursor
+            self.edtr_state.focus_view(panes.focused);
+            if let Some(pane) = self.edtr_state.create_view(buffer_id)
+                && panes.layout.split(panes.focused, axis, pane)
+            {
+                panes.focused = pane;
+                self.edtr_state.focus_view(pane);
+                self.reveal_cursor = true;
+            }
+            self.panes = Some(panes);
+            self.validate_panes();
+        }
+
+        /// Closes the focused pane, moving the focus to the pane before it. Closing all but one
+        /// pane undoes the split.
+        fn close_pane(&mut self) {
+            let Some(panes) = &mut self.panes else {
+                return;
+            };
+            let closed = panes.focused;
+            let order = panes.layout.panes();
+            let index = order.iter().position(|pane| *pane == closed).unwrap_or(0);
+            if !panes.layout.remove(closed) {
+                return;
+            }
+            let next = order[if index == 0 { 1 } else { index - 1 }];
+            self.edtr_state.close_view(closed);
+            self.focus_pane(next);
+            self.validate_panes();
+        }
+
+        /// Splits the editor to the right, or undoes the split, keeping the focused pane.
+        fn toggle_split(&mut self) {
+            match self.panes.take() {
+                Some(panes) => self.unsplit(panes),
+                None => self.split_pane(layout::Axis::Row),
+            }
+        }
 
-                let mut text_editor =
-                    Widget::new(buffer_id, &mut self.edtr_state, &mut self.gui_ctx);
-                text_editor.show_line_numbers = self.show_line_numbers;
-                text_editor.tab_size = self.tab_size;
+        /// Goes back to a single pane, keeping the focused pane's cursor as its buffer's cursor.
+        fn unsplit(&mut self, panes: Panes) {
+            for pane in panes.layout.panes() {
+                if pane != panes.focused {
+                    self.edtr_state.close_view(pane);
+                }
+            }
+            self.edtr_state.focus_view(panes.focused);
+            self.edtr_state.close_view(panes.focused);
+        }
+
+        /// Keeps the panes in step with the buffers. Panes whose buffer was closed go, and the
+        /// focused pane switches to the active buffer when another one becomes active, e.g. from
+        /// the tab bar. A split left with one pane is undone.
+        fn validate_panes(&mut self) {
+            let active = self
+                .edtr_state
+                .get_active_buffer()
+                .filter(|id| Some(*id) != self.notes_buffer_id());
+            let Some(mut panes) = self.panes.take() else {
+                return;
+            };
+            for pane in panes.layout.panes() {
+                if self.edtr_state.view(pane).is_none() {
+                    panes.layout.remove(pane);
+                }
+            }
+            let shown: Vec<view::ID> = panes
+                .layout
+                .panes()
+                .into_iter()
+                .filter(|pane| self.edtr_state.view(*pane).is_some())
+                .collect();
+            let Some(&first) = shown.first() else {
+                return;
+            };
+            if !shown.contains(&panes.focused) {
+                // The focused pane's buffer was closed; the focus moves on rather than the
+                // next pane taking whichever buffer became active
+                panes.focused = first;
+                self.edtr_state.focus_view(first);
+                let buffer_id = self.edtr_state.view(first).map(|v| v.buffer_id());
+                self.edtr_state.set_active_buffer(buffer_id);
+            } else if let Some(active) = active
+                && self.edtr_state.view(panes.focused).map(|v| v.buffer_id()) != Some(active)
+                && let Some(pane) = self.edtr_state.create_view(active)
+            {
+                panes.layout.replace(panes.focused, pane);
+                self.edtr_state.close_view(panes.focused);
+                panes.focused = pane;
+            }
+            if matches!(panes.layout, layout::Layout::Pane(_)) {
+                self.unsplit(panes);
+            } else {
+                self.panes = Some(panes);
+            }
+        }
 
-                let response = text_editor.show(ui, avail_rect);
+        fn render_welcome_screen(&mut self, ui: &mut egui::Ui) {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.0);
+                ui.heading("LED");
+                ui.label(i18n::tr("welcome-tagline"));
+                ui.add_space(16.0);
+                if ui.button(i18n::tr("welcome-new-file")).clicked() {
+                    self.edtr_state.create_buffer(String::new());
+                }
+                if ui.button(i18n::tr("welcome-open-file")).clicked() {
+                    self.open_file_dialog();
+                }
+            });
+        }
+
+        fn render_status_bar(&mut self, ui: &mut egui::Ui) {
+            ui.horizontal(|ui| {
+                ui.label(i18n::tr_with(
+                    "status-frame-time",
+                    &[("ms", format!("{:.1}", self.frame_time * 1000.0).into())],
+                ))
+            });
+            ui.separator();
+            if self.settings.track_latency
+                && let Some(summary) = self.latency.summary()
+            {
+                let label = ui.label(i18n::tr_with(
+                    "status-latency",
+                    &[(
+                        "ms",
+                        format!("{:.1}", summary.p95.as_secs_f64() * 1000.0).into(),
+                    )],
+                ));
+                let over = if summary.within(latency::KEYSTROKE_BUDGET) {
+                    String::new()
+                } else {
+                    format!(" ({})", i18n::tr("status-over-budget"))
+                };
+                label.on_hover_text(format!("{}{}", summary, over));
+                ui.separator();
+            }
+            // Cursor pos
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer()
+                && let Some(cursor) = self.edtr_state.get_cursor_state(buffer_id)
+            {
+                // Shown in characters, as Go to Line reads it, rather than the caret's bytes
+                let position = cursor.position();
+                let column = self
+                    .edtr_state
+                    .buffer(buffer_id)
+                    .map_or(position.column, |table| {
+                        let offset = table.position_to_offset(position);
+                        table
+                            .get_text(
+                                offset - position.column.min(offset),
+                                position.column.min(offset),
+                            )
+                            .chars()
+                            .count()
+                    });
+                ui.label(i18n::tr_with(
+                    "status-cursor",
+                    &[
+                        ("line", (position.line + 1).into()),
+                        ("column", (column + 1).into()),
+                    ],
+                ));
+            }
+            ui.separator();
+
+            match self.prompt {
+                Some(Prompt::SetMark) => {
+                    ui.label(i18n::tr("status-set-mark"));
+                    ui.separator();
+                }
+                Some(Prompt::JumpToMark) => {
+                    ui.label(i18n::tr("status-jump-to-mark"));
+                    ui.separator();
+                }
+                Some(Prompt::ChangeSurround) => {
+                    ui.label(i18n::tr("status-change-surround"));
+                    ui.separator();
+                }
+                None => {}
+            }
+
+            if self.index_job.is_some() {
+                ui.spinner();
+                ui.label(i18n::tr("status-indexing"));
+                ui.separator();
+            }
+
+            // Merge conflicts
+            if let Some((current, total)) = self.conflict_status() {
+                match current {
+                    Some(idx) => ui.label(i18n::tr_with(
+                        "status-conflict",
+                        &[("current", (idx + 1).into()), ("total", total.into())],
+                    )),
+                    None => ui.label(i18n::tr_with(
+                        "status-conflicts",
+                        &[("total", total.into())],
+                    )),
+                };
+                ui.separator();
+            }
+
+            if let Some(flash) = &self.flash {
+                ui.label(egui::RichText::new(&flash.message).color(ui.visuals().warn_fg_color));
+                ui.separator();
+            }
+
+            // Buffer info
+            ui.label("UTF-8");
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer()
+                && let Some(text) = self.edtr_state.get_buffer_text(buffer_id)
+            {
+                if file_format::has_bom(&text)
+                    && ui
+                        .small_button(i18n::tr("status-bom"))
+                        .on_hover_text(i18n::tr("status-bom-hint"))
+                        .clicked()
+                {
+                    self.toggle_bom();
+                }
+                if file_format::lacks_final_newline(&text)
+                    && ui
+                        .small_button(i18n::tr("status-no-final-newline"))
+                        .on_hover_text(i18n::tr("status-no-final-newline-hint"))
+                        .clicked()
+                {
+                    self.append_final_newline();
+                }
+            }
+            ui.label(&self.status_language);
+        }
+
+        fn render_menu_bar(&mut self, ui: &mut egui::Ui) {
+            egui::MenuBar::new().ui(ui, |ui| {
+                ui.menu_button(i18n::tr("menu-file"), |ui| {
+                    if self.menu_item(ui, &i18n::tr("menu-new"), keymap::action::NEW_FILE) {
+                        self.run_action(ui.ctx(), keymap::action::NEW_FILE);
+                    }
+
+                    if self.menu_item(ui, &i18n::tr("menu-open"), keymap::action::OPEN_FILE) {
+                        self.run_action(ui.ctx(), keymap::action::OPEN_FILE);
+                    }
+
+                    if self.menu_item(ui, &i18n::tr("menu-save"), keymap::action::SAVE_FILE) {
+                        self.run_action(ui.ctx(), keymap::action::SAVE_FILE);
+                    }
+
+                    if self.menu_item(ui, &i18n::tr("menu-reload"), keymap::action::RELOAD_FILE) {
+                        self.run_action(ui.ctx(), keymap::action::RELOAD_FILE);
+                    }
+
+                    if self.menu_item(ui, &i18n::tr("menu-close"), keymap::action::CLOSE_BUFFER) {
+                        self.run_action(ui.ctx(), keymap::action::CLOSE_BUFFER);
+                    }
+
+                    ui.separator();
+
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-check-changes"),
+                        keymap::action::CHECK_CHANGES,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::CHECK_CHANGES);
+                    }
+
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-import-settings"),
+                        keymap::action::IMPORT_SETTINGS,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::IMPORT_SETTINGS);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-check-for-updates"),
+                        keymap::action::CHECK_FOR_UPDATES,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::CHECK_FOR_UPDATES);
+                    }
+
+                    ui.menu_button(i18n::tr("menu-preferences"), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-language"));
+                            let languages = i18n::languages();
+                            let selected = languages
+                                .iter()
+                                .find(|(locale, _)| *locale == self.settings.language)
+                                .map_or_else(
+                                    || i18n::tr("prefs-language-system"),
+                                    |(_, name)| name.clone(),
+                                );
+                            egui::ComboBox::from_id_salt("language")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut self.settings.language,
+                                        String::new(),
+                                        i18n::tr("prefs-language-system"),
+                                    );
+                                    for (locale, name) in languages {
+                                        ui.selectable_value(
+                                            &mut self.settings.language,
+                                            locale,
+                                            name,
+                                        );
+                                    }
+                                });
+                        });
+                        ui.separator();
+                        ui.label(i18n::tr("prefs-last-buffer-closed"));
+                        for behavior in LastBufferClosed::ALL {
+                            ui.radio_value(
+                                &mut self.settings.on_last_buffer_closed,
+                                behavior,
+                                behavior.label(),
+                            );
+                        }
+                        ui.separator();
+                        ui.checkbox(
+                            &mut self.settings.confirm_quit,
+                            i18n::tr("prefs-confirm-quit"),
+                        );
+                        ui.checkbox(
+                            &mut self.settings.selection_toolbar,
+                            i18n::tr("prefs-selection-toolbar"),
+                        );
+                        ui.checkbox(
+                            &mut self.settings.selection_counts,
+                            i18n::tr("prefs-selection-counts"),
+                        );
+                        ui.separator();
+                        ui.label(i18n::tr("prefs-bom-on-save"));
+                        for behavior in BomOnSave::ALL {
+                            ui.radio_value(
+                                &mut self.settings.bom_on_save,
+                                behavior,
+                                behavior.label(),
+                            );
+                        }
+                        ui.checkbox(
+                            &mut self.settings.ensure_final_newline,
+                            i18n::tr("prefs-final-newline"),
+                        );
+                        ui.checkbox(
+                            &mut self.settings.trim_trailing_whitespace,
+                            i18n::tr("prefs-trim-trailing-whitespace"),
+                        );
+                        ui.checkbox(
+                            &mut self.settings.native_file_dialogs,
+                            i18n::tr("prefs-native-file-dialogs"),
+                        );
+                        ui.checkbox(
+                            &mut self.settings.insert_tabs,
+                            i18n::tr("prefs-insert-tabs"),
+                        );
+                        ui.separator();
+                        ui.label(i18n::tr("prefs-evaluation-result"));
+                        for behavior in EvaluationResult::ALL {
+                            ui.radio_value(
+                                &mut self.settings.evaluation_result,
+                                behavior,
+                                behavior.label(),
+                            );
+                        }
+                        for (label, format) in [
+                            ("prefs-date-format", &mut self.settings.date_format),
+                            ("prefs-time-format", &mut self.settings.time_format),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.label(i18n::tr(label));
+                                ui.add(egui::TextEdit::singleline(format).desired_width(180.0))
+                                    .on_hover_text(i18n::tr("prefs-date-format-hint"));
+                            });
+                        }
+                        ui.separator();
+                        if ui
+                            .checkbox(
+                                &mut self.settings.project_index,
+                                i18n::tr("prefs-project-index"),
+                            )
+                            .changed()
+                        {
+                            if self.settings.project_index {
+                                self.start_indexing(false);
+                            } else {
+                                self.index = None;
+                                self.index_job = None;
+                            }
+                        }
+                        if ui
+                            .checkbox(
+                                &mut self.settings.show_ignored_files,
+                                i18n::tr("prefs-show-ignored"),
+                            )
+                            .on_hover_text(i18n::tr("prefs-show-ignored-hint"))
+                            .changed()
+                            && self.settings.project_index
+                        {
+                            self.start_indexing(false);
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-index-size-cap"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.index_size_cap_mb)
+                                    .range(1..=4096),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-search-result-limit"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.search_result_limit)
+                                    .range(1..=10_000),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-search-max-file"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.search_max_file_kb)
+                                    .range(1..=1024),
+                            );
+                        });
+                        ui.separator();
+                        if ui
+                            .checkbox(
+                                &mut self.settings.track_latency,
+                                i18n::tr("prefs-track-latency"),
+                            )
+                            .on_hover_text(i18n::tr("prefs-track-latency-hint"))
+                            .changed()
+                        {
+                            self.latency = latency::Tracker::default();
+                        }
+                        ui.label(i18n::tr("prefs-delays"));
+                        for (label, delay) in [
+                            (
+                                "prefs-delay-highlights",
+                                &mut self.settings.highlight_delay_ms,
+                            ),
+                            (
+                                "prefs-delay-suggestions",
+                                &mut self.settings.suggestion_delay_ms,
+                            ),
+                            ("prefs-delay-autosave", &mut self.settings.autosave_delay_ms),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.label(i18n::tr(label));
+                                ui.add(egui::DragValue::new(delay).range(0..=10_000));
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-long-line-wrap"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.long_line_wrap)
+                                    .range(0..=1_000_000_000),
+                            )
+                            .on_hover_text(i18n::tr("prefs-long-line-wrap-hint"));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-frame-budget"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.frame_budget_ms)
+                                    .range(1..=16),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-cache-ceiling"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.cache_ceiling_mb)
+                                    .range(1..=4096),
+                            );
+                        });
+                        ui.separator();
+                        ui.checkbox(
+                            &mut self.settings.check_for_updates,
+                            i18n::tr("prefs-check-for-updates"),
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-update-channel"));
+                            for channel in UpdateChannel::ALL {
+                                ui.radio_value(
+                                    &mut self.settings.update_channel,
+                                    channel,
+                                    channel.label(),
+                                );
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-release-feed"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.settings.update_feed_url)
+                                    .hint_text("https://…/releases.json")
+                                    .desired_width(220.0),
+                            );
+                        });
+                        ui.separator();
+                        ui.label(i18n::tr("prefs-processes"));
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-process-timeout"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.process_timeout_ms)
+                                    .range(100..=600_000),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(i18n::tr("prefs-process-output"));
+                            ui.add(
+                                egui::DragValue::new(&mut self.settings.process_output_kb)
+                                    .range(1..=65_536),
+                            );
+                        });
+                    });
+
+                    ui.separator();
+
+                    if self.menu_item(ui, &i18n::tr("menu-exit"), keymap::action::QUIT) {
+                        self.run_action(ui.ctx(), keymap::action::QUIT);
+                    }
+                });
+
+                ui.menu_button(i18n::tr("menu-edit"), |ui| {
+                    if self.menu_item(ui, &i18n::tr("menu-undo"), keymap::action::UNDO) {
+                        self.run_action(ui.ctx(), keymap::action::UNDO);
+                    }
+                    if self.menu_item(ui, &i18n::tr("menu-redo"), keymap::action::REDO) {
+                        self.run_action(ui.ctx(), keymap::action::REDO);
+                    }
+                    if self.menu_item(ui, &i18n::tr("menu-select-all"), keymap::action::SELECT_ALL)
+                    {
+                        self.run_action(ui.ctx(), keymap::action::SELECT_ALL);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-go-to-last-edit"),
+                        keymap::action::GOTO_LAST_EDIT,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::GOTO_LAST_EDIT);
+                    }
+                    if self.menu_item(ui, &i18n::tr("menu-go-to-line"), keymap::action::GOTO_LINE) {
+                        self.run_action(ui.ctx(), keymap::action::GOTO_LINE);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-jump-to-bracket"),
+                        keymap::action::JUMP_TO_BRACKET,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::JUMP_TO_BRACKET);
+                    }
+                    for (label, action) in [
+                        ("menu-delete-line", keymap::action::DELETE_LINE),
+                        ("menu-duplicate-line", keymap::action::DUPLICATE_LINE),
+                        ("menu-move-line-up", keymap::action::MOVE_LINE_UP),
+                        ("menu-move-line-down", keymap::action::MOVE_LINE_DOWN),
+                        ("menu-join-lines", keymap::action::JOIN_LINES),
+                        ("menu-toggle-comment", keymap::action::TOGGLE_COMMENT),
+                        ("menu-sort-lines", keymap::action::SORT_LINES),
+                        (
+                            "menu-sort-lines-case-insensitive",
+                            keymap::action::SORT_LINES_CASE_INSENSITIVE,
+                        ),
+                        (
+                            "menu-sort-lines-reverse",
+                            keymap::action::SORT_LINES_REVERSE,
+                        ),
+                    ] {
+                        if self.menu_item(ui, &i18n::tr(label), action) {
+                            self.run_action(ui.ctx(), action);
+                        }
+                    }
+                    ui.menu_button(i18n::tr("menu-transform"), |ui| {
+                        for (label, action) in [
+                            ("menu-upper-case", keymap::action::UPPER_CASE),
+                            ("menu-lower-case", keymap::action::LOWER_CASE),
+                            ("menu-title-case", keymap::action::TITLE_CASE),
+                        ] {
+                            if self.menu_item(ui, &i18n::tr(label), action) {
+                                self.run_action(ui.ctx(), action);
+                            }
+                        }
+                    });
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-change-surround"),
+                        keymap::action::CHANGE_SURROUND,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::CHANGE_SURROUND);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-delete-surround"),
+                        keymap::action::DELETE_SURROUND,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::DELETE_SURROUND);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-evaluate-selection"),
+                        keymap::action::EVALUATE_SELECTION,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::EVALUATE_SELECTION);
+                    }
+
+                    ui.separator();
+
+                    if self.menu_item(ui, &i18n::tr("menu-find"), keymap::action::FIND) {
+                        self.run_action(ui.ctx(), keymap::action::FIND);
+                    }
+                    if self.menu_item(ui, &i18n::tr("menu-find-replace"), keymap::action::REPLACE) {
+                        self.run_action(ui.ctx(), keymap::action::REPLACE);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-find-word-next"),
+                        keymap::action::FIND_WORD_NEXT,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::FIND_WORD_NEXT);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-find-word-previous"),
+                        keymap::action::FIND_WORD_PREVIOUS,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::FIND_WORD_PREVIOUS);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-find-in-files"),
+                        keymap::action::FIND_IN_FILES,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::FIND_IN_FILES);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-go-to-symbol"),
+                        keymap::action::GOTO_SYMBOL,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::GOTO_SYMBOL);
+                    }
+                    if ui.button(i18n::tr("menu-rebuild-index")).clicked() {
+                        self.run_action(ui.ctx(), keymap::action::REBUILD_INDEX);
+                    }
+
+                    ui.separator();
+
+                    if ui.button(i18n::tr("menu-toggle-bom")).clicked() {
+                        self.toggle_bom();
+                    }
+                    if ui.button(i18n::tr("menu-add-final-newline")).clicked() {
+                        self.append_final_newline();
+                    }
+
+                    ui.separator();
+
+                    if self.menu_item(ui, &i18n::tr("menu-set-mark"), keymap::action::SET_MARK) {
+                        self.run_action(ui.ctx(), keymap::action::SET_MARK);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-jump-to-mark"),
+                        keymap::action::JUMP_TO_MARK,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::JUMP_TO_MARK);
+                    }
+
+                    ui.separator();
+
+                    ui.menu_button(i18n::tr("menu-merge-conflicts"), |ui| {
+                        if ui.button(i18n::tr("menu-accept-ours")).clicked() {
+                            self.resolve_conflict(conflict::Resolution::Ours);
+                        }
+                        if ui.button(i18n::tr("menu-accept-theirs")).clicked() {
+                            self.resolve_conflict(conflict::Resolution::Theirs);
+                        }
+                        if ui.button(i18n::tr("menu-accept-both")).clicked() {
+                            self.resolve_conflict(conflict::Resolution::Both);
+                        }
+                        ui.separator();
+                        if ui.button(i18n::tr("menu-next-conflict")).clicked() {
+                            self.goto_next_conflict();
+                        }
+                    });
+                });
+                ui.menu_button(i18n::tr("menu-view"), |ui| {
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-command-palette"),
+                        keymap::action::COMMAND_PALETTE,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::COMMAND_PALETTE);
+                    }
+                    if self.menu_item(
+                        ui,
+                        &i18n::tr("menu-switch-buffer"),
+                        keymap::action::SWITCH_BUFFER,
+                    ) {
+                        self.run_action(ui.ctx(), keymap::action::SWITCH_BUFFER);
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.show_line_numbers, i18n::tr("menu-line-numbers"));
+                    let mut show_notes = self.show_notes;
+                    let notes_label =
+                        self.shortcut_hint(&i18n::tr("menu-notes"), keymap::action::TOGGLE_NOTES);
+                    if ui.checkbox(&mut show_notes, notes_label).changed() {
+                        self.toggle_notes();
+                    }
+                    let marks_label =
+                        self.shortcut_hint(&i18n::tr("menu-marks"), keymap::action::TOGGLE_MARKS);
+                    ui.checkbox(&mut self.show_marks, marks_label);
+                    let mut split = self.panes.is_some();
+                    let split_label = self
+                        .shortcut_hint(&i18n::tr("menu-split-view"), keymap::action::TOGGLE_SPLIT);
+                    if ui.checkbox(&mut split, split_label).changed() {
+                        self.toggle_split();
+                    }
+                    let lua_panels: Vec<(String, String, bool)> = self
+                        .lua_panels
+                        .iter()
+                        .map(|panel| (panel.name.clone(), panel.title.clone(), panel.visible))
+                        .collect();
+                    for (name, title, mut visible) in lua_panels {
+                        if ui.checkbox(&mut visible, title).changed()
+                            && let Err(e) = self.lua_runtime.set_panel_visible(&name, visible)
+                        {
+                            eprintln!("Failed to toggle Lua panel `{}`: {:#}", name, e);
+                        }
+                    }
+                    ui.menu_button(i18n::tr("menu-pane"), |ui| {
+                        for (key, action) in [
+                            ("menu-pane-zoom-in", keymap::action::PANE_ZOOM_IN),
+                            ("menu-pane-zoom-out", keymap::action::PANE_ZOOM_OUT),
+                            ("menu-pane-toggle-wrap", keymap::action::PANE_TOGGLE_WRAP),
+                            ("menu-pane-reset", keymap::action::PANE_RESET),
+                            ("menu-pane-split-right", keymap::action::PANE_SPLIT_RIGHT),
+                            ("menu-pane-split-down", keymap::action::PANE_SPLIT_DOWN),
+                            ("menu-pane-close", keymap::action::PANE_CLOSE),
+                        ] {
+                            if self.menu_item(ui, &i18n::tr(key), action) {
+                                self.run_action(ui.ctx(), action);
+                            }
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-folding"), |ui| {
+                        for (key, action) in [
+                            ("menu-toggle-fold", keymap::action::TOGGLE_FOLD),
+                            ("menu-fold-all", keymap::action::FOLD_ALL),
+                            ("menu-unfold-all", keymap::action::UNFOLD_ALL),
+                        ] {
+                            if self.menu_item(ui, &i18n::tr(key), action) {
+                                self.run_action(ui.ctx(), action);
+                            }
+                        }
+                    });
+                    ui.separator();
+
+                    let zen_label =
+                        self.shortcut_hint(&i18n::tr("menu-zen-mode"), keymap::action::TOGGLE_ZEN);
+                    ui.checkbox(&mut self.zen_mode, zen_label);
+                    ui.checkbox(
+                        &mut self.typewriter_scrolling,
+                        i18n::tr("menu-typewriter-scrolling"),
+                    );
+                    ui.checkbox(&mut self.soft_wrap, i18n::tr("menu-soft-wrap"));
+                    ui.label(i18n::tr("menu-wrap-column"));
+                    ui.add(egui::Slider::new(&mut self.wrap_column, 40..=120));
+                    ui.separator();
+
+                    ui.label(i18n::tr("menu-font-size"));
+                    ui.add(egui::Slider::new(
+                        &mut self.font_size,
+                        MIN_FONT_SIZE..=MAX_FONT_SIZE,
+                    ));
+
+                    ui.label(i18n::tr("menu-tab-size"));
+                    ui.add(egui::Slider::new(&mut self.tab_size, 2..=8));
+                    ui.checkbox(&mut self.tab_arrows, i18n::tr("menu-tab-arrows"));
+                });
+            });
+        }
+    }
+
+    impl App {
+        /// Returns the colors of the active theme, for the editor widgets.
+        fn theme(&self) -> Theme {
+            self.gui_ctx.style_system.get_active_theme().clone()
+        }
+
+        /// Runs a named editor action, whether triggered from the keymap or a menu.
+        /// Shows the suggestions made from Lua, and asks the suggestion providers for a new one
+        /// after the active buffer was edited.
+        /// Runs the idle jobs that are due, after registering the Lua idle handlers as jobs.
+        fn run_idle_jobs(&mut self, ctx: &egui::Context, now: std::time::Instant) {
+            match self.lua_runtime.idle_handlers() {
+                Ok(handlers) => {
+                    for (name, delay) in handlers {
+                        self.idle
+                            .register(&format!("{LUA_IDLE_PREFIX}{name}"), delay);
+                    }
+                }
+                Err(e) => eprintln!("Failed to read Lua idle handlers: {:#}", e),
+            }
+            for job in self.idle.due(now) {
+                if job == COMPACT_JOB {
+                    self.edtr_state.compact_buffers(COMPACT_MIN_PIECES);
+                } else if job == TRIM_CACHES_JOB {
+                    let ceiling = self.settings.cache_ceiling_mb as usize * 1024 * 1024;
+                    self.trim_caches(ctx, ceiling);
+                } else if let Some(name) = job.strip_prefix(LUA_IDLE_PREFIX) {
+                    match self.lua_runtime.run_idle_handler(name) {
+                        Ok(true) => {}
+                        Ok(false) => self.idle.unregister(&job),
+                        Err(e) => eprintln!("Lua idle handler `{}` failed: {:#}", name, e),
+                    }
+                }
+            }
+            if let Some(deadline) = self.idle.deadline() {
+                ctx.request_repaint_after(deadline.saturating_duration_since(now));
+            }
+        }
+
+        fn update_suggestion(&mut self, ctx: &egui::Context, now: std::time::Instant) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let Some(cursor) = self.edtr_state.get_cursor_state(buffer_id) else {
+                return;
+            };
+            let position = cursor.position();
+            let has_selection = cursor.selection().is_some_and(|s| !s.is_empty());
+            let Some(offset) = self
+                .edtr_state
+                .buffers()
+                .get(&buffer_id)
+                .map(|table| table.position_to_offset(position))
+            else {
+                return;
+            };
+
+            let requested = match self.lua_runtime.take_suggestions() {
+                Ok(suggestions) => suggestions.into_iter().last(),
+                Err(e) => {
+                    eprintln!("Failed to read Lua suggestions: {}", e);
+                    None
+                }
+            };
+
+            // Providers are only asked once typing in the buffer they last saw pauses
+            let digest = self.edtr_state.content_digest(buffer_id);
+            let seen = digest.map(|digest| (buffer_id, digest));
+            if self.suggested_for.is_some_and(|(id, _)| id != buffer_id) {
+                self.suggestion_debounce.cancel();
+            } else if self.suggested_for.is_some() && self.suggested_for != seen {
+                self.suggestion_debounce.changed(now);
+            }
+            self.suggested_for = seen;
+            self.suggestion_debounce
+                .set_interval(std::time::Duration::from_millis(
+                    self.settings.suggestion_delay_ms,
+                ));
+            if let Some(deadline) = self.suggestion_debounce.deadline() {
+                ctx.request_repaint_after(deadline.saturating_duration_since(now));
+            }
+            let provided = if self.suggestion_debounce.ready(now)
+                && !has_selection
+                && self.lua_runtime.has_suggestion_providers().unwrap_or(false)
+            {
+                let line = self
+                    .edtr_state
+                    .get_buffer_text(buffer_id)
+                    .and_then(|text| text.lines().nth(position.line).map(str::to_string))
+                    .unwrap_or_default();
+                let column = position.column.min(line.len());
+                let context = lua::SuggestionContext {
+                    path: self
+                        .edtr_state
+                        .metadata(buffer_id)
+                        .and_then(|meta| meta.file_path.clone()),
+                    line: position.line,
+                    column,
+                    before: line.get(..column).unwrap_or_default().to_string(),
+                    after: line.get(column..).unwrap_or_default().to_string(),
+                };
+                match self.lua_runtime.request_suggestion(&context) {
+                    Ok(suggestion) => suggestion,
+                    Err(e) => {
+                        eprintln!("Suggestion provider failed: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            if let Some(text) = requested.or(provided) {
+                self.edtr_state
+                    .virtual_text_mut()
+                    .suggest(buffer_id, offset, text);
+            }
+        }
+
+        /// Runs the actions of virtual text clicked since the last frame.
+        fn run_virtual_text_actions(&mut self, ctx: &egui::Context) {
+            let clicks = self.edtr_state.virtual_text_mut().take_clicks();
+            for id in clicks {
+                let action = self
+                    .edtr_state
+                    .virtual_text()
+                    .get(id)
+                    .and_then(|item| item.action.clone());
+                if let Some(action) = action {
+                    self.run_action(ctx, &action);
+                }
+            }
+        }
+
+        fn run_action(&mut self, ctx: &egui::Context, action: &str) {
+            match action {
+                keymap::action::NEW_FILE => {
+                    self.edtr_state.create_buffer(String::new());
+                }
+                keymap::action::OPEN_FILE => self.open_file_dialog(),
+                keymap::action::SAVE_FILE => self.save_active_buffer(),
+                keymap::action::RELOAD_FILE => self.reload_active_buffer(),
+                keymap::action::CLOSE_BUFFER => self.close_active_buffer(ctx),
+                keymap::action::QUIT => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                keymap::action::TOGGLE_NOTES => self.toggle_notes(),
+                keymap::action::TOGGLE_ZEN => self.zen_mode = !self.zen_mode,
+                keymap::action::TOGGLE_MARKS => self.show_marks = !self.show_marks,
+                keymap::action::SET_MARK => self.prompt = Some(Prompt::SetMark),
+                keymap::action::JUMP_TO_MARK => self.prompt = Some(Prompt::JumpToMark),
+                keymap::action::UNDO => self.step_history(true),
+                keymap::action::REDO => self.step_history(false),
+                keymap::action::SELECT_ALL => self.select_all(),
+                keymap::action::GOTO_LAST_EDIT => self.goto_last_edit(),
+                keymap::action::TOGGLE_FOLD
+                | keymap::action::FOLD_ALL
+                | keymap::action::UNFOLD_ALL => self.fold(action),
+                keymap::action::JUMP_TO_BRACKET => self.jump_to_matching_bracket(),
+                keymap::action::GOTO_LINE => {
+                    if self.edtr_state.get_active_buffer().is_some() {
+                        self.goto_line = Some(String::new());
+                    }
+                }
+                keymap::action::DELETE_LINE => {
+                    self.edit_lines(|buffer_id| editor::Command::DeleteLines { buffer_id })
+                }
+                keymap::action::DUPLICATE_LINE => {
+                    self.edit_lines(|buffer_id| editor::Command::DuplicateLines { buffer_id })
+                }
+                keymap::action::MOVE_LINE_UP => {
+                    self.edit_lines(|buffer_id| editor::Command::MoveLines {
+                        buffer_id,
+                        up: true,
+                    })
+                }
+                keymap::action::MOVE_LINE_DOWN => {
+                    self.edit_lines(|buffer_id| editor::Command::MoveLines {
+                        buffer_id,
+                        up: false,
+                    })
+                }
+                keymap::action::JOIN_LINES => {
+                    self.edit_lines(|buffer_id| editor::Command::JoinLines { buffer_id })
+                }
+                keymap::action::TOGGLE_COMMENT => self.toggle_comment(),
+                keymap::action::UPPER_CASE => self.convert_case(case::Case::Upper),
+                keymap::action::LOWER_CASE => self.convert_case(case::Case::Lower),
+                keymap::action::TITLE_CASE => self.convert_case(case::Case::Title),
+                keymap::action::SORT_LINES
+                | keymap::action::SORT_LINES_CASE_INSENSITIVE
+                | keymap::action::SORT_LINES_REVERSE => {
+                    self.edit_lines(|buffer_id| editor::Command::SortLines {
+                        buffer_id,
+                        case_insensitive: action == keymap::action::SORT_LINES_CASE_INSENSITIVE,
+                        reverse: action == keymap::action::SORT_LINES_REVERSE,
+                    })
+                }
+                keymap::action::CHANGE_SURROUND => {
+                    if self.active_enclosing().is_some() {
+                        self.prompt = Some(Prompt::ChangeSurround);
+                    }
+                }
+                keymap::action::DELETE_SURROUND => self.delete_surround(),
+                keymap::action::INCREMENT_NUMBER => self.add_to_number(1, false),
+                keymap::action::DECREMENT_NUMBER => self.add_to_number(-1, false),
+                keymap::action::INCREMENT_SEQUENCE => self.add_to_number(1, true),
+                keymap::action::EVALUATE_SELECTION => self.evaluate_selection(),
+                keymap::action::INSERT_DATE => self.insert_snippet("${date}"),
+                keymap::action::INSERT_TIME => self.insert_snippet("${time}"),
+                keymap::action::COMMAND_PALETTE => self.palette = Some(CommandPalette::default()),
+                keymap::action::SWITCH_BUFFER => self.open_buffer_switcher(ctx),
+                keymap::action::TOGGLE_SPLIT => self.toggle_split(),
+                keymap::action::PANE_ZOOM_IN => self.zoom_pane(PANE_ZOOM_STEP),
+                keymap::action::PANE_ZOOM_OUT => self.zoom_pane(-PANE_ZOOM_STEP),
+                keymap::action::PANE_TOGGLE_WRAP => self.toggle_pane_wrap(),
+                keymap::action::PANE_RESET => self.reset_pane(),
+                keymap::action::PANE_SPLIT_RIGHT => self.split_pane(layout::Axis::Row),
+                keymap::action::PANE_SPLIT_DOWN => self.split_pane(layout::Axis::Column),
+                keymap::action::PANE_CLOSE => self.close_pane(),
+                keymap::action::PANE_FOCUS_LEFT => self.focus_pane_toward(layout::Direction::Left),
+                keymap::action::PANE_FOCUS_RIGHT => {
+                    self.focus_pane_toward(layout::Direction::Right)
+                }
+                keymap::action::PANE_FOCUS_UP => self.focus_pane_toward(layout::Direction::Up),
+                keymap::action::PANE_FOCUS_DOWN => self.focus_pane_toward(layout::Direction::Down),
+                keymap::action::FIND => self.open_find_bar(),
+                keymap::action::REPLACE => self.open_find_replace(),
+                keymap::action::FIND_WORD_NEXT => self.find_word_under_cursor(false),
+                keymap::action::FIND_WORD_PREVIOUS => self.find_word_under_cursor(true),
+                keymap::action::FIND_IN_FILES => self.open_project_search(SearchKind::Text),
+                keymap::action::GOTO_SYMBOL => self.open_project_search(SearchKind::Symbol),
+                keymap::action::CHECK_FOR_UPDATES => self.start_update_check(true),
+                keymap::action::RECORD_INPUT => self.toggle_input_recording(),
+                keymap::action::CHECK_CHANGES => self.start_check(),
+                keymap::action::IMPORT_SETTINGS => {
+                    self.import_wizard = Some(ImportWizard::new(import::Editor::VsCode));
+                }
+                keymap::action::REBUILD_INDEX => {
+                    self.settings.project_index = true;
+                    self.start_indexing(true);
+                }
+                other => match other.strip_prefix(keymap::action::SNIPPET_PREFIX) {
+                    Some(name) => self.insert_registered_snippet(name),
+                    None => self.invoke_lua_command(other),
+                },
+            }
+        }
+
+        /// Runs a command registered from Lua, first asking for its arguments in the palette.
+        fn invoke_lua_command(&mut self, name: &str) {
+            let registered = match self.lua_runtime.registered_commands() {
+                Ok(registered) => registered,
+                Err(e) => {
+                    eprintln!("Failed to list Lua commands: {:#}", e);
+                    return;
+                }
+            };
+            let Some(command) = registered.into_iter().find(|c| c.name == name) else {
+                eprintln!("Unknown action: {}", name);
+                return;
+            };
+            if command.prompts.is_empty() {
+                self.run_lua_command(name, &[]);
+            } else {
+                self.palette = Some(CommandPalette {
+                    pending: Some(PendingCommand {
+                        name: command.name,
+                        prompts: command.prompts,
+                        args: Vec::new(),
+                    }),
+                    ..Default::default()
+                });
+            }
+        }
+
+        fn run_lua_command(&mut self, name: &str, args: &[String]) {
+            let current = self.edtr_state.get_active_buffer();
+            if let Err(e) = self
+                .lua_runtime
+                .set_current_buffer(current)
+                .and_then(|()| self.lua_runtime.run_command(name, args))
+            {
+                eprintln!("Lua command `{}` failed: {:#}", name, e);
+            }
+        }
+
+        /// Returns the built-in actions followed by the commands registered from Lua.
+        fn palette_entries(&self) -> Vec<palette::Entry> {
+            let mut entries: Vec<palette::Entry> = keymap::action::TITLES
+                .iter()
+                .map(|(id, title)| palette::Entry {
+                    id: id.to_string(),
+                    title: i18n::action_title(id, title),
+                    description: None,
+                })
+                .collect();
+            match self.lua_runtime.registered_commands() {
+                Ok(registered) => entries.extend(
+                    registered
+                        .into_iter()
+                        // Built-in actions take precedence over Lua commands of the same name
+                        .filter(|c| !keymap::action::TITLES.iter().any(|(id, _)| *id == c.name))
+                        .map(|c| palette::Entry {
+                            id: c.name.clone(),
+                            title: i18n::action_title(&c.name, &c.name),
+                            description: c.description,
+                        }),
+                ),
+                Err(e) => eprintln!("Failed to list Lua commands: {:#}", e),
+            }
+            match self.lua_runtime.registered_snippets() {
+                Ok(snippets) => entries.extend(snippets.into_iter().map(|s| palette::Entry {
+                    id: format!("{}{}", keymap::action::SNIPPET_PREFIX, s.name),
+                    title: i18n::tr_with("palette-snippet", &[("name", s.name.into())]),
+                    description: s.description,
+                })),
+                Err(e) => eprintln!("Failed to list Lua snippets: {:#}", e),
+            }
+            entries
+        }
+
+        /// Shows the command palette, if open, and runs the command picked in it.
+        ///
+        /// The palette lists built-in actions and Lua commands filtered by fuzzy match. Up/Down
+        /// move the highlight, Tab completes the query, Enter runs the highlighted command and Esc
+        /// closes the palette. Lua commands with argument prompts ask for each argument in turn.
+        fn render_command_palette(&mut self, ctx: &egui::Context) {
+            let Some(mut state) = self.palette.take() else {
+                return;
+            };
+            let (up, down, tab, enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::ArrowUp),
+                    i.consume_key(none, egui::Key::ArrowDown),
+                    i.consume_key(none, egui::Key::Tab),
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            if escape {
+                return;
+            }
+
+            let entries = self.palette_entries();
+            let matches: Vec<palette::Entry> = match state.pending {
+                Some(_) => Vec::new(),
+                None => palette::filter(&entries, &state.query)
+                    .into_iter()
+                    .take(PALETTE_MAX_ENTRIES)
+                    .cloned()
+                    .collect(),
+            };
+            if tab
+                && state.pending.is_none()
+                && let Some(completion) =
+                    palette::complete(&matches.iter().collect::<Vec<_>>(), &state.query)
+            {
+                state.query = completion;
+            }
+            if down {
+                state.selected += 1;
+            }
+            if up {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            state.selected = state.selected.min(matches.len().saturating_sub(1));
+
+            let hint = match &state.pending {
+                Some(pending) => pending.prompts[pending.args.len()].clone(),
+                None => i18n::tr("palette-hint"),
+            };
+            let mut chosen = None;
+            egui::Window::new(i18n::tr("palette-title"))
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(480.0, 0.0))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+                .show(ctx, |ui| {
+                    if let Some(pending) = &state.pending {
+                        ui.strong(&pending.name);
+                    }
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut state.query)
+                            .hint_text(hint)
+                            .desired_width(f32::INFINITY),
+                    );
+                    input.request_focus();
+                    if input.changed() {
+                        state.selected = 0;
+                    }
+                    for (i, entry) in matches.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let label = ui.selectable_label(i == state.selected, &entry.title);
+                            if label.clicked() {
+                                chosen = Some(entry.id.clone());
+                            }
+                            if let Some(description) = &entry.description {
+                                ui.weak(description);
+                            }
+                            if let Some(shortcut) = self.keymap.label_for(&entry.id) {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| ui.weak(shortcut),
+                                );
+                            }
+                        });
+                    }
+                });
+
+            if enter {
+                match &mut state.pending {
+                    Some(pending) => {
+                        pending.args.push(std::mem::take(&mut state.query));
+                        if pending.args.len() == pending.prompts.len() {
+                            let (name, args) = (pending.name.clone(), pending.args.clone());
+                            self.run_lua_command(&name, &args);
+                            return;
+                        }
+                    }
+                    None => chosen = matches.get(state.selected).map(|entry| entry.id.clone()),
+                }
+            }
+            match chosen {
+                Some(id) => self.run_action(ctx, &id),
+                None => self.palette = Some(state),
+            }
+        }
+
+        /// Opens the buffer switcher with the previously active document highlighted, or
+        /// highlights the next document if it is open already.
+        fn open_buffer_switcher(&mut self, ctx: &egui::Context) {
+            match &mut self.buffer_switcher {
+                Some(switcher) => switcher.selected += 1,
+                None => {
+                    self.buffer_switcher = Some(BufferSwitcher {
+                        selected: 1,
+                        held: ctx.input(|i| i.modifiers.ctrl),
+                        ..BufferSwitcher::default()
+                    });
+                }
+            }
+        }
+
+        /// Shows the buffer switcher, if open, and makes the picked document active.
+        ///
+        /// Documents are listed most recently active first and filtered by a fuzzy match on
+        /// their name or path. Up/Down and Ctrl+Tab/Ctrl+Shift+Tab move the highlight, wrapping
+        /// around; Enter or a click picks a document and Esc closes the switcher. Opened with
+        /// Ctrl+Tab, releasing Control picks the highlighted document, so a quick Ctrl+Tab goes
+        /// back to the previous one.
+        fn render_buffer_switcher(&mut self, ctx: &egui::Context) {
+            let Some(mut state) = self.buffer_switcher.take() else {
+                return;
+            };
+            let (up, down, back, enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::ArrowUp),
+                    i.consume_key(none, egui::Key::ArrowDown),
+                    i.consume_key(
+                        egui::Modifiers::CTRL | egui::Modifiers::SHIFT,
+                        egui::Key::Tab,
+                    ),
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            if escape {
+                return;
+            }
+            let released = state.held && !ctx.input(|i| i.modifiers.ctrl);
+
+            self.buffer_mru = switcher::by_recent_use(&self.document_buffers(), &self.buffer_mru);
+            let items: Vec<switcher::Item<led::buffer::ID>> = self
+                .buffer_mru
+                .iter()
+                .map(|&id| switcher::Item {
+                    id,
+                    name: self.buffer_name(id),
+                    path: self
+                        .edtr_state
+                        .metadata(id)
+                        .and_then(|meta| meta.file_path.clone()),
+                })
+                .collect();
+            let matches = switcher::filter(&items, &state.query);
+            if down {
+                state.selected += 1;
+            }
+            if up || back {
+                state.selected = state
+                    .selected
+                    .checked_sub(1)
+                    .unwrap_or(matches.len().saturating_sub(1));
+            }
+            state.selected = state.selected.checked_rem(matches.len()).unwrap_or(0);
+
+            let mut chosen = None;
+            egui::Window::new(i18n::tr("menu-switch-buffer"))
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(480.0, 0.0))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+                .show(ctx, |ui| {
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut state.query)
+                            .hint_text(i18n::tr("switcher-hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    input.request_focus();
+                    if input.changed() {
+                        state.selected = 0;
+                    }
+                    for (i, item) in matches.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .selectable_label(i == state.selected, &item.name)
+                                .clicked()
+                            {
+                                chosen = Some(item.id);
+                            }
+                            if let Some(path) = &item.path {
+                                let path = Path::new(path);
+                                let path = self
+                                    .project_root
+                                    .as_deref()
+                                    .and_then(|root| path.strip_prefix(root).ok())
+                                    .unwrap_or(path);
+                                ui.weak(path.display().to_string());
+                            }
+                        });
+                    }
+                });
+
+            if enter || released {
+                chosen = chosen.or(matches.get(state.selected).map(|item| item.id));
+            }
+            match chosen {
+                Some(buffer_id) => {
+                    self.edtr_state.set_active_buffer(Some(buffer_id));
+                    self.notes_focused = false;
+                }
+                None if released => {}
+                None => self.buffer_switcher = Some(state),
+            }
+        }
+
+        /// Adds a menu button showing the action's shortcut, returning whether it was clicked.
+        fn menu_item(&self, ui: &mut egui::Ui, label: &str, action: &str) -> bool {
+            let shortcut = self.keymap.label_for(action).unwrap_or_default();
+            ui.add(egui::Button::new(label).shortcut_text(shortcut))
+                .clicked()
+        }
+
+        /// Appends the action's shortcut to a label, e.g. `Notes (Ctrl+Shift+N)`.
+        fn shortcut_hint(&self, label: &str, action: &str) -> String {
+            match self.keymap.label_for(action) {
+                Some(shortcut) => format!("{} ({})", label, shortcut),
+                None => label.to_string(),
+            }
+        }
+
+        /// Saves the active buffer to its file, first asking for a path if it has none.
+        fn save_active_buffer(&mut self) {
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer() {
+                self.save_buffer(buffer_id);
+            }
+        }
+
+        /// Saves a buffer to its file, or asks where to save it if it has none yet.
+        fn save_buffer(&mut self, buffer_id: led::buffer::ID) {
+            let file_path = self
+                .edtr_state
+                .metadata(buffer_id)
+                .and_then(|meta| meta.file_path.clone());
+            match file_path {
+                Some(path) => self.save_buffer_to(buffer_id, path),
+                None => self.pick_path(PickTarget::SaveAs(buffer_id)),
+            }
+        }
+
+        /// Writes a buffer to a file, which becomes the buffer's file.
+        fn save_buffer_to(&mut self, buffer_id: led::buffer::ID, path: String) {
+            if let Some(text) = self.edtr_state.get_buffer_text(buffer_id) {
+                let commands = file_format::prepare_for_save(buffer_id, &text, &self.settings);
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::Batch(commands));
+            }
+            if let Some(content) = self.edtr_state.get_buffer_text(buffer_id) {
+                match save::write_atomically(Path::new(&path), &content) {
+                    Ok(()) => {
+                        // Update buffer metadata
+                        if let Some(index) = &mut self.index {
+                            index.update_file(Path::new(&path), &content);
+                        }
+                        self.edtr_state.set_file_path(buffer_id, path);
+                        self.edtr_state.mark_saved(buffer_id);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to save file: {:#}", e);
+                        // TODO: Display error in UI instead of just printing to console
+                    }
+                }
+            }
+        }
+
+        /// Starts recording the input, or stops and saves the recording to the project's
+        /// `.led/recordings` directory, where tests can pick it up.
+        fn toggle_input_recording(&mut self) {
+            let Some(recorder) = self.input_recorder.take() else {
+                self.input_recorder = Some(testing::Recorder::new(std::time::Instant::now()));
+                self.flash(i18n::tr("recording-started"));
+                return;
+            };
+            let Some(root) = &self.project_root else {
+                return;
+            };
+            let seconds = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            let path = root
+                .join(notes::STATE_DIR)
+                .join(RECORDINGS_DIR)
+                .join(format!("input-{}.json", seconds));
+            let count = recorder.len();
+            match recorder.finish().save(&path) {
+                Ok(()) => self.flash(i18n::tr_with(
+                    "recording-saved",
+                    &[
+                        ("count", count.into()),
+                        ("path", path.display().to_string().into()),
+                    ],
+                )),
+                Err(e) => eprintln!("Failed to save the input recording: {:#}", e),
+            }
+        }
+
+        /// Adds or removes the byte order mark of the active buffer.
+        fn toggle_bom(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            if let Some(text) = self.edtr_state.get_buffer_text(buffer_id) {
+                let _ = self
+                    .edtr_state
+                    .execute_command(file_format::toggle_bom(buffer_id, &text));
+            }
+        }
+
+        /// Appends a line break to the active buffer if it doesn't end with one.
+        fn append_final_newline(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            if let Some(text) = self.edtr_state.get_buffer_text(buffer_id)
+                && let Some(command) = file_format::append_final_newline(buffer_id, &text)
+            {
+                let _ = self.edtr_state.execute_command(command);
+            }
+        }
+
+        fn open_file_dialog(&mut self) {
+            self.pick_path(PickTarget::Open);
+        }
+
+        /// Asks for a path in the file picker, or in a native dialog if the settings say so.
+        /// Neither blocks the editor; the path is used once picked, in a later frame.
+        fn pick_path(&mut self, target: PickTarget) {
+            if !self.settings.native_file_dialogs {
+                self.file_picker = Some(FilePicker {
+                    target,
+                    input: String::new(),
+                    selected: 0,
+                    show_hidden: false,
+                    listing: None,
+                });
+                return;
+            }
+            if self.native_dialog.is_some() {
+                return;
+            }
+            let mut dialog = rfd::AsyncFileDialog::new();
+            if let Some(dir) = self.recent_dirs.first() {
+                dialog = dialog.set_directory(dir);
+            }
+            let dialog: NativeDialog = match target {
+                PickTarget::Open => Box::pin(dialog.pick_file()),
+                PickTarget::SaveAs(_) => Box::pin(dialog.save_file()),
+            };
+            self.native_dialog = Some((target, dialog));
+        }
+
+        /// Checks whether the open native dialog was answered, and keeps frames coming until it
+        /// is.
+        fn poll_native_dialog(&mut self, ctx: &egui::Context) {
+            let Some((target, dialog)) = &mut self.native_dialog else {
+                return;
+            };
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            let std::task::Poll::Ready(handle) = dialog.as_mut().poll(&mut cx) else {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            };
+            let target = *target;
+            self.native_dialog = None;
+            if let Some(handle) = handle {
+                self.picked(target, handle.path().to_path_buf());
+            }
+        }
+
+        /// Opens or saves to a path picked in the file picker or a native dialog.
+        fn picked(&mut self, target: PickTarget, path: PathBuf) {
+            if let Some(dir) = path.parent() {
+                file_picker::remember(&mut self.recent_dirs, dir.to_path_buf());
+            }
+            let path = path.to_string_lossy().to_string();
+            match target {
+                PickTarget::Open => {
+                    self.open_path(&path);
+                }
+                PickTarget::SaveAs(buffer_id) => self.save_buffer_to(buffer_id, path),
+            }
+        }
+
+        /// Opens the files dropped onto the window from the system's file manager, or switches to
+        /// those already open. The last of them becomes the active buffer. Folders and files
+        /// that can't be read are skipped, and a toast says how many were.
+        fn open_dropped_files(&mut self, ctx: &egui::Context) {
+            let dropped: Vec<PathBuf> = ctx.input(|i| {
+                i.raw
+                    .dropped_files
+                    .iter()
+                    .filter_map(|file| file.path.clone())
+                    .collect()
+            });
+            if dropped.is_empty() {
+                return;
+            }
+            let mut skipped = 0usize;
+            for path in dropped {
+                let name = path.to_string_lossy().to_string();
+                let open = self.edtr_state.buffer_for_path(&name);
+                let Some(buffer_id) =
+                    open.or_else(|| path.is_file().then(|| self.open_path(&name)).flatten())
+                else {
+                    skipped += 1;
+                    continue;
+                };
+                self.edtr_state.set_active_buffer(Some(buffer_id));
+                if let Some(dir) = path.parent() {
+                    file_picker::remember(&mut self.recent_dirs, dir.to_path_buf());
+                }
+            }
+            self.notes_focused = false;
+            if skipped > 0 {
+                self.flash(i18n::tr_with("drop-skipped", &[("count", skipped.into())]));
+            }
+        }
+
+        /// Dims the window while files are dragged over it, saying they will be opened.
+        fn render_drop_hint(&self, ctx: &egui::Context) {
+            if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+                return;
+            }
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_hint"),
+            ));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, DROP_HINT_BG);
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                i18n::tr("drop-hint"),
+                egui::FontId::proportional(20.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        /// Shows the Go to Line window, if open, and moves the caret of the active buffer to the
+        /// location typed in it, scrolled to the middle of the view.
+        ///
+        /// The location is a line or `line:column`, checked against the buffer as it is typed.
+        /// Enter goes there and Esc closes the window.
+        fn render_goto_line(&mut self, ctx: &egui::Context) {
+            let Some(mut input) = self.goto_line.take() else {
+                return;
+            };
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let (enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            if escape {
+                return;
+            }
+
+            let text = self
+                .edtr_state
+                .get_buffer_text(buffer_id)
+                .unwrap_or_default();
+            let lines = text.split('\n').count();
+            let mut target = Err(goto::Invalid::Syntax);
+            egui::Window::new(i18n::tr("goto-line-title"))
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(320.0, 0.0))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+                .show(ctx, |ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut input)
+                            .hint_text(i18n::tr_with("goto-line-hint", &[("count", lines.into())]))
+                            .desired_width(f32::INFINITY),
+                    )
+                    .request_focus();
+                    target = goto::parse(&input, &text);
+                    let problem = match target {
+                        Err(goto::Invalid::Line { lines }) => Some(i18n::tr_with(
+                            "goto-line-out-of-range",
+                            &[("count", lines.into())],
+                        )),
+                        Err(goto::Invalid::Syntax) if !input.trim().is_empty() => {
+                            Some(i18n::tr("goto-line-invalid"))
+                        }
+                        _ => None,
+                    };
+                    if let Some(problem) = problem {
+                        ui.colored_label(ui.visuals().error_fg_color, problem);
+                    }
+                });
+
+            match target {
+                Ok(position) if enter => {
+                    let _ = self
+                        .edtr_state
+                        .execute_command(editor::Command::MoveCursor {
+                            buffer_id,
+                            position,
+                        });
+                    self.center_cursor = true;
+                }
+                _ => self.goto_line = Some(input),
+            }
+        }
+
+        /// Shows the file picker, if open, and opens or saves to the path picked in it.
+        ///
+        /// The typed path is completed against the listing of its directory, filtered by fuzzy
+        /// match. Up/Down move the highlight, Tab completes the name, Enter enters the
+        /// highlighted directory or picks the file and Esc closes the picker. When saving, Enter
+        /// picks the typed name unless it is that of a directory.
+        fn render_file_picker(&mut self, ctx: &egui::Context) {
+            let Some(mut picker) = self.file_picker.take() else {
+                return;
+            };
+            let (up, down, tab, enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::ArrowUp),
+                    i.consume_key(none, egui::Key::ArrowDown),
+                    i.consume_key(none, egui::Key::Tab),
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            if escape {
+                return;
+            }
+
+            let base = self.project_root.clone().unwrap_or_default();
+            let (dir, name) = file_picker::split(&picker.input, &base);
+            let name = name.to_string();
+            let listed = picker
+                .listing
+                .as_ref()
+                .is_some_and(|(listed, hidden, _)| *listed == dir && *hidden == picker.show_hidden);
+            if !listed {
+                let entries = file_picker::list(&dir, picker.show_hidden).unwrap_or_default();
+                picker.listing = Some((dir.clone(), picker.show_hidden, entries));
+            }
+            let matches: Vec<file_picker::Entry> = picker
+                .listing
+                .as_ref()
+                .map(|(_, _, entries)| {
+                    file_picker::filter(entries, &name)
+                        .into_iter()
+                        .take(FILE_PICKER_MAX_ENTRIES)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            if down {
+                picker.selected += 1;
+            }
+            if up {
+                picker.selected = picker.selected.saturating_sub(1);
+            }
+            picker.selected = picker.selected.min(matches.len().saturating_sub(1));
+            if tab && let Some(entry) = matches.get(picker.selected) {
+                picker.input = file_picker::complete(&picker.input, entry);
+                picker.selected = 0;
+                ctx.request_repaint();
+            }
+
+            let title = match picker.target {
+                PickTarget::Open => i18n::tr("file-picker-open"),
+                PickTarget::SaveAs(_) => i18n::tr("file-picker-save"),
+            };
+            let mut chosen = None;
+            egui::Window::new(&title)
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(480.0, 0.0))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+                .show(ctx, |ui| {
+                    ui.strong(&title);
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut picker.input)
+                            .hint_text(i18n::tr("file-picker-hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    input.request_focus();
+                    if input.changed() {
+                        picker.selected = 0;
+                    }
+                    ui.horizontal_wrapped(|ui| {
+                        ui.checkbox(&mut picker.show_hidden, i18n::tr("file-picker-show-hidden"));
+                        if !self.recent_dirs.is_empty() {
+                            ui.separator();
+                            ui.weak(i18n::tr("file-picker-recent"));
+                        }
+                        for recent in &self.recent_dirs {
+                            let label = recent.file_name().map_or_else(
+                                || recent.to_string_lossy(),
+                                |name| name.to_string_lossy(),
+                            );
+                            let button = ui
+                                .small_button(label)
+                                .on_hover_text(recent.to_string_lossy());
+                            if button.clicked() {
+                                picker.input = format!("{}/", recent.to_string_lossy());
+                                picker.selected = 0;
+                            }
+                        }
+                    });
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(320.0)
+                        .show(ui, |ui| {
+                            for (i, entry) in matches.iter().enumerate() {
+                                let label = if entry.is_dir {
+                                    format!("{}/", entry.name)
+                                } else {
+                                    entry.name.clone()
+                                };
+                                let row = ui.selectable_label(i == picker.selected, label);
+                                if i == picker.selected && (up || down) {
+                                    row.scroll_to_me(None);
+                                }
+                                if row.clicked() {
+                                    chosen = Some(entry.clone());
+                                }
+                            }
+                        });
+                });
+
+            let mut picked = None;
+            if enter {
+                let highlighted = matches.get(picker.selected);
+                match picker.target {
+                    PickTarget::Open => chosen = highlighted.cloned(),
+                    PickTarget::SaveAs(_) => match highlighted {
+                        Some(entry) if name.is_empty() || entry.name == name => {
+                            chosen = Some(entry.clone());
+                        }
+                        _ if !name.is_empty() => picked = Some(dir.join(&name)),
+                        _ => {}
+                    },
+                }
+            }
+            match chosen {
+                Some(entry) if entry.is_dir => {
+                    picker.input = file_picker::complete(&picker.input, &entry);
+                    picker.selected = 0;
+                }
+                Some(entry) => picked = Some(dir.join(&entry.name)),
+                None => {}
+            }
+            match picked {
+                Some(path) => self.picked(picker.target, path),
+                None => self.file_picker = Some(picker),
+            }
+        }
+
+        /// Reopens the files of the last session with their carets and scroll positions, and
+        /// makes the file that was active so again. Files that can't be read anymore are
+        /// skipped; carets past the end of a file that changed since are clamped.
+        fn restore_files(&mut self, files: Vec<session::OpenFile>, active: Option<String>) {
+            for file in files {
+                if self.edtr_state.buffer_for_path(&file.path).is_some() {
+                    continue;
+                }
+                let Some(buffer_id) = self.open_path(&file.path) else {
+                    continue;
+                };
+                if let Some(table) = self.edtr_state.buffer(buffer_id) {
+                    let position = table.clamp_position(file.cursor);
+                    let _ = self
+                        .edtr_state
+                        .execute_command(editor::Command::MoveCursor {
+                            buffer_id,
+                            position,
+                        });
+                }
+                self.restored_scroll
+                    .insert(buffer_id, egui::vec2(file.scroll[0], file.scroll[1]));
+            }
+            if let Some(buffer_id) = active.and_then(|path| self.edtr_state.buffer_for_path(&path))
+            {
+                self.edtr_state.set_active_buffer(Some(buffer_id));
+            }
+        }
+
+        /// Opens the files named on the command line, or switches to those already open, with
+        /// the caret at the location given after a file. A file that doesn't exist yet gets an
+        /// empty buffer, which saving creates it from. The last file becomes the active buffer.
+        fn open_named_files(&mut self, files: Vec<launch::FileArg>) {
+            for file in files {
+                let path = std::path::absolute(&file.path).unwrap_or(file.path);
+                let name = path.to_string_lossy().to_string();
+                let buffer_id = match self.edtr_state.buffer_for_path(&name) {
+                    Some(buffer_id) => Some(buffer_id),
+                    None if !path.exists() => {
+                        let buffer_id = self.edtr_state.create_buffer(String::new());
+                        self.edtr_state.set_file_path(buffer_id, name);
+                        Some(buffer_id)
+                    }
+                    None => self.open_path(&name),
+                };
+                let Some(buffer_id) = buffer_id else {
+                    continue;
+                };
+                self.edtr_state.set_active_buffer(Some(buffer_id));
+                let (Some(location), Some(text)) =
+                    (file.location, self.edtr_state.get_buffer_text(buffer_id))
+                else {
+                    continue;
+                };
+                // A line past the end goes to the last line
+                let position = goto::parse(&location, &text).or_else(|invalid| match invalid {
+                    goto::Invalid::Line { lines } => goto::parse(&lines.to_string(), &text),
+                    goto::Invalid::Syntax => Err(invalid),
+                });
+                let Ok(position) = position else {
+                    eprintln!("Not a line or line:column: {}", location);
+                    continue;
+                };
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::MoveCursor {
+                        buffer_id,
+                        position,
+                    });
+                // The caret is brought into view rather than where the last session scrolled to
+                self.restored_scroll.remove(&buffer_id);
+                self.center_cursor = true;
+            }
+        }
+
+        /// Opens a file in a new buffer and makes it active, restoring its marks.
+        fn open_path(&mut self, path: &str) -> Option<led::buffer::ID> {
+            match fs::read_to_string(path) {
+                Ok(content) => {
+                    let buffer_id = self.edtr_state.create_buffer(content);
+                    // Store file path in buffer metadata
+                    self.edtr_state.set_file_path(buffer_id, path.to_string());
+                    self.edtr_state.open_file_marks(buffer_id, path);
+                    Some(buffer_id)
+                }
+                Err(e) => {
+                    eprintln!("Failed to open file: {}", e);
+                    // TODO: Display error in UI instead of just printing to console
+                    None
+                }
+            }
+        }
+
+        /// Opens a file, or switches to it if it is already open, and moves the caret.
+        ///
+        /// Positions past the end of the file are clamped, since the file may have changed
+        /// since the position was recorded.
+        fn goto_location(&mut self, path: &Path, position: Position) {
+            let path = path.to_string_lossy().to_string();
+            let open = self.edtr_state.buffer_for_path(&path);
+            let Some(buffer_id) = open.or_else(|| self.open_path(&path)) else {
+                return;
+            };
+            let Some(table) = self.edtr_state.buffer(buffer_id) else {
+                return;
+            };
+            let position = table.clamp_position(position);
+            self.edtr_state.set_active_buffer(Some(buffer_id));
+            self.notes_focused = Some(buffer_id) == self.notes_buffer_id();
+            let _ = self
+                .edtr_state
+                .execute_command(editor::Command::MoveCursor {
+                    buffer_id,
+                    position,
+                });
+        }
+
+        /// Goes back to where the text was last edited, in whichever buffer that was. Repeating
+        /// it goes further back through the recent edits.
+        /// Undoes or redoes the most recent edit of the active buffer and brings the caret into
+        /// view where it was made.
+        fn step_history(&mut self, undo: bool) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let stepped = if undo {
+                self.edtr_state.undo(buffer_id)
+            } else {
+                self.edtr_state.redo(buffer_id)
+            };
+            match stepped {
+                Ok(stepped) => self.reveal_cursor |= stepped,
+                Err(e) => eprintln!("Failed to {}: {:#}", if undo { "undo" } else { "redo" }, e),
+            }
+        }
+
+        /// Selects the whole text of the active buffer.
+        fn select_all(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            if let Err(e) = self
+                .edtr_state
+                .execute_command(editor::Command::SelectAll { buffer_id })
+            {
+                eprintln!("Failed to select all: {:#}", e);
+            }
+        }
+
+        /// Runs a line command on the lines of the active buffer under the cursor or selection.
+        fn edit_lines(&mut self, command: impl FnOnce(led::types::buffer::ID) -> editor::Command) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            if let Err(e) = self.edtr_state.execute_command(command(buffer_id)) {
+                eprintln!("Failed to edit lines: {:#}", e);
+            }
+        }
+
+        /// Converts the text selected in the active buffer to a letter case, or says that
+        /// nothing is selected.
+        fn convert_case(&mut self, case: case::Case) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let selected = self
+                .edtr_state
+                .get_cursor_state(buffer_id)
+                .and_then(|cursor| cursor.selection())
+                .is_some_and(|selection| !selection.is_empty());
+            if !selected {
+                self.flash(i18n::tr("case-needs-selection"));
+                return;
+            }
+            if let Err(e) = case::convert_selection(&mut self.edtr_state, buffer_id, case) {
+                eprintln!("Failed to convert case: {:#}", e);
+            }
+        }
+
+        /// Comments out or uncomments the lines under the cursor or selection of the active
+        /// buffer, or says why not when its language has no line comments.
+        fn toggle_comment(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let supported = self
+                .edtr_state
+                .metadata(buffer_id)
+                .is_some_and(|meta| meta.line_comment().is_some());
+            if !supported {
+                self.flash(i18n::tr("comment-unsupported"));
+                return;
+            }
+            if let Err(e) = comment::toggle(&mut self.edtr_state, buffer_id) {
+                eprintln!("Failed to toggle comments: {:#}", e);
+            }
+        }
+
+        fn goto_last_edit(&mut self) {
+            let Some(location) = self.edtr_state.edits_mut().previous() else {
+                return;
+            };
+            let Some(table) = self.edtr_state.buffer(location.buffer_id) else {
+                return;
+            };
+            let position = table.offset_to_position(location.offset.min(table.len()));
+            self.edtr_state.set_active_buffer(Some(location.buffer_id));
+            self.notes_focused = Some(location.buffer_id) == self.notes_buffer_id();
+            self.show_notes |= self.notes_focused;
+            let _ = self
+                .edtr_state
+                .execute_command(editor::Command::MoveCursor {
+                    buffer_id: location.buffer_id,
+                    position,
+                });
+            self.reveal_cursor = true;
+        }
+
+        /// Folds or unfolds the region around the caret of the active buffer, or all of its
+        /// regions, as a folding action asks.
+        fn fold(&mut self, action: &str) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            match action {
+                keymap::action::FOLD_ALL => {
+                    self.edtr_state.fold_all(buffer_id, self.tab_size);
+                }
+                keymap::action::UNFOLD_ALL => {
+                    self.edtr_state.folds_mut().unfold_all(buffer_id);
+                }
+                _ => {
+                    let line = self
+                        .edtr_state
+                        .get_cursor_state(buffer_id)
+                        .map_or(0, |cursor| cursor.position().line);
+                    if !self.edtr_state.toggle_fold(buffer_id, line, self.tab_size) {
+                        self.flash(i18n::tr("nothing-to-fold"));
+                    }
+                }
+            }
+        }
+
+        /// Moves the caret of the active buffer to the bracket matching the one next to it, or
+        /// says that there is none.
+        fn jump_to_matching_bracket(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            match brackets::jump(&mut self.edtr_state, buffer_id) {
+                Ok(true) => self.reveal_cursor = true,
+                Ok(false) => self.flash(i18n::tr("no-matching-bracket")),
+                Err(e) => eprintln!("Failed to jump to the matching bracket: {:#}", e),
+            }
+        }
+
+        /// Adds to the number under or after the caret in the active buffer, leaving the caret at
+        /// its end.
+        ///
+        /// With several lines selected, the first number on each line is added to instead, and
+        /// the lines stay selected; `sequence` makes each line count one `amount` further than
+        /// the one before.
+        fn add_to_number(&mut self, amount: i64, sequence: bool) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let (Some(text), Some(cursor)) = (
+                self.edtr_state.get_buffer_text(buffer_id),
+                self.edtr_state.get_cursor_state(buffer_id),
+            ) else {
+                return;
+            };
+            let position = cursor.position();
+            let lines = cursor
+                .selection()
+                .map(|selection| selection.ordered())
+                .filter(|selection| selection.start.line != selection.end.line);
+
+            let (commands, caret) = match lines {
+                Some(Range { start, end }) => {
+                    let commands = number::add_to_lines(
+                        buffer_id, &text, start.line, end.line, amount, sequence,
+                    );
+                    (commands, None)
+                }
+                None => {
+                    let line = text.split('\n').nth(position.line).unwrap_or_default();
+                    let Some(found) = number::number_at(line, position.column) else {
+                        return;
+                    };
+                    let Some(new) = number::add(&line[found.clone()], amount) else {
+                        return;
+                    };
+                    let column = found.start + new.len();
+                    let line_start =
+                        self.edtr_state.buffers()[&buffer_id].position_to_offset(Position {
+                            line: position.line,
+                            column: 0,
+                        });
+                    let range = line_start + found.start..line_start + found.end;
+                    let commands = number::replace(buffer_id, range, new).to_vec();
+                    (
+                        commands,
+                        Some(Position {
+                            line: position.line,
+                            column,
+                        }),
+                    )
+                }
+            };
+            if commands.is_empty() {
+                return;
+            }
+            if let Err(e) = self
+                .edtr_state
+                .execute_command(editor::Command::Batch(commands))
+            {
+                eprintln!("Failed to change number: {:#}", e);
+                return;
+            }
+            let follow_up = match (caret, lines) {
+                (Some(position), _) => editor::Command::MoveCursor {
+                    buffer_id,
+                    position,
+                },
+                (None, Some(Range { start, end })) => {
+                    let text = self
+                        .edtr_state
+                        .get_buffer_text(buffer_id)
+                        .unwrap_or_default();
+                    let end_column = text.split('\n').nth(end.line).map_or(0, str::len);
+                    editor::Command::SetSelection {
+                        buffer_id,
+                        range: Range {
+                            start: Position {
+                                line: start.line,
+                                column: 0,
+                            },
+                            end: Position {
+                                line: end.line,
+                                column: end_column,
+                            },
+                        },
+                    }
+                }
+                (None, None) => return,
+            };
+            let _ = self.edtr_state.execute_command(follow_up);
+        }
+
+        /// Starts indexing the project in a background thread, unless it is already running.
+        ///
+        /// Normally the stored index is loaded and only changed files are read again; `rebuild`
+        /// discards it and reads every file.
+        fn start_indexing(&mut self, rebuild: bool) {
+            let Some(root) = self.project_root.clone() else {
+                return;
+            };
+            if self.index_job.is_some() {
+                return;
+            }
+            let size_cap = self.settings.index_size_cap_mb * 1024 * 1024;
+            let include_ignored = self.settings.show_ignored_files;
+            let excluded_dirs = self.settings.excluded_dirs.clone();
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                let mut project_index = if rebuild {
+                    index::Index::new(&root)
+                } else {
+                    index::Index::load(&root).unwrap_or_else(|e| {
+                        eprintln!("Discarding unreadable project index: {:#}", e);
+                        index::Index::new(&root)
+                    })
+                };
+                project_index.set_excluded_dirs(excluded_dirs);
+                let result = project_index
+                    .refresh(size_cap, include_ignored)
+                    .and_then(|_| project_index.save())
+                    .map(|_| project_index);
+                let _ = sender.send(result);
+            });
+            self.index_job = Some(receiver);
+        }
+
+        /// Picks up the index once the background build has finished.
+        fn poll_indexing(&mut self) {
+            let Some(receiver) = &self.index_job else {
+                return;
+            };
+            match receiver.try_recv() {
+                Ok(Ok(project_index)) => {
+                    self.index = Some(project_index);
+                    self.index_job = None;
+                    // Search again with the fresh index
+                    if let Some(search) = &mut self.project_search {
+                        search.searched = None;
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("Failed to index project: {:#}", e);
+                    self.index_job = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.index_job = None,
+            }
+        }
+
+        /// Starts formatting and linting the files with unsaved or uncommitted changes in the
+        /// background, with the formatters and linters set for their extensions.
+        fn start_check(&mut self) {
+            let Some(root) = self.project_root.clone() else {
+                self.flash(i18n::tr("check-no-project"));
+                return;
+            };
+            if self.check_job.is_some() {
+                return;
+            }
+            if self.settings.formatters.is_empty() && self.settings.linters.is_empty() {
+                self.flash(i18n::tr("check-no-tools"));
+                return;
+            }
+            let unsaved: HashMap<PathBuf, String> = self
+                .document_buffers()
+                .into_iter()
+                .filter_map(|buffer_id| {
+                    let meta = self.edtr_state.metadata(buffer_id)?;
+                    if !meta.modified {
+                        return None;
+                    }
+                    let path = PathBuf::from(meta.file_path.as_ref()?);
+                    Some((path, self.edtr_state.get_buffer_text(buffer_id)?))
+                })
+                .collect();
+            let formatters = self.settings.formatters.clone();
+            let linters = self.settings.linters.clone();
+            let limits = self.process_limits();
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                let env = shell::login_environment()
+                    .for_project(&root)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Failed to read the project environment: {:#}", e);
+                        shell::login_environment().clone()
+                    });
+                let tools = check::Tools {
+                    formatters,
+                    linters,
+                    env,
+                    limits,
+                };
+                let _ = sender.send(check::check(&root, unsaved, &tools));
+            });
+            self.check_job = Some(receiver);
+            self.flash(i18n::tr("check-running"));
+        }
+
+        /// Once the background format and lint run has finished, applies the formatting as edits
+        /// and lists the problems.
+        ///
+        /// Files that aren't open are opened to be formatted, and buffers edited while the
+        /// formatter ran are left alone. Nothing is saved, so the changes can be reviewed and
+        /// undone.
+        fn poll_check(&mut self) {
+            let Some(receiver) = &self.check_job else {
+                return;
+            };
+            let reports = match receiver.try_recv() {
+                Ok(reports) => reports,
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.check_job = None;
+                    return;
+                }
+            };
+            self.check_job = None;
+
+            let active = self.edtr_state.get_active_buffer();
+            let mut formatted: usize = 0;
+            let mut errors = Vec::new();
+            self.problems.clear();
+            for report in &reports {
+                errors.extend(report.errors.iter().cloned());
+                self.problems.extend(report.findings.iter().cloned());
+                let Some(text) = &report.formatted else {
+                    continue;
+                };
+                let path = report.path.to_string_lossy().to_string();
+                let open = self.edtr_state.buffer_for_path(&path);
+                if let Some(buffer_id) = open.or_else(|| self.open_path(&path))
+                    && self.edtr_state.matches_text(buffer_id, &report.text)
+                {
+                    match self.edtr_state.replace_text(buffer_id, text) {
+                        Ok(_) => formatted += 1,
+                        Err(e) => errors.push(format!("{}: {:#}", path, e)),
+                    }
+                }
+            }
+            // Opening files to format them doesn't switch away from the buffer being edited
+            self.edtr_state.set_active_buffer(active);
+
+            for error in &errors {
+                eprintln!("Format and lint: {}", error);
+            }
+            let message = match errors.first() {
+                Some(error) => i18n::tr_with("check-failed", &[("error", error.as_str().into())]),
+                None => i18n::tr_with(
+                    "check-done",
+                    &[
+                        ("formatted", formatted.into()),
+                        ("files", reports.len().into()),
+                        ("problems", self.problems.len().into()),
+                    ],
+                ),
+            };
+            self.flash(message);
+        }
+
+        /// Lists what the last format and lint run found. Clicking a problem goes to it.
+        fn render_problems_panel(&mut self, ui: &mut egui::Ui) {
+            ui.horizontal(|ui| {
+                ui.strong(i18n::tr("problems-title"));
+                if ui.button(i18n::tr("button-dismiss")).clicked() {
+                    self.problems.clear();
+                }
+            });
+            ui.separator();
+
+            let mut target = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for finding in &self.problems {
+                    let path = self
+                        .project_root
+                        .as_deref()
+                        .and_then(|root| finding.path.strip_prefix(root).ok())
+                        .unwrap_or(&finding.path);
+                    let label = format!(
+                        "{}:{}:{}  {}",
+                        path.display(),
+                        finding.position.line + 1,
+                        finding.position.column + 1,
+                        finding.message
+                    );
+                    if ui.selectable_label(false, label).clicked() {
+                        target = Some((finding.path.clone(), finding.position));
+                    }
+                }
+            });
+            if let Some((path, position)) = target {
+                self.goto_location(&path, position);
+                self.reveal_cursor = true;
+            }
+        }
+
+        /// Starts reading the release feed in the background.
+        ///
+        /// # Arguments
+        ///
+        /// * `manual` - Whether the user asked, in which case "no update" is reported too.
+        fn start_update_check(&mut self, manual: bool) {
+            if matches!(
+                self.update_status,
+                UpdateStatus::Checking { .. } | UpdateStatus::Downloading { .. }
+            ) {
+                return;
+            }
+            let feed_url = self.settings.update_feed_url.trim().to_string();
+            if feed_url.is_empty() {
+                if manual {
+                    self.update_status = UpdateStatus::Message(i18n::tr("update-no-feed"));
+                }
+                return;
+            }
+            let channel = self.settings.update_channel;
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                let _ = sender.send(update::check(&feed_url, channel));
+            });
+            self.update_status = UpdateStatus::Checking {
+                job: receiver,
+                manual,
+            };
+        }
+
+        /// Picks up the result of a running update check or download.
+        fn poll_update(&mut self) {
+            let status = std::mem::replace(&mut self.update_status, UpdateStatus::Idle);
+            self.update_status = match status {
+                UpdateStatus::Checking { job, manual } => match job.try_recv() {
+                    Ok(Ok(Some(release))) => UpdateStatus::Available(release),
+                    Ok(Ok(None)) if manual => UpdateStatus::Message(i18n::tr_with(
+                        "update-up-to-date",
+                        &[("version", update::CURRENT_VERSION.into())],
+                    )),
+                    Ok(Err(e)) if manual => UpdateStatus::Message(format!("{:#}", e)),
+                    Ok(Ok(None)) | Ok(Err(_)) | Err(mpsc::TryRecvError::Disconnected) => {
+                        UpdateStatus::Idle
+                    }
+                    Err(mpsc::TryRecvError::Empty) => UpdateStatus::Checking { job, manual },
+                },
+                UpdateStatus::Downloading { release, job } => match job.try_recv() {
+                    Ok(Ok(())) => UpdateStatus::Staged(release),
+                    Ok(Err(e)) => UpdateStatus::Message(format!("{:#}", e)),
+                    Err(mpsc::TryRecvError::Empty) => UpdateStatus::Downloading { release, job },
+                    Err(mpsc::TryRecvError::Disconnected) => UpdateStatus::Idle,
+                },
+                other => other,
+            };
+        }
+
+        /// Shows what the update checker found, and lets the user download an update.
+        fn render_update_status(&mut self, ctx: &egui::Context) {
+            let mut dismiss = false;
+            let mut download = None;
+            let title = match &self.update_status {
+                UpdateStatus::Idle | UpdateStatus::Checking { .. } => return,
+                UpdateStatus::Available(_) | UpdateStatus::Downloading { .. } => {
+                    i18n::tr("update-available-title")
+                }
+                UpdateStatus::Staged(_) => i18n::tr("update-ready-title"),
+                UpdateStatus::Message(_) => i18n::tr("updates-title"),
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+                .show(ctx, |ui| {
+                    match &self.update_status {
+                        UpdateStatus::Available(release)
+                        | UpdateStatus::Downloading { release, .. } => {
+                            ui.label(i18n::tr_with(
+                                "update-available",
+                                &[
+                                    ("version", release.version.to_string().into()),
+                                    ("current", update::CURRENT_VERSION.into()),
+                                ],
+                            ));
+                            let preview = release.changelog_preview(CHANGELOG_PREVIEW_LINES);
+                            if !preview.is_empty() {
+                                ui.separator();
+                                ui.label(preview);
+                            }
+                        }
+                        UpdateStatus::Staged(release) => {
+                            ui.label(i18n::tr_with(
+                                "update-staged",
+                                &[("version", release.version.to_string().into())],
+                            ));
+                        }
+                        UpdateStatus::Message(message) => {
+                            ui.label(message);
+                        }
+                        UpdateStatus::Idle | UpdateStatus::Checking { .. } => {}
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| match &self.update_status {
+                        UpdateStatus::Available(release) => {
+                            if update::can_install() && release.asset().is_some() {
+                                if ui.button(i18n::tr("update-download")).clicked() {
+                                    download = Some(release.clone());
+                                }
+                            } else {
+                                ui.label(i18n::tr("update-manual"));
+                            }
+                            if ui.button(i18n::tr("update-later")).clicked() {
+                                dismiss = true;
+                            }
+                        }
+                        UpdateStatus::Downloading { .. } => {
+                            ui.spinner();
+                            ui.label(i18n::tr("update-downloading"));
+                        }
+                        _ => {
+                            if ui.button(i18n::tr("button-ok")).clicked() {
+                                dismiss = true;
+                            }
+                        }
+                    });
+                });
+
+            if let Some(release) = download {
+                let (sender, receiver) = mpsc::channel();
+                let staged = release.clone();
+                std::thread::spawn(move || {
+                    let _ = sender.send(update::download(&staged));
+                });
+                self.update_status = UpdateStatus::Downloading {
+                    release,
+                    job: receiver,
+                };
+            } else if dismiss {
+                // A staged update is still installed on quit
+                if !matches!(self.update_status, UpdateStatus::Staged(_)) {
+                    self.update_status = UpdateStatus::Idle;
+                }
+            }
+        }
+
+        /// Stores the index, including files reindexed on save, for the next run.
+        fn save_index(&self) {
+            if let Some(project_index) = &self.index
+                && let Err(e) = project_index.save()
+            {
+                eprintln!("Failed to save project index: {:#}", e);
+            }
+        }
+
+        fn open_project_search(&mut self, kind: SearchKind) {
+            self.project_search = Some(ProjectSearch {
+                kind,
+                query: String::new(),
+                selected: 0,
+                include: String::new(),
+                exclude: String::new(),
+                searched: None,
+                hits: Vec::new(),
+                truncated: false,
+                filter_error: None,
+                replacing: false,
+                replacement: String::new(),
+                regex: false,
+                match_case: false,
+                previewed: None,
+                changes: Vec::new(),
+                changes_truncated: false,
+                query_error: None,
+            });
+        }
+
+        /// Shows the find-in-files or go-to-symbol window, if open, and jumps to the picked hit.
+        ///
+        /// Hits come from the project index, are refreshed as the query changes and are listed
+        /// under the path of their file. Symbols are grouped by file in order of their best match.
+        /// Up/Down move the highlight, Enter opens the highlighted hit and Esc closes the window.
+        fn render_project_search(&mut self, ctx: &egui::Context) {
+            let Some(mut search) = self.project_search.take() else {
+                return;
+            };
+            let (up, down, enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::ArrowUp),
+                    i.consume_key(none, egui::Key::ArrowDown),
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            if escape {
+                return;
+            }
+
+            if let Some(project_index) = &self.index
+                && let Some(root) = &self.project_root
+                && search.searched.as_ref() != Some(&search.key())
+            {
+                let limit = self.settings.search_result_limit as usize;
+                search.truncated = false;
+                search.filter_error = None;
+                search.hits = match search.kind {
+                    SearchKind::Text => match index::Filter::new(
+                        root,
+                        &search.include,
+                        &search.exclude,
+                        self.settings.search_max_file_kb * 1024,
+                    ) {
+                        Ok(filter) => {
+                            let found = project_index.find_text(&search.query, &filter, limit);
+                            search.truncated = found.truncated;
+                            found.hits
+                        }
+                        Err(e) => {
+                            search.filter_error = Some(e.to_string());
+                            Vec::new()
+                        }
+                    },
+                    SearchKind::Symbol => {
+                        index::group_by_file(project_index.find_symbols(&search.query, limit))
+                    }
+                };
+                search.searched = Some(search.key());
+                search.selected = 0;
+            }
+            if search.replacing && search.previewed.as_ref() != Some(&search.preview_key()) {
+                self.preview_project_replace(&mut search);
+            }
+            if down {
+                search.selected += 1;
+            }
+            if up {
+                search.selected = search.selected.saturating_sub(1);
+            }
+            search.selected = search.selected.min(search.hits.len().saturating_sub(1));
+
+            let (title, hint) = match search.kind {
+                SearchKind::Text => (
+                    i18n::tr("search-files-title"),
+                    i18n::tr("search-files-hint"),
+                ),
+                SearchKind::Symbol => (
+                    i18n::tr("search-symbol-title"),
+                    i18n::tr("search-symbol-hint"),
+                ),
+            };
+            let mut chosen = None;
+            let mut build = false;
+            let mut apply = false;
+            egui::Window::new(&title)
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(560.0, 0.0))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.strong(&title);
+                        if search.kind == SearchKind::Text {
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    if ui
+                                        .selectable_label(search.replacing, "⇄")
+                                        .on_hover_text(i18n::tr("find-toggle-replace"))
+                                        .clicked()
+                                    {
+                                        search.replacing = !search.replacing;
+                                    }
+                                },
+                            );
+                        }
+                    });
+                    let input = ui.add(
+                        egui::TextEdit::singleline(&mut search.query)
+                            .hint_text(hint)
+                            .desired_width(f32::INFINITY),
+                    );
+                    // The query keeps focus unless a filter field has it
+                    if ui.memory(|m| m.focused().is_none()) {
+                        input.request_focus();
+                    }
+                    if search.kind == SearchKind::Text {
+                        ui.horizontal(|ui| {
+                            for (filter, hint) in [
+                                (&mut search.include, "search-include-hint"),
+                                (&mut search.exclude, "search-exclude-hint"),
+                            ] {
+                                ui.add(
+                                    egui::TextEdit::singleline(filter)
+                                        .hint_text(i18n::tr(hint))
+                                        .desired_width(270.0),
+                                );
+                            }
+                        });
+                        if let Some(error) = &search.filter_error {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                    }
+                    if search.replacing {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut search.replacement)
+                                .hint_text(i18n::tr("replace-with-hint"))
+                                .desired_width(f32::INFINITY),
+                        );
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut search.regex, i18n::tr("find-regex"));
+                            ui.checkbox(&mut search.match_case, i18n::tr("find-match-case"));
+                        });
+                        if let Some(error) = &search.query_error {
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                    }
+
+                    match &self.index {
+                        None if self.index_job.is_some() => {
+                            ui.weak(i18n::tr("search-indexing"));
+                        }
+                        None => {
+                            ui.horizontal(|ui| {
+                                ui.weak(i18n::tr("search-index-off"));
+                                build = ui.small_button(i18n::tr("search-build-index")).clicked();
+                            });
+                        }
+                        Some(project_index) if project_index.is_truncated() => {
+                            ui.weak(i18n::tr_with(
+                                "search-index-capped",
+                                &[("count", project_index.file_count().into())],
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+
+                    if search.replacing {
+                        apply = self.show_replace_preview(ui, &mut search);
+                        return;
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(360.0)
+                        .show(ui, |ui| {
+                            let mut previous: Option<&Path> = None;
+                            for (i, hit) in search.hits.iter().enumerate() {
+                                // Hits are grouped by file under a header with the file's path
+                                if previous != Some(hit.path.as_path()) {
+                                    let path = self
+                                        .project_root
+                                        .as_deref()
+                                        .and_then(|root| hit.path.strip_prefix(root).ok())
+                                        .unwrap_or(&hit.path);
+                                    let mut header =
+                                        egui::RichText::new(path.display().to_string()).strong();
+                                    if hit.ignored {
+                                        header = header.weak();
+                                    }
+                                    ui.label(header);
+                                    previous = Some(&hit.path);
+                                }
+                                let location = format!("{}", hit.position.line + 1);
+                                ui.horizontal(|ui| {
+                                    ui.add_space(12.0);
+                                    let mut preview = egui::RichText::new(&hit.preview);
+                                    if hit.ignored {
+                                        preview = preview.weak();
+                                    }
+                                    let label = ui.selectable_label(i == search.selected, preview);
+                                    if label.clicked() {
+                                        chosen = Some(i);
+                                    }
+                                    if i == search.selected && (up || down) {
+                                        label.scroll_to_me(None);
+                                    }
+                                    ui.with_layout(
+                                        egui::Layout::right_to_left(egui::Align::Center),
+                                        |ui| ui.weak(location),
+                                    );
+                                });
+                            }
+                        });
+                    if search.truncated {
+                        ui.weak(i18n::tr_with(
+                            "search-truncated",
+                            &[("count", search.hits.len().into())],
+                        ));
+                    }
+                });
+
+            if enter && !search.replacing {
+                chosen = chosen.or(Some(search.selected));
+            }
+            if build {
+                self.settings.project_index = true;
+                self.start_indexing(false);
+            }
+            if apply {
+                self.apply_project_replace(&search.changes);
+                return;
+            }
+            match chosen.and_then(|i| search.hits.get(i)).cloned() {
+                Some(hit) => self.goto_location(&hit.path, hit.position),
+                None => self.project_search = Some(search),
+            }
+        }
+
+        /// Finds the changes replacing the query of a find-in-files search makes, for its preview.
+        ///
+        /// The open buffers' text is searched instead of their files, so unsaved edits are
+        /// replaced as well.
+        fn preview_project_replace(&self, search: &mut ProjectSearch) {
+            search.previewed = Some(search.preview_key());
+            search.changes.clear();
+            search.changes_truncated = false;
+            search.query_error = None;
+            let (Some(project_index), Some(root)) = (&self.index, &self.project_root) else {
+                return;
+            };
+            let Ok(filter) = index::Filter::new(
+                root,
+                &search.include,
+                &search.exclude,
+                self.settings.search_max_file_kb * 1024,
+            ) else {
+                return;
+            };
+            let pattern =
+                match search::Pattern::query(&search.query, search.regex, search.match_case, false)
+                {
+                    Ok(Some(pattern)) => pattern,
+                    Ok(None) => return,
+                    Err(e) => {
+                        let message = e.to_string();
+                        search.query_error = message.lines().last().map(str::to_string);
+                        return;
+                    }
+                };
+            let literal = (!search.regex).then_some(search.query.as_str());
+            let read = |path: &Path| {
+                let open = self.edtr_state.buffer_for_path(&path.to_string_lossy());
+                if let Some(buffer_id) = open {
+                    return self.edtr_state.get_buffer_text(buffer_id);
+                }
+                let content = fs::read(path).ok()?;
+                if index::is_binary(&content) {
+                    return None;
+                }
+                String::from_utf8(content).ok()
+            };
+            (search.changes, search.changes_truncated) = replace::find(
+                &project_index.files(literal, &filter),
+                read,
+                &pattern,
+                &search.replacement,
+                self.settings.search_result_limit as usize,
+            );
+        }
+
+        /// Lists the changes a project-wide replace makes, each line as it is struck through
+        /// above the line as it will be, with a checkbox to accept each change and one to accept
+        /// all the changes to a file.
+        ///
+        /// # Returns
+        ///
+        /// Whether the accepted changes are to be made.
+        fn show_replace_preview(&self, ui: &mut Ui, search: &mut ProjectSearch) -> bool {
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    for file in &mut search.changes {
+                        let path = self
+                            .project_root
+                            .as_deref()
+                            .and_then(|root| file.path.strip_prefix(root).ok())
+                            .unwrap_or(&file.path);
+                        let mut all = file.accepted() == file.changes.len();
+                        let header = egui::RichText::new(path.display().to_string()).strong();
+                        if ui.checkbox(&mut all, header).changed() {
+                            for change in &mut file.changes {
+                                change.accepted = all;
+                            }
+                        }
+                        for change in &mut file.changes {
+                            ui.horizontal(|ui| {
+                                ui.add_space(12.0);
+                                ui.checkbox(&mut change.accepted, "");
+                                ui.vertical(|ui| {
+                                    let numbers = change.line + 1..;
+                                    for (number, line) in numbers.zip(change.old.lines()) {
+                                        ui.label(
+                                            egui::RichText::new(format!("{number:>5} - {line}"))
+                                                .monospace()
+                                                .color(REPLACE_REMOVED_FG)
+                                                .strikethrough(),
+                                        );
+                                    }
+                                    for line in change.new.lines() {
+                                        ui.label(
+                                            egui::RichText::new(format!("{:>5} + {line}", ""))
+                                                .monospace()
+                                                .color(REPLACE_ADDED_FG),
+                                        );
+                                    }
+                                });
+                            });
+                        }
+                    }
+                });
+            if search.changes_truncated {
+                ui.weak(i18n::tr_with(
+                    "replace-preview-truncated",
+                    &[(
+                        "count",
+                        search
+                            .changes
+                            .iter()
+                            .map(|file| file.changes.len())
+                            .sum::<usize>()
+                            .into(),
+                    )],
+                ));
+            }
+            let accepted: usize = search.changes.iter().map(|file| file.accepted()).sum();
+            ui.add_enabled(
+                accepted > 0,
+                egui::Button::new(i18n::tr_with(
+                    "replace-apply",
+                    &[("count", accepted.into())],
+                )),
+            )
+            .clicked()
+        }
+
+        /// Makes the accepted changes of a project-wide replace.
+        ///
+        /// Open files are changed in their buffers, as one step that can be undone, and left
+        /// unsaved. Other files are written directly. A file whose text changed since the
+        /// preview is left alone.
+        fn apply_project_replace(&mut self, files: &[replace::FileChanges]) {
+            let (mut changed, mut skipped) = (0usize, 0usize);
+            for file in files.iter().filter(|file| file.accepted() > 0) {
+                let text = file.apply();
+                let done = match self
+                    .edtr_state
+                    .buffer_for_path(&file.path.to_string_lossy())
+                {
+                    Some(buffer_id) => {
+                        self.edtr_state.matches_text(buffer_id, &file.text)
+                            && self.edtr_state.replace_text(buffer_id, &text).is_ok()
+                    }
+                    None => match fs::read_to_string(&file.path) {
+                        Ok(current) if current == file.text => {
+                            match save::write_atomically(&file.path, &text) {
+                                Ok(()) => {
+                                    if let Some(project_index) = &mut self.index {
+                                        project_index.update_file(&file.path, &text);
+                                    }
+                                    true
+                                }
+                                Err(e) => {
+                                    eprintln!("Failed to write {}: {:#}", file.path.display(), e);
+                                    false
+                                }
+                            }
+                        }
+                        _ => false,
+                    },
+                };
+                if done {
+                    changed += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+            self.flash(i18n::tr_with(
+                "replace-files-done",
+                &[("count", changed.into()), ("skipped", skipped.into())],
+            ));
+        }
+
+        /// Opens the find-and-replace window.
+        ///
+        /// A selection within one line becomes the query; a selection over several lines limits
+        /// the replacement to it. Matches are stepped through from the caret. Opened on a match
+        /// of the word search, the window continues that search.
+        fn open_find_replace(&mut self) {
+            let mut find = FindReplace {
+                focus_query: true,
+                ..FindReplace::default()
+            };
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer()
+                && let Some(cursor) = self.edtr_state.get_cursor_state(buffer_id)
+            {
+                find.from =
+                    self.edtr_state.buffers()[&buffer_id].position_to_offset(cursor.position());
+            }
+            find.selection = self.selection_offsets();
+            if let Some((buffer_id, selection)) = self.active_selection()
+                && let Some(range) = find.selection.clone()
+            {
+                if let Some((_, search)) = self
+                    .word_search
+                    .as_ref()
+                    .filter(|(id, search)| *id == buffer_id && search.shown == find.selection)
+                {
+                    find.query = search.query.clone();
+                    find.regex = search.regex;
+                    find.from = range.start;
+                    find.shown = search.shown.clone();
+                } else if selection.start.line == selection.end.line {
+                    let table = &self.edtr_state.buffers()[&buffer_id];
+                    find.query = table.get_text(range.start, range.len());
+                    find.from = range.start;
+                } else {
+                    find.scope = Some(range);
+                }
+            }
+            self.find_bar = None;
+            self.find_replace = Some(find);
+        }
+
+        /// Opens the find bar, or gives its query field the keyboard focus again.
+        ///
+        /// Matches are looked for from the caret. A selection within one line becomes the query;
+        /// one over several lines is what Replace All in Selection replaces in. A selected match
+        /// of the word search is looked for as a whole word, and without a selection a newly
+        /// opened bar starts from the most recent query.
+        fn open_find_bar(&mut self) {
+            let mut bar = self.find_bar.take().unwrap_or_else(|| FindBar {
+                query: self.search_history.first().cloned().unwrap_or_default(),
+                ..FindBar::default()
+            });
+            bar.focus_query = true;
+            bar.shown = None;
+            bar.selection = None;
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer()
+                && let Some(cursor) = self.edtr_state.get_cursor_state(buffer_id)
+            {
+                bar.from =
+                    self.edtr_state.buffers()[&buffer_id].position_to_offset(cursor.position());
+            }
+            if let Some((buffer_id, selection)) = self.active_selection()
+                && selection.start.line == selection.end.line
+                && let Some(range) = self.selection_offsets()
+            {
+                let word_match = self.word_search.as_ref().is_some_and(|(id, search)| {
+                    *id == buffer_id && search.shown == Some(range.clone())
+                });
+                if word_match {
+                    bar.regex = false;
+                    bar.match_case = true;
+                    bar.whole_word = true;
+                }
+                let table = &self.edtr_state.buffers()[&buffer_id];
+                bar.query = table.get_text(range.start, range.len());
+                bar.from = range.start;
+            } else {
+                bar.selection = self.selection_offsets();
+            }
+            self.find_replace = None;
+            self.find_bar = Some(bar);
+        }
+
+        /// Shows the find bar, if open, searching as the query is typed.
+        ///
+        /// The first match from where the search started is selected and scrolled into view.
+        /// Enter steps to the next match and Shift+Enter to the previous one, wrapping around the
+        /// buffer; Esc closes the bar, leaving the match selected. Up/Down in the query field
+        /// cycle through recent queries, and the query becomes the most recent one when the bar
+        /// steps or closes.
+        ///
+        /// With the replace field shown, Enter in it replaces the match and moves on to the next.
+        /// Replace All replaces every match in the buffer or the selection the bar was opened on,
+        /// as one edit. Regular expressions may refer to capture groups in the replacement.
+        fn render_find_bar(&mut self, ctx: &egui::Context) {
+            let Some(mut bar) = self.find_bar.take() else {
+                return;
+            };
+            let query_id = egui::Id::new("find_bar_query");
+            let replace_id = egui::Id::new("find_bar_replacement");
+            let in_query = ctx.memory(|m| m.has_focus(query_id));
+            let in_replacement = bar.replacing && ctx.memory(|m| m.has_focus(replace_id));
+            let (previous, next, escape, older, newer) = ctx.input_mut(|i| {
+                // Shift+Enter first, since Enter without modifiers also matches it
+                let previous = i.consume_key(egui::Modifiers::SHIFT, egui::Key::Enter);
+                (
+                    previous,
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+                    in_query && i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    in_query && i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                )
+            });
+            if escape {
+                session::remember_search(&mut self.search_history, &bar.query);
+                return;
+            }
+            if older || newer {
+                bar.browse_history(&self.search_history, older);
+            }
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(buffer_id) else {
+                return;
+            };
+
+            let compiled = bar.pattern();
+            let pattern = compiled.as_ref().ok().and_then(Option::as_ref);
+            let matches =
+                pattern.map_or_else(Vec::new, |pattern| pattern.find_all(&text, 0..text.len()));
+            let current =
+                pattern.and_then(|pattern| pattern.find_next(&text, bar.from, 0..text.len()));
+            if current != bar.shown
+                && let Some(found) = &current
+            {
+                self.select_match(buffer_id, found.clone());
+            }
+            bar.shown = current.clone();
+
+            // Whether to step backward or forward, and what to replace
+            let mut step = if previous {
+                Some(true)
+            } else {
+                (next && !in_replacement).then_some(false)
+            };
+            let mut replace = (next && in_replacement).then_some(ReplaceStep::One);
+            let mut in_selection = false;
+            egui::Window::new(i18n::tr("menu-find"))
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(420.0, 0.0))
+                .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 48.0))
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(bar.replacing, "⇄")
+                            .on_hover_text(i18n::tr("find-toggle-replace"))
+                            .clicked()
+                        {
+                            bar.replacing = !bar.replacing;
+                        }
+                        let query = ui.add(
+                            egui::TextEdit::singleline(&mut bar.query)
+                                .id(query_id)
+                                .hint_text(i18n::tr("find-hint"))
+                                .desired_width(180.0),
+                        );
+                        if query.changed() {
+                            bar.history = None;
+                            ui.ctx().request_repaint();
+                        }
+                        if bar.focus_query {
+                            query.request_focus();
+                            bar.focus_query = false;
+                        }
+                        let any = current.is_some();
+                        if ui
+                            .add_enabled(any, egui::Button::new("↑"))
+                            .on_hover_text(i18n::tr("find-previous"))
+                            .clicked()
+                        {
+                            step = Some(true);
+                        }
+                        if ui
+                            .add_enabled(any, egui::Button::new("↓"))
+                            .on_hover_text(i18n::tr("find-next"))
+                            .clicked()
+                        {
+                            step = Some(false);
+                        }
+                        match &current {
+                            Some(found) => {
+                                let index = matches.partition_point(|m| m.start < found.start) + 1;
+                                ui.weak(i18n::tr_with(
+                                    "find-position",
+                                    &[("index", index.into()), ("count", matches.len().into())],
+                                ));
+                            }
+                            None => {
+                                let problem = match &compiled {
+                                    Err(e) => Some(
+                                        e.to_string()
+                                            .lines()
+                                            .last()
+                                            .unwrap_or_default()
+                                            .to_string(),
+                                    ),
+                                    Ok(Some(_)) => Some(i18n::tr("find-no-matches")),
+                                    Ok(None) => None,
+                                };
+                                if let Some(problem) = problem {
+                                    ui.colored_label(ui.visuals().error_fg_color, problem);
+                                }
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let regex = ui.checkbox(&mut bar.regex, i18n::tr("find-regex"));
+                        let case = ui.checkbox(&mut bar.match_case, i18n::tr("find-match-case"));
+                        let word = ui.checkbox(&mut bar.whole_word, i18n::tr("find-whole-word"));
+                        if regex.changed() || case.changed() || word.changed() {
+                            ui.ctx().request_repaint();
+                        }
+                    });
+                    if bar.replacing {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut bar.replacement)
+                                    .id(replace_id)
+                                    .hint_text(i18n::tr("replace-with-hint"))
+                                    .desired_width(180.0),
+                            );
+                            let any = current.is_some();
+                            if ui
+                                .add_enabled(any, egui::Button::new(i18n::tr("replace-one")))
+                                .clicked()
+                            {
+                                replace = Some(ReplaceStep::One);
+                            }
+                            if ui
+                                .add_enabled(any, egui::Button::new(i18n::tr("replace-all")))
+                                .clicked()
+                            {
+                                replace = Some(ReplaceStep::All);
+                            }
+                            if ui
+                                .add_enabled(
+                                    any && bar.selection.is_some(),
+                                    egui::Button::new(i18n::tr("find-replace-in-selection")),
+                                )
+                                .clicked()
+                            {
+                                replace = Some(ReplaceStep::All);
+                                in_selection = true;
+                            }
+                        });
+                    }
+                });
+
+            if let (Some(pattern), Some(found), Some(replace)) = (pattern, current.clone(), replace)
+            {
+                match replace {
+                    ReplaceStep::All if in_selection => {
+                        self.replace_all(
+                            buffer_id,
+                            pattern,
+                            &text,
+                            &bar.replacement,
+                            &mut bar.selection,
+                            &mut bar.from,
+                        );
+                    }
+                    ReplaceStep::All => {
+                        self.replace_all(
+                            buffer_id,
+                            pattern,
+                            &text,
+                            &bar.replacement,
+                            &mut None,
+                            &mut bar.from,
+                        );
+                        // The selection's offsets no longer match the text
+                        bar.selection = None;
+                    }
+                    ReplaceStep::One | ReplaceStep::Skip => {
+                        if let Some(from) = self.replace_match(
+                            buffer_id,
+                            pattern,
+                            &text,
+                            found,
+                            &bar.replacement,
+                            bar.selection.as_mut(),
+                        ) {
+                            bar.from = from;
+                        }
+                    }
+                }
+                bar.shown = None;
+                ctx.request_repaint();
+                step = None;
+            }
+
+            if let (Some(pattern), Some(found), Some(backward)) = (pattern, current, step) {
+                session::remember_search(&mut self.search_history, &bar.query);
+                let stepped = if backward {
+                    pattern.find_prev(&text, found.start, 0..text.len())
+                } else {
+                    pattern.find_next(&text, after(&text, found.clone()), 0..text.len())
+                };
+                if let Some(stepped) = stepped {
+                    let wrapped = if backward {
+                        stepped.start >= found.start
+                    } else {
+                        stepped.start <= found.start
+                    };
+                    if wrapped && matches.len() > 1 {
+                        self.flash(i18n::tr("search-wrapped"));
+                    }
+                    bar.from = stepped.start;
+                    bar.shown = Some(stepped.clone());
+                    self.select_match(buffer_id, stepped);
+                    ctx.request_repaint();
+                }
+            }
+            self.find_bar = Some(bar);
+        }
+
+        /// Returns the active buffer and its selection, if anything is selected.
+        fn active_selection(&self) -> Option<(led::types::buffer::ID, Range)> {
+            let buffer_id = self.edtr_state.get_active_buffer()?;
+            let selection = self.edtr_state.get_cursor_state(buffer_id)?.selection()?;
+            (selection.start != selection.end).then_some((buffer_id, selection))
+        }
+
+        /// Returns the byte range of the active buffer's selection, if anything is selected.
+        fn selection_offsets(&self) -> Option<std::ops::Range<usize>> {
+            let (buffer_id, selection) = self.active_selection()?;
+            let table = self.edtr_state.buffers().get(&buffer_id)?;
+            let anchor = table.position_to_offset(selection.start);
+            let head = table.position_to_offset(selection.end);
+            Some(anchor.min(head)..anchor.max(head))
+        }
+
+        /// Shows the find-and-replace window, if open, and replaces matches when asked.
+        ///
+        /// The highlighted match is selected in the buffer and previewed with its replacement,
+        /// which for regular expressions may refer to capture groups as `$1` or `${name}`.
+        /// Enter in the query field or Skip moves on to the next match, Enter elsewhere or
+        /// Replace replaces it and moves on, Replace All replaces every match at once and Esc
+        /// closes the window.
+        fn render_find_replace(&mut self, ctx: &egui::Context) {
+            let Some(mut find) = self.find_replace.take() else {
+                return;
+            };
+            let query_id = egui::Id::new("replace_query");
+            let in_query = ctx.memory(|m| m.has_focus(query_id));
+            let (enter, escape) = ctx.input_mut(|i| {
+                let none = egui::Modifiers::NONE;
+                (
+                    i.consume_key(none, egui::Key::Enter),
+                    i.consume_key(none, egui::Key::Escape),
+                )
+            });
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(buffer_id) else {
+                return;
+            };
+            if escape {
+                return;
+            }
+            let pattern = search::Pattern::new(&find.query, find.regex, find.preserve_case);
+            let scope = find.scope(text.len());
+            let (matches, current) = match &pattern {
+                Ok(Some(pattern)) => (
+                    pattern.find_all(&text, scope.clone()).len(),
+                    pattern.find_next(&text, find.from, scope),
+                ),
+                _ => (0, None),
+            };
+            if current != find.shown
+                && let Some(found) = &current
+            {
+                self.select_match(buffer_id, found.clone());
+            }
+            find.shown = current.clone();
+
+            let title = i18n::tr("replace-title");
+            let mut step = enter.then_some(if in_query {
+                ReplaceStep::Skip
+            } else {
+                ReplaceStep::One
+            });
+            egui::Window::new(&title)
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .fixed_size(egui::vec2(360.0, 0.0))
+                .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 48.0))
+                .show(ctx, |ui| {
+                    ui.strong(&title);
+                    let query = ui.add(
+                        egui::TextEdit::singleline(&mut find.query)
+                            .id(query_id)
+                            .hint_text(i18n::tr("replace-find-hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    if find.focus_query {
+                        query.request_focus();
+                        find.focus_query = false;
+                    }
+                    ui.add(
+                        egui::TextEdit::singleline(&mut find.replacement)
+                            .hint_text(i18n::tr("replace-with-hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut find.regex, i18n::tr("replace-regex"))
+                            .on_hover_text(i18n::tr("replace-regex-hint"));
+                        ui.checkbox(&mut find.preserve_case, i18n::tr("replace-preserve-case"))
+                            .on_hover_text(i18n::tr("replace-preserve-case-hint"));
+                        let mut in_selection = find.scope.is_some();
+                        if ui
+                            .add_enabled(
+                                in_selection || find.selection.is_some(),
+                                egui::Checkbox::new(
+                                    &mut in_selection,
+                                    i18n::tr("replace-in-selection"),
+                                ),
+                            )
+                            .changed()
+                        {
+                            find.scope = if in_selection {
+                                find.selection.clone()
+                            } else {
+                                None
+                            };
+                        }
+                    });
+                    if let Err(e) = &pattern {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            e.to_string().lines().last().unwrap_or_default(),
+                        );
+                    }
+                    if let (Ok(Some(pattern)), Some(found)) = (&pattern, &current) {
+                        let expanded = pattern.expand(&text, found.clone(), &find.replacement);
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(
+                                egui::RichText::new(preview(&text[found.clone()]))
+                                    .monospace()
+                                    .strikethrough(),
+                            );
+                            ui.label("→");
+                            ui.label(egui::RichText::new(preview(&expanded)).monospace());
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.weak(i18n::tr_with(
+                            "replace-matches",
+                            &[("count", matches.into())],
+                        ));
+                        let any = current.is_some();
+                        if ui
+                            .add_enabled(any, egui::Button::new(i18n::tr("replace-one")))
+                            .clicked()
+                        {
+                            step = Some(ReplaceStep::One);
+                        }
+                        if ui
+                            .add_enabled(any, egui::Button::new(i18n::tr("replace-skip")))
+                            .clicked()
+                        {
+                            step = Some(ReplaceStep::Skip);
+                        }
+                        if ui
+                            .add_enabled(any, egui::Button::new(i18n::tr("replace-all")))
+                            .clicked()
+                        {
+                            step = Some(ReplaceStep::All);
+                        }
+                    });
+                });
+
+            if let (Ok(Some(pattern)), Some(found), Some(step)) = (&pattern, current, step) {
+                match step {
+                    ReplaceStep::One => {
+                        if let Some(from) = self.replace_match(
+                            buffer_id,
+                            pattern,
+                            &text,
+                            found,
+                            &find.replacement,
+                            find.scope.as_mut(),
+                        ) {
+                            find.from = from;
+                        }
+                    }
+                    ReplaceStep::Skip => find.from = after(&text, found),
+                    ReplaceStep::All => self.replace_all(
+                        buffer_id,
+                        pattern,
+                        &text,
+                        &find.replacement,
+                        &mut find.scope,
+                        &mut find.from,
+                    ),
+                }
+                find.shown = None;
+            }
+            self.find_replace = Some(find);
+        }
+
+        /// Selects a match in a buffer and scrolls it into view.
+        fn select_match(
+            &mut self,
+            buffer_id: led::types::buffer::ID,
+            found: std::ops::Range<usize>,
+        ) {
+            let Some(table) = self.edtr_state.buffers().get(&buffer_id) else {
+                return;
+            };
+            let range = Range {
+                start: table.offset_to_position(found.start),
+                end: table.offset_to_position(found.end),
+            };
+            for command in [
+                editor::Command::MoveCursor {
+                    buffer_id,
+                    position: range.end,
+                },
+                editor::Command::SetSelection { buffer_id, range },
+            ] {
+                let _ = self.edtr_state.execute_command(command);
+            }
+            self.reveal_cursor = true;
+        }
+
+        /// Selects the next or previous whole-word occurrence of the word under the caret,
+        /// wrapping around the buffer and saying so in the status bar.
+        ///
+        /// The search is shared with the find window and the find bar: an open one switches to
+        /// the word, and one opened later on its match continues it. The word becomes the most
+        /// recent search query.
+        fn find_word_under_cursor(&mut self, backward: bool) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let (Some(text), Some(cursor)) = (
+                self.edtr_state.get_buffer_text(buffer_id),
+                self.edtr_state.get_cursor_state(buffer_id),
+            ) else {
+                return;
+            };
+            let position = cursor.position();
+            let line_start: usize = text
+                .split('\n')
+                .take(position.line)
+                .map(|line| line.len() + 1)
+                .sum();
+            let line = text[line_start.min(text.len())..]
+                .split('\n')
+                .next()
+                .unwrap_or_default();
+            let Some(word) = word::word_at(line, position.column.min(line.len())) else {
+                self.flash(i18n::tr("search-no-word"));
+                return;
+            };
+            let word = line_start + word.start..line_start + word.end;
+            session::remember_search(&mut self.search_history, &text[word.clone()]);
+            let query = search::whole_word(&text[word.clone()]);
+            let Ok(Some(pattern)) = search::Pattern::new(&query, true, false) else {
+                return;
+            };
+            let found = if backward {
+                pattern.find_prev(&text, word.start, 0..text.len())
+            } else {
+                pattern.find_next(&text, word.end, 0..text.len())
+            };
+            let Some(found) = found else {
+                return;
+            };
+            self.select_match(buffer_id, found.clone());
+            let wrapped = if backward {
+                found.start >= word.start
+            } else {
+                found.start < word.end
+            };
+            if wrapped {
+                self.flash(i18n::tr("search-wrapped"));
+            }
+
+            if let Some(find) = &mut self.find_replace {
+                find.query = query.clone();
+                find.regex = true;
+                find.preserve_case = false;
+                find.from = found.start;
+                find.shown = Some(found.clone());
+            }
+            if let Some(bar) = &mut self.find_bar {
+                bar.query = text[word].to_string();
+                bar.regex = false;
+                bar.match_case = true;
+                bar.whole_word = true;
+                bar.history = None;
+                bar.from = found.start;
+                bar.shown = Some(found.clone());
+            }
+            let search = FindReplace {
+                query,
+                regex: true,
+                from: found.start,
+                shown: Some(found),
+                ..FindReplace::default()
+            };
+            self.word_search = Some((buffer_id, search));
+        }
+
+        /// Returns the byte ranges of the search matches to highlight in a buffer: those of the
+        /// find bar's or the find window's query while one is open, otherwise those of the word
+        /// search while one of its matches is selected.
+        fn occurrences(&self, buffer_id: led::types::buffer::ID) -> Vec<std::ops::Range<usize>> {
+            if let Some(bar) = &self.find_bar {
+                let (Ok(Some(pattern)), Some(text)) =
+                    (bar.pattern(), self.edtr_state.get_buffer_text(buffer_id))
+                else {
+                    return Vec::new();
+                };
+                let mut matches = pattern.find_all(&text, 0..text.len());
+                matches.truncate(MAX_OCCURRENCES);
+                return matches;
+            }
+            let search = match &self.find_replace {
+                Some(find) => find,
+                None => match &self.word_search {
+                    Some((id, search))
+                        if *id == buffer_id && search.shown == self.selection_offsets() =>
+                    {
+                        search
+                    }
+                    _ => return Vec::new(),
+                },
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(buffer_id) else {
+                return Vec::new();
+            };
+            match search::Pattern::new(&search.query, search.regex, search.preserve_case) {
+                Ok(Some(pattern)) => {
+                    let mut matches = pattern.find_all(&text, search.scope(text.len()));
+                    matches.truncate(MAX_OCCURRENCES);
+                    matches
+                }
+                _ => Vec::new(),
+            }
+        }
+
+        /// Replaces one match as a single edit, moving a scope of the search along.
+        ///
+        /// # Returns
+        ///
+        /// Where the search continues: past the replacement. `None` if the edit failed.
+        fn replace_match(
+            &mut self,
+            buffer_id: led::types::buffer::ID,
+            pattern: &search::Pattern,
+            text: &str,
+            found: std::ops::Range<usize>,
+            replacement: &str,
+            scope: Option<&mut std::ops::Range<usize>>,
+        ) -> Option<usize> {
+            let expanded = pattern.expand(text, found.clone(), replacement);
+            let batch = editor::Command::Batch(vec![
+                editor::Command::DeleteText {
+                    buffer_id,
+                    start: found.start,
+                    length: found.len(),
+                },
+                editor::Command::InsertText {
+                    buffer_id,
+                    offset: found.start,
+                    text: expanded.clone(),
+                },
+            ]);
+            if let Err(e) = self.edtr_state.execute_command(batch) {
+                eprintln!("Failed to replace: {:#}", e);
+                return None;
+            }
+            // A scope before the match stays, one after it moves along with its end
+            if let Some(scope) = scope
+                && found.start < scope.end
+            {
+                if found.end <= scope.start {
+                    scope.start = scope.start + expanded.len() - found.len();
+                }
+                scope.end = scope.end + expanded.len() - found.len();
+            }
+            // An empty match is stepped over so it isn't found again right away
+            Some(after(text, found.clone()) - found.len() + expanded.len())
+        }
+
+        /// Replaces every match of the query in a buffer as a single edit, and reports how many
+        /// were replaced.
+        ///
+        /// The caret and selection are carried over into the new text, so the view stays where
+        /// it was; after replacing inside the selection, the replaced region is selected.
+        ///
+        /// # Arguments
+        ///
+        /// * `scope` - The byte range to replace in, the whole buffer if `None`; moved to the
+        ///   replaced region.
+        /// * `from` - Where the search continues, moved into the new text.
+        fn replace_all(
+            &mut self,
+            buffer_id: led::types::buffer::ID,
+            pattern: &search::Pattern,
+            text: &str,
+            replacement: &str,
+            scope: &mut Option<std::ops::Range<usize>>,
+            from: &mut usize,
+        ) {
+            let within = match scope {
+                Some(scope) => scope.start.min(text.len())..scope.end.min(text.len()),
+                None => 0..text.len(),
+            };
+            let Some(replacement) = pattern.replace_all(text, replacement, within) else {
+                return;
+            };
+            let table = &self.edtr_state.buffers()[&buffer_id];
+            let caret = self
+                .edtr_state
+                .get_cursor_state(buffer_id)
+                .map(|c| table.position_to_offset(c.position()));
+            let batch = editor::Command::Batch(vec![
+                editor::Command::DeleteText {
+                    buffer_id,
+                    start: replacement.range.start,
+                    length: replacement.range.len(),
+                },
+                editor::Command::InsertText {
+                    buffer_id,
+                    offset: replacement.range.start,
+                    text: replacement.text.clone(),
+                },
+            ]);
+            if let Err(e) = self.edtr_state.execute_command(batch) {
+                eprintln!("Failed to replace: {:#}", e);
+                return;
+            }
+
+            let table = &self.edtr_state.buffers()[&buffer_id];
+            let mut commands = Vec::new();
+            if let Some(caret) = caret {
+                commands.push(editor::Command::MoveCursor {
+                    buffer_id,
+                    position: table.offset_to_position(replacement.map(caret)),
+                });
+            }
+            if let Some(scope) = scope {
+                *scope = replacement.map(scope.start)..replacement.map(scope.end);
+                commands.push(editor::Command::SetSelection {
+                    buffer_id,
+                    range: Range {
+                        start: table.offset_to_position(scope.start),
+                        end: table.offset_to_position(scope.end),
+                    },
+                });
+            }
+            for command in commands {
+                let _ = self.edtr_state.execute_command(command);
+            }
+            *from = replacement.map(*from);
+            self.flash(i18n::tr_with(
+                "replace-done",
+                &[("count", replacement.count().into())],
+            ));
+        }
+
+        /// Shows a short message as a toast and in the status bar.
+        fn flash(&mut self, message: String) {
+            self.flash = Some(Flash {
+                message,
+                until: std::time::Instant::now() + FLASH_DURATION,
+            });
+        }
+
+        /// Shows the flash message, if any, as a toast in the bottom-right corner until it
+        /// expires.
+        fn render_flash(&mut self, ctx: &egui::Context) {
+            let Some(flash) = &self.flash else {
+                return;
+            };
+            let now = std::time::Instant::now();
+            if now >= flash.until {
+                self.flash = None;
+                return;
+            }
+            egui::Area::new(egui::Id::new("flash"))
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -40.0))
+                .interactable(false)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(&flash.message));
+                });
+            ctx.request_repaint_after(flash.until - now);
+        }
+
+        /// Completes a pending prompt with the next typed character, or cancels it on Esc.
+        ///
+        /// Key presses are swallowed while the prompt is open so the character is not also
+        /// typed into the buffer.
+        fn handle_prompt(&mut self, ctx: &egui::Context) {
+            let Some(prompt) = self.prompt else {
+                return;
+            };
+            let mut cancelled = false;
+            let mut name = None;
+            ctx.input_mut(|i| {
+                i.events.retain(|event| match event {
+                    egui::Event::Key {
+                        key: egui::Key::Escape,
+                        pressed: true,
+                        ..
+                    } => {
+                        cancelled = true;
+                        false
+                    }
+                    egui::Event::Key { .. } => false,
+                    egui::Event::Text(text) => {
+                        name = name.or_else(|| text.chars().next());
+                        false
+                    }
+                    _ => true,
+                });
+            });
+
+            if cancelled {
+                self.prompt = None;
+                return;
+            }
+            let Some(name) = name else {
+                return;
+            };
+            self.prompt = None;
+            match prompt {
+                Prompt::SetMark => self.set_mark(name),
+                Prompt::JumpToMark => self.jump_to_mark(name),
+                Prompt::ChangeSurround => self.change_surround(name),
+            }
+        }
+
+        /// Returns the active buffer, its text, its caret offset and the innermost pair around
+        /// the caret.
+        fn active_enclosing(
+            &self,
+        ) -> Option<(led::types::buffer::ID, String, usize, surround::Enclosing)> {
+            let buffer_id = self.edtr_state.get_active_buffer()?;
+            let text = self.edtr_state.get_buffer_text(buffer_id)?;
+            let cursor = self.edtr_state.get_cursor_state(buffer_id)?;
+            let offset = self
+                .edtr_state
+                .buffers()
+                .get(&buffer_id)?
+                .position_to_offset(cursor.position());
+            let enclosing = surround::enclosing(&text, offset)?;
+            Some((buffer_id, text, offset, enclosing))
+        }
+
+        /// Replaces the pair around the caret with the pair a typed character opens or closes.
+        fn change_surround(&mut self, c: char) {
+            let Some(pair) = surround::pair_for(c) else {
+                return;
+            };
+            if let Some((buffer_id, _, offset, enclosing)) = self.active_enclosing() {
+                self.edit_keeping_caret(
+                    buffer_id,
+                    offset,
+                    surround::change(buffer_id, &enclosing, pair),
+                );
+            }
+        }
+
+        /// Removes the pair around the caret.
+        fn delete_surround(&mut self) {
+            if let Some((buffer_id, _, offset, enclosing)) = self.active_enclosing() {
+                self.edit_keeping_caret(buffer_id, offset, surround::delete(buffer_id, &enclosing));
+            }
+        }
+
+        /// Applies edits as one and moves the caret along with the text around it.
+        fn edit_keeping_caret(
+            &mut self,
+            buffer_id: led::types::buffer::ID,
+            caret: usize,
+            commands: Vec<editor::Command>,
+        ) {
+            let caret = commands
+                .iter()
+                .filter_map(delta::Delta::from_command)
+                .fold(caret, |caret, (_, delta)| delta.map_offset(caret));
+            if let Err(e) = self
+                .edtr_state
+                .execute_command(editor::Command::Batch(commands))
+            {
+                eprintln!("Failed to edit: {:#}", e);
+                return;
+            }
+            if let Some(table) = self.edtr_state.buffers().get(&buffer_id) {
+                let position = table.offset_to_position(caret);
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::MoveCursor {
+                        buffer_id,
+                        position,
+                    });
+            }
+        }
+
+        /// Sets a mark at the caret of the active buffer.
+        fn set_mark(&mut self, name: char) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let (Some(table), Some(cursor)) = (
+                self.edtr_state.buffer(buffer_id),
+                self.edtr_state.get_cursor_state(buffer_id),
+            ) else {
+                return;
+            };
+            let offset = table.position_to_offset(cursor.position());
+            self.edtr_state.marks_mut().set(name, buffer_id, offset);
+        }
+
+        /// Jumps to a mark visible from the active buffer, opening its file if necessary.
+        fn jump_to_mark(&mut self, name: char) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            if let Some(mark) = self.edtr_state.marks().get(name, buffer_id) {
+                self.goto_mark(mark);
+                return;
+            }
+            // Global marks may point into a file that is not open yet
+            if marks::is_global(name)
+                && let Some(path) = self
+                    .edtr_state
+                    .marks()
+                    .pending()
+                    .iter()
+                    .find(|m| m.name == name)
+                    .map(|m| m.path.clone())
+            {
+                self.goto_file_mark(&path, name);
+            }
+        }
+
+        /// Makes the mark's buffer active and moves its caret to the mark.
+        fn goto_mark(&mut self, mark: marks::Mark) {
+            let Some(table) = self.edtr_state.buffer(mark.buffer_id) else {
+                return;
+            };
+            let position = table.offset_to_position(mark.offset.min(table.len()));
+            self.edtr_state.set_active_buffer(Some(mark.buffer_id));
+            self.notes_focused = Some(mark.buffer_id) == self.notes_buffer_id();
+            let _ = self
+                .edtr_state
+                .execute_command(editor::Command::MoveCursor {
+                    buffer_id: mark.buffer_id,
+                    position,
+                });
+        }
+
+        /// Opens the file of a pending mark and jumps to the mark.
+        fn goto_file_mark(&mut self, path: &str, name: char) {
+            if let Some(buffer_id) = self.open_path(path)
+                && let Some(mark) = self.edtr_state.marks().get(name, buffer_id)
+            {
+                self.goto_mark(mark);
+            }
+        }
+
+        /// Shows a tab for each open document, with a dot after the name of one with unsaved
+        /// changes. Clicking a tab makes its document active; middle-clicking it or its × closes
+        /// the document. Tabs are reordered by dragging one onto another.
+        fn render_tab_bar(&mut self, ui: &mut egui::Ui) {
+            let open = self.open_documents();
+            tab_bar::sync(&mut self.tab_order, &open);
+
+            let active = self.edtr_state.get_active_buffer();
+            let mut activate = None;
+            let mut close = None;
+            let mut dropped = None;
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for &buffer_id in &self.tab_order {
+                        let meta = self.edtr_state.metadata(buffer_id);
+                        let mut name = self.buffer_name(buffer_id);
+                        if meta.is_some_and(|meta| meta.modified) {
+                            name.push_str(" ●");
+                        }
+                        let mut tab = ui.add(
+                            egui::Button::selectable(Some(buffer_id) == active, name)
+                                .sense(egui::Sense::click_and_drag()),
+                        );
+                        if let Some(path) = meta.and_then(|meta| meta.file_path.as_deref()) {
+                            tab = tab.on_hover_text(path);
+                        }
+                        tab.dnd_set_drag_payload(buffer_id);
+                        if tab.dnd_hover_payload::<led::buffer::ID>().is_some() {
+                            ui.painter().vline(
+                                tab.rect.left(),
+                                tab.rect.y_range(),
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                        if let Some(dragged) = tab.dnd_release_payload::<led::buffer::ID>() {
+                            dropped = Some((*dragged, buffer_id));
+                        }
+                        if tab.clicked() {
+                            activate = Some(buffer_id);
+                        }
+                        if tab.middle_clicked()
+                            || ui
+                                .small_button("×")
+                                .on_hover_text(i18n::tr("tab-close"))
+                                .clicked()
+                        {
+                            close = Some(buffer_id);
+                        }
+                        ui.add_space(4.0);
+                    }
+                });
+            });
+
+            if let Some((tab, onto)) = dropped {
+                tab_bar::move_tab(&mut self.tab_order, tab, onto);
+            }
+            if let Some(buffer_id) = activate {
+                self.edtr_state.set_active_buffer(Some(buffer_id));
+                self.notes_focused = false;
+            }
+            if let Some(buffer_id) = close {
+                self.close_buffer(ui.ctx(), buffer_id);
+            }
+        }
+
+        /// Returns a short display name for a buffer: its file name, or `Untitled`.
+        fn buffer_name(&self, buffer_id: led::buffer::ID) -> String {
+            self.edtr_state
+                .metadata(buffer_id)
+                .and_then(|meta| meta.file_path.as_deref())
+                .map_or_else(|| i18n::tr("untitled"), file_name)
+        }
+
+        fn render_marks_panel(&mut self, ui: &mut egui::Ui) {
+            ui.strong(i18n::tr("marks-title"));
+            ui.separator();
+
+            let open: Vec<(marks::Mark, String, Position)> = self
+                .edtr_state
+                .marks()
+                .iter()
+                .filter_map(|mark| {
+                    let table = self.edtr_state.buffer(mark.buffer_id)?;
+                    let position = table.offset_to_position(mark.offset.min(table.len()));
+                    Some((*mark, self.buffer_name(mark.buffer_id), position))
+                })
+                .collect();
+            let pending = self.edtr_state.marks().pending().to_vec();
+            if open.is_empty() && pending.is_empty() {
+                let shortcut = self
+                    .keymap
+                    .label_for(keymap::action::SET_MARK)
+                    .unwrap_or_else(|| i18n::tr("marks-set-mark-menu"));
+                ui.weak(i18n::tr_with(
+                    "marks-empty",
+                    &[("shortcut", shortcut.into())],
+                ));
+                return;
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (mark, name, position) in open {
+                    let label = format!(
+                        "{}  {}  {}:{}",
+                        mark.name,
+                        name,
+                        position.line + 1,
+                        position.column + 1
+                    );
+                    if ui.selectable_label(false, label).clicked() {
+                        self.goto_mark(mark);
+                    }
+                }
+                for mark in pending {
+                    let label = format!(
+                        "{}  {}  {}:{}",
+                        mark.name,
+                        file_name(&mark.path),
+                        mark.position.line + 1,
+                        mark.position.column + 1
+                    );
+                    let response = ui
+                        .add(
+                            egui::Label::new(egui::RichText::new(label).weak())
+                                .sense(egui::Sense::click()),
+                        )
+                        .on_hover_text(&mark.path);
+                    if response.clicked() {
+                        self.goto_file_mark(&mark.path, mark.name);
+                    }
+                }
+            });
+        }
+
+        /// Shows a panel registered from Lua and passes what the user did in it on to the
+        /// script.
+        fn render_lua_panel(&mut self, ui: &mut egui::Ui, panel: &lua::Panel) {
+            let mut hide = false;
+            ui.horizontal(|ui| {
+                ui.strong(&panel.title);
+                hide = ui.button(i18n::tr("button-hide")).clicked();
+            });
+            ui.separator();
+            if let Some(error) = &panel.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            let mut events = Vec::new();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, widget) in panel.widgets.iter().enumerate() {
+                    match widget {
+                        lua::PanelWidget::Label { text } => {
+                            ui.label(text);
+                        }
+                        lua::PanelWidget::Button { text } => {
+                            if ui.button(text).clicked() {
+                                events.push((index, lua::PanelEvent::Clicked));
+                            }
+                        }
+                        lua::PanelWidget::List { items, selected } => {
+                            for (item_index, item) in items.iter().enumerate() {
+                                if ui
+                                    .selectable_label(*selected == Some(item_index), item)
+                                    .clicked()
+                                {
+                                    events.push((index, lua::PanelEvent::Selected(item_index)));
+                                }
+                            }
+                        }
+                        lua::PanelWidget::Input {
+                            id,
+                            text,
+                            hint,
+                            clear_on_submit,
+                        } => {
+                            let value = self
+                                .panel_inputs
+                                .entry((panel.name.clone(), id.clone()))
+                                .or_insert_with(|| text.clone());
+                            let mut input = egui::TextEdit::singleline(value)
+                                .id_salt(("lua_panel_input", &panel.name, id))
+                                .desired_width(f32::INFINITY);
+                            if let Some(hint) = hint {
+                                input = input.hint_text(hint);
+                            }
+                            let response = ui.add(input);
+                            self.panel_input_focused |= response.has_focus();
+                            if response.changed() {
+                                events.push((index, lua::PanelEvent::Changed(value.clone())));
+                            }
+                            if response.lost_focus()
+                                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                            {
+                                events.push((index, lua::PanelEvent::Submitted(value.clone())));
+                                if *clear_on_submit {
+                                    value.clear();
+                                }
+                                response.request_focus();
+                            }
+                        }
+                    }
+                }
+            });
+
+            for (index, event) in events {
+                if let Err(e) = self.lua_runtime.panel_event(&panel.name, index, event) {
+                    eprintln!("Lua panel `{}` failed: {:#}", panel.name, e);
+                }
+            }
+            if hide && let Err(e) = self.lua_runtime.set_panel_visible(&panel.name, false) {
+                eprintln!("Failed to hide Lua panel `{}`: {:#}", panel.name, e);
+            }
+        }
+
+        /// Records where the main window is for the session. Once the monitor is known, a window
+        /// restored from the session is first fitted onto it, in case the monitors changed.
+        fn track_window(&mut self, ctx: &egui::Context) {
+            let (viewport, content) = ctx.input(|i| (i.viewport().clone(), i.screen_rect().size()));
+            let Some(monitor) = viewport.monitor_size else {
+                return;
+            };
+            if let Some(fitted) = self
+                .window_to_fit
+                .take()
+                .and_then(|window| window.fit_to(monitor))
+            {
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(fitted.size.into()));
+                if let Some(position) = fitted.position {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position.into()));
+                }
+            }
+            if viewport.minimized == Some(true) || viewport.fullscreen == Some(true) {
+                return;
+            }
+            let normal = WindowGeometry {
+                position: viewport.outer_rect.map(|rect| rect.min.into()),
+                size: content.into(),
+                maximized: false,
+                monitor: Some(monitor.into()),
+            };
+            self.window = Some(if viewport.maximized == Some(true) {
+                // Keep the size from before maximizing, for when the window is restored
+                WindowGeometry {
+                    maximized: true,
+                    ..self.window.unwrap_or(normal)
+                }
+            } else {
+                normal
+            });
+        }
+
+        /// Updates the window title, the language in the status bar and the saved session from
+        /// the changes to buffer metadata since the last frame.
+        fn apply_metadata_changes(&mut self, ctx: &egui::Context) {
+            let changes = self.edtr_state.take_metadata_changes();
+            for change in &changes {
+                if let led::buffer::meta::Change::Closed { buffer_id } = change {
+                    self.caches.forget_buffer(*buffer_id);
+                    forget_conflict_highlights(ctx, *buffer_id);
+                }
+            }
+            // Marks of a file are saved under its path, and move with it
+            if changes.iter().any(|change| {
+                matches!(
+                    change,
+                    led::buffer::meta::Change::FilePath { .. }
+                        | led::buffer::meta::Change::Closed { .. }
+                )
+            }) {
+                self.save_session();
+            }
+
+            let active = self.edtr_state.get_active_buffer();
+            if active == self.titled_buffer
+                && !changes
+                    .iter()
+                    .any(|change| Some(change.buffer_id()) == active)
+            {
+                return;
+            }
+            self.titled_buffer = active;
+            let meta = active.and_then(|buffer_id| self.edtr_state.metadata(buffer_id));
+            self.status_language = match meta.and_then(|meta| meta.language.as_deref()) {
+                Some(language) => {
+                    let mut chars = language.chars();
+                    chars.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().chain(chars).collect()
+                    })
+                }
+                None => i18n::tr("status-plain-text"),
+            };
+            let title = match (active, meta) {
+                (Some(buffer_id), Some(meta)) => i18n::tr_with(
+                    if meta.modified {
+                        "window-title-modified"
+                    } else {
+                        "window-title"
+                    },
+                    &[("name", self.buffer_name(buffer_id).into())],
+                ),
+                _ => APP_NAME.to_string(),
+            };
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+        }
+
+        /// Records which buffers are on screen, and shrinks the caches when the window loses
+        /// the focus: all of them are dropped and the buffers compacted, since the user is
+        /// likely away for a while.
+        fn track_caches(&mut self, ctx: &egui::Context, now: std::time::Instant) {
+            let panes = self.panes.as_ref().map_or_else(Vec::new, |panes| {
+                panes
+                    .layout
+                    .panes()
+                    .into_iter()
+                    .filter_map(|pane| self.edtr_state.view(pane))
+                    .map(|view| Some(view.buffer_id()))
+                    .collect()
+            });
+            let notes = self.notes_buffer_id().filter(|_| self.show_notes);
+            for buffer_id in [self.edtr_state.get_active_buffer(), notes]
+                .into_iter()
+                .chain(panes)
+                .flatten()
+            {
+                self.caches.shown(buffer_id, now);
+            }
+            if self.project_search.is_some() {
+                self.caches.shown(cache::Owner::Project, now);
+                if std::mem::take(&mut self.index_evicted) && self.settings.project_index {
+                    self.start_indexing(false);
+                }
+            }
+
+            let focused = ctx.input(|i| i.focused);
+            if self.window_focused && !focused {
+                self.trim_caches(ctx, 0);
+                self.edtr_state.compact_buffers(1);
+            }
+            self.window_focused = focused;
+        }
+
+        /// Measures the caches of every buffer and of the project and drops those over a ceiling,
+        /// starting with those used least recently. Dropped caches are rebuilt when next needed;
+        /// the project index is saved first and loaded again when a search opens.
+        ///
+        /// egui keeps its own cache of laid-out text, which drops what wasn't drawn lately; it
+        /// is not counted here.
+        ///
+        /// # Arguments
+        ///
+        /// * `ceiling` - The bytes the caches may hold.
+        fn trim_caches(&mut self, ctx: &egui::Context, ceiling: usize) {
+            let snapshots: HashMap<_, _> = self.edtr_state.snapshot_sizes().into_iter().collect();
+            for buffer_id in self.edtr_state.buffer_ids() {
+                let snapshot = snapshots.get(&buffer_id).copied().unwrap_or(0);
+                self.caches
+                    .set_size(cache::Kind::Snapshot, buffer_id, snapshot);
+                self.caches.set_size(
+                    cache::Kind::ConflictHighlights,
+                    buffer_id,
+                    conflict_highlights_size(ctx, buffer_id),
+                );
+            }
+            let index_size = self.index.as_ref().map_or(0, index::Index::memory_size);
+            self.caches
+                .set_size(cache::Kind::ProjectIndex, cache::Owner::Project, index_size);
+            for (kind, owner) in self.caches.evict(ceiling) {
+                match (kind, owner) {
+                    (cache::Kind::Snapshot, cache::Owner::Buffer(buffer_id)) => {
+                        self.edtr_state.forget_snapshot(buffer_id)
+                    }
+                    (cache::Kind::ConflictHighlights, cache::Owner::Buffer(buffer_id)) => {
+                        forget_conflict_highlights(ctx, buffer_id)
+                    }
+                    (cache::Kind::ProjectIndex, _) => {
+                        self.save_index();
+                        self.index = None;
+                        self.index_evicted = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        /// Saves marks and other per-project state to `.led/session.json`.
+        fn save_session(&self) {
+            let Some(root) = &self.project_root else {
+                return;
+            };
+            let mut order = self.tab_order.clone();
+            tab_bar::sync(&mut order, &self.open_documents());
+            let open_files = order
+                .into_iter()
+                .filter_map(|buffer_id| {
+                    let path = self.edtr_state.metadata(buffer_id)?.file_path.clone()?;
+                    let cursor = self.edtr_state.get_cursor_state(buffer_id)?.position();
+                    let scroll = self
+                        .scroll_offsets
+                        .get(&buffer_id)
+                        .or_else(|| self.restored_scroll.get(&buffer_id))
+                        .copied()
+                        .unwrap_or_default();
+                    Some(session::OpenFile {
+                        path,
+                        cursor,
+                        scroll: [scroll.x, scroll.y],
+                    })
+                })
+                .collect();
+            let active_file = self
+                .edtr_state
+                .get_active_buffer()
+                .and_then(|buffer_id| self.edtr_state.metadata(buffer_id)?.file_path.clone());
+            let session = Session {
+                marks: marks::file_marks(&self.edtr_state),
+                window: self.window,
+                recent_dirs: self.recent_dirs.clone(),
+                search_history: self.search_history.clone(),
+                open_files,
+                active_file,
+            };
+            if let Err(e) = session.save(root) {
+                eprintln!("Failed to save session: {:#}", e);
+            }
+        }
+
+        fn notes_buffer_id(&self) -> Option<led::buffer::ID> {
+            self.notes.as_ref().map(|scratchpad| scratchpad.buffer_id())
+        }
+
+        /// Returns the open documents, each with when it was opened, as the tab bar orders them.
+        fn open_documents(&self) -> Vec<(led::buffer::ID, std::time::SystemTime)> {
+            self.document_buffers()
+                .into_iter()
+                .filter_map(|id| Some((id, self.edtr_state.metadata(id)?.created_at)))
+                .collect()
+        }
+
+        /// Returns the IDs of open buffers the user edits directly, i.e. all but the notes buffer.
+        fn document_buffers(&self) -> Vec<led::buffer::ID> {
+            let notes = self.notes_buffer_id();
+            self.edtr_state
+                .buffers()
+                .keys()
+                .copied()
+                .filter(|id| Some(*id) != notes)
+                .collect()
+        }
+
+        /// Reads the active buffer's file again, editing the buffer to match it so marks, views
+        /// and the caret stay on their text. Buffers with unsaved changes are left alone.
+        fn reload_active_buffer(&mut self) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let Some(meta) = self.edtr_state.metadata(buffer_id) else {
+                return;
+            };
+            let Some(path) = meta.file_path.clone() else {
+                return;
+            };
+            if meta.modified {
+                self.flash(i18n::tr("reload-unsaved"));
+                return;
+            }
+            let result = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| self.edtr_state.reload_text(buffer_id, &content));
+            if let Err(e) = result {
+                eprintln!("Failed to reload {}: {:#}", path, e);
+            }
+        }
+
+        /// Evaluates the selected text as Lua and shows the result or inserts it after the
+        /// selection, as configured.
+        fn evaluate_selection(&mut self) {
+            let (Some(buffer_id), Some(range)) = (
+                self.edtr_state.get_active_buffer(),
+                self.selection_offsets(),
+            ) else {
+                self.flash(i18n::tr("evaluate-no-selection"));
+                return;
+            };
+            let Some(source) = self
+                .edtr_state
+                .buffers()
+                .get(&buffer_id)
+                .map(|table| table.get_text(range.start, range.len()))
+            else {
+                return;
+            };
+            let value = match self.lua_runtime.evaluate(&source) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    self.flash(i18n::tr("evaluate-no-result"));
+                    return;
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    let error = error.lines().next().unwrap_or_default().to_string();
+                    self.flash(i18n::tr_with("evaluate-failed", &[("error", error.into())]));
+                    return;
+                }
+            };
+            match self.settings.evaluation_result {
+                EvaluationResult::Popup => {
+                    self.flash(i18n::tr_with("evaluate-result", &[("value", value.into())]));
+                }
+                EvaluationResult::Insert => {
+                    let text = format!(" = {}", value);
+                    let end = range.end + text.len();
+                    let _ = self
+                        .edtr_state
+                        .execute_command(editor::Command::InsertText {
+                            buffer_id,
+                            offset: range.end,
+                            text,
+                        });
+                    if let Some(table) = self.edtr_state.buffers().get(&buffer_id) {
+                        let position = table.offset_to_position(end);
+                        let _ = self
+                            .edtr_state
+                            .execute_command(editor::Command::MoveCursor {
+                                buffer_id,
+                                position,
+                            });
+                    }
+                    self.reveal_cursor = true;
+                }
+            }
+        }
+
+        /// Inserts the snippet registered from Lua under a name.
+        fn insert_registered_snippet(&mut self, name: &str) {
+            let body = match self.lua_runtime.registered_snippets() {
+                Ok(snippets) => snippets
+                    .into_iter()
+                    .find(|s| s.name == name)
+                    .map(|s| s.body),
+                Err(e) => {
+                    eprintln!("Failed to list Lua snippets: {:#}", e);
+                    return;
+                }
+            };
+            match body {
+                Some(body) => self.insert_snippet(&body),
+                None => eprintln!("Unknown snippet: {}", name),
+            }
+        }
+
+        /// Fills in the placeholders of a snippet and inserts it at the caret of the active
+        /// buffer, replacing the selection. The caret ends up at the snippet's `${cursor}`,
+        /// or after it.
+        fn insert_snippet(&mut self, body: &str) {
+            let Some(buffer_id) = self.edtr_state.get_active_buffer() else {
+                return;
+            };
+            let (Some(table), Some(cursor)) = (
+                self.edtr_state.buffers().get(&buffer_id),
+                self.edtr_state.get_cursor_state(buffer_id),
+            ) else {
+                return;
+            };
+            let caret = table.position_to_offset(cursor.position()).min(table.len());
+            let range = self.selection_offsets().unwrap_or(caret..caret);
+            let selection = table.get_text(range.start, range.len());
+            let path = self
+                .edtr_state
+                .metadata(buffer_id)
+                .and_then(|meta| meta.file_path.clone());
+            let context = snippet::Context {
+                file_path: path.as_deref(),
+                selection: &selection,
+                now: snippet::now(),
+                date_format: &self.settings.date_format,
+                time_format: &self.settings.time_format,
+            };
+            let runtime = &self.lua_runtime;
+            let expansion = snippet::expand(body, |name| {
+                if let Some(value) = runtime.placeholder(name, path.as_deref(), &selection)? {
+                    return Ok(value);
+                }
+                snippet::builtin(name, &context)?
+                    .ok_or_else(|| anyhow::anyhow!("unknown placeholder `{}`", name))
+            });
+            let expansion = match expansion {
+                Ok(expansion) => expansion,
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    self.flash(i18n::tr_with("snippet-failed", &[("error", error.into())]));
+                    return;
+                }
+            };
+
+            let caret = range.start + expansion.cursor.unwrap_or(expansion.text.len());
+            let mut commands = Vec::new();
+            if !range.is_empty() {
+                commands.push(editor::Command::DeleteText {
+                    buffer_id,
+                    start: range.start,
+                    length: range.len(),
+                });
+            }
+            commands.push(editor::Command::InsertText {
+                buffer_id,
+                offset: range.start,
+                text: expansion.text,
+            });
+            if let Err(e) = self
+                .edtr_state
+                .execute_command(editor::Command::Batch(commands))
+            {
+                eprintln!("Failed to insert snippet: {:#}", e);
+                return;
+            }
+            if let Some(table) = self.edtr_state.buffers().get(&buffer_id) {
+                let position = table.offset_to_position(caret);
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::MoveCursor {
+                        buffer_id,
+                        position,
+                    });
+            }
+            self.reveal_cursor = true;
+        }
+
+        /// Closes the active buffer and applies the last-buffer behavior if none remain.
+        fn close_active_buffer(&mut self, ctx: &egui::Context) {
+            if let Some(buffer_id) = self.edtr_state.get_active_buffer() {
+                self.close_buffer(ctx, buffer_id);
+            }
+        }
+
+        /// Closes a buffer other than the notes buffer, first asking whether to save unsaved
+        /// changes.
+        fn close_buffer(&mut self, ctx: &egui::Context, buffer_id: led::buffer::ID) {
+            let modified = self
+                .edtr_state
+                .metadata(buffer_id)
+                .is_some_and(|meta| meta.modified);
+            if modified && Some(buffer_id) != self.notes_buffer_id() {
+                self.confirming_close = Some(buffer_id);
+            } else {
+                self.close_buffer_now(ctx, buffer_id);
+            }
+        }
+
+        /// Closes a buffer other than the notes buffer, dropping any unsaved changes. If it was
+        /// the active one, the newest remaining document becomes active, and the last-buffer
+        /// behavior applies if none remain.
+        fn close_buffer_now(&mut self, ctx: &egui::Context, buffer_id: led::buffer::ID) {
+            if Some(buffer_id) == self.notes_buffer_id() {
+                return;
+            }
+            let was_active = self.edtr_state.get_active_buffer() == Some(buffer_id);
+            let _ = self
+                .edtr_state
+                .execute_command(editor::Command::CloseBuffer { buffer_id });
+            self.scroll_offsets.remove(&buffer_id);
+            if !was_active {
+                return;
+            }
+
+            // The notes buffer is never shown in the main editor, so fall back to the newest document
+            let newest = self
+                .document_buffers()
+                .into_iter()
+                .max_by_key(|&id| self.edtr_state.metadata(id).map(|meta| meta.created_at));
+            self.edtr_state.set_active_buffer(newest);
+            if newest.is_some() {
+                return;
+            }
+
+            match self.settings.on_last_buffer_closed {
+                LastBufferClosed::WelcomeScreen => {}
+                LastBufferClosed::ScratchBuffer => {
+                    self.edtr_state.create_buffer(String::new());
+                }
+                LastBufferClosed::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+
+        /// Returns the display names of buffers with unsaved changes.
+        fn unsaved_buffers(&self) -> Vec<String> {
+            let mut names: Vec<String> = self
+                .document_buffers()
+                .into_iter()
+                .filter_map(|id| self.edtr_state.metadata(id))
+                .filter(|meta| meta.modified)
+                .map(|meta| {
+                    meta.file_path
+                        .clone()
+                        .unwrap_or_else(|| i18n::tr("untitled"))
+                })
+                .collect();
+            names.sort();
+            names
+        }
+
+        /// Intercepts window close requests and asks for confirmation while there is unsaved work.
+        fn handle_close_request(&mut self, ctx: &egui::Context) {
+            if !ctx.input(|i| i.viewport().close_requested()) || self.quit_confirmed {
+                return;
+            }
+            if self.settings.confirm_quit && !self.unsaved_buffers().is_empty() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.confirming_quit = true;
+            }
+        }
+
+        fn render_quit_dialog(&mut self, ctx: &egui::Context) {
+            let unsaved = self.unsaved_buffers();
+            egui::Window::new(i18n::tr("quit-title"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(i18n::tr_with(
+                        "quit-unsaved",
+                        &[("count", unsaved.len().into())],
+                    ));
+                    for name in &unsaved {
+                        ui.label(format!("  • {}", name));
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(i18n::tr("quit-without-saving")).clicked() {
+                            self.confirming_quit = false;
+                            self.quit_confirmed = true;
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                        if ui.button(i18n::tr("button-cancel")).clicked() {
+                            self.confirming_quit = false;
+                        }
+                    });
+                });
+        }
+
+        /// Asks whether to save the unsaved changes of a document being closed. Save closes it
+        /// once it is saved, which for a document without a file is after a path was picked;
+        /// Discard closes it right away and Cancel or Esc keeps it open.
+        fn render_close_dialog(&mut self, ctx: &egui::Context) {
+            let Some(buffer_id) = self.confirming_close else {
+                return;
+            };
+            if self.edtr_state.metadata(buffer_id).is_none() {
+                self.confirming_close = None;
+                return;
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.confirming_close = None;
+                return;
+            }
+            let name = self.buffer_name(buffer_id);
+            let mut choice = None;
+            egui::Window::new(i18n::tr("close-title"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(i18n::tr_with("close-unsaved", &[("name", name.into())]));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(i18n::tr("close-save")).clicked() {
+                            choice = Some(true);
+                        }
+                        if ui.button(i18n::tr("close-discard")).clicked() {
+                            choice = Some(false);
+                        }
+                        if ui.button(i18n::tr("button-cancel")).clicked() {
+                            self.confirming_close = None;
+                        }
+                    });
+                });
+            match choice {
+                Some(true) => {
+                    self.confirming_close = None;
+                    self.closing_after_save = Some(buffer_id);
+                    self.save_buffer(buffer_id);
+                }
+                Some(false) => {
+                    self.confirming_close = None;
+                    self.close_buffer_now(ctx, buffer_id);
+                }
+                None => {}
+            }
+        }
+
+        /// Closes the document saved from the close dialog once it is saved. If it is still
+        /// unsaved after the path picker was dismissed, or saving failed, it stays open.
+        fn close_saved_buffer(&mut self, ctx: &egui::Context) {
+            let Some(buffer_id) = self.closing_after_save else {
+                return;
+            };
+            let modified = self
+                .edtr_state
+                .metadata(buffer_id)
+                .map(|meta| meta.modified);
+            match modified {
+                Some(false) => {
+                    self.closing_after_save = None;
+                    self.close_buffer_now(ctx, buffer_id);
+                }
+                Some(true) if self.file_picker.is_some() || self.native_dialog.is_some() => {}
+                _ => self.closing_after_save = None,
+            }
+        }
+
+        /// Shows the window importing another editor's settings and shortcuts into the project.
+        fn render_import_wizard(&mut self, ctx: &egui::Context) {
+            let Some(mut wizard) = self.import_wizard.take() else {
+                return;
+            };
+            let mut open = true;
+            let mut run = false;
+            egui::Window::new(i18n::tr("import-title"))
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(i18n::tr("import-from"));
+                        for editor in [import::Editor::VsCode, import::Editor::Sublime] {
+                            if ui.radio(wizard.editor == editor, editor.name()).clicked()
+                                && wizard.editor != editor
+                            {
+                                wizard = ImportWizard::new(editor);
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(i18n::tr("import-folder"));
+                        ui.add(egui::TextEdit::singleline(&mut wizard.dir).desired_width(360.0));
+                    });
+                    ui.add_space(4.0);
+                    ui.add_enabled_ui(self.project_root.is_some(), |ui| {
+                        run = ui
+                            .button(i18n::tr("import-run"))
+                            .on_disabled_hover_text(i18n::tr("import-no-project"))
+                            .clicked();
+                    });
+
+                    match &wizard.outcome {
+                        None => {}
+                        Some(Err(e)) => {
+                            ui.separator();
+                            ui.colored_label(ui.visuals().error_fg_color, e);
+                        }
+                        Some(Ok(imported)) => {
+                            ui.separator();
+                            ui.label(i18n::tr_with(
+                                "import-done",
+                                &[
+                                    ("settings", imported.settings.len().into()),
+                                    ("shortcuts", imported.bindings.len().into()),
+                                ],
+                            ));
+                            if !imported.skipped.is_empty() {
+                                ui.label(i18n::tr_with(
+                                    "import-skipped",
+                                    &[("count", imported.skipped.len().into())],
+                                ));
+                                egui::ScrollArea::vertical()
+                                    .max_height(240.0)
+                                    .show(ui, |ui| {
+                                        for skipped in &imported.skipped {
+                                            ui.label(format!(
+                                                "  • {}: {}",
+                                                skipped.key.as_deref().unwrap_or_default(),
+                                                skipped.message
+                                            ));
+                                        }
+                                    });
+                            }
+                        }
+                    }
+                });
 
-                // Commands are now executed immediately in Widget::show, so do not execute them here.
+            if run && let Some(root) = self.project_root.clone() {
+                let platform = Platform::current();
+                let dir = PathBuf::from(wizard.dir.trim());
+                let outcome = import::Import::read(wizard.editor, &dir, platform)
+                    .and_then(|imported| imported.write(&root, platform).map(|()| imported));
+                wizard.outcome = Some(outcome.map_err(|e| format!("{:#}", e)));
+                if matches!(wizard.outcome, Some(Ok(_))) {
+                    self.load_settings();
+                    match Keymap::load(&root, platform) {
+                        Ok(keymap) => self.keymap = keymap,
+                        Err(e) => eprintln!("Failed to load keymap: {:#}", e),
+                    }
+                }
+            }
+            if open {
+                self.import_wizard = Some(wizard);
             }
         }
 
-        fn render_status_bar(&self, ui: &mut egui::Ui) {
-            ui.horizontal(|ui| ui.label(format!("Frame: {:.1}ms", self.frame_time * 1000.0)));
-            ui.separator();
-            // Cursor pos
-            if let Some(buffer_id) = self.edtr_state.get_active_buffer() {
-                if let Some(cursor) = self.edtr_state.get_cursor_state(buffer_id) {
-                    ui.label(format!(
-                        "Ln {}, Col {}",
-                        cursor.position().line + 1,
-                        cursor.position().column + 1
+        /// Asks whether the first script waiting for permission may run programs.
+        fn render_permission_request(&mut self, ctx: &egui::Context) {
+            let script = self.permission_requests[0].clone();
+            let mut decision = None;
+            egui::Window::new(i18n::tr("permission-title"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(i18n::tr("permission-body"));
+                    ui.monospace(&script);
+                    ui.label(i18n::tr_with(
+                        "permission-limits",
+                        &[(
+                            "seconds",
+                            format!("{:.1}", self.settings.process_timeout_ms as f64 / 1000.0)
+                                .into(),
+                        )],
                     ));
+                    ui.label(i18n::tr("permission-remembered"));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(i18n::tr("permission-allow")).clicked() {
+                            decision = Some(true);
+                        }
+                        if ui.button(i18n::tr("permission-deny")).clicked() {
+                            decision = Some(false);
+                        }
+                    });
+                });
+            if let Some(allow) = decision {
+                if let Err(e) = self.lua_runtime.decide_permission(&script, allow) {
+                    eprintln!("Failed to save process permissions: {:#}", e);
                 }
+                self.permission_requests.remove(0);
             }
-            ui.separator();
-
-            // Buffer info
-            ui.label("UTF-8");
-            ui.label("Rust");
         }
 
-        fn render_menu_bar(&mut self, ui: &mut egui::Ui) {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("New").clicked() {
-                        let buffer_id = self.edtr_state.create_buffer(String::new());
+        /// Lists the problems found in the settings files, so invalid values don't fall back to
+        /// their defaults unnoticed.
+        fn render_settings_problems(&mut self, ctx: &egui::Context) {
+            egui::Window::new(i18n::tr("settings-problems-title"))
+                .collapsible(true)
+                .resizable(false)
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+                .show(ctx, |ui| {
+                    for problem in &self.settings_problems {
+                        ui.label(egui::RichText::new(problem.file.display().to_string()).weak());
+                        match &problem.key {
+                            Some(key) => ui.label(format!("  • {}: {}", key, problem.message)),
+                            None => ui.label(format!("  • {}", problem.message)),
+                        };
                     }
-
-                    if ui.button("Open").clicked() {
-                        if let Some(path) = FileDialog::new().pick_file() {
-                            match fs::read_to_string(&path) {
-                                Ok(content) => {
-                                    let buffer_id = self.edtr_state.create_buffer(content);
-                                    // Store file path in buffer metadata
-                                    if let Some(meta) =
-                                        self.edtr_state.buffer_metadata.get_mut(&buffer_id)
-                                    {
-                                        meta.file_path = Some(path.to_string_lossy().to_string());
-                                        meta.modified = false;
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to open file: {}", e);
-                                    // TODO: Display error in UI instead of just printing to console
-                                }
-                            }
-                        }
+                    ui.add_space(8.0);
+                    if ui.button(i18n::tr("button-dismiss")).clicked() {
+                        self.settings_problems.clear();
                     }
+                });
+        }
 
-                    if ui.button("Save").clicked() {
-                        if let Some(buffer_id) = self.edtr_state.get_active_buffer() {
-                            let file_path = self
-                                .edtr_state
-                                .buffer_metadata
-                                .get(&buffer_id)
-                                .and_then(|meta| meta.file_path.clone())
-                                .or_else(|| {
-                                    FileDialog::new()
-                                        .save_file()
-                                        .map(|p| p.to_string_lossy().to_string())
-                                });
+        /// Shows or hides the scratchpad notes panel, focusing it when shown and saving it when hidden.
+        fn toggle_notes(&mut self) {
+            self.show_notes = !self.show_notes;
+            self.notes_focused = self.show_notes;
+            if !self.show_notes {
+                self.flush_notes();
+            }
+        }
 
-                            if let Some(path) = file_path {
-                                if let Some(content) = self.edtr_state.get_buffer_text(buffer_id) {
-                                    match fs::write(&path, content) {
-                                        Ok(_) => {
-                                            // Update buffer metadata
-                                            if let Some(meta) =
-                                                self.edtr_state.buffer_metadata.get_mut(&buffer_id)
-                                            {
-                                                meta.file_path = Some(path);
-                                                meta.modified = false;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Failed to save file: {}", e);
-                                            // TODO: Display error in UI instead of just printing to console
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+        /// Writes any unsaved notes to disk immediately.
+        fn flush_notes(&mut self) {
+            if let Some(scratchpad) = &mut self.notes
+                && let Err(e) = scratchpad.save(&mut self.edtr_state)
+            {
+                eprintln!("Failed to save notes: {}", e);
+            }
+        }
 
-                    ui.separator();
+        fn render_notes_panel(&mut self, ui: &mut egui::Ui) {
+            let Some(scratchpad) = &self.notes else {
+                ui.label(i18n::tr("notes-unavailable"));
+                return;
+            };
+            let buffer_id = scratchpad.buffer_id();
 
-                    if ui.button("Exit").clicked() {
-                        std::process::exit(0);
-                    }
-                });
+            ui.horizontal(|ui| {
+                ui.strong(i18n::tr("notes-title"));
+                ui.weak(notes::NOTES_FILE);
+            });
+            ui.separator();
 
-                ui.menu_button("Edit", |ui| {
-                    if ui.button("Undo").clicked() {
-                        todo!("Implement editing support");
-                    }
+            if ui.ui_contains_pointer() && ui.input(|i| i.pointer.primary_pressed()) {
+                self.notes_focused = true;
+            }
 
-                    if ui.button("Redo").clicked() {
-                        todo!("Implement editing support");
-                    }
+            let theme = self.theme();
+            let mut notes_editor = Widget::new(buffer_id, &mut self.edtr_state, theme);
+            notes_editor.show_line_numbers = false;
+            notes_editor.tab_size = self.tab_size;
+            notes_editor.tab_arrows = self.tab_arrows;
+            notes_editor.focused = self.notes_focused
+                && !self.panel_input_focused
+                && self.palette.is_none()
+                && self.file_picker.is_none()
+                && self.goto_line.is_none()
+                && self.project_search.is_none()
+                && self.find_bar.is_none()
+                && self.find_replace.is_none()
+                && self.buffer_switcher.is_none();
+            notes_editor.selection_toolbar = self.settings.selection_toolbar;
+            notes_editor.selection_counts = self.settings.selection_counts;
+            notes_editor.highlight_delay =
+                std::time::Duration::from_millis(self.settings.highlight_delay_ms);
+            notes_editor.long_line_wrap = self.settings.long_line_wrap as usize;
+            notes_editor.indent_after = self.settings.indent_after.clone();
+            notes_editor.use_tabs = self.settings.insert_tabs;
+            notes_editor.scheduler = Some(&mut self.scheduler);
+            notes_editor.font_size = self.font_size;
+            notes_editor.show(ui);
+            self.font_size = notes_editor.font_size;
+        }
 
-                    ui.separator();
+        /// Returns the active buffer, its conflicts and the cursor offset, if it has conflicts.
+        ///
+        /// Conflicts are only detected again once the buffer or its content changed.
+        fn active_conflicts(&mut self) -> Option<(led::buffer::ID, &[conflict::Region], usize)> {
+            let buffer_id = self.edtr_state.get_active_buffer()?;
+            let digest = self.edtr_state.content_digest(buffer_id)?;
+            if !matches!(&self.conflicts, Some((id, seen, _)) if *id == buffer_id && *seen == digest)
+            {
+                let text = self.edtr_state.get_buffer_text(buffer_id)?;
+                self.conflicts = Some((buffer_id, digest, conflict::detect(&text)));
+            }
+            let cursor = self.edtr_state.get_cursor_state(buffer_id)?;
+            let offset = self
+                .edtr_state
+                .buffers()
+                .get(&buffer_id)?
+                .position_to_offset(cursor.position());
+            let (_, _, regions) = self.conflicts.as_ref()?;
+            (!regions.is_empty()).then_some((buffer_id, regions.as_slice(), offset))
+        }
 
-                    if ui.button("Find").clicked() {
-                        todo!("Implement Find functionality");
-                    }
-                });
-                ui.menu_button("View", |ui| {
-                    ui.checkbox(&mut self.show_line_numbers, "Show Line Numbers");
-                    ui.separator();
+        /// Returns the index of the conflict under the cursor (if any) and the total conflict count.
+        fn conflict_status(&mut self) -> Option<(Option<usize>, usize)> {
+            let (_, regions, offset) = self.active_conflicts()?;
+            let current = regions.iter().position(|r| r.range.contains(&offset));
+            Some((current, regions.len()))
+        }
 
-                    ui.label("Font Size:");
-                    ui.add(egui::Slider::new(&mut self.font_size, 8.0..=24.0));
+        /// Rewrites the conflict under (or after) the cursor, keeping the chosen side(s).
+        fn resolve_conflict(&mut self, resolution: conflict::Resolution) {
+            let Some((buffer_id, regions, offset)) = self.active_conflicts() else {
+                return;
+            };
+            let Some(region) = conflict::at_or_after(regions, offset).cloned() else {
+                return;
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(buffer_id) else {
+                return;
+            };
+            let commands = region.resolve(buffer_id, &text, resolution);
+            let _ = self
+                .edtr_state
+                .execute_command(editor::Command::Batch(commands));
+            if let Some(buffer) = self.edtr_state.buffers().get(&buffer_id) {
+                let position = buffer.offset_to_position(region.range.start);
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::MoveCursor {
+                        buffer_id,
+                        position,
+                    });
+            }
+        }
 
-                    ui.label("Tab Size:");
-                    ui.add(egui::Slider::new(&mut self.tab_size, 2..=8));
-                });
-            });
+        /// Moves the cursor to the start of the next conflict, wrapping around at the end of the buffer.
+        fn goto_next_conflict(&mut self) {
+            let Some((buffer_id, regions, offset)) = self.active_conflicts() else {
+                return;
+            };
+            let Some(start) = conflict::next_after(regions, offset).map(|r| r.range.start) else {
+                return;
+            };
+            if let Some(buffer) = self.edtr_state.buffers().get(&buffer_id) {
+                let position = buffer.offset_to_position(start);
+                let _ = self
+                    .edtr_state
+                    .execute_command(editor::Command::MoveCursor {
+                        buffer_id,
+                        position,
+                    });
+            }
         }
     }
 
+    /// Draws a buffer and edits it with the keyboard and pointer, for one frame.
+    ///
+    /// The widget only borrows the editor state while it is shown: create one every frame,
+    /// set its options and call [`Widget::show`]. Everything that lasts between frames, such
+    /// as the scroll position, lives in the state or in egui's memory.
     pub struct Widget<'a> {
         buffer_id: led::buffer::ID,
+        /// The view this widget draws, if the buffer is shown in more than one pane.
+        ///
+        /// Each view keeps its own scroll position; `None` uses the buffer's.
+        view: Option<view::ID>,
         edtr_state: &'a mut led::buffer::editor::State,
-        gui_ctx: &'a mut saran::context::Context,
-        show_line_numbers: bool,
+        /// The colors to draw with.
+        theme: Theme,
+        pub show_line_numbers: bool,
 
-        font_size: f32,
-        tab_size: usize,
+        /// The font size in points; pinching changes it.
+        pub font_size: f32,
+        pub tab_size: usize,
+        /// Whether a level of indentation is a tab rather than `tab_size` spaces, in buffers
+        /// whose indentation wasn't detected when they were opened.
+        pub use_tabs: bool,
+        /// Whether tabs are drawn as arrows.
+        pub tab_arrows: bool,
+
+        /// Whether this widget receives keyboard input.
+        pub focused: bool,
+        /// Whether the caret is scrolled into view this frame even without input.
+        pub reveal_cursor: bool,
+        /// Whether the caret is scrolled to the middle of the view this frame.
+        pub center_cursor: bool,
+        /// Where to scroll the text to this frame, in points; after showing, where it is
+        /// scrolled to.
+        pub scroll_offset: Option<egui::Vec2>,
+        /// Column at which lines are soft-wrapped, if any.
+        pub wrap_column: Option<usize>,
+        /// Length in bytes above which lines are wrapped to the viewport when they aren't
+        /// soft-wrapped; 0 never wraps them.
+        pub long_line_wrap: usize,
+        /// Whether the text column is horizontally centered in the viewport.
+        pub center_text: bool,
+        /// Whether the caret row is kept vertically centered while typing and moving.
+        pub typewriter: bool,
+        /// Whether pointer selections show a floating toolbar with selection actions.
+        pub selection_toolbar: bool,
+        /// Whether a tooltip with line, word and character counts follows the pointer while
+        /// selecting by dragging.
+        pub selection_counts: bool,
+        /// The shortest time between two recomputations of highlights while typing.
+        pub highlight_delay: std::time::Duration,
+        /// Byte ranges of search matches highlighted behind the text.
+        pub occurrences: Vec<std::ops::Range<usize>>,
+        /// The characters that indent the next line by language, from the settings.
+        pub indent_after: std::collections::BTreeMap<String, String>,
+        /// Shares the frame's budget for incremental work; without one, such work is done at
+        /// once.
+        scheduler: Option<&'a mut Scheduler>,
 
         cursor_blink_time: f32,
-        scroll_offset: egui::Vec2,
     }
 
+    /// Merge conflict highlights of a buffer, kept between frames so they are only recomputed
+    /// once typing allows.
+    #[derive(Debug, Clone)]
+    struct ConflictHighlights {
+        /// Content digest of the text the regions were detected in.
+        digest: Option<u64>,
+        regions: Vec<conflict::Region>,
+        debouncer: Debouncer,
+        /// A scan in progress, with the digest of the text being scanned.
+        scan: Option<(Option<u64>, conflict::Scanner)>,
+    }
+
+    fn conflict_highlights_id(buffer_id: led::buffer::ID) -> egui::Id {
+        egui::Id::new(("conflict_highlights", buffer_id))
+    }
+
+    /// Returns roughly how many bytes the conflict highlights of a buffer hold.
+    fn conflict_highlights_size(ctx: &egui::Context, buffer_id: led::buffer::ID) -> usize {
+        ctx.data(|d| d.get_temp::<ConflictHighlights>(conflict_highlights_id(buffer_id)))
+            .map_or(0, |highlights| {
+                std::mem::size_of::<ConflictHighlights>()
+                    + highlights.regions.capacity() * std::mem::size_of::<conflict::Region>()
+            })
+    }
+
+    /// Drops the conflict highlights of a buffer; they are detected again when it is shown.
+    fn forget_conflict_highlights(ctx: &egui::Context, buffer_id: led::buffer::ID) {
+        ctx.data_mut(|d| d.remove::<ConflictHighlights>(conflict_highlights_id(buffer_id)));
+    }
+
+    /// Lines scanned for conflicts per step of a highlighting scan.
+    const CONFLICT_SCAN_LINES: usize = 5_000;
+
+    /// Rows longer than this many bytes are only shaped where they are visible.
+    const LONG_ROW_COLUMNS: usize = 1_024;
+
     // Padding constants for editor layout
     const TOP_PADDING: f32 = 4.0;
     const LEFT_PADDING: f32 = 4.0;
@@ -252,36 +6361,465 @@ fn main() {
     const TEXT_TOP_PADDING: f32 = 16.0;
     const TEXT_LEFT_PADDING: f32 = 32.0;
 
+    // Line backgrounds for merge conflict regions
+    const CONFLICT_MARKER_BG: egui::Color32 = egui::Color32::from_rgb(60, 64, 72);
+    const CONFLICT_OURS_BG: egui::Color32 = egui::Color32::from_rgb(38, 70, 52);
+    const CONFLICT_BASE_BG: egui::Color32 = egui::Color32::from_rgb(70, 62, 40);
+    const CONFLICT_THEIRS_BG: egui::Color32 = egui::Color32::from_rgb(36, 58, 84);
+
+    // Background behind virtual text, so it reads as separate from the buffer's text
+    const VIRTUAL_TEXT_BG: egui::Color32 = egui::Color32::from_rgb(46, 50, 58);
+
+    /// Returns the text color for a virtual text style.
+    fn virtual_text_color(style: virtual_text::Style) -> egui::Color32 {
+        match style {
+            virtual_text::Style::Hint => egui::Color32::from_rgb(128, 134, 146),
+            virtual_text::Style::Ghost => egui::Color32::from_rgb(100, 106, 118),
+            virtual_text::Style::Info => egui::Color32::from_rgb(97, 175, 239),
+            virtual_text::Style::Warning => egui::Color32::from_rgb(229, 192, 123),
+            virtual_text::Style::Error => egui::Color32::from_rgb(224, 108, 117),
+        }
+    }
+
+    // Font size range reachable from the View menu and pinch-to-zoom
+    const MIN_FONT_SIZE: f32 = 8.0;
+    const MAX_FONT_SIZE: f32 = 24.0;
+    // Points a pane's font size changes by per zoom command
+    const PANE_ZOOM_STEP: f32 = 1.0;
+    // Space between the panes of a split editor
+    const PANE_GAP: f32 = 4.0;
+    // Dims the window while files are dragged over it
+    const DROP_HINT_BG: egui::Color32 = egui::Color32::from_black_alpha(160);
+    // Radius of the touch selection drag handles
+    const HANDLE_RADIUS: f32 = 7.0;
+
+    // Background of search matches other than the selected one
+    const OCCURRENCE_BG: egui::Color32 = egui::Color32::from_rgba_premultiplied(43, 36, 23, 48);
+    // Lines removed and added in the preview of a project-wide replace
+    const REPLACE_REMOVED_FG: egui::Color32 = egui::Color32::from_rgb(224, 108, 117);
+    const REPLACE_ADDED_FG: egui::Color32 = egui::Color32::from_rgb(152, 195, 121);
+    // Scrollbar markers showing where search matches are in the document
+    const MATCH_MARKER: egui::Color32 = egui::Color32::from_rgba_premultiplied(172, 140, 70, 200);
+    // Height of a scrollbar marker, in points
+    const MATCH_MARKER_HEIGHT: f32 = 2.0;
+    // Background of the bracket next to the caret and its match
+    const MATCHING_BRACKET_BG: egui::Color32 =
+        egui::Color32::from_rgba_premultiplied(38, 50, 64, 72);
+
+    // Markdown heading colour for the notes buffer
+    const MARKDOWN_HEADING_FG: egui::Color32 = egui::Color32::from_rgb(97, 175, 239);
+
+    /// Screen geometry of a widget's text area for a single frame.
+    struct Layout {
+        /// Top-left corner of the first visual row.
+        text_origin: egui::Pos2,
+        line_height: f32,
+        char_width: f32,
+        /// Text rows in display order, as a line index and a byte range within that line.
+        rows: Vec<(usize, std::ops::Range<usize>)>,
+        /// The visual order of each row with right-to-left text; `None` for rows shown in
+        /// logical order.
+        bidi: Vec<Option<bidi::Line>>,
+        /// The cells of each row with tabs and no right-to-left text; `None` for rows where
+        /// each column is one cell.
+        cells: Vec<Option<tabs::Cells>>,
+        /// Where virtual text sits among the rows.
+        arrangement: virtual_text::Arrangement,
+        /// The folded lines, which have no rows.
+        hidden: Vec<std::ops::Range<usize>>,
+    }
+
+    /// Pointer selection state kept in egui memory between frames.
+    #[derive(Debug, Clone, Copy, Default)]
+    struct PointerSelection {
+        /// Where a long-press or mouse drag started the selection; `None` when no pointer
+        /// selection is being made.
+        anchor: Option<Position>,
+        /// Whether the selection was started by a long-press, and so has drag handles.
+        touch: bool,
+        /// The selection the floating toolbar belongs to, until it is dismissed.
+        toolbar: Option<Range>,
+    }
+
+    /// Actions offered by the floating selection toolbar.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ToolbarAction {
+        Copy,
+        Cut,
+        Comment,
+        Search,
+    }
+
+    /// Returns where Left or Right moves the caret.
+    ///
+    /// Within a line with right-to-left text the caret moves the way the arrow points on
+    /// screen. Past the line's edge it moves to the neighbouring line, following the line's
+    /// direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The buffer's lines.
+    /// * `position` - The caret position.
+    /// * `right` - Whether Right was pressed rather than Left.
+    /// * `tab_size` - The distance between tab stops, in cells.
+    fn horizontal_move(
+        lines: &[&str],
+        position: Position,
+        right: bool,
+        tab_size: usize,
+    ) -> Position {
+        let mut forward = right;
+        if let Some(line) = lines.get(position.line)
+            && bidi::has_rtl(line)
+        {
+            let visual = bidi::Line::new(line, 0..line.len(), tab_size);
+            if let Some(column) = visual.move_visually(position.column, right) {
+                return Position { column, ..position };
+            }
+            forward = right == (visual.direction() == bidi::Direction::LeftToRight);
+        }
+
+        let mut new_pos = position;
+        // Columns are bytes, so step over whole characters
+        if !forward {
+            if new_pos.column > 0 {
+                let before = lines
+                    .get(new_pos.line)
+                    .and_then(|line| line.get(..new_pos.column))
+                    .and_then(|text| text.chars().next_back());
+                new_pos.column -= before.map_or(1, char::len_utf8);
+            } else if new_pos.line > 0 {
+                new_pos.line -= 1;
+                // Move to end of previous line
+                if new_pos.line < lines.len() {
+                    new_pos.column = lines[new_pos.line].len();
+                }
+            }
+        } else if new_pos.line < lines.len() {
+            let current_line = lines[new_pos.line];
+            if new_pos.column < current_line.len() {
+                let after = current_line
+                    .get(new_pos.column..)
+                    .and_then(|text| text.chars().next());
+                new_pos.column += after.map_or(1, char::len_utf8);
+            } else if new_pos.line + 1 < lines.len() {
+                new_pos.line += 1;
+                new_pos.column = 0;
+            }
+        }
+        new_pos
+    }
+
+    /// Returns where a horizontal move that landed on a folded line goes instead: past the folded
+    /// lines when moving forward, to the end of their header when moving back. A forward move
+    /// into folded lines that run to the end of the text stays where it was.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The buffer's lines.
+    /// * `hidden` - The folded lines.
+    /// * `from` - The caret position before the move.
+    /// * `to` - Where the move would go.
+    fn skip_folded(
+        lines: &[&str],
+        hidden: &[std::ops::Range<usize>],
+        from: Position,
+        to: Position,
+    ) -> Position {
+        if !folds::is_hidden(hidden, to.line) {
+            return to;
+        }
+        let down = to.line > from.line;
+        let line = folds::skip_hidden(hidden, to.line, down);
+        if down {
+            if line < lines.len() {
+                Position { line, column: 0 }
+            } else {
+                from
+            }
+        } else {
+            Position {
+                line,
+                column: lines.get(line).map_or(0, |text| text.len()),
+            }
+        }
+    }
+
+    impl Layout {
+        /// Returns the visual row and the column within that row for a buffer position.
+        fn row_of(&self, position: Position) -> (usize, usize) {
+            // Folded lines show at the end of their fold's header
+            if folds::is_hidden(&self.hidden, position.line) {
+                let header = folds::skip_hidden(&self.hidden, position.line, false);
+                let row = self
+                    .rows
+                    .partition_point(|(line, _)| *line <= header)
+                    .saturating_sub(1);
+                let end = self.rows.get(row).map_or(0, |(_, range)| range.len());
+                return (row, end);
+            }
+            let idx = self.rows.partition_point(|(line, range)| {
+                (*line, range.start) <= (position.line, position.column)
+            });
+            match idx.checked_sub(1).map(|i| (i, &self.rows[i])) {
+                Some((i, (line, range))) if *line == position.line => {
+                    (i, position.column - range.start)
+                }
+                // Positions past the last painted line (e.g. a trailing empty line), less the
+                // folded lines between
+                Some((i, (line, _))) => {
+                    let folded: usize = self
+                        .hidden
+                        .iter()
+                        .map(|range| {
+                            range
+                                .end
+                                .min(position.line)
+                                .saturating_sub(range.start.max(line + 1))
+                        })
+                        .sum();
+                    (i + position.line - line - folded, position.column)
+                }
+                None => (position.line, position.column),
+            }
+        }
+
+        /// Returns the buffer position closest to a point on screen.
+        ///
+        /// Points on a virtual text block map to the start of the line below it, and points on
+        /// inline virtual text to the nearer of its two sides.
+        fn position_at(&self, point: egui::Pos2) -> Position {
+            let Some(last) = self.rows.len().checked_sub(1) else {
+                return Position { line: 0, column: 0 };
+            };
+            let offset = point - self.text_origin;
+            let visual = (offset.y / self.line_height).floor().max(0.0) as usize;
+            let row = self
+                .arrangement
+                .row_tops
+                .partition_point(|top| *top < visual)
+                .min(last);
+            let (line, range) = &self.rows[row];
+            let target = offset.x / self.char_width;
+            if let Some(bidi) = &self.bidi[row] {
+                return Position {
+                    line: *line,
+                    column: range.start + bidi.column_at(target),
+                };
+            }
+            let candidates: Vec<usize> = match &self.cells[row] {
+                Some(cells) => cells.columns().to_vec(),
+                None => (0..=range.len()).collect(),
+            };
+            let column = candidates
+                .into_iter()
+                .min_by(|a, b| {
+                    let distance = |column: &usize| {
+                        let shift = self
+                            .arrangement
+                            .inline_shift(row, range.start + column, false);
+                        ((self.cell(row, *column) + shift) as f32 - target).abs()
+                    };
+                    distance(a).total_cmp(&distance(b))
+                })
+                .unwrap_or(0);
+            Position {
+                line: *line,
+                column: range.start + column,
+            }
+        }
+
+        /// Returns the top of the visual row a text row is drawn on.
+        fn row_y(&self, row: usize) -> f32 {
+            let tops = &self.arrangement.row_tops;
+            let visual = match tops.get(row) {
+                Some(top) => *top,
+                // Rows past the last painted line (e.g. a trailing empty line)
+                None => self.arrangement.visual_rows + row - tops.len(),
+            };
+            self.text_origin.y + visual as f32 * self.line_height
+        }
+
+        /// Returns the left edge of a column within a row, making room for inline virtual text.
+        ///
+        /// # Arguments
+        ///
+        /// * `row` - Index of the text row.
+        /// * `column` - Column within the row.
+        /// * `inclusive` - Whether inline text inserted at the column comes before it, as it
+        ///   does for the character there but not for the caret.
+        fn column_x(&self, row: usize, column: usize, inclusive: bool) -> f32 {
+            let start = self.rows.get(row).map_or(0, |(_, range)| range.start);
+            let shift = self
+                .arrangement
+                .inline_shift(row, start + column, inclusive);
+            self.text_origin.x + (self.cell(row, column) + shift) as f32 * self.char_width
+        }
+
+        /// Returns the cell where the caret at a column within a row is drawn, not counting
+        /// inline virtual text.
+        fn cell(&self, row: usize, column: usize) -> usize {
+            if let Some(Some(bidi)) = self.bidi.get(row) {
+                bidi.caret_x(column)
+            } else if let Some(Some(cells)) = self.cells.get(row) {
+                cells.cell(column)
+            } else {
+                column
+            }
+        }
+
+        /// Returns the top of the caret for a buffer position.
+        fn caret_pos(&self, position: Position) -> egui::Pos2 {
+            let (row, column) = self.row_of(position);
+            egui::pos2(self.column_x(row, column, false), self.row_y(row))
+        }
+
+        /// Returns the index of the arranged virtual text at a point on screen, if any.
+        fn virtual_text_at(&self, point: egui::Pos2) -> Option<usize> {
+            let offset = point - self.text_origin;
+            if offset.y < 0.0 {
+                return None;
+            }
+            let visual = (offset.y / self.line_height) as usize;
+            if let Some(block) = self
+                .arrangement
+                .blocks
+                .iter()
+                .find(|block| (block.top..block.top + block.height).contains(&visual))
+            {
+                return Some(block.item);
+            }
+            let row = self
+                .arrangement
+                .row_tops
+                .iter()
+                .position(|top| *top == visual)?;
+            let start = self.rows[row].1.start;
+            self.arrangement
+                .inlines
+                .iter()
+                .find(|span| {
+                    let left = self.column_x(row, span.column - start, false);
+                    let right = left + span.width as f32 * self.char_width;
+                    span.row == row && (left..right).contains(&point.x)
+                })
+                .map(|span| span.item)
+        }
+    }
+
     impl<'a> Widget<'a> {
+        /// Creates a widget showing a buffer of the editor state, with the default options.
         pub fn new(
             buffer_id: led::buffer::ID,
             edtr_state: &'a mut led::buffer::editor::State,
-            gui_ctx: &'a mut saran::context::Context,
+            theme: Theme,
         ) -> Self {
-            // println!("[DEBUG] Widget::new called");
             Self {
                 buffer_id,
+                view: None,
                 edtr_state,
-                gui_ctx,
+                theme,
                 show_line_numbers: true,
                 font_size: 14.0,
                 tab_size: 4,
+                use_tabs: false,
+                tab_arrows: false,
+                focused: true,
+                reveal_cursor: false,
+                center_cursor: false,
+                scroll_offset: None,
+                wrap_column: None,
+                long_line_wrap: 0,
+                center_text: false,
+                typewriter: false,
+                selection_toolbar: true,
+                selection_counts: true,
+                highlight_delay: std::time::Duration::from_millis(
+                    Settings::default().highlight_delay_ms,
+                ),
+                occurrences: Vec::new(),
+                indent_after: Default::default(),
+                scheduler: None,
                 cursor_blink_time: 0.0,
-                scroll_offset: egui::Vec2::ZERO,
             }
         }
 
-        pub fn show(
-            &mut self,
-            ui: &mut Ui,
-            _rect: Rect,
-        ) -> Option<led::commands::editor::Response> {
+        /// Returns the merge conflicts of the buffer, detected at most once per highlight delay
+        /// while the text keeps changing.
+        ///
+        /// Large buffers are scanned over several frames; until a scan is done, the conflicts
+        /// found by the previous one are returned.
+        fn conflict_highlights(&mut self, ui: &Ui, text: &str) -> Vec<conflict::Region> {
+            let id = conflict_highlights_id(self.buffer_id);
+            let now = std::time::Instant::now();
+            let mut highlights = ui
+                .data(|d| d.get_temp::<ConflictHighlights>(id))
+                .unwrap_or_else(|| ConflictHighlights {
+                    digest: None,
+                    regions: Vec::new(),
+                    debouncer: Debouncer::new(self.highlight_delay, Edge::Leading),
+                    scan: None,
+                });
+            highlights.debouncer.set_interval(self.highlight_delay);
+            let digest = self.edtr_state.content_digest(self.buffer_id);
+            if highlights.digest != digest {
+                highlights.digest = digest;
+                highlights.debouncer.changed(now);
+            }
+            if highlights.debouncer.ready(now) {
+                highlights.scan = Some((digest, conflict::Scanner::new(CONFLICT_SCAN_LINES)));
+            } else if let Some(deadline) = highlights.debouncer.deadline() {
+                ui.ctx()
+                    .request_repaint_after(deadline.saturating_duration_since(now));
+            }
+            // A scan of text that has changed since is useless; the debouncer starts another
+            if highlights
+                .scan
+                .as_ref()
+                .is_some_and(|(scanned, _)| *scanned != digest)
+            {
+                highlights.scan = None;
+            }
+            if let Some((_, mut scanner)) = highlights.scan.take() {
+                let step = match self.scheduler.as_deref_mut() {
+                    Some(scheduler) => scheduler.run(&mut scanner, text),
+                    None => {
+                        while scanner.step(text) == Step::Continue {}
+                        Step::Done
+                    }
+                };
+                match step {
+                    Step::Done => highlights.regions = scanner.into_regions(),
+                    Step::Continue => {
+                        highlights.scan = Some((digest, scanner));
+                        ui.ctx().request_repaint();
+                    }
+                }
+            }
+            let regions = highlights.regions.clone();
+            ui.data_mut(|d| d.insert_temp(id, highlights));
+            regions
+        }
+
+        /// Shows the buffer in the space left in `ui` and handles this frame's input. The
+        /// commands the input produced have already been executed when this returns.
+        ///
+        /// # Returns
+        ///
+        /// What the input did, or `None` if the buffer doesn't exist.
+        pub fn show(&mut self, ui: &mut Ui) -> Option<led::commands::editor::Response> {
             let mut response = Response {
                 commands: Vec::new(),
                 cursor_moved: false,
                 text_changed: false,
             };
 
+            // A caret moved onto folded lines, as by a search or a jump, unfolds them
+            if let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) {
+                let line = cursor.position().line;
+                self.edtr_state.reveal_line(self.buffer_id, line);
+            }
+
             // Get buffer text and cursor state
             let text = self.edtr_state.get_buffer_text(self.buffer_id)?.to_string();
             let mut crsr_state = self.edtr_state.get_cursor_state(self.buffer_id)?.clone();
@@ -290,10 +6828,37 @@ fn main() {
             let line_height = ui.fonts(|f| f.row_height(&font_id));
             let char_width = ui.fonts(|f| f.glyph_width(&font_id, ' '));
 
-            let line_count = text.lines().count();
-            let max_line_length = text.lines().map(|l| l.len()).max().unwrap_or(0);
+            let conflicts = self.conflict_highlights(ui, &text);
+            // Virtual text of this buffer, with the current position of each anchor
+            let table = self.edtr_state.buffers().get(&self.buffer_id)?;
+            let virtual_items: Vec<(Position, virtual_text::Item)> = self
+                .edtr_state
+                .virtual_text()
+                .for_buffer(self.buffer_id)
+                .map(|item| {
+                    let offset = item.offset.min(table.len());
+                    (table.offset_to_position(offset), item.clone())
+                })
+                .collect();
+            let occurrences = search::positions(&text, &self.occurrences);
+            // The bracket next to the caret and its match, one character each
+            let brackets: Vec<Range> =
+                brackets::find(table, table.position_to_offset(crsr_state.position()))
+                    .map(|found| [found.bracket, found.matching])
+                    .into_iter()
+                    .flatten()
+                    .map(|offset| Range {
+                        start: table.offset_to_position(offset),
+                        end: table.offset_to_position(offset + 1),
+                    })
+                    .collect();
+            let language = self
+                .edtr_state
+                .metadata(self.buffer_id)
+                .and_then(|meta| meta.language.clone());
+            let is_markdown = language.as_deref() == Some("markdown");
+            let is_prose = prose::is_prose(language.as_deref());
 
-            // Calculate content size for scrolling
             // Fixed gutter width for up to 99,999 lines (5 digits)
             let max_digits = 5;
             let line_number_width = if self.show_line_numbers {
@@ -301,84 +6866,194 @@ fn main() {
             } else {
                 0.0
             };
+            // Lines too long to scroll through are wrapped to the viewport when enabled
+            let viewport_columns =
+                ((ui.available_width() - LEFT_PADDING - TEXT_LEFT_PADDING - line_number_width)
+                    / char_width)
+                    .max(1.0) as usize;
+
+            // Folded lines get no rows; headers of foldable regions get a marker in the gutter
+            let hidden = self.edtr_state.hidden_lines(self.buffer_id);
+            let folded_headers: Vec<usize> = self
+                .edtr_state
+                .folds()
+                .folded(self.buffer_id, table)
+                .iter()
+                .map(|region| region.start)
+                .collect();
+            let fold_regions = folds::regions(&text, self.tab_size);
+
+            // Break lines into visual rows, soft-wrapping at the configured column
+            let lines: Vec<&str> = text.lines().collect();
+            let rows: Vec<(usize, std::ops::Range<usize>)> = lines
+                .iter()
+                .enumerate()
+                .filter(|(line_num, _)| !folds::is_hidden(&hidden, *line_num))
+                .flat_map(|(line_num, line)| {
+                    let width = match self.wrap_column {
+                        Some(width) => width,
+                        None if self.long_line_wrap > 0 && line.len() > self.long_line_wrap => {
+                            viewport_columns
+                        }
+                        None => 0,
+                    };
+                    prose::wrap(line, width)
+                        .into_iter()
+                        .map(move |range| (line_num, range))
+                })
+                .collect();
+            // Rows with right-to-left text or tabs don't take one cell per column
+            let bidi: Vec<Option<bidi::Line>> = rows
+                .iter()
+                .map(|(line_num, range)| {
+                    let line = lines[*line_num];
+                    bidi::has_rtl(line).then(|| bidi::Line::new(line, range.clone(), self.tab_size))
+                })
+                .collect();
+            let cells: Vec<Option<tabs::Cells>> = rows
+                .iter()
+                .zip(&bidi)
+                .map(|((line_num, range), bidi)| {
+                    let text = &lines[*line_num][range.clone()];
+                    (bidi.is_none() && text.contains('\t'))
+                        .then(|| tabs::Cells::new(text, self.tab_size))
+                })
+                .collect();
+            let arrangement = virtual_text::arrange(
+                &rows,
+                &virtual_items
+                    .iter()
+                    .map(|(position, item)| (*position, item))
+                    .collect::<Vec<_>>(),
+            );
+            let max_row_length = rows
+                .iter()
+                .enumerate()
+                .map(|(row, (_, range))| {
+                    let width = match (&bidi[row], &cells[row]) {
+                        (Some(bidi), _) => bidi.width(),
+                        (_, Some(cells)) => cells.width(),
+                        _ => range.len(),
+                    };
+                    width + arrangement.inline_shift(row, range.end, true)
+                })
+                .chain(arrangement.blocks.iter().map(|block| {
+                    virtual_items[block.item]
+                        .1
+                        .text
+                        .lines()
+                        .map(|l| l.chars().count())
+                        .max()
+                        .unwrap_or(0)
+                }))
+                .max()
+                .unwrap_or(0);
+
+            // Calculate content size for scrolling
+            // Calculate minimum allocation based on available viewport
+            let min_width = ui.available_width();
+            let min_height = ui.available_height();
+
+            // Center a column of `wrap_column` characters (or the widest row) in the viewport
+            let text_width = max_row_length as f32 * char_width;
+            let center_offset = if self.center_text {
+                let column_width = self
+                    .wrap_column
+                    .map_or(text_width, |width| width as f32 * char_width);
+                ((min_width - column_width) / 2.0
+                    - LEFT_PADDING
+                    - TEXT_LEFT_PADDING
+                    - line_number_width)
+                    .max(0.0)
+            } else {
+                0.0
+            };
+            // Typewriter scrolling needs half a viewport of room to center the first and last rows
+            let typewriter_inset = if self.typewriter {
+                min_height / 2.0
+            } else {
+                0.0
+            };
+
             let content_width = LEFT_PADDING
                 + TEXT_LEFT_PADDING
                 + line_number_width
-                + (max_line_length as f32 * char_width)
+                + center_offset
+                + text_width
+                + 100.0;
+            let content_height = TOP_PADDING
+                + TEXT_TOP_PADDING
+                + typewriter_inset * 2.0
+                + (arrangement.visual_rows as f32 * line_height)
                 + 100.0;
-            let content_height =
-                TOP_PADDING + TEXT_TOP_PADDING + (line_count as f32 * line_height) + 100.0;
 
             // Scroll area for both axes
-            // Calculate minimum allocation based on available viewport
-            let min_width = ui.available_width();
-            let min_height = ui.available_height();
             let alloc_width = content_width.max(min_width);
             let alloc_height = content_height.max(min_height);
 
-            egui::ScrollArea::both()
+            // Each buffer keeps its own pixel scroll offset, so switching buffers restores the view.
+            // egui already turns Shift+wheel into horizontal scrolling and passes touchpad deltas
+            // through unsmoothed, so both axes scroll by exact pixel amounts.
+            let mut scroll_area = egui::ScrollArea::both();
+            if let Some(offset) = self.scroll_offset {
+                scroll_area = scroll_area.scroll_offset(offset);
+            }
+            let scroll = scroll_area
+                .id_salt(("editor_scroll", self.buffer_id, self.view))
                 .auto_shrink([false, false])
                 .stick_to_right(false)
                 .stick_to_bottom(false)
                 .show(ui, |ui| {
                     // Allocate the full content area (fixed for morphing/jank)
-                    let (rect, _response) = ui.allocate_exact_size(
+                    let (rect, content_response) = ui.allocate_exact_size(
                         egui::vec2(alloc_width, alloc_height),
-                        egui::Sense::hover(),
+                        egui::Sense::click_and_drag(),
                     );
 
-                    let theme = self.gui_ctx.style_system.get_active_theme().clone();
+                    let theme = self.theme.clone();
                     let origin = ui.min_rect().min;
+                    let layout = Layout {
+                        text_origin: egui::pos2(
+                            origin.x
+                                + LEFT_PADDING
+                                + line_number_width
+                                + center_offset
+                                + TEXT_LEFT_PADDING,
+                            origin.y + TOP_PADDING + TEXT_TOP_PADDING + typewriter_inset,
+                        ),
+                        line_height,
+                        char_width,
+                        bidi,
+                        cells,
+                        rows,
+                        arrangement,
+                        hidden,
+                    };
 
                     // Local flag for auto-scroll
-                    let mut should_scroll_to_cursor = false;
+                    let mut should_scroll_to_cursor = self.reveal_cursor || self.center_cursor;
 
                     // Handle keyboard and text input
+                    let focused = self.focused;
                     ui.input(|i| {
+                        if !focused {
+                            return;
+                        }
+                        // Text arriving together, as from a paste, is inserted as one edit
+                        let mut typed = input::Typed::default();
                         for event in &i.events {
                             match event {
-                                egui::Event::Text(text) => {
-                                    // Insert text at refreshed cursor position
-                                    if let Some(cursor) =
-                                        self.edtr_state.get_cursor_state(self.buffer_id)
-                                    {
-                                        let buffer =
-                                            self.edtr_state.buffers().get(&self.buffer_id).unwrap();
-                                        let offset = buffer.position_to_offset(cursor.position());
-
-                                        response.commands.push(editor::Command::InsertText {
-                                            buffer_id: self.buffer_id,
-                                            offset,
-                                            text: text.clone(),
-                                        });
-
-                                        response.text_changed = true;
-
-                                        // Advance cursor right by one column after insert
-                                        let mut new_pos = cursor.position();
-                                        new_pos.column += text.chars().count(); // Usually 1, but supports paste
-                                        response.commands.push(editor::Command::MoveCursor {
-                                            buffer_id: self.buffer_id,
-                                            position: new_pos,
-                                        });
-                                        response.cursor_moved = true;
-
-                                        // Reset preferred_column on text input
-                                        if let Some(cursor_mut) =
-                                            self.edtr_state.cursors.get_mut(&self.buffer_id)
-                                        {
-                                            cursor_mut.preferred_column = None;
-                                        }
-                                        // Set flag to auto-scroll after text input
-                                        should_scroll_to_cursor = true;
-                                    }
-                                }
+                                egui::Event::Text(text) => typed.push(text),
+                                egui::Event::Paste(text) => typed.push_paste(text),
                                 egui::Event::Key {
                                     key,
                                     pressed: true,
                                     modifiers,
                                     ..
                                 } => {
+                                    if self.insert_typed(typed.take(), &mut response) {
+                                        should_scroll_to_cursor = true;
+                                    }
                                     self.handle_key_event(*key, *modifiers, &mut response);
                                     // Set flag to auto-scroll only if movement or edit occurred
                                     if response.cursor_moved || response.text_changed {
@@ -388,94 +7063,341 @@ fn main() {
                                 _ => {}
                             }
                         }
+                        if self.insert_typed(typed, &mut response) {
+                            should_scroll_to_cursor = true;
+                        }
                     });
 
                     // Paint background
-                    ui.painter()
-                        .rect_filled(rect, egui::Rounding::ZERO, theme.background);
+                    let mut surface = render::EguiSurface::new(ui.painter().clone());
+                    surface.push_clip(rect);
+                    surface.fill_rect(rect, 0.0, theme.background);
+
+                    // Only the rows and cells within the viewport are painted, so very long
+                    // lines aren't shaped in full every frame
+                    let clip = surface.clip_rect();
+                    let visible_cells = ((clip.left() - layout.text_origin.x) / char_width)
+                        .floor()
+                        .max(0.0) as usize
+                        ..((clip.right() - layout.text_origin.x) / char_width)
+                            .ceil()
+                            .max(0.0) as usize;
 
                     // Paint line numbers and text
-                    let mut y = origin.y + TOP_PADDING + TEXT_TOP_PADDING;
-                    for (line_num, line) in text.lines().enumerate() {
-                        let mut x = origin.x + LEFT_PADDING;
-                        if self.show_line_numbers {
+                    let marker_x = layout.text_origin.x - TEXT_LEFT_PADDING / 2.0;
+                    let hovered = content_response.hovered();
+                    for (row_index, (line_num, range)) in layout.rows.iter().enumerate() {
+                        let line = lines[*line_num];
+                        let y = layout.row_y(row_index);
+                        if y + line_height < clip.top() || y > clip.bottom() {
+                            continue;
+                        }
+                        // Folded headers always show their marker, other foldable lines only
+                        // while the pointer is over the editor
+                        let is_folded = folded_headers.binary_search(line_num).is_ok();
+                        if range.start == 0
+                            && (is_folded
+                                || (hovered
+                                    && fold_regions
+                                        .binary_search_by_key(line_num, |region| region.start)
+                                        .is_ok()))
+                        {
+                            surface.draw_text_run(
+                                egui::pos2(marker_x, y),
+                                egui::Align2::CENTER_TOP,
+                                if is_folded { "⏵" } else { "⏷" },
+                                &font_id,
+                                theme.line_numbers,
+                            );
+                        }
+                        if is_folded
+                            && layout
+                                .rows
+                                .get(row_index + 1)
+                                .is_none_or(|(next, _)| next != line_num)
+                        {
+                            let x = layout.column_x(row_index, range.len(), false) + char_width;
+                            surface.fill_rect(
+                                egui::Rect::from_min_size(
+                                    egui::pos2(x, y),
+                                    egui::vec2(char_width * 2.0, line_height),
+                                ),
+                                3.0,
+                                VIRTUAL_TEXT_BG,
+                            );
+                            surface.draw_text_run(
+                                egui::pos2(x + char_width, y),
+                                egui::Align2::CENTER_TOP,
+                                "…",
+                                &font_id,
+                                theme.line_numbers,
+                            );
+                        }
+                        if self.show_line_numbers && range.start == 0 {
                             // Pad line numbers to 5 digits, right-aligned
                             let line_text = format!("{:>width$}", line_num + 1, width = max_digits);
                             // The right edge of the gutter:
                             let gutter_right_x =
-                                origin.x + LEFT_PADDING + line_number_width - char_width;
+                                layout.text_origin.x - TEXT_LEFT_PADDING - char_width;
                             // Paint the line number so its right edge is at gutter_right_x
                             let pos = egui::pos2(gutter_right_x, y);
-                            ui.painter().text(
+                            surface.draw_text_run(
                                 pos,
                                 egui::Align2::RIGHT_TOP,
-                                line_text,
-                                font_id.clone(),
+                                &line_text,
+                                &font_id,
                                 theme.line_numbers,
                             );
-                            x += line_number_width;
                         }
-                        x += TEXT_LEFT_PADDING;
-                        let color = if line.trim_start().starts_with("//") {
+                        let x = layout.text_origin.x;
+                        let conflict_side = conflicts
+                            .iter()
+                            .find_map(|region| region.side_of_line(*line_num));
+                        if let Some(side) = conflict_side {
+                            let background = match side {
+                                conflict::Side::Marker => CONFLICT_MARKER_BG,
+                                conflict::Side::Ours => CONFLICT_OURS_BG,
+                                conflict::Side::Base => CONFLICT_BASE_BG,
+                                conflict::Side::Theirs => CONFLICT_THEIRS_BG,
+                            };
+                            surface.fill_rect(
+                                egui::Rect::from_min_max(
+                                    egui::pos2(x, y),
+                                    egui::pos2(rect.right(), y + line_height),
+                                ),
+                                0.0,
+                                background,
+                            );
+                        }
+                        let color = if is_markdown && line.starts_with('#') {
+                            MARKDOWN_HEADING_FG
+                        } else if is_prose {
+                            theme.foreground
+                        } else if line.trim_start().starts_with("//") {
                             egui::Color32::from_rgb(128, 128, 128)
                         } else if line.contains("fn ") || line.contains("let ") {
                             egui::Color32::from_rgb(198, 120, 221)
                         } else {
                             theme.foreground
                         };
-                        let pos = egui::pos2(x, y);
-                        ui.painter().text(
+                        // Rows with right-to-left text are painted a character at a time, in
+                        // visual order
+                        if let Some(bidi) = &layout.bidi[row_index] {
+                            for (cell, column, glyph) in bidi.glyphs() {
+                                let shift = layout.arrangement.inline_shift(
+                                    row_index,
+                                    range.start + column,
+                                    true,
+                                );
+                                if cell + shift + 1 < visible_cells.start
+                                    || cell + shift >= visible_cells.end
+                                {
+                                    continue;
+                                }
+                                let pos = egui::pos2(x + (cell + shift) as f32 * char_width, y);
+                                if glyph == '\t' {
+                                    self.paint_tab(&mut surface, pos, &font_id, &theme);
+                                    continue;
+                                }
+                                surface.draw_text_run(
+                                    pos,
+                                    egui::Align2::LEFT_TOP,
+                                    glyph.encode_utf8(&mut [0; 4]),
+                                    &font_id,
+                                    color,
+                                );
+                            }
+                            continue;
+                        }
+                        // Long rows are painted only between the columns in view. Inline virtual
+                        // text only pushes columns right, so the row's full shift bounds how far
+                        // left a visible column can be.
+                        let visible = if range.len() > LONG_ROW_COLUMNS {
+                            let shift = layout.arrangement.inline_shift(row_index, range.end, true);
+                            let cells =
+                                visible_cells.start.saturating_sub(shift)..visible_cells.end + 1;
+                            let columns = match &layout.cells[row_index] {
+                                Some(row_cells) => row_cells.columns_within(cells),
+                                None => cells.start.min(range.len())..cells.end.min(range.len()),
+                            };
+                            let mut start = range.start + columns.start;
+                            let mut end = range.start + columns.end;
+                            while !line.is_char_boundary(start) {
+                                start -= 1;
+                            }
+                            while !line.is_char_boundary(end) {
+                                end += 1;
+                            }
+                            start..end
+                        } else {
+                            range.clone()
+                        };
+                        // Paint the row in pieces, split where inline virtual text is inserted
+                        let mut breaks: Vec<usize> = layout
+                            .arrangement
+                            .inlines
+                            .iter()
+                            .filter(|span| span.row == row_index)
+                            .map(|span| span.column)
+                            .filter(|column| line.is_char_boundary(*column))
+                            .collect();
+                        // Tabs are painted on their own, so the text after them starts at the
+                        // next tab stop
+                        for (column, _) in line[range.clone()].match_indices('\t') {
+                            breaks.extend([range.start + column, range.start + column + 1]);
+                        }
+                        breaks.push(range.end);
+                        breaks.sort_unstable();
+                        let mut from = visible.start;
+                        for to in breaks {
+                            let to = to.min(visible.end);
+                            if to > from {
+                                let pos = egui::pos2(
+                                    layout.column_x(row_index, from - range.start, true),
+                                    y,
+                                );
+                                if &line[from..to] == "\t" {
+                                    self.paint_tab(&mut surface, pos, &font_id, &theme);
+                                    from = to;
+                                    continue;
+                                }
+                                surface.draw_text_run(
+                                    pos,
+                                    egui::Align2::LEFT_TOP,
+                                    &line[from..to],
+                                    &font_id,
+                                    color,
+                                );
+                            }
+                            from = from.max(to);
+                        }
+                    }
+
+                    // Paint virtual text
+                    for span in &layout.arrangement.inlines {
+                        let item = &virtual_items[span.item].1;
+                        let start = layout.rows[span.row].1.start;
+                        let pos = egui::pos2(
+                            layout.column_x(span.row, span.column - start, false),
+                            layout.row_y(span.row),
+                        );
+                        let text = item.text.lines().next().unwrap_or_default();
+                        if item.style != virtual_text::Style::Ghost {
+                            let size =
+                                egui::vec2(text.chars().count() as f32 * char_width, line_height);
+                            surface.fill_rect(
+                                egui::Rect::from_min_size(pos, size),
+                                3.0,
+                                VIRTUAL_TEXT_BG,
+                            );
+                        }
+                        surface.draw_text_run(
                             pos,
                             egui::Align2::LEFT_TOP,
-                            line,
-                            font_id.clone(),
-                            color,
+                            text,
+                            &font_id,
+                            virtual_text_color(item.style),
+                        );
+                    }
+                    for block in &layout.arrangement.blocks {
+                        let item = &virtual_items[block.item].1;
+                        let top = layout.text_origin.y + block.top as f32 * line_height;
+                        surface.fill_rect(
+                            egui::Rect::from_min_max(
+                                egui::pos2(layout.text_origin.x, top),
+                                egui::pos2(rect.right(), top + block.height as f32 * line_height),
+                            ),
+                            0.0,
+                            VIRTUAL_TEXT_BG,
                         );
-                        y += line_height;
+                        for (i, text) in item.text.lines().enumerate() {
+                            surface.draw_text_run(
+                                egui::pos2(layout.text_origin.x, top + i as f32 * line_height),
+                                egui::Align2::LEFT_TOP,
+                                text,
+                                &font_id,
+                                virtual_text_color(item.style),
+                            );
+                        }
                     }
 
-                    // Render selection and cursor after text
-                    let selection = crsr_state.selection().unwrap_or_else(|| Range {
-                        start: Position { line: 0, column: 0 },
-                        end: Position { line: 0, column: 0 },
-                    });
-                    self.render_selection(
-                        ui,
-                        &text,
-                        selection,
-                        line_height,
-                        char_width,
-                        &theme,
-                        line_number_width,
-                    );
-                    self.render_cursor(
+                    // Render search matches, selection and cursor after text
+                    for occurrence in &occurrences {
+                        self.render_selection(&mut surface, *occurrence, &layout, OCCURRENCE_BG);
+                    }
+                    for bracket in &brackets {
+                        self.render_selection(&mut surface, *bracket, &layout, MATCHING_BRACKET_BG);
+                    }
+                    if let Some(selection) = crsr_state.selection() {
+                        self.render_selection(&mut surface, selection, &layout, theme.selection);
+                    }
+                    self.cursor_blink_time += ui.input(|i| i.unstable_dt);
+                    self.render_cursor(&mut surface, &crsr_state, &layout, &theme);
+
+                    // Clicks on virtual text go to whoever added it
+                    if content_response.clicked()
+                        && let Some(point) = content_response.interact_pointer_pos()
+                        && let Some(index) = layout.virtual_text_at(point)
+                    {
+                        let id = virtual_items[index].1.id;
+                        self.edtr_state.virtual_text_mut().click(id);
+                    }
+
+                    // Clicks on a fold marker fold or unfold its region
+                    if content_response.clicked()
+                        && let Some(point) = content_response.interact_pointer_pos()
+                        && (point.x - marker_x).abs() <= TEXT_LEFT_PADDING / 2.0
+                    {
+                        let line = layout.position_at(point).line;
+                        if (folded_headers.binary_search(&line).is_ok()
+                            || fold_regions
+                                .binary_search_by_key(&line, |region| region.start)
+                                .is_ok())
+                            && self
+                                .edtr_state
+                                .toggle_fold(self.buffer_id, line, self.tab_size)
+                        {
+                            ui.ctx().request_repaint();
+                        }
+                    }
+
+                    // Pointer and touch gestures
+                    let handles = self.handle_pointer(
                         ui,
+                        &content_response,
+                        &layout,
                         &crsr_state,
-                        line_height,
-                        char_width,
-                        &theme,
-                        line_number_width,
+                        &mut response,
                     );
+                    for center in handles {
+                        surface.fill_rect(
+                            egui::Rect::from_center_size(
+                                center,
+                                egui::Vec2::splat(HANDLE_RADIUS * 2.0),
+                            ),
+                            HANDLE_RADIUS,
+                            theme.cursor,
+                        );
+                    }
+                    surface.pop_clip();
                     // Always refetch the updated cursor state after executing commands
                     if let Some(cursor_state) = self.edtr_state.get_cursor_state(self.buffer_id) {
                         crsr_state = cursor_state.clone();
                     }
 
                     // Only auto-scroll if movement or edit occurred (fix phantom scrolling)
-                    if should_scroll_to_cursor {
-                        let cursor_x = crsr_state.position().column as f32 * char_width
-                            + origin.x
-                            + LEFT_PADDING
-                            + line_number_width
-                            + TEXT_LEFT_PADDING;
-                        let cursor_y = crsr_state.position().line as f32 * line_height
-                            + origin.y
-                            + TOP_PADDING
-                            + TEXT_TOP_PADDING;
+                    // Don't fight an in-progress wheel or kinetic touchpad scroll; the next edit
+                    // after it settles brings the caret back into view.
+                    let user_scrolling = ui.input(|i| i.smooth_scroll_delta != egui::Vec2::ZERO);
+                    if should_scroll_to_cursor && !user_scrolling {
                         let cursor_rect = egui::Rect::from_min_size(
-                            egui::pos2(cursor_x, cursor_y),
+                            layout.caret_pos(crsr_state.position()),
                             egui::vec2(2.0, line_height),
                         );
+                        // Typewriter scrolling keeps the caret row vertically centered
+                        if self.typewriter || self.center_cursor {
+                            ui.scroll_to_rect(cursor_rect, Some(egui::Align::Center));
+                        }
                         // Add a 2-line scroll margin so the cursor can move closer to the top/bottom before triggering scroll
                         let margin_lines = 2.0;
                         let margin_y = line_height * margin_lines;
@@ -484,205 +7406,878 @@ fn main() {
                         let expanded_cursor_rect =
                             cursor_rect.expand2(egui::vec2(margin_x, margin_y));
 
-                        if !clip_rect.contains_rect(expanded_cursor_rect) {
+                        if !self.typewriter
+                            && !self.center_cursor
+                            && !clip_rect.contains_rect(expanded_cursor_rect)
+                        {
                             ui.scroll_to_rect(expanded_cursor_rect, None);
                         }
                     }
 
                     // Handle input (mouse and keyboard) with scroll offset
                     // (removed call to handle_input_with_scroll; all input handling is now inside the scroll area closure)
+
+                    // Where the matches are down the content, as fractions of its height
+                    occurrences
+                        .iter()
+                        .map(|found| (layout.caret_pos(found.start).y - origin.y) / alloc_height)
+                        .collect::<Vec<f32>>()
                 });
 
+            self.scroll_offset = Some(scroll.state.offset);
+
+            // Mark the matches along the vertical scrollbar, one marker per point of its height
+            let track = scroll.inner_rect;
+            let bar_width = ui.spacing().scroll.bar_width;
+            let mut marked = std::collections::BTreeSet::new();
+            let mut surface = render::EguiSurface::new(ui.painter().clone());
+            surface.push_clip(track);
+            for fraction in scroll.inner {
+                let y = track.top() + fraction.clamp(0.0, 1.0) * track.height();
+                if marked.insert(y.round() as i32) {
+                    let marker = egui::Rect::from_min_max(
+                        egui::pos2(track.right() - bar_width, y - MATCH_MARKER_HEIGHT / 2.0),
+                        egui::pos2(track.right(), y + MATCH_MARKER_HEIGHT / 2.0),
+                    );
+                    surface.fill_rect(marker, 0.0, MATCH_MARKER);
+                }
+            }
+            surface.pop_clip();
+
             // Immediately execute commands so state is up-to-date
             for command in &response.commands {
                 let _ = self.edtr_state.execute_command(command.clone());
             }
-            // Always refetch the updated cursor state after executing commands
-            crsr_state = self.edtr_state.get_cursor_state(self.buffer_id)?.clone();
-
             Some(response)
         }
 
-        fn render_line_numbers(&self, ui: &mut egui::Ui, text: &str, line_height: f32, width: f32) {
-            let theme = self.gui_ctx.style_system.get_active_theme();
-            let font_id = egui::FontId::monospace(self.font_size);
+        /// Handles clicks and mouse drags, taps, long-press selection with drag handles, and touch
+        /// scrolling and zooming.
+        ///
+        /// A tap (or click) places the caret; Shift+click selects from the caret to the click, a
+        /// double click selects the word under the pointer and a triple click the whole line.
+        /// Dragging with the mouse selects from where the button went down, scrolling the view
+        /// while the pointer is past its edge. A long-press starts a selection that follows the
+        /// finger and shows a drag handle at each end. One finger drags scroll the view on touch
+        /// screens; two fingers scroll and pinch to change the font size. Selections made this way
+        /// show the floating selection toolbar until the user taps elsewhere or presses Esc.
+        ///
+        /// # Returns
+        ///
+        /// The centers of the drag handles to paint, if any are shown.
+        fn handle_pointer(
+            &mut self,
+            ui: &mut egui::Ui,
+            content: &egui::Response,
+            layout: &Layout,
+            cursor_state: &cursor::State,
+            response: &mut editor::Response,
+        ) -> Vec<egui::Pos2> {
+            let pointer_id = content.id.with("pointer_selection");
+            let mut pointer_sel: PointerSelection =
+                ui.data(|d| d.get_temp(pointer_id)).unwrap_or_default();
+            let pointer = content.interact_pointer_pos();
 
-            let mut y = TOP_PADDING;
-            for (line_num, _) in text.lines().enumerate() {
-                let line_text = format!("{:>4}", line_num + 1);
-                // No baseline adjustment; align exactly with buffer text
-                let baseline_adjust = line_height * 0.8;
-                let pos = egui::pos2(LEFT_PADDING, y + baseline_adjust);
-                ui.painter().text(
-                    pos,
-                    egui::Align2::LEFT_TOP,
-                    line_text,
-                    font_id.clone(),
-                    theme.line_numbers,
-                );
-                y += line_height;
+            if let Some(gesture) = ui.input(|i| i.multi_touch()) {
+                if content.contains_pointer() {
+                    ui.scroll_with_delta(gesture.translation_delta);
+                    self.font_size =
+                        (self.font_size * gesture.zoom_delta).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+                }
+            } else if content.long_touched() {
+                if let Some(point) = pointer {
+                    let position = layout.position_at(point);
+                    pointer_sel.anchor = Some(position);
+                    pointer_sel.touch = true;
+                    pointer_sel.toolbar = Some(self.select(position, position, response));
+                }
+            } else if content.clicked() {
+                pointer_sel = PointerSelection::default();
+                if let Some(point) = pointer {
+                    let position = layout.position_at(point);
+                    if content.triple_clicked() {
+                        let (start, end) = self.line_bounds(position.line);
+                        pointer_sel.toolbar = Some(self.select(start, end, response));
+                    } else if content.double_clicked() {
+                        let line = self.line_text(position.line);
+                        let segment = word::segment_at(&line, position.column.min(line.len()));
+                        let at = |column| Position {
+                            line: position.line,
+                            column,
+                        };
+                        pointer_sel.toolbar =
+                            Some(self.select(at(segment.start), at(segment.end), response));
+                    } else if ui.input(|i| i.modifiers.shift) {
+                        let anchor = Self::selection_anchor(cursor_state);
+                        pointer_sel.toolbar = Some(self.select(anchor, position, response));
+                    } else {
+                        response.commands.push(editor::Command::MoveCursor {
+                            buffer_id: self.buffer_id,
+                            position,
+                        });
+                        response.cursor_moved = true;
+                        self.reset_preferred_column();
+                    }
+                }
+            } else if content.dragged() {
+                let touches = ui.input(|i| i.any_touches());
+                if content.drag_started()
+                    && !touches
+                    && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+                {
+                    pointer_sel = PointerSelection {
+                        anchor: Some(if ui.input(|i| i.modifiers.shift) {
+                            Self::selection_anchor(cursor_state)
+                        } else {
+                            layout.position_at(origin)
+                        }),
+                        ..PointerSelection::default()
+                    };
+                }
+                if let (Some(anchor), Some(point)) = (pointer_sel.anchor, pointer) {
+                    let selection = self.select(anchor, layout.position_at(point), response);
+                    pointer_sel.toolbar = Some(selection);
+                    if self.selection_counts && !selection.is_empty() {
+                        self.render_selection_counts(ui, content, point, selection);
+                    }
+                    if !pointer_sel.touch {
+                        Self::scroll_towards(ui, point);
+                    }
+                } else if touches {
+                    ui.scroll_with_delta(content.drag_delta());
+                }
+            } else if content.drag_stopped() && !pointer_sel.touch {
+                pointer_sel.anchor = None;
+            }
+
+            // Drag handles at both ends of a touch selection
+            let mut handles = Vec::new();
+            if pointer_sel.anchor.is_some()
+                && pointer_sel.touch
+                && let Some(selection) = cursor_state.selection()
+            {
+                let ends = [selection.start, selection.end];
+                for (i, end) in ends.iter().enumerate() {
+                    let center = layout.caret_pos(*end)
+                        + egui::vec2(0.0, layout.line_height + HANDLE_RADIUS);
+                    let handle = ui.interact(
+                        egui::Rect::from_center_size(
+                            center,
+                            egui::Vec2::splat(HANDLE_RADIUS * 3.0),
+                        ),
+                        pointer_id.with(i),
+                        egui::Sense::drag(),
+                    );
+                    if handle.dragged()
+                        && let Some(point) = handle.interact_pointer_pos()
+                    {
+                        // The finger sits below the handle's line, so aim one row up
+                        let target = layout.position_at(
+                            point - egui::vec2(0.0, layout.line_height + HANDLE_RADIUS),
+                        );
+                        let other = ends[1 - i];
+                        pointer_sel.anchor = Some(other);
+                        pointer_sel.toolbar = Some(self.select(other, target, response));
+                    }
+                    handles.push(center);
+                }
             }
 
-            let sep_x = width - (self.font_size * 0.5) + LEFT_PADDING;
-            ui.painter().line_segment(
-                [
-                    egui::pos2(sep_x, TOP_PADDING),
-                    egui::pos2(sep_x, ui.available_height()),
-                ],
-                egui::Stroke::new(1.0, theme.line_numbers),
+            if self.focused && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                pointer_sel.toolbar = None;
+            }
+            // The toolbar only belongs to the selection it was opened for; keyboard edits and
+            // cursor movement replace or clear the selection and so dismiss it.
+            if let Some(selection) = pointer_sel.toolbar
+                && cursor_state.selection() == Some(selection)
+                && !selection.is_empty()
+                && self.selection_toolbar
+                && let Some(action) = self.render_selection_toolbar(ui, content, layout, selection)
+            {
+                pointer_sel.toolbar = self.run_toolbar_action(ui, action, selection, response);
+            }
+
+            ui.data_mut(|d| d.insert_temp(pointer_id, pointer_sel));
+            handles
+        }
+
+        /// Returns the text of a line of the buffer, without its line break.
+        fn line_text(&self, line: usize) -> String {
+            self.edtr_state
+                .get_buffer_text(self.buffer_id)
+                .and_then(|text| text.split('\n').nth(line).map(str::to_string))
+                .unwrap_or_default()
+        }
+
+        /// Returns where a line starts and where the next one starts, or where the line ends if
+        /// it is the last one.
+        fn line_bounds(&self, line: usize) -> (Position, Position) {
+            let text = self
+                .edtr_state
+                .get_buffer_text(self.buffer_id)
+                .unwrap_or_default();
+            let lines = text.split('\n').count();
+            let end = if line + 1 < lines {
+                Position {
+                    line: line + 1,
+                    column: 0,
+                }
+            } else {
+                Position {
+                    line,
+                    column: text.split('\n').nth(line).map_or(0, str::len),
+                }
+            };
+            (Position { line, column: 0 }, end)
+        }
+
+        /// Returns the end of the selection that stays put when it is extended: the selection's
+        /// start, or the caret if nothing is selected.
+        fn selection_anchor(cursor_state: &cursor::State) -> Position {
+            cursor_state
+                .selection()
+                .map_or(cursor_state.position(), |selection| selection.start)
+        }
+
+        /// Scrolls the view towards a point past its visible edge, so a drag can select text
+        /// that is scrolled out of view.
+        fn scroll_towards(ui: &mut egui::Ui, point: egui::Pos2) {
+            let clip = ui.clip_rect();
+            let past = |value: f32, min: f32, max: f32| {
+                if value < min {
+                    min - value
+                } else if value > max {
+                    max - value
+                } else {
+                    0.0
+                }
+            };
+            let delta = egui::vec2(
+                past(point.x, clip.left(), clip.right()),
+                past(point.y, clip.top(), clip.bottom()),
             );
+            if delta != egui::Vec2::ZERO {
+                ui.scroll_with_delta(delta);
+                // Keep scrolling while the pointer stays put
+                ui.ctx().request_repaint();
+            }
         }
 
-        fn render_text_content(
-            &mut self,
-            ui: &mut egui::Ui,
-            text: &str,
-            cursor_state: &cursor::State,
-            line_height: f32,
-            char_width: f32,
-            _response: &mut editor::Response,
+        /// Returns the text of a range of the buffer.
+        fn selected_text(&self, selection: Range) -> Option<String> {
+            let Range { start, end } = selection.ordered();
+            let buffer = self.edtr_state.buffers().get(&self.buffer_id)?;
+            let start = buffer.position_to_offset(start);
+            Some(buffer.get_text(start, buffer.position_to_offset(end) - start))
+        }
+
+        /// Shows how many lines, words and characters are selected in a tooltip next to the
+        /// pointer, while a selection is dragged out.
+        fn render_selection_counts(
+            &self,
+            ui: &egui::Ui,
+            content: &egui::Response,
+            pointer: egui::Pos2,
+            selection: Range,
         ) {
-            // Calculate line_number_width here for use in selection/cursor rendering
-            let line_number_width = if self.show_line_numbers {
-                let line_count = text.lines().count();
-                let digits = line_count.to_string().len();
-                (digits as f32 * char_width) + (char_width * 2.0)
+            let Some(selected) = self.selected_text(selection) else {
+                return;
+            };
+            egui::Area::new(content.id.with("selection_counts"))
+                .order(egui::Order::Tooltip)
+                .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+                .interactable(false)
+                .constrain(true)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.weak(i18n::tr_with(
+                            "selection-counts",
+                            &[
+                                ("lines", selected.lines().count().into()),
+                                ("words", led::util::count_words(&selected).into()),
+                                ("chars", selected.chars().count().into()),
+                            ],
+                        ));
+                    });
+                });
+        }
+
+        /// Shows the floating toolbar next to a pointer selection.
+        ///
+        /// # Returns
+        ///
+        /// The action the user picked this frame, if any.
+        fn render_selection_toolbar(
+            &self,
+            ui: &egui::Ui,
+            content: &egui::Response,
+            layout: &Layout,
+            selection: Range,
+        ) -> Option<ToolbarAction> {
+            let Range { start, end } = selection.ordered();
+            let selected = self.selected_text(selection)?;
+
+            // Sit above the selection, or below it when its first row is scrolled out of view
+            let toolbar_height = layout.line_height + 20.0;
+            let above = layout.caret_pos(start) - egui::vec2(0.0, toolbar_height);
+            let pos = if above.y >= ui.clip_rect().top() {
+                above
             } else {
-                0.0
+                layout.caret_pos(end) + egui::vec2(0.0, layout.line_height + 4.0)
             };
-            // Clone theme before any mutable borrow of self
-            let theme = self.gui_ctx.style_system.get_active_theme().clone();
-            let font_id = egui::FontId::monospace(self.font_size);
 
-            // Render background
-            ui.painter().rect_filled(
-                ui.available_rect_before_wrap(),
-                egui::Rounding::ZERO,
-                theme.background,
-            );
+            let mut action = None;
+            egui::Area::new(content.id.with("selection_toolbar"))
+                .order(egui::Order::Foreground)
+                .fixed_pos(pos)
+                .constrain(true)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            for (label, choice) in [
+                                ("toolbar-copy", ToolbarAction::Copy),
+                                ("toolbar-cut", ToolbarAction::Cut),
+                                ("toolbar-comment", ToolbarAction::Comment),
+                                ("toolbar-search", ToolbarAction::Search),
+                            ] {
+                                if ui.button(i18n::tr(label)).clicked() {
+                                    action = Some(choice);
+                                }
+                            }
+                            ui.separator();
+                            ui.weak(i18n::tr_with(
+                                "toolbar-counts",
+                                &[
+                                    ("chars", selected.chars().count().into()),
+                                    ("words", led::util::count_words(&selected).into()),
+                                ],
+                            ));
+                        });
+                    });
+                });
+            action
+        }
 
-            // Render selection
-            if let Some(selection) = cursor_state.selection() {
-                self.render_selection(
-                    ui,
-                    text,
-                    selection,
-                    line_height,
-                    char_width,
-                    &theme,
-                    line_number_width,
-                );
-            }
+        /// Runs a selection toolbar action.
+        ///
+        /// # Returns
+        ///
+        /// The selection the toolbar should stay open for, or `None` to dismiss it.
+        fn run_toolbar_action(
+            &mut self,
+            ui: &egui::Ui,
+            action: ToolbarAction,
+            selection: Range,
+            response: &mut editor::Response,
+        ) -> Option<Range> {
+            let Range { start, end } = selection.ordered();
+            let buffer = self.edtr_state.buffers().get(&self.buffer_id)?;
+            let start_offset = buffer.position_to_offset(start);
+            let end_offset = buffer.position_to_offset(end);
+            let selected = buffer.get_text(start_offset, end_offset - start_offset);
 
-            // Render text
-            let mut y = TOP_PADDING;
-            for (_line_num, line) in text.lines().enumerate() {
-                let pos = egui::pos2(LEFT_PADDING, y);
+            match action {
+                ToolbarAction::Copy => {
+                    ui.ctx().copy_text(selected);
+                    Some(selection)
+                }
+                ToolbarAction::Cut => {
+                    ui.ctx().copy_text(selected);
+                    response.commands.push(editor::Command::DeleteText {
+                        buffer_id: self.buffer_id,
+                        start: start_offset,
+                        length: end_offset - start_offset,
+                    });
+                    response.commands.push(editor::Command::MoveCursor {
+                        buffer_id: self.buffer_id,
+                        position: start,
+                    });
+                    response.text_changed = true;
+                    response.cursor_moved = true;
+                    self.reset_preferred_column();
+                    None
+                }
+                ToolbarAction::Comment => {
+                    let text = buffer.get_text(0, buffer.len());
+                    let prefix = self
+                        .edtr_state
+                        .metadata(self.buffer_id)
+                        .map_or(Some("//"), |meta| meta.line_comment())?;
+                    // A selection ending at the start of a line does not include that line
+                    let last_line = if end.column == 0 && end.line > start.line {
+                        end.line - 1
+                    } else {
+                        end.line
+                    };
+                    let commands =
+                        comment::toggle_lines(self.buffer_id, &text, start.line, last_line, prefix);
+                    if !commands.is_empty() {
+                        response.text_changed = true;
+                        response.commands.push(editor::Command::Batch(commands));
+                    }
+                    // Column offsets shift with the comment tokens, so drop the selection
+                    response.commands.push(editor::Command::MoveCursor {
+                        buffer_id: self.buffer_id,
+                        position: Position {
+                            line: start.line,
+                            column: 0,
+                        },
+                    });
+                    response.cursor_moved = true;
+                    self.reset_preferred_column();
+                    None
+                }
+                ToolbarAction::Search => {
+                    let text = buffer.get_text(0, buffer.len());
+                    let found = search::find_next(&text, &selected, end_offset)?;
+                    let anchor = buffer.offset_to_position(found.start);
+                    let head = buffer.offset_to_position(found.end);
+                    Some(self.select(anchor, head, response))
+                }
+            }
+        }
 
-                // Simple syntax highlighting (can be expanded)
-                let color = if line.trim_start().starts_with("//") {
-                    egui::Color32::from_rgb(128, 128, 128) // Comments
-                } else if line.contains("fn ") || line.contains("let ") {
-                    egui::Color32::from_rgb(198, 120, 221) // Keywords
-                } else {
-                    theme.foreground
-                };
+        /// Selects from `anchor` to `head`, leaving the caret at `head`.
+        ///
+        /// # Returns
+        ///
+        /// The selected range.
+        fn select(
+            &mut self,
+            anchor: Position,
+            head: Position,
+            response: &mut editor::Response,
+        ) -> Range {
+            let range = Range {
+                start: anchor,
+                end: head,
+            };
+            response.commands.push(editor::Command::MoveCursor {
+                buffer_id: self.buffer_id,
+                position: head,
+            });
+            response.commands.push(editor::Command::SetSelection {
+                buffer_id: self.buffer_id,
+                range,
+            });
+            response.cursor_moved = true;
+            self.reset_preferred_column();
+            range
+        }
 
-                ui.painter()
-                    .text(pos, egui::Align2::LEFT_TOP, line, font_id.clone(), color);
+        /// Inserts text typed or pasted in a row at the caret as a single edit, moving the caret
+        /// to its end. Returns whether anything was inserted.
+        fn insert_typed(&mut self, typed: input::Typed, response: &mut editor::Response) -> bool {
+            if typed.is_empty() {
+                return false;
+            }
+            let Some(position) = self
+                .edtr_state
+                .get_cursor_state(self.buffer_id)
+                .map(|cursor| cursor.position())
+            else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let offset = buffer.position_to_offset(position);
 
-                y += line_height;
+            // Typing one half of a pair over a selection surrounds it instead
+            let selection = self
+                .edtr_state
+                .get_cursor_state(self.buffer_id)
+                .and_then(|cursor| cursor.selection())
+                .filter(|selection| !selection.is_empty());
+            let mut chars = typed.text().chars();
+            if let (Some(selection), Some(c), None) = (selection, chars.next(), chars.next())
+                && !typed.is_burst()
+                && let Some(pair) = surround::pair_for(c)
+            {
+                let Range { start, end } = selection.ordered();
+                let range = buffer.position_to_offset(start)..buffer.position_to_offset(end);
+                let commands = surround::wrap(self.buffer_id, range, pair);
+                let moved = |p| surround::wrapped_position(p, start, pair.0);
+                response.commands.push(editor::Command::Batch(commands));
+                response.commands.push(editor::Command::MoveCursor {
+                    buffer_id: self.buffer_id,
+                    position: moved(position),
+                });
+                response.commands.push(editor::Command::SetSelection {
+                    buffer_id: self.buffer_id,
+                    range: Range {
+                        start: moved(selection.start),
+                        end: moved(selection.end),
+                    },
+                });
+                response.text_changed = true;
+                response.cursor_moved = true;
+                return true;
             }
 
-            // Render cursor
-            self.render_cursor(ui, cursor_state, line_height, char_width, &theme, 0.0);
+            response.commands.push(editor::Command::InsertText {
+                buffer_id: self.buffer_id,
+                offset,
+                text: typed.text().to_string(),
+            });
+            response.text_changed = true;
+            response.commands.push(editor::Command::MoveCursor {
+                buffer_id: self.buffer_id,
+                position: input::end_position(position, typed.text()),
+            });
+            response.cursor_moved = true;
+            self.reset_preferred_column();
+            true
+        }
 
-            // Handle text input
-            if ui.rect_contains_pointer(ui.available_rect_before_wrap()) {
-                ui.memory_mut(|mem| mem.request_focus(ui.next_auto_id()));
+        fn reset_preferred_column(&mut self) {
+            if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
+                cursor.preferred_column = None;
             }
         }
 
-        // Helper: handle input with scroll offset
-        // (now unused, all input handling is inside the scroll area closure)
+        /// Paints a tab as an arrow, if tabs are shown.
+        fn paint_tab(
+            &self,
+            surface: &mut dyn RenderSurface,
+            pos: egui::Pos2,
+            font_id: &egui::FontId,
+            theme: &Theme,
+        ) {
+            if self.tab_arrows {
+                surface.draw_text_run(
+                    pos,
+                    egui::Align2::LEFT_TOP,
+                    "→",
+                    font_id,
+                    theme.line_numbers,
+                );
+            }
+        }
 
         fn render_cursor(
-            &mut self,
-            ui: &mut egui::Ui,
+            &self,
+            surface: &mut dyn RenderSurface,
             cursor_state: &cursor::State,
-            line_height: f32,
-            char_width: f32,
+            layout: &Layout,
             theme: &Theme,
-            line_number_width: f32,
         ) {
             // Cursor blinking
-            self.cursor_blink_time += ui.input(|i| i.unstable_dt);
             let cursor_visible = (self.cursor_blink_time * 2.0) % 2.0 < 1.0;
 
             if cursor_visible {
-                let origin = ui.min_rect().min;
-                let cursor_x = cursor_state.position().column as f32 * char_width
-                    + origin.x
-                    + LEFT_PADDING
-                    + line_number_width
-                    + TEXT_LEFT_PADDING;
-                let cursor_y = cursor_state.position().line as f32 * line_height
-                    + origin.y
-                    + TOP_PADDING
-                    + TEXT_TOP_PADDING;
-
-                ui.painter().line_segment(
-                    [
-                        egui::pos2(cursor_x, cursor_y),
-                        egui::pos2(cursor_x, cursor_y + line_height),
-                    ],
-                    egui::Stroke::new(2.0, theme.cursor),
+                let top = layout.caret_pos(cursor_state.position());
+                surface.fill_rect(
+                    egui::Rect::from_center_size(
+                        top + egui::vec2(0.0, layout.line_height / 2.0),
+                        egui::vec2(2.0, layout.line_height),
+                    ),
+                    0.0,
+                    theme.cursor,
                 );
             }
         }
 
+        /// Paints the background of a range of text, such as the selection or a search match.
         fn render_selection(
             &self,
-            ui: &mut egui::Ui,
-            text: &str,
+            surface: &mut dyn RenderSurface,
             selection: Range,
-            line_height: f32,
-            char_width: f32,
-            theme: &Theme,
-            line_number_width: f32,
+            layout: &Layout,
+            color: egui::Color32,
         ) {
-            // Simple selection rendering - can be optimized
-            let start_y = selection.start.line as f32 * line_height + TOP_PADDING;
-            let end_y = selection.end.line as f32 * line_height + TOP_PADDING;
-            if selection.start.line == selection.end.line {
-                // Single line selection
-                let start_x =
-                    selection.start.column as f32 * char_width + LEFT_PADDING + line_number_width;
-                let end_x =
-                    selection.end.column as f32 * char_width + LEFT_PADDING + line_number_width;
-
-                ui.painter().rect_filled(
-                    egui::Rect::from_min_size(
-                        egui::pos2(start_x, start_y),
-                        egui::vec2(end_x - start_x, line_height),
+            let Range { start, end } = selection.ordered();
+            let (start_row, start_col) = layout.row_of(start);
+            let (end_row, end_col) = layout.row_of(end);
+
+            for row in start_row..=end_row {
+                let row_len = layout.rows.get(row).map_or(0, |(_, range)| range.len());
+                let from = if row == start_row { start_col } else { 0 };
+                // Extend rows that continue onto the next one by a column to show the line break
+                let to = if row == end_row { end_col } else { row_len + 1 };
+                let top = layout.row_y(row);
+                // Right-to-left text can split a selection into several stretches on screen
+                if let Some(Some(bidi)) = layout.bidi.get(row) {
+                    let x = |cells: usize| layout.text_origin.x + cells as f32 * layout.char_width;
+                    let mut cells = bidi.selection(from, to);
+                    if to > row_len {
+                        let end = bidi.width();
+                        cells.push(end..end + 1);
+                    }
+                    for cells in cells {
+                        surface.fill_rect(
+                            egui::Rect::from_min_max(
+                                egui::pos2(x(cells.start), top),
+                                egui::pos2(x(cells.end), top + layout.line_height),
+                            ),
+                            0.0,
+                            color,
+                        );
+                    }
+                    continue;
+                }
+                let left = layout.column_x(row, from, false);
+                let right = layout.column_x(row, to, false).max(left);
+                surface.fill_rect(
+                    egui::Rect::from_min_max(
+                        egui::pos2(left, top),
+                        egui::pos2(right, top + layout.line_height),
                     ),
-                    egui::Rounding::ZERO,
-                    theme.selection,
+                    0.0,
+                    color,
                 );
+            }
+        }
+
+        /// Moves the cursor by sentence (Alt+Left/Right) or by paragraph (Ctrl+Alt+Up/Down;
+        /// Alt+Up/Down alone moves lines).
+        ///
+        /// # Returns
+        ///
+        /// `true` if the key was a prose navigation key and the cursor was moved.
+        fn handle_prose_navigation(
+            &mut self,
+            key: egui::Key,
+            command: bool,
+            response: &mut editor::Response,
+        ) -> bool {
+            use egui::Key;
+
+            let motion: fn(&str, usize) -> usize = match (key, command) {
+                (Key::ArrowLeft, _) => prose::prev_sentence,
+                (Key::ArrowRight, _) => prose::next_sentence,
+                (Key::ArrowUp, true) => prose::prev_paragraph,
+                (Key::ArrowDown, true) => prose::next_paragraph,
+                _ => return false,
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(self.buffer_id) else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) else {
+                return false;
+            };
+            let offset = buffer.position_to_offset(cursor.position());
+            let position = buffer.offset_to_position(motion(&text, offset));
+
+            if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
+                cursor.preferred_column = None;
+            }
+            response.commands.push(editor::Command::MoveCursor {
+                buffer_id: self.buffer_id,
+                position,
+            });
+            response.cursor_moved = true;
+            true
+        }
+
+        /// Moves the cursor to the start of the previous word (Ctrl+Left) or the end of the next
+        /// one (Ctrl+Right), extending the selection when Shift is held.
+        ///
+        /// # Returns
+        ///
+        /// `true` if the key was a word navigation key and the cursor was moved.
+        fn handle_word_navigation(
+            &mut self,
+            key: egui::Key,
+            extend: bool,
+            response: &mut editor::Response,
+        ) -> bool {
+            use egui::Key;
+
+            let motion: fn(&str, usize) -> usize = match key {
+                Key::ArrowLeft => led::util::prev_word_start,
+                Key::ArrowRight => led::util::next_word_end,
+                _ => return false,
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(self.buffer_id) else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) else {
+                return false;
+            };
+            let offset = buffer.position_to_offset(cursor.position());
+            let position = buffer.offset_to_position(motion(&text, offset));
+            let anchor = extend.then(|| Self::selection_anchor(cursor));
+
+            self.reset_preferred_column();
+            match anchor {
+                Some(anchor) => {
+                    self.select(anchor, position, response);
+                }
+                None => response.commands.push(editor::Command::MoveCursor {
+                    buffer_id: self.buffer_id,
+                    position,
+                }),
+            }
+            response.cursor_moved = true;
+            true
+        }
+
+        /// Deletes from the caret to the start of the previous word (Ctrl+Backspace) or to the
+        /// end of the next one (Ctrl+Delete), as a single edit.
+        ///
+        /// # Returns
+        ///
+        /// `true` if the key was Backspace or Delete, even if there was nothing to delete.
+        fn handle_word_deletion(
+            &mut self,
+            key: egui::Key,
+            response: &mut editor::Response,
+        ) -> bool {
+            use egui::Key;
+
+            let motion: fn(&str, usize) -> usize = match key {
+                Key::Backspace => led::util::prev_word_start,
+                Key::Delete => led::util::next_word_end,
+                _ => return false,
+            };
+            let Some(text) = self.edtr_state.get_buffer_text(self.buffer_id) else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) else {
+                return false;
+            };
+            let offset = buffer.position_to_offset(cursor.position());
+            let other = motion(&text, offset);
+            let (start, end) = (offset.min(other), offset.max(other));
+            if start == end {
+                return true;
+            }
+            let position = buffer.offset_to_position(start);
+
+            response.commands.push(editor::Command::DeleteText {
+                buffer_id: self.buffer_id,
+                start,
+                length: end - start,
+            });
+            response.commands.push(editor::Command::MoveCursor {
+                buffer_id: self.buffer_id,
+                position,
+            });
+            response.text_changed = true;
+            response.cursor_moved = true;
+            self.reset_preferred_column();
+            true
+        }
+
+        /// Moves the cursor to the start (Ctrl+Home) or the end (Ctrl+End) of the buffer,
+        /// extending the selection when Shift is held.
+        ///
+        /// # Returns
+        ///
+        /// `true` if the key was Home or End and the cursor was moved.
+        fn handle_document_navigation(
+            &mut self,
+            key: egui::Key,
+            extend: bool,
+            response: &mut editor::Response,
+        ) -> bool {
+            use egui::Key;
+
+            if !matches!(key, Key::Home | Key::End) {
+                return false;
+            }
+            let Some(text) = self.edtr_state.get_buffer_text(self.buffer_id) else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) else {
+                return false;
+            };
+            let position = if key == Key::Home {
+                Position { line: 0, column: 0 }
             } else {
-                // Multi-line selection (simplified)
-                for line in selection.start.line..=selection.end.line {
-                    let y = line as f32 * line_height + TOP_PADDING;
-                    ui.painter().rect_filled(
-                        egui::Rect::from_min_size(
-                            egui::pos2(LEFT_PADDING + line_number_width, y),
-                            egui::vec2(ui.available_width(), line_height),
-                        ),
-                        egui::Rounding::ZERO,
-                        theme.selection,
-                    );
+                buffer.offset_to_position(text.len())
+            };
+            let anchor = extend.then(|| Self::selection_anchor(cursor));
+
+            self.reset_preferred_column();
+            match anchor {
+                Some(anchor) => {
+                    self.select(anchor, position, response);
                 }
+                None => response.commands.push(editor::Command::MoveCursor {
+                    buffer_id: self.buffer_id,
+                    position,
+                }),
             }
+            response.cursor_moved = true;
+            true
+        }
+
+        /// Returns how the buffer is indented: as detected when it was opened, or else as the
+        /// settings say.
+        fn indentation(&self) -> led::buffer::meta::Indentation {
+            self.edtr_state
+                .metadata(self.buffer_id)
+                .and_then(|meta| meta.indentation)
+                .unwrap_or(if self.use_tabs {
+                    led::buffer::meta::Indentation::Tabs
+                } else {
+                    led::buffer::meta::Indentation::Spaces(self.tab_size)
+                })
+        }
+
+        /// Inserts the suggestion shown at the cursor, if there is one.
+        ///
+        /// # Returns
+        ///
+        /// `true` if a suggestion was accepted.
+        fn accept_suggestion(&mut self, response: &mut editor::Response) -> bool {
+            let Some(position) = self
+                .edtr_state
+                .get_cursor_state(self.buffer_id)
+                .map(|cursor| cursor.position())
+            else {
+                return false;
+            };
+            let Some(buffer) = self.edtr_state.buffers().get(&self.buffer_id) else {
+                return false;
+            };
+            let offset = buffer.position_to_offset(position);
+            let virtual_text = self.edtr_state.virtual_text_mut();
+            if virtual_text
+                .suggestion(self.buffer_id)
+                .is_none_or(|suggestion| suggestion.offset != offset)
+            {
+                return false;
+            }
+            let Some(suggestion) = virtual_text.take_suggestion(self.buffer_id) else {
+                return false;
+            };
+
+            // Move the cursor to the end of the inserted text
+            let mut new_pos = position;
+            match suggestion.text.rsplit_once('\n') {
+                Some((before, last)) => {
+                    new_pos.line += before.matches('\n').count() + 1;
+                    new_pos.column = last.len();
+                }
+                None => new_pos.column += suggestion.text.len(),
+            }
+            response.commands.push(editor::Command::InsertText {
+                buffer_id: self.buffer_id,
+                offset,
+                text: suggestion.text,
+            });
+            response.commands.push(editor::Command::MoveCursor {
+                buffer_id: self.buffer_id,
+                position: new_pos,
+            });
+            response.text_changed = true;
+            response.cursor_moved = true;
+            true
         }
 
         fn handle_key_event(
@@ -693,6 +8288,20 @@ fn main() {
         ) {
             use egui::Key;
 
+            if modifiers.alt && self.handle_prose_navigation(key, modifiers.command, response) {
+                return;
+            }
+            if modifiers.command && self.handle_word_navigation(key, modifiers.shift, response) {
+                return;
+            }
+            if modifiers.command && self.handle_document_navigation(key, modifiers.shift, response)
+            {
+                return;
+            }
+            if modifiers.command && self.handle_word_deletion(key, response) {
+                return;
+            }
+
             match key {
                 Key::ArrowLeft => {
                     // Move cursor left
@@ -701,17 +8310,14 @@ fn main() {
                         .get_buffer_text(self.buffer_id)
                         .unwrap_or_default();
                     let lines: Vec<&str> = text.lines().collect();
-                    if let Some(cursor) = self.edtr_state.cursors.get_mut(&self.buffer_id) {
-                        let mut new_pos = cursor.position;
-                        if new_pos.column > 0 {
-                            new_pos.column -= 1;
-                        } else if new_pos.line > 0 {
-                            new_pos.line -= 1;
-                            // Move to end of previous line
-                            if new_pos.line < lines.len() {
-                                new_pos.column = lines[new_pos.line].len();
-                            }
-                        }
+                    let hidden = self.edtr_state.hidden_lines(self.buffer_id);
+                    if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
+                        let new_pos = skip_folded(
+                            &lines,
+                            &hidden,
+                            cursor.position,
+                            horizontal_move(&lines, cursor.position, false, self.tab_size),
+                        );
                         // Reset preferred column on horizontal movement
                         cursor.preferred_column = None;
 
@@ -731,18 +8337,14 @@ fn main() {
                         .get_buffer_text(self.buffer_id)
                         .unwrap_or_default();
                     let lines: Vec<&str> = text.lines().collect();
-                    if let Some(cursor) = self.edtr_state.cursors.get_mut(&self.buffer_id) {
-                        let mut new_pos = cursor.position;
-
-                        if new_pos.line < lines.len() {
-                            let current_line = lines[new_pos.line];
-                            if new_pos.column < current_line.len() {
-                                new_pos.column += 1;
-                            } else if new_pos.line + 1 < lines.len() {
-                                new_pos.line += 1;
-                                new_pos.column = 0;
-                            }
-                        }
+                    let hidden = self.edtr_state.hidden_lines(self.buffer_id);
+                    if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
+                        let new_pos = skip_folded(
+                            &lines,
+                            &hidden,
+                            cursor.position,
+                            horizontal_move(&lines, cursor.position, true, self.tab_size),
+                        );
                         // Reset preferred column on horizontal movement
                         cursor.preferred_column = None;
 
@@ -762,12 +8364,15 @@ fn main() {
                         .get_buffer_text(self.buffer_id)
                         .unwrap_or_default();
                     let lines: Vec<&str> = text.lines().collect();
-                    if let Some(cursor) = self.edtr_state.cursors.get_mut(&self.buffer_id) {
+                    let hidden = self.edtr_state.hidden_lines(self.buffer_id);
+                    if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
                         let mut new_pos = cursor.position;
 
                         // Set preferred_column only if None (first vertical move after horizontal)
                         if cursor.preferred_column.is_none() {
-                            cursor.preferred_column = Some(cursor.position.column);
+                            let line = lines.get(cursor.position.line).copied().unwrap_or("");
+                            let cells = tabs::Cells::new(line, self.tab_size);
+                            cursor.preferred_column = Some(cells.cell(cursor.position.column));
                         }
                         // println!(
                         //     "[DEBUG][ArrowUp] preferred_column={:?}, before={:?}, moving to line={}, target_line_len={}",
@@ -789,12 +8394,14 @@ fn main() {
                         // );
 
                         if new_pos.line > 0 {
-                            new_pos.line -= 1;
+                            new_pos.line = folds::skip_hidden(&hidden, new_pos.line - 1, false);
                         }
 
-                        // Always use preferred_column for vertical moves, clamped to line length
-                        let target_line_len = lines.get(new_pos.line).map(|l| l.len()).unwrap_or(0);
-                        new_pos.column = cursor.preferred_column.unwrap().min(target_line_len);
+                        // Always use preferred_column for vertical moves, the character
+                        // boundary nearest to it on screen
+                        let target = lines.get(new_pos.line).copied().unwrap_or("");
+                        new_pos.column = tabs::Cells::new(target, self.tab_size)
+                            .column_at(cursor.preferred_column.unwrap() as f32);
 
                         // println!(
                         //     "[DEBUG][ArrowUp] after move: new_pos={:?}, preferred_column={:?}",
@@ -816,12 +8423,15 @@ fn main() {
                         .get_buffer_text(self.buffer_id)
                         .unwrap_or_default();
                     let lines: Vec<&str> = text.lines().collect();
-                    if let Some(cursor) = self.edtr_state.cursors.get_mut(&self.buffer_id) {
+                    let hidden = self.edtr_state.hidden_lines(self.buffer_id);
+                    if let Some(cursor) = self.edtr_state.cursor_mut(self.buffer_id) {
                         let mut new_pos = cursor.position;
 
                         // Set preferred_column only if None (first vertical move after horizontal)
                         if cursor.preferred_column.is_none() {
-                            cursor.preferred_column = Some(cursor.position.column);
+                            let line = lines.get(cursor.position.line).copied().unwrap_or("");
+                            let cells = tabs::Cells::new(line, self.tab_size);
+                            cursor.preferred_column = Some(cells.cell(cursor.position.column));
                         }
                         // println!(
                         //     "[DEBUG][ArrowDown] preferred_column={:?}, before={:?}, moving to line={}, target_line_len={}",
@@ -842,13 +8452,17 @@ fn main() {
                         //         .unwrap_or(0)
                         // );
 
-                        if new_pos.line + 1 < lines.len() {
-                            new_pos.line += 1;
+                        // Folded lines are skipped, unless they run to the end of the text
+                        let below = folds::skip_hidden(&hidden, new_pos.line + 1, true);
+                        if below < lines.len() {
+                            new_pos.line = below;
                         }
 
-                        // Always use preferred_column for vertical moves, clamped to line length
-                        let target_line_len = lines.get(new_pos.line).map(|l| l.len()).unwrap_or(0);
-                        new_pos.column = cursor.preferred_column.unwrap().min(target_line_len);
+                        // Always use preferred_column for vertical moves, the character
+                        // boundary nearest to it on screen
+                        let target = lines.get(new_pos.line).copied().unwrap_or("");
+                        new_pos.column = tabs::Cells::new(target, self.tab_size)
+                            .column_at(cursor.preferred_column.unwrap() as f32);
 
                         // println!(
                         //     "[DEBUG][ArrowDown] after move: new_pos={:?}, preferred_column={:?}",
@@ -865,50 +8479,54 @@ fn main() {
 
                 Key::Backspace => {
                     // Delete character before cursor
-                    if let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) {
-                        if cursor.position().column > 0 || cursor.position().line > 0 {
-                            let buffer = self.edtr_state.buffers().get(&self.buffer_id).unwrap();
-                            let offset = buffer.position_to_offset(cursor.position());
-
-                            if offset > 0 {
-                                response.commands.push(editor::Command::DeleteText {
-                                    buffer_id: self.buffer_id,
-                                    start: offset - 1,
-                                    length: 1,
-                                });
+                    if let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id)
+                        && (cursor.position().column > 0 || cursor.position().line > 0)
+                    {
+                        let buffer = self.edtr_state.buffers().get(&self.buffer_id).unwrap();
+                        let offset = buffer.position_to_offset(cursor.position());
 
-                                response.text_changed = true;
-
-                                // Move cursor left after deletion
-                                let mut new_pos = cursor.position();
-                                if new_pos.column > 0 {
-                                    new_pos.column -= 1;
-                                } else if new_pos.line > 0 {
-                                    new_pos.line -= 1;
-                                    // Move to end of previous line
-                                    if let Some(text) =
-                                        self.edtr_state.get_buffer_text(self.buffer_id)
-                                    {
-                                        let lines: Vec<&str> = text.lines().collect();
-                                        if new_pos.line < lines.len() {
-                                            new_pos.column = lines[new_pos.line].len();
-                                        }
-                                    }
-                                }
-                                response.commands.push(editor::Command::MoveCursor {
-                                    buffer_id: self.buffer_id,
-                                    position: new_pos,
-                                });
-                                response.cursor_moved = true;
+                        if offset > 0 {
+                            // Columns are bytes, so delete the whole character before the caret
+                            let column = cursor.position().column;
+                            let length = buffer
+                                .get_text(offset - column, column)
+                                .chars()
+                                .next_back()
+                                .map_or(1, char::len_utf8);
+                            response.commands.push(editor::Command::DeleteText {
+                                buffer_id: self.buffer_id,
+                                start: offset - length,
+                                length,
+                            });
 
-                                // Reset preferred_column on deletion
-                                if let Some(cursor_mut) =
-                                    self.edtr_state.cursors.get_mut(&self.buffer_id)
+                            response.text_changed = true;
+
+                            // Move cursor left after deletion
+                            let mut new_pos = cursor.position();
+                            if new_pos.column > 0 {
+                                new_pos.column -= length;
+                            } else if new_pos.line > 0 {
+                                new_pos.line -= 1;
+                                // Move to end of previous line
+                                if let Some(text) = self.edtr_state.get_buffer_text(self.buffer_id)
                                 {
-                                    cursor_mut.preferred_column = None;
+                                    let lines: Vec<&str> = text.lines().collect();
+                                    if new_pos.line < lines.len() {
+                                        new_pos.column = lines[new_pos.line].len();
+                                    }
                                 }
-                                // Set flag to auto-scroll after deletion
                             }
+                            response.commands.push(editor::Command::MoveCursor {
+                                buffer_id: self.buffer_id,
+                                position: new_pos,
+                            });
+                            response.cursor_moved = true;
+
+                            // Reset preferred_column on deletion
+                            if let Some(cursor_mut) = self.edtr_state.cursor_mut(self.buffer_id) {
+                                cursor_mut.preferred_column = None;
+                            }
+                            // Set flag to auto-scroll after deletion
                         }
                     }
                 }
@@ -920,18 +8538,21 @@ fn main() {
                         let offset = buffer.position_to_offset(cursor.position());
 
                         if offset < buffer.len() {
+                            let next: Vec<u8> = buffer.bytes_from(offset).take(4).collect();
+                            let length = String::from_utf8_lossy(&next)
+                                .chars()
+                                .next()
+                                .map_or(1, char::len_utf8);
                             response.commands.push(editor::Command::DeleteText {
                                 buffer_id: self.buffer_id,
                                 start: offset,
-                                length: 1,
+                                length,
                             });
 
                             response.text_changed = true;
 
                             // Reset preferred_column on deletion
-                            if let Some(cursor_mut) =
-                                self.edtr_state.cursors.get_mut(&self.buffer_id)
-                            {
+                            if let Some(cursor_mut) = self.edtr_state.cursor_mut(self.buffer_id) {
                                 cursor_mut.preferred_column = None;
                             }
                             // Set flag to auto-scroll after deletion
@@ -939,24 +8560,57 @@ fn main() {
                     }
                 }
 
+                Key::Tab if modifiers.shift => {
+                    response.commands.push(editor::Command::DedentLines {
+                        buffer_id: self.buffer_id,
+                        tab_size: self.indentation().width(self.tab_size),
+                    });
+                    response.text_changed = true;
+                    response.cursor_moved = true;
+                }
+
+                Key::Tab if self.accept_suggestion(response) => {}
+
+                Key::Tab
+                    if self
+                        .edtr_state
+                        .get_cursor_state(self.buffer_id)
+                        .and_then(|cursor| cursor.selection())
+                        .is_some_and(|range| range.start.line != range.end.line) =>
+                {
+                    response.commands.push(editor::Command::IndentLines {
+                        buffer_id: self.buffer_id,
+                        unit: self.indentation().unit(),
+                    });
+                    response.text_changed = true;
+                    response.cursor_moved = true;
+                }
+
+                Key::Escape => {
+                    self.edtr_state
+                        .virtual_text_mut()
+                        .dismiss_suggestion(self.buffer_id);
+                }
+
                 Key::Tab => {
-                    // Insert tab_size spaces
+                    // Insert one level of indentation
+                    let unit = self.indentation().unit();
                     if let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) {
                         let buffer = self.edtr_state.buffers().get(&self.buffer_id).unwrap();
                         let offset = buffer.position_to_offset(cursor.position());
 
-                        let tab_str = " ".repeat(self.tab_size);
+                        let columns = unit.len();
                         response.commands.push(editor::Command::InsertText {
                             buffer_id: self.buffer_id,
                             offset,
-                            text: tab_str.clone(),
+                            text: unit,
                         });
 
                         response.text_changed = true;
 
-                        // Advance cursor by tab_size columns
+                        // Advance cursor past the indentation
                         let mut new_pos = cursor.position();
-                        new_pos.column += self.tab_size;
+                        new_pos.column += columns;
                         response.commands.push(editor::Command::MoveCursor {
                             buffer_id: self.buffer_id,
                             position: new_pos,
@@ -966,23 +8620,37 @@ fn main() {
                 }
 
                 Key::Enter => {
-                    // Insert newline
+                    // Insert a line break, indenting the new line like the current one
                     if let Some(cursor) = self.edtr_state.get_cursor_state(self.buffer_id) {
                         let buffer = self.edtr_state.buffers().get(&self.buffer_id).unwrap();
-                        let offset = buffer.position_to_offset(cursor.position());
+                        let position = cursor.position();
+                        let offset = buffer.position_to_offset(position);
+                        let line_start = buffer.position_to_offset(Position {
+                            line: position.line,
+                            column: 0,
+                        });
+                        let before = buffer.get_text(line_start, offset - line_start);
+                        let language = self
+                            .edtr_state
+                            .metadata(self.buffer_id)
+                            .and_then(|meta| meta.language.as_deref());
+                        let openers = indent::openers(language, &self.indent_after);
+                        let unit = self.indentation().unit();
+                        let text = indent::line_break(&before, before.len(), openers, &unit);
+                        let column = text.len() - 1;
 
                         response.commands.push(editor::Command::InsertText {
                             buffer_id: self.buffer_id,
                             offset,
-                            text: "\n".to_string(),
+                            text,
                         });
 
                         response.text_changed = true;
 
-                        // Move cursor to start of next line
-                        let mut new_pos = cursor.position();
+                        // Move cursor past the indentation of the new line
+                        let mut new_pos = position;
                         new_pos.line += 1;
-                        new_pos.column = 0;
+                        new_pos.column = column;
                         response.commands.push(editor::Command::MoveCursor {
                             buffer_id: self.buffer_id,
                             position: new_pos,
@@ -0,0 +1,543 @@
+use anyhow::{Context as _, Result as AnyResult, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::dirs;
+use super::sandbox::{self, Limits};
+use super::settings::UpdateChannel;
+use super::shell;
+
+/// The version of this build.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Directory inside the configuration directory where downloaded updates wait to be installed.
+const STAGING_DIR: &str = "updates";
+/// File in the staging directory describing the staged update.
+const STAGED_FILE: &str = "staged.json";
+/// Limits for reading the release feed.
+const FEED_LIMITS: Limits = Limits {
+    timeout: Duration::from_secs(15),
+    max_output: 1024 * 1024,
+};
+/// Limits for downloading an update.
+const DOWNLOAD_LIMITS: Limits = Limits {
+    timeout: Duration::from_secs(600),
+    max_output: 64 * 1024,
+};
+
+/// A version number such as `1.4.2` or `1.5.0-nightly.20261017`.
+///
+/// Versions compare by number, and a pre-release sorts before the release it leads up to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// The part after `-`, such as `nightly.20261017`.
+    pub pre: Option<String>,
+}
+
+impl Version {
+    /// Parses a version, with or without a leading `v`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is not `MAJOR.MINOR.PATCH`, optionally followed by
+    /// `-PRERELEASE`.
+    pub fn parse(text: &str) -> AnyResult<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let (numbers, pre) = match text.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre.to_string())),
+            None => (text, None),
+        };
+        let parts: Vec<u64> = numbers
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| anyhow!("invalid version `{}`", text))?;
+        let [major, minor, patch] = parts[..] else {
+            return Err(anyhow!("invalid version `{}`", text));
+        };
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    /// Returns the version of this build.
+    pub fn current() -> Self {
+        Self::parse(CURRENT_VERSION).expect("the package version is valid")
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                // Nightly builds are named so that later ones sort later
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+/// A release listed in the release feed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    pub version: String,
+    pub channel: UpdateChannel,
+    /// The changelog, in Markdown.
+    #[serde(default)]
+    pub notes: String,
+    /// The release's executables, by platform (see [`platform_key`]).
+    #[serde(default)]
+    pub assets: BTreeMap<String, Asset>,
+}
+
+/// A downloadable executable of a release.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Asset {
+    /// Download address, which must be `https`.
+    pub url: String,
+    /// SHA-256 digest of the executable, in hexadecimal. The download is checked against it
+    /// before it is staged and again before it is installed.
+    pub sha256: String,
+}
+
+impl Release {
+    /// Returns the executable for this platform, if there is one.
+    pub fn asset(&self) -> Option<&Asset> {
+        self.assets.get(&platform_key())
+    }
+
+    /// Returns the first lines of the changelog, with a note if there is more.
+    pub fn changelog_preview(&self, max_lines: usize) -> String {
+        let lines: Vec<&str> = self.notes.trim().lines().collect();
+        let mut preview = lines[..lines.len().min(max_lines)].join("\n");
+        if lines.len() > max_lines {
+            preview.push_str(&format!("\n… and {} more lines", lines.len() - max_lines));
+        }
+        preview
+    }
+}
+
+/// The release feed, a JSON document listing the published releases:
+///
+/// ```json
+/// {
+///     "releases": [
+///         {
+///             "version": "0.2.0",
+///             "channel": "Stable",
+///             "notes": "- Faster search",
+///             "assets": {
+///                 "linux-x86_64": {
+///                     "url": "https://example.com/led-0.2.0-linux-x86_64",
+///                     "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+///                 }
+///             }
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Feed {
+    pub releases: Vec<Release>,
+}
+
+impl Feed {
+    /// Parses a release feed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the feed is not valid JSON of the expected shape.
+    pub fn parse(json: &str) -> AnyResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the newest release on a channel that is newer than `current`.
+    ///
+    /// The nightly channel also offers stable releases. Releases with invalid versions are
+    /// ignored.
+    pub fn newest(&self, channel: UpdateChannel, current: &Version) -> Option<&Release> {
+        self.releases
+            .iter()
+            .filter(|release| {
+                channel == UpdateChannel::Nightly || release.channel == UpdateChannel::Stable
+            })
+            .filter_map(|release| Some((Version::parse(&release.version).ok()?, release)))
+            .filter(|(version, _)| version > current)
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, release)| release)
+    }
+}
+
+/// Names this platform in the feed's assets, such as `linux-x86_64`.
+pub fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Returns whether updates can be installed on this platform.
+///
+/// A macOS application is a bundle rather than a single executable, so there updates are only
+/// announced.
+pub fn can_install() -> bool {
+    cfg!(any(target_os = "linux", target_os = "windows"))
+}
+
+/// Runs `curl` with the user's environment. Only `https` is allowed, redirects included.
+fn curl(args: &[&str], limits: Limits) -> AnyResult<String> {
+    let mut command = shell::login_environment().command("curl");
+    command.args(["--fail", "--silent", "--show-error", "--location"]);
+    command.args(["--proto", "=https", "--proto-redir", "=https"]);
+    command.args(args);
+    let output = sandbox::run(command, limits)?;
+    if output.timed_out {
+        return Err(anyhow!("the download took too long"));
+    }
+    if output.status != Some(0) {
+        return Err(anyhow!("{}", output.stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Reads the release feed and returns the newest release on a channel that is newer than this
+/// build.
+///
+/// Blocks until the feed has been read, so call it on a background thread.
+///
+/// # Errors
+///
+/// Returns an error if the feed cannot be downloaded or parsed.
+pub fn check(feed_url: &str, channel: UpdateChannel) -> AnyResult<Option<Release>> {
+    require_https(feed_url)?;
+    let json = curl(&[feed_url], FEED_LIMITS)
+        .with_context(|| format!("failed to read the release feed {}", feed_url))?;
+    let feed = Feed::parse(&json).context("invalid release feed")?;
+    Ok(feed.newest(channel, &Version::current()).cloned())
+}
+
+/// Returns an error unless an address is `https`, so that updates can't be swapped on the way.
+fn require_https(url: &str) -> AnyResult<()> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    if !scheme.is_some_and(|scheme| scheme.eq_ignore_ascii_case("https")) {
+        return Err(anyhow!("{} is not an https address", url));
+    }
+    Ok(())
+}
+
+/// Returns an error unless a file's SHA-256 digest is `expected`, given in hexadecimal.
+fn verify_sha256(path: &Path, expected: &str) -> AnyResult<()> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let actual = format!("{:x}", hasher.finalize());
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(anyhow!(
+            "{} does not match its checksum: expected {}, got {}",
+            path.display(),
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+/// Describes the update waiting to be installed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Staged {
+    version: String,
+    executable: PathBuf,
+    /// SHA-256 digest the executable had in the release feed.
+    sha256: String,
+}
+
+/// Returns the directory downloaded updates wait in.
+fn staging_dir() -> AnyResult<PathBuf> {
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("no configuration directory"))?;
+    Ok(dir.join(STAGING_DIR))
+}
+
+/// Downloads a release's executable and stages it to replace this one when LED exits.
+///
+/// Blocks until the download is finished, so call it on a background thread.
+///
+/// # Errors
+///
+/// Returns an error if updates can't be installed here, the release has no executable for
+/// this platform, its address is not `https`, or the download fails or doesn't match its
+/// checksum.
+pub fn download(release: &Release) -> AnyResult<()> {
+    if !can_install() {
+        return Err(anyhow!("updates can't be installed on this platform"));
+    }
+    let asset = release.asset().ok_or_else(|| {
+        anyhow!(
+            "release {} has no build for {}",
+            release.version,
+            platform_key()
+        )
+    })?;
+    require_https(&asset.url)?;
+    let dir = staging_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let executable = dir.join(format!(
+        "led-{}{}",
+        release.version,
+        std::env::consts::EXE_SUFFIX
+    ));
+    let target = executable.to_string_lossy();
+    curl(&["--output", &target, &asset.url], DOWNLOAD_LIMITS)
+        .with_context(|| format!("failed to download {}", asset.url))?;
+    if let Err(e) = verify_sha256(&executable, &asset.sha256) {
+        let _ = std::fs::remove_file(&executable);
+        return Err(e);
+    }
+    let staged = Staged {
+        version: release.version.clone(),
+        executable,
+        sha256: asset.sha256.clone(),
+    };
+    std::fs::write(dir.join(STAGED_FILE), serde_json::to_string(&staged)?)?;
+    Ok(())
+}
+
+/// Replaces this executable with the staged update, if there is one. Called when LED exits,
+/// so the next start runs the new version.
+///
+/// # Returns
+///
+/// The installed version, or `None` if no update was staged.
+///
+/// # Errors
+///
+/// Returns an error if the executable cannot be replaced; the update then stays staged. A
+/// staged update that no longer matches its checksum is discarded instead.
+pub fn install_staged() -> AnyResult<Option<String>> {
+    let exe = std::env::current_exe()?;
+    install(&staging_dir()?, &exe)
+}
+
+/// Replaces `exe` with the update staged in `dir`.
+fn install(dir: &Path, exe: &Path) -> AnyResult<Option<String>> {
+    let marker = dir.join(STAGED_FILE);
+    let content = match std::fs::read_to_string(&marker) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // Staged by an older version without a checksum, or broken: it can't be trusted
+    let staged: Staged = match serde_json::from_str(&content) {
+        Ok(staged) => staged,
+        Err(e) => {
+            let _ = std::fs::remove_file(&marker);
+            return Err(anyhow::Error::from(e).context("discarded the staged update"));
+        }
+    };
+    // Checked again, since the staged file could have been changed while it waited
+    if let Err(e) = verify_sha256(&staged.executable, &staged.sha256) {
+        let _ = std::fs::remove_file(&staged.executable);
+        let _ = std::fs::remove_file(&marker);
+        return Err(e.context("discarded the staged update"));
+    }
+
+    // Copy next to the executable first, so that the final step is a rename on one file
+    // system. A running executable can be renamed, but not overwritten, on Windows.
+    let new = exe.with_extension("new");
+    let old = exe.with_extension("old");
+    std::fs::copy(&staged.executable, &new)
+        .with_context(|| format!("failed to copy {}", staged.executable.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    let _ = std::fs::remove_file(&old);
+    std::fs::rename(exe, &old)?;
+    if let Err(e) = std::fs::rename(&new, exe) {
+        // Put the old executable back rather than leave none
+        let _ = std::fs::rename(&old, exe);
+        return Err(e.into());
+    }
+    let _ = std::fs::remove_file(&staged.executable);
+    std::fs::remove_file(&marker)?;
+    Ok(Some(staged.version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(version: &str, channel: UpdateChannel) -> Release {
+        Release {
+            version: version.to_string(),
+            channel,
+            notes: String::new(),
+            assets: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn versions_order_prereleases_before_releases() {
+        let parse = |v| Version::parse(v).unwrap();
+        assert!(parse("v1.2.3") < parse("1.10.0"));
+        assert!(parse("1.3.0-nightly.20261017") < parse("1.3.0"));
+        assert!(parse("1.3.0-nightly.20261017") > parse("1.3.0-nightly.20261016"));
+        assert!(parse("1.3.0-nightly.1") > parse("1.2.9"));
+        assert_eq!(parse("1.3.0-rc.1").to_string(), "1.3.0-rc.1");
+        assert!(Version::parse("1.3").is_err());
+        assert!(Version::parse("one.two.three").is_err());
+        Version::current();
+    }
+
+    #[test]
+    fn newest_release_depends_on_the_channel() {
+        let feed = Feed {
+            releases: vec![
+                release("0.2.0", UpdateChannel::Stable),
+                release("0.3.0-nightly.20261017", UpdateChannel::Nightly),
+                release("not a version", UpdateChannel::Stable),
+                release("0.0.1", UpdateChannel::Stable),
+            ],
+        };
+        let current = Version::parse("0.1.0").unwrap();
+        let newest = |channel| feed.newest(channel, &current).map(|r| r.version.as_str());
+        assert_eq!(newest(UpdateChannel::Stable), Some("0.2.0"));
+        assert_eq!(
+            newest(UpdateChannel::Nightly),
+            Some("0.3.0-nightly.20261017")
+        );
+
+        let current = Version::parse("0.3.0").unwrap();
+        assert_eq!(feed.newest(UpdateChannel::Nightly, &current), None);
+
+        let parsed = Feed::parse(
+            r#"{"releases": [{"version": "0.2.0", "channel": "Stable", "notes": "a\nb\nc"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.releases[0].changelog_preview(2),
+            "a\nb\n… and 1 more lines"
+        );
+        assert_eq!(parsed.releases[0].asset(), None);
+
+        // Every executable comes with its checksum
+        let asset = |fields: &str| {
+            Feed::parse(&format!(
+                r#"{{"releases": [{{"version": "0.2.0", "channel": "Stable", "assets": {{"{}": {{{}}}}}}}]}}"#,
+                platform_key(),
+                fields
+            ))
+        };
+        let parsed = asset(r#""url": "https://example.com/led", "sha256": "ab12""#).unwrap();
+        assert_eq!(parsed.releases[0].asset().unwrap().sha256, "ab12");
+        assert!(asset(r#""url": "https://example.com/led""#).is_err());
+    }
+
+    #[test]
+    fn staged_update_replaces_the_executable() {
+        let dir = std::env::temp_dir().join(format!("led-update-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("led");
+        std::fs::write(&exe, "old build").unwrap();
+        assert_eq!(install(&dir, &exe).unwrap(), None);
+
+        let staged = Staged {
+            version: "0.2.0".to_string(),
+            executable: dir.join("led-0.2.0"),
+            sha256: sha256_hex("new build"),
+        };
+        std::fs::write(&staged.executable, "new build").unwrap();
+        std::fs::write(
+            dir.join(STAGED_FILE),
+            serde_json::to_string(&staged).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(install(&dir, &exe).unwrap().as_deref(), Some("0.2.0"));
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "new build");
+        assert_eq!(
+            std::fs::read_to_string(exe.with_extension("old")).unwrap(),
+            "old build"
+        );
+        assert!(!dir.join(STAGED_FILE).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_updates_are_discarded_rather_than_installed() {
+        let dir = std::env::temp_dir().join(format!("led-update-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("led");
+        std::fs::write(&exe, "old build").unwrap();
+
+        let staged = Staged {
+            version: "0.2.0".to_string(),
+            executable: dir.join("led-0.2.0"),
+            sha256: sha256_hex("new build"),
+        };
+        std::fs::write(&staged.executable, "swapped build").unwrap();
+        std::fs::write(
+            dir.join(STAGED_FILE),
+            serde_json::to_string(&staged).unwrap(),
+        )
+        .unwrap();
+
+        assert!(install(&dir, &exe).is_err());
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old build");
+        assert!(!staged.executable.exists());
+        assert!(!dir.join(STAGED_FILE).exists());
+
+        // An update staged without a checksum is not trusted either
+        std::fs::write(&staged.executable, "new build").unwrap();
+        std::fs::write(
+            dir.join(STAGED_FILE),
+            r#"{"version": "0.2.0", "executable": "led-0.2.0"}"#,
+        )
+        .unwrap();
+        assert!(install(&dir, &exe).is_err());
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old build");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_https_addresses_are_used() {
+        assert!(require_https("https://example.com/feed.json").is_ok());
+        assert!(require_https("HTTPS://example.com/feed.json").is_ok());
+        assert!(require_https("http://example.com/feed.json").is_err());
+        assert!(require_https("file:///tmp/led").is_err());
+        assert!(require_https("example.com/led").is_err());
+        assert!(check("http://example.com/feed.json", UpdateChannel::Stable).is_err());
+    }
+
+    fn sha256_hex(content: &str) -> String {
+        format!("{:x}", Sha256::digest(content))
+    }
+}
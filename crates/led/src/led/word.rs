@@ -0,0 +1,102 @@
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Returns whether a piece of text counts as a word: it has a letter, digit or underscore.
+pub fn is_word(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Returns the byte ranges of a line's segments: words, runs of whitespace and punctuation
+/// characters.
+///
+/// Segments follow Unicode word boundaries, except that ASCII punctuation other than `_`
+/// always splits words, so `self.name` and `don't` are not one word as they would be in prose.
+pub fn segments(line: &str) -> Vec<Range<usize>> {
+    let mut segments = Vec::new();
+    for (start, segment) in line.split_word_bound_indices() {
+        let mut run = start;
+        for (i, c) in segment.char_indices() {
+            if c.is_ascii_punctuation() && c != '_' {
+                let at = start + i;
+                if run < at {
+                    segments.push(run..at);
+                }
+                segments.push(at..at + 1);
+                run = at + 1;
+            }
+        }
+        if run < start + segment.len() {
+            segments.push(run..start + segment.len());
+        }
+    }
+    segments
+}
+
+/// Returns the byte range of the segment of a line at a column: a word, a run of whitespace
+/// or a punctuation character (see [`segments`]).
+///
+/// A column between a word and something else belongs to the word, so a column just after
+/// the end of a word still finds it.
+///
+/// # Arguments
+///
+/// * `line` - The line, without its line break.
+/// * `column` - The byte column.
+pub fn segment_at(line: &str, column: usize) -> Range<usize> {
+    let segments = segments(line);
+    let after = segments.iter().find(|segment| segment.contains(&column));
+    let before = segments.iter().find(|segment| segment.end == column);
+    match (after, before) {
+        (Some(after), Some(before))
+            if !is_word(&line[after.clone()]) && is_word(&line[before.clone()]) =>
+        {
+            before.clone()
+        }
+        (Some(segment), _) | (None, Some(segment)) => segment.clone(),
+        (None, None) => column..column,
+    }
+}
+
+/// Returns the byte range of the word at a column of a line, if there is one.
+///
+/// # Arguments
+///
+/// * `line` - The line, without its line break.
+/// * `column` - The byte column.
+pub fn word_at(line: &str, column: usize) -> Option<Range<usize>> {
+    let segment = segment_at(line, column);
+    is_word(&line[segment.clone()]).then_some(segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_segment_at_a_column() {
+        let line = "let größe = foo_bar.baz();";
+        let at = |column| &line[segment_at(line, column)];
+        assert_eq!(at(0), "let");
+        assert_eq!(at(5), "größe");
+        // Just after a word, the word wins over what follows it
+        assert_eq!(at(3), "let");
+        assert_eq!(at(11), "größe");
+        assert_eq!(at(13), " ");
+        assert_eq!(at(15), "foo_bar");
+        assert_eq!(at(21), "foo_bar");
+        assert_eq!(at(23), "baz");
+        assert_eq!(at(line.len()), ";");
+        assert_eq!(segment_at("", 0), 0..0);
+        assert_eq!(&"don't"[segment_at("don't", 0)], "don");
+    }
+
+    #[test]
+    fn finds_only_words() {
+        let line = "a + b";
+        assert_eq!(word_at(line, 0), Some(0..1));
+        assert_eq!(word_at(line, 2), None);
+        assert_eq!(word_at(line, 5), Some(4..5));
+        assert!(is_word("x1"));
+        assert!(!is_word("->"));
+    }
+}
@@ -1,12 +1,68 @@
 mod led;
 
+pub use led::batch;
+pub use led::bidi;
+pub use led::brackets;
 pub use led::buffer;
+pub use led::cache;
+pub use led::case;
+pub use led::check;
 pub use led::commands;
+pub use led::comment;
+pub use led::conflict;
 pub use led::cursor;
+pub use led::debounce;
+pub use led::delta;
+pub use led::diff;
+pub use led::dirs;
+pub use led::editor_view;
+pub use led::edits;
+pub use led::file_format;
+pub use led::file_picker;
+pub use led::folds;
+pub use led::goto;
+pub use led::hash;
+pub use led::i18n;
+pub use led::idle;
+pub use led::ignore_rules;
+pub use led::import;
+pub use led::indent;
+pub use led::index;
+pub use led::input;
+pub use led::keymap;
+pub use led::latency;
+pub use led::launch;
+pub use led::layout;
 pub use led::piece_table;
 
 pub use led::lua;
+pub use led::marks;
+pub use led::notes;
+pub use led::number;
+pub use led::palette;
+pub use led::project;
+pub use led::prose;
+pub use led::render;
+pub use led::replace;
+pub use led::sandbox;
+pub use led::save;
+pub use led::scheduler;
+pub use led::search;
+pub use led::session;
+pub use led::settings;
+pub use led::shell;
+pub use led::snapshot;
+pub use led::snippet;
+pub use led::surround;
+pub use led::switcher;
+pub use led::tab_bar;
+pub use led::tabs;
+pub use led::testing;
 pub use led::txt;
 pub use led::types;
+pub use led::update;
 pub use led::util;
+pub use led::view;
+pub use led::virtual_text;
+pub use led::word;
 pub use piece_table::piece;
@@ -1,15 +1,41 @@
 use eframe::egui;
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(dir) = led::dirs::init(args.iter().cloned()) {
+        println!("Portable mode: keeping configuration in {}", dir.display());
+    }
+    // Batch runs edit files without opening a window
+    match led::batch::parse(args) {
+        Some(Ok(invocation)) => std::process::exit(led::batch::run(&invocation)),
+        Some(Err(e)) => {
+            eprintln!("{:#}", e);
+            eprintln!("Usage: led {} script.lua file…", led::batch::BATCH_FLAG);
+            std::process::exit(led::batch::EXIT_USAGE);
+        }
+        None => {}
+    }
+    // The time zone can only be looked up reliably before other threads start
+    led::snippet::local_offset();
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([1200.0, 800.0])
+        .with_min_inner_size([800.0, 600.0]);
+    // Reopen the window where the last session in this directory left it
+    let window = std::env::current_dir()
+        .ok()
+        .and_then(|root| led::session::Session::load(&root).ok())
+        .and_then(|session| session.window);
+    if let Some(window) = window {
+        viewport = window.viewport(viewport);
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1200.0, 800.0])
-            .with_min_inner_size([800.0, 600.0]),
+        viewport,
         ..Default::default()
     };
 
     eframe::run_native(
-        "LED Editor",
+        led::txt::edtr::APP_NAME,
         options,
         Box::new(|cc| Ok(Box::new(led::txt::edtr::App::new(cc)))),
     )
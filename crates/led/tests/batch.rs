@@ -0,0 +1,82 @@
+#![cfg(feature = "lua")]
+
+use led::batch::{BATCH_FLAG, EXIT_FAILURE, EXIT_USAGE};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Returns a new empty directory to run a batch in.
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("led-batch-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs `led --batch` in a directory, with the configuration kept in it too.
+fn led_batch(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_led"))
+        .arg(BATCH_FLAG)
+        .args(args)
+        .current_dir(dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn scripts_edit_and_save_each_file() {
+    let dir = temp_dir();
+    fs::write(
+        dir.join("upper.lua"),
+        r#"
+local buffer = kup.buffer
+print("editing " .. buffer.path)
+buffer.set_line(1, buffer.line(1):upper())
+"#,
+    )
+    .unwrap();
+    fs::write(dir.join("a.txt"), "first\nsecond\n").unwrap();
+    fs::write(dir.join("b.txt"), "ALREADY\n").unwrap();
+
+    let output = led_batch(&dir, &["upper.lua", "a.txt", "b.txt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("editing a.txt\n"), "{}", stdout);
+    assert!(stdout.contains("editing b.txt\n"), "{}", stdout);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert_eq!(
+        fs::read_to_string(dir.join("a.txt")).unwrap(),
+        "FIRST\nsecond\n"
+    );
+    assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "ALREADY\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failing_files_are_left_untouched_and_reported() {
+    let dir = temp_dir();
+    fs::write(dir.join("third.lua"), "kup.buffer.set_line(3, 'third')\n").unwrap();
+    fs::write(dir.join("short.txt"), "one\n").unwrap();
+    fs::write(dir.join("long.txt"), "one\ntwo\nthree\n").unwrap();
+
+    let output = led_batch(&dir, &["third.lua", "short.txt", "long.txt"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("short.txt: ") && stderr.contains("the buffer has no line 3"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("long.txt"), "{}", stderr);
+    assert_eq!(output.status.code(), Some(EXIT_FAILURE));
+    assert_eq!(fs::read_to_string(dir.join("short.txt")).unwrap(), "one\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("long.txt")).unwrap(),
+        "one\ntwo\nthird\n"
+    );
+
+    let output = led_batch(&dir, &["missing.lua", "long.txt"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to read missing.lua"));
+    let output = led_batch(&dir, &["third.lua"]);
+    assert_eq!(output.status.code(), Some(EXIT_USAGE));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,76 @@
+use egui::{Key, Modifiers};
+use led::testing::{Harness, RecordedEvent, Recording, assert_golden};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Returns the path of a file in `tests/fixtures`.
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn caret_keeps_its_column_across_short_lines() {
+    let mut harness = Harness::new("a long first line\nab\nanother long line\n");
+    harness.frame(Vec::new());
+    for _ in 0..10 {
+        harness.press(Key::ArrowRight, Modifiers::NONE);
+    }
+    harness.press(Key::ArrowDown, Modifiers::NONE);
+    harness.press(Key::ArrowDown, Modifiers::NONE);
+    assert_golden(fixture("preferred_column.golden"), &harness.end_state());
+}
+
+#[test]
+fn tabs_count_to_the_next_tab_stop_and_follow_the_file() {
+    let mut harness = Harness::new("\tx\nabcdefg\n");
+    harness.frame(Vec::new());
+    // After the tab the caret is four cells in, so moving down lands after the fourth letter
+    harness.press(Key::ArrowRight, Modifiers::NONE);
+    harness.press(Key::ArrowDown, Modifiers::NONE);
+    // The file is indented with tabs, so Tab inserts one
+    harness.press(Key::Tab, Modifiers::NONE);
+    assert_golden(fixture("tab_stops.golden"), &harness.end_state());
+}
+
+#[test]
+fn recorded_selection_replays_to_the_same_end_state() {
+    let mut harness = Harness::new("one two three\n");
+    let recording = Recording::load(&fixture("select_word.json")).unwrap();
+    harness.replay(&recording);
+    assert_golden(fixture("select_word.golden"), &harness.end_state());
+}
+
+#[test]
+fn replay_follows_the_recorded_timing() {
+    let mut harness = Harness::new("");
+    let recording = Recording {
+        events: vec![
+            RecordedEvent {
+                at_ms: 100,
+                event: egui::Event::Text("a".to_string()),
+            },
+            RecordedEvent {
+                at_ms: 1_500,
+                event: egui::Event::Text("b".to_string()),
+            },
+        ],
+    };
+    harness.replay(&recording);
+    assert_eq!(harness.view.text(), "ab");
+    assert_eq!(harness.clock.elapsed(), Duration::from_millis(1_500));
+}
+
+#[test]
+fn folded_lines_are_skipped_by_the_caret() {
+    let mut harness = Harness::new("fn a() {\n    b();\n    c();\n}\nd\n");
+    harness.frame(Vec::new());
+    // Ctrl+[ as egui reports it on Linux and Windows
+    harness.press(Key::OpenBracket, Modifiers::CTRL | Modifiers::COMMAND);
+    harness.press(Key::ArrowDown, Modifiers::NONE);
+    harness.press(Key::ArrowDown, Modifiers::NONE);
+    harness.press(Key::ArrowUp, Modifiers::NONE);
+    assert_golden(fixture("folded_lines.golden"), &harness.end_state());
+}
@@ -0,0 +1,33 @@
+use led::buffer::editor::State;
+use led::latency::{self, KEYSTROKE_BUDGET};
+
+/// A Rust-like file of about 5000 lines.
+fn large_file() -> String {
+    (0..5000)
+        .map(|i| format!("    let value_{} = compute({}, \"text\");\n", i, i))
+        .collect()
+}
+
+#[test]
+fn typing_stays_within_the_keystroke_budget() {
+    let mut state = State::new();
+    let buffer_id = state.create_buffer(large_file());
+    let script = "fn main() {\n    println!(\"hello\");\u{8}\u{8}\u{8});\n}\n".repeat(4);
+
+    let tracker = latency::replay(&mut state, buffer_id, &script);
+    let summary = tracker.summary().expect("keystrokes were measured");
+    assert_eq!(summary.count, script.chars().count());
+    assert!(
+        summary.within(KEYSTROKE_BUDGET),
+        "keystroke latency over budget: {}",
+        summary
+    );
+}
+
+#[test]
+fn replay_types_the_script() {
+    let mut state = State::new();
+    let buffer_id = state.create_buffer(String::new());
+    latency::replay(&mut state, buffer_id, "\u{8}ab\u{8}c\nd");
+    assert_eq!(state.get_buffer_text(buffer_id).unwrap(), "ac\nd");
+}
@@ -77,6 +77,7 @@ impl<'a> DrawContext for EguiDrawContext<'a> {
 }
 
 #[cfg(test)]
+#[allow(clippy::unit_cmp)]
 mod tests {
     use super::*;
     use egui::Context as EguiContext;
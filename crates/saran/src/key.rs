@@ -38,6 +38,29 @@ impl Modifiers {
     }
 }
 
+impl Modifiers {
+    /// The platform's primary shortcut modifier: Command (Super) on macOS, Control elsewhere.
+    pub const COMMAND: Modifiers = Modifiers::primary(cfg!(target_os = "macos"));
+
+    /// Returns the primary shortcut modifier of a platform, for bindings made for another one.
+    ///
+    /// # Arguments
+    ///
+    /// * `macos` - Whether the platform is macOS, which uses Command (Super) rather than Control.
+    pub const fn primary(macos: bool) -> Modifiers {
+        if macos {
+            Modifiers::SUPER
+        } else {
+            Modifiers::CTRL
+        }
+    }
+
+    /// Returns `true` if the platform's primary shortcut modifier is pressed.
+    pub fn command(self) -> bool {
+        self.contains(Modifiers::COMMAND)
+    }
+}
+
 impl Default for Modifiers {
     /// Returns an empty set of modifiers (no modifier keys pressed).
     fn default() -> Self {
@@ -45,6 +68,21 @@ impl Default for Modifiers {
     }
 }
 
+impl From<egui::Modifiers> for Modifiers {
+    /// Converts egui modifiers, mapping the macOS Command key to `SUPER`.
+    ///
+    /// egui's platform-dependent `command` flag is not used directly; it is always
+    /// represented by either `ctrl` or `mac_cmd`.
+    fn from(modifiers: egui::Modifiers) -> Self {
+        let mut result = Modifiers::empty();
+        result.set(Modifiers::SHIFT, modifiers.shift);
+        result.set(Modifiers::CTRL, modifiers.ctrl);
+        result.set(Modifiers::ALT, modifiers.alt);
+        result.set(Modifiers::SUPER, modifiers.mac_cmd);
+        result
+    }
+}
+
 /// Represents keyboard keys that can be used in keyboard events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Code {
@@ -202,6 +240,101 @@ pub enum Code {
     Other(u32),
 }
 
+impl From<egui::Key> for Code {
+    /// Converts an egui logical key.
+    ///
+    /// Punctuation keys without a dedicated variant map to `Other` with the key's character as the code.
+    fn from(key: egui::Key) -> Self {
+        use egui::Key;
+
+        match key {
+            Key::Enter => Code::Enter,
+            Key::Escape => Code::Escape,
+            Key::Tab => Code::Tab,
+            Key::Backspace => Code::Backspace,
+            Key::ArrowUp => Code::ArrowUp,
+            Key::ArrowDown => Code::ArrowDown,
+            Key::ArrowLeft => Code::ArrowLeft,
+            Key::ArrowRight => Code::ArrowRight,
+            Key::Home => Code::Home,
+            Key::End => Code::End,
+            Key::PageUp => Code::PageUp,
+            Key::PageDown => Code::PageDown,
+            Key::Delete => Code::Delete,
+            Key::Insert => Code::Insert,
+            Key::Space => Code::Space,
+            Key::A => Code::A,
+            Key::B => Code::B,
+            Key::C => Code::C,
+            Key::D => Code::D,
+            Key::E => Code::E,
+            Key::F => Code::F,
+            Key::G => Code::G,
+            Key::H => Code::H,
+            Key::I => Code::I,
+            Key::J => Code::J,
+            Key::K => Code::K,
+            Key::L => Code::L,
+            Key::M => Code::M,
+            Key::N => Code::N,
+            Key::O => Code::O,
+            Key::P => Code::P,
+            Key::Q => Code::Q,
+            Key::R => Code::R,
+            Key::S => Code::S,
+            Key::T => Code::T,
+            Key::U => Code::U,
+            Key::V => Code::V,
+            Key::W => Code::W,
+            Key::X => Code::X,
+            Key::Y => Code::Y,
+            Key::Z => Code::Z,
+            Key::Num0 => Code::Zero,
+            Key::Num1 => Code::One,
+            Key::Num2 => Code::Two,
+            Key::Num3 => Code::Three,
+            Key::Num4 => Code::Four,
+            Key::Num5 => Code::Five,
+            Key::Num6 => Code::Six,
+            Key::Num7 => Code::Seven,
+            Key::Num8 => Code::Eight,
+            Key::Num9 => Code::Nine,
+            Key::F1 => Code::F1,
+            Key::F2 => Code::F2,
+            Key::F3 => Code::F3,
+            Key::F4 => Code::F4,
+            Key::F5 => Code::F5,
+            Key::F6 => Code::F6,
+            Key::F7 => Code::F7,
+            Key::F8 => Code::F8,
+            Key::F9 => Code::F9,
+            Key::F10 => Code::F10,
+            Key::F11 => Code::F11,
+            Key::F12 => Code::F12,
+            Key::Colon => Code::Other(':' as u32),
+            Key::Comma => Code::Other(',' as u32),
+            Key::Backslash => Code::Other('\\' as u32),
+            Key::Slash => Code::Other('/' as u32),
+            Key::Pipe => Code::Other('|' as u32),
+            Key::Questionmark => Code::Other('?' as u32),
+            Key::Exclamationmark => Code::Other('!' as u32),
+            Key::OpenBracket => Code::Other('[' as u32),
+            Key::CloseBracket => Code::Other(']' as u32),
+            Key::OpenCurlyBracket => Code::Other('{' as u32),
+            Key::CloseCurlyBracket => Code::Other('}' as u32),
+            Key::Backtick => Code::Other('`' as u32),
+            Key::Minus => Code::Other('-' as u32),
+            Key::Period => Code::Other('.' as u32),
+            Key::Plus => Code::Other('+' as u32),
+            Key::Equals => Code::Other('=' as u32),
+            Key::Semicolon => Code::Other(';' as u32),
+            Key::Quote => Code::Other('\'' as u32),
+            // Keys without a character (F13+, media keys) count down from u32::MAX to avoid colliding with characters
+            other => Code::Other(u32::MAX - other as u32),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +386,42 @@ mod tests {
         assert_eq!(mods, Modifiers::empty());
     }
 
+    #[test]
+    fn modifiers_command_is_platform_primary() {
+        if cfg!(target_os = "macos") {
+            assert_eq!(Modifiers::COMMAND, Modifiers::SUPER);
+        } else {
+            assert_eq!(Modifiers::COMMAND, Modifiers::CTRL);
+        }
+        assert!(Modifiers::COMMAND.command());
+        assert!(!Modifiers::SHIFT.command());
+        assert_eq!(Modifiers::primary(true), Modifiers::SUPER);
+        assert_eq!(Modifiers::primary(false), Modifiers::CTRL);
+    }
+
+    #[test]
+    fn modifiers_from_egui() {
+        let mods = Modifiers::from(egui::Modifiers {
+            alt: true,
+            ctrl: false,
+            shift: true,
+            mac_cmd: true,
+            command: true,
+        });
+        assert_eq!(mods, Modifiers::ALT | Modifiers::SHIFT | Modifiers::SUPER);
+        assert_eq!(Modifiers::from(egui::Modifiers::CTRL), Modifiers::CTRL);
+        assert_eq!(Modifiers::from(egui::Modifiers::NONE), Modifiers::empty());
+    }
+
+    #[test]
+    fn code_from_egui_key() {
+        assert_eq!(Code::from(egui::Key::S), Code::S);
+        assert_eq!(Code::from(egui::Key::Num7), Code::Seven);
+        assert_eq!(Code::from(egui::Key::ArrowLeft), Code::ArrowLeft);
+        assert_eq!(Code::from(egui::Key::Slash), Code::Other('/' as u32));
+        assert_ne!(Code::from(egui::Key::F13), Code::from(egui::Key::F14));
+    }
+
     #[test]
     fn code_enum_variants_are_distinct() {
         assert_ne!(Code::Enter, Code::Escape);
@@ -12,7 +12,7 @@ pub type ScreenSize = Size<f32>;
 ///   to avoid redundant layout computations for the same text.
 /// - `glyph_cache`: Caches `egui::epaint::text::FontId` objects keyed by character,
 ///   to speed up font resolution for glyphs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Cache {
     /// Stores cached text layouts for strings.
     text_layouts: HashMap<String, egui::text::LayoutJob>,
@@ -31,6 +31,32 @@ impl Cache {
             glyph_cache: HashMap::new(),
         }
     }
+
+    /// Returns the cached layout for `text`, if there is one.
+    pub fn get_text_layout(&self, text: &str) -> Option<&egui::text::LayoutJob> {
+        self.text_layouts.get(text)
+    }
+
+    /// Caches the layout computed for `text`, replacing any earlier one.
+    pub fn insert_text_layout(&mut self, text: String, layout: egui::text::LayoutJob) {
+        self.text_layouts.insert(text, layout);
+    }
+
+    /// Returns the cached font ID for `glyph`, if there is one.
+    pub fn get_glyph_font(&self, glyph: char) -> Option<&egui::epaint::text::FontId> {
+        self.glyph_cache.get(&glyph)
+    }
+
+    /// Caches the font ID resolved for `glyph`, replacing any earlier one.
+    pub fn insert_glyph_font(&mut self, glyph: char, font: egui::epaint::text::FontId) {
+        self.glyph_cache.insert(glyph, font);
+    }
+
+    /// Empties both caches, e.g. after the fonts or theme changed.
+    pub fn clear(&mut self) {
+        self.text_layouts.clear();
+        self.glyph_cache.clear();
+    }
 }
 
 /// Represents the layout direction for UI elements.
@@ -81,6 +107,7 @@ impl<'a> Context<'a> {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::*;
     use crate::size::Size;
@@ -92,6 +119,28 @@ mod tests {
         assert!(cache.glyph_cache.is_empty());
     }
 
+    #[test]
+    fn cache_stores_layouts_and_glyph_fonts_until_cleared() {
+        let mut cache = Cache::new();
+        let font = egui::FontId::monospace(14.0);
+        cache.insert_text_layout(
+            "hello".to_string(),
+            egui::text::LayoutJob::simple_singleline(
+                "hello".to_string(),
+                font.clone(),
+                egui::Color32::WHITE,
+            ),
+        );
+        cache.insert_glyph_font('h', font.clone());
+        assert_eq!(cache.get_text_layout("hello").unwrap().text, "hello");
+        assert!(cache.get_text_layout("world").is_none());
+        assert_eq!(cache.get_glyph_font('h'), Some(&font));
+
+        cache.clear();
+        assert!(cache.get_text_layout("hello").is_none());
+        assert!(cache.get_glyph_font('h').is_none());
+    }
+
     #[test]
     fn context_initializes_with_given_parameters() {
         let available_space = Size::new(100.0, 50.0);
@@ -349,6 +349,7 @@ impl<T> From<(T, T)> for Point<T> {
 }
 
 #[cfg(test)]
+#[allow(clippy::clone_on_copy, clippy::erasing_op)]
 mod tests {
     use super::*;
 
@@ -1,5 +1,33 @@
 use crate::theme::Theme;
 use std::collections::HashMap;
+use std::fmt;
+
+/// The name of the theme every `System` starts with, used when the active theme is missing.
+pub const DEFAULT_THEME: &str = "dark";
+
+/// The colors of the default theme.
+const DARK: Theme = Theme {
+    background: egui::Color32::from_rgb(40, 44, 52),
+    foreground: egui::Color32::from_rgb(171, 178, 191),
+    selection: egui::Color32::from_rgb(61, 133, 198),
+    cursor: egui::Color32::WHITE,
+    line_numbers: egui::Color32::from_rgb(128, 128, 128),
+};
+
+/// The error returned when a theme is asked for by a name no theme has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeNotFound {
+    /// The name that was asked for.
+    pub name: String,
+}
+
+impl fmt::Display for ThemeNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no theme named `{}`", self.name)
+    }
+}
+
+impl std::error::Error for ThemeNotFound {}
 
 /// The `System` struct manages multiple UI themes and tracks the currently active theme.
 ///
@@ -14,6 +42,12 @@ pub struct System {
     active_theme: String,
 }
 
+impl Default for System {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl System {
     /// Creates a new `System` with a default "dark" theme.
     ///
@@ -21,29 +55,49 @@ impl System {
     /// A `System` instance with the "dark" theme set as active.
     pub fn new() -> Self {
         let mut themes = HashMap::new();
-        themes.insert(
-            "dark".to_string(),
-            Theme {
-                background: egui::Color32::from_rgb(40, 44, 52),
-                foreground: egui::Color32::from_rgb(171, 178, 191),
-                selection: egui::Color32::from_rgb(61, 133, 198),
-                cursor: egui::Color32::WHITE,
-                line_numbers: egui::Color32::from_rgb(128, 128, 128),
-            },
-        );
+        themes.insert(DEFAULT_THEME.to_string(), DARK);
 
         Self {
             themes,
-            active_theme: "dark".to_string(),
+            active_theme: DEFAULT_THEME.to_string(),
         }
     }
 
+    /// Adds a theme, or replaces the theme of the same name.
+    pub fn add_theme(&mut self, name: impl Into<String>, theme: Theme) {
+        self.themes.insert(name.into(), theme);
+    }
+
     /// Returns a reference to the currently active `Theme`.
     ///
     /// # Returns
-    /// A reference to the `Theme` corresponding to the active theme name.
+    /// A reference to the `Theme` corresponding to the active theme name, or to the default
+    /// theme, with a warning logged, if no theme has that name.
     pub fn get_active_theme(&self) -> &Theme {
-        &self.themes[&self.active_theme]
+        match self.themes.get(&self.active_theme) {
+            Some(theme) => theme,
+            None => {
+                eprintln!(
+                    "Theme `{}` not found, falling back to `{}`",
+                    self.active_theme, DEFAULT_THEME
+                );
+                self.themes.get(DEFAULT_THEME).unwrap_or(&DARK)
+            }
+        }
+    }
+
+    /// Makes the theme of a name the active one.
+    ///
+    /// # Errors
+    /// Returns [`ThemeNotFound`] if no theme has the name; the active theme is left as it was.
+    pub fn set_active_theme(&mut self, name: &str) -> Result<(), ThemeNotFound> {
+        if !self.themes.contains_key(name) {
+            return Err(ThemeNotFound {
+                name: name.to_string(),
+            });
+        }
+        self.active_theme = name.to_string();
+        Ok(())
     }
 }
 
@@ -83,12 +137,35 @@ mod tests {
     }
 
     #[test]
-    fn get_active_theme_panics_if_active_theme_missing() {
+    fn get_active_theme_falls_back_if_active_theme_missing() {
         let mut system = System::new();
         system.active_theme = "nonexistent".to_string();
-        let result = std::panic::catch_unwind(|| {
-            system.get_active_theme();
-        });
-        assert!(result.is_err());
+        assert_eq!(*system.get_active_theme(), DARK);
+
+        system.themes.clear();
+        assert_eq!(*system.get_active_theme(), DARK);
+    }
+
+    #[test]
+    fn set_active_theme_only_accepts_known_themes() {
+        let mut system = System::new();
+        let light = Theme {
+            background: Color32::WHITE,
+            foreground: Color32::BLACK,
+            selection: Color32::from_rgb(200, 200, 200),
+            cursor: Color32::BLACK,
+            line_numbers: Color32::from_rgb(100, 100, 100),
+        };
+        assert_eq!(
+            system.set_active_theme("light"),
+            Err(ThemeNotFound {
+                name: "light".to_string()
+            })
+        );
+        assert_eq!(system.active_theme, DEFAULT_THEME);
+
+        system.add_theme("light", light.clone());
+        assert_eq!(system.set_active_theme("light"), Ok(()));
+        assert_eq!(*system.get_active_theme(), light);
     }
 }
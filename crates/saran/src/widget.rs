@@ -146,16 +146,12 @@ impl Widget for Button {
     /// Returns true if the event was handled.
     fn handle_event(&mut self, event: &Event) -> bool {
         match event {
-            Event::MouseDown { .. } => {
-                if self.enabled {
-                    if let Some(cb) = &mut self.on_click {
-                        cb();
-                    }
-                    self.focused = true;
-                    true
-                } else {
-                    false
+            Event::MouseDown { .. } if self.enabled => {
+                if let Some(cb) = &mut self.on_click {
+                    cb();
                 }
+                self.focused = true;
+                true
             }
             Event::FocusGained => {
                 self.focused = true;
@@ -213,6 +209,12 @@ pub struct TextInput {
     focused: bool,
 }
 
+impl Default for TextInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TextInput {
     pub fn new() -> Self {
         Self {
@@ -344,6 +346,12 @@ pub struct Column {
     focused_child: Option<WidgetId>,
 }
 
+impl Default for Column {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Column {
     pub fn new() -> Self {
         Self {
@@ -1,4 +1,4 @@
-///! This module contains window management functionality, including the definition of an ID type used for unique identification of windows.
+//! This module contains window management functionality, including the definition of an ID type used for unique identification of windows.
 
 /// A unique identifier for windows.
 ///
@@ -38,19 +38,6 @@ impl ID {
         self.0
     }
 
-    /// Converts the `ID` to a string representation.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use saran::window::ID;
-    /// let id = ID::new(123);
-    /// assert_eq!(id.to_string(), "123");
-    /// ```
-    pub fn to_string(self) -> String {
-        self.0.to_string()
-    }
-
     /// Attempts to create an `ID` from a string.
     ///
     /// Returns `Some(ID)` if the string can be parsed as a `u32`, otherwise `None`.
@@ -79,12 +66,28 @@ impl ID {
     /// assert_eq!(id.value(), 2);
     /// assert_eq!(next_id.value(), 2);
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Self {
         self.0 = self.0.wrapping_add(1);
         Self(self.0)
     }
 }
 
+impl std::fmt::Display for ID {
+    /// Formats the `ID` as its underlying value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use saran::window::ID;
+    /// let id = ID::new(123);
+    /// assert_eq!(id.to_string(), "123");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;